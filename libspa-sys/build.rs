@@ -4,10 +4,33 @@
 use std::env;
 use std::path::PathBuf;
 
-fn main() {
-    let libs = system_deps::Config::new()
+#[cfg(feature = "vendored")]
+mod vendored;
+
+#[cfg(not(feature = "vendored"))]
+fn probe() -> system_deps::Dependencies {
+    system_deps::Config::new()
         .probe()
-        .expect("Cannot find libraries");
+        .expect("Cannot find libraries")
+}
+
+#[cfg(feature = "vendored")]
+fn probe() -> system_deps::Dependencies {
+    if let Ok(libs) = system_deps::Config::new().probe() {
+        return libs;
+    }
+
+    let prefix = vendored::build();
+    let pkg_config_path = vendored::add_pkg_config_path(&prefix);
+    // Dependent build scripts read it as `DEP_LIBSPA_0.2_PKGCONFIG`, see `pipewire-sys`.
+    println!("cargo:pkgconfig={}", pkg_config_path.to_string_lossy());
+    system_deps::Config::new()
+        .probe()
+        .expect("Cannot find the vendored libraries")
+}
+
+fn main() {
+    let libs = probe();
 
     // Tell cargo to invalidate the built crate whenever the wrapper changes
     println!("cargo:rerun-if-changed=wrapper.h");
@@ -48,8 +71,11 @@ fn main() {
     bindings
         .write_to_file(out_path.join("bindings.rs"))
         .expect("Couldn't write bindings!");
-    const FILES: &[&str] = &["src/type-info.c"];
-    let cc_files = &[PathBuf::from(FILES[0]), out_path.join("static_fns.c")];
+    const FILES: &[&str] = &["src/type-info.c", "src/log.c"];
+    let cc_files = FILES
+        .iter()
+        .map(PathBuf::from)
+        .chain(std::iter::once(out_path.join("static_fns.c")));
 
     for file in FILES {
         println!("cargo:rerun-if-changed={file}");
@@ -21,3 +21,6 @@ pub use bindings::*;
 // The rest is added in modules here.
 mod type_info;
 pub use type_info::*;
+
+mod log;
+pub use log::*;
@@ -0,0 +1,113 @@
+#include <stdarg.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#include <spa/support/log.h>
+#include <spa/utils/defs.h>
+
+// A `spa_log` implementation that formats messages and hands them to a callback.
+//
+// The methods of the log interface are variadic, which can't be defined in stable Rust,
+// so the formatting is done here and only the finished message is passed to Rust.
+
+typedef void (*libspa_rs_log_func_t)(void *data, enum spa_log_level level, const char *topic,
+				     const char *file, int line, const char *func, const char *msg);
+
+struct libspa_rs_log {
+	struct spa_log log;
+	struct spa_log_methods methods;
+	libspa_rs_log_func_t func;
+	void *data;
+};
+
+static void libspa_rs_log_emit(struct libspa_rs_log *impl, enum spa_log_level level,
+			       const char *topic, const char *file, int line, const char *func,
+			       const char *fmt, va_list args)
+{
+	char stack_buf[512];
+	char *msg = stack_buf;
+	va_list copy;
+	int len;
+
+	va_copy(copy, args);
+	len = vsnprintf(stack_buf, sizeof(stack_buf), fmt, copy);
+	va_end(copy);
+
+	if (len < 0)
+		return;
+
+	if ((size_t)len >= sizeof(stack_buf)) {
+		msg = malloc(len + 1);
+		if (msg == NULL)
+			return;
+		vsnprintf(msg, len + 1, fmt, args);
+	}
+
+	impl->func(impl->data, level, topic, file, line, func, msg);
+
+	if (msg != stack_buf)
+		free(msg);
+}
+
+static void libspa_rs_log_logv(void *object, enum spa_log_level level, const char *file, int line,
+			       const char *func, const char *fmt, va_list args)
+{
+	libspa_rs_log_emit(object, level, NULL, file, line, func, fmt, args);
+}
+
+static void libspa_rs_log_log(void *object, enum spa_log_level level, const char *file, int line,
+			      const char *func, const char *fmt, ...)
+{
+	va_list args;
+	va_start(args, fmt);
+	libspa_rs_log_logv(object, level, file, line, func, fmt, args);
+	va_end(args);
+}
+
+#if SPA_VERSION_LOG_METHODS >= 1
+static void libspa_rs_log_logtv(void *object, enum spa_log_level level,
+				const struct spa_log_topic *topic, const char *file, int line,
+				const char *func, const char *fmt, va_list args)
+{
+	libspa_rs_log_emit(object, level, topic ? topic->topic : NULL, file, line, func, fmt, args);
+}
+
+static void libspa_rs_log_logt(void *object, enum spa_log_level level,
+			       const struct spa_log_topic *topic, const char *file, int line,
+			       const char *func, const char *fmt, ...)
+{
+	va_list args;
+	va_start(args, fmt);
+	libspa_rs_log_logtv(object, level, topic, file, line, func, fmt, args);
+	va_end(args);
+}
+#endif
+
+struct spa_log *libspa_rs_log_new(enum spa_log_level level, libspa_rs_log_func_t func, void *data)
+{
+	struct libspa_rs_log *impl = calloc(1, sizeof(*impl));
+	if (impl == NULL)
+		return NULL;
+
+	impl->methods.version = SPA_VERSION_LOG_METHODS;
+	impl->methods.log = libspa_rs_log_log;
+	impl->methods.logv = libspa_rs_log_logv;
+#if SPA_VERSION_LOG_METHODS >= 1
+	impl->methods.logt = libspa_rs_log_logt;
+	impl->methods.logtv = libspa_rs_log_logtv;
+#endif
+
+	impl->func = func;
+	impl->data = data;
+
+	impl->log.iface = SPA_INTERFACE_INIT(SPA_TYPE_INTERFACE_Log, SPA_VERSION_LOG, &impl->methods,
+					     impl);
+	impl->log.level = level;
+
+	return &impl->log;
+}
+
+void libspa_rs_log_free(struct spa_log *log)
+{
+	free(SPA_CONTAINER_OF(log, struct libspa_rs_log, log));
+}
@@ -0,0 +1,31 @@
+use super::*;
+
+/// Callback receiving fully formatted log messages from a log created with [`libspa_rs_log_new`].
+///
+/// `topic` is NULL if the message was logged without a topic.
+#[allow(non_camel_case_types)]
+pub type libspa_rs_log_func_t = Option<
+    unsafe extern "C" fn(
+        data: *mut ::std::os::raw::c_void,
+        level: spa_log_level,
+        topic: *const ::std::os::raw::c_char,
+        file: *const ::std::os::raw::c_char,
+        line: ::std::os::raw::c_int,
+        func: *const ::std::os::raw::c_char,
+        msg: *const ::std::os::raw::c_char,
+    ),
+>;
+
+extern "C" {
+    /// Create a new `spa_log` that formats messages and passes them to `func`.
+    ///
+    /// The returned log must be freed with [`libspa_rs_log_free`].
+    pub fn libspa_rs_log_new(
+        level: spa_log_level,
+        func: libspa_rs_log_func_t,
+        data: *mut ::std::os::raw::c_void,
+    ) -> *mut spa_log;
+
+    /// Free a log created with [`libspa_rs_log_new`].
+    pub fn libspa_rs_log_free(log: *mut spa_log);
+}
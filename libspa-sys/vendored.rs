@@ -0,0 +1,105 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Build of a pinned PipeWire, used by the `vendored` feature when pkg-config cannot find the
+//! system libraries.
+//!
+//! The sources are cloned from the PipeWire repository, or taken from `PIPEWIRE_VENDORED_SRC`,
+//! and built with meson and ninja, which have to be installed. `PIPEWIRE_VENDORED_VERSION`
+//! selects the tag to build.
+
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const REPOSITORY: &str = "https://gitlab.freedesktop.org/pipewire/pipewire.git";
+const DEFAULT_VERSION: &str = "1.2.7";
+
+/// Meson options disabling everything but the libraries and the SPA plugins they need.
+const MESON_OPTIONS: &[&str] = &[
+    "-Dauto_features=disabled",
+    "-Dsession-managers=[]",
+    "-Dexamples=disabled",
+    "-Dtests=disabled",
+    "-Ddocs=disabled",
+    "-Dman=disabled",
+    "-Dalsa=disabled",
+    "-Dpipewire-alsa=disabled",
+    "-Dpipewire-jack=disabled",
+    "-Dpipewire-v4l2=disabled",
+];
+
+/// Build and install PipeWire, returning the installation prefix.
+pub fn build() -> PathBuf {
+    println!("cargo:rerun-if-env-changed=PIPEWIRE_VENDORED_VERSION");
+    println!("cargo:rerun-if-env-changed=PIPEWIRE_VENDORED_SRC");
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let src = match env::var_os("PIPEWIRE_VENDORED_SRC") {
+        Some(src) => PathBuf::from(src),
+        None => {
+            let version = env::var("PIPEWIRE_VENDORED_VERSION")
+                .unwrap_or_else(|_| DEFAULT_VERSION.to_owned());
+            fetch(&out_dir.join("pipewire-src"), &version)
+        }
+    };
+    let build = out_dir.join("pipewire-build");
+    let prefix = out_dir.join("pipewire");
+
+    if !build.join("build.ninja").exists() {
+        run(Command::new("meson")
+            .arg("setup")
+            .arg(&build)
+            .arg(&src)
+            .arg(format!("--prefix={}", prefix.display()))
+            .arg("--libdir=lib")
+            .arg("--buildtype=release")
+            .args(MESON_OPTIONS));
+    }
+    run(Command::new("meson").arg("install").arg("-C").arg(&build));
+
+    prefix
+}
+
+/// Make pkg-config find the libraries installed in `prefix`, before the system ones, returning
+/// the new `PKG_CONFIG_PATH`.
+pub fn add_pkg_config_path(prefix: &Path) -> OsString {
+    let mut paths = vec![prefix.join("lib").join("pkgconfig")];
+    paths.extend(
+        env::var_os("PKG_CONFIG_PATH")
+            .iter()
+            .flat_map(env::split_paths),
+    );
+    let path = env::join_paths(paths).unwrap();
+    env::set_var("PKG_CONFIG_PATH", &path);
+    path
+}
+
+/// Clone the sources of `version` into `dir`, unless they already are.
+fn fetch(dir: &Path, version: &str) -> PathBuf {
+    let stamp = dir.join(".vendored-version");
+    if fs::read_to_string(&stamp).map_or(false, |fetched| fetched == version) {
+        return dir.to_owned();
+    }
+
+    if dir.exists() {
+        fs::remove_dir_all(dir).expect("Cannot remove the previous PipeWire sources");
+    }
+    run(Command::new("git")
+        .args(["clone", "--depth", "1", "--branch", version, REPOSITORY])
+        .arg(dir));
+    fs::write(&stamp, version).expect("Cannot write the PipeWire version stamp");
+
+    dir.to_owned()
+}
+
+fn run(command: &mut Command) {
+    let status = command
+        .status()
+        .unwrap_or_else(|err| panic!("Cannot run {command:?}, is it installed? {err}"));
+    assert!(status.success(), "{command:?} failed with {status}");
+}
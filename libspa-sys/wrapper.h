@@ -103,6 +103,9 @@
 #endif
 #include <spa/utils/defs.h>
 #include <spa/utils/dict.h>
+#if PW_CHECK_VERSION(0,3,20)
+#include <spa/utils/dll.h>
+#endif
 #include <spa/utils/hook.h>
 #if PW_CHECK_VERSION(0,3,18)
 #include <spa/utils/json.h>
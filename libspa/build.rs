@@ -1,10 +1,28 @@
+/// Probe the libraries, falling back to the PipeWire built by `libspa-sys` with its `vendored`
+/// feature, which exports the `PKG_CONFIG_PATH` finding it.
+#[cfg(feature = "vendored")]
+fn probe() -> Result<system_deps::Dependencies, system_deps::Error> {
+    println!("cargo:rerun-if-env-changed=DEP_LIBSPA_0.2_PKGCONFIG");
+    let probed = system_deps::Config::new().probe();
+    match std::env::var_os("DEP_LIBSPA_0.2_PKGCONFIG") {
+        Some(path) if probed.is_err() => {
+            std::env::set_var("PKG_CONFIG_PATH", path);
+            system_deps::Config::new().probe()
+        }
+        _ => probed,
+    }
+}
+
+#[cfg(not(feature = "vendored"))]
+fn probe() -> Result<system_deps::Dependencies, system_deps::Error> {
+    system_deps::Config::new().probe()
+}
+
 fn main() {
     // FIXME: It would be nice to run this only when tests are run.
     println!("cargo:rerun-if-changed=tests/pod.c");
 
-    let libs = system_deps::Config::new()
-        .probe()
-        .expect("Cannot find libspa");
+    let libs = probe().expect("Cannot find libspa");
     let libspa = libs.get_by_name("libspa").unwrap();
 
     cc::Build::new()
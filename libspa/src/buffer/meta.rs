@@ -0,0 +1,323 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Metadata attached to buffers, such as timestamps or the explicit sync timeline points.
+
+use std::fmt::Debug;
+
+use crate::utils::{Point, Region};
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct MetaType(u32);
+
+#[allow(non_upper_case_globals)]
+impl MetaType {
+    pub const Invalid: Self = Self(spa_sys::SPA_META_Invalid);
+    /// Header of the buffer, see `spa_meta_header`.
+    pub const Header: Self = Self(spa_sys::SPA_META_Header);
+    /// Cropping region of a video frame, see `spa_meta_region`.
+    pub const VideoCrop: Self = Self(spa_sys::SPA_META_VideoCrop);
+    /// Array of damaged regions of a video frame.
+    pub const VideoDamage: Self = Self(spa_sys::SPA_META_VideoDamage);
+    /// Bitmap, see `spa_meta_bitmap`.
+    pub const Bitmap: Self = Self(spa_sys::SPA_META_Bitmap);
+    /// Cursor, see `spa_meta_cursor`.
+    pub const Cursor: Self = Self(spa_sys::SPA_META_Cursor);
+    /// Control sequence.
+    pub const Control: Self = Self(spa_sys::SPA_META_Control);
+    /// Reference count of the buffer, set while it is in use.
+    pub const Busy: Self = Self(spa_sys::SPA_META_Busy);
+    /// Explicit sync timeline points, see [`SyncTimelineMeta`].
+    #[cfg(feature = "v1_2_0")]
+    pub const SyncTimeline: Self = Self(spa_sys::SPA_META_SyncTimeline);
+
+    pub fn from_raw(raw: u32) -> Self {
+        Self(raw)
+    }
+
+    pub fn as_raw(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Debug for MetaType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = format!(
+            "MetaType::{}",
+            match *self {
+                Self::Invalid => "Invalid",
+                Self::Header => "Header",
+                Self::VideoCrop => "VideoCrop",
+                Self::VideoDamage => "VideoDamage",
+                Self::Bitmap => "Bitmap",
+                Self::Cursor => "Cursor",
+                Self::Control => "Control",
+                Self::Busy => "Busy",
+                #[cfg(feature = "v1_2_0")]
+                Self::SyncTimeline => "SyncTimeline",
+                _ => "Unknown",
+            }
+        );
+        f.write_str(&name)
+    }
+}
+
+/// A typed metadata of a buffer.
+///
+/// # Safety
+/// The type must be a `repr(transparent)` wrapper of the C struct of the metadata of type
+/// [`TYPE`](Self::TYPE).
+pub unsafe trait Meta {
+    const TYPE: MetaType;
+}
+
+bitflags::bitflags! {
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub struct HeaderFlags: u32 {
+        /// Data is not continuous with previous buffer
+        const DISCONT = spa_sys::SPA_META_HEADER_FLAG_DISCONT;
+        /// Data might be corrupted
+        const CORRUPTED = spa_sys::SPA_META_HEADER_FLAG_CORRUPTED;
+        /// Media specific marker
+        const MARKER = spa_sys::SPA_META_HEADER_FLAG_MARKER;
+        /// Data contains a codec specific header
+        const HEADER = spa_sys::SPA_META_HEADER_FLAG_HEADER;
+        /// Data contains media neutral data
+        const GAP = spa_sys::SPA_META_HEADER_FLAG_GAP;
+        /// Cannot be decoded independently
+        const DELTA_UNIT = spa_sys::SPA_META_HEADER_FLAG_DELTA_UNIT;
+    }
+}
+
+/// The header of a buffer, describing its content.
+#[repr(transparent)]
+pub struct HeaderMeta(spa_sys::spa_meta_header);
+
+impl HeaderMeta {
+    pub fn as_raw(&self) -> &spa_sys::spa_meta_header {
+        &self.0
+    }
+
+    pub fn flags(&self) -> HeaderFlags {
+        HeaderFlags::from_bits_retain(self.0.flags)
+    }
+
+    pub fn set_flags(&mut self, flags: HeaderFlags) {
+        self.0.flags = flags.bits();
+    }
+
+    /// The offset in the current cycle.
+    pub fn offset(&self) -> u32 {
+        self.0.offset
+    }
+
+    pub fn set_offset(&mut self, offset: u32) {
+        self.0.offset = offset;
+    }
+
+    /// The presentation timestamp in nanoseconds.
+    pub fn pts(&self) -> i64 {
+        self.0.pts
+    }
+
+    pub fn set_pts(&mut self, pts: i64) {
+        self.0.pts = pts;
+    }
+
+    /// The decoding timestamp, as a difference with the pts.
+    pub fn dts_offset(&self) -> i64 {
+        self.0.dts_offset
+    }
+
+    pub fn set_dts_offset(&mut self, dts_offset: i64) {
+        self.0.dts_offset = dts_offset;
+    }
+
+    /// The sequence number, increasing with each buffer.
+    pub fn seq(&self) -> u64 {
+        self.0.seq
+    }
+
+    pub fn set_seq(&mut self, seq: u64) {
+        self.0.seq = seq;
+    }
+}
+
+unsafe impl Meta for HeaderMeta {
+    const TYPE: MetaType = MetaType::Header;
+}
+
+impl Debug for HeaderMeta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HeaderMeta")
+            .field("flags", &self.flags())
+            .field("offset", &self.offset())
+            .field("pts", &self.pts())
+            .field("dts_offset", &self.dts_offset())
+            .field("seq", &self.seq())
+            .finish()
+    }
+}
+
+/// The region of a video frame holding its content.
+#[repr(transparent)]
+pub struct VideoCropMeta(spa_sys::spa_meta_region);
+
+impl VideoCropMeta {
+    pub fn as_raw(&self) -> &spa_sys::spa_meta_region {
+        &self.0
+    }
+
+    pub fn region(&self) -> Region {
+        self.0.region
+    }
+
+    pub fn set_region(&mut self, region: Region) {
+        self.0.region = region;
+    }
+}
+
+unsafe impl Meta for VideoCropMeta {
+    const TYPE: MetaType = MetaType::VideoCrop;
+}
+
+impl Debug for VideoCropMeta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VideoCropMeta")
+            .field("region", &self.region())
+            .finish()
+    }
+}
+
+/// The cursor of a screen capture.
+///
+/// The bitmap of the cursor, if any, is stored in the same meta after the cursor,
+/// see [`bitmap_offset`](Self::bitmap_offset).
+#[repr(transparent)]
+pub struct CursorMeta(spa_sys::spa_meta_cursor);
+
+impl CursorMeta {
+    pub fn as_raw(&self) -> &spa_sys::spa_meta_cursor {
+        &self.0
+    }
+
+    /// The id of the cursor, `0` when there is no cursor.
+    pub fn id(&self) -> u32 {
+        self.0.id
+    }
+
+    pub fn set_id(&mut self, id: u32) {
+        self.0.id = id;
+    }
+
+    /// The position of the cursor in the frame.
+    pub fn position(&self) -> Point {
+        self.0.position
+    }
+
+    pub fn set_position(&mut self, position: Point) {
+        self.0.position = position;
+    }
+
+    /// The position of the hotspot in the bitmap of the cursor.
+    pub fn hotspot(&self) -> Point {
+        self.0.hotspot
+    }
+
+    pub fn set_hotspot(&mut self, hotspot: Point) {
+        self.0.hotspot = hotspot;
+    }
+
+    /// The offset of the bitmap from the start of the meta, `0` when the bitmap is unchanged.
+    pub fn bitmap_offset(&self) -> u32 {
+        self.0.bitmap_offset
+    }
+
+    pub fn set_bitmap_offset(&mut self, offset: u32) {
+        self.0.bitmap_offset = offset;
+    }
+}
+
+unsafe impl Meta for CursorMeta {
+    const TYPE: MetaType = MetaType::Cursor;
+}
+
+impl Debug for CursorMeta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CursorMeta")
+            .field("id", &self.id())
+            .field("position", &self.position())
+            .field("hotspot", &self.hotspot())
+            .field("bitmap_offset", &self.bitmap_offset())
+            .finish()
+    }
+}
+
+#[cfg(feature = "v1_2_0")]
+bitflags::bitflags! {
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub struct SyncTimelineFlags: u32 {
+        /// The consumer will not signal the release point, the producer has to do it
+        /// when reusing the buffer.
+        const UNSCHEDULED_RELEASE = 1<<0;
+    }
+}
+
+/// The timeline points of a buffer using explicit sync.
+///
+/// The buffer holds two `SyncObj` datas, the DRM syncobj timelines of the acquire and release points.
+/// The producer signals the acquire point once the content of the buffer is ready, and the consumer
+/// waits for it before reading the buffer. The consumer signals the release point once it is done with
+/// the buffer, and the producer waits for it before reusing the buffer.
+#[cfg(feature = "v1_2_0")]
+#[repr(transparent)]
+pub struct SyncTimelineMeta(spa_sys::spa_meta_sync_timeline);
+
+#[cfg(feature = "v1_2_0")]
+impl SyncTimelineMeta {
+    pub fn as_raw(&self) -> &spa_sys::spa_meta_sync_timeline {
+        &self.0
+    }
+
+    pub fn flags(&self) -> SyncTimelineFlags {
+        SyncTimelineFlags::from_bits_retain(self.0.flags)
+    }
+
+    pub fn set_flags(&mut self, flags: SyncTimelineFlags) {
+        self.0.flags = flags.bits();
+    }
+
+    /// The point of the acquire timeline signaled when the buffer can be read.
+    pub fn acquire_point(&self) -> u64 {
+        self.0.acquire_point
+    }
+
+    pub fn set_acquire_point(&mut self, point: u64) {
+        self.0.acquire_point = point;
+    }
+
+    /// The point of the release timeline signaled when the buffer can be reused.
+    pub fn release_point(&self) -> u64 {
+        self.0.release_point
+    }
+
+    pub fn set_release_point(&mut self, point: u64) {
+        self.0.release_point = point;
+    }
+}
+
+#[cfg(feature = "v1_2_0")]
+unsafe impl Meta for SyncTimelineMeta {
+    const TYPE: MetaType = MetaType::SyncTimeline;
+}
+
+#[cfg(feature = "v1_2_0")]
+impl Debug for SyncTimelineMeta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SyncTimelineMeta")
+            .field("flags", &self.flags())
+            .field("acquire_point", &self.acquire_point())
+            .field("release_point", &self.release_point())
+            .finish()
+    }
+}
@@ -1,7 +1,18 @@
 // Copyright The pipewire-rs Contributors.
 // SPDX-License-Identifier: MIT
 
-use std::{convert::TryFrom, fmt::Debug};
+use std::{
+    convert::TryFrom,
+    fmt::Debug,
+    os::fd::{BorrowedFd, RawFd},
+};
+
+use crate::{
+    pod::{ChoiceValue, Property, Value},
+    utils::{Choice, ChoiceEnum, ChoiceFlags},
+};
+
+pub mod meta;
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub struct DataType(spa_sys::spa_data_type);
@@ -17,6 +28,9 @@ impl DataType {
     pub const DmaBuf: Self = Self(spa_sys::SPA_DATA_DmaBuf);
     /// Memory is identified with an id
     pub const MemId: Self = Self(spa_sys::SPA_DATA_MemId);
+    /// Fd to a DRM syncobj timeline, used for explicit sync
+    #[cfg(feature = "v1_2_0")]
+    pub const SyncObj: Self = Self(spa_sys::SPA_DATA_SyncObj);
 
     pub fn from_raw(raw: spa_sys::spa_data_type) -> Self {
         Self(raw)
@@ -25,6 +39,150 @@ impl DataType {
     pub fn as_raw(&self) -> spa_sys::spa_data_type {
         self.0
     }
+
+    /// Whether the memory is referenced by a file descriptor, available from [`Data::fd`].
+    pub fn has_fd(&self) -> bool {
+        match *self {
+            Self::MemFd | Self::DmaBuf => true,
+            #[cfg(feature = "v1_2_0")]
+            Self::SyncObj => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the memory can be mapped with `mmap`, using its fd and [`Data::map_offset`].
+    ///
+    /// Accessing mapped DMA-BUF memory is usually slow and needs to be synchronized with the
+    /// device owning it.
+    pub fn supports_mmap(&self) -> bool {
+        matches!(*self, Self::MemFd | Self::DmaBuf)
+    }
+
+    /// The bit of the type in a [`DataTypes`] mask.
+    fn bit(&self) -> u32 {
+        1u32.checked_shl(self.0).unwrap_or(0)
+    }
+}
+
+/// A set of [`DataType`]s, such as the types of memory accepted for the buffers of a stream.
+///
+/// This is the mask used by the `dataType` property of the `Buffers` param, where each type is
+/// the bit `1 << type`, and by the type of a [`Data`] before its memory is allocated.
+///
+/// ```
+/// use libspa::buffer::{DataType, DataTypes};
+///
+/// let types = DataTypes::from(DataType::MemFd).with(DataType::DmaBuf);
+/// assert!(types.contains(DataType::DmaBuf));
+/// assert_eq!(types.bits(), (1 << 2) | (1 << 3));
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Default, Hash)]
+pub struct DataTypes(u32);
+
+impl DataTypes {
+    /// The set without any type.
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// The set of the types whose bit is set in `bits`.
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// The set with `type_` added.
+    #[must_use]
+    pub fn with(mut self, type_: DataType) -> Self {
+        self.insert(type_);
+        self
+    }
+
+    pub fn insert(&mut self, type_: DataType) {
+        self.0 |= type_.bit();
+    }
+
+    pub fn remove(&mut self, type_: DataType) {
+        self.0 &= !type_.bit();
+    }
+
+    pub fn contains(&self, type_: DataType) -> bool {
+        type_.bit() != 0 && self.0 & type_.bit() != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// The types of the set, in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = DataType> {
+        let bits = self.0;
+        (0..u32::BITS)
+            .filter(move |bit| bits & (1 << bit) != 0)
+            .map(DataType::from_raw)
+    }
+
+    /// The value of the `dataType` property of the `Buffers` param accepting the types of
+    /// the set.
+    pub fn to_choice(&self) -> Choice<i32> {
+        Choice(
+            ChoiceFlags::empty(),
+            ChoiceEnum::Flags {
+                default: self.0 as i32,
+                flags: Vec::new(),
+            },
+        )
+    }
+
+    /// The `dataType` property of the `Buffers` param accepting the types of the set.
+    pub fn to_property(&self) -> Property {
+        Property::new(
+            spa_sys::SPA_PARAM_BUFFERS_dataType,
+            Value::Choice(ChoiceValue::Int(self.to_choice())),
+        )
+    }
+
+    /// Read the types from the value of the `dataType` property of the `Buffers` param.
+    ///
+    /// Returns `None` if the value is neither an int nor a choice of flags.
+    pub fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Int(bits) => Some(Self(*bits as u32)),
+            Value::Choice(ChoiceValue::Int(Choice(_, ChoiceEnum::Flags { default, .. }))) => {
+                Some(Self(*default as u32))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<DataType> for DataTypes {
+    fn from(type_: DataType) -> Self {
+        Self::empty().with(type_)
+    }
+}
+
+impl FromIterator<DataType> for DataTypes {
+    fn from_iter<I: IntoIterator<Item = DataType>>(iter: I) -> Self {
+        iter.into_iter().fold(Self::empty(), Self::with)
+    }
+}
+
+impl std::ops::BitOr for DataTypes {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl std::fmt::Debug for DataTypes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
 }
 
 impl std::fmt::Debug for DataType {
@@ -37,6 +195,8 @@ impl std::fmt::Debug for DataType {
                 Self::MemFd => "MemFd",
                 Self::DmaBuf => "DmaBuf",
                 Self::MemId => "MemId",
+                #[cfg(feature = "v1_2_0")]
+                Self::SyncObj => "SyncObj",
                 _ => "Unknown",
             }
         );
@@ -73,12 +233,64 @@ impl Data {
         DataFlags::from_bits_retain(self.0.flags)
     }
 
-    // FIXME: Add bindings for the fd field, but how to detect when it is not set / invalid?
+    /// The types of memory accepted for the data, before its memory is allocated.
+    ///
+    /// Until then, such as in the `add_buffer` event of a stream allocating its buffers,
+    /// the type of the data holds the mask of the accepted types instead of a [`DataType`].
+    pub fn accepted_types(&self) -> DataTypes {
+        DataTypes::from_bits(self.0.type_)
+    }
+
+    /// The file descriptor of the memory, for [`MemFd`](DataType::MemFd),
+    /// [`DmaBuf`](DataType::DmaBuf) and `SyncObj` data.
+    ///
+    /// Returns `None` for other types of memory, or if the file descriptor is not set.
+    pub fn fd(&self) -> Option<BorrowedFd<'_>> {
+        match self.type_() {
+            #[cfg(feature = "v1_2_0")]
+            DataType::SyncObj => self.raw_fd(),
+            DataType::MemFd | DataType::DmaBuf => self.raw_fd(),
+            _ => None,
+        }
+    }
+
+    fn raw_fd(&self) -> Option<BorrowedFd<'_>> {
+        let fd = RawFd::try_from(self.0.fd).ok().filter(|&fd| fd >= 0)?;
+        // Safety: the fd stays open while the buffer holding the data exists.
+        Some(unsafe { BorrowedFd::borrow_raw(fd) })
+    }
+
+    /// The offset of the memory in the file descriptor.
+    pub fn map_offset(&self) -> u32 {
+        self.0.mapoffset
+    }
+
+    /// The size of the memory.
+    pub fn max_size(&self) -> u32 {
+        self.0.maxsize
+    }
 
-    pub fn data(&mut self) -> Option<&mut [u8]> {
-        // FIXME: For safety, perhaps only return a non-mut slice when DataFlags::WRITABLE is not set?
+    /// The memory of the data, or `None` if it is not mapped.
+    pub fn data(&self) -> Option<&[u8]> {
         if self.0.data.is_null() {
             None
+        } else {
+            unsafe {
+                Some(std::slice::from_raw_parts(
+                    self.0.data as *const u8,
+                    usize::try_from(self.0.maxsize).unwrap(),
+                ))
+            }
+        }
+    }
+
+    /// The memory of the data, or `None` if it is not mapped or not [`WRITABLE`](DataFlags::WRITABLE).
+    ///
+    /// The memory of buffers received from a capture stream is usually shared with other clients
+    /// and only readable.
+    pub fn data_mut(&mut self) -> Option<&mut [u8]> {
+        if self.0.data.is_null() || !self.flags().contains(DataFlags::WRITABLE) {
+            None
         } else {
             unsafe {
                 Some(std::slice::from_raw_parts_mut(
@@ -111,7 +323,7 @@ impl Debug for Data {
         f.debug_struct("Data")
             .field("type", &self.type_())
             .field("flags", &self.flags())
-            // FIXME: Add fd
+            .field("fd", &self.fd())
             .field("data", &self.0.data) // Only print the pointer here, as we don't want to print a (potentially very big) slice.
             .field("chunk", &self.chunk())
             .finish()
@@ -161,6 +373,10 @@ impl Chunk {
     pub fn flags(&self) -> ChunkFlags {
         ChunkFlags::from_bits_retain(self.0.flags)
     }
+
+    pub fn set_flags(&mut self, flags: ChunkFlags) {
+        self.0.flags = flags.bits();
+    }
 }
 
 impl Debug for Chunk {
@@ -173,3 +389,33 @@ impl Debug for Chunk {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_types() {
+        let types: DataTypes = [DataType::MemPtr, DataType::MemFd].into_iter().collect();
+        assert_eq!(types.bits(), (1 << 1) | (1 << 2));
+        assert!(types.contains(DataType::MemFd));
+        assert!(!types.contains(DataType::DmaBuf));
+        assert!(!types.contains(DataType::from_raw(64)));
+        assert_eq!(
+            types.iter().collect::<Vec<_>>(),
+            [DataType::MemPtr, DataType::MemFd]
+        );
+
+        let property = types.to_property();
+        assert_eq!(property.key, spa_sys::SPA_PARAM_BUFFERS_dataType);
+        assert_eq!(DataTypes::from_value(&property.value), Some(types));
+        assert_eq!(
+            DataTypes::from_value(&Value::Int(1 << 3)),
+            Some(DataType::DmaBuf.into())
+        );
+        assert_eq!(DataTypes::from_value(&Value::Bool(true)), None);
+
+        assert!(DataType::MemFd.has_fd() && DataType::MemFd.supports_mmap());
+        assert!(!DataType::MemPtr.has_fd() && !DataType::MemPtr.supports_mmap());
+    }
+}
@@ -0,0 +1,388 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! SPA devices, such as the device monitors of the v4l2 or libcamera plugins.
+//!
+//! A device is obtained from an object instantiated from a plugin, see [`crate::support::plugin`],
+//! with [`DeviceRef::from_interface`]. Monitors report the devices they find as objects
+//! through the `object_info` event.
+
+use std::{ffi::CStr, fmt, mem, os::raw::c_char, pin::Pin, ptr};
+
+use bitflags::bitflags;
+
+use crate::{
+    param::{ParamInfo, ParamType},
+    pod::Pod,
+    spa_interface_call_method,
+    support::plugin::{InterfaceRef, TYPE_INTERFACE_DEVICE},
+    utils::{
+        dict::DictRef,
+        result::{Error, SpaResult},
+    },
+};
+
+/// A reference to a SPA device.
+#[repr(transparent)]
+pub struct DeviceRef(spa_sys::spa_device);
+
+impl DeviceRef {
+    pub fn as_raw(&self) -> &spa_sys::spa_device {
+        &self.0
+    }
+
+    pub fn as_raw_ptr(&self) -> *mut spa_sys::spa_device {
+        std::ptr::addr_of!(self.0).cast_mut()
+    }
+
+    /// Get the device implementing `interface`, if it is a device interface.
+    pub fn from_interface(interface: &InterfaceRef) -> Option<&Self> {
+        if interface.type_() == TYPE_INTERFACE_DEVICE {
+            Some(unsafe { &*(interface as *const InterfaceRef as *const Self) })
+        } else {
+            None
+        }
+    }
+
+    #[must_use]
+    pub fn add_listener_local(&self) -> DeviceListenerLocalBuilder {
+        DeviceListenerLocalBuilder {
+            device: self,
+            cbs: ListenerLocalCallbacks::default(),
+        }
+    }
+
+    /// Ask the device to emit a `result` event with `seq` once all the pending operations completed.
+    pub fn sync(&self, seq: i32) -> Result<(), Error> {
+        let res = unsafe {
+            spa_interface_call_method!(self.as_raw_ptr(), spa_sys::spa_device_methods, sync, seq)
+        };
+
+        SpaResult::from_c(res).into_sync_result()?;
+        Ok(())
+    }
+
+    /// Enumerate the device parameters of type `id`, emitting them as `result` events.
+    ///
+    /// # Parameters
+    /// `seq`: a sequence number to place in the results \
+    /// `start`: the index of the first param to enumerate \
+    /// `max`: the maximum number of params to enumerate \
+    /// `filter`: only emit params matching this filter
+    pub fn enum_params(
+        &self,
+        seq: i32,
+        id: ParamType,
+        start: u32,
+        max: u32,
+        filter: Option<&Pod>,
+    ) -> Result<(), Error> {
+        let res = unsafe {
+            spa_interface_call_method!(
+                self.as_raw_ptr(),
+                spa_sys::spa_device_methods,
+                enum_params,
+                seq,
+                id.as_raw(),
+                start,
+                max,
+                filter.map_or(ptr::null(), |filter| filter.as_ptr())
+            )
+        };
+
+        SpaResult::from_c(res).into_sync_result()?;
+        Ok(())
+    }
+
+    /// Set a device parameter.
+    pub fn set_param(&self, id: ParamType, flags: u32, param: &Pod) -> Result<(), Error> {
+        let res = unsafe {
+            spa_interface_call_method!(
+                self.as_raw_ptr(),
+                spa_sys::spa_device_methods,
+                set_param,
+                id.as_raw(),
+                flags,
+                param.as_ptr()
+            )
+        };
+
+        SpaResult::from_c(res).into_sync_result()?;
+        Ok(())
+    }
+}
+
+bitflags! {
+    /// The fields of [`DeviceInfoRef`] that changed.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub struct DeviceChangeMask: u64 {
+        const FLAGS = spa_sys::SPA_DEVICE_CHANGE_MASK_FLAGS as u64;
+        const PROPS = spa_sys::SPA_DEVICE_CHANGE_MASK_PROPS as u64;
+        const PARAMS = spa_sys::SPA_DEVICE_CHANGE_MASK_PARAMS as u64;
+    }
+}
+
+/// Information about a [`DeviceRef`].
+#[repr(transparent)]
+pub struct DeviceInfoRef(spa_sys::spa_device_info);
+
+impl DeviceInfoRef {
+    pub fn as_raw(&self) -> &spa_sys::spa_device_info {
+        &self.0
+    }
+
+    pub fn change_mask(&self) -> DeviceChangeMask {
+        DeviceChangeMask::from_bits_retain(self.0.change_mask)
+    }
+
+    pub fn props(&self) -> Option<&DictRef> {
+        unsafe { (self.0.props as *const DictRef).as_ref() }
+    }
+
+    /// Get the param infos for the device.
+    pub fn params(&self) -> &[ParamInfo] {
+        let params = self.0.params;
+        if params.is_null() {
+            &[]
+        } else {
+            unsafe {
+                std::slice::from_raw_parts(params as *const _, self.0.n_params.try_into().unwrap())
+            }
+        }
+    }
+}
+
+impl fmt::Debug for DeviceInfoRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeviceInfoRef")
+            .field("change-mask", &self.change_mask())
+            .field("props", &self.props())
+            .field("params", &self.params())
+            .finish()
+    }
+}
+
+bitflags! {
+    /// The fields of [`DeviceObjectInfoRef`] that changed.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub struct DeviceObjectChangeMask: u64 {
+        const FLAGS = spa_sys::SPA_DEVICE_OBJECT_CHANGE_MASK_FLAGS as u64;
+        const PROPS = spa_sys::SPA_DEVICE_OBJECT_CHANGE_MASK_PROPS as u64;
+    }
+}
+
+/// Information about an object, such as a node or another device, exposed by a [`DeviceRef`].
+#[repr(transparent)]
+pub struct DeviceObjectInfoRef(spa_sys::spa_device_object_info);
+
+impl DeviceObjectInfoRef {
+    pub fn as_raw(&self) -> &spa_sys::spa_device_object_info {
+        &self.0
+    }
+
+    /// The interface type of the object, such as `Spa:Pointer:Interface:Node`.
+    pub fn type_(&self) -> &str {
+        unsafe { cstr_or_empty(self.0.type_) }
+    }
+
+    /// The name of the factory to instantiate the object with.
+    pub fn factory_name(&self) -> &str {
+        unsafe { cstr_or_empty(self.0.factory_name) }
+    }
+
+    pub fn change_mask(&self) -> DeviceObjectChangeMask {
+        DeviceObjectChangeMask::from_bits_retain(self.0.change_mask)
+    }
+
+    pub fn props(&self) -> Option<&DictRef> {
+        unsafe { (self.0.props as *const DictRef).as_ref() }
+    }
+}
+
+impl fmt::Debug for DeviceObjectInfoRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeviceObjectInfoRef")
+            .field("type", &self.type_())
+            .field("factory-name", &self.factory_name())
+            .field("change-mask", &self.change_mask())
+            .field("props", &self.props())
+            .finish()
+    }
+}
+
+unsafe fn cstr_or_empty<'a>(s: *const c_char) -> &'a str {
+    if s.is_null() {
+        ""
+    } else {
+        CStr::from_ptr(s).to_str().unwrap()
+    }
+}
+
+type InfoCallback = dyn Fn(&DeviceInfoRef);
+type ResultCallback = dyn Fn(i32, i32, u32, *const std::os::raw::c_void);
+type EventCallback = dyn Fn(&Pod);
+type ObjectInfoCallback = dyn Fn(u32, Option<&DeviceObjectInfoRef>);
+
+#[derive(Default)]
+struct ListenerLocalCallbacks {
+    info: Option<Box<InfoCallback>>,
+    result: Option<Box<ResultCallback>>,
+    event: Option<Box<EventCallback>>,
+    object_info: Option<Box<ObjectInfoCallback>>,
+}
+
+pub struct DeviceListener {
+    // Need to stay allocated while the listener is registered
+    #[allow(dead_code)]
+    events: Pin<Box<spa_sys::spa_device_events>>,
+    listener: Pin<Box<spa_sys::spa_hook>>,
+    #[allow(dead_code)]
+    data: Box<ListenerLocalCallbacks>,
+}
+
+impl Drop for DeviceListener {
+    fn drop(&mut self) {
+        crate::utils::hook::remove(*self.listener);
+    }
+}
+
+#[must_use]
+pub struct DeviceListenerLocalBuilder<'d> {
+    device: &'d DeviceRef,
+    cbs: ListenerLocalCallbacks,
+}
+
+impl<'d> DeviceListenerLocalBuilder<'d> {
+    /// Add the device info callback, called when registering the listener and when the info changes.
+    pub fn info<F>(mut self, info: F) -> Self
+    where
+        F: Fn(&DeviceInfoRef) + 'static,
+    {
+        self.cbs.info = Some(Box::new(info));
+        self
+    }
+
+    /// Add the result callback.
+    ///
+    /// Callback parameters: seq, res, type of the result, result.
+    /// For results of [`DeviceRef::enum_params`], the type is `SPA_RESULT_TYPE_DEVICE_PARAMS`
+    /// and the result points to a `spa_result_device_params`.
+    pub fn result<F>(mut self, result: F) -> Self
+    where
+        F: Fn(i32, i32, u32, *const std::os::raw::c_void) + 'static,
+    {
+        self.cbs.result = Some(Box::new(result));
+        self
+    }
+
+    /// Add the event callback.
+    pub fn event<F>(mut self, event: F) -> Self
+    where
+        F: Fn(&Pod) + 'static,
+    {
+        self.cbs.event = Some(Box::new(event));
+        self
+    }
+
+    /// Add the object info callback.
+    ///
+    /// Callback parameters: id of the object, info of the object or `None` if it was removed.
+    pub fn object_info<F>(mut self, object_info: F) -> Self
+    where
+        F: Fn(u32, Option<&DeviceObjectInfoRef>) + 'static,
+    {
+        self.cbs.object_info = Some(Box::new(object_info));
+        self
+    }
+
+    /// Register the listener.
+    ///
+    /// Monitors emit the info of the device and all its existing objects during this call.
+    pub fn register(self) -> Result<DeviceListener, Error> {
+        unsafe extern "C" fn device_events_info(
+            data: *mut std::os::raw::c_void,
+            info: *const spa_sys::spa_device_info,
+        ) {
+            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
+            let info = (info as *const DeviceInfoRef)
+                .as_ref()
+                .expect("info is NULL");
+            callbacks.info.as_ref().unwrap()(info);
+        }
+
+        unsafe extern "C" fn device_events_result(
+            data: *mut std::os::raw::c_void,
+            seq: i32,
+            res: i32,
+            type_: u32,
+            result: *const std::os::raw::c_void,
+        ) {
+            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
+            callbacks.result.as_ref().unwrap()(seq, res, type_, result);
+        }
+
+        unsafe extern "C" fn device_events_event(
+            data: *mut std::os::raw::c_void,
+            event: *const spa_sys::spa_event,
+        ) {
+            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
+            let event = (event as *const Pod).as_ref().expect("event is NULL");
+            callbacks.event.as_ref().unwrap()(event);
+        }
+
+        unsafe extern "C" fn device_events_object_info(
+            data: *mut std::os::raw::c_void,
+            id: u32,
+            info: *const spa_sys::spa_device_object_info,
+        ) {
+            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
+            let info = (info as *const DeviceObjectInfoRef).as_ref();
+            callbacks.object_info.as_ref().unwrap()(id, info);
+        }
+
+        let e = unsafe {
+            let mut e: Pin<Box<spa_sys::spa_device_events>> = Box::pin(mem::zeroed());
+            e.version = spa_sys::SPA_VERSION_DEVICE_EVENTS;
+
+            if self.cbs.info.is_some() {
+                e.info = Some(device_events_info);
+            }
+            if self.cbs.result.is_some() {
+                e.result = Some(device_events_result);
+            }
+            if self.cbs.event.is_some() {
+                e.event = Some(device_events_event);
+            }
+            if self.cbs.object_info.is_some() {
+                e.object_info = Some(device_events_object_info);
+            }
+
+            e
+        };
+
+        let (listener, data, res) = unsafe {
+            let data = Box::into_raw(Box::new(self.cbs));
+            let mut listener: Pin<Box<spa_sys::spa_hook>> = Box::pin(mem::zeroed());
+            let listener_ptr: *mut spa_sys::spa_hook = listener.as_mut().get_unchecked_mut();
+
+            let res = spa_interface_call_method!(
+                self.device.as_raw_ptr(),
+                spa_sys::spa_device_methods,
+                add_listener,
+                listener_ptr,
+                e.as_ref().get_ref(),
+                data as *mut _
+            );
+
+            (listener, Box::from_raw(data), res)
+        };
+
+        SpaResult::from_c(res).into_sync_result()?;
+
+        Ok(DeviceListener {
+            events: e,
+            listener,
+            data,
+        })
+    }
+}
@@ -5,12 +5,16 @@
 //! [libspa](https://gitlab.freedesktop.org/pipewire/pipewire/-/tree/master/doc/spa).
 
 pub mod buffer;
+pub mod device;
 pub mod param;
 pub mod pod;
 pub mod support;
 pub mod utils;
 
 pub use spa_sys as sys;
+pub use utils::dict::{ReadableDict, WritableDict};
 
 /// prelude module re-exporing all the traits providing public API.
-pub mod prelude {}
+pub mod prelude {
+    pub use crate::utils::dict::{ReadableDict, WritableDict};
+}
@@ -60,6 +60,18 @@ impl AudioFormat {
     pub const F64P: Self = Self(spa_sys::SPA_AUDIO_FORMAT_F64P);
     pub const S8P: Self = Self(spa_sys::SPA_AUDIO_FORMAT_S8P);
 
+    // Interleaved formats in the native byte order.
+    pub const S16: Self = native(Self::S16LE, Self::S16BE);
+    pub const U16: Self = native(Self::U16LE, Self::U16BE);
+    pub const S24_32: Self = native(Self::S24_32LE, Self::S24_32BE);
+    pub const U24_32: Self = native(Self::U24_32LE, Self::U24_32BE);
+    pub const S32: Self = native(Self::S32LE, Self::S32BE);
+    pub const U32: Self = native(Self::U32LE, Self::U32BE);
+    pub const S24: Self = native(Self::S24LE, Self::S24BE);
+    pub const U24: Self = native(Self::U24LE, Self::U24BE);
+    pub const F32: Self = native(Self::F32LE, Self::F32BE);
+    pub const F64: Self = native(Self::F64LE, Self::F64BE);
+
     const INTERLEAVED_RANGE: Range<Self> = Self::S8..Self(spa_sys::SPA_AUDIO_FORMAT_START_Planar);
     const PLANAR_RANGE: Range<Self> = Self::U8P..Self(spa_sys::SPA_AUDIO_FORMAT_START_Other);
 
@@ -71,6 +83,118 @@ impl AudioFormat {
         Self::PLANAR_RANGE.contains(self)
     }
 
+    /// The layout of the samples: their size in bytes, kind and byte order.
+    fn layout(&self) -> Option<(usize, SampleKind, Option<Endianness>)> {
+        use Endianness::{Big, Little};
+        use SampleKind::{Companded, Float, Signed, Unsigned};
+
+        // Planar formats are in the native byte order.
+        let native = Some(Endianness::NATIVE);
+        let layout = match *self {
+            Self::S8 | Self::S8P => (1, Signed, None),
+            Self::U8 | Self::U8P => (1, Unsigned, None),
+            Self::ULAW | Self::ALAW => (1, Companded, None),
+            Self::S16LE => (2, Signed, Some(Little)),
+            Self::S16BE => (2, Signed, Some(Big)),
+            Self::U16LE => (2, Unsigned, Some(Little)),
+            Self::U16BE => (2, Unsigned, Some(Big)),
+            Self::S16P => (2, Signed, native),
+            Self::S24_32LE | Self::S32LE => (4, Signed, Some(Little)),
+            Self::S24_32BE | Self::S32BE => (4, Signed, Some(Big)),
+            Self::U24_32LE | Self::U32LE => (4, Unsigned, Some(Little)),
+            Self::U24_32BE | Self::U32BE => (4, Unsigned, Some(Big)),
+            Self::S24_32P | Self::S32P => (4, Signed, native),
+            Self::S24LE | Self::S20LE | Self::S18LE => (3, Signed, Some(Little)),
+            Self::S24BE | Self::S20BE | Self::S18BE => (3, Signed, Some(Big)),
+            Self::U24LE | Self::U20LE | Self::U18LE => (3, Unsigned, Some(Little)),
+            Self::U24BE | Self::U20BE | Self::U18BE => (3, Unsigned, Some(Big)),
+            Self::S24P => (3, Signed, native),
+            Self::F32LE => (4, Float, Some(Little)),
+            Self::F32BE => (4, Float, Some(Big)),
+            Self::F32P => (4, Float, native),
+            Self::F64LE => (8, Float, Some(Little)),
+            Self::F64BE => (8, Float, Some(Big)),
+            Self::F64P => (8, Float, native),
+            _ => return None,
+        };
+        Some(layout)
+    }
+
+    /// The size of a sample in bytes, or `None` for unknown and encoded formats.
+    pub fn sample_size(&self) -> Option<usize> {
+        self.layout().map(|(size, _, _)| size)
+    }
+
+    /// The size in bytes of a frame of `channels` channels in one buffer data.
+    ///
+    /// As the channels of planar formats are each in their own data, this is the sample size for them.
+    pub fn frame_size(&self, channels: u32) -> Option<usize> {
+        let sample_size = self.sample_size()?;
+        if self.is_planar() {
+            Some(sample_size)
+        } else {
+            sample_size.checked_mul(usize::try_from(channels).ok()?)
+        }
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self.layout(), Some((_, SampleKind::Float, _)))
+    }
+
+    /// Whether the samples are signed, which is the case of the float formats.
+    ///
+    /// The companded formats, [`ULAW`](Self::ULAW) and [`ALAW`](Self::ALAW), are neither signed nor unsigned.
+    pub fn is_signed(&self) -> bool {
+        matches!(
+            self.layout(),
+            Some((_, SampleKind::Signed | SampleKind::Float, _))
+        )
+    }
+
+    /// The byte order of the samples, or `None` for single byte samples and unknown formats.
+    pub fn endianness(&self) -> Option<Endianness> {
+        self.layout().and_then(|(_, _, endianness)| endianness)
+    }
+
+    /// The planar format with the same samples as this interleaved format.
+    ///
+    /// Returns the format itself for planar formats, and `None` for formats without a planar
+    /// equivalent, such as the formats that are not in the native byte order.
+    pub fn planar_equivalent(&self) -> Option<Self> {
+        let planar = match *self {
+            Self::U8 => Self::U8P,
+            Self::S8 => Self::S8P,
+            Self::S16 => Self::S16P,
+            Self::S24_32 => Self::S24_32P,
+            Self::S32 => Self::S32P,
+            Self::S24 => Self::S24P,
+            Self::F32 => Self::F32P,
+            Self::F64 => Self::F64P,
+            format if format.is_planar() => format,
+            _ => return None,
+        };
+        Some(planar)
+    }
+
+    /// The interleaved format with the same samples as this planar format.
+    ///
+    /// Returns the format itself for interleaved formats.
+    pub fn interleaved_equivalent(&self) -> Option<Self> {
+        let interleaved = match *self {
+            Self::U8P => Self::U8,
+            Self::S8P => Self::S8,
+            Self::S16P => Self::S16,
+            Self::S24_32P => Self::S24_32,
+            Self::S32P => Self::S32,
+            Self::S24P => Self::S24,
+            Self::F32P => Self::F32,
+            Self::F64P => Self::F64,
+            format if format.is_interleaved() => format,
+            _ => return None,
+        };
+        Some(interleaved)
+    }
+
     /// Obtain an [`AudioFormat`] from a raw `spa_audio_format` variant.
     pub fn from_raw(raw: spa_sys::spa_audio_format) -> Self {
         Self(raw)
@@ -82,6 +206,82 @@ impl AudioFormat {
     }
 }
 
+const fn native(le: AudioFormat, be: AudioFormat) -> AudioFormat {
+    if cfg!(target_endian = "little") {
+        le
+    } else {
+        be
+    }
+}
+
+#[derive(Clone, Copy)]
+enum SampleKind {
+    Signed,
+    Unsigned,
+    Float,
+    /// Logarithmically encoded, as with u-law and A-law.
+    Companded,
+}
+
+/// The byte order of audio samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+impl Endianness {
+    /// The byte order of the target.
+    pub const NATIVE: Self = if cfg!(target_endian = "little") {
+        Self::Little
+    } else {
+        Self::Big
+    };
+}
+
+/// A type holding one audio sample, whose slices can be obtained from the bytes of a buffer with
+/// [`AudioInfoRaw::samples`].
+///
+/// # Safety
+/// All bit patterns must be valid values of the type, and the type must have the size and
+/// representation of the samples of its [`FORMATS`](Self::FORMATS).
+pub unsafe trait Sample: Copy + 'static {
+    /// The interleaved formats whose samples are of this type.
+    const FORMATS: &'static [AudioFormat];
+}
+
+unsafe impl Sample for u8 {
+    const FORMATS: &'static [AudioFormat] = &[AudioFormat::U8];
+}
+
+unsafe impl Sample for i8 {
+    const FORMATS: &'static [AudioFormat] = &[AudioFormat::S8];
+}
+
+unsafe impl Sample for i16 {
+    const FORMATS: &'static [AudioFormat] = &[AudioFormat::S16];
+}
+
+unsafe impl Sample for u16 {
+    const FORMATS: &'static [AudioFormat] = &[AudioFormat::U16];
+}
+
+unsafe impl Sample for i32 {
+    const FORMATS: &'static [AudioFormat] = &[AudioFormat::S32, AudioFormat::S24_32];
+}
+
+unsafe impl Sample for u32 {
+    const FORMATS: &'static [AudioFormat] = &[AudioFormat::U32, AudioFormat::U24_32];
+}
+
+unsafe impl Sample for f32 {
+    const FORMATS: &'static [AudioFormat] = &[AudioFormat::F32];
+}
+
+unsafe impl Sample for f64 {
+    const FORMATS: &'static [AudioFormat] = &[AudioFormat::F64];
+}
+
 impl Debug for AudioFormat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match *self {
@@ -105,6 +305,8 @@ impl Debug for AudioFormat {
     }
 }
 
+impl_short_name!(AudioFormat, spa_sys::spa_type_audio_format);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -121,4 +323,60 @@ mod tests {
             format!("{:?}", AudioFormat::S24_32LE)
         );
     }
+
+    #[test]
+    fn sample_layout() {
+        assert_eq!(AudioFormat::S16LE.sample_size(), Some(2));
+        assert_eq!(AudioFormat::S24BE.sample_size(), Some(3));
+        assert_eq!(AudioFormat::F64P.sample_size(), Some(8));
+        assert_eq!(AudioFormat::Encoded.sample_size(), None);
+
+        assert_eq!(AudioFormat::S24_32LE.frame_size(2), Some(8));
+        assert_eq!(AudioFormat::F32P.frame_size(2), Some(4));
+
+        assert!(AudioFormat::F32BE.is_float());
+        assert!(AudioFormat::F32BE.is_signed());
+        assert!(!AudioFormat::U16LE.is_signed());
+        assert!(!AudioFormat::ULAW.is_signed());
+
+        assert_eq!(AudioFormat::S16BE.endianness(), Some(Endianness::Big));
+        assert_eq!(AudioFormat::S16P.endianness(), Some(Endianness::NATIVE));
+        assert_eq!(AudioFormat::U8.endianness(), None);
+    }
+
+    #[test]
+    fn planar_equivalent() {
+        assert_eq!(
+            AudioFormat::F32.planar_equivalent(),
+            Some(AudioFormat::F32P)
+        );
+        assert_eq!(
+            AudioFormat::S16P.planar_equivalent(),
+            Some(AudioFormat::S16P)
+        );
+        assert_eq!(AudioFormat::U16LE.planar_equivalent(), None);
+        assert_eq!(
+            AudioFormat::S24P.interleaved_equivalent(),
+            Some(AudioFormat::S24)
+        );
+    }
+
+    #[test]
+    fn samples() {
+        let mut info = AudioInfoRaw::new();
+        info.set_format(AudioFormat::F32);
+        info.set_channels(2);
+
+        let samples = [0.5f32, -0.5, 1.0, -1.0];
+        let bytes: &[u8] = unsafe {
+            std::slice::from_raw_parts(samples.as_ptr().cast(), std::mem::size_of_val(&samples))
+        };
+
+        assert_eq!(info.samples::<f32>(bytes), Some(&samples[..]));
+        assert_eq!(info.samples::<i32>(bytes), None);
+        // Not a whole number of frames.
+        assert_eq!(info.samples::<f32>(&bytes[..4]), None);
+        // Misaligned.
+        assert_eq!(info.samples::<f32>(&bytes[1..]), None);
+    }
 }
@@ -1,8 +1,8 @@
 // Copyright The pipewire-rs Contributors.
 // SPDX-License-Identifier: MIT
 
-use crate::param::audio::AudioFormat;
-use crate::pod::{Property, Value, ValueArray};
+use crate::param::audio::{AudioFormat, Sample};
+use crate::pod::{Property, Value};
 use crate::utils::{
     self,
     result::{Error, SpaResult, SpaSuccess},
@@ -78,9 +78,61 @@ impl AudioInfoRaw {
         self.0.position
     }
 
+    /// The size in bytes of a frame in one buffer data, see [`AudioFormat::frame_size`].
+    pub fn frame_size(&self) -> Option<usize> {
+        self.format().frame_size(self.channels())
+    }
+
+    /// Reinterpret the bytes of a buffer data holding samples of this format.
+    ///
+    /// Returns `None` if the samples of the format are not of type `T`, if the bytes are not
+    /// aligned for `T` or if they do not hold a whole number of frames.
+    pub fn samples<'a, T: Sample>(&self, bytes: &'a [u8]) -> Option<&'a [T]> {
+        if bytes.is_empty() {
+            return Some(&[]);
+        }
+        self.check_samples::<T>(bytes)?;
+
+        // Safety: the bytes are aligned and sized for T, which can hold any bit pattern.
+        Some(unsafe {
+            std::slice::from_raw_parts(
+                bytes.as_ptr().cast(),
+                bytes.len() / std::mem::size_of::<T>(),
+            )
+        })
+    }
+
+    /// Mutable version of [`samples`](Self::samples).
+    pub fn samples_mut<'a, T: Sample>(&self, bytes: &'a mut [u8]) -> Option<&'a mut [T]> {
+        if bytes.is_empty() {
+            return Some(&mut []);
+        }
+        self.check_samples::<T>(bytes)?;
+
+        // Safety: the bytes are aligned and sized for T, which can hold any bit pattern.
+        Some(unsafe {
+            std::slice::from_raw_parts_mut(
+                bytes.as_mut_ptr().cast(),
+                bytes.len() / std::mem::size_of::<T>(),
+            )
+        })
+    }
+
+    fn check_samples<T: Sample>(&self, bytes: &[u8]) -> Option<()> {
+        let format = self.format();
+        let interleaved = format.interleaved_equivalent()?;
+        let frame_size = self.frame_size()?;
+
+        let matches = T::FORMATS.contains(&interleaved)
+            && bytes.as_ptr().align_offset(std::mem::align_of::<T>()) == 0
+            && frame_size != 0
+            && bytes.len() % frame_size == 0;
+        matches.then_some(())
+    }
+
     /// helper function to parse format properties type
     pub fn parse(&mut self, format: &crate::pod::Pod) -> Result<SpaSuccess, Error> {
-        let res = unsafe { spa_sys::spa_format_audio_raw_parse(format.as_raw_ptr(), &mut self.0) };
+        let res = unsafe { spa_sys::spa_format_audio_raw_parse(format.as_ptr(), &mut self.0) };
         SpaResult::from_c(res).into_result()
     }
 
@@ -133,14 +185,13 @@ impl From<AudioInfoRaw> for Vec<Property> {
                 Value::Int(value.channels() as i32),
             ));
             if !value.flags().contains(AudioInfoRawFlags::UNPOSITIONED) {
-                let array = value.position()[0..value.channels() as usize]
+                let positions = value.position()[0..value.channels() as usize]
                     .iter()
                     .copied()
-                    .map(utils::Id)
-                    .collect();
+                    .map(utils::Id);
                 props.push(Property::new(
                     spa_sys::SPA_FORMAT_AUDIO_position,
-                    Value::ValueArray(ValueArray::Id(array)),
+                    Value::array(positions),
                 ));
             }
         }
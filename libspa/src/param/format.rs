@@ -53,6 +53,8 @@ impl Debug for MediaType {
     }
 }
 
+impl_short_name!(MediaType, spa_sys::spa_type_media_type);
+
 /// Different media sub-types
 #[derive(PartialEq, PartialOrd, Eq, Clone, Copy)]
 pub struct MediaSubtype(pub spa_sys::spa_media_subtype);
@@ -172,6 +174,8 @@ impl Debug for MediaSubtype {
     }
 }
 
+impl_short_name!(MediaSubtype, spa_sys::spa_type_media_subtype);
+
 #[derive(PartialEq, PartialOrd, Eq, Clone, Copy)]
 pub struct FormatProperties(pub spa_sys::spa_format);
 
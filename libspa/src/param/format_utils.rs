@@ -16,7 +16,7 @@ pub fn parse_format(format: &Pod) -> Result<(MediaType, MediaSubtype), Error> {
 
     let res = unsafe {
         spa_sys::spa_format_parse(
-            format.as_raw_ptr(),
+            format.as_ptr(),
             media_type.as_mut_ptr(),
             media_subtype.as_mut_ptr(),
         )
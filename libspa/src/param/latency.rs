@@ -0,0 +1,237 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Types for the latency params, [`ParamType::Latency`](super::ParamType::Latency) and
+//! [`ParamType::ProcessLatency`](super::ParamType::ProcessLatency).
+//!
+//! Latencies are expressed in three parts, which are added together: a multiple of the
+//! graph quantum, a number of samples at the graph rate and a number of nanoseconds.
+
+use std::fmt::Debug;
+
+use crate::{
+    pod::Pod,
+    utils::{
+        result::{Error, SpaResult},
+        Direction,
+    },
+};
+
+/// Rust representation of [`spa_sys::spa_latency_info`].
+///
+/// The latency reported in the [`Direction::Output`] direction is the latency from the
+/// sources of the graph to the object, the one in the [`Direction::Input`] direction is the
+/// latency from the object to the sinks of the graph.
+#[repr(transparent)]
+#[derive(Clone, Copy)]
+pub struct LatencyInfo(spa_sys::spa_latency_info);
+
+impl LatencyInfo {
+    /// Create a zero latency in `direction`.
+    pub fn new(direction: Direction) -> Self {
+        Self(spa_sys::spa_latency_info {
+            direction: direction.as_raw(),
+            min_quantum: 0.0,
+            max_quantum: 0.0,
+            min_rate: 0,
+            max_rate: 0,
+            min_ns: 0,
+            max_ns: 0,
+        })
+    }
+
+    /// Parse a [`ParamType::Latency`](super::ParamType::Latency) param.
+    pub fn parse(param: &Pod) -> Result<Self, Error> {
+        let mut info = Self::new(Direction::Input);
+        let res = unsafe { spa_sys::spa_latency_parse(param.as_ptr(), &mut info.0) };
+        SpaResult::from_c(res).into_sync_result()?;
+
+        Ok(info)
+    }
+
+    /// Combine the latency of another path in the same direction,
+    /// keeping the smallest minimum and the largest maximum.
+    ///
+    /// Returns `false` if the directions differ.
+    pub fn combine(&mut self, other: &Self) -> bool {
+        unsafe { spa_sys::spa_latency_info_combine(&mut self.0, &other.0) == 0 }
+    }
+
+    /// The direction of the latency, `None` if it is unknown.
+    pub fn direction(&self) -> Option<Direction> {
+        Direction::from_raw(self.0.direction)
+    }
+
+    pub fn min_quantum(&self) -> f32 {
+        self.0.min_quantum
+    }
+
+    pub fn set_min_quantum(&mut self, min_quantum: f32) {
+        self.0.min_quantum = min_quantum;
+    }
+
+    pub fn max_quantum(&self) -> f32 {
+        self.0.max_quantum
+    }
+
+    pub fn set_max_quantum(&mut self, max_quantum: f32) {
+        self.0.max_quantum = max_quantum;
+    }
+
+    pub fn min_rate(&self) -> u32 {
+        self.0.min_rate
+    }
+
+    pub fn set_min_rate(&mut self, min_rate: u32) {
+        self.0.min_rate = min_rate;
+    }
+
+    pub fn max_rate(&self) -> u32 {
+        self.0.max_rate
+    }
+
+    pub fn set_max_rate(&mut self, max_rate: u32) {
+        self.0.max_rate = max_rate;
+    }
+
+    pub fn min_ns(&self) -> u64 {
+        self.0.min_ns
+    }
+
+    pub fn set_min_ns(&mut self, min_ns: u64) {
+        self.0.min_ns = min_ns;
+    }
+
+    pub fn max_ns(&self) -> u64 {
+        self.0.max_ns
+    }
+
+    pub fn set_max_ns(&mut self, max_ns: u64) {
+        self.0.max_ns = max_ns;
+    }
+
+    /// Obtain a [`LatencyInfo`] from a raw `spa_latency_info`.
+    pub fn from_raw(raw: spa_sys::spa_latency_info) -> Self {
+        Self(raw)
+    }
+
+    /// Get the raw [`spa_sys::spa_latency_info`] representing this `LatencyInfo`.
+    pub fn as_raw(&self) -> spa_sys::spa_latency_info {
+        self.0
+    }
+}
+
+impl Debug for LatencyInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LatencyInfo")
+            .field("direction", &self.direction())
+            .field("min_quantum", &self.min_quantum())
+            .field("max_quantum", &self.max_quantum())
+            .field("min_rate", &self.min_rate())
+            .field("max_rate", &self.max_rate())
+            .field("min_ns", &self.min_ns())
+            .field("max_ns", &self.max_ns())
+            .finish()
+    }
+}
+
+/// Rust representation of [`spa_sys::spa_process_latency_info`],
+/// the latency added by the processing of a node.
+#[repr(transparent)]
+#[derive(Clone, Copy)]
+pub struct ProcessLatencyInfo(spa_sys::spa_process_latency_info);
+
+impl ProcessLatencyInfo {
+    /// Create a zero processing latency.
+    pub fn new() -> Self {
+        Self(spa_sys::spa_process_latency_info {
+            quantum: 0.0,
+            rate: 0,
+            ns: 0,
+        })
+    }
+
+    /// Parse a [`ParamType::ProcessLatency`](super::ParamType::ProcessLatency) param.
+    pub fn parse(param: &Pod) -> Result<Self, Error> {
+        let mut info = Self::new();
+        let res = unsafe { spa_sys::spa_process_latency_parse(param.as_ptr(), &mut info.0) };
+        SpaResult::from_c(res).into_sync_result()?;
+
+        Ok(info)
+    }
+
+    pub fn quantum(&self) -> f32 {
+        self.0.quantum
+    }
+
+    pub fn set_quantum(&mut self, quantum: f32) {
+        self.0.quantum = quantum;
+    }
+
+    pub fn rate(&self) -> u32 {
+        self.0.rate
+    }
+
+    pub fn set_rate(&mut self, rate: u32) {
+        self.0.rate = rate;
+    }
+
+    pub fn ns(&self) -> u64 {
+        self.0.ns
+    }
+
+    pub fn set_ns(&mut self, ns: u64) {
+        self.0.ns = ns;
+    }
+
+    /// Obtain a [`ProcessLatencyInfo`] from a raw `spa_process_latency_info`.
+    pub fn from_raw(raw: spa_sys::spa_process_latency_info) -> Self {
+        Self(raw)
+    }
+
+    /// Get the raw [`spa_sys::spa_process_latency_info`] representing this `ProcessLatencyInfo`.
+    pub fn as_raw(&self) -> spa_sys::spa_process_latency_info {
+        self.0
+    }
+}
+
+impl Default for ProcessLatencyInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for ProcessLatencyInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProcessLatencyInfo")
+            .field("quantum", &self.quantum())
+            .field("rate", &self.rate())
+            .field("ns", &self.ns())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn combine() {
+        let mut latency = LatencyInfo::new(Direction::Output);
+        latency.set_min_rate(128);
+        latency.set_max_rate(256);
+
+        let mut other = LatencyInfo::new(Direction::Output);
+        other.set_min_rate(64);
+        other.set_max_rate(512);
+        other.set_max_ns(1000);
+
+        assert!(latency.combine(&other));
+        assert_eq!(latency.min_rate(), 64);
+        assert_eq!(latency.max_rate(), 512);
+        assert_eq!(latency.max_ns(), 1000);
+
+        assert!(!latency.combine(&LatencyInfo::new(Direction::Input)));
+    }
+}
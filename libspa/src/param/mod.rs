@@ -3,13 +3,37 @@
 
 //! Types for dealing with SPA parameters.
 
+/// Implement [`Display`](std::fmt::Display) and [`FromStr`](std::str::FromStr) for an id type,
+/// using the short names of its SPA type info table.
+macro_rules! impl_short_name {
+    ($type:ty, $info:expr) => {
+        impl std::fmt::Display for $type {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match unsafe { $crate::param::type_short_name($info, self.as_raw()) } {
+                    Some(name) => f.write_str(name),
+                    None => write!(f, "{}", self.as_raw()),
+                }
+            }
+        }
+
+        impl std::str::FromStr for $type {
+            type Err = $crate::param::ParseNameError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                unsafe { $crate::param::type_from_short_name($info, s) }.map(Self::from_raw)
+            }
+        }
+    };
+}
+
 pub mod audio;
 pub mod format;
 pub mod format_utils;
+pub mod latency;
 pub mod video;
 
 use std::ffi::CStr;
-use std::fmt::Debug;
+use std::fmt::{self, Debug, Display};
 
 /// Different parameter types that can be queried
 #[derive(Copy, Clone, PartialEq, Eq)]
@@ -51,6 +75,12 @@ impl ParamType {
     pub const Latency: Self = Self(spa_sys::SPA_PARAM_Latency);
     /// processing latency, a SPA_TYPE_OBJECT_ParamProcessLatency
     pub const ProcessLatency: Self = Self(spa_sys::SPA_PARAM_ProcessLatency);
+    /// tag reporting, a SPA_TYPE_OBJECT_ParamTag
+    #[cfg(feature = "v1_2_0")]
+    pub const Tag: Self = Self(spa_sys::SPA_PARAM_Tag);
+    /// capabilities of the peer, a SPA_TYPE_OBJECT_ParamDict
+    #[cfg(feature = "v1_2_0")]
+    pub const PeerCapability: Self = Self(spa_sys::SPA_PARAM_PeerCapability);
 
     /// Obtain a [`ParamType`] from a raw `spa_param_type` variant.
     pub fn from_raw(raw: spa_sys::spa_param_type) -> Self {
@@ -78,6 +108,70 @@ impl Debug for ParamType {
     }
 }
 
+impl_short_name!(ParamType, spa_sys::spa_type_param);
+
+/// Error returned when parsing a name that SPA does not know for the type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseNameError {
+    name: String,
+}
+
+impl ParseNameError {
+    /// The name that failed to parse.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Display for ParseNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown name `{}`", self.name)
+    }
+}
+
+impl std::error::Error for ParseNameError {}
+
+/// The short name of `id` in the type info table `info`, such as `S16LE`.
+///
+/// # Safety
+/// `info` must point to a SPA type info table.
+pub(crate) unsafe fn type_short_name(
+    info: *const spa_sys::spa_type_info,
+    id: u32,
+) -> Option<&'static str> {
+    let name = spa_sys::spa_debug_type_find_short_name(info, id);
+    if name.is_null() {
+        return None;
+    }
+    CStr::from_ptr(name).to_str().ok()
+}
+
+/// The id with the short `name` in the type info table `info`, or the id written as a number.
+///
+/// # Safety
+/// `info` must point to a SPA type info table.
+pub(crate) unsafe fn type_from_short_name(
+    info: *const spa_sys::spa_type_info,
+    name: &str,
+) -> Result<u32, ParseNameError> {
+    let mut entry = info;
+    while !(*entry).name.is_null() {
+        let full = CStr::from_ptr((*entry).name).to_bytes();
+        let short = match full.iter().rposition(|&b| b == b':') {
+            Some(colon) => &full[colon + 1..],
+            None => full,
+        };
+        if short == name.as_bytes() {
+            return Ok((*entry).type_);
+        }
+        entry = entry.add(1);
+    }
+
+    name.parse().map_err(|_| ParseNameError {
+        name: name.to_owned(),
+    })
+}
+
 bitflags::bitflags! {
     #[derive(Debug, PartialEq, Eq, Clone, Copy)]
     pub struct ParamInfoFlags: u32 {
@@ -110,3 +204,31 @@ impl Debug for ParamInfo {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{audio::AudioFormat, format::*, video::VideoFormat, *};
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn short_names() {
+        assert_eq!(MediaType::Audio.to_string(), "audio");
+        assert_eq!(MediaSubtype::Raw.to_string(), "raw");
+        assert_eq!(AudioFormat::S16LE.to_string(), "S16LE");
+        assert_eq!(VideoFormat::NV12.to_string(), "NV12");
+        assert_eq!(ParamType::EnumFormat.to_string(), "EnumFormat");
+
+        assert_eq!("video".parse(), Ok(MediaType::Video));
+        assert_eq!("mjpg".parse(), Ok(MediaSubtype::Mjpg));
+        assert_eq!("F32P".parse(), Ok(AudioFormat::F32P));
+        assert_eq!("xRGB_210LE".parse(), Ok(VideoFormat::xRGB_210LE));
+        assert_eq!("Props".parse(), Ok(ParamType::Props));
+        // Ids without a name are written as numbers.
+        assert_eq!(ParamType::from_raw(0x12345).to_string(), "74565");
+        assert_eq!("74565".parse(), Ok(ParamType::from_raw(0x12345)));
+
+        let err = "NV13".parse::<VideoFormat>().unwrap_err();
+        assert_eq!(err.name(), "NV13");
+        assert_eq!(err.to_string(), "unknown name `NV13`");
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+use super::fourcc;
+
+/// The arrangement of the color filters of a Bayer sensor, named after the colors of its top left
+/// 2x2 pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BayerPattern {
+    Bggr,
+    Gbrg,
+    Grbg,
+    Rggb,
+}
+
+impl BayerPattern {
+    /// The pattern of the image flipped horizontally and/or vertically, as done by sensors that
+    /// are mounted upside down.
+    pub fn flipped(self, horizontal: bool, vertical: bool) -> Self {
+        let pattern = if horizontal {
+            match self {
+                Self::Bggr => Self::Gbrg,
+                Self::Gbrg => Self::Bggr,
+                Self::Grbg => Self::Rggb,
+                Self::Rggb => Self::Grbg,
+            }
+        } else {
+            self
+        };
+
+        if vertical {
+            match pattern {
+                Self::Bggr => Self::Grbg,
+                Self::Gbrg => Self::Rggb,
+                Self::Grbg => Self::Bggr,
+                Self::Rggb => Self::Gbrg,
+            }
+        } else {
+            pattern
+        }
+    }
+}
+
+/// A raw Bayer sensor format, negotiated with [`MediaSubtype::Bayer`](crate::param::format::MediaSubtype::Bayer).
+///
+/// SPA does not describe the layout of Bayer frames in the format params, so this maps the fourcc
+/// codes used by libcamera and V4L2 to it. Some of these codes are also used by other formats, such
+/// as `RG16` being `DRM_FORMAT_RGB565`, so they have to be interpreted according to the media
+/// subtype.
+///
+/// ```
+/// use libspa::param::video::{fourcc, BayerFormat, BayerPattern};
+///
+/// let format = BayerFormat::from_v4l2_fourcc(fourcc(b"pRAA")).unwrap();
+/// assert_eq!(format, BayerFormat { pattern: BayerPattern::Rggb, bits: 10, packed: true });
+/// assert_eq!(format.stride(1920), 2400);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BayerFormat {
+    pub pattern: BayerPattern,
+    /// The number of significant bits of a sample: 8, 10, 12 or 16.
+    pub bits: u32,
+    /// Whether the samples are packed as done by MIPI CSI-2 rather than stored in 16 bits words,
+    /// such as 4 pixels of 10 bits in 5 bytes.
+    pub packed: bool,
+}
+
+/// The V4L2 fourcc codes of the Bayer formats, in the order of [`BayerPattern`].
+const V4L2_FOURCCS: &[(u32, bool, [&[u8; 4]; 4])] = &[
+    (8, false, [b"BA81", b"GBRG", b"GRBG", b"RGGB"]),
+    (10, false, [b"BG10", b"GB10", b"BA10", b"RG10"]),
+    (12, false, [b"BG12", b"GB12", b"BA12", b"RG12"]),
+    (16, false, [b"BYR2", b"GB16", b"GR16", b"RG16"]),
+    (10, true, [b"pBAA", b"pGAA", b"pgAA", b"pRAA"]),
+    (12, true, [b"pBCC", b"pGCC", b"pgCC", b"pRCC"]),
+];
+
+const PATTERNS: [BayerPattern; 4] = [
+    BayerPattern::Bggr,
+    BayerPattern::Gbrg,
+    BayerPattern::Grbg,
+    BayerPattern::Rggb,
+];
+
+impl BayerFormat {
+    /// The format with the V4L2 fourcc `code`, such as `V4L2_PIX_FMT_SRGGB10P`.
+    pub fn from_v4l2_fourcc(code: u32) -> Option<Self> {
+        V4L2_FOURCCS.iter().find_map(|(bits, packed, codes)| {
+            let index = codes.iter().position(|c| fourcc(c) == code)?;
+            Some(Self {
+                pattern: PATTERNS[index],
+                bits: *bits,
+                packed: *packed,
+            })
+        })
+    }
+
+    /// The V4L2 fourcc code of the format.
+    pub fn v4l2_fourcc(&self) -> Option<u32> {
+        let (_, _, codes) = V4L2_FOURCCS
+            .iter()
+            .find(|(bits, packed, _)| *bits == self.bits && *packed == self.packed)?;
+        let index = PATTERNS.iter().position(|p| *p == self.pattern)?;
+        Some(fourcc(codes[index]))
+    }
+
+    /// The format with the DRM fourcc `code`, as used by libcamera.
+    ///
+    /// DRM uses the same codes as V4L2 for the unpacked formats, and describes packing with the
+    /// `MIPI_FORMAT_MOD_CSI2_PACKED` modifier, which is given by `csi2_packed`.
+    pub fn from_drm_fourcc(code: u32, csi2_packed: bool) -> Option<Self> {
+        let format = Self::from_v4l2_fourcc(code).filter(|format| !format.packed)?;
+        let format = Self {
+            packed: csi2_packed,
+            ..format
+        };
+        // Check that the packed variant exists.
+        format.v4l2_fourcc().map(|_| format)
+    }
+
+    /// The DRM fourcc code of the format, to be used with the `MIPI_FORMAT_MOD_CSI2_PACKED`
+    /// modifier if the format is [`packed`](Self::packed).
+    pub fn drm_fourcc(&self) -> Option<u32> {
+        Self {
+            packed: false,
+            ..*self
+        }
+        .v4l2_fourcc()
+    }
+
+    /// The number of bits used to store a sample.
+    pub fn bits_per_pixel(&self) -> u32 {
+        match (self.packed, self.bits) {
+            (true, bits) => bits,
+            (false, 8) => 8,
+            (false, _) => 16,
+        }
+    }
+
+    /// The size in bytes of a tightly packed row of `width` pixels.
+    pub fn stride(&self, width: u32) -> u32 {
+        let bits = width * self.bits_per_pixel();
+        bits / 8 + u32::from(bits % 8 != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fourccs() {
+        for (bits, packed, codes) in V4L2_FOURCCS {
+            for (pattern, code) in PATTERNS.iter().zip(codes) {
+                let format = BayerFormat {
+                    pattern: *pattern,
+                    bits: *bits,
+                    packed: *packed,
+                };
+                assert_eq!(BayerFormat::from_v4l2_fourcc(fourcc(code)), Some(format));
+                assert_eq!(format.v4l2_fourcc(), Some(fourcc(code)));
+
+                let drm = format.drm_fourcc().unwrap();
+                assert_eq!(BayerFormat::from_drm_fourcc(drm, *packed), Some(format));
+            }
+        }
+
+        // There is no packed 16 bits format.
+        assert_eq!(BayerFormat::from_drm_fourcc(fourcc(b"RG16"), true), None);
+    }
+
+    #[test]
+    fn flipped() {
+        assert_eq!(BayerPattern::Rggb.flipped(true, false), BayerPattern::Grbg);
+        assert_eq!(BayerPattern::Rggb.flipped(false, true), BayerPattern::Gbrg);
+        assert_eq!(BayerPattern::Rggb.flipped(true, true), BayerPattern::Bggr);
+        assert_eq!(BayerPattern::Bggr.flipped(false, false), BayerPattern::Bggr);
+    }
+}
@@ -0,0 +1,242 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+use super::VideoFormat;
+
+/// Build a fourcc code from its four characters, as done by the `fourcc_code` and `v4l2_fourcc`
+/// C macros.
+pub const fn fourcc(code: &[u8; 4]) -> u32 {
+    u32::from_le_bytes(*code)
+}
+
+/// The formats with their DRM and V4L2 fourcc codes.
+///
+/// DRM codes describe the order of the components in a little endian word, whereas SPA describes
+/// their order in memory, hence [`VideoFormat::RGB`] being `DRM_FORMAT_BGR888`.
+const FOURCCS: &[(VideoFormat, Option<u32>, Option<u32>)] = &[
+    (
+        VideoFormat::I420,
+        Some(fourcc(b"YU12")),
+        Some(fourcc(b"YU12")),
+    ),
+    (
+        VideoFormat::YV12,
+        Some(fourcc(b"YV12")),
+        Some(fourcc(b"YV12")),
+    ),
+    (
+        VideoFormat::Y42B,
+        Some(fourcc(b"YU16")),
+        Some(fourcc(b"422P")),
+    ),
+    (VideoFormat::Y41B, None, Some(fourcc(b"411P"))),
+    (VideoFormat::Y444, Some(fourcc(b"YU24")), None),
+    (
+        VideoFormat::YUV9,
+        Some(fourcc(b"YUV9")),
+        Some(fourcc(b"YUV9")),
+    ),
+    (
+        VideoFormat::YVU9,
+        Some(fourcc(b"YVU9")),
+        Some(fourcc(b"YVU9")),
+    ),
+    (
+        VideoFormat::NV12,
+        Some(fourcc(b"NV12")),
+        Some(fourcc(b"NV12")),
+    ),
+    (
+        VideoFormat::NV21,
+        Some(fourcc(b"NV21")),
+        Some(fourcc(b"NV21")),
+    ),
+    (
+        VideoFormat::NV16,
+        Some(fourcc(b"NV16")),
+        Some(fourcc(b"NV16")),
+    ),
+    (
+        VideoFormat::NV61,
+        Some(fourcc(b"NV61")),
+        Some(fourcc(b"NV61")),
+    ),
+    (
+        VideoFormat::NV24,
+        Some(fourcc(b"NV24")),
+        Some(fourcc(b"NV24")),
+    ),
+    (
+        VideoFormat::P010_10LE,
+        Some(fourcc(b"P010")),
+        Some(fourcc(b"P010")),
+    ),
+    (
+        VideoFormat::YUY2,
+        Some(fourcc(b"YUYV")),
+        Some(fourcc(b"YUYV")),
+    ),
+    (
+        VideoFormat::YVYU,
+        Some(fourcc(b"YVYU")),
+        Some(fourcc(b"YVYU")),
+    ),
+    (
+        VideoFormat::UYVY,
+        Some(fourcc(b"UYVY")),
+        Some(fourcc(b"UYVY")),
+    ),
+    (
+        VideoFormat::VYUY,
+        Some(fourcc(b"VYUY")),
+        Some(fourcc(b"VYUY")),
+    ),
+    (
+        VideoFormat::GRAY8,
+        Some(fourcc(b"R8  ")),
+        Some(fourcc(b"GREY")),
+    ),
+    (
+        VideoFormat::GRAY16_LE,
+        Some(fourcc(b"R16 ")),
+        Some(fourcc(b"Y16 ")),
+    ),
+    (
+        VideoFormat::RGB,
+        Some(fourcc(b"BG24")),
+        Some(fourcc(b"RGB3")),
+    ),
+    (
+        VideoFormat::BGR,
+        Some(fourcc(b"RG24")),
+        Some(fourcc(b"BGR3")),
+    ),
+    (
+        VideoFormat::RGB16,
+        Some(fourcc(b"RG16")),
+        Some(fourcc(b"RGBP")),
+    ),
+    (VideoFormat::BGR16, Some(fourcc(b"BG16")), None),
+    (
+        VideoFormat::RGB15,
+        Some(fourcc(b"XR15")),
+        Some(fourcc(b"RGBO")),
+    ),
+    (
+        VideoFormat::BGRx,
+        Some(fourcc(b"XR24")),
+        Some(fourcc(b"XR24")),
+    ),
+    (
+        VideoFormat::BGRA,
+        Some(fourcc(b"AR24")),
+        Some(fourcc(b"AR24")),
+    ),
+    (
+        VideoFormat::RGBx,
+        Some(fourcc(b"XB24")),
+        Some(fourcc(b"XB24")),
+    ),
+    (
+        VideoFormat::RGBA,
+        Some(fourcc(b"AB24")),
+        Some(fourcc(b"AB24")),
+    ),
+    (
+        VideoFormat::xRGB,
+        Some(fourcc(b"BX24")),
+        Some(fourcc(b"BX24")),
+    ),
+    (
+        VideoFormat::ARGB,
+        Some(fourcc(b"BA24")),
+        Some(fourcc(b"BA24")),
+    ),
+    (
+        VideoFormat::xBGR,
+        Some(fourcc(b"RX24")),
+        Some(fourcc(b"RX24")),
+    ),
+    (
+        VideoFormat::ABGR,
+        Some(fourcc(b"RA24")),
+        Some(fourcc(b"RA24")),
+    ),
+    (VideoFormat::xRGB_210LE, Some(fourcc(b"XR30")), None),
+    (VideoFormat::xBGR_210LE, Some(fourcc(b"XB30")), None),
+    (VideoFormat::RGBx_102LE, Some(fourcc(b"RX30")), None),
+    (VideoFormat::BGRx_102LE, Some(fourcc(b"BX30")), None),
+    (VideoFormat::ARGB_210LE, Some(fourcc(b"AR30")), None),
+    (VideoFormat::ABGR_210LE, Some(fourcc(b"AB30")), None),
+    (VideoFormat::RGBA_102LE, Some(fourcc(b"RA30")), None),
+    (VideoFormat::BGRA_102LE, Some(fourcc(b"BA30")), None),
+    (VideoFormat::RGBA_F16, Some(fourcc(b"AB4H")), None),
+];
+
+impl VideoFormat {
+    /// The format with the DRM fourcc `code`, such as `DRM_FORMAT_XRGB8888` for [`BGRx`](Self::BGRx).
+    pub fn from_drm_fourcc(code: u32) -> Option<Self> {
+        FOURCCS
+            .iter()
+            .find(|(_, drm, _)| *drm == Some(code))
+            .map(|(format, _, _)| *format)
+    }
+
+    /// The DRM fourcc code of the format, as used with dmabufs.
+    pub fn drm_fourcc(&self) -> Option<u32> {
+        FOURCCS
+            .iter()
+            .find(|(format, _, _)| format == self)
+            .and_then(|(_, drm, _)| *drm)
+    }
+
+    /// The format with the V4L2 fourcc `code`, such as `V4L2_PIX_FMT_YUYV` for [`YUY2`](Self::YUY2).
+    pub fn from_v4l2_fourcc(code: u32) -> Option<Self> {
+        FOURCCS
+            .iter()
+            .find(|(_, _, v4l2)| *v4l2 == Some(code))
+            .map(|(format, _, _)| *format)
+    }
+
+    /// The V4L2 fourcc code of the format.
+    pub fn v4l2_fourcc(&self) -> Option<u32> {
+        FOURCCS
+            .iter()
+            .find(|(format, _, _)| format == self)
+            .and_then(|(_, _, v4l2)| *v4l2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for (format, drm, v4l2) in FOURCCS {
+            if let Some(drm) = drm {
+                assert_eq!(VideoFormat::from_drm_fourcc(*drm), Some(*format));
+                assert_eq!(format.drm_fourcc(), Some(*drm));
+            }
+            if let Some(v4l2) = v4l2 {
+                assert_eq!(VideoFormat::from_v4l2_fourcc(*v4l2), Some(*format));
+                assert_eq!(format.v4l2_fourcc(), Some(*v4l2));
+            }
+        }
+    }
+
+    #[test]
+    fn codes() {
+        // DRM_FORMAT_XRGB8888
+        assert_eq!(fourcc(b"XR24"), 0x34325258);
+        assert_eq!(
+            VideoFormat::from_drm_fourcc(0x34325258),
+            Some(VideoFormat::BGRx)
+        );
+        assert_eq!(
+            VideoFormat::from_v4l2_fourcc(fourcc(b"RGB3")),
+            Some(VideoFormat::RGB)
+        );
+        assert_eq!(VideoFormat::Encoded.drm_fourcc(), None);
+    }
+}
@@ -0,0 +1,263 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+use super::VideoFormat;
+
+/// The layout of a plane of a [`VideoFormat`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlaneInfo {
+    /// The size in bytes of a group of `x_subsampling` pixels of a row.
+    pub group_size: u32,
+    /// The number of horizontal pixels sharing a group, such as `2` for the chroma planes of I420.
+    pub x_subsampling: u32,
+    /// The number of rows sharing a row of the plane, such as `2` for the chroma planes of I420.
+    pub y_subsampling: u32,
+}
+
+impl PlaneInfo {
+    const fn new(group_size: u32, x_subsampling: u32, y_subsampling: u32) -> Self {
+        Self {
+            group_size,
+            x_subsampling,
+            y_subsampling,
+        }
+    }
+}
+
+/// A plane with one byte per pixel.
+const BYTE: PlaneInfo = PlaneInfo::new(1, 1, 1);
+
+/// The memory layout of a [`VideoFormat`], akin to the format info tables of SPA.
+///
+/// The sizes are those of tightly packed planes. Producers may pad the rows, in which case the
+/// stride of the chunk has to be used with [`plane_size_with_stride`](Self::plane_size_with_stride).
+///
+/// ```
+/// use libspa::param::video::{VideoFormat, VideoFormatInfo};
+///
+/// let info = VideoFormatInfo::new(VideoFormat::I420).unwrap();
+/// assert_eq!(info.n_planes(), 3);
+/// assert_eq!(info.plane_size(640, 480, 0), Some(640 * 480));
+/// assert_eq!(info.plane_size(640, 480, 1), Some(320 * 240));
+/// assert_eq!(info.frame_size(640, 480), Some(640 * 480 * 3 / 2));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoFormatInfo {
+    format: VideoFormat,
+    planes: &'static [PlaneInfo],
+}
+
+impl VideoFormatInfo {
+    /// The layout of `format`, or `None` for unknown, encoded and unsupported formats.
+    pub fn new(format: VideoFormat) -> Option<Self> {
+        let planes: &'static [PlaneInfo] = match format {
+            VideoFormat::GRAY8 => &[BYTE],
+            VideoFormat::GRAY16_BE
+            | VideoFormat::GRAY16_LE
+            | VideoFormat::RGB16
+            | VideoFormat::BGR16
+            | VideoFormat::RGB15
+            | VideoFormat::BGR15 => &[PlaneInfo::new(2, 1, 1)],
+            VideoFormat::RGB | VideoFormat::BGR | VideoFormat::v308 | VideoFormat::IYU2 => {
+                &[PlaneInfo::new(3, 1, 1)]
+            }
+            VideoFormat::RGBx
+            | VideoFormat::BGRx
+            | VideoFormat::xRGB
+            | VideoFormat::xBGR
+            | VideoFormat::RGBA
+            | VideoFormat::BGRA
+            | VideoFormat::ARGB
+            | VideoFormat::ABGR
+            | VideoFormat::AYUV
+            | VideoFormat::r210
+            | VideoFormat::xRGB_210LE
+            | VideoFormat::xBGR_210LE
+            | VideoFormat::RGBx_102LE
+            | VideoFormat::BGRx_102LE
+            | VideoFormat::ARGB_210LE
+            | VideoFormat::ABGR_210LE
+            | VideoFormat::RGBA_102LE
+            | VideoFormat::BGRA_102LE => &[PlaneInfo::new(4, 1, 1)],
+            VideoFormat::ARGB64 | VideoFormat::AYUV64 | VideoFormat::RGBA_F16 => {
+                &[PlaneInfo::new(8, 1, 1)]
+            }
+            VideoFormat::RGBA_F32 => &[PlaneInfo::new(16, 1, 1)],
+            // Two pixels share their chroma in four bytes.
+            VideoFormat::YUY2 | VideoFormat::UYVY | VideoFormat::YVYU | VideoFormat::VYUY => {
+                &[PlaneInfo::new(4, 2, 1)]
+            }
+            VideoFormat::I420 | VideoFormat::YV12 => {
+                &[BYTE, PlaneInfo::new(1, 2, 2), PlaneInfo::new(1, 2, 2)]
+            }
+            VideoFormat::A420 => &[BYTE, PlaneInfo::new(1, 2, 2), PlaneInfo::new(1, 2, 2), BYTE],
+            VideoFormat::Y41B => &[BYTE, PlaneInfo::new(1, 4, 1), PlaneInfo::new(1, 4, 1)],
+            VideoFormat::Y42B => &[BYTE, PlaneInfo::new(1, 2, 1), PlaneInfo::new(1, 2, 1)],
+            VideoFormat::Y444 | VideoFormat::GBR => &[BYTE, BYTE, BYTE],
+            VideoFormat::GBRA => &[BYTE, BYTE, BYTE, BYTE],
+            VideoFormat::YUV9 | VideoFormat::YVU9 => {
+                &[BYTE, PlaneInfo::new(1, 4, 4), PlaneInfo::new(1, 4, 4)]
+            }
+            VideoFormat::NV12 | VideoFormat::NV21 => &[BYTE, PlaneInfo::new(2, 2, 2)],
+            VideoFormat::NV16 | VideoFormat::NV61 => &[BYTE, PlaneInfo::new(2, 2, 1)],
+            VideoFormat::NV24 => &[BYTE, PlaneInfo::new(2, 1, 1)],
+            VideoFormat::P010_10BE | VideoFormat::P010_10LE => {
+                &[PlaneInfo::new(2, 1, 1), PlaneInfo::new(4, 2, 2)]
+            }
+            VideoFormat::I420_10BE
+            | VideoFormat::I420_10LE
+            | VideoFormat::I420_12BE
+            | VideoFormat::I420_12LE => &[
+                PlaneInfo::new(2, 1, 1),
+                PlaneInfo::new(2, 2, 2),
+                PlaneInfo::new(2, 2, 2),
+            ],
+            VideoFormat::A420_10BE | VideoFormat::A420_10LE => &[
+                PlaneInfo::new(2, 1, 1),
+                PlaneInfo::new(2, 2, 2),
+                PlaneInfo::new(2, 2, 2),
+                PlaneInfo::new(2, 1, 1),
+            ],
+            VideoFormat::I422_10BE
+            | VideoFormat::I422_10LE
+            | VideoFormat::I422_12BE
+            | VideoFormat::I422_12LE => &[
+                PlaneInfo::new(2, 1, 1),
+                PlaneInfo::new(2, 2, 1),
+                PlaneInfo::new(2, 2, 1),
+            ],
+            VideoFormat::A422_10BE | VideoFormat::A422_10LE => &[
+                PlaneInfo::new(2, 1, 1),
+                PlaneInfo::new(2, 2, 1),
+                PlaneInfo::new(2, 2, 1),
+                PlaneInfo::new(2, 1, 1),
+            ],
+            VideoFormat::Y444_10BE
+            | VideoFormat::Y444_10LE
+            | VideoFormat::Y444_12BE
+            | VideoFormat::Y444_12LE
+            | VideoFormat::GBR_10BE
+            | VideoFormat::GBR_10LE
+            | VideoFormat::GBR_12BE
+            | VideoFormat::GBR_12LE => &[PlaneInfo::new(2, 1, 1); 3],
+            VideoFormat::A444_10BE
+            | VideoFormat::A444_10LE
+            | VideoFormat::GBRA_10BE
+            | VideoFormat::GBRA_10LE
+            | VideoFormat::GBRA_12BE
+            | VideoFormat::GBRA_12LE => &[PlaneInfo::new(2, 1, 1); 4],
+            _ => return None,
+        };
+
+        Some(Self { format, planes })
+    }
+
+    pub fn format(&self) -> VideoFormat {
+        self.format
+    }
+
+    pub fn planes(&self) -> &'static [PlaneInfo] {
+        self.planes
+    }
+
+    pub fn n_planes(&self) -> usize {
+        self.planes.len()
+    }
+
+    /// The average number of bits used by a pixel, such as `12` for I420.
+    pub fn bits_per_pixel(&self) -> u32 {
+        // Sum in sixteenths of bits, as chroma planes can be subsampled by up to 4 in each direction.
+        let sixteenths: u32 = self
+            .planes
+            .iter()
+            .map(|plane| plane.group_size * 8 * 16 / (plane.x_subsampling * plane.y_subsampling))
+            .sum();
+        sixteenths / 16
+    }
+
+    /// The size in bytes of a tightly packed row of `plane` for a frame `width` pixels wide.
+    pub fn plane_stride(&self, width: u32, plane: usize) -> Option<u32> {
+        let info = self.planes.get(plane)?;
+        div_ceil(width, info.x_subsampling).checked_mul(info.group_size)
+    }
+
+    /// The number of rows of `plane` for a frame `height` pixels high.
+    pub fn plane_height(&self, height: u32, plane: usize) -> Option<u32> {
+        let info = self.planes.get(plane)?;
+        Some(div_ceil(height, info.y_subsampling))
+    }
+
+    /// The size in bytes of the tightly packed `plane` of a `width`x`height` frame.
+    pub fn plane_size(&self, width: u32, height: u32, plane: usize) -> Option<u32> {
+        let stride = self.plane_stride(width, plane)?;
+        self.plane_size_with_stride(stride, height, plane)
+    }
+
+    /// The size in bytes of `plane` of a frame `height` pixels high, with rows of `stride` bytes.
+    pub fn plane_size_with_stride(&self, stride: u32, height: u32, plane: usize) -> Option<u32> {
+        self.plane_height(height, plane)?.checked_mul(stride)
+    }
+
+    /// The offset of `plane` when all the tightly packed planes of a `width`x`height` frame are
+    /// stored one after the other, as in a single data buffer.
+    pub fn plane_offset(&self, width: u32, height: u32, plane: usize) -> Option<u32> {
+        if plane >= self.planes.len() {
+            return None;
+        }
+        (0..plane).try_fold(0u32, |offset, plane| {
+            offset.checked_add(self.plane_size(width, height, plane)?)
+        })
+    }
+
+    /// The size in bytes of all the tightly packed planes of a `width`x`height` frame.
+    pub fn frame_size(&self, width: u32, height: u32) -> Option<u32> {
+        (0..self.planes.len()).try_fold(0u32, |size, plane| {
+            size.checked_add(self.plane_size(width, height, plane)?)
+        })
+    }
+}
+
+impl VideoFormat {
+    /// The memory layout of the format, see [`VideoFormatInfo::new`].
+    pub fn info(&self) -> Option<VideoFormatInfo> {
+        VideoFormatInfo::new(*self)
+    }
+}
+
+fn div_ceil(n: u32, d: u32) -> u32 {
+    n / d + u32::from(n % d != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bits_per_pixel() {
+        let bpp = |format: VideoFormat| format.info().unwrap().bits_per_pixel();
+
+        assert_eq!(bpp(VideoFormat::I420), 12);
+        assert_eq!(bpp(VideoFormat::NV12), 12);
+        assert_eq!(bpp(VideoFormat::YUY2), 16);
+        assert_eq!(bpp(VideoFormat::YUV9), 9);
+        assert_eq!(bpp(VideoFormat::BGRx), 32);
+        assert_eq!(bpp(VideoFormat::P010_10LE), 24);
+        assert!(VideoFormat::Encoded.info().is_none());
+    }
+
+    #[test]
+    fn plane_sizes() {
+        let nv12 = VideoFormat::NV12.info().unwrap();
+        assert_eq!(nv12.n_planes(), 2);
+        // Odd sizes are rounded up for the subsampled plane.
+        assert_eq!(nv12.plane_stride(5, 1), Some(6));
+        assert_eq!(nv12.plane_size(5, 3, 1), Some(12));
+        assert_eq!(nv12.plane_offset(5, 3, 1), Some(15));
+        assert_eq!(nv12.frame_size(5, 3), Some(27));
+        assert_eq!(nv12.plane_size(5, 3, 2), None);
+
+        let yuy2 = VideoFormat::YUY2.info().unwrap();
+        assert_eq!(yuy2.plane_stride(3, 0), Some(8));
+        assert_eq!(yuy2.plane_size_with_stride(64, 2, 0), Some(128));
+    }
+}
@@ -1,5 +1,11 @@
 // Copyright The pipewire-rs Contributors.
 // SPDX-License-Identifier: MIT
 
+mod bayer;
+mod fourcc;
+mod info;
 mod raw;
+pub use bayer::*;
+pub use fourcc::*;
+pub use info::*;
 pub use raw::*;
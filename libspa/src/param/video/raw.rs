@@ -152,6 +152,8 @@ impl Debug for VideoFormat {
     }
 }
 
+impl_short_name!(VideoFormat, spa_sys::spa_type_video_format);
+
 bitflags::bitflags! {
     #[derive(Debug, PartialEq, Eq, Clone, Copy)]
     pub struct VideoFlags: u32 {
@@ -379,7 +381,7 @@ impl VideoInfoRaw {
 
     /// helper function to parse format properties type
     pub fn parse(&mut self, format: &crate::pod::Pod) -> Result<SpaSuccess, Error> {
-        let res = unsafe { spa_sys::spa_format_video_raw_parse(format.as_raw_ptr(), &mut self.0) };
+        let res = unsafe { spa_sys::spa_format_video_raw_parse(format.as_ptr(), &mut self.0) };
         SpaResult::from_c(res).into_result()
     }
 
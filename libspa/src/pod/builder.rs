@@ -1,11 +1,14 @@
 use std::{
-    ffi::{c_int, c_void, CString},
+    ffi::{c_int, c_void},
     mem::MaybeUninit,
 };
 
 use nix::errno::Errno;
 
-use crate::utils::{Fraction, Id, Rectangle};
+use crate::{
+    pod::{io::PodBuf, Pod},
+    utils::{Fraction, Id, Rectangle},
+};
 
 static CALLBACKS: spa_sys::spa_pod_builder_callbacks = spa_sys::spa_pod_builder_callbacks {
     version: spa_sys::SPA_VERSION_POD_BUILDER_CALLBACKS,
@@ -73,6 +76,39 @@ impl<'d> Builder<'d> {
         }
     }
 
+    /// Build a single pod of any size with the builder passed to `f`.
+    ///
+    /// The buffer of the builder grows as needed, and the built pod is returned once `f` succeeds.
+    /// Returns [`Errno::EINVAL`] if `f` did not add any pod.
+    ///
+    /// ```
+    /// use libspa::pod::builder::{builder_add, Builder};
+    ///
+    /// let strings = vec!["a"; 1000];
+    /// let pod = Builder::build(|b| unsafe {
+    ///     let mut frame = std::mem::MaybeUninit::uninit();
+    ///     b.push_struct(&mut frame)?;
+    ///     for s in &strings {
+    ///         b.add_string(s)?;
+    ///     }
+    ///     b.pop(frame.assume_init_mut());
+    ///     Ok(())
+    /// })?;
+    /// assert!(pod.is_struct());
+    ///
+    /// let pod = Builder::build(|b| builder_add!(b, Struct { Int(1), String("a") }))?;
+    /// assert!(pod.is_struct());
+    /// # Ok::<(), nix::errno::Errno>(())
+    /// ```
+    pub fn build<F>(f: F) -> Result<PodBuf, Errno>
+    where
+        F: FnOnce(&mut Builder) -> Result<(), Errno>,
+    {
+        let mut data = Vec::new();
+        f(&mut Builder::new(&mut data))?;
+        PodBuf::from_bytes(&data).ok_or(Errno::EINVAL)
+    }
+
     pub fn as_raw(&self) -> &spa_sys::spa_pod_builder {
         &self.inner.builder
     }
@@ -154,7 +190,20 @@ impl<'d> Builder<'d> {
         }
     }
 
-    // TODO: primitive
+    /// Add an existing pod, such as a format built separately, as is.
+    ///
+    /// Inside an array or a choice, only its body is added, as the children share their header.
+    pub fn add_pod(&mut self, pod: &Pod) -> Result<(), Errno> {
+        unsafe {
+            let res = spa_sys::spa_pod_builder_primitive(self.as_raw_ptr(), pod.as_ptr());
+
+            if res >= 0 {
+                Ok(())
+            } else {
+                Err(Errno::from_i32(-res))
+            }
+        }
+    }
 
     pub fn add_none(&mut self) -> Result<(), Errno> {
         unsafe {
@@ -242,16 +291,34 @@ impl<'d> Builder<'d> {
         }
     }
 
-    // TODO: write_string, string_len,
-    // TODO: add_string_raw variant?
+    // TODO: write_string
 
     /// # Panics
     ///
-    /// If `string` contains an interior null byte
+    /// If `string` contains an interior null byte, use [`add_string_bytes`](Self::add_string_bytes)
+    /// or [`add_string_lossy`](Self::add_string_lossy) for untrusted strings.
     pub fn add_string(&mut self, string: &str) -> Result<(), Errno> {
-        let c_str = CString::new(string).expect("string should not contain an interior null byte");
+        assert!(
+            !string.contains('\0'),
+            "string should not contain an interior null byte"
+        );
+
+        self.add_string_bytes(string.as_bytes())
+    }
 
-        let res = unsafe { spa_sys::spa_pod_builder_string(self.as_raw_ptr(), c_str.as_ptr()) };
+    /// Add a string pod holding `bytes`, which do not have to be UTF-8.
+    ///
+    /// The null terminator is added by the builder. Fails with `EINVAL` if `bytes` contains a null
+    /// byte.
+    pub fn add_string_bytes(&mut self, bytes: &[u8]) -> Result<(), Errno> {
+        if bytes.contains(&0) {
+            return Err(Errno::EINVAL);
+        }
+        let len = bytes.len().try_into().map_err(|_| Errno::EINVAL)?;
+
+        let res = unsafe {
+            spa_sys::spa_pod_builder_string_len(self.as_raw_ptr(), bytes.as_ptr().cast(), len)
+        };
 
         if res >= 0 {
             Ok(())
@@ -260,6 +327,14 @@ impl<'d> Builder<'d> {
         }
     }
 
+    /// Add a string pod holding `string` up to its first null byte, if any, which is what C
+    /// code reading the pod would see anyway.
+    pub fn add_string_lossy(&mut self, string: &str) -> Result<(), Errno> {
+        let bytes = string.as_bytes();
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        self.add_string_bytes(&bytes[..end])
+    }
+
     // TODO: raw bytes variant?
 
     pub fn add_bytes(&mut self, bytes: &[u8]) -> Result<(), Errno> {
@@ -489,7 +564,7 @@ impl<'d> Builder<'d> {
 ///
 /// ```ignore
 /// builder_add!(<&mut libspa::pod::builder::Builder>, Bool(<bool>));
-/// builder_add!(<&mut libspa::pod::builder::Builder>, Id(<ibspa::utils::Id>));
+/// builder_add!(<&mut libspa::pod::builder::Builder>, Id(<impl libspa::utils::IdEnum>));
 /// builder_add!(<&mut libspa::pod::builder::Builder>, Int(<i32>));
 /// builder_add!(<&mut libspa::pod::builder::Builder>, Long(<i64>));
 /// builder_add!(<&mut libspa::pod::builder::Builder>, Float(<f32>));
@@ -501,6 +576,8 @@ impl<'d> Builder<'d> {
 /// builder_add!(<&mut libspa::pod::builder::Builder>, Fd(<i64>));
 /// builder_add!(<&mut libspa::pod::builder::Builder>, Rectangle(<libspa::utils::Rectangle>));
 /// builder_add!(<&mut libspa::pod::builder::Builder>, Fraction(<libspa::utils::Fraction>));
+/// // Adds an existing pod as is.
+/// builder_add!(<&mut libspa::pod::builder::Builder>, Pod(<&libspa::pod::Pod>));
 /// builder_add!(<&mut libspa::pod::builder::Builder>,
 ///     Struct {
 ///         // 0 to n fields, e.g.:
@@ -523,6 +600,8 @@ impl<'d> Builder<'d> {
 ///         313 => String("313"),
 ///     }
 /// );
+/// // Adds a `None` pod if the value is `None`.
+/// builder_add!(<&mut libspa::pod::builder::Builder>, Option(Int(<Option<i32>>)));
 /// ```
 ///
 /// # Returns
@@ -539,7 +618,7 @@ macro_rules! __builder_add__ {
         $crate::pod::builder::Builder::add_bool($builder, $val)
     };
     ($builder:expr, Id($val:expr)) => {
-        $crate::pod::builder::Builder::add_id($builder, $val)
+        $crate::pod::builder::Builder::add_id($builder, $crate::utils::IdEnum::to_id(&$val))
     };
     ($builder:expr, Int($val:expr)) => {
         $crate::pod::builder::Builder::add_int($builder, $val)
@@ -571,6 +650,15 @@ macro_rules! __builder_add__ {
     ($builder:expr, Fraction($val:expr)) => {
         $crate::pod::builder::Builder::add_fraction($builder, $val)
     };
+    ($builder:expr, Pod($val:expr)) => {
+        $crate::pod::builder::Builder::add_pod($builder, $val)
+    };
+    ($builder:expr, Option($type_:ident($val:expr))) => {
+        match $val {
+            Some(val) => $crate::__builder_add__!($builder, $type_(val)),
+            None => $crate::pod::builder::Builder::add_none($builder),
+        }
+    };
     // TODO: Choice
     (
         $builder:expr,
@@ -657,6 +745,32 @@ mod tests {
         assert_eq!(&data, &other)
     }
 
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn build_owned() {
+        let pod = Builder::build(|b| builder_add!(b, Struct { Int(1) })).unwrap();
+        assert!(pod.is_struct());
+        assert_eq!(pod.size(), 16);
+
+        assert_eq!(Builder::build(|_| Ok(())).unwrap_err(), Errno::EINVAL);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn build_strings() {
+        let pod = Builder::build(|b| b.add_string_bytes(b"caf\xe9")).unwrap();
+        assert_eq!(pod.get_str(), Err(Errno::EINVAL));
+        assert_eq!(pod.get_str_lossy().unwrap(), "caf\u{fffd}");
+
+        let pod = Builder::build(|b| b.add_string_lossy("name\0suffix")).unwrap();
+        assert_eq!(pod.get_str(), Ok("name"));
+
+        let mut data = Vec::new();
+        let mut builder = Builder::new(&mut data);
+        assert_eq!(builder.add_string_bytes(b"a\0b"), Err(Errno::EINVAL));
+        assert!(data.is_empty());
+    }
+
     #[test]
     #[cfg_attr(miri, ignore)]
     fn build_small_struct() {
@@ -750,4 +864,67 @@ mod tests {
 
         assert!(res.is_ok());
     }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn build_optional() {
+        let mut data = Vec::new();
+
+        let mut builder = Builder::new(&mut data);
+        let res = builder_add!(
+            &mut builder,
+            Struct {
+                Option(Int(Some(3))),
+                Option(Int(None)),
+            }
+        );
+
+        assert!(res.is_ok());
+
+        let other: Vec<u8> = [
+            24u32.to_ne_bytes(), // body has size 24
+            14u32.to_ne_bytes(), // struct type is 14
+            4u32.to_ne_bytes(),  // child body size is 4
+            4u32.to_ne_bytes(),  // Int child type is 4
+            3i32.to_ne_bytes(),  // the integer
+            [0, 0, 0, 0],        // padding
+            0u32.to_ne_bytes(),  // child body size is 0
+            1u32.to_ne_bytes(),  // None child type is 1
+        ]
+        .iter()
+        .copied()
+        .flatten()
+        .collect();
+
+        assert_eq!(&data, &other)
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn build_embedded_pod() {
+        let format = Builder::build(|b| {
+            builder_add!(
+                b,
+                Object(spa_sys::SPA_TYPE_OBJECT_Format, spa_sys::SPA_PARAM_EnumFormat) {
+                    spa_sys::SPA_FORMAT_mediaType => Id(Id(spa_sys::SPA_MEDIA_TYPE_audio)),
+                }
+            )
+        })
+        .unwrap();
+
+        let pod = Builder::build(|b| {
+            builder_add!(
+                b,
+                Struct {
+                    Int(1),
+                    Pod(&format),
+                }
+            )
+        })
+        .unwrap();
+
+        let fields: Vec<&Pod> = pod.struct_fields().unwrap().collect();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[1].as_bytes(), format.as_bytes());
+    }
 }
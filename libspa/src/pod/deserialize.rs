@@ -20,6 +20,7 @@ use nom::{
 };
 
 use super::{
+    value_ref::{fixed_pod_size, ArrayRef, ChoiceRef, ObjectRef, StructRef, ValueRef},
     CanonicalFixedSizedPod, ChoiceValue, FixedSizedPod, Object, PropertyFlags, Value, ValueArray,
 };
 use crate::{
@@ -220,9 +221,36 @@ impl<'de, P: FixedSizedPod + CanonicalFixedSizedPod + std::marker::Copy> PodDese
 /// to finish deserialization of their pod instead of stopping after deserializing only part of a pod.
 pub struct DeserializeSuccess<'de>(PodDeserializer<'de>);
 
+/// How an [`ObjectPodDeserializer`] handles properties with keys that the [`PodDeserialize`]
+/// implementor does not expect, such as properties added by a newer version of PipeWire.
+///
+/// The param types of [`crate::param`] are parsed by SPA itself, which always ignores them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownProperties {
+    /// Fail with [`DeserializeError::PropertyWrongKey`] when a property does not have the expected key.
+    #[default]
+    Deny,
+    /// Skip the properties with unexpected keys.
+    Skip,
+    /// Skip the properties with unexpected keys, and return them once the deserialization is done.
+    Collect,
+}
+
+/// A property skipped by an [`ObjectPodDeserializer`], see [`UnknownProperties::Collect`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownProperty<'de> {
+    pub key: u32,
+    pub flags: PropertyFlags,
+    /// The raw value pod, which can be deserialized with [`PodDeserializer::deserialize_any_from`].
+    pub value: &'de [u8],
+}
+
 /// This struct is responsible for deserializing a raw pod into a [`PodDeserialize`] implementor.
 pub struct PodDeserializer<'de> {
     input: &'de [u8],
+    unknown_properties: UnknownProperties,
+    /// The properties skipped with [`UnknownProperties::Collect`].
+    skipped: Vec<UnknownProperty<'de>>,
 }
 
 impl<'de> PodDeserializer<'de> {
@@ -238,10 +266,38 @@ impl<'de> PodDeserializer<'de> {
     pub fn deserialize_from<P: PodDeserialize<'de>>(
         input: &'de [u8],
     ) -> Result<(&'de [u8], P), DeserializeError<&'de [u8]>> {
-        let deserializer = Self { input };
+        let deserializer = Self::new(input);
         P::deserialize(deserializer).map(|(res, success)| (success.0.input, res))
     }
 
+    /// Variant of [`Self::deserialize_from`] handling the properties of objects with unexpected keys
+    /// according to `unknown_properties`, so that pods from newer versions of PipeWire can be
+    /// deserialized.
+    ///
+    /// This only affects objects deserialized with
+    /// [`ObjectPodDeserializer::deserialize_property_key`] and finished with
+    /// [`ObjectPodDeserializer::end`]. The properties skipped with [`UnknownProperties::Collect`]
+    /// are returned along with the remaining input and the type.
+    #[allow(clippy::type_complexity)]
+    pub fn deserialize_lenient_from<P: PodDeserialize<'de>>(
+        input: &'de [u8],
+        unknown_properties: UnknownProperties,
+    ) -> Result<(&'de [u8], P, Vec<UnknownProperty<'de>>), DeserializeError<&'de [u8]>> {
+        let deserializer = Self {
+            unknown_properties,
+            ..Self::new(input)
+        };
+        P::deserialize(deserializer).map(|(res, success)| (success.0.input, res, success.0.skipped))
+    }
+
+    fn new(input: &'de [u8]) -> Self {
+        Self {
+            input,
+            unknown_properties: UnknownProperties::Deny,
+            skipped: Vec::new(),
+        }
+    }
+
     /// Deserialize a `spa_sys::spa_pod` pointer.
     ///
     /// # Safety
@@ -482,6 +538,7 @@ impl<'de> PodDeserializer<'de> {
             remaining: len - 8,
             object_type,
             object_id,
+            property_flags: None,
         })
     }
 
@@ -632,83 +689,6 @@ impl<'de> PodDeserializer<'de> {
             self.parse(pair(u32(Endianness::Native), u32(Endianness::Native)))?;
         let num_values = (len - 16) / child_size;
 
-        fn create_choice<'de, E>(
-            choice_type: u32,
-            values: Vec<E>,
-            flags: u32,
-        ) -> Result<Choice<E>, DeserializeError<&'de [u8]>>
-        where
-            E: CanonicalFixedSizedPod + FixedSizedPod + Copy,
-        {
-            let flags = ChoiceFlags::from_bits_retain(flags);
-
-            match choice_type {
-                spa_sys::SPA_CHOICE_None => {
-                    if values.is_empty() {
-                        Err(DeserializeError::MissingChoiceValues)
-                    } else {
-                        Ok(Choice(ChoiceFlags::empty(), ChoiceEnum::None(values[0])))
-                    }
-                }
-                spa_sys::SPA_CHOICE_Range => {
-                    if values.len() < 3 {
-                        Err(DeserializeError::MissingChoiceValues)
-                    } else {
-                        Ok(Choice(
-                            flags,
-                            ChoiceEnum::Range {
-                                default: values[0],
-                                min: values[1],
-                                max: values[2],
-                            },
-                        ))
-                    }
-                }
-                spa_sys::SPA_CHOICE_Step => {
-                    if values.len() < 4 {
-                        Err(DeserializeError::MissingChoiceValues)
-                    } else {
-                        Ok(Choice(
-                            flags,
-                            ChoiceEnum::Step {
-                                default: values[0],
-                                min: values[1],
-                                max: values[2],
-                                step: values[3],
-                            },
-                        ))
-                    }
-                }
-                spa_sys::SPA_CHOICE_Enum => {
-                    if values.is_empty() {
-                        Err(DeserializeError::MissingChoiceValues)
-                    } else {
-                        Ok(Choice(
-                            flags,
-                            ChoiceEnum::Enum {
-                                default: values[0],
-                                alternatives: values[1..].to_vec(),
-                            },
-                        ))
-                    }
-                }
-                spa_sys::SPA_CHOICE_Flags => {
-                    if values.is_empty() {
-                        Err(DeserializeError::MissingChoiceValues)
-                    } else {
-                        Ok(Choice(
-                            flags,
-                            ChoiceEnum::Flags {
-                                default: values[0],
-                                flags: values[1..].to_vec(),
-                            },
-                        ))
-                    }
-                }
-                _ => Err(DeserializeError::InvalidChoiceType),
-            }
-        }
-
         match child_type {
             spa_sys::SPA_TYPE_Bool => {
                 let (values, success) = self.deserialize_choice_values::<bool>(num_values)?;
@@ -811,10 +791,27 @@ impl<'de> PodDeserializer<'de> {
             spa_sys::SPA_TYPE_Object => self.deserialize_object(ValueVisitor),
             spa_sys::SPA_TYPE_Choice => self.deserialize_choice(ValueVisitor),
             spa_sys::SPA_TYPE_Pointer => self.deserialize_pointer(ValueVisitor),
-            _ => Err(DeserializeError::InvalidType),
+            _ => self.deserialize_other(),
         }
     }
 
+    /// Deserialize a pod of an unknown type into [`Value::Other`], keeping its raw body.
+    fn deserialize_other(
+        mut self,
+    ) -> Result<(Value, DeserializeSuccess<'de>), DeserializeError<&'de [u8]>> {
+        let (len, type_) = self.parse(pair(u32(Endianness::Native), u32(Endianness::Native)))?;
+        let padding = Self::calc_padding_needed(len);
+        let bytes = self.parse(terminated(take(len), take(padding)))?;
+
+        Ok((
+            Value::Other {
+                type_,
+                bytes: bytes.to_vec(),
+            },
+            DeserializeSuccess(self),
+        ))
+    }
+
     fn deserialize_array_any(
         self,
     ) -> Result<(Value, DeserializeSuccess<'de>), DeserializeError<&'de [u8]>> {
@@ -883,6 +880,201 @@ impl<'de> PodDeserializer<'de> {
     ) -> Result<(&'de [u8], Value), DeserializeError<&'de [u8]>> {
         Self::deserialize_from(input)
     }
+
+    /// Deserialize any kind of pod into a [`ValueRef`] borrowing the input, without allocating.
+    ///
+    /// The whole pod is validated, so that the children of the containers it holds can then be
+    /// iterated lazily.
+    pub fn deserialize_any_ref(
+        self,
+    ) -> Result<(ValueRef<'de>, DeserializeSuccess<'de>), DeserializeError<&'de [u8]>> {
+        let type_ = self.peek(Self::type_())?;
+
+        match type_ {
+            spa_sys::SPA_TYPE_None => self.deserialize_none(ValueRefVisitor),
+            spa_sys::SPA_TYPE_Bool => self.deserialize_bool(ValueRefVisitor),
+            spa_sys::SPA_TYPE_Id => self.deserialize_id(ValueRefVisitor),
+            spa_sys::SPA_TYPE_Int => self.deserialize_int(ValueRefVisitor),
+            spa_sys::SPA_TYPE_Long => self.deserialize_long(ValueRefVisitor),
+            spa_sys::SPA_TYPE_Float => self.deserialize_float(ValueRefVisitor),
+            spa_sys::SPA_TYPE_Double => self.deserialize_double(ValueRefVisitor),
+            spa_sys::SPA_TYPE_String => self.deserialize_str(ValueRefVisitor),
+            spa_sys::SPA_TYPE_Bytes => self.deserialize_bytes(ValueRefVisitor),
+            spa_sys::SPA_TYPE_Rectangle => self.deserialize_rectangle(ValueRefVisitor),
+            spa_sys::SPA_TYPE_Fraction => self.deserialize_fraction(ValueRefVisitor),
+            spa_sys::SPA_TYPE_Fd => self.deserialize_fd(ValueRefVisitor),
+            spa_sys::SPA_TYPE_Pointer => self.deserialize_pointer(ValueRefVisitor),
+            _ => self.deserialize_body_ref(),
+        }
+    }
+
+    /// Deserialize a container pod, or a pod of an unknown type, keeping its body.
+    fn deserialize_body_ref(
+        mut self,
+    ) -> Result<(ValueRef<'de>, DeserializeSuccess<'de>), DeserializeError<&'de [u8]>> {
+        let (len, type_) = self.parse(pair(u32(Endianness::Native), u32(Endianness::Native)))?;
+        let padding = Self::calc_padding_needed(len);
+        let body = self.parse(terminated(take(len), take(padding)))?;
+
+        fn pair_u32(input: &[u8]) -> IResult<&[u8], (u32, u32)> {
+            pair(u32(Endianness::Native), u32(Endianness::Native))(input)
+        }
+
+        let value = match type_ {
+            spa_sys::SPA_TYPE_Struct => {
+                let mut fields = body;
+                while !fields.is_empty() {
+                    fields = Self::deserialize_any_ref_from(fields)?.0;
+                }
+                ValueRef::Struct(StructRef { fields: body })
+            }
+            spa_sys::SPA_TYPE_Object => {
+                let (properties, (type_, id)) = pair_u32(body)?;
+                let mut rest = properties;
+                while !rest.is_empty() {
+                    // Skip the key and flags of the property.
+                    let value = pair_u32(rest)?.0;
+                    rest = Self::deserialize_any_ref_from(value)?.0;
+                }
+                ValueRef::Object(ObjectRef {
+                    type_,
+                    id,
+                    properties,
+                })
+            }
+            spa_sys::SPA_TYPE_Array => {
+                let (elements, (child_size, child_type)) = pair_u32(body)?;
+                if fixed_pod_size(child_type) != Some(child_size) {
+                    return Err(DeserializeError::InvalidType);
+                }
+                ValueRef::ValueArray(ArrayRef {
+                    child_type,
+                    elements,
+                })
+            }
+            spa_sys::SPA_TYPE_Choice => {
+                let (rest, (choice_type, flags)) = pair_u32(body)?;
+                let (values, (child_size, child_type)) = pair_u32(rest)?;
+                if child_type == spa_sys::SPA_TYPE_None
+                    || fixed_pod_size(child_type) != Some(child_size)
+                {
+                    return Err(DeserializeError::InvalidType);
+                }
+                let min_values =
+                    min_choice_values(choice_type).ok_or(DeserializeError::InvalidChoiceType)?;
+                if values.len() / (child_size as usize) < min_values {
+                    return Err(DeserializeError::MissingChoiceValues);
+                }
+                ValueRef::Choice(ChoiceRef {
+                    choice_type,
+                    flags: ChoiceFlags::from_bits_retain(flags),
+                    child_type,
+                    values,
+                })
+            }
+            _ => ValueRef::Other { type_, bytes: body },
+        };
+
+        Ok((value, DeserializeSuccess(self)))
+    }
+
+    /// Variant of [`Self::deserialize_any_from`] returning the parsed value as a [`ValueRef`]
+    /// borrowing the input.
+    pub fn deserialize_any_ref_from(
+        input: &'de [u8],
+    ) -> Result<(&'de [u8], ValueRef<'de>), DeserializeError<&'de [u8]>> {
+        Self::new(input)
+            .deserialize_any_ref()
+            .map(|(value, success)| (success.0.input, value))
+    }
+}
+
+/// Build a [`Choice`] of type `choice_type` from its serialized values.
+pub(super) fn create_choice<'de, E>(
+    choice_type: u32,
+    values: Vec<E>,
+    flags: u32,
+) -> Result<Choice<E>, DeserializeError<&'de [u8]>>
+where
+    E: CanonicalFixedSizedPod + FixedSizedPod + Copy,
+{
+    let flags = ChoiceFlags::from_bits_retain(flags);
+
+    match choice_type {
+        spa_sys::SPA_CHOICE_None => {
+            if values.is_empty() {
+                Err(DeserializeError::MissingChoiceValues)
+            } else {
+                Ok(Choice(flags, ChoiceEnum::None(values[0])))
+            }
+        }
+        spa_sys::SPA_CHOICE_Range => {
+            if values.len() < 3 {
+                Err(DeserializeError::MissingChoiceValues)
+            } else {
+                Ok(Choice(
+                    flags,
+                    ChoiceEnum::Range {
+                        default: values[0],
+                        min: values[1],
+                        max: values[2],
+                    },
+                ))
+            }
+        }
+        spa_sys::SPA_CHOICE_Step => {
+            if values.len() < 4 {
+                Err(DeserializeError::MissingChoiceValues)
+            } else {
+                Ok(Choice(
+                    flags,
+                    ChoiceEnum::Step {
+                        default: values[0],
+                        min: values[1],
+                        max: values[2],
+                        step: values[3],
+                    },
+                ))
+            }
+        }
+        spa_sys::SPA_CHOICE_Enum => {
+            if values.is_empty() {
+                Err(DeserializeError::MissingChoiceValues)
+            } else {
+                Ok(Choice(
+                    flags,
+                    ChoiceEnum::Enum {
+                        default: values[0],
+                        alternatives: values[1..].to_vec(),
+                    },
+                ))
+            }
+        }
+        spa_sys::SPA_CHOICE_Flags => {
+            if values.is_empty() {
+                Err(DeserializeError::MissingChoiceValues)
+            } else {
+                Ok(Choice(
+                    flags,
+                    ChoiceEnum::Flags {
+                        default: values[0],
+                        flags: values[1..].to_vec(),
+                    },
+                ))
+            }
+        }
+        _ => Err(DeserializeError::InvalidChoiceType),
+    }
+}
+
+/// The number of values a choice of type `choice_type` needs, or `None` if the type is unknown.
+fn min_choice_values(choice_type: u32) -> Option<usize> {
+    match choice_type {
+        spa_sys::SPA_CHOICE_None | spa_sys::SPA_CHOICE_Enum | spa_sys::SPA_CHOICE_Flags => Some(1),
+        spa_sys::SPA_CHOICE_Range => Some(3),
+        spa_sys::SPA_CHOICE_Step => Some(4),
+        _ => None,
+    }
 }
 
 /// This struct handles deserializing arrays.
@@ -1032,9 +1224,28 @@ pub struct ObjectPodDeserializer<'de> {
     object_type: u32,
     /// id of the object
     object_id: u32,
+    /// flags of the last deserialized property
+    property_flags: Option<PropertyFlags>,
 }
 
 impl<'de> ObjectPodDeserializer<'de> {
+    /// The type of the object being deserialized.
+    pub fn object_type(&self) -> u32 {
+        self.object_type
+    }
+
+    /// The id of the object being deserialized.
+    pub fn object_id(&self) -> u32 {
+        self.object_id
+    }
+
+    /// The flags of the property that was deserialized last.
+    ///
+    /// Returns `None` if no property has been deserialized yet.
+    pub fn property_flags(&self) -> Option<PropertyFlags> {
+        self.property_flags
+    }
+
     /// Deserialize a single property of the object.
     ///
     /// Returns `Some` when a property was successfully deserialized and `None` when all properties have been read.
@@ -1057,6 +1268,7 @@ impl<'de> ObjectPodDeserializer<'de> {
             let flags = deserializer.parse(u32(Endianness::Native))?;
 
             let flags = PropertyFlags::from_bits_retain(flags);
+            self.property_flags = Some(flags);
             let (res, success) = P::deserialize(deserializer)?;
 
             // The amount of bytes deserialized is the length of the remaining input
@@ -1069,15 +1281,94 @@ impl<'de> ObjectPodDeserializer<'de> {
         }
     }
 
+    /// Variant of [`Self::deserialize_property`] converting the key of the property into `K`,
+    /// e.g. a type representing the keys of the specific object type being deserialized.
+    #[allow(clippy::type_complexity)]
+    pub fn deserialize_typed_property<P: PodDeserialize<'de>, K: From<u32>>(
+        &mut self,
+    ) -> Result<Option<(P, K, PropertyFlags)>, DeserializeError<&'de [u8]>> {
+        Ok(self
+            .deserialize_property()?
+            .map(|(prop, key, flags)| (prop, K::from(key), flags)))
+    }
+
+    /// Skip the next property of the object without deserializing it.
+    ///
+    /// Returns the key of the skipped property, or `None` when all properties have been read.
+    /// The property is collected with [`UnknownProperties::Collect`].
+    pub fn skip_property(&mut self) -> Result<Option<u32>, DeserializeError<&'de [u8]>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        let deserializer = self
+            .deserializer
+            .as_mut()
+            .expect("ObjectPodDeserializer does not contain a deserializer");
+
+        let key = deserializer.parse(u32(Endianness::Native))?;
+        let flags = PropertyFlags::from_bits_retain(deserializer.parse(u32(Endianness::Native))?);
+        let size = deserializer.peek(u32(Endianness::Native))?;
+        // The value pod, with its header and padding.
+        let len = size
+            .checked_add(8 + PodDeserializer::calc_padding_needed(size))
+            .ok_or(DeserializeError::InvalidType)?;
+        let value = deserializer.parse(take(len))?;
+
+        self.remaining = self
+            .remaining
+            .checked_sub(8)
+            .and_then(|remaining| remaining.checked_sub(len))
+            .ok_or(DeserializeError::InvalidType)?;
+        if deserializer.unknown_properties == UnknownProperties::Collect {
+            deserializer
+                .skipped
+                .push(UnknownProperty { key, flags, value });
+        }
+
+        Ok(Some(key))
+    }
+
+    /// The key of the next property, or `None` when all properties have been read.
+    fn peek_key(&self) -> Result<Option<u32>, DeserializeError<&'de [u8]>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        let deserializer = self
+            .deserializer
+            .as_ref()
+            .expect("ObjectPodDeserializer does not contain a deserializer");
+        Ok(Some(deserializer.peek(u32(Endianness::Native))?))
+    }
+
+    fn is_lenient(&self) -> bool {
+        self.deserializer.as_ref().map_or(false, |deserializer| {
+            deserializer.unknown_properties != UnknownProperties::Deny
+        })
+    }
+
     /// Variant of [`Self::deserialize_property`] ensuring the property has a given key.
     ///
     /// Returns [`DeserializeError::PropertyMissing`] if the property is missing
     /// and [`DeserializeError::PropertyWrongKey`] if the property does not have the
     /// expected key.
+    ///
+    /// When deserializing with [`PodDeserializer::deserialize_lenient_from`], the properties
+    /// before the one with the expected key are skipped instead.
     pub fn deserialize_property_key<P: PodDeserialize<'de>>(
         &mut self,
         key: u32,
     ) -> Result<(P, PropertyFlags), DeserializeError<&'de [u8]>> {
+        if self.is_lenient() {
+            while let Some(k) = self.peek_key()? {
+                if k == key {
+                    break;
+                }
+                self.skip_property()?;
+            }
+        }
+
         let (prop, k, flags) = self
             .deserialize_property()?
             .ok_or(DeserializeError::PropertyMissing)?;
@@ -1091,9 +1382,16 @@ impl<'de> ObjectPodDeserializer<'de> {
 
     /// Finish deserialization of the pod.
     ///
+    /// When deserializing with [`PodDeserializer::deserialize_lenient_from`], the remaining
+    /// properties are skipped.
+    ///
     /// # Panics
-    /// Panics if not all properties of the pod have been deserialized.
-    pub fn end(self) -> Result<DeserializeSuccess<'de>, DeserializeError<&'de [u8]>> {
+    /// Panics if not all properties of the pod have been deserialized, unless they are skipped.
+    pub fn end(mut self) -> Result<DeserializeSuccess<'de>, DeserializeError<&'de [u8]>> {
+        if self.is_lenient() {
+            while self.skip_property()?.is_some() {}
+        }
+
         assert!(
             self.remaining == 0,
             "Not all properties have been deserialized from the object"
@@ -1472,6 +1770,70 @@ impl<'de, E: CanonicalFixedSizedPod + std::marker::Copy> Visitor<'de> for VecVis
     }
 }
 
+/// A visitor producing the scalar [`ValueRef`]s, borrowing strings and bytes.
+struct ValueRefVisitor;
+
+impl<'de> Visitor<'de> for ValueRefVisitor {
+    type Value = ValueRef<'de>;
+    type ArrayElem = Infallible;
+
+    fn visit_none(&self) -> Result<Self::Value, DeserializeError<&'de [u8]>> {
+        Ok(ValueRef::None)
+    }
+
+    fn visit_bool(&self, v: bool) -> Result<Self::Value, DeserializeError<&'de [u8]>> {
+        Ok(ValueRef::Bool(v))
+    }
+
+    fn visit_int(&self, v: i32) -> Result<Self::Value, DeserializeError<&'de [u8]>> {
+        Ok(ValueRef::Int(v))
+    }
+
+    fn visit_long(&self, v: i64) -> Result<Self::Value, DeserializeError<&'de [u8]>> {
+        Ok(ValueRef::Long(v))
+    }
+
+    fn visit_float(&self, v: f32) -> Result<Self::Value, DeserializeError<&'de [u8]>> {
+        Ok(ValueRef::Float(v))
+    }
+
+    fn visit_double(&self, v: f64) -> Result<Self::Value, DeserializeError<&'de [u8]>> {
+        Ok(ValueRef::Double(v))
+    }
+
+    fn visit_string(&self, v: &'de str) -> Result<Self::Value, DeserializeError<&'de [u8]>> {
+        Ok(ValueRef::String(v))
+    }
+
+    fn visit_bytes(&self, v: &'de [u8]) -> Result<Self::Value, DeserializeError<&'de [u8]>> {
+        Ok(ValueRef::Bytes(v))
+    }
+
+    fn visit_rectangle(&self, v: Rectangle) -> Result<Self::Value, DeserializeError<&'de [u8]>> {
+        Ok(ValueRef::Rectangle(v))
+    }
+
+    fn visit_fraction(&self, v: Fraction) -> Result<Self::Value, DeserializeError<&'de [u8]>> {
+        Ok(ValueRef::Fraction(v))
+    }
+
+    fn visit_id(&self, v: Id) -> Result<Self::Value, DeserializeError<&'de [u8]>> {
+        Ok(ValueRef::Id(v))
+    }
+
+    fn visit_fd(&self, v: Fd) -> Result<Self::Value, DeserializeError<&'de [u8]>> {
+        Ok(ValueRef::Fd(v))
+    }
+
+    fn visit_pointer(
+        &self,
+        type_: u32,
+        pointer: *const c_void,
+    ) -> Result<Self::Value, DeserializeError<&'de [u8]>> {
+        Ok(ValueRef::Pointer(type_, pointer))
+    }
+}
+
 /// A visitor producing [`Value`] for all type of values.
 pub struct ValueVisitor;
 
@@ -1552,8 +1914,8 @@ impl<'de> Visitor<'de> for ValueVisitor {
         }
 
         let object = Object {
-            type_: object_deserializer.object_type,
-            id: object_deserializer.object_id,
+            type_: object_deserializer.object_type(),
+            id: object_deserializer.object_id(),
             properties,
         };
 
@@ -0,0 +1,229 @@
+//! Reading and writing pods over [`std::io`] streams.
+//!
+//! Pods are written as their header and body followed by the padding to the next multiple of 8 bytes,
+//! which is how they are laid out in memory. The header holds the size of the body, so pods
+//! can be written one after the other and read back without any additional framing.
+//!
+//! This can be used to persist negotiated formats or params to disk or to exchange them between
+//! processes.
+//!
+//! ```
+//! use libspa::pod::{io::PodBuf, Value};
+//!
+//! let pod = PodBuf::serialize(&Value::Int(42)).unwrap();
+//!
+//! let mut file = Vec::new();
+//! pod.write_to(&mut file)?;
+//!
+//! let read = PodBuf::read_from(&mut file.as_slice())?;
+//! assert_eq!(read.as_bytes(), pod.as_bytes());
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::{
+    fmt,
+    io::{self, Cursor, Read, Write},
+    mem::size_of,
+    ops::Deref,
+};
+
+use super::{
+    serialize::{GenError, PodSerialize, PodSerializer},
+    Pod,
+};
+
+const HEADER_SIZE: usize = size_of::<spa_sys::spa_pod>();
+const PADDING: [u8; 8] = [0; 8];
+
+fn padding(size: usize) -> usize {
+    (8 - (size % 8)) % 8
+}
+
+impl Pod {
+    /// Write the pod followed by its padding to `w`.
+    pub fn write_to<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let bytes = self.as_bytes();
+        w.write_all(bytes)?;
+        w.write_all(&PADDING[..padding(bytes.len())])
+    }
+}
+
+/// An owned pod, stored in a correctly aligned buffer.
+///
+/// The pod can be accessed through [`Deref`].
+#[derive(Clone)]
+pub struct PodBuf {
+    // Use u64 so that the pod is correctly aligned.
+    buf: Vec<u64>,
+}
+
+impl PodBuf {
+    /// Read a pod written by [`Pod::write_to`] from `r`.
+    ///
+    /// The padding following the pod is consumed, so that further pods can be read from `r`.
+    ///
+    /// As the bytes usually come from outside the program, the pod is checked with [`Pod::validate`],
+    /// returning an error of kind [`io::ErrorKind::InvalidData`] if it is invalid.
+    /// If `r` ends before the pod, an error of kind [`io::ErrorKind::UnexpectedEof`] is returned.
+    pub fn read_from<R: Read>(mut r: R) -> io::Result<Self> {
+        let mut bytes = vec![0; HEADER_SIZE];
+        r.read_exact(&mut bytes)?;
+
+        let size = usize::try_from(u32::from_ne_bytes(bytes[..4].try_into().unwrap())).unwrap();
+        let len = u64::try_from(size + padding(size)).unwrap();
+
+        // Read incrementally instead of trusting the size to allocate the buffer.
+        let read = r.take(len).read_to_end(&mut bytes)?;
+        if u64::try_from(read).unwrap() != len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "pod is truncated",
+            ));
+        }
+
+        let buf = Self::copy(&bytes);
+        Pod::validate(buf.bytes())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        Ok(buf)
+    }
+
+    /// Serialize `value` into a new pod.
+    pub fn serialize<P: PodSerialize + ?Sized>(value: &P) -> Result<Self, GenError> {
+        let (cursor, _) = PodSerializer::serialize(Cursor::new(Vec::new()), value)?;
+        Ok(Self::copy(&cursor.into_inner()))
+    }
+
+    /// Copy the pod at the start of `bytes`.
+    ///
+    /// Returns `None` if `bytes` does not fit the entire pod, without its padding.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < HEADER_SIZE {
+            return None;
+        }
+
+        let size = u32::from_ne_bytes(bytes[..4].try_into().unwrap());
+        let len = HEADER_SIZE + usize::try_from(size).unwrap();
+        let bytes = bytes.get(..len)?;
+
+        Some(Self::copy(bytes))
+    }
+
+    /// Copy the bytes of a pod, adding the padding.
+    fn copy(bytes: &[u8]) -> Self {
+        let buf = bytes
+            .chunks(8)
+            .map(|chunk| {
+                let mut word = [0; 8];
+                word[..chunk.len()].copy_from_slice(chunk);
+                u64::from_ne_bytes(word)
+            })
+            .collect();
+
+        Self { buf }
+    }
+
+    /// The bytes of the buffer, including the padding of the pod.
+    fn bytes(&self) -> &[u8] {
+        // Safety: any u64 is a valid sequence of bytes.
+        unsafe { std::slice::from_raw_parts(self.buf.as_ptr().cast(), self.buf.len() * 8) }
+    }
+
+    pub fn as_pod(&self) -> &Pod {
+        // The buffer always holds the entire pod.
+        Pod::from_bytes(self.bytes()).unwrap()
+    }
+}
+
+impl From<&Pod> for PodBuf {
+    fn from(pod: &Pod) -> Self {
+        Self::copy(pod.as_bytes())
+    }
+}
+
+impl Deref for PodBuf {
+    type Target = Pod;
+
+    fn deref(&self) -> &Pod {
+        self.as_pod()
+    }
+}
+
+impl AsRef<Pod> for PodBuf {
+    fn as_ref(&self) -> &Pod {
+        self.as_pod()
+    }
+}
+
+impl fmt::Debug for PodBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PodBuf")
+            .field("type", &self.type_())
+            .field("size", &self.size())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pod::{deserialize::PodDeserializer, Object, Property, Value};
+
+    #[test]
+    fn roundtrip() {
+        let values = [
+            Value::String("pipewire".to_owned()),
+            Value::Object(Object {
+                type_: spa_sys::SPA_TYPE_OBJECT_Props,
+                id: spa_sys::SPA_PARAM_Props,
+                properties: vec![Property::new(spa_sys::SPA_PROP_volume, Value::Float(0.5))],
+            }),
+        ];
+
+        let mut stream = Vec::new();
+        for value in &values {
+            PodBuf::serialize(value)
+                .unwrap()
+                .write_to(&mut stream)
+                .unwrap();
+        }
+        // The string is padded.
+        assert_eq!(stream.len() % 8, 0);
+
+        let mut r = stream.as_slice();
+        for value in &values {
+            let pod = PodBuf::read_from(&mut r).unwrap();
+            let (_, read) = PodDeserializer::deserialize_any_from(pod.as_bytes()).unwrap();
+            assert_eq!(&read, value);
+        }
+        assert!(r.is_empty());
+    }
+
+    #[test]
+    fn truncated() {
+        let mut stream = Vec::new();
+        PodBuf::serialize(&Value::Long(3))
+            .unwrap()
+            .write_to(&mut stream)
+            .unwrap();
+
+        for len in [4, stream.len() - 1] {
+            let err = PodBuf::read_from(&stream[..len]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        let mut stream = Vec::new();
+        PodBuf::serialize(&Value::String("pipewire".to_owned()))
+            .unwrap()
+            .write_to(&mut stream)
+            .unwrap();
+        // Overwrite the NUL terminator.
+        stream[HEADER_SIZE + 8] = b'!';
+
+        let err = PodBuf::read_from(stream.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,409 @@
+//! Iteration over the children of container pods, implemented in pure Rust over the bytes of the pod.
+//!
+//! This mirrors the `SPA_POD_STRUCT_FOREACH`, `SPA_POD_OBJECT_FOREACH`, `SPA_POD_ARRAY_FOREACH`
+//! and `SPA_POD_SEQUENCE_FOREACH` macros of `spa/pod/iter.h`.
+
+use std::mem::size_of;
+
+use nix::errno::Errno;
+
+use super::{Pod, PropertyFlags};
+use crate::utils::SpaTypes;
+
+const HEADER_SIZE: usize = size_of::<spa_sys::spa_pod>();
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_ne_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn round_up(size: usize) -> usize {
+    (size + 7) & !7
+}
+
+/// Get the pod at the start of `bytes` if it fits entirely, as well as the bytes after it.
+pub(super) fn next_pod(bytes: &[u8]) -> Option<(&Pod, &[u8])> {
+    if bytes.len() < HEADER_SIZE {
+        return None;
+    }
+
+    let size = HEADER_SIZE + usize::try_from(read_u32(bytes, 0)).ok()?;
+    if size > bytes.len() {
+        return None;
+    }
+
+    // Safety: the header and body of the pod are inside `bytes`.
+    let pod = unsafe { Pod::from_raw(bytes.as_ptr().cast()) };
+    let rest = bytes.get(round_up(size)..).unwrap_or_default();
+
+    Some((pod, rest))
+}
+
+fn body(pod: &Pod) -> &[u8] {
+    &pod.as_bytes()[HEADER_SIZE..]
+}
+
+impl Pod {
+    /// Iterate over the fields of a struct pod.
+    pub fn struct_fields(&self) -> Result<StructFields<'_>, Errno> {
+        if !self.is_struct() {
+            return Err(Errno::EINVAL);
+        }
+
+        Ok(StructFields { bytes: body(self) })
+    }
+
+    /// Iterate over the properties of an object pod.
+    pub fn object_props(&self) -> Result<ObjectProps<'_>, Errno> {
+        if !self.is_object() {
+            return Err(Errno::EINVAL);
+        }
+
+        let body = body(self);
+        Ok(ObjectProps {
+            type_: read_u32(body, 0),
+            id: read_u32(body, 4),
+            bytes: &body[size_of::<spa_sys::spa_pod_object_body>()..],
+        })
+    }
+
+    /// Iterate over the bodies of the values of an array pod.
+    pub fn array_values(&self) -> Result<ArrayValues<'_>, Errno> {
+        if !self.is_array() {
+            return Err(Errno::EINVAL);
+        }
+
+        let body = body(self);
+        Ok(ArrayValues {
+            child_size: usize::try_from(read_u32(body, 0)).unwrap(),
+            child_type: SpaTypes::from_raw(read_u32(body, 4)),
+            bytes: &body[HEADER_SIZE..],
+        })
+    }
+
+    /// Iterate over the controls of a sequence pod.
+    pub fn sequence_controls(&self) -> Result<SequenceControls<'_>, Errno> {
+        if !self.is_sequence() {
+            return Err(Errno::EINVAL);
+        }
+
+        let body = body(self);
+        Ok(SequenceControls {
+            unit: read_u32(body, 0),
+            bytes: &body[size_of::<spa_sys::spa_pod_sequence_body>()..],
+        })
+    }
+}
+
+/// Iterator over the fields of a struct pod, returned by [`Pod::struct_fields`].
+#[derive(Debug, Clone)]
+pub struct StructFields<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for StructFields<'a> {
+    type Item = &'a Pod;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (pod, rest) = next_pod(self.bytes)?;
+        self.bytes = rest;
+
+        Some(pod)
+    }
+}
+
+/// A property of an object pod.
+#[derive(Debug, Clone, Copy)]
+pub struct PodProp<'a> {
+    pub key: u32,
+    pub flags: PropertyFlags,
+    pub value: &'a Pod,
+}
+
+/// Iterator over the properties of an object pod, returned by [`Pod::object_props`].
+#[derive(Debug, Clone)]
+pub struct ObjectProps<'a> {
+    type_: u32,
+    id: u32,
+    bytes: &'a [u8],
+}
+
+impl<'a> ObjectProps<'a> {
+    /// The type of the object.
+    pub fn type_(&self) -> u32 {
+        self.type_
+    }
+
+    /// The id of the object.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl<'a> Iterator for ObjectProps<'a> {
+    type Item = PodProp<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The key and flags of the property are followed by its value.
+        let key = read_u32(self.bytes.get(..8)?, 0);
+        let flags = PropertyFlags::from_bits_retain(read_u32(self.bytes, 4));
+        let (value, rest) = next_pod(&self.bytes[8..])?;
+        self.bytes = rest;
+
+        Some(PodProp { key, flags, value })
+    }
+}
+
+/// Iterator over the bodies of the values of an array pod, returned by [`Pod::array_values`].
+#[derive(Debug, Clone)]
+pub struct ArrayValues<'a> {
+    child_size: usize,
+    child_type: SpaTypes,
+    bytes: &'a [u8],
+}
+
+impl<'a> ArrayValues<'a> {
+    /// The type of the values.
+    pub fn child_type(&self) -> SpaTypes {
+        self.child_type
+    }
+
+    /// The size of the body of each value.
+    pub fn child_size(&self) -> usize {
+        self.child_size
+    }
+}
+
+impl<'a> Iterator for ArrayValues<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.child_size == 0 || self.bytes.len() < self.child_size {
+            return None;
+        }
+
+        let (value, rest) = self.bytes.split_at(self.child_size);
+        self.bytes = rest;
+
+        Some(value)
+    }
+}
+
+/// A control of a sequence pod.
+#[derive(Debug, Clone, Copy)]
+pub struct PodControl<'a> {
+    /// The offset of the control, in the unit of the sequence.
+    pub offset: u32,
+    /// The type of the control, a `SPA_CONTROL_*` value.
+    pub type_: u32,
+    pub value: &'a Pod,
+}
+
+/// Iterator over the controls of a sequence pod, returned by [`Pod::sequence_controls`].
+#[derive(Debug, Clone)]
+pub struct SequenceControls<'a> {
+    unit: u32,
+    bytes: &'a [u8],
+}
+
+impl<'a> SequenceControls<'a> {
+    /// The unit of the offsets of the controls.
+    pub fn unit(&self) -> u32 {
+        self.unit
+    }
+}
+
+impl<'a> Iterator for SequenceControls<'a> {
+    type Item = PodControl<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The offset and type of the control are followed by its value.
+        let offset = read_u32(self.bytes.get(..8)?, 0);
+        let type_ = read_u32(self.bytes, 4);
+        let (value, rest) = next_pod(&self.bytes[8..])?;
+        self.bytes = rest;
+
+        Some(PodControl {
+            offset,
+            type_,
+            value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        pod::{
+            test_utils::{as_bytes, serialize},
+            Object, Property, Value, ValueArray,
+        },
+        utils::Fd,
+    };
+
+    fn as_pod(buffer: &[u64]) -> &Pod {
+        Pod::from_bytes(as_bytes(buffer)).unwrap()
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(feature = "no-ffi-pod")), ignore)]
+    fn struct_fields() {
+        let buffer = serialize(&Value::Struct(vec![
+            Value::Int(42),
+            Value::String("pipewire".to_owned()),
+            Value::Bool(true),
+        ]));
+        let pod = as_pod(&buffer);
+
+        let fields: Vec<&Pod> = pod.struct_fields().unwrap().collect();
+        assert_eq!(fields.len(), 3);
+        assert_eq!(fields[0].get_int(), Ok(42));
+        assert!(fields[1].is_string());
+        assert_eq!(fields[2].get_bool(), Ok(true));
+
+        assert_eq!(fields[0].struct_fields().unwrap_err(), Errno::EINVAL);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(feature = "no-ffi-pod")), ignore)]
+    fn object_props() {
+        let buffer = serialize(&Value::Object(Object {
+            type_: spa_sys::SPA_TYPE_OBJECT_Props,
+            id: spa_sys::SPA_PARAM_Props,
+            properties: vec![
+                Property::new(spa_sys::SPA_PROP_volume, Value::Float(0.5)),
+                Property::new(spa_sys::SPA_PROP_mute, Value::Bool(false))
+                    .with_flags(PropertyFlags::HARDWARE),
+            ],
+        }));
+        let pod = as_pod(&buffer);
+
+        let props = pod.object_props().unwrap();
+        assert_eq!(props.type_(), spa_sys::SPA_TYPE_OBJECT_Props);
+        assert_eq!(props.id(), spa_sys::SPA_PARAM_Props);
+
+        let props: Vec<PodProp> = props.collect();
+        assert_eq!(props.len(), 2);
+        assert_eq!(props[0].key, spa_sys::SPA_PROP_volume);
+        assert_eq!(props[0].value.get_float(), Ok(0.5));
+        assert_eq!(props[1].key, spa_sys::SPA_PROP_mute);
+        assert_eq!(props[1].flags, PropertyFlags::HARDWARE);
+        assert_eq!(props[1].value.get_bool(), Ok(false));
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(feature = "no-ffi-pod")), ignore)]
+    fn array_values() {
+        let buffer = serialize(&Value::ValueArray(ValueArray::Int(vec![1, 2, 3])));
+        let pod = as_pod(&buffer);
+
+        let values = pod.array_values().unwrap();
+        assert!(values.child_type() == SpaTypes::Int);
+        assert_eq!(values.child_size(), 4);
+
+        let values: Vec<i32> = values
+            .map(|value| i32::from_ne_bytes(value.try_into().unwrap()))
+            .collect();
+        assert_eq!(values, [1, 2, 3]);
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(feature = "no-ffi-pod")), ignore)]
+    fn sequence_controls() {
+        // A sequence with an int control at offset 16, written by hand as sequences cannot be
+        // serialized yet.
+        let words: [u32; 10] = [
+            32,
+            spa_sys::SPA_TYPE_Sequence,
+            0,
+            0,
+            16,
+            spa_sys::SPA_CONTROL_Properties,
+            4,
+            spa_sys::SPA_TYPE_Int,
+            7,
+            0,
+        ];
+        let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_ne_bytes()).collect();
+        let buffer: Vec<u64> = bytes
+            .chunks(8)
+            .map(|chunk| u64::from_ne_bytes(chunk.try_into().unwrap()))
+            .collect();
+        let pod = as_pod(&buffer);
+
+        let controls = pod.sequence_controls().unwrap();
+        assert_eq!(controls.unit(), 0);
+
+        let controls: Vec<PodControl> = controls.collect();
+        assert_eq!(controls.len(), 1);
+        assert_eq!(controls[0].offset, 16);
+        assert_eq!(controls[0].type_, spa_sys::SPA_CONTROL_Properties);
+        assert_eq!(controls[0].value.get_int(), Ok(7));
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(feature = "no-ffi-pod")), ignore)]
+    fn next_in() {
+        let buffer = serialize(&Value::Struct(vec![
+            Value::Int(1),
+            Value::Long(2),
+            Value::Bool(true),
+        ]));
+        let pod = as_pod(&buffer);
+        let body = body(pod);
+
+        let first = pod.struct_fields().unwrap().next().unwrap();
+        assert!(first.is_inside(body));
+        assert!(!pod.is_inside(body));
+
+        let second = first.next_in(body).unwrap();
+        assert_eq!(second.get_long(), Ok(2));
+        let third = second.next_in(body).unwrap();
+        assert_eq!(third.get_bool(), Ok(true));
+        assert!(third.next_in(body).is_none());
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(feature = "no-ffi-pod")), ignore)]
+    fn find_prop() {
+        let buffer = serialize(&Value::Object(Object {
+            type_: spa_sys::SPA_TYPE_OBJECT_Props,
+            id: spa_sys::SPA_PARAM_Props,
+            properties: vec![
+                Property::new(spa_sys::SPA_PROP_volume, Value::Float(0.5)),
+                Property::new(spa_sys::SPA_PROP_mute, Value::Bool(false)),
+            ],
+        }));
+        let pod = as_pod(&buffer);
+
+        let mute = pod.find_prop(spa_sys::SPA_PROP_mute).unwrap();
+        assert_eq!(mute.key, spa_sys::SPA_PROP_mute);
+        assert_eq!(mute.value.get_bool(), Ok(false));
+        assert!(pod.find_prop(spa_sys::SPA_PROP_channelVolumes).is_none());
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(feature = "no-ffi-pod")), ignore)]
+    fn from_data() {
+        let mut buffer = vec![0];
+        buffer.extend(serialize(&Value::Int(42)));
+        let data = unsafe { std::slice::from_raw_parts(buffer.as_ptr().cast::<u8>(), 24) };
+
+        let pod = Pod::from_data(data, 8, 16).unwrap();
+        assert_eq!(pod.get_int(), Ok(42));
+        // Too small for the pod, out of `data` and misaligned.
+        assert!(Pod::from_data(data, 8, 8).is_none());
+        assert!(Pod::from_data(data, 16, 16).is_none());
+        assert!(Pod::from_data(data, 2, 16).is_none());
+    }
+
+    #[test]
+    #[cfg_attr(all(miri, not(feature = "no-ffi-pod")), ignore)]
+    fn fd_out_of_range() {
+        let buffer = serialize(&Value::Fd(Fd(i64::MAX)));
+        let pod = as_pod(&buffer);
+
+        assert!(pod.is_fd());
+        assert_eq!(pod.get_fd(), Err(Errno::EINVAL));
+    }
+}
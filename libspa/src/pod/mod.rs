@@ -9,16 +9,58 @@
 
 pub mod builder;
 pub mod deserialize;
+pub mod io;
+pub mod iter;
+#[cfg(feature = "no-ffi-pod")]
+mod no_ffi;
 pub mod parser;
 pub mod serialize;
+pub mod text;
+pub mod validate;
+pub mod value_ref;
+
+/// Helpers shared by the tests of the pod modules.
+#[cfg(test)]
+mod test_utils {
+    use std::io::Cursor;
+
+    use super::{serialize::PodSerializer, Value};
+
+    /// Serialize `value` into a buffer of `u64`, so that the pod is correctly aligned.
+    pub(crate) fn serialize(value: &Value) -> Vec<u64> {
+        let bytes = PodSerializer::serialize(Cursor::new(Vec::new()), value)
+            .unwrap()
+            .0
+            .into_inner();
+
+        bytes
+            .chunks(8)
+            .map(|chunk| u64::from_ne_bytes(chunk.try_into().unwrap()))
+            .collect()
+    }
+
+    /// The bytes of a buffer returned by [`serialize`].
+    pub(crate) fn as_bytes(buffer: &[u64]) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(buffer.as_ptr().cast(), buffer.len() * 8) }
+    }
+
+    /// The bytes of a buffer returned by [`serialize`], to corrupt the pod.
+    pub(crate) fn as_bytes_mut(buffer: &mut [u64]) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(buffer.as_mut_ptr().cast(), buffer.len() * 8) }
+    }
+}
 
 use std::{
+    borrow::Cow,
     ffi::c_void,
+    fmt,
     io::{Seek, Write},
-    mem::MaybeUninit,
     os::fd::RawFd,
 };
 
+#[cfg(not(feature = "no-ffi-pod"))]
+use std::mem::MaybeUninit;
+
 use bitflags::bitflags;
 use cookie_factory::{
     bytes::{ne_f32, ne_f64, ne_i32, ne_i64, ne_u32},
@@ -39,7 +81,7 @@ use nom::{
 use deserialize::{BoolVisitor, NoneVisitor, PodDeserialize, PodDeserializer};
 use serialize::{PodSerialize, PodSerializer};
 
-use crate::utils::{Choice, Fd, Fraction, Id, Rectangle, SpaTypes};
+use crate::utils::{Choice, ChoiceEnum, Fd, Fraction, Id, Rectangle, SpaTypes};
 
 use self::deserialize::{
     ChoiceBoolVisitor, ChoiceDoubleVisitor, ChoiceFdVisitor, ChoiceFloatVisitor,
@@ -88,8 +130,21 @@ impl Pod {
         pod.cast::<Self>().as_mut().unwrap()
     }
 
+    #[deprecated(
+        note = "Use `as_ptr` or `as_mut_ptr`, which do not allow mutation through a shared borrow"
+    )]
     pub fn as_raw_ptr(&self) -> *mut spa_sys::spa_pod {
-        std::ptr::addr_of!(self.0).cast_mut()
+        self.as_ptr().cast_mut()
+    }
+
+    /// Get a pointer to the pod, which must only be used for reading it.
+    pub fn as_ptr(&self) -> *const spa_sys::spa_pod {
+        std::ptr::addr_of!(self.0)
+    }
+
+    /// Get a pointer to the pod, which can be used for mutating it.
+    pub fn as_mut_ptr(&mut self) -> *mut spa_sys::spa_pod {
+        std::ptr::addr_of_mut!(self.0)
     }
 
     /// Construct a pod from raw bytes.
@@ -123,7 +178,7 @@ impl Pod {
     }
 
     pub fn as_bytes(&self) -> &[u8] {
-        let ptr: *const u8 = self.as_raw_ptr().cast();
+        let ptr: *const u8 = self.as_ptr().cast();
         let size: usize = self.size().try_into().unwrap();
         let size = size + std::mem::size_of::<spa_sys::spa_pod>();
 
@@ -138,22 +193,45 @@ impl Pod {
         self.0.size
     }
 
-    // TODO: Other methods from iter.h that are still missing
+    /// The string of a string pod, failing with `EINVAL` if it is not valid UTF-8.
+    pub fn get_str(&self) -> Result<&str, Errno> {
+        self.get_cstr()?.to_str().map_err(|_| Errno::EINVAL)
+    }
+
+    /// The string of a string pod, with invalid UTF-8 sequences replaced by `U+FFFD`.
+    pub fn get_str_lossy(&self) -> Result<Cow<'_, str>, Errno> {
+        Ok(self.get_cstr()?.to_string_lossy())
+    }
+}
+
+impl fmt::Debug for Pod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pod")
+            .field("type", &self.type_())
+            .field("size", &self.size())
+            .finish()
+    }
+}
 
+/// Accessors calling the static inline functions of `spa/pod/iter.h`.
+///
+/// With the `no-ffi-pod` feature, they are implemented in pure Rust instead.
+#[cfg(not(feature = "no-ffi-pod"))]
+impl Pod {
     pub fn is_none(&self) -> bool {
-        let res = unsafe { spa_sys::spa_pod_is_none(self.as_raw_ptr()) };
+        let res = unsafe { spa_sys::spa_pod_is_none(self.as_ptr()) };
         res != 0
     }
 
     pub fn is_bool(&self) -> bool {
-        let res = unsafe { spa_sys::spa_pod_is_bool(self.as_raw_ptr()) };
+        let res = unsafe { spa_sys::spa_pod_is_bool(self.as_ptr()) };
         res != 0
     }
 
     pub fn get_bool(&self) -> Result<bool, Errno> {
         unsafe {
             let mut b: MaybeUninit<bool> = MaybeUninit::uninit();
-            let res = spa_sys::spa_pod_get_bool(self.as_raw_ptr(), b.as_mut_ptr());
+            let res = spa_sys::spa_pod_get_bool(self.as_ptr(), b.as_mut_ptr());
 
             if res >= 0 {
                 Ok(b.assume_init())
@@ -164,14 +242,14 @@ impl Pod {
     }
 
     pub fn is_id(&self) -> bool {
-        let res = unsafe { spa_sys::spa_pod_is_id(self.as_raw_ptr()) };
+        let res = unsafe { spa_sys::spa_pod_is_id(self.as_ptr()) };
         res != 0
     }
 
     pub fn get_id(&self) -> Result<Id, Errno> {
         unsafe {
             let mut id: MaybeUninit<u32> = MaybeUninit::uninit();
-            let res = spa_sys::spa_pod_get_id(self.as_raw_ptr(), id.as_mut_ptr());
+            let res = spa_sys::spa_pod_get_id(self.as_ptr(), id.as_mut_ptr());
 
             if res >= 0 {
                 Ok(Id(id.assume_init()))
@@ -182,14 +260,14 @@ impl Pod {
     }
 
     pub fn is_int(&self) -> bool {
-        let res = unsafe { spa_sys::spa_pod_is_int(self.as_raw_ptr()) };
+        let res = unsafe { spa_sys::spa_pod_is_int(self.as_ptr()) };
         res != 0
     }
 
     pub fn get_int(&self) -> Result<i32, Errno> {
         unsafe {
             let mut int: MaybeUninit<i32> = MaybeUninit::uninit();
-            let res = spa_sys::spa_pod_get_int(self.as_raw_ptr(), int.as_mut_ptr());
+            let res = spa_sys::spa_pod_get_int(self.as_ptr(), int.as_mut_ptr());
 
             if res >= 0 {
                 Ok(int.assume_init())
@@ -200,14 +278,14 @@ impl Pod {
     }
 
     pub fn is_long(&self) -> bool {
-        let res = unsafe { spa_sys::spa_pod_is_long(self.as_raw_ptr()) };
+        let res = unsafe { spa_sys::spa_pod_is_long(self.as_ptr()) };
         res != 0
     }
 
     pub fn get_long(&self) -> Result<i64, Errno> {
         unsafe {
             let mut long: MaybeUninit<i64> = MaybeUninit::uninit();
-            let res = spa_sys::spa_pod_get_long(self.as_raw_ptr(), long.as_mut_ptr());
+            let res = spa_sys::spa_pod_get_long(self.as_ptr(), long.as_mut_ptr());
 
             if res >= 0 {
                 Ok(long.assume_init())
@@ -218,14 +296,14 @@ impl Pod {
     }
 
     pub fn is_float(&self) -> bool {
-        let res = unsafe { spa_sys::spa_pod_is_float(self.as_raw_ptr()) };
+        let res = unsafe { spa_sys::spa_pod_is_float(self.as_ptr()) };
         res != 0
     }
 
     pub fn get_float(&self) -> Result<f32, Errno> {
         unsafe {
             let mut float: MaybeUninit<f32> = MaybeUninit::uninit();
-            let res = spa_sys::spa_pod_get_float(self.as_raw_ptr(), float.as_mut_ptr());
+            let res = spa_sys::spa_pod_get_float(self.as_ptr(), float.as_mut_ptr());
 
             if res >= 0 {
                 Ok(float.assume_init())
@@ -236,14 +314,14 @@ impl Pod {
     }
 
     pub fn is_double(&self) -> bool {
-        let res = unsafe { spa_sys::spa_pod_is_double(self.as_raw_ptr()) };
+        let res = unsafe { spa_sys::spa_pod_is_double(self.as_ptr()) };
         res != 0
     }
 
     pub fn get_double(&self) -> Result<f64, Errno> {
         unsafe {
             let mut double: MaybeUninit<f64> = MaybeUninit::uninit();
-            let res = spa_sys::spa_pod_get_double(self.as_raw_ptr(), double.as_mut_ptr());
+            let res = spa_sys::spa_pod_get_double(self.as_ptr(), double.as_mut_ptr());
 
             if res >= 0 {
                 Ok(double.assume_init())
@@ -254,14 +332,27 @@ impl Pod {
     }
 
     pub fn is_string(&self) -> bool {
-        let res = unsafe { spa_sys::spa_pod_is_string(self.as_raw_ptr()) };
+        let res = unsafe { spa_sys::spa_pod_is_string(self.as_ptr()) };
         res != 0
     }
 
-    // TODO: to_string
+    /// The string of a string pod, as it is stored in the pod.
+    pub fn get_cstr(&self) -> Result<&std::ffi::CStr, Errno> {
+        unsafe {
+            let mut string: MaybeUninit<*const std::ffi::c_char> = MaybeUninit::uninit();
+            let res = spa_sys::spa_pod_get_string(self.as_ptr(), string.as_mut_ptr());
+
+            if res >= 0 {
+                // The pod has been checked to end with a null byte.
+                Ok(std::ffi::CStr::from_ptr(string.assume_init()))
+            } else {
+                Err(Errno::from_i32(-res))
+            }
+        }
+    }
 
     pub fn is_bytes(&self) -> bool {
-        let res = unsafe { spa_sys::spa_pod_is_bytes(self.as_raw_ptr()) };
+        let res = unsafe { spa_sys::spa_pod_is_bytes(self.as_ptr()) };
         res != 0
     }
 
@@ -270,7 +361,7 @@ impl Pod {
             let mut bytes: MaybeUninit<*const c_void> = MaybeUninit::uninit();
             let mut len: MaybeUninit<u32> = MaybeUninit::uninit();
             let res =
-                spa_sys::spa_pod_get_bytes(self.as_raw_ptr(), bytes.as_mut_ptr(), len.as_mut_ptr());
+                spa_sys::spa_pod_get_bytes(self.as_ptr(), bytes.as_mut_ptr(), len.as_mut_ptr());
 
             if res >= 0 {
                 let bytes = bytes.assume_init();
@@ -284,7 +375,7 @@ impl Pod {
     }
 
     pub fn is_pointer(&self) -> bool {
-        let res = unsafe { spa_sys::spa_pod_is_pointer(self.as_raw_ptr()) };
+        let res = unsafe { spa_sys::spa_pod_is_pointer(self.as_ptr()) };
         res != 0
     }
 
@@ -293,7 +384,7 @@ impl Pod {
             let mut _type: MaybeUninit<u32> = MaybeUninit::uninit();
             let mut pointer: MaybeUninit<*const c_void> = MaybeUninit::uninit();
             let res = spa_sys::spa_pod_get_pointer(
-                self.as_raw_ptr(),
+                self.as_ptr(),
                 _type.as_mut_ptr(),
                 pointer.as_mut_ptr(),
             );
@@ -309,19 +400,17 @@ impl Pod {
     }
 
     pub fn is_fd(&self) -> bool {
-        let res = unsafe { spa_sys::spa_pod_is_fd(self.as_raw_ptr()) };
+        let res = unsafe { spa_sys::spa_pod_is_fd(self.as_ptr()) };
         res != 0
     }
 
     pub fn get_fd(&self) -> Result<RawFd, Errno> {
         unsafe {
             let mut fd: MaybeUninit<i64> = MaybeUninit::uninit();
-            let res = spa_sys::spa_pod_get_fd(self.as_raw_ptr(), fd.as_mut_ptr());
+            let res = spa_sys::spa_pod_get_fd(self.as_ptr(), fd.as_mut_ptr());
 
             if res >= 0 {
-                let fd = fd.assume_init();
-                let fd: RawFd = fd.try_into().unwrap();
-                Ok(fd)
+                fd.assume_init().try_into().map_err(|_| Errno::EINVAL)
             } else {
                 Err(Errno::from_i32(-res))
             }
@@ -329,14 +418,14 @@ impl Pod {
     }
 
     pub fn is_rectangle(&self) -> bool {
-        let res = unsafe { spa_sys::spa_pod_is_rectangle(self.as_raw_ptr()) };
+        let res = unsafe { spa_sys::spa_pod_is_rectangle(self.as_ptr()) };
         res != 0
     }
 
     pub fn get_rectangle(&self) -> Result<Rectangle, Errno> {
         unsafe {
             let mut rectangle: MaybeUninit<spa_sys::spa_rectangle> = MaybeUninit::uninit();
-            let res = spa_sys::spa_pod_get_rectangle(self.as_raw_ptr(), rectangle.as_mut_ptr());
+            let res = spa_sys::spa_pod_get_rectangle(self.as_ptr(), rectangle.as_mut_ptr());
 
             if res >= 0 {
                 Ok(rectangle.assume_init())
@@ -347,14 +436,14 @@ impl Pod {
     }
 
     pub fn is_fraction(&self) -> bool {
-        let res = unsafe { spa_sys::spa_pod_is_fraction(self.as_raw_ptr()) };
+        let res = unsafe { spa_sys::spa_pod_is_fraction(self.as_ptr()) };
         res != 0
     }
 
     pub fn get_fraction(&self) -> Result<Fraction, Errno> {
         unsafe {
             let mut fraction: MaybeUninit<spa_sys::spa_fraction> = MaybeUninit::uninit();
-            let res = spa_sys::spa_pod_get_fraction(self.as_raw_ptr(), fraction.as_mut_ptr());
+            let res = spa_sys::spa_pod_get_fraction(self.as_ptr(), fraction.as_mut_ptr());
 
             if res >= 0 {
                 Ok(fraction.assume_init())
@@ -365,34 +454,105 @@ impl Pod {
     }
 
     pub fn is_bitmap(&self) -> bool {
-        let res = unsafe { spa_sys::spa_pod_is_bitmap(self.as_raw_ptr()) };
+        let res = unsafe { spa_sys::spa_pod_is_bitmap(self.as_ptr()) };
         res != 0
     }
 
     pub fn is_array(&self) -> bool {
-        let res = unsafe { spa_sys::spa_pod_is_array(self.as_raw_ptr()) };
+        let res = unsafe { spa_sys::spa_pod_is_array(self.as_ptr()) };
         res != 0
     }
 
     pub fn is_choice(&self) -> bool {
-        let res = unsafe { spa_sys::spa_pod_is_choice(self.as_raw_ptr()) };
+        let res = unsafe { spa_sys::spa_pod_is_choice(self.as_ptr()) };
         res != 0
     }
 
     pub fn is_struct(&self) -> bool {
-        let res = unsafe { spa_sys::spa_pod_is_struct(self.as_raw_ptr()) };
+        let res = unsafe { spa_sys::spa_pod_is_struct(self.as_ptr()) };
         res != 0
     }
 
     pub fn is_object(&self) -> bool {
-        let res = unsafe { spa_sys::spa_pod_is_object(self.as_raw_ptr()) };
+        let res = unsafe { spa_sys::spa_pod_is_object(self.as_ptr()) };
         res != 0
     }
 
     pub fn is_sequence(&self) -> bool {
-        let res = unsafe { spa_sys::spa_pod_is_sequence(self.as_raw_ptr()) };
+        let res = unsafe { spa_sys::spa_pod_is_sequence(self.as_ptr()) };
         res != 0
     }
+
+    /// Get the pod of at most `size` bytes at `offset` in `data`, such as the pod in the chunk of a
+    /// buffer, or `None` if it does not fit or is not aligned.
+    pub fn from_data(data: &[u8], offset: usize, size: usize) -> Option<&Self> {
+        if size < std::mem::size_of::<spa_sys::spa_pod>() {
+            return None;
+        }
+
+        let pod = unsafe {
+            spa_sys::spa_pod_from_data(
+                data.as_ptr().cast_mut().cast(),
+                data.len(),
+                offset.try_into().ok()?,
+                size,
+            )
+        };
+        if pod.is_null() || pod as usize % std::mem::align_of::<spa_sys::spa_pod>() != 0 {
+            return None;
+        }
+
+        // Safety: the header and body of the pod are inside `data`.
+        Some(unsafe { Self::from_raw(pod.cast()) })
+    }
+
+    /// Whether the pod lies entirely inside `body`, such as the body of a container pod.
+    pub fn is_inside(&self, body: &[u8]) -> bool {
+        let Ok(size) = u32::try_from(body.len()) else {
+            return false;
+        };
+
+        self.as_ptr() as usize >= body.as_ptr() as usize
+            && unsafe {
+                spa_sys::spa_pod_is_inside(body.as_ptr().cast(), size, self.as_ptr().cast())
+            }
+    }
+
+    /// Get the pod following this one in `body`, such as the next field of a struct pod,
+    /// or `None` if this is the last pod of `body`.
+    pub fn next_in<'a>(&self, body: &'a [u8]) -> Option<&'a Self> {
+        if !self.is_inside(body) {
+            return None;
+        }
+
+        unsafe {
+            let next = spa_sys::spa_pod_next(self.as_ptr().cast());
+            let size = body.len().try_into().unwrap();
+            if !spa_sys::spa_pod_is_inside(body.as_ptr().cast(), size, next.cast()) {
+                return None;
+            }
+
+            // Safety: the header and body of the next pod are inside `body`.
+            Some(Self::from_raw(next.cast()))
+        }
+    }
+
+    /// Find the property with key `key` of an object pod.
+    pub fn find_prop(&self, key: u32) -> Option<iter::PodProp<'_>> {
+        if !self.is_object() {
+            return None;
+        }
+
+        unsafe {
+            let prop = spa_sys::spa_pod_find_prop(self.as_ptr(), std::ptr::null(), key).as_ref()?;
+
+            Some(iter::PodProp {
+                key: prop.key,
+                flags: PropertyFlags::from_bits_retain(prop.flags),
+                value: Self::from_raw(&prop.value),
+            })
+        }
+    }
 }
 
 /// Implementors of this trait are the canonical representation of a specific type of fixed sized SPA pod.
@@ -418,6 +578,18 @@ pub trait CanonicalFixedSizedPod: private::CanonicalFixedSizedPodSeal {
     fn deserialize_body(input: &[u8]) -> IResult<&[u8], Self>
     where
         Self: Sized;
+    #[doc(hidden)]
+    fn into_value(self) -> Value
+    where
+        Self: Sized;
+    #[doc(hidden)]
+    fn into_value_array(values: Vec<Self>) -> ValueArray
+    where
+        Self: Sized;
+    #[doc(hidden)]
+    fn from_value_array(array: &ValueArray) -> Option<&[Self]>
+    where
+        Self: Sized;
 }
 
 mod private {
@@ -448,6 +620,30 @@ impl<T: CanonicalFixedSizedPod + Copy> FixedSizedPod for T {
     }
 }
 
+/// Implement the conversions of a [`CanonicalFixedSizedPod`] from and to the `$variant` variants
+/// of [`Value`] and [`ValueArray`].
+macro_rules! impl_value_conversions {
+    ($variant:ident) => {
+        fn into_value(self) -> Value {
+            Value::$variant(self)
+        }
+
+        impl_value_conversions!(@array $variant);
+    };
+    (@array $variant:ident) => {
+        fn into_value_array(values: Vec<Self>) -> ValueArray {
+            ValueArray::$variant(values)
+        }
+
+        fn from_value_array(array: &ValueArray) -> Option<&[Self]> {
+            match array {
+                ValueArray::$variant(values) => Some(values),
+                _ => None,
+            }
+        }
+    };
+}
+
 /// Serialize into a `None` type pod.
 impl CanonicalFixedSizedPod for () {
     const TYPE: u32 = spa_sys::SPA_TYPE_None;
@@ -463,6 +659,12 @@ impl CanonicalFixedSizedPod for () {
     {
         Ok((input, ()))
     }
+
+    fn into_value(self) -> Value {
+        Value::None
+    }
+
+    impl_value_conversions!(@array None);
 }
 
 /// Serialize into a `Bool` type pod.
@@ -480,6 +682,8 @@ impl CanonicalFixedSizedPod for bool {
     {
         map(u32(Endianness::Native), |b| b != 0)(input)
     }
+
+    impl_value_conversions!(Bool);
 }
 
 /// Serialize into a `Int` type pod.
@@ -497,6 +701,8 @@ impl CanonicalFixedSizedPod for i32 {
     {
         i32(Endianness::Native)(input)
     }
+
+    impl_value_conversions!(Int);
 }
 
 /// Serialize into a `Long` type pod.
@@ -514,6 +720,8 @@ impl CanonicalFixedSizedPod for i64 {
     {
         i64(Endianness::Native)(input)
     }
+
+    impl_value_conversions!(Long);
 }
 
 /// Serialize into a `Float` type pod.
@@ -531,6 +739,8 @@ impl CanonicalFixedSizedPod for f32 {
     {
         f32(Endianness::Native)(input)
     }
+
+    impl_value_conversions!(Float);
 }
 
 /// Serialize into a `Double` type pod.
@@ -548,6 +758,8 @@ impl CanonicalFixedSizedPod for f64 {
     {
         f64(Endianness::Native)(input)
     }
+
+    impl_value_conversions!(Double);
 }
 
 /// Serialize into a `Rectangle` type pod.
@@ -568,6 +780,8 @@ impl CanonicalFixedSizedPod for Rectangle {
             |(width, height)| Rectangle { width, height },
         )(input)
     }
+
+    impl_value_conversions!(Rectangle);
 }
 
 /// Serialize into a `Fraction` type pod.
@@ -588,6 +802,8 @@ impl CanonicalFixedSizedPod for Fraction {
             |(num, denom)| Fraction { num, denom },
         )(input)
     }
+
+    impl_value_conversions!(Fraction);
 }
 
 impl CanonicalFixedSizedPod for Id {
@@ -604,6 +820,8 @@ impl CanonicalFixedSizedPod for Id {
     {
         map(u32(Endianness::Native), Id)(input)
     }
+
+    impl_value_conversions!(Id);
 }
 
 impl CanonicalFixedSizedPod for Fd {
@@ -620,6 +838,8 @@ impl CanonicalFixedSizedPod for Fd {
     {
         map(i64(Endianness::Native), Fd)(input)
     }
+
+    impl_value_conversions!(Fd);
 }
 
 /// Implementors of this trait can be serialized into pods that always have the same size.
@@ -1010,6 +1230,10 @@ pub enum Value {
     Choice(ChoiceValue),
     /// a pointer.
     Pointer(u32, *const c_void),
+    /// a pod of a type unknown to these bindings, such as a vendor extension.
+    ///
+    /// The body is kept as is, so that the pod is serialized back unchanged.
+    Other { type_: u32, bytes: Vec<u8> },
 }
 
 /// an array of same type objects.
@@ -1037,6 +1261,139 @@ pub enum ValueArray {
     Fd(Vec<Fd>),
 }
 
+/// Apply `$body` to the values of `$array`, whatever their type.
+macro_rules! with_values {
+    ($array:expr, $values:ident => $body:expr) => {
+        match $array {
+            ValueArray::None($values) => $body,
+            ValueArray::Bool($values) => $body,
+            ValueArray::Id($values) => $body,
+            ValueArray::Int($values) => $body,
+            ValueArray::Long($values) => $body,
+            ValueArray::Float($values) => $body,
+            ValueArray::Double($values) => $body,
+            ValueArray::Rectangle($values) => $body,
+            ValueArray::Fraction($values) => $body,
+            ValueArray::Fd($values) => $body,
+        }
+    };
+}
+
+impl ValueArray {
+    /// The number of values in the array.
+    pub fn len(&self) -> usize {
+        with_values!(self, values => values.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the value at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<Value> {
+        with_values!(self, values => {
+            values.get(index).copied().map(CanonicalFixedSizedPod::into_value)
+        })
+    }
+
+    /// Iterate over the values of the array.
+    pub fn iter(&self) -> impl Iterator<Item = Value> + '_ {
+        (0..self.len()).map(move |index| self.get(index).unwrap())
+    }
+}
+
+/// Create an array from values of any type that can be serialized into fixed sized pods.
+///
+/// ```
+/// use libspa::{pod::ValueArray, utils::Id};
+///
+/// let array = ValueArray::from(vec![0.5f32, 0.8]);
+/// assert_eq!(array, ValueArray::Float(vec![0.5, 0.8]));
+///
+/// let positions: Vec<Id> = array.try_into().unwrap_or_default();
+/// assert!(positions.is_empty());
+/// ```
+impl<T: FixedSizedPod> From<Vec<T>> for ValueArray {
+    fn from(values: Vec<T>) -> Self {
+        T::CanonicalType::into_value_array(
+            values
+                .iter()
+                .map(FixedSizedPod::as_canonical_type)
+                .collect(),
+        )
+    }
+}
+
+/// Get the values of an array, if they have the type of pod `T` converts from.
+///
+/// The array is returned back as the error if its values have another type.
+impl<T: FixedSizedPod> TryFrom<ValueArray> for Vec<T> {
+    type Error = ValueArray;
+
+    fn try_from(array: ValueArray) -> Result<Self, Self::Error> {
+        match T::CanonicalType::from_value_array(&array) {
+            Some(values) => Ok(values.iter().map(T::from_canonical_type).collect()),
+            None => Err(array),
+        }
+    }
+}
+
+impl Value {
+    /// Create a [`Value::ValueArray`] from values of any type that can be serialized into fixed sized pods.
+    ///
+    /// ```
+    /// use libspa::pod::{Property, Value};
+    ///
+    /// let volumes = Property::new(libspa::sys::SPA_PROP_channelVolumes, Value::array([1.0f32; 2]));
+    /// ```
+    pub fn array<T: FixedSizedPod>(values: impl IntoIterator<Item = T>) -> Self {
+        Self::ValueArray(values.into_iter().collect::<Vec<T>>().into())
+    }
+
+    /// Create a [`Value::Choice`] without flags, from anything convertible into a [`ChoiceEnum`],
+    /// such as a [`RangeInclusive`](std::ops::RangeInclusive) or a [`StepRange`](crate::utils::StepRange).
+    ///
+    /// ```
+    /// use libspa::{pod::Value, utils::StepRange};
+    ///
+    /// let rates = Value::choice(8000..=192000);
+    /// let volumes = Value::choice(StepRange::new(0.0f32..=1.0, 0.1));
+    /// ```
+    pub fn choice<T: CanonicalFixedSizedPod>(choice: impl Into<ChoiceEnum<T>>) -> Self
+    where
+        Choice<T>: Into<ChoiceValue>,
+    {
+        Self::Choice(Choice::from(choice.into()).into())
+    }
+
+    /// Create a [`Value::Choice`] of a [`ChoiceEnum::Range`].
+    pub fn choice_range<T: CanonicalFixedSizedPod>(default: T, min: T, max: T) -> Self
+    where
+        Choice<T>: Into<ChoiceValue>,
+    {
+        Self::choice(ChoiceEnum::Range { default, min, max })
+    }
+
+    /// Create a [`Value::Choice`] of a [`ChoiceEnum::Step`].
+    pub fn choice_step<T: CanonicalFixedSizedPod>(default: T, min: T, max: T, step: T) -> Self
+    where
+        Choice<T>: Into<ChoiceValue>,
+    {
+        Self::choice(ChoiceEnum::Step {
+            default,
+            min,
+            max,
+            step,
+        })
+    }
+}
+
+impl From<ValueArray> for Value {
+    fn from(array: ValueArray) -> Self {
+        Self::ValueArray(array)
+    }
+}
+
 /// A typed choice.
 #[derive(Debug, Clone, PartialEq)]
 pub enum ChoiceValue {
@@ -1060,6 +1417,36 @@ pub enum ChoiceValue {
     Fd(Choice<Fd>),
 }
 
+macro_rules! impl_choice_value_from {
+    ($($variant:ident($type_:ty)),* $(,)?) => {
+        $(
+            impl From<Choice<$type_>> for ChoiceValue {
+                fn from(choice: Choice<$type_>) -> Self {
+                    Self::$variant(choice)
+                }
+            }
+        )*
+    };
+}
+
+impl_choice_value_from!(
+    Bool(bool),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    Id(Id),
+    Rectangle(Rectangle),
+    Fraction(Fraction),
+    Fd(Fd),
+);
+
+impl From<ChoiceValue> for Value {
+    fn from(choice: ChoiceValue) -> Self {
+        Self::Choice(choice)
+    }
+}
+
 /// An object from a pod.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Object {
@@ -1074,6 +1461,9 @@ pub struct Object {
 /// A macro for creating a new [`Object`] with properties.
 ///
 /// The macro accepts the object type, id and a list of properties, separated by commas.
+/// The list can end with `..properties`, where `properties` is anything implementing
+/// `IntoIterator<Item = Property>`, such as an existing `Vec<Property>`, to append those properties
+/// to the ones listed before.
 ///
 /// # Examples:
 /// Create an `Object`.
@@ -1095,16 +1485,59 @@ pub struct Object {
 ///     ),
 /// };
 /// ```
+///
+/// Merge properties assembled at runtime with literal ones.
+/// ```rust
+/// use libspa::pod::{object, property};
+///
+/// let extra = vec![property!(
+///     libspa::param::format::FormatProperties::VideoFormat,
+///     Id,
+///     libspa::param::video::VideoFormat::RGB
+/// )];
+///
+/// let pod_object = object!{
+///     libspa::utils::SpaTypes::ObjectParamFormat,
+///     libspa::param::ParamType::EnumFormat,
+///     property!(
+///         libspa::param::format::FormatProperties::MediaType,
+///         Id,
+///         libspa::param::format::MediaType::Video
+///     ),
+///     ..extra
+/// };
+///
+/// assert_eq!(pod_object.properties.len(), 2);
+/// ```
 #[doc(hidden)]
 #[macro_export]
 macro_rules! __object__ {
-    ($type_:expr, $id:expr, $($properties:expr),* $(,)?) => {
-        pipewire::spa::pod::Object {
+    (@properties ($type_:expr, $id:expr) [$($properties:expr,)*] .. $rest:expr $(,)?) => {{
+        let mut properties: Vec<$crate::pod::Property> = [ $( $properties, )* ].to_vec();
+        properties.extend($rest);
+
+        $crate::pod::Object {
+            type_: $type_.as_raw(),
+            id: $id.as_raw(),
+            properties,
+        }
+    }};
+
+    (@properties ($type_:expr, $id:expr) [$($properties:expr,)*] $property:expr $(, $($tail:tt)*)?) => {
+        $crate::__object__!(@properties ($type_, $id) [$($properties,)* $property,] $($($tail)*)?)
+    };
+
+    (@properties ($type_:expr, $id:expr) [$($properties:expr,)*]) => {
+        $crate::pod::Object {
             type_: $type_.as_raw(),
             id: $id.as_raw(),
             properties: [ $( $properties, )* ].to_vec(),
         }
     };
+
+    ($type_:expr, $id:expr $(, $($properties:tt)*)?) => {
+        $crate::__object__!(@properties ($type_, $id) [] $($($properties)*)?)
+    };
 }
 #[doc(inline)]
 pub use __object__ as object;
@@ -1128,6 +1561,13 @@ impl Property {
             flags: PropertyFlags::empty(),
         }
     }
+
+    /// Return the property with its flags replaced by `flags`.
+    #[must_use]
+    pub fn with_flags(mut self, flags: PropertyFlags) -> Self {
+        self.flags = flags;
+        self
+    }
 }
 
 bitflags! {
@@ -1171,34 +1611,62 @@ bitflags! {
 ///                 libspa::utils::`<type>`(default),
 ///                 libspa::utils::`<type>`(min),
 ///                 libspa::utils::`<type>`(max))
+///
+/// - properties!(libspa::format::FormatProperties::`<key>`, `<value>`), where the value can be
+///   built with shorthands such as [`Value::choice`] or [`Value::choice_range`]
+///
+/// The values of `Id` properties and choices can be of any type implementing [`IdEnum`](crate::utils::IdEnum),
+/// such as [`MediaType`](crate::param::format::MediaType) or [`Id`](crate::utils::Id).
+///
+/// Any of these forms can set the [`PropertyFlags`] of the property by adding `flags = <flags>`
+/// after the key:
+/// - properties!(libspa::format::FormatProperties::`<key>`, flags = `<flags>`, Id, `<value>`)
+///
+/// # Examples:
+/// ```rust
+/// use libspa::pod::{property, PropertyFlags};
+///
+/// let property = property!(
+///     libspa::param::format::FormatProperties::MediaType,
+///     flags = PropertyFlags::READONLY | PropertyFlags::MANDATORY,
+///     Id,
+///     libspa::param::format::MediaType::Video
+/// );
+///
+/// assert_eq!(property.flags, PropertyFlags::READONLY | PropertyFlags::MANDATORY);
+/// ```
 #[doc(hidden)]
 #[macro_export]
 macro_rules! __property__ {
+    ($key:expr, flags = $flags:expr, $($rest:tt)+) => {
+        $crate::pod::Property::with_flags($crate::__property__!($key, $($rest)+), $flags)
+    };
+
     ($key:expr, $value:expr) => {
-        pipewire::spa::pod::Property {
+        $crate::pod::Property {
             key: $key.as_raw(),
-            flags: pipewire::spa::pod::PropertyFlags::empty(),
+            flags: $crate::pod::PropertyFlags::empty(),
             value: $value,
         }
     };
 
     ($key:expr, Id, $value:expr) => {
-        pipewire::spa::pod::property!($key, pipewire::spa::pod::Value::Id(pipewire::spa::utils::Id($value.as_raw())))
+        $crate::__property__!($key, $crate::pod::Value::Id($crate::utils::IdEnum::to_id(&$value)))
     };
 
     ($key:expr, $type_:ident, $value:expr) => {
-        pipewire::spa::pod::property!($key, pipewire::spa::pod::Value::$type_($value))
+        $crate::__property__!($key, $crate::pod::Value::$type_($value))
     };
 
     ($key:expr, Choice, Enum, Id, $default:expr, $($alternative:expr),+ $(,)?) => {
-        pipewire::spa::pod::property!(
+        $crate::__property__!(
             $key,
-            pipewire::spa::pod::Value::Choice(pipewire::spa::pod::ChoiceValue::Id(
-                pipewire::spa::utils::Choice::<pipewire::spa::utils::Id>(
-                    pipewire::spa::utils::ChoiceFlags::empty(),
-                    pipewire::spa::utils::ChoiceEnum::<pipewire::spa::utils::Id>::Enum {
-                        default: pipewire::spa::utils::Id($default.as_raw()),
-                        alternatives: [ $( pipewire::spa::utils::Id($alternative.as_raw()), )+ ].to_vec()
+            $crate::pod::Value::Choice($crate::pod::ChoiceValue::Id(
+                $crate::utils::Choice::<$crate::utils::Id>(
+                    $crate::utils::ChoiceFlags::empty(),
+                    $crate::utils::ChoiceEnum::<$crate::utils::Id>::Enum {
+                        default: $crate::utils::IdEnum::to_id(&$default),
+                        alternatives: [ $( $crate::utils::IdEnum::to_id(&$alternative), )+ ].to_vec()
                     }
                 )
             ))
@@ -1206,12 +1674,12 @@ macro_rules! __property__ {
     };
 
     ($key:expr, Choice, Enum, $type_:ident, $default:expr, $($alternative:expr),+ $(,)?) => {
-        pipewire::spa::pod::property!(
+        $crate::__property__!(
             $key,
-            pipewire::spa::pod::Value::Choice(pipewire::spa::pod::ChoiceValue::$type_(
-                pipewire::spa::utils::Choice::<pipewire::spa::utils::$type_>(
-                    pipewire::spa::utils::ChoiceFlags::empty(),
-                    pipewire::spa::utils::ChoiceEnum::<pipewire::spa::utils::$type_>::Enum {
+            $crate::pod::Value::Choice($crate::pod::ChoiceValue::$type_(
+                $crate::utils::Choice::<$crate::utils::$type_>(
+                    $crate::utils::ChoiceFlags::empty(),
+                    $crate::utils::ChoiceEnum::<$crate::utils::$type_>::Enum {
                         default: $default,
                         alternatives: [ $( $alternative, )+ ].to_vec()
                     }
@@ -1221,12 +1689,12 @@ macro_rules! __property__ {
     };
 
     ($key:expr, Choice, Flags, $type_:ident, $default:expr, $($alternative:expr),+ $(,)?) => {
-        pipewire::spa::pod::property!(
+        $crate::__property__!(
             $key,
-            pipewire::spa::pod::Value::Choice(pipewire::spa::pod::ChoiceValue::$type_(
-                pipewire::spa::utils::Choice::<pipewire::spa::utils::$type_>(
-                    pipewire::spa::utils::ChoiceFlags::empty(),
-                    pipewire::spa::utils::ChoiceEnum::<pipewire::spa::utils::$type_>::Flags {
+            $crate::pod::Value::Choice($crate::pod::ChoiceValue::$type_(
+                $crate::utils::Choice::<$crate::utils::$type_>(
+                    $crate::utils::ChoiceFlags::empty(),
+                    $crate::utils::ChoiceEnum::<$crate::utils::$type_>::Flags {
                         default: $default,
                         flags: [ $( $alternative, )+ ].to_vec()
                     }
@@ -1236,12 +1704,12 @@ macro_rules! __property__ {
     };
 
     ($key:expr, Choice, Step, $type_:ident, $default:expr, $min:expr, $max:expr, $step:expr) => {
-        pipewire::spa::pod::property!(
+        $crate::__property__!(
             $key,
-            pipewire::spa::pod::Value::Choice(pipewire::spa::pod::ChoiceValue::$type_(
-                pipewire::spa::utils::Choice::<pipewire::spa::utils::$type_>(
-                    pipewire::spa::utils::ChoiceFlags::empty(),
-                    pipewire::spa::utils::ChoiceEnum::<pipewire::spa::utils::$type_>::Step {
+            $crate::pod::Value::Choice($crate::pod::ChoiceValue::$type_(
+                $crate::utils::Choice::<$crate::utils::$type_>(
+                    $crate::utils::ChoiceFlags::empty(),
+                    $crate::utils::ChoiceEnum::<$crate::utils::$type_>::Step {
                         default: $default,
                         min: $min,
                         max: $max,
@@ -1253,12 +1721,12 @@ macro_rules! __property__ {
     };
 
     ($key:expr, Choice, Range, $type_:ident, $default:expr, $min:expr, $max:expr) => {
-        pipewire::spa::pod::property!(
+        $crate::__property__!(
             $key,
-            pipewire::spa::pod::Value::Choice(pipewire::spa::pod::ChoiceValue::$type_(
-                pipewire::spa::utils::Choice::<pipewire::spa::utils::$type_>(
-                    pipewire::spa::utils::ChoiceFlags::empty(),
-                    pipewire::spa::utils::ChoiceEnum::<pipewire::spa::utils::$type_>::Range {
+            $crate::pod::Value::Choice($crate::pod::ChoiceValue::$type_(
+                $crate::utils::Choice::<$crate::utils::$type_>(
+                    $crate::utils::ChoiceFlags::empty(),
+                    $crate::utils::ChoiceEnum::<$crate::utils::$type_>::Range {
                         default: $default,
                         min: $min,
                         max: $max,
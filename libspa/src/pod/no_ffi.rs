@@ -0,0 +1,238 @@
+//! Pure Rust implementation of the [`Pod`] accessors, used with the `no-ffi-pod` feature.
+//!
+//! These follow the static inline functions of `spa/pod/iter.h` but only read the bytes of the pod,
+//! so that they can run under Miri or fuzzers.
+
+use std::{
+    ffi::{c_void, CStr},
+    mem::{align_of, size_of},
+    os::fd::RawFd,
+};
+
+use nix::errno::Errno;
+
+use super::{
+    iter::{next_pod, PodProp},
+    Pod,
+};
+use crate::utils::{Fraction, Id, Rectangle, SpaTypes};
+
+impl Pod {
+    /// The body of the pod, without its header.
+    fn body(&self) -> &[u8] {
+        &self.as_bytes()[size_of::<spa_sys::spa_pod>()..]
+    }
+
+    /// Whether the pod has type `type_` and a body of at least `min_size` bytes.
+    fn is_type(&self, type_: SpaTypes, min_size: usize) -> bool {
+        self.type_() == type_ && self.body().len() >= min_size
+    }
+
+    /// Read the start of the body if the pod has type `type_`.
+    fn read_body<const N: usize>(&self, type_: SpaTypes) -> Result<[u8; N], Errno> {
+        if !self.is_type(type_, N) {
+            return Err(Errno::EINVAL);
+        }
+
+        Ok(self.body()[..N].try_into().unwrap())
+    }
+
+    fn read_u32_pair(&self, type_: SpaTypes) -> Result<(u32, u32), Errno> {
+        let body: [u8; 8] = self.read_body(type_)?;
+        let first = u32::from_ne_bytes(body[..4].try_into().unwrap());
+        let second = u32::from_ne_bytes(body[4..].try_into().unwrap());
+
+        Ok((first, second))
+    }
+
+    pub fn is_none(&self) -> bool {
+        self.is_type(SpaTypes::None, 0)
+    }
+
+    pub fn is_bool(&self) -> bool {
+        self.is_type(SpaTypes::Bool, size_of::<i32>())
+    }
+
+    pub fn get_bool(&self) -> Result<bool, Errno> {
+        let body = self.read_body(SpaTypes::Bool)?;
+        Ok(i32::from_ne_bytes(body) != 0)
+    }
+
+    pub fn is_id(&self) -> bool {
+        self.is_type(SpaTypes::Id, size_of::<u32>())
+    }
+
+    pub fn get_id(&self) -> Result<Id, Errno> {
+        let body = self.read_body(SpaTypes::Id)?;
+        Ok(Id(u32::from_ne_bytes(body)))
+    }
+
+    pub fn is_int(&self) -> bool {
+        self.is_type(SpaTypes::Int, size_of::<i32>())
+    }
+
+    pub fn get_int(&self) -> Result<i32, Errno> {
+        let body = self.read_body(SpaTypes::Int)?;
+        Ok(i32::from_ne_bytes(body))
+    }
+
+    pub fn is_long(&self) -> bool {
+        self.is_type(SpaTypes::Long, size_of::<i64>())
+    }
+
+    pub fn get_long(&self) -> Result<i64, Errno> {
+        let body = self.read_body(SpaTypes::Long)?;
+        Ok(i64::from_ne_bytes(body))
+    }
+
+    pub fn is_float(&self) -> bool {
+        self.is_type(SpaTypes::Float, size_of::<f32>())
+    }
+
+    pub fn get_float(&self) -> Result<f32, Errno> {
+        let body = self.read_body(SpaTypes::Float)?;
+        Ok(f32::from_ne_bytes(body))
+    }
+
+    pub fn is_double(&self) -> bool {
+        self.is_type(SpaTypes::Double, size_of::<f64>())
+    }
+
+    pub fn get_double(&self) -> Result<f64, Errno> {
+        let body = self.read_body(SpaTypes::Double)?;
+        Ok(f64::from_ne_bytes(body))
+    }
+
+    pub fn is_string(&self) -> bool {
+        self.is_type(SpaTypes::String, 1) && self.body().last() == Some(&0)
+    }
+
+    pub fn get_cstr(&self) -> Result<&CStr, Errno> {
+        if !self.is_string() {
+            return Err(Errno::EINVAL);
+        }
+
+        let body = self.body();
+        let end = body.iter().position(|&b| b == 0).unwrap();
+        Ok(CStr::from_bytes_with_nul(&body[..=end]).unwrap())
+    }
+
+    pub fn is_bytes(&self) -> bool {
+        self.is_type(SpaTypes::Bytes, 0)
+    }
+
+    pub fn get_bytes(&self) -> Result<&[u8], Errno> {
+        if !self.is_bytes() {
+            return Err(Errno::EINVAL);
+        }
+
+        Ok(self.body())
+    }
+
+    pub fn is_pointer(&self) -> bool {
+        self.is_type(
+            SpaTypes::Pointer,
+            size_of::<spa_sys::spa_pod_pointer_body>(),
+        )
+    }
+
+    pub fn get_pointer(&self) -> Result<(*const c_void, Id), Errno> {
+        if !self.is_pointer() {
+            return Err(Errno::EINVAL);
+        }
+
+        let body = self.body();
+        let type_ = u32::from_ne_bytes(body[..4].try_into().unwrap());
+        // The pointer is stored after the type and 4 bytes of padding.
+        let value = usize::from_ne_bytes(body[8..8 + size_of::<usize>()].try_into().unwrap());
+
+        Ok((value as *const c_void, Id(type_)))
+    }
+
+    pub fn is_fd(&self) -> bool {
+        self.is_type(SpaTypes::Fd, size_of::<i64>())
+    }
+
+    pub fn get_fd(&self) -> Result<RawFd, Errno> {
+        let body = self.read_body(SpaTypes::Fd)?;
+        i64::from_ne_bytes(body)
+            .try_into()
+            .map_err(|_| Errno::EINVAL)
+    }
+
+    pub fn is_rectangle(&self) -> bool {
+        self.is_type(SpaTypes::Rectangle, size_of::<Rectangle>())
+    }
+
+    pub fn get_rectangle(&self) -> Result<Rectangle, Errno> {
+        let (width, height) = self.read_u32_pair(SpaTypes::Rectangle)?;
+        Ok(Rectangle { width, height })
+    }
+
+    pub fn is_fraction(&self) -> bool {
+        self.is_type(SpaTypes::Fraction, size_of::<Fraction>())
+    }
+
+    pub fn get_fraction(&self) -> Result<Fraction, Errno> {
+        let (num, denom) = self.read_u32_pair(SpaTypes::Fraction)?;
+        Ok(Fraction { num, denom })
+    }
+
+    pub fn is_bitmap(&self) -> bool {
+        self.is_type(SpaTypes::Bitmap, size_of::<u8>())
+    }
+
+    pub fn is_array(&self) -> bool {
+        self.is_type(SpaTypes::Array, size_of::<spa_sys::spa_pod_array_body>())
+    }
+
+    pub fn is_choice(&self) -> bool {
+        self.is_type(SpaTypes::Choice, size_of::<spa_sys::spa_pod_choice_body>())
+    }
+
+    pub fn is_struct(&self) -> bool {
+        self.is_type(SpaTypes::Struct, 0)
+    }
+
+    pub fn is_object(&self) -> bool {
+        self.is_type(SpaTypes::Object, size_of::<spa_sys::spa_pod_object_body>())
+    }
+
+    pub fn is_sequence(&self) -> bool {
+        self.is_type(
+            SpaTypes::Sequence,
+            size_of::<spa_sys::spa_pod_sequence_body>(),
+        )
+    }
+
+    pub fn from_data(data: &[u8], offset: usize, size: usize) -> Option<&Self> {
+        let bytes = data.get(offset..offset.checked_add(size)?)?;
+        if bytes.as_ptr() as usize % align_of::<spa_sys::spa_pod>() != 0 {
+            return None;
+        }
+
+        next_pod(bytes).map(|(pod, _)| pod)
+    }
+
+    pub fn is_inside(&self, body: &[u8]) -> bool {
+        let start = body.as_ptr() as usize;
+        let pod = self.as_ptr() as usize;
+
+        pod >= start && pod + self.as_bytes().len() <= start + body.len()
+    }
+
+    pub fn next_in<'a>(&self, body: &'a [u8]) -> Option<&'a Self> {
+        if !self.is_inside(body) {
+            return None;
+        }
+
+        // Pods are padded to 8 bytes.
+        let offset = self.as_ptr() as usize - body.as_ptr() as usize;
+        let next = offset + ((self.as_bytes().len() + 7) & !7);
+        next_pod(body.get(next..)?).map(|(pod, _)| pod)
+    }
+
+    pub fn find_prop(&self, key: u32) -> Option<PodProp<'_>> {
+        self.object_props().ok()?.find(|prop| prop.key == key)
+    }
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: MIT
 
 use std::{
+    borrow::Cow,
     ffi::{c_char, c_double, c_float, c_void, CStr},
     marker::PhantomData,
     mem::MaybeUninit,
@@ -42,7 +43,7 @@ impl<'d> Parser<'d> {
     pub fn from_pod(pod: &'d crate::pod::Pod) -> Self {
         unsafe {
             let mut parser: MaybeUninit<spa_sys::spa_pod_parser> = MaybeUninit::uninit();
-            spa_sys::spa_pod_parser_pod(parser.as_mut_ptr(), pod.as_raw_ptr());
+            spa_sys::spa_pod_parser_pod(parser.as_mut_ptr(), pod.as_ptr());
             Self {
                 parser: parser.assume_init(),
                 data: PhantomData,
@@ -225,6 +226,18 @@ impl<'d> Parser<'d> {
         }
     }
 
+    /// Get a string, failing with `EINVAL` if it is not valid UTF-8.
+    ///
+    /// The string pod is consumed even if it is not valid UTF-8.
+    pub fn get_str(&mut self) -> Result<&'d str, Errno> {
+        self.get_string_raw()?.to_str().map_err(|_| Errno::EINVAL)
+    }
+
+    /// Get a string, with invalid UTF-8 sequences replaced by `U+FFFD`.
+    pub fn get_str_lossy(&mut self) -> Result<Cow<'d, str>, Errno> {
+        Ok(self.get_string_raw()?.to_string_lossy())
+    }
+
     pub fn get_bytes(&mut self) -> Result<&'d [u8], Errno> {
         unsafe {
             let mut bytes: MaybeUninit<*const u8> = MaybeUninit::uninit();
@@ -356,6 +369,152 @@ impl<'d> Parser<'d> {
             Err(Errno::from_i32(-res))
         }
     }
+
+    /// Enter the struct pod at the current position of the parser.
+    ///
+    /// The returned [`StructReader`] dereferences to the parser, so the fields of the struct can be parsed through it.
+    /// The struct frame is popped again when the reader is [finished](`StructReader::finish`) or dropped.
+    pub fn struct_reader(&mut self) -> Result<StructReader<'_, 'd>, Errno> {
+        let frame = PushedFrame::push(self, |parser, frame| unsafe { parser.push_struct(frame) })?;
+
+        Ok(StructReader { frame })
+    }
+
+    /// Enter the object pod of type `type_` at the current position of the parser.
+    ///
+    /// Properties of the object can be looked up by key using the returned [`ObjectReader`].
+    /// The object frame is popped again when the reader is [finished](`ObjectReader::finish`) or dropped.
+    pub fn object_reader(&mut self, type_: u32) -> Result<ObjectReader<'_, 'd>, Errno> {
+        let mut id = Id(0);
+        let frame = PushedFrame::push(self, |parser, frame| {
+            id = unsafe { parser.push_object(frame, type_)? };
+            Ok(())
+        })?;
+
+        Ok(ObjectReader { frame, id })
+    }
+}
+
+/// A frame that has been pushed to a [`Parser`] and is popped again when dropped.
+struct PushedFrame<'p, 'd> {
+    parser: &'p mut Parser<'d>,
+    // The frame is boxed so that it does not move while it is pushed, even if the guard holding it is moved.
+    frame: Box<spa_sys::spa_pod_frame>,
+    popped: bool,
+}
+
+impl<'p, 'd> PushedFrame<'p, 'd> {
+    fn push<F>(parser: &'p mut Parser<'d>, push: F) -> Result<Self, Errno>
+    where
+        F: FnOnce(&mut Parser<'d>, &mut MaybeUninit<spa_sys::spa_pod_frame>) -> Result<(), Errno>,
+    {
+        let mut frame: Box<MaybeUninit<spa_sys::spa_pod_frame>> = Box::new(MaybeUninit::uninit());
+        push(parser, &mut frame)?;
+
+        Ok(Self {
+            parser,
+            // Safety: The push function returned `Ok`, so the frame has been initialized.
+            frame: unsafe { Box::from_raw(Box::into_raw(frame).cast()) },
+            popped: false,
+        })
+    }
+
+    fn pop(&mut self) -> Result<(), Errno> {
+        if self.popped {
+            return Ok(());
+        }
+        self.popped = true;
+
+        // Safety: Any frame pushed after this one is owned by a guard borrowing this one,
+        // so it has already been popped.
+        unsafe { self.parser.pop(&mut self.frame) }
+    }
+
+    /// Get the pod the frame was pushed for.
+    fn pod(&self) -> &'d crate::pod::Pod {
+        unsafe {
+            let pod = spa_sys::spa_pod_parser_frame(
+                self.parser.as_raw_ptr(),
+                std::ptr::addr_of!(*self.frame).cast_mut(),
+            );
+            crate::pod::Pod::from_raw(pod)
+        }
+    }
+}
+
+impl Drop for PushedFrame<'_, '_> {
+    fn drop(&mut self) {
+        let _ = self.pop();
+    }
+}
+
+/// A guard for parsing the fields of a struct pod, obtained from [`Parser::struct_reader`].
+///
+/// Fields are parsed in order using the methods of the [`Parser`] this dereferences to.
+pub struct StructReader<'p, 'd> {
+    frame: PushedFrame<'p, 'd>,
+}
+
+impl<'p, 'd> StructReader<'p, 'd> {
+    /// Leave the struct, returning any error that occurred while popping its frame.
+    pub fn finish(mut self) -> Result<(), Errno> {
+        self.frame.pop()
+    }
+}
+
+impl<'p, 'd> std::ops::Deref for StructReader<'p, 'd> {
+    type Target = Parser<'d>;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.frame.parser
+    }
+}
+
+impl<'p, 'd> std::ops::DerefMut for StructReader<'p, 'd> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut *self.frame.parser
+    }
+}
+
+/// A guard for parsing the properties of an object pod, obtained from [`Parser::object_reader`].
+pub struct ObjectReader<'p, 'd> {
+    frame: PushedFrame<'p, 'd>,
+    id: Id,
+}
+
+impl<'p, 'd> ObjectReader<'p, 'd> {
+    /// The id of the object.
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    /// Find the value of the property with the provided key.
+    ///
+    /// Returns `None` if the object has no such property.
+    pub fn find_prop(&self, key: u32) -> Option<&'d crate::pod::Pod> {
+        unsafe {
+            let object: *const spa_sys::spa_pod_object = self.frame.pod().as_ptr().cast();
+            let prop = spa_sys::spa_pod_object_find_prop(object, std::ptr::null(), key);
+
+            if prop.is_null() {
+                None
+            } else {
+                Some(crate::pod::Pod::from_raw(std::ptr::addr_of!((*prop).value)))
+            }
+        }
+    }
+
+    /// Variant of [`Self::find_prop`] returning an error if the property is missing.
+    ///
+    /// Like `spa_pod_parser_get()`, `ESRCH` is returned for missing properties.
+    pub fn get_prop(&self, key: u32) -> Result<&'d crate::pod::Pod, Errno> {
+        self.find_prop(key).ok_or(Errno::ESRCH)
+    }
+
+    /// Leave the object, returning any error that occurred while popping its frame.
+    pub fn finish(mut self) -> Result<(), Errno> {
+        self.frame.pop()
+    }
 }
 
 /// Convenience macro to parse values from a spa pod using a spa pod parser.
@@ -385,6 +544,33 @@ impl<'d> Parser<'d> {
 ///         Bytes(<&mut &[u8]),
 ///     }
 /// );
+/// parser_get!(<&mut libspa::pod::parser::Parser>,
+///     Object(
+///         <type as u32>,
+///         <&mut libspa::utils::Id>
+///     ) {
+///         // 0 to n properties of format
+///         // `<key as u32> => <value>`
+///         // e.g.
+///           0 => Bool(<&mut bool>),
+///         313 => Int(<&mut i32>),
+///     }
+/// );
+/// ```
+///
+/// Object properties are looked up by their key, so they may be listed in any order.
+/// A property that is missing from the object makes parsing fail with `ESRCH`.
+///
+/// Any non-container value can also be parsed as optional by wrapping it in `Option`,
+/// in which case a `None` pod or a missing object property results in `None`:
+///
+/// ```ignore
+/// parser_get!(<&mut libspa::pod::parser::Parser>, Option(Int(<&mut Option<i32>>)));
+/// parser_get!(<&mut libspa::pod::parser::Parser>,
+///     Object(<type as u32>, <&mut libspa::utils::Id>) {
+///         0 => Option(Bool(<&mut Option<bool>>)),
+///     }
+/// );
 /// ```
 ///
 /// # Returns
@@ -394,6 +580,42 @@ impl<'d> Parser<'d> {
 /// Otherwise, the `Err(Errno)` from the point where parsing failed is returned, and the rest of the values are not parsed.
 #[macro_export]
 macro_rules! __parser_get__ {
+    // Internal rule parsing the value of a single object property.
+    (@prop $reader:expr, $key:expr, Option($type_:ident($val:expr))) => {
+        match $crate::pod::parser::ObjectReader::find_prop($reader, $key) {
+            Some(pod) => {
+                let mut parser = $crate::pod::parser::Parser::from_pod(pod);
+                $crate::__parser_get__!(&mut parser, Option($type_($val)))
+            }
+            None => {
+                let val: &mut ::std::option::Option<_> = $val;
+                *val = None;
+                Ok(())
+            }
+        }
+    };
+    (@prop $reader:expr, $key:expr, $value_type:tt $value:tt) => {
+        match $crate::pod::parser::ObjectReader::get_prop($reader, $key) {
+            Ok(pod) => {
+                let mut parser = $crate::pod::parser::Parser::from_pod(pod);
+                $crate::__parser_get__!(&mut parser, $value_type $value)
+            }
+            Err(err) => Err(err),
+        }
+    };
+    // Internal rules mapping a pod type to the parser method returning its value.
+    (@value $parser:expr, Bool) => { $crate::pod::parser::Parser::get_bool($parser) };
+    (@value $parser:expr, Id) => { $crate::pod::parser::Parser::get_id($parser) };
+    (@value $parser:expr, Int) => { $crate::pod::parser::Parser::get_int($parser) };
+    (@value $parser:expr, Long) => { $crate::pod::parser::Parser::get_long($parser) };
+    (@value $parser:expr, Float) => { $crate::pod::parser::Parser::get_float($parser) };
+    (@value $parser:expr, Double) => { $crate::pod::parser::Parser::get_double($parser) };
+    (@value $parser:expr, Bytes) => { $crate::pod::parser::Parser::get_bytes($parser) };
+    (@value $parser:expr, Pointer) => { $crate::pod::parser::Parser::get_pointer($parser) };
+    (@value $parser:expr, Fd) => { $crate::pod::parser::Parser::get_fd($parser) };
+    (@value $parser:expr, Rectangle) => { $crate::pod::parser::Parser::get_rectangle($parser) };
+    (@value $parser:expr, Fraction) => { $crate::pod::parser::Parser::get_fraction($parser) };
+    (@value $parser:expr, Pod) => { $crate::pod::parser::Parser::get_pod($parser) };
     ($parser:expr, Bool($val:expr)) => {
         {
             let val: &mut bool = $val;
@@ -534,14 +756,52 @@ macro_rules! __parser_get__ {
             unsafe { $crate::pod::parser::Parser::pop($parser, frame.assume_init_mut()) }
         }
     };
-    // TODO: Object
-    // TODO: ($parser:expr, Option( $type_:tt $val:tt )) or similar for optional values
+    (
+        $parser:expr,
+        Object($type_:expr, $id:expr $(,)?) {
+            $( $key:expr => $value_type:tt $value:tt ),* $(,)?
+        }
+    ) => {
+        'outer: {
+            let id: &mut $crate::utils::Id = $id;
+            let reader = match $crate::pod::parser::Parser::object_reader($parser, $type_) {
+                Ok(reader) => reader,
+                Err(err) => break 'outer Err(err),
+            };
+            *id = reader.id();
+
+            $(
+                let res = $crate::__parser_get__!(@prop &reader, $key, $value_type $value);
+                if res.is_err() {
+                    break 'outer res;
+                }
+            )*
+
+            reader.finish()
+        }
+    };
+    ($parser:expr, Option($type_:ident($val:expr))) => {
+        {
+            let val: &mut ::std::option::Option<_> = $val;
+            let pod = $crate::pod::parser::Parser::current($parser);
+            if !pod.is_null() && unsafe { $crate::pod::Pod::from_raw(pod) }.is_none() {
+                unsafe { $crate::pod::parser::Parser::advance($parser, pod) };
+                *val = None;
+                Ok(())
+            } else {
+                $crate::__parser_get__!(@value $parser, $type_).map(|value| {
+                    *val = Some(value);
+                })
+            }
+        }
+    };
 }
 pub use __parser_get__ as parser_get;
 
 #[cfg(test)]
 mod tests {
     use super::{parser_get, Parser};
+    use nix::errno::Errno;
 
     // FIXME: The way we construct raw pods here is rather crude and error-prone.
     //        Maybe replace it with the pod builder in the future, and share the tests with it.
@@ -569,6 +829,24 @@ mod tests {
         assert!(bool);
     }
 
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn parse_str() {
+        let pod: Vec<u8> = [
+            &4u32.to_ne_bytes(), // string body size
+            &8u32.to_ne_bytes(), // string type
+            &[b'a', 0xff, b'b', 0],
+            &[0, 0, 0, 0], // padding
+        ]
+        .into_iter()
+        .flatten()
+        .copied()
+        .collect();
+
+        assert_eq!(Parser::new(&pod).get_str(), Err(Errno::EINVAL));
+        assert_eq!(Parser::new(&pod).get_str_lossy().unwrap(), "a\u{fffd}b");
+    }
+
     #[test]
     #[cfg_attr(miri, ignore)]
     fn parse_empty_struct() {
@@ -696,4 +974,162 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn struct_reader() {
+        let pod: Vec<u8> = [
+            &16u32.to_ne_bytes(),  // body size: 1 child * 16 bytes per child
+            &14u32.to_ne_bytes(),  // struct type
+            &4u32.to_ne_bytes(),   // int body size
+            &4u32.to_ne_bytes(),   // int type
+            &313i32.to_ne_bytes(), // int 313
+            &[0, 0, 0, 0],         // padding
+        ]
+        .into_iter()
+        .flatten()
+        .copied()
+        .collect();
+
+        let mut parser = Parser::new(&pod);
+
+        let mut reader = parser.struct_reader().unwrap();
+        assert_eq!(reader.get_int(), Ok(313));
+        assert!(reader.finish().is_ok());
+    }
+
+    fn object_pod() -> Vec<u8> {
+        let pod: &[&[u8]] = &[
+            &56u32.to_ne_bytes(), // body size: 8 bytes object body + (2 props * 24 bytes per prop) = 56 bytes
+            &15u32.to_ne_bytes(), // object type
+            &spa_sys::SPA_TYPE_OBJECT_Props.to_ne_bytes(), // object body type
+            &spa_sys::SPA_PARAM_Props.to_ne_bytes(), // object body id
+            &1u32.to_ne_bytes(),  // prop key
+            &0u32.to_ne_bytes(),  // prop flags
+            &4u32.to_ne_bytes(),  // bool body size
+            &2u32.to_ne_bytes(),  // bool type
+            &1u32.to_ne_bytes(),  // bool "true"
+            &[0, 0, 0, 0],        // padding
+            &2u32.to_ne_bytes(),  // prop key
+            &0u32.to_ne_bytes(),  // prop flags
+            &4u32.to_ne_bytes(),  // int body size
+            &4u32.to_ne_bytes(),  // int type
+            &313i32.to_ne_bytes(), // int 313
+            &[0, 0, 0, 0],        // padding
+        ];
+        pod.iter().flat_map(|f| (*f)).copied().collect()
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn parse_object() {
+        let pod = object_pod();
+        let mut parser = Parser::new(&pod);
+
+        let mut id = crate::utils::Id(0);
+        let mut bool = false;
+        let mut int = 0i32;
+
+        // Properties are looked up by key, so the order does not matter.
+        let res = parser_get!(
+            &mut parser,
+            Object(spa_sys::SPA_TYPE_OBJECT_Props, &mut id) {
+                2 => Int(&mut int),
+                1 => Bool(&mut bool),
+            }
+        );
+
+        assert!(res.is_ok());
+        assert_eq!(id, crate::utils::Id(spa_sys::SPA_PARAM_Props));
+        assert!(bool);
+        assert_eq!(int, 313);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn parse_object_missing_prop() {
+        let pod = object_pod();
+        let mut parser = Parser::new(&pod);
+
+        let mut id = crate::utils::Id(0);
+        let mut int = 0i32;
+
+        let res = parser_get!(
+            &mut parser,
+            Object(spa_sys::SPA_TYPE_OBJECT_Props, &mut id) {
+                3 => Int(&mut int),
+            }
+        );
+
+        assert_eq!(res, Err(Errno::ESRCH));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn object_reader_wrong_type() {
+        let pod = object_pod();
+        let mut parser = Parser::new(&pod);
+
+        assert!(parser
+            .object_reader(spa_sys::SPA_TYPE_OBJECT_Format)
+            .is_err());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn parse_optional() {
+        let pod: Vec<u8> = [
+            &24u32.to_ne_bytes(), // body size: (1 child * 16 bytes) + (1 child * 8 bytes) = 24 bytes
+            &14u32.to_ne_bytes(), // struct type
+            &4u32.to_ne_bytes(),  // int body size
+            &4u32.to_ne_bytes(),  // int type
+            &313i32.to_ne_bytes(), // int 313
+            &[0, 0, 0, 0],        // padding
+            &0u32.to_ne_bytes(),  // none body size
+            &1u32.to_ne_bytes(),  // none type
+        ]
+        .into_iter()
+        .flatten()
+        .copied()
+        .collect();
+
+        let mut parser = Parser::new(&pod);
+        let mut int = None;
+        let mut none = Some(0i32);
+
+        let res = parser_get!(
+            &mut parser,
+            Struct {
+                Option(Int(&mut int)),
+                Option(Int(&mut none)),
+            }
+        );
+
+        assert!(res.is_ok());
+        assert_eq!(int, Some(313));
+        assert_eq!(none, None);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn parse_object_optional_prop() {
+        let pod = object_pod();
+        let mut parser = Parser::new(&pod);
+
+        let mut id = crate::utils::Id(0);
+        let mut int = None;
+        let mut missing = Some(0i32);
+
+        let res = parser_get!(
+            &mut parser,
+            Object(spa_sys::SPA_TYPE_OBJECT_Props, &mut id) {
+                2 => Option(Int(&mut int)),
+                3 => Option(Int(&mut missing)),
+            }
+        );
+
+        assert!(res.is_ok());
+        assert_eq!(int, Some(313));
+        assert_eq!(missing, None);
+    }
 }
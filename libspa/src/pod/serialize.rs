@@ -10,6 +10,7 @@
 //! information on how to do that.
 
 use std::{
+    collections::HashSet,
     convert::TryInto,
     ffi::CString,
     io::{Seek, SeekFrom, Write},
@@ -203,6 +204,7 @@ impl PodSerialize for Value {
                 ChoiceValue::Fd(choice) => serializer.serialize_choice(choice),
             },
             Value::Pointer(type_, pointer) => serializer.serialize_pointer(*type_, *pointer),
+            Value::Other { type_, bytes } => serializer.serialize_raw(*type_, bytes),
         }
     }
 }
@@ -226,6 +228,90 @@ impl<P: FixedSizedPod> PodSerialize for [P] {
     }
 }
 
+// Serialize into an `Array` pod.
+impl<P: FixedSizedPod> PodSerialize for &[P] {
+    fn serialize<O: Write + Seek>(
+        &self,
+        serializer: PodSerializer<O>,
+    ) -> Result<SerializeSuccess<O>, GenError> {
+        (**self).serialize(serializer)
+    }
+}
+
+// Serialize into an `Array` pod.
+impl<P: FixedSizedPod, const N: usize> PodSerialize for [P; N] {
+    fn serialize<O: Write + Seek>(
+        &self,
+        serializer: PodSerializer<O>,
+    ) -> Result<SerializeSuccess<O>, GenError> {
+        self.as_slice().serialize(serializer)
+    }
+}
+
+// Serialize into an `Array` pod.
+impl<P: FixedSizedPod> PodSerialize for Vec<P> {
+    fn serialize<O: Write + Seek>(
+        &self,
+        serializer: PodSerializer<O>,
+    ) -> Result<SerializeSuccess<O>, GenError> {
+        self.as_slice().serialize(serializer)
+    }
+}
+
+// Serialize into an `Array` pod, in the iteration order of the set.
+impl<P: FixedSizedPod, S> PodSerialize for HashSet<P, S> {
+    fn serialize<O: Write + Seek>(
+        &self,
+        serializer: PodSerializer<O>,
+    ) -> Result<SerializeSuccess<O>, GenError> {
+        let mut arr_serializer = serializer.serialize_array(
+            self.len()
+                .try_into()
+                .expect("Array length does not fit in a u32"),
+        )?;
+
+        for element in self.iter() {
+            arr_serializer.serialize_element(element)?;
+        }
+
+        arr_serializer.end()
+    }
+}
+
+// Serialize into a `String` pod.
+impl PodSerialize for String {
+    fn serialize<O: Write + Seek>(
+        &self,
+        serializer: PodSerializer<O>,
+    ) -> Result<SerializeSuccess<O>, GenError> {
+        serializer.serialize_string(self)
+    }
+}
+
+// Serialize into a `Struct` pod of `String` pods, as arrays can only hold fixed sized pods.
+impl PodSerialize for [String] {
+    fn serialize<O: Write + Seek>(
+        &self,
+        serializer: PodSerializer<O>,
+    ) -> Result<SerializeSuccess<O>, GenError> {
+        let mut struct_serializer = serializer.serialize_struct()?;
+        for string in self {
+            struct_serializer.serialize_field(string.as_str())?;
+        }
+        struct_serializer.end()
+    }
+}
+
+// Serialize into a `Struct` pod of `String` pods.
+impl PodSerialize for Vec<String> {
+    fn serialize<O: Write + Seek>(
+        &self,
+        serializer: PodSerializer<O>,
+    ) -> Result<SerializeSuccess<O>, GenError> {
+        self.as_slice().serialize(serializer)
+    }
+}
+
 impl<T> PodSerialize for (u32, *const T) {
     fn serialize<O: Write + Seek>(
         &self,
@@ -364,6 +450,12 @@ impl<O: Write + Seek> PodSerializer<O> {
         self.write_pod(bytes.len(), spa_sys::SPA_TYPE_Bytes, slice(bytes))
     }
 
+    /// Serialize a pod of type `type_` from its raw body, such as a pod of a type unknown to
+    /// these bindings.
+    pub fn serialize_raw(self, type_: u32, body: &[u8]) -> Result<SerializeSuccess<O>, GenError> {
+        self.write_pod(body.len(), type_, slice(body))
+    }
+
     /// Begin serializing an `Array` pod with exactly `length` elements.
     pub fn serialize_array<P: FixedSizedPod>(
         mut self,
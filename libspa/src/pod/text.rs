@@ -0,0 +1,803 @@
+//! A textual representation of pods, to print them for humans and parse human-authored params.
+//!
+//! The syntax is close to the one accepted by `pw-cli set-param`:
+//!
+//! | Pod | Text |
+//! |-----|------|
+//! | None | `null` |
+//! | Bool | `true`, `false` |
+//! | Int | `42` |
+//! | Long | `Long(42)`, or an integer too large for an Int |
+//! | Float | `0.5` |
+//! | Double | `Double(0.5)` |
+//! | Id | `Id(3)` |
+//! | String | `"text"`, with JSON escapes |
+//! | Bytes | `Bytes(00ff)` |
+//! | Rectangle | `640x480` |
+//! | Fraction | `30/1` |
+//! | Fd | `Fd(3)` |
+//! | Array | `[ 1, 2, 3 ]` |
+//! | Struct | `( 1, "text" )` |
+//! | Object | `{ mute = true, volume = 0.5 }` for props, `Object(<type>, <id>) { <key> = <value> }` otherwise |
+//! | Choice | `Range(1, 0, 10)`, `Step(1, 0, 10, 2)`, `Enum(1, 1, 2)`, `Flags(0, 1, 2)`, `Choice(1)` |
+//! | Unknown types | `Other(<type>, <hex body>)`, such as `Other(65536, 00ff)` |
+//!
+//! Values can be separated by commas or whitespace, and `:` can be used instead of `=` in objects.
+//! The keys of props objects are the names of the props, such as `volume`, or their numeric ids.
+//! The flags of choices and object properties are not represented, and pointers cannot be parsed.
+//!
+//! ```
+//! use libspa::pod::text::{from_text, to_text};
+//!
+//! let pod = from_text("{ mute = true, volume = 1 }").unwrap();
+//! assert_eq!(to_text(&pod), "{ mute = true, volume = 1.0 }");
+//! ```
+
+use std::fmt;
+
+use super::{
+    deserialize::PodDeserializer, io::PodBuf, CanonicalFixedSizedPod, ChoiceValue, Object, Pod,
+    Property, Value, ValueArray,
+};
+use crate::utils::{Choice, ChoiceEnum, ChoiceFlags, Fd, Fraction, Id, Rectangle};
+
+/// The expected type of a prop, used to convert numbers written without a decimal point.
+#[derive(Clone, Copy)]
+enum Hint {
+    Bool,
+    Int,
+    Long,
+    Float,
+    Double,
+    String,
+    FloatArray,
+    Any,
+}
+
+/// The props that can be referred to by name.
+const PROPS: &[(&str, u32, Hint)] = &[
+    ("device", spa_sys::SPA_PROP_device, Hint::String),
+    ("deviceName", spa_sys::SPA_PROP_deviceName, Hint::String),
+    ("card", spa_sys::SPA_PROP_card, Hint::String),
+    ("cardName", spa_sys::SPA_PROP_cardName, Hint::String),
+    ("minLatency", spa_sys::SPA_PROP_minLatency, Hint::Int),
+    ("maxLatency", spa_sys::SPA_PROP_maxLatency, Hint::Int),
+    ("live", spa_sys::SPA_PROP_live, Hint::Bool),
+    ("rate", spa_sys::SPA_PROP_rate, Hint::Double),
+    ("quality", spa_sys::SPA_PROP_quality, Hint::Int),
+    ("waveType", spa_sys::SPA_PROP_waveType, Hint::Any),
+    ("frequency", spa_sys::SPA_PROP_frequency, Hint::Float),
+    ("volume", spa_sys::SPA_PROP_volume, Hint::Float),
+    ("mute", spa_sys::SPA_PROP_mute, Hint::Bool),
+    (
+        "channelVolumes",
+        spa_sys::SPA_PROP_channelVolumes,
+        Hint::FloatArray,
+    ),
+    ("volumeBase", spa_sys::SPA_PROP_volumeBase, Hint::Float),
+    ("volumeStep", spa_sys::SPA_PROP_volumeStep, Hint::Float),
+    ("monitorMute", spa_sys::SPA_PROP_monitorMute, Hint::Bool),
+    (
+        "monitorVolumes",
+        spa_sys::SPA_PROP_monitorVolumes,
+        Hint::FloatArray,
+    ),
+    (
+        "latencyOffsetNsec",
+        spa_sys::SPA_PROP_latencyOffsetNsec,
+        Hint::Long,
+    ),
+    ("softMute", spa_sys::SPA_PROP_softMute, Hint::Bool),
+    (
+        "softVolumes",
+        spa_sys::SPA_PROP_softVolumes,
+        Hint::FloatArray,
+    ),
+    ("brightness", spa_sys::SPA_PROP_brightness, Hint::Any),
+    ("contrast", spa_sys::SPA_PROP_contrast, Hint::Any),
+    ("saturation", spa_sys::SPA_PROP_saturation, Hint::Any),
+    ("hue", spa_sys::SPA_PROP_hue, Hint::Any),
+    ("gamma", spa_sys::SPA_PROP_gamma, Hint::Any),
+    ("exposure", spa_sys::SPA_PROP_exposure, Hint::Any),
+    ("gain", spa_sys::SPA_PROP_gain, Hint::Any),
+    ("sharpness", spa_sys::SPA_PROP_sharpness, Hint::Any),
+];
+
+fn is_props(type_: u32) -> bool {
+    type_ == spa_sys::SPA_TYPE_OBJECT_Props
+}
+
+/// Print `pod` as text.
+///
+/// A pod that cannot be deserialized is printed as `<invalid>`.
+pub fn to_text(pod: &Pod) -> String {
+    match PodDeserializer::deserialize_any_from(pod.as_bytes()) {
+        Ok((_, value)) => value_to_text(&value),
+        Err(_) => String::from("<invalid>"),
+    }
+}
+
+/// Parse a pod from its text.
+pub fn from_text(text: &str) -> Result<PodBuf, ParseError> {
+    let value = value_from_text(text)?;
+    PodBuf::serialize(&value).map_err(|_| ParseError {
+        offset: 0,
+        message: "value cannot be serialized",
+    })
+}
+
+/// Print `value` as text.
+pub fn value_to_text(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value);
+    out
+}
+
+/// Parse a value from its text.
+pub fn value_from_text(text: &str) -> Result<Value, ParseError> {
+    let mut parser = Parser { text, pos: 0 };
+    let value = parser.value()?;
+
+    parser.skip_whitespace();
+    if parser.pos != text.len() {
+        return Err(parser.error("unexpected text after the value"));
+    }
+    Ok(value)
+}
+
+/// Error returned when parsing the text of a pod fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    /// The offset of the error in the text, in bytes.
+    pub offset: usize,
+    pub message: &'static str,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::None => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Id(id) => out.push_str(&format!("Id({})", id.0)),
+        Value::Int(i) => out.push_str(&i.to_string()),
+        Value::Long(l) => out.push_str(&format!("Long({})", l)),
+        Value::Float(f) => out.push_str(&format!("{:?}", f)),
+        Value::Double(d) => out.push_str(&format!("Double({:?})", d)),
+        Value::String(s) => write_string(out, s),
+        Value::Bytes(bytes) => {
+            out.push_str("Bytes(");
+            write_hex(out, bytes);
+            out.push(')');
+        }
+        Value::Rectangle(rect) => out.push_str(&format!("{}x{}", rect.width, rect.height)),
+        Value::Fraction(frac) => out.push_str(&format!("{}/{}", frac.num, frac.denom)),
+        Value::Fd(fd) => out.push_str(&format!("Fd({})", fd.0)),
+        Value::ValueArray(array) => write_list(out, "[", &array_values(array), "]", true),
+        Value::Struct(values) => write_list(out, "(", values, ")", true),
+        Value::Object(object) => {
+            if !is_props(object.type_) || object.id != spa_sys::SPA_PARAM_Props {
+                out.push_str(&format!("Object({}, {}) ", object.type_, object.id));
+            }
+
+            if object.properties.is_empty() {
+                out.push_str("{ }");
+                return;
+            }
+            out.push_str("{ ");
+            for (i, property) in object.properties.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                match PROPS.iter().find(|(_, key, _)| *key == property.key) {
+                    Some((name, _, _)) if is_props(object.type_) => out.push_str(name),
+                    _ => out.push_str(&property.key.to_string()),
+                }
+                out.push_str(" = ");
+                write_value(out, &property.value);
+            }
+            out.push_str(" }");
+        }
+        Value::Choice(choice) => {
+            let (kind, values) = choice_values(choice);
+            write_list(out, &format!("{}(", kind), &values, ")", false);
+        }
+        Value::Pointer(type_, pointer) => {
+            out.push_str(&format!("Pointer({}, {:?})", type_, pointer))
+        }
+        Value::Other { type_, bytes } => {
+            out.push_str(&format!("Other({}, ", type_));
+            write_hex(out, bytes);
+            out.push(')');
+        }
+    }
+}
+
+fn write_hex(out: &mut String, bytes: &[u8]) {
+    for byte in bytes {
+        out.push_str(&format!("{:02x}", byte));
+    }
+}
+
+fn write_list(out: &mut String, open: &str, values: &[Value], close: &str, padded: bool) {
+    out.push_str(open);
+    if padded && !values.is_empty() {
+        out.push(' ');
+    }
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        write_value(out, value);
+    }
+    if padded {
+        out.push(' ');
+    }
+    out.push_str(close);
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn array_values(array: &ValueArray) -> Vec<Value> {
+    match array {
+        ValueArray::None(values) => values.iter().map(|_| Value::None).collect(),
+        ValueArray::Bool(values) => values.iter().copied().map(Value::Bool).collect(),
+        ValueArray::Id(values) => values.iter().copied().map(Value::Id).collect(),
+        ValueArray::Int(values) => values.iter().copied().map(Value::Int).collect(),
+        ValueArray::Long(values) => values.iter().copied().map(Value::Long).collect(),
+        ValueArray::Float(values) => values.iter().copied().map(Value::Float).collect(),
+        ValueArray::Double(values) => values.iter().copied().map(Value::Double).collect(),
+        ValueArray::Rectangle(values) => values.iter().copied().map(Value::Rectangle).collect(),
+        ValueArray::Fraction(values) => values.iter().copied().map(Value::Fraction).collect(),
+        ValueArray::Fd(values) => values.iter().copied().map(Value::Fd).collect(),
+    }
+}
+
+fn choice_values(choice: &ChoiceValue) -> (&'static str, Vec<Value>) {
+    fn values<T: CanonicalFixedSizedPod + Copy>(
+        choice: &ChoiceEnum<T>,
+        f: fn(T) -> Value,
+    ) -> (&'static str, Vec<Value>) {
+        let (kind, values): (_, Vec<T>) = match choice {
+            ChoiceEnum::None(value) => ("Choice", vec![*value]),
+            ChoiceEnum::Range { default, min, max } => ("Range", vec![*default, *min, *max]),
+            ChoiceEnum::Step {
+                default,
+                min,
+                max,
+                step,
+            } => ("Step", vec![*default, *min, *max, *step]),
+            ChoiceEnum::Enum {
+                default,
+                alternatives,
+            } => (
+                "Enum",
+                [*default].iter().chain(alternatives).copied().collect(),
+            ),
+            ChoiceEnum::Flags { default, flags } => {
+                ("Flags", [*default].iter().chain(flags).copied().collect())
+            }
+        };
+        (kind, values.into_iter().map(f).collect())
+    }
+
+    match choice {
+        ChoiceValue::Bool(Choice(_, choice)) => values(choice, Value::Bool),
+        ChoiceValue::Int(Choice(_, choice)) => values(choice, Value::Int),
+        ChoiceValue::Long(Choice(_, choice)) => values(choice, Value::Long),
+        ChoiceValue::Float(Choice(_, choice)) => values(choice, Value::Float),
+        ChoiceValue::Double(Choice(_, choice)) => values(choice, Value::Double),
+        ChoiceValue::Id(Choice(_, choice)) => values(choice, Value::Id),
+        ChoiceValue::Rectangle(Choice(_, choice)) => values(choice, Value::Rectangle),
+        ChoiceValue::Fraction(Choice(_, choice)) => values(choice, Value::Fraction),
+        ChoiceValue::Fd(Choice(_, choice)) => values(choice, Value::Fd),
+    }
+}
+
+/// Convert the numbers of `value` to the type expected by `hint`.
+fn coerce(value: Value, hint: Hint) -> Value {
+    match (hint, value) {
+        (Hint::Long, Value::Int(i)) => Value::Long(i.into()),
+        (Hint::Float, Value::Int(i)) => Value::Float(i as f32),
+        (Hint::Double, Value::Int(i)) => Value::Double(i.into()),
+        (Hint::Double, Value::Float(f)) => Value::Double(f.into()),
+        (Hint::FloatArray, Value::ValueArray(ValueArray::Int(values))) => Value::ValueArray(
+            ValueArray::Float(values.into_iter().map(|i| i as f32).collect()),
+        ),
+        (_, value) => value,
+    }
+}
+
+/// The hint matching the values of a list, which is the widest type of its numbers.
+fn list_hint(values: &[Value]) -> Hint {
+    let mut hint = match values.first() {
+        Some(Value::Int(_)) => Hint::Int,
+        _ => return Hint::Any,
+    };
+    for value in values {
+        hint = match (hint, value) {
+            (_, Value::Double(_)) => Hint::Double,
+            (Hint::Int | Hint::Long, Value::Float(_)) => Hint::Float,
+            (Hint::Int, Value::Long(_)) => Hint::Long,
+            (hint, _) => hint,
+        };
+    }
+    hint
+}
+
+/// Convert `values` to a list of `T` with `f`, after converting their numbers to the widest type.
+fn typed<T>(values: Vec<Value>, f: fn(Value) -> Option<T>) -> Option<Vec<T>> {
+    let hint = list_hint(&values);
+    values.into_iter().map(|v| f(coerce(v, hint))).collect()
+}
+
+macro_rules! typed_values {
+    ($values:expr, $wrap:ident, $make:expr) => {{
+        let values: Vec<Value> = $values;
+        let hint = list_hint(&values);
+        // The type of the values is the type of the first one once converted to the widest type.
+        match values.first().map(|v| coerce(v.clone(), hint)) {
+            None => None,
+            Some(Value::Bool(_)) => typed(values, |v| match v {
+                Value::Bool(v) => Some(v),
+                _ => None,
+            })
+            .and_then($make)
+            .map($wrap::Bool),
+            Some(Value::Int(_)) => typed(values, |v| match v {
+                Value::Int(v) => Some(v),
+                _ => None,
+            })
+            .and_then($make)
+            .map($wrap::Int),
+            Some(Value::Long(_)) => typed(values, |v| match v {
+                Value::Long(v) => Some(v),
+                _ => None,
+            })
+            .and_then($make)
+            .map($wrap::Long),
+            Some(Value::Float(_)) => typed(values, |v| match v {
+                Value::Float(v) => Some(v),
+                _ => None,
+            })
+            .and_then($make)
+            .map($wrap::Float),
+            Some(Value::Double(_)) => typed(values, |v| match v {
+                Value::Double(v) => Some(v),
+                _ => None,
+            })
+            .and_then($make)
+            .map($wrap::Double),
+            Some(Value::Id(_)) => typed(values, |v| match v {
+                Value::Id(v) => Some(v),
+                _ => None,
+            })
+            .and_then($make)
+            .map($wrap::Id),
+            Some(Value::Rectangle(_)) => typed(values, |v| match v {
+                Value::Rectangle(v) => Some(v),
+                _ => None,
+            })
+            .and_then($make)
+            .map($wrap::Rectangle),
+            Some(Value::Fraction(_)) => typed(values, |v| match v {
+                Value::Fraction(v) => Some(v),
+                _ => None,
+            })
+            .and_then($make)
+            .map($wrap::Fraction),
+            Some(Value::Fd(_)) => typed(values, |v| match v {
+                Value::Fd(v) => Some(v),
+                _ => None,
+            })
+            .and_then($make)
+            .map($wrap::Fd),
+            Some(_) => None,
+        }
+    }};
+}
+
+fn array(values: Vec<Value>) -> Option<ValueArray> {
+    if values.iter().all(|v| *v == Value::None) {
+        return Some(ValueArray::None(vec![(); values.len()]));
+    }
+    typed_values!(values, ValueArray, Some)
+}
+
+fn choice(kind: &str, values: Vec<Value>) -> Option<ChoiceValue> {
+    fn make<T: CanonicalFixedSizedPod + Copy>(kind: &str, values: Vec<T>) -> Option<Choice<T>> {
+        let choice = match (kind, values.as_slice()) {
+            ("Choice", [value]) => ChoiceEnum::None(*value),
+            ("Range", [default, min, max]) => ChoiceEnum::Range {
+                default: *default,
+                min: *min,
+                max: *max,
+            },
+            ("Step", [default, min, max, step]) => ChoiceEnum::Step {
+                default: *default,
+                min: *min,
+                max: *max,
+                step: *step,
+            },
+            ("Enum", [default, alternatives @ ..]) => ChoiceEnum::Enum {
+                default: *default,
+                alternatives: alternatives.to_vec(),
+            },
+            ("Flags", [default, flags @ ..]) => ChoiceEnum::Flags {
+                default: *default,
+                flags: flags.to_vec(),
+            },
+            _ => return None,
+        };
+        Some(Choice(ChoiceFlags::empty(), choice))
+    }
+
+    typed_values!(values, ChoiceValue, |values| make(kind, values))
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &'static str) -> ParseError {
+        ParseError {
+            offset: self.pos,
+            message,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char, message: &'static str) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(message))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    fn skip_separators(&mut self) {
+        self.take_while(|c| c.is_whitespace() || c == ',');
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        while let Some(c) = self.peek().filter(|c| f(*c)) {
+            self.pos += c.len_utf8();
+        }
+        &self.text[start..self.pos]
+    }
+
+    fn word(&mut self) -> &'a str {
+        self.take_while(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+
+    fn number(&mut self) -> &'a str {
+        self.take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.'))
+    }
+
+    fn parse<T: std::str::FromStr>(&mut self, message: &'static str) -> Result<T, ParseError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        self.number().parse().map_err(|_| ParseError {
+            offset: start,
+            message,
+        })
+    }
+
+    fn list(&mut self, close: char) -> Result<Vec<Value>, ParseError> {
+        let mut values = Vec::new();
+        loop {
+            self.skip_separators();
+            if self.eat(close) {
+                return Ok(values);
+            }
+            if self.peek().is_none() {
+                return Err(self.error("unterminated list"));
+            }
+            values.push(self.value()?);
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, ParseError> {
+        self.skip_whitespace();
+        let start = self.pos;
+
+        match self.peek() {
+            None => Err(self.error("expected a value")),
+            Some('"') => self.string().map(Value::String),
+            Some('[') => {
+                self.pos += 1;
+                let values = self.list(']')?;
+                array(values).map(Value::ValueArray).ok_or(ParseError {
+                    offset: start,
+                    message: "array values have different types",
+                })
+            }
+            Some('(') => {
+                self.pos += 1;
+                self.list(')').map(Value::Struct)
+            }
+            Some('{') => self.object(spa_sys::SPA_TYPE_OBJECT_Props, spa_sys::SPA_PARAM_Props),
+            Some(c) if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' => self.numeric(),
+            Some(c) if c.is_ascii_alphabetic() => self.named(),
+            Some(_) => Err(self.error("expected a value")),
+        }
+    }
+
+    fn numeric(&mut self) -> Result<Value, ParseError> {
+        let start = self.pos;
+        let token =
+            self.take_while(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'));
+        let error = ParseError {
+            offset: start,
+            message: "invalid number",
+        };
+
+        if self.eat('x') {
+            let width = token.parse().map_err(|_| error)?;
+            let height = self.parse("invalid rectangle height")?;
+            return Ok(Value::Rectangle(Rectangle { width, height }));
+        }
+        if self.eat('/') {
+            let num = token.parse().map_err(|_| error)?;
+            let denom = self.parse("invalid fraction denominator")?;
+            return Ok(Value::Fraction(Fraction { num, denom }));
+        }
+
+        if token.contains(|c| matches!(c, '.' | 'e' | 'E')) {
+            return token.parse().map(Value::Float).map_err(|_| error);
+        }
+        let value: i64 = token.parse().map_err(|_| error)?;
+        Ok(i32::try_from(value).map_or(Value::Long(value), Value::Int))
+    }
+
+    fn named(&mut self) -> Result<Value, ParseError> {
+        let start = self.pos;
+        let word = self.word();
+
+        match word {
+            "null" => return Ok(Value::None),
+            "true" => return Ok(Value::Bool(true)),
+            "false" => return Ok(Value::Bool(false)),
+            "inf" => return Ok(Value::Float(f32::INFINITY)),
+            "NaN" => return Ok(Value::Float(f32::NAN)),
+            _ => {}
+        }
+
+        self.expect('(', "unknown keyword")?;
+        let value = match word {
+            "Long" => Value::Long(self.parse("invalid long")?),
+            "Double" => Value::Double(self.parse("invalid double")?),
+            "Id" => Value::Id(Id(self.parse("invalid id")?)),
+            "Fd" => Value::Fd(Fd(self.parse("invalid fd")?)),
+            "Bytes" => Value::Bytes(self.hex()?),
+            "Other" => {
+                let type_ = self.parse("invalid type")?;
+                self.expect(',', "expected a comma")?;
+                Value::Other {
+                    type_,
+                    bytes: self.hex()?,
+                }
+            }
+            "Object" => {
+                let type_ = self.parse("invalid object type")?;
+                self.expect(',', "expected a comma")?;
+                let id = self.parse("invalid object id")?;
+                self.expect(')', "expected a closing parenthesis")?;
+                self.skip_whitespace();
+                return self.object(type_, id);
+            }
+            "Choice" | "Range" | "Step" | "Enum" | "Flags" => {
+                let values = self.list(')')?;
+                return choice(word, values).map(Value::Choice).ok_or(ParseError {
+                    offset: start,
+                    message: "invalid choice values",
+                });
+            }
+            _ => {
+                return Err(ParseError {
+                    offset: start,
+                    message: "unknown keyword",
+                })
+            }
+        };
+        self.expect(')', "expected a closing parenthesis")?;
+
+        Ok(value)
+    }
+
+    fn hex(&mut self) -> Result<Vec<u8>, ParseError> {
+        self.skip_whitespace();
+        let hex = self.take_while(|c| c.is_ascii_hexdigit());
+        if hex.len() % 2 != 0 {
+            return Err(self.error("odd number of hexadecimal digits"));
+        }
+        Ok((0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect())
+    }
+
+    fn object(&mut self, type_: u32, id: u32) -> Result<Value, ParseError> {
+        self.expect('{', "expected an object")?;
+
+        let mut properties = Vec::new();
+        loop {
+            self.skip_separators();
+            if self.eat('}') {
+                break;
+            }
+
+            let start = self.pos;
+            let name = match self.peek() {
+                Some('"') => self.string()?,
+                Some(c) if c.is_ascii_alphanumeric() => self.word().to_owned(),
+                _ => return Err(self.error("expected a key")),
+            };
+            let prop = PROPS
+                .iter()
+                .find(|(prop, _, _)| is_props(type_) && *prop == name);
+            let (key, hint) = match (prop, name.parse()) {
+                (Some((_, key, hint)), _) => (*key, *hint),
+                (None, Ok(key)) => (key, Hint::Any),
+                (None, Err(_)) => {
+                    return Err(ParseError {
+                        offset: start,
+                        message: "unknown key",
+                    })
+                }
+            };
+
+            self.skip_whitespace();
+            if !self.eat('=') && !self.eat(':') {
+                return Err(self.error("expected `=`"));
+            }
+            let value = coerce(self.value()?, hint);
+            properties.push(Property::new(key, value));
+        }
+
+        Ok(Value::Object(Object {
+            type_,
+            id,
+            properties,
+        }))
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        // Skip the opening quote.
+        self.pos += 1;
+
+        let mut s = String::new();
+        loop {
+            let c = self
+                .peek()
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += c.len_utf8();
+            let c = match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let escape = self
+                        .peek()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += escape.len_utf8();
+                    match escape {
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        'u' => {
+                            let hex = self.text.get(self.pos..self.pos + 4);
+                            let c = hex
+                                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error("invalid unicode escape"))?;
+                            self.pos += 4;
+                            c
+                        }
+                        c => c,
+                    }
+                }
+                c => c,
+            };
+            // Strings are serialized NUL-terminated.
+            if c == '\0' {
+                return Err(self.error("NUL character in string"));
+            }
+            s.push(c);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(text: &str) {
+        let pod = from_text(text).unwrap();
+        assert_eq!(to_text(&pod), text);
+    }
+
+    #[test]
+    fn props() {
+        let value =
+            value_from_text("{ mute: true, volume = 1, channelVolumes = [ 1, 0.5 ] }").unwrap();
+        assert_eq!(
+            value,
+            Value::Object(Object {
+                type_: spa_sys::SPA_TYPE_OBJECT_Props,
+                id: spa_sys::SPA_PARAM_Props,
+                properties: vec![
+                    Property::new(spa_sys::SPA_PROP_mute, Value::Bool(true)),
+                    Property::new(spa_sys::SPA_PROP_volume, Value::Float(1.0)),
+                    Property::new(
+                        spa_sys::SPA_PROP_channelVolumes,
+                        Value::ValueArray(ValueArray::Float(vec![1.0, 0.5]))
+                    ),
+                ],
+            })
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn values() {
+        round_trip("( null, true, 42, Long(42), 0.5, Double(0.25), Id(3), \"a \\\"b\\\"\" )");
+        round_trip("( Bytes(00ff), 640x480, 30/1, Fd(3), [ 1, 2 ], [ ] )");
+        round_trip("( Other(65536, 0102030405), 1 )");
+        round_trip("Object(262147, 4) { 1 = Enum(Id(2), Id(1), Id(2)) }");
+        round_trip("{ volume = Range(1.0, 0.0, 10.0), mute = Choice(false) }");
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            value_from_text("{ unknown = 1 }"),
+            Err(ParseError {
+                offset: 2,
+                message: "unknown key"
+            })
+        );
+        assert!(value_from_text("[ 1, \"a\" ]").is_err());
+        assert!(value_from_text("Range(1, 2)").is_err());
+        assert!(value_from_text("1 2").is_err());
+        assert!(from_text("\"a\\u0000\"").is_err());
+        assert!(from_text("\"a\0\"").is_err());
+    }
+}
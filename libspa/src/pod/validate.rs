@@ -0,0 +1,455 @@
+//! Strict validation of untrusted pods.
+//!
+//! [`Pod::from_bytes`] only checks that the outer pod fits in the provided bytes.
+//! [`Pod::validate`] checks the whole pod recursively, so that pods read from sockets or files
+//! can be safely accessed afterwards.
+
+use std::{fmt, mem};
+
+use super::Pod;
+use crate::utils::SpaTypes;
+
+const HEADER_SIZE: usize = mem::size_of::<spa_sys::spa_pod>();
+
+/// The maximum nesting of container pods accepted by [`Pod::validate`].
+pub const MAX_DEPTH: usize = 64;
+
+/// The reason a pod failed to validate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationErrorKind {
+    /// The bytes are not aligned as required by a pod header.
+    Misaligned,
+    /// The pod extends past the end of its parent.
+    Truncated { needed: usize, available: usize },
+    /// The pod is not followed by the padding to the next multiple of 8 bytes.
+    MissingPadding,
+    /// The type of the pod is unknown.
+    UnknownType(u32),
+    /// The body of the pod is too small for its type.
+    InvalidSize { type_: u32, size: usize },
+    /// A string pod is not terminated by a NUL byte.
+    UnterminatedString,
+    /// The values of an array or choice do not have a size matching their type,
+    /// or do not fill the body of the pod.
+    InvalidChildSize { child_type: u32, child_size: usize },
+    /// The type of a choice pod is unknown.
+    UnknownChoiceType(u32),
+    /// A choice pod does not have enough values for its type.
+    MissingChoiceValues { choice_type: u32, count: usize },
+    /// Container pods are nested deeper than [`MAX_DEPTH`].
+    TooDeep,
+}
+
+/// Error returned by [`Pod::validate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValidationError {
+    /// The offset, in the validated bytes, of the pod or value that is invalid.
+    pub offset: usize,
+    pub kind: ValidationErrorKind,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ValidationErrorKind::Misaligned => write!(f, "pod is misaligned"),
+            ValidationErrorKind::Truncated { needed, available } => write!(
+                f,
+                "pod is truncated: {} bytes needed, {} available",
+                needed, available
+            ),
+            ValidationErrorKind::MissingPadding => write!(f, "pod is missing its padding"),
+            ValidationErrorKind::UnknownType(type_) => write!(f, "unknown pod type {}", type_),
+            ValidationErrorKind::InvalidSize { type_, size } => write!(
+                f,
+                "invalid body size {} for pod of type {:?}",
+                size,
+                SpaTypes::from_raw(type_)
+            ),
+            ValidationErrorKind::UnterminatedString => write!(f, "string is not NUL terminated"),
+            ValidationErrorKind::InvalidChildSize {
+                child_type,
+                child_size,
+            } => write!(
+                f,
+                "invalid size {} for values of type {:?}",
+                child_size,
+                SpaTypes::from_raw(child_type)
+            ),
+            ValidationErrorKind::UnknownChoiceType(choice_type) => {
+                write!(f, "unknown choice type {}", choice_type)
+            }
+            ValidationErrorKind::MissingChoiceValues { choice_type, count } => write!(
+                f,
+                "choice of type {} has only {} values",
+                choice_type, count
+            ),
+            ValidationErrorKind::TooDeep => write!(f, "pods are nested too deeply"),
+        }?;
+
+        write!(f, " at offset {}", self.offset)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl Pod {
+    /// Check that `bytes` contains a valid pod.
+    ///
+    /// In addition to what [`Pod::from_bytes`] checks, this checks that the body of the pod matches
+    /// its type, recursively for the children of container pods: the children must fit in their
+    /// parent and be padded, and the values of arrays and choices must have a size matching
+    /// their type.
+    ///
+    /// A pod passing this check can be accessed with [`Pod::from_bytes`] and the accessors of
+    /// [`Pod`] without reading outside of `bytes`.
+    pub fn validate(bytes: &[u8]) -> Result<(), ValidationError> {
+        if bytes
+            .as_ptr()
+            .align_offset(mem::align_of::<spa_sys::spa_pod>())
+            != 0
+        {
+            return Err(error(0, ValidationErrorKind::Misaligned));
+        }
+
+        let validator = Validator { bytes };
+        let end = validator.pod(0, bytes.len(), 0)?;
+        if round_up(end) > bytes.len() {
+            return Err(error(end, ValidationErrorKind::MissingPadding));
+        }
+
+        Ok(())
+    }
+}
+
+fn error(offset: usize, kind: ValidationErrorKind) -> ValidationError {
+    ValidationError { offset, kind }
+}
+
+fn round_up(size: usize) -> usize {
+    (size + 7) & !7
+}
+
+/// The minimum size of the body of the pods of type `type_`.
+fn min_body_size(type_: u32) -> Option<usize> {
+    let size = match type_ {
+        spa_sys::SPA_TYPE_None
+        | spa_sys::SPA_TYPE_Bytes
+        | spa_sys::SPA_TYPE_Bitmap
+        | spa_sys::SPA_TYPE_Struct => 0,
+        spa_sys::SPA_TYPE_String => 1,
+        spa_sys::SPA_TYPE_Bool
+        | spa_sys::SPA_TYPE_Id
+        | spa_sys::SPA_TYPE_Int
+        | spa_sys::SPA_TYPE_Float => 4,
+        spa_sys::SPA_TYPE_Long
+        | spa_sys::SPA_TYPE_Double
+        | spa_sys::SPA_TYPE_Fd
+        | spa_sys::SPA_TYPE_Rectangle
+        | spa_sys::SPA_TYPE_Fraction => 8,
+        spa_sys::SPA_TYPE_Pointer => mem::size_of::<spa_sys::spa_pod_pointer_body>(),
+        spa_sys::SPA_TYPE_Array => mem::size_of::<spa_sys::spa_pod_array_body>(),
+        spa_sys::SPA_TYPE_Choice => mem::size_of::<spa_sys::spa_pod_choice_body>(),
+        spa_sys::SPA_TYPE_Object => mem::size_of::<spa_sys::spa_pod_object_body>(),
+        spa_sys::SPA_TYPE_Sequence => mem::size_of::<spa_sys::spa_pod_sequence_body>(),
+        _ => return None,
+    };
+
+    Some(size)
+}
+
+struct Validator<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Validator<'a> {
+    fn u32_at(&self, offset: usize) -> u32 {
+        u32::from_ne_bytes(self.bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    /// Check that `needed` bytes starting at `offset` are available before `end`.
+    fn fits(&self, offset: usize, needed: usize, end: usize) -> Result<(), ValidationError> {
+        let available = end.saturating_sub(offset);
+        if needed > available {
+            Err(error(
+                offset,
+                ValidationErrorKind::Truncated { needed, available },
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Validate the pod starting at `offset` and ending before `end`,
+    /// returning the offset of the end of its body.
+    fn pod(&self, offset: usize, end: usize, depth: usize) -> Result<usize, ValidationError> {
+        self.fits(offset, HEADER_SIZE, end)?;
+        let size = usize::try_from(self.u32_at(offset)).unwrap();
+        let type_ = self.u32_at(offset + 4);
+
+        let body = offset + HEADER_SIZE;
+        self.fits(offset, HEADER_SIZE.saturating_add(size), end)?;
+        self.body(type_, offset, body, body + size, depth)?;
+
+        Ok(body + size)
+    }
+
+    /// Validate a sequence of padded pods, each preceded by a prefix of `prefix` bytes.
+    fn children(
+        &self,
+        mut offset: usize,
+        end: usize,
+        prefix: usize,
+        depth: usize,
+    ) -> Result<(), ValidationError> {
+        while offset < end {
+            self.fits(offset, prefix, end)?;
+            let child_end = self.pod(offset + prefix, end, depth)?;
+            offset = round_up(child_end);
+            if offset > end {
+                return Err(error(child_end, ValidationErrorKind::MissingPadding));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate `count` values of `child_size` bytes of type `child_type` starting at `offset`.
+    fn values(
+        &self,
+        offset: usize,
+        end: usize,
+        child_type: u32,
+        child_size: usize,
+        depth: usize,
+    ) -> Result<usize, ValidationError> {
+        let invalid = error(
+            offset,
+            ValidationErrorKind::InvalidChildSize {
+                child_type,
+                child_size,
+            },
+        );
+        let len = end - offset;
+        if len == 0 {
+            return Ok(0);
+        }
+        if child_size == 0 || len % child_size != 0 {
+            return Err(invalid);
+        }
+        match min_body_size(child_type) {
+            Some(min) if child_size >= min => {}
+            Some(_) => return Err(invalid),
+            None => return Err(error(offset, ValidationErrorKind::UnknownType(child_type))),
+        }
+
+        for value in (offset..end).step_by(child_size) {
+            self.body(child_type, value, value, value + child_size, depth)?;
+        }
+
+        Ok(len / child_size)
+    }
+
+    /// Validate the body of type `type_` between `start` and `end`,
+    /// `offset` being the offset of the pod reported in errors.
+    fn body(
+        &self,
+        type_: u32,
+        offset: usize,
+        start: usize,
+        end: usize,
+        depth: usize,
+    ) -> Result<(), ValidationError> {
+        let size = end - start;
+        let min = min_body_size(type_)
+            .ok_or_else(|| error(offset, ValidationErrorKind::UnknownType(type_)))?;
+        if size < min {
+            return Err(error(
+                offset,
+                ValidationErrorKind::InvalidSize { type_, size },
+            ));
+        }
+
+        let is_container = matches!(
+            type_,
+            spa_sys::SPA_TYPE_Array
+                | spa_sys::SPA_TYPE_Choice
+                | spa_sys::SPA_TYPE_Struct
+                | spa_sys::SPA_TYPE_Object
+                | spa_sys::SPA_TYPE_Sequence
+        );
+        if is_container && depth >= MAX_DEPTH {
+            return Err(error(offset, ValidationErrorKind::TooDeep));
+        }
+        let depth = depth + 1;
+
+        match type_ {
+            spa_sys::SPA_TYPE_String => {
+                if self.bytes[end - 1] != 0 {
+                    return Err(error(offset, ValidationErrorKind::UnterminatedString));
+                }
+            }
+            spa_sys::SPA_TYPE_Array => {
+                let child_size = usize::try_from(self.u32_at(start)).unwrap();
+                let child_type = self.u32_at(start + 4);
+                self.values(start + HEADER_SIZE, end, child_type, child_size, depth)?;
+            }
+            spa_sys::SPA_TYPE_Choice => {
+                let choice_type = self.u32_at(start);
+                let child_size = usize::try_from(self.u32_at(start + 8)).unwrap();
+                let child_type = self.u32_at(start + 12);
+                let min_values = match choice_type {
+                    spa_sys::SPA_CHOICE_None
+                    | spa_sys::SPA_CHOICE_Enum
+                    | spa_sys::SPA_CHOICE_Flags => 1,
+                    spa_sys::SPA_CHOICE_Range => 3,
+                    spa_sys::SPA_CHOICE_Step => 4,
+                    _ => {
+                        return Err(error(
+                            offset,
+                            ValidationErrorKind::UnknownChoiceType(choice_type),
+                        ))
+                    }
+                };
+
+                let values_offset = start + min;
+                let count = self.values(values_offset, end, child_type, child_size, depth)?;
+                if count < min_values {
+                    return Err(error(
+                        offset,
+                        ValidationErrorKind::MissingChoiceValues { choice_type, count },
+                    ));
+                }
+            }
+            // Fields are pods
+            spa_sys::SPA_TYPE_Struct => self.children(start, end, 0, depth)?,
+            // Properties are a key and flags followed by a pod
+            spa_sys::SPA_TYPE_Object => self.children(start + min, end, 8, depth)?,
+            // Controls are an offset and type followed by a pod
+            spa_sys::SPA_TYPE_Sequence => self.children(start + min, end, 8, depth)?,
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        pod::{
+            test_utils::{as_bytes, as_bytes_mut, serialize},
+            ChoiceValue, Object, Property, Value, ValueArray,
+        },
+        utils::{Choice, ChoiceEnum, ChoiceFlags},
+    };
+
+    fn props() -> Value {
+        Value::Object(Object {
+            type_: spa_sys::SPA_TYPE_OBJECT_Props,
+            id: spa_sys::SPA_PARAM_Props,
+            properties: vec![
+                Property::new(
+                    spa_sys::SPA_PROP_channelVolumes,
+                    Value::ValueArray(ValueArray::Float(vec![0.5, 0.5])),
+                ),
+                Property::new(
+                    spa_sys::SPA_PROP_volume,
+                    Value::Choice(ChoiceValue::Float(Choice(
+                        ChoiceFlags::empty(),
+                        ChoiceEnum::Range {
+                            default: 1.0,
+                            min: 0.0,
+                            max: 10.0,
+                        },
+                    ))),
+                ),
+                Property::new(
+                    spa_sys::SPA_PROP_deviceName,
+                    Value::Struct(vec![Value::String("name".to_owned()), Value::Long(3)]),
+                ),
+            ],
+        })
+    }
+
+    #[test]
+    fn valid() {
+        for value in [
+            Value::None,
+            Value::Int(1),
+            Value::String("pipewire".to_owned()),
+            Value::Bytes(vec![1, 2, 3]),
+            props(),
+        ] {
+            let buffer = serialize(&value);
+            assert_eq!(Pod::validate(as_bytes(&buffer)), Ok(()));
+        }
+    }
+
+    #[test]
+    fn truncated() {
+        let buffer = serialize(&props());
+        let bytes = as_bytes(&buffer);
+
+        let err = Pod::validate(&bytes[..4]).unwrap_err();
+        assert_eq!(
+            err.kind,
+            ValidationErrorKind::Truncated {
+                needed: 8,
+                available: 4
+            }
+        );
+
+        let err = Pod::validate(&bytes[..bytes.len() - 8]).unwrap_err();
+        assert_eq!(err.offset, 0);
+        assert!(matches!(err.kind, ValidationErrorKind::Truncated { .. }));
+    }
+
+    #[test]
+    fn nested() {
+        let mut buffer = serialize(&props());
+        let bytes = as_bytes_mut(&mut buffer);
+
+        // Header of the object, object body, key and flags of the first property,
+        // followed by the header of the array.
+        let array = 8 + 8 + 8;
+        // The size of the values no longer divides the size of the array.
+        bytes[array + 8..array + 12].copy_from_slice(&3u32.to_ne_bytes());
+
+        let err = Pod::validate(bytes).unwrap_err();
+        assert_eq!(err.offset, array + 16);
+        assert_eq!(
+            err.kind,
+            ValidationErrorKind::InvalidChildSize {
+                child_type: spa_sys::SPA_TYPE_Float,
+                child_size: 3
+            }
+        );
+    }
+
+    #[test]
+    fn string() {
+        let mut buffer = serialize(&Value::String("pipewire".to_owned()));
+        let bytes = as_bytes_mut(&mut buffer);
+        bytes[8 + 8] = b'!';
+
+        let err = Pod::validate(bytes).unwrap_err();
+        assert_eq!(err.offset, 0);
+        assert_eq!(err.kind, ValidationErrorKind::UnterminatedString);
+        assert_eq!(err.to_string(), "string is not NUL terminated at offset 0");
+    }
+
+    #[test]
+    fn too_deep() {
+        let mut value = Value::Int(0);
+        for _ in 0..=MAX_DEPTH {
+            value = Value::Struct(vec![value]);
+        }
+
+        let buffer = serialize(&value);
+        let err = Pod::validate(as_bytes(&buffer)).unwrap_err();
+        assert_eq!(err.kind, ValidationErrorKind::TooDeep);
+        assert_eq!(err.offset, MAX_DEPTH * 8);
+    }
+}
@@ -0,0 +1,552 @@
+//! Borrowed counterpart of [`Value`], deserialized without allocating.
+//!
+//! [`PodDeserializer::deserialize_any_ref`] produces a [`ValueRef`] whose strings and bytes borrow
+//! the deserialized buffer, and whose containers keep their body to iterate over their children
+//! lazily. This makes it cheap to look at a pod which is mostly skipped, such as params received
+//! on every update. Use the `to_value` methods to get an owned [`Value`] instead.
+//!
+//! ```rust
+//! use std::io::Cursor;
+//! use libspa::pod::{deserialize::PodDeserializer, serialize::PodSerializer, value_ref::ValueRef, Value};
+//!
+//! let value = Value::Struct(vec![Value::String("hello".into()), Value::Int(1)]);
+//! let bytes = PodSerializer::serialize(Cursor::new(Vec::new()), &value)
+//!     .unwrap()
+//!     .0
+//!     .into_inner();
+//!
+//! let (_, value_ref) = PodDeserializer::deserialize_any_ref_from(&bytes).unwrap();
+//! let ValueRef::Struct(fields) = value_ref else { unreachable!() };
+//! assert_eq!(fields.iter().next(), Some(ValueRef::String("hello")));
+//! assert_eq!(value_ref.to_value(), value);
+//! ```
+
+use std::{ffi::c_void, marker::PhantomData};
+
+use super::{
+    deserialize::{create_choice, PodDeserializer},
+    CanonicalFixedSizedPod, ChoiceValue, FixedSizedPod, Object, Property, PropertyFlags, Value,
+    ValueArray,
+};
+use crate::utils::{Choice, ChoiceFlags, Fd, Fraction, Id, Rectangle};
+
+/// The size of the body of a fixed sized pod of type `type_`, or `None` if the type is not one.
+pub(super) fn fixed_pod_size(type_: u32) -> Option<u32> {
+    match type_ {
+        spa_sys::SPA_TYPE_None => Some(<() as CanonicalFixedSizedPod>::SIZE),
+        spa_sys::SPA_TYPE_Bool => Some(bool::SIZE),
+        spa_sys::SPA_TYPE_Id => Some(Id::SIZE),
+        spa_sys::SPA_TYPE_Int => Some(i32::SIZE),
+        spa_sys::SPA_TYPE_Long => Some(i64::SIZE),
+        spa_sys::SPA_TYPE_Float => Some(f32::SIZE),
+        spa_sys::SPA_TYPE_Double => Some(f64::SIZE),
+        spa_sys::SPA_TYPE_Rectangle => Some(Rectangle::SIZE),
+        spa_sys::SPA_TYPE_Fraction => Some(Fraction::SIZE),
+        spa_sys::SPA_TYPE_Fd => Some(Fd::SIZE),
+        _ => None,
+    }
+}
+
+/// A pod value borrowing the buffer it was deserialized from, see the [module documentation](self).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueRef<'a> {
+    /// no value or a NULL pointer.
+    None,
+    /// a boolean value.
+    Bool(bool),
+    /// an enumerated value.
+    Id(Id),
+    /// a 32 bits integer.
+    Int(i32),
+    /// a 64 bits integer.
+    Long(i64),
+    /// a 32 bits floating.
+    Float(f32),
+    /// a 64 bits floating.
+    Double(f64),
+    /// a string.
+    String(&'a str),
+    /// a byte array.
+    Bytes(&'a [u8]),
+    /// a rectangle with width and height.
+    Rectangle(Rectangle),
+    /// a fraction with numerator and denominator.
+    Fraction(Fraction),
+    /// a file descriptor.
+    Fd(Fd),
+    /// an array of same type objects.
+    ValueArray(ArrayRef<'a>),
+    /// a collection of types and objects.
+    Struct(StructRef<'a>),
+    /// an object.
+    Object(ObjectRef<'a>),
+    /// a choice.
+    Choice(ChoiceRef<'a>),
+    /// a pointer.
+    Pointer(u32, *const c_void),
+    /// a pod of a type unknown to these bindings, see [`Value::Other`].
+    Other { type_: u32, bytes: &'a [u8] },
+}
+
+impl ValueRef<'_> {
+    /// Copy the value into an owned [`Value`].
+    pub fn to_value(&self) -> Value {
+        match *self {
+            Self::None => Value::None,
+            Self::Bool(v) => Value::Bool(v),
+            Self::Id(v) => Value::Id(v),
+            Self::Int(v) => Value::Int(v),
+            Self::Long(v) => Value::Long(v),
+            Self::Float(v) => Value::Float(v),
+            Self::Double(v) => Value::Double(v),
+            Self::String(v) => Value::String(v.to_owned()),
+            Self::Bytes(v) => Value::Bytes(v.to_vec()),
+            Self::Rectangle(v) => Value::Rectangle(v),
+            Self::Fraction(v) => Value::Fraction(v),
+            Self::Fd(v) => Value::Fd(v),
+            Self::ValueArray(array) => Value::ValueArray(array.to_value()),
+            Self::Struct(fields) => Value::Struct(fields.to_value()),
+            Self::Object(object) => Value::Object(object.to_value()),
+            Self::Choice(choice) => Value::Choice(choice.to_value()),
+            Self::Pointer(type_, pointer) => Value::Pointer(type_, pointer),
+            Self::Other { type_, bytes } => Value::Other {
+                type_,
+                bytes: bytes.to_vec(),
+            },
+        }
+    }
+}
+
+/// The fields of a `Struct` pod.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StructRef<'a> {
+    /// The body of the struct, which has been validated.
+    pub(super) fields: &'a [u8],
+}
+
+impl<'a> StructRef<'a> {
+    /// Iterate over the fields of the struct.
+    pub fn iter(&self) -> StructIter<'a> {
+        StructIter { rest: self.fields }
+    }
+
+    /// Copy the fields into owned [`Value`]s.
+    pub fn to_value(&self) -> Vec<Value> {
+        self.iter().map(|field| field.to_value()).collect()
+    }
+}
+
+impl<'a> IntoIterator for StructRef<'a> {
+    type Item = ValueRef<'a>;
+    type IntoIter = StructIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the fields of a [`StructRef`].
+#[derive(Debug, Clone)]
+pub struct StructIter<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Iterator for StructIter<'a> {
+    type Item = ValueRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        let (rest, field) = PodDeserializer::deserialize_any_ref_from(self.rest).ok()?;
+        self.rest = rest;
+        Some(field)
+    }
+}
+
+/// An `Object` pod.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectRef<'a> {
+    /// the object type.
+    pub type_: u32,
+    /// the object id.
+    pub id: u32,
+    /// The serialized properties of the object, which have been validated.
+    pub(super) properties: &'a [u8],
+}
+
+impl<'a> ObjectRef<'a> {
+    /// Iterate over the properties of the object.
+    pub fn properties(&self) -> PropertiesIter<'a> {
+        PropertiesIter {
+            rest: self.properties,
+        }
+    }
+
+    /// Find the property with the key `key`.
+    pub fn property(&self, key: u32) -> Option<PropertyRef<'a>> {
+        self.properties().find(|property| property.key == key)
+    }
+
+    /// Copy the object into an owned [`Object`].
+    pub fn to_value(&self) -> Object {
+        Object {
+            type_: self.type_,
+            id: self.id,
+            properties: self
+                .properties()
+                .map(|property| Property {
+                    key: property.key,
+                    flags: property.flags,
+                    value: property.value.to_value(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A property of an [`ObjectRef`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PropertyRef<'a> {
+    /// key of the property, list of valid keys depends on the object type.
+    pub key: u32,
+    /// flags for the property.
+    pub flags: PropertyFlags,
+    /// value of the property.
+    pub value: ValueRef<'a>,
+}
+
+/// An iterator over the properties of an [`ObjectRef`].
+#[derive(Debug, Clone)]
+pub struct PropertiesIter<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Iterator for PropertiesIter<'a> {
+    type Item = PropertyRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = u32::from_ne_bytes(self.rest.get(0..4)?.try_into().unwrap());
+        let flags = u32::from_ne_bytes(self.rest.get(4..8)?.try_into().unwrap());
+        let (rest, value) = PodDeserializer::deserialize_any_ref_from(&self.rest[8..]).ok()?;
+        self.rest = rest;
+
+        Some(PropertyRef {
+            key,
+            flags: PropertyFlags::from_bits_retain(flags),
+            value,
+        })
+    }
+}
+
+/// An `Array` pod, whose elements are all fixed sized pods of the same type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArrayRef<'a> {
+    /// The type of the elements, which is a fixed sized pod type.
+    pub(super) child_type: u32,
+    pub(super) elements: &'a [u8],
+}
+
+impl<'a> ArrayRef<'a> {
+    /// The pod type of the elements of the array.
+    pub fn child_type(&self) -> u32 {
+        self.child_type
+    }
+
+    /// The number of elements of the array.
+    pub fn len(&self) -> usize {
+        element_count(self.child_type, self.elements)
+    }
+
+    /// Whether the array has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate over the elements of the array as `T`, or return `None` if the elements are not
+    /// of the type `T` deserializes from.
+    pub fn values<T: FixedSizedPod>(&self) -> Option<FixedSizedIter<'a, T>> {
+        FixedSizedIter::new(self.child_type, self.elements)
+    }
+
+    /// Copy the elements into an owned [`ValueArray`].
+    pub fn to_value(&self) -> ValueArray {
+        fn collect<T: CanonicalFixedSizedPod + FixedSizedPod>(array: &ArrayRef) -> ValueArray {
+            T::into_value_array(array.values::<T>().into_iter().flatten().collect())
+        }
+
+        match self.child_type {
+            spa_sys::SPA_TYPE_None => collect::<()>(self),
+            spa_sys::SPA_TYPE_Bool => collect::<bool>(self),
+            spa_sys::SPA_TYPE_Id => collect::<Id>(self),
+            spa_sys::SPA_TYPE_Int => collect::<i32>(self),
+            spa_sys::SPA_TYPE_Long => collect::<i64>(self),
+            spa_sys::SPA_TYPE_Float => collect::<f32>(self),
+            spa_sys::SPA_TYPE_Double => collect::<f64>(self),
+            spa_sys::SPA_TYPE_Rectangle => collect::<Rectangle>(self),
+            spa_sys::SPA_TYPE_Fraction => collect::<Fraction>(self),
+            spa_sys::SPA_TYPE_Fd => collect::<Fd>(self),
+            _ => unreachable!("array of non fixed sized pods"),
+        }
+    }
+}
+
+/// A `Choice` pod.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChoiceRef<'a> {
+    /// The raw `SPA_CHOICE_*` type of the choice.
+    pub(super) choice_type: u32,
+    pub(super) flags: ChoiceFlags,
+    /// The type of the values, which is a fixed sized pod type.
+    pub(super) child_type: u32,
+    /// The values, holding enough values for the choice type.
+    pub(super) values: &'a [u8],
+}
+
+impl<'a> ChoiceRef<'a> {
+    /// The raw type of the choice, one of the `SPA_CHOICE_*` constants.
+    pub fn choice_type(&self) -> u32 {
+        self.choice_type
+    }
+
+    /// The flags of the choice.
+    pub fn flags(&self) -> ChoiceFlags {
+        self.flags
+    }
+
+    /// The pod type of the values of the choice.
+    pub fn child_type(&self) -> u32 {
+        self.child_type
+    }
+
+    /// Iterate over the serialized values of the choice as `T`, or return `None` if they are not
+    /// of the type `T` deserializes from.
+    ///
+    /// The first value is the default, followed by the values specific to the choice type,
+    /// in the order of the fields of [`ChoiceEnum`](crate::utils::ChoiceEnum).
+    pub fn values<T: FixedSizedPod>(&self) -> Option<FixedSizedIter<'a, T>> {
+        FixedSizedIter::new(self.child_type, self.values)
+    }
+
+    /// The default value of the choice, or `None` if it is not of the type `T` deserializes from.
+    pub fn default<T: FixedSizedPod>(&self) -> Option<T> {
+        self.values()?.next()
+    }
+
+    /// Copy the choice into an owned [`ChoiceValue`].
+    pub fn to_value(&self) -> ChoiceValue {
+        fn choice<T: CanonicalFixedSizedPod + FixedSizedPod + Copy>(
+            choice: &ChoiceRef,
+        ) -> Choice<T> {
+            let values = choice.values::<T>().into_iter().flatten().collect();
+            create_choice(choice.choice_type, values, choice.flags.bits())
+                .expect("choice was validated when deserialized")
+        }
+
+        match self.child_type {
+            spa_sys::SPA_TYPE_Bool => ChoiceValue::Bool(choice(self)),
+            spa_sys::SPA_TYPE_Id => ChoiceValue::Id(choice(self)),
+            spa_sys::SPA_TYPE_Int => ChoiceValue::Int(choice(self)),
+            spa_sys::SPA_TYPE_Long => ChoiceValue::Long(choice(self)),
+            spa_sys::SPA_TYPE_Float => ChoiceValue::Float(choice(self)),
+            spa_sys::SPA_TYPE_Double => ChoiceValue::Double(choice(self)),
+            spa_sys::SPA_TYPE_Rectangle => ChoiceValue::Rectangle(choice(self)),
+            spa_sys::SPA_TYPE_Fraction => ChoiceValue::Fraction(choice(self)),
+            spa_sys::SPA_TYPE_Fd => ChoiceValue::Fd(choice(self)),
+            _ => unreachable!("choice of non fixed sized pods"),
+        }
+    }
+}
+
+/// The number of fixed sized pods of type `type_` serialized in `bytes`.
+fn element_count(type_: u32, bytes: &[u8]) -> usize {
+    match fixed_pod_size(type_) {
+        Some(0) | None => 0,
+        Some(size) => bytes.len() / size as usize,
+    }
+}
+
+/// An iterator over the elements of an [`ArrayRef`] or the values of a [`ChoiceRef`].
+#[derive(Debug, Clone)]
+pub struct FixedSizedIter<'a, T> {
+    bytes: &'a [u8],
+    remaining: usize,
+    _phantom: PhantomData<T>,
+}
+
+impl<'a, T: FixedSizedPod> FixedSizedIter<'a, T> {
+    fn new(type_: u32, bytes: &'a [u8]) -> Option<Self> {
+        (type_ == T::CanonicalType::TYPE).then(|| Self {
+            bytes,
+            remaining: element_count(type_, bytes),
+            _phantom: PhantomData,
+        })
+    }
+}
+
+impl<T: FixedSizedPod> Iterator for FixedSizedIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let (bytes, value) = T::CanonicalType::deserialize_body(self.bytes).ok()?;
+        self.bytes = bytes;
+        self.remaining -= 1;
+        Some(T::from_canonical_type(&value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T: FixedSizedPod> ExactSizeIterator for FixedSizedIter<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        pod::{
+            deserialize::DeserializeError,
+            test_utils::{as_bytes, as_bytes_mut, serialize},
+        },
+        utils::ChoiceEnum,
+    };
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn to_value() {
+        let value = Value::Struct(vec![
+            Value::None,
+            Value::String("name".into()),
+            Value::Bytes(vec![1, 2, 3]),
+            Value::array([1.0f32, 0.5]),
+            Value::Object(Object {
+                type_: spa_sys::SPA_TYPE_OBJECT_Props,
+                id: spa_sys::SPA_PARAM_Props,
+                properties: vec![
+                    Property::new(spa_sys::SPA_PROP_volume, Value::Float(0.5)),
+                    Property::new(
+                        spa_sys::SPA_PROP_mute,
+                        Value::Choice(ChoiceValue::Bool(Choice(
+                            ChoiceFlags::empty(),
+                            ChoiceEnum::Enum {
+                                default: false,
+                                alternatives: vec![false, true],
+                            },
+                        ))),
+                    ),
+                ],
+            }),
+            Value::Choice(ChoiceValue::Int(Choice(
+                ChoiceFlags::empty(),
+                ChoiceEnum::Range {
+                    default: 2,
+                    min: 1,
+                    max: 8,
+                },
+            ))),
+            Value::Other {
+                type_: 0x10000,
+                bytes: vec![1, 2, 3, 4, 5],
+            },
+            Value::Long(-1),
+        ]);
+        let buffer = serialize(&value);
+
+        let (rest, value_ref) =
+            PodDeserializer::deserialize_any_ref_from(as_bytes(&buffer)).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(value_ref.to_value(), value);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn borrowed() {
+        let buffer = serialize(&Value::Object(Object {
+            type_: spa_sys::SPA_TYPE_OBJECT_Props,
+            id: spa_sys::SPA_PARAM_Props,
+            properties: vec![
+                Property::new(spa_sys::SPA_PROP_device, Value::String("hw:0".into())),
+                Property::new(
+                    spa_sys::SPA_PROP_channelVolumes,
+                    Value::array([0.25f32, 1.0]),
+                ),
+                Property::new(
+                    spa_sys::SPA_PROP_rate,
+                    Value::Choice(ChoiceValue::Double(Choice(
+                        ChoiceFlags::empty(),
+                        ChoiceEnum::Step {
+                            default: 1.0,
+                            min: 0.5,
+                            max: 2.0,
+                            step: 0.5,
+                        },
+                    ))),
+                ),
+            ],
+        }));
+        let bytes = as_bytes(&buffer);
+
+        let (_, ValueRef::Object(object)) =
+            PodDeserializer::deserialize_any_ref_from(bytes).unwrap()
+        else {
+            panic!("expected an object");
+        };
+        assert_eq!(object.properties().count(), 3);
+
+        let device = object.property(spa_sys::SPA_PROP_device).unwrap().value;
+        let ValueRef::String(device) = device else {
+            panic!("expected a string");
+        };
+        assert_eq!(device, "hw:0");
+        assert!(bytes.as_ptr_range().contains(&device.as_ptr()));
+
+        let volumes = object.property(spa_sys::SPA_PROP_channelVolumes).unwrap();
+        let ValueRef::ValueArray(volumes) = volumes.value else {
+            panic!("expected an array");
+        };
+        assert_eq!(volumes.len(), 2);
+        assert!(volumes.values::<i32>().is_none());
+        assert_eq!(
+            volumes.values::<f32>().unwrap().collect::<Vec<_>>(),
+            [0.25, 1.0]
+        );
+
+        let rate = object.property(spa_sys::SPA_PROP_rate).unwrap();
+        let ValueRef::Choice(rate) = rate.value else {
+            panic!("expected a choice");
+        };
+        assert_eq!(rate.choice_type(), spa_sys::SPA_CHOICE_Step);
+        assert_eq!(rate.default::<f64>(), Some(1.0));
+        assert_eq!(rate.values::<f64>().unwrap().len(), 4);
+
+        assert!(object.property(spa_sys::SPA_PROP_mute).is_none());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn invalid() {
+        let mut buffer = serialize(&Value::Struct(vec![Value::Int(1), Value::Int(2)]));
+        let bytes = as_bytes_mut(&mut buffer);
+        // Truncate the size of the last field, so that it exceeds the struct.
+        let len = bytes.len();
+        bytes[len - 16..len - 12].copy_from_slice(&12u32.to_ne_bytes());
+
+        assert!(PodDeserializer::deserialize_any_ref_from(bytes).is_err());
+
+        let mut buffer = serialize(&Value::array([1i32, 2]));
+        let bytes = as_bytes_mut(&mut buffer);
+        // Claim the elements are longs, which don't have the size of the elements.
+        bytes[12..16].copy_from_slice(&spa_sys::SPA_TYPE_Long.to_ne_bytes());
+
+        assert_eq!(
+            PodDeserializer::deserialize_any_ref_from(bytes),
+            Err(DeserializeError::InvalidType)
+        );
+    }
+}
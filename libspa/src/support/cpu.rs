@@ -0,0 +1,225 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! The SPA CPU support interface.
+//!
+//! The interface can be used to query the features of the CPU, for example to select
+//! SIMD-optimized processing paths at runtime.
+
+use bitflags::bitflags;
+
+use crate::utils::result::{Error, SpaResult};
+
+bitflags! {
+    /// CPU feature flags.
+    ///
+    /// The meaning of the flags depends on the architecture, so only the flags of the
+    /// target architecture are available.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub struct CpuFlags: u32 {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        const MMX = spa_sys::SPA_CPU_FLAG_MMX;
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        const MMXEXT = spa_sys::SPA_CPU_FLAG_MMXEXT;
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        const SSE = spa_sys::SPA_CPU_FLAG_SSE;
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        const SSE2 = spa_sys::SPA_CPU_FLAG_SSE2;
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        const SSE3 = spa_sys::SPA_CPU_FLAG_SSE3;
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        const SSSE3 = spa_sys::SPA_CPU_FLAG_SSSE3;
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        const SSE41 = spa_sys::SPA_CPU_FLAG_SSE41;
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        const SSE42 = spa_sys::SPA_CPU_FLAG_SSE42;
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        const AESNI = spa_sys::SPA_CPU_FLAG_AESNI;
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        const AVX = spa_sys::SPA_CPU_FLAG_AVX;
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        const XOP = spa_sys::SPA_CPU_FLAG_XOP;
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        const FMA4 = spa_sys::SPA_CPU_FLAG_FMA4;
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        const CMOV = spa_sys::SPA_CPU_FLAG_CMOV;
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        const AVX2 = spa_sys::SPA_CPU_FLAG_AVX2;
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        const FMA3 = spa_sys::SPA_CPU_FLAG_FMA3;
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        const BMI1 = spa_sys::SPA_CPU_FLAG_BMI1;
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        const BMI2 = spa_sys::SPA_CPU_FLAG_BMI2;
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        const AVX512 = spa_sys::SPA_CPU_FLAG_AVX512;
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        const SLOW_UNALIGNED = spa_sys::SPA_CPU_FLAG_SLOW_UNALIGNED;
+
+        #[cfg(any(target_arch = "powerpc", target_arch = "powerpc64"))]
+        const ALTIVEC = spa_sys::SPA_CPU_FLAG_ALTIVEC;
+        #[cfg(any(target_arch = "powerpc", target_arch = "powerpc64"))]
+        const VSX = spa_sys::SPA_CPU_FLAG_VSX;
+        #[cfg(any(target_arch = "powerpc", target_arch = "powerpc64"))]
+        const POWER8 = spa_sys::SPA_CPU_FLAG_POWER8;
+
+        #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+        const ARMV5TE = spa_sys::SPA_CPU_FLAG_ARMV5TE;
+        #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+        const ARMV6 = spa_sys::SPA_CPU_FLAG_ARMV6;
+        #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+        const ARMV6T2 = spa_sys::SPA_CPU_FLAG_ARMV6T2;
+        #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+        const VFP = spa_sys::SPA_CPU_FLAG_VFP;
+        #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+        const VFPV3 = spa_sys::SPA_CPU_FLAG_VFPV3;
+        #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+        const NEON = spa_sys::SPA_CPU_FLAG_NEON;
+        #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+        const ARMV8 = spa_sys::SPA_CPU_FLAG_ARMV8;
+    }
+}
+
+bitflags! {
+    /// The kind of virtual machine the process is running in.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub struct VmType: u32 {
+        const OTHER = spa_sys::SPA_CPU_VM_OTHER;
+        const KVM = spa_sys::SPA_CPU_VM_KVM;
+        const QEMU = spa_sys::SPA_CPU_VM_QEMU;
+        const BOCHS = spa_sys::SPA_CPU_VM_BOCHS;
+        const XEN = spa_sys::SPA_CPU_VM_XEN;
+        const UML = spa_sys::SPA_CPU_VM_UML;
+        const VMWARE = spa_sys::SPA_CPU_VM_VMWARE;
+        const ORACLE = spa_sys::SPA_CPU_VM_ORACLE;
+        const MICROSOFT = spa_sys::SPA_CPU_VM_MICROSOFT;
+        const ZVM = spa_sys::SPA_CPU_VM_ZVM;
+        const PARALLELS = spa_sys::SPA_CPU_VM_PARALLELS;
+        const BHYVE = spa_sys::SPA_CPU_VM_BHYVE;
+        const QNX = spa_sys::SPA_CPU_VM_QNX;
+        const ACRN = spa_sys::SPA_CPU_VM_ACRN;
+        const POWERVM = spa_sys::SPA_CPU_VM_POWERVM;
+    }
+}
+
+/// A reference to a SPA CPU interface.
+///
+/// Usually obtained from the support interfaces of a context or plugin with [`CpuRef::from_support`].
+#[repr(transparent)]
+pub struct CpuRef(spa_sys::spa_cpu);
+
+impl CpuRef {
+    pub fn as_raw(&self) -> &spa_sys::spa_cpu {
+        &self.0
+    }
+
+    pub fn as_raw_ptr(&self) -> *mut spa_sys::spa_cpu {
+        std::ptr::addr_of!(self.0).cast_mut()
+    }
+
+    /// Find the CPU interface in a list of support interfaces.
+    pub fn from_support(support: &[spa_sys::spa_support]) -> Option<&Self> {
+        unsafe {
+            let cpu = spa_sys::spa_support_find(
+                support.as_ptr(),
+                support.len().try_into().unwrap(),
+                spa_sys::SPA_TYPE_INTERFACE_CPU.as_ptr().cast(),
+            );
+            (cpu as *const Self).as_ref()
+        }
+    }
+
+    fn methods_version(&self) -> u32 {
+        unsafe {
+            let funcs: *const spa_sys::spa_cpu_methods = self.0.iface.cb.funcs.cast();
+            (*funcs).version
+        }
+    }
+
+    /// Get the CPU feature flags, which may have been overridden with [`CpuRef::force_flags`].
+    pub fn flags(&self) -> CpuFlags {
+        let flags = unsafe {
+            crate::spa_interface_call_method!(
+                self.as_raw_ptr(),
+                spa_sys::spa_cpu_methods,
+                get_flags
+            )
+        };
+        CpuFlags::from_bits_retain(flags)
+    }
+
+    /// Force the CPU feature flags, or go back to the detected flags if `flags` is [`None`].
+    pub fn force_flags(&self, flags: Option<CpuFlags>) -> Result<(), Error> {
+        let flags = flags.map_or(spa_sys::SPA_CPU_FORCE_AUTODETECT, |f| f.bits());
+        let res = unsafe {
+            crate::spa_interface_call_method!(
+                self.as_raw_ptr(),
+                spa_sys::spa_cpu_methods,
+                force_flags,
+                flags
+            )
+        };
+        SpaResult::from_c(res).into_sync_result()?;
+        Ok(())
+    }
+
+    /// Get the number of CPU cores.
+    pub fn count(&self) -> u32 {
+        unsafe {
+            crate::spa_interface_call_method!(
+                self.as_raw_ptr(),
+                spa_sys::spa_cpu_methods,
+                get_count
+            )
+        }
+    }
+
+    /// Get the maximum memory alignment required by the SIMD instructions of the CPU.
+    pub fn max_align(&self) -> u32 {
+        unsafe {
+            crate::spa_interface_call_method!(
+                self.as_raw_ptr(),
+                spa_sys::spa_cpu_methods,
+                get_max_align
+            )
+        }
+    }
+
+    /// Get the kind of virtual machine the process is running in, if any.
+    ///
+    /// Returns [`None`] if the interface does not support querying the VM type.
+    pub fn vm_type(&self) -> Option<VmType> {
+        if self.methods_version() < 1 {
+            return None;
+        }
+
+        let vm_type = unsafe {
+            crate::spa_interface_call_method!(
+                self.as_raw_ptr(),
+                spa_sys::spa_cpu_methods,
+                get_vm_type
+            )
+        };
+        Some(VmType::from_bits_retain(vm_type))
+    }
+
+    /// Enable or disable flushing denormal floating point numbers to zero on the calling thread.
+    ///
+    /// Returns `Err` with `ENOTSUP` if the interface does not support it.
+    pub fn zero_denormals(&self, enable: bool) -> Result<(), Error> {
+        if self.methods_version() < 2 {
+            SpaResult::from_c(-libc::ENOTSUP).into_sync_result()?;
+        }
+
+        let res = unsafe {
+            crate::spa_interface_call_method!(
+                self.as_raw_ptr(),
+                spa_sys::spa_cpu_methods,
+                zero_denormals,
+                enable
+            )
+        };
+        SpaResult::from_c(res).into_sync_result()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,281 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! SPA logging.
+//!
+//! The [`Log`] type implements the SPA log interface on top of a Rust callback,
+//! which allows routing log messages emitted by SPA plugins and libpipewire into Rust code.
+
+use std::{
+    ffi::CStr,
+    os::raw::{c_char, c_int, c_void},
+    ptr,
+};
+
+/// A SPA log level.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct LogLevel(spa_sys::spa_log_level);
+
+#[allow(non_upper_case_globals)]
+impl LogLevel {
+    pub const None: Self = Self(spa_sys::SPA_LOG_LEVEL_NONE);
+    pub const Error: Self = Self(spa_sys::SPA_LOG_LEVEL_ERROR);
+    pub const Warn: Self = Self(spa_sys::SPA_LOG_LEVEL_WARN);
+    pub const Info: Self = Self(spa_sys::SPA_LOG_LEVEL_INFO);
+    pub const Debug: Self = Self(spa_sys::SPA_LOG_LEVEL_DEBUG);
+    pub const Trace: Self = Self(spa_sys::SPA_LOG_LEVEL_TRACE);
+
+    pub fn from_raw(raw: spa_sys::spa_log_level) -> Self {
+        Self(raw)
+    }
+
+    pub fn as_raw(&self) -> spa_sys::spa_log_level {
+        self.0
+    }
+}
+
+impl std::fmt::Debug for LogLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = format!(
+            "LogLevel::{}",
+            match *self {
+                Self::None => "None",
+                Self::Error => "Error",
+                Self::Warn => "Warn",
+                Self::Info => "Info",
+                Self::Debug => "Debug",
+                Self::Trace => "Trace",
+                _ => "Unknown",
+            }
+        );
+        f.write_str(&name)
+    }
+}
+
+/// A single formatted log message.
+#[derive(Debug, Clone, Copy)]
+pub struct LogRecord<'a> {
+    pub level: LogLevel,
+    /// The topic the message was logged with, if any.
+    pub topic: Option<&'a str>,
+    pub file: &'a str,
+    pub line: u32,
+    pub function: &'a str,
+    pub message: &'a str,
+}
+
+type LogCallback = dyn Fn(&LogRecord) + Send + Sync;
+
+/// An implementation of the SPA log interface that passes every message to a Rust callback.
+///
+/// Messages are formatted before the callback is called.
+/// The callback may be called from any thread that logs, including realtime threads.
+pub struct Log {
+    ptr: ptr::NonNull<spa_sys::spa_log>,
+    // Need to stay allocated while the log is alive
+    #[allow(dead_code)]
+    callback: Box<Box<LogCallback>>,
+}
+
+impl Log {
+    /// Create a new log that logs messages up to `level` to `callback`.
+    pub fn new<F>(level: LogLevel, callback: F) -> Self
+    where
+        F: Fn(&LogRecord) + Send + Sync + 'static,
+    {
+        unsafe extern "C" fn log_func(
+            data: *mut c_void,
+            level: spa_sys::spa_log_level,
+            topic: *const c_char,
+            file: *const c_char,
+            line: c_int,
+            func: *const c_char,
+            msg: *const c_char,
+        ) {
+            let callback = (data as *const Box<LogCallback>).as_ref().unwrap();
+
+            let to_str = |s: *const c_char| {
+                if s.is_null() {
+                    None
+                } else {
+                    Some(CStr::from_ptr(s).to_str().unwrap_or("<invalid utf-8>"))
+                }
+            };
+
+            let record = LogRecord {
+                level: LogLevel::from_raw(level),
+                topic: to_str(topic),
+                file: to_str(file).unwrap_or_default(),
+                line: line.try_into().unwrap_or_default(),
+                function: to_str(func).unwrap_or_default(),
+                message: to_str(msg).unwrap_or_default(),
+            };
+
+            callback(&record);
+        }
+
+        let callback: Box<Box<LogCallback>> = Box::new(Box::new(callback));
+        let data = &*callback as *const Box<LogCallback> as *mut c_void;
+
+        let ptr = unsafe { spa_sys::libspa_rs_log_new(level.as_raw(), Some(log_func), data) };
+        let ptr = ptr::NonNull::new(ptr).expect("failed to allocate log");
+
+        Self { ptr, callback }
+    }
+
+    /// Create a new log that forwards messages to the [`log`](https://docs.rs/log) crate.
+    ///
+    /// Messages are logged with the `pipewire` target, or `pipewire::<topic>` if the message has a topic.
+    /// The level of the returned log is taken from [`log::max_level()`].
+    #[cfg(feature = "log")]
+    pub fn with_log_crate() -> Self {
+        let level = match log::max_level() {
+            log::LevelFilter::Off => LogLevel::None,
+            log::LevelFilter::Error => LogLevel::Error,
+            log::LevelFilter::Warn => LogLevel::Warn,
+            log::LevelFilter::Info => LogLevel::Info,
+            log::LevelFilter::Debug => LogLevel::Debug,
+            log::LevelFilter::Trace => LogLevel::Trace,
+        };
+
+        Self::new(level, |record| {
+            let level = match record.level {
+                LogLevel::Error => log::Level::Error,
+                LogLevel::Warn => log::Level::Warn,
+                LogLevel::Info => log::Level::Info,
+                LogLevel::Debug => log::Level::Debug,
+                _ => log::Level::Trace,
+            };
+            let target = record
+                .topic
+                .map_or_else(|| "pipewire".to_string(), |t| format!("pipewire::{t}"));
+
+            log::logger().log(
+                &log::Record::builder()
+                    .level(level)
+                    .target(&target)
+                    .file(Some(record.file))
+                    .line(Some(record.line))
+                    .args(format_args!("{}", record.message))
+                    .build(),
+            );
+        })
+    }
+
+    /// Create a new log that emits messages as [`tracing`](https://docs.rs/tracing) events.
+    ///
+    /// Events are emitted with the `pipewire` target and carry the topic, file, line and function
+    /// of the message as fields.
+    /// The level of the returned log is taken from the current [`tracing::level_filters::LevelFilter`].
+    #[cfg(feature = "tracing")]
+    pub fn with_tracing() -> Self {
+        use tracing::level_filters::LevelFilter;
+
+        let level = match LevelFilter::current() {
+            LevelFilter::OFF => LogLevel::None,
+            LevelFilter::ERROR => LogLevel::Error,
+            LevelFilter::WARN => LogLevel::Warn,
+            LevelFilter::INFO => LogLevel::Info,
+            LevelFilter::DEBUG => LogLevel::Debug,
+            _ => LogLevel::Trace,
+        };
+
+        Self::new(level, |record| {
+            macro_rules! event {
+                ($level:expr) => {
+                    tracing::event!(
+                        target: "pipewire",
+                        $level,
+                        topic = record.topic,
+                        file = record.file,
+                        line = record.line,
+                        function = record.function,
+                        "{}",
+                        record.message
+                    )
+                };
+            }
+
+            match record.level {
+                LogLevel::Error => event!(tracing::Level::ERROR),
+                LogLevel::Warn => event!(tracing::Level::WARN),
+                LogLevel::Info => event!(tracing::Level::INFO),
+                LogLevel::Debug => event!(tracing::Level::DEBUG),
+                _ => event!(tracing::Level::TRACE),
+            }
+        })
+    }
+
+    pub fn as_raw(&self) -> &spa_sys::spa_log {
+        unsafe { self.ptr.as_ref() }
+    }
+
+    pub fn as_raw_ptr(&self) -> *mut spa_sys::spa_log {
+        self.ptr.as_ptr()
+    }
+
+    /// Get the maximum level of messages that are logged.
+    pub fn level(&self) -> LogLevel {
+        LogLevel::from_raw(self.as_raw().level)
+    }
+
+    /// Set the maximum level of messages that are logged.
+    pub fn set_level(&mut self, level: LogLevel) {
+        unsafe {
+            self.ptr.as_mut().level = level.as_raw();
+        }
+    }
+}
+
+impl Drop for Log {
+    fn drop(&mut self) {
+        unsafe { spa_sys::libspa_rs_log_free(self.ptr.as_ptr()) }
+    }
+}
+
+// The log is only accessed through the callback, which is required to be `Send + Sync`.
+unsafe impl Send for Log {}
+unsafe impl Sync for Log {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn log_callback() {
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let log = Log::new(LogLevel::Info, {
+            let messages = messages.clone();
+            move |record| {
+                messages.lock().unwrap().push((
+                    record.level,
+                    record.topic.map(str::to_string),
+                    record.line,
+                    record.message.to_string(),
+                ))
+            }
+        });
+        assert_eq!(log.level(), LogLevel::Info);
+
+        unsafe {
+            let iface = &log.as_raw().iface;
+            let methods = iface.cb.funcs as *const spa_sys::spa_log_methods;
+            (*methods).log.unwrap()(
+                iface.cb.data,
+                spa_sys::SPA_LOG_LEVEL_WARN,
+                b"file.c\0".as_ptr().cast(),
+                42,
+                b"func\0".as_ptr().cast(),
+                b"hello %d\0".as_ptr().cast(),
+                7 as c_int,
+            );
+        }
+
+        assert_eq!(
+            *messages.lock().unwrap(),
+            vec![(LogLevel::Warn, None, 42, "hello 7".to_string())]
+        );
+    }
+}
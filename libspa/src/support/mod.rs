@@ -1,4 +1,7 @@
 // Copyright The pipewire-rs Contributors.
 // SPDX-License-Identifier: MIT
 
+pub mod cpu;
+pub mod log;
+pub mod plugin;
 pub mod system;
@@ -0,0 +1,283 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! SPA plugins, the objects instantiated from their factories, and the interfaces they implement.
+//!
+//! ```no_run
+//! use libspa::support::plugin::{Plugin, TYPE_INTERFACE_DEVICE};
+//!
+//! let plugin = Plugin::open("/usr/lib/spa-0.2/v4l2/libspa-v4l2.so")?;
+//! let factory = plugin.find_factory("api.v4l2.enum.udev").expect("no v4l2 monitor");
+//! // Real code passes the support interfaces of its context, such as a loop and a system.
+//! let handle = factory.instantiate(None, &[])?;
+//! let device = handle.get_interface(TYPE_INTERFACE_DEVICE)?;
+//! # Ok::<(), libspa::utils::result::Error>(())
+//! ```
+
+use std::{
+    alloc::{self, Layout},
+    ffi::{c_void, CStr, CString},
+    marker::PhantomData,
+    ops::Deref,
+    os::unix::ffi::OsStrExt,
+    path::Path,
+    ptr,
+};
+
+use crate::utils::{
+    dict::DictRef,
+    result::{Error, SpaResult},
+};
+
+/// The type of the `spa_node` interface.
+pub const TYPE_INTERFACE_NODE: &str = "Spa:Pointer:Interface:Node";
+/// The type of the `spa_device` interface.
+pub const TYPE_INTERFACE_DEVICE: &str = "Spa:Pointer:Interface:Device";
+
+/// A reference to an object instantiated from a SPA plugin factory.
+///
+/// Use [`HandleRef::get_interface`] to access the interfaces implemented by the object.
+#[repr(transparent)]
+pub struct HandleRef(spa_sys::spa_handle);
+
+impl HandleRef {
+    pub fn as_raw(&self) -> &spa_sys::spa_handle {
+        &self.0
+    }
+
+    pub fn as_raw_ptr(&self) -> *mut spa_sys::spa_handle {
+        std::ptr::addr_of!(self.0).cast_mut()
+    }
+
+    /// Get the interface of type `type_`, such as [`TYPE_INTERFACE_NODE`], implemented by the object.
+    ///
+    /// # Panics
+    /// If `type_` contains a null byte.
+    pub fn get_interface(&self, type_: &str) -> Result<&InterfaceRef, Error> {
+        let type_ = CString::new(type_).expect("Null byte in type_ parameter");
+        let get_interface = self
+            .0
+            .get_interface
+            .expect("spa_handle without get_interface");
+
+        let mut interface: *mut c_void = ptr::null_mut();
+        let res = unsafe { get_interface(self.as_raw_ptr(), type_.as_ptr(), &mut interface) };
+        SpaResult::from_c(res).into_sync_result()?;
+
+        Ok(unsafe { &*(interface as *const InterfaceRef) })
+    }
+}
+
+/// A reference to a SPA interface, such as a node or a device, implemented by an object.
+#[repr(transparent)]
+pub struct InterfaceRef(spa_sys::spa_interface);
+
+impl InterfaceRef {
+    pub fn as_raw(&self) -> &spa_sys::spa_interface {
+        &self.0
+    }
+
+    pub fn as_raw_ptr(&self) -> *mut spa_sys::spa_interface {
+        std::ptr::addr_of!(self.0).cast_mut()
+    }
+
+    /// The type of the interface, such as [`TYPE_INTERFACE_NODE`].
+    pub fn type_(&self) -> &str {
+        unsafe { CStr::from_ptr(self.0.type_).to_str().unwrap() }
+    }
+
+    /// The version of the interface.
+    pub fn version(&self) -> u32 {
+        self.0.version
+    }
+}
+
+impl std::fmt::Debug for InterfaceRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InterfaceRef")
+            .field("type", &self.type_())
+            .field("version", &self.version())
+            .finish()
+    }
+}
+
+type HandleFactoryEnumFunc =
+    unsafe extern "C" fn(*mut *const spa_sys::spa_handle_factory, *mut u32) -> i32;
+
+/// A SPA plugin library, providing handle factories.
+pub struct Plugin {
+    library: ptr::NonNull<c_void>,
+    enum_func: HandleFactoryEnumFunc,
+}
+
+impl Plugin {
+    /// Load the plugin library at `path`.
+    ///
+    /// Fails with `ENOENT` if the library can't be loaded, and `EINVAL` if it isn't a SPA plugin.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = CString::new(path.as_ref().as_os_str().as_bytes())
+            .map_err(|_| Error::new(libc::EINVAL))?;
+
+        unsafe {
+            let library = libc::dlopen(path.as_ptr(), libc::RTLD_NOW);
+            let library = ptr::NonNull::new(library).ok_or_else(|| Error::new(libc::ENOENT))?;
+
+            let enum_func = libc::dlsym(
+                library.as_ptr(),
+                spa_sys::SPA_HANDLE_FACTORY_ENUM_FUNC_NAME.as_ptr().cast(),
+            );
+            if enum_func.is_null() {
+                libc::dlclose(library.as_ptr());
+                return Err(Error::new(libc::EINVAL));
+            }
+
+            Ok(Self {
+                library,
+                enum_func: std::mem::transmute::<*mut c_void, HandleFactoryEnumFunc>(enum_func),
+            })
+        }
+    }
+
+    /// Iterate over the handle factories provided by the plugin.
+    pub fn factories(&self) -> impl Iterator<Item = &HandleFactoryRef> {
+        let mut index = 0;
+        std::iter::from_fn(move || unsafe {
+            let mut factory: *const spa_sys::spa_handle_factory = ptr::null();
+            if (self.enum_func)(&mut factory, &mut index) <= 0 {
+                return None;
+            }
+            (factory as *const HandleFactoryRef).as_ref()
+        })
+    }
+
+    /// Find the handle factory named `name`, such as `api.v4l2.enum.udev`.
+    pub fn find_factory(&self, name: &str) -> Option<&HandleFactoryRef> {
+        self.factories().find(|factory| factory.name() == name)
+    }
+}
+
+impl Drop for Plugin {
+    fn drop(&mut self) {
+        unsafe {
+            libc::dlclose(self.library.as_ptr());
+        }
+    }
+}
+
+/// A factory instantiating objects of a [`Plugin`].
+#[repr(transparent)]
+pub struct HandleFactoryRef(spa_sys::spa_handle_factory);
+
+impl HandleFactoryRef {
+    pub fn as_raw(&self) -> &spa_sys::spa_handle_factory {
+        &self.0
+    }
+
+    pub fn as_raw_ptr(&self) -> *mut spa_sys::spa_handle_factory {
+        std::ptr::addr_of!(self.0).cast_mut()
+    }
+
+    /// The name of the factory.
+    pub fn name(&self) -> &str {
+        unsafe { CStr::from_ptr(self.0.name).to_str().unwrap() }
+    }
+
+    /// Extra information about the factory.
+    pub fn info(&self) -> Option<&DictRef> {
+        unsafe { (self.0.info as *const DictRef).as_ref() }
+    }
+
+    /// Instantiate an object, configured with `info` and using the `support` interfaces.
+    ///
+    /// Most plugins need at least a log, a system and a loop in `support`.
+    pub fn instantiate(
+        &self,
+        info: Option<&DictRef>,
+        support: &[spa_sys::spa_support],
+    ) -> Result<Handle<'_>, Error> {
+        let info = info.map_or(ptr::null(), |info| info.as_raw_ptr().cast_const());
+        let get_size = self
+            .0
+            .get_size
+            .expect("spa_handle_factory without get_size");
+        let init = self.0.init.expect("spa_handle_factory without init");
+
+        unsafe {
+            let size = get_size(self.as_raw_ptr(), info);
+            let layout = Layout::from_size_align(
+                size.max(std::mem::size_of::<spa_sys::spa_handle>()),
+                std::mem::align_of::<u128>(),
+            )
+            .map_err(|_| Error::new(libc::EINVAL))?;
+
+            let ptr = alloc::alloc_zeroed(layout);
+            let ptr = ptr::NonNull::new(ptr.cast::<spa_sys::spa_handle>())
+                .ok_or_else(|| Error::new(libc::ENOMEM))?;
+
+            let res = init(
+                self.as_raw_ptr(),
+                ptr.as_ptr(),
+                info,
+                support.as_ptr(),
+                support.len().try_into().unwrap(),
+            );
+            if let Err(e) = SpaResult::from_c(res).into_sync_result() {
+                alloc::dealloc(ptr.as_ptr().cast(), layout);
+                return Err(e);
+            }
+
+            Ok(Handle {
+                ptr,
+                layout,
+                _factory: PhantomData,
+            })
+        }
+    }
+}
+
+/// An object instantiated with [`HandleFactoryRef::instantiate`].
+///
+/// The object is cleared when this is dropped.
+pub struct Handle<'f> {
+    ptr: ptr::NonNull<spa_sys::spa_handle>,
+    layout: Layout,
+    _factory: PhantomData<&'f HandleFactoryRef>,
+}
+
+impl Deref for Handle<'_> {
+    type Target = HandleRef;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.ptr.cast::<HandleRef>().as_ref() }
+    }
+}
+
+impl Drop for Handle<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(clear) = (*self.ptr.as_ptr()).clear {
+                clear(self.ptr.as_ptr());
+            }
+            alloc::dealloc(self.ptr.as_ptr().cast(), self.layout);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interface_types() {
+        let sys_type = |t: &'static [u8]| CStr::from_bytes_with_nul(t).unwrap().to_str().unwrap();
+
+        assert_eq!(
+            TYPE_INTERFACE_NODE,
+            sys_type(spa_sys::SPA_TYPE_INTERFACE_Node)
+        );
+        assert_eq!(
+            TYPE_INTERFACE_DEVICE,
+            sys_type(spa_sys::SPA_TYPE_INTERFACE_Device)
+        );
+    }
+}
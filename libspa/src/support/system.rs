@@ -1,5 +1,9 @@
+use std::time::Duration;
+
 use bitflags::bitflags;
 
+use crate::utils::result::{Error, SpaResult};
+
 bitflags! {
     /// Flags used to specify different IO events.
     #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -14,3 +18,63 @@ bitflags! {
         const HUP = spa_sys::SPA_IO_HUP;
     }
 }
+
+/// A reference to a SPA system interface.
+///
+/// Usually obtained from the support interfaces of a context or plugin with [`SystemRef::from_support`].
+#[repr(transparent)]
+pub struct SystemRef(spa_sys::spa_system);
+
+impl SystemRef {
+    pub fn as_raw(&self) -> &spa_sys::spa_system {
+        &self.0
+    }
+
+    pub fn as_raw_ptr(&self) -> *mut spa_sys::spa_system {
+        std::ptr::addr_of!(self.0).cast_mut()
+    }
+
+    /// Find the system interface in a list of support interfaces.
+    ///
+    /// This returns the regular system interface. Realtime threads should use the one found with
+    /// [`SystemRef::data_from_support`] instead.
+    pub fn from_support(support: &[spa_sys::spa_support]) -> Option<&Self> {
+        Self::find(support, spa_sys::SPA_TYPE_INTERFACE_System)
+    }
+
+    /// Find the data system interface, which is safe to use from realtime threads, in a list of support interfaces.
+    pub fn data_from_support(support: &[spa_sys::spa_support]) -> Option<&Self> {
+        Self::find(support, spa_sys::SPA_TYPE_INTERFACE_DataSystem)
+    }
+
+    fn find<'a>(support: &'a [spa_sys::spa_support], type_: &[u8]) -> Option<&'a Self> {
+        unsafe {
+            let system = spa_sys::spa_support_find(
+                support.as_ptr(),
+                support.len().try_into().unwrap(),
+                type_.as_ptr().cast(),
+            );
+            (system as *const Self).as_ref()
+        }
+    }
+
+    /// Get the time of the clock `clock_id`, such as [`libc::CLOCK_MONOTONIC`].
+    pub fn clock_gettime(&self, clock_id: libc::clockid_t) -> Result<Duration, Error> {
+        let mut value: spa_sys::timespec = unsafe { std::mem::zeroed() };
+        let res = unsafe {
+            crate::spa_interface_call_method!(
+                self.as_raw_ptr(),
+                spa_sys::spa_system_methods,
+                clock_gettime,
+                clock_id,
+                &mut value
+            )
+        };
+        SpaResult::from_c(res).into_sync_result()?;
+
+        Ok(Duration::new(
+            value.tv_sec.try_into().unwrap(),
+            value.tv_nsec.try_into().unwrap(),
+        ))
+    }
+}
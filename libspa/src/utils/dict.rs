@@ -6,7 +6,7 @@
 use bitflags::bitflags;
 // re-exported as used in the static_dict! macro implementation
 pub use spa_sys::spa_dict_item;
-use std::{convert::TryInto, ffi::CStr, fmt, marker::PhantomData, ptr};
+use std::{collections::HashMap, convert::TryInto, ffi::CStr, fmt, marker::PhantomData, ptr};
 
 #[repr(transparent)]
 pub struct DictRef(spa_sys::spa_dict);
@@ -131,6 +131,144 @@ impl DictRef {
                 }),
             })
     }
+
+    /// Copy all key-value pairs that are valid utf-8 into a [`HashMap`].
+    pub fn to_hashmap(&self) -> HashMap<String, String> {
+        self.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    /// Compare the dict with a `new` version of it.
+    ///
+    /// Only the key-value pairs that are valid utf-8 are compared.
+    ///
+    /// # Examples
+    /// ```
+    /// use libspa::static_dict;
+    ///
+    /// let old = static_dict! { "K0" => "V0", "K1" => "V1" };
+    /// let new = static_dict! { "K1" => "V2", "K2" => "V3" };
+    ///
+    /// let diff = old.diff(&new);
+    /// assert_eq!(diff.added, [("K2", "V3")]);
+    /// assert_eq!(diff.removed, [("K0", "V0")]);
+    /// assert_eq!(diff.changed, [("K1", "V1", "V2")]);
+    /// ```
+    pub fn diff<'a>(&'a self, new: &'a DictRef) -> DictDiff<'a> {
+        let old_map: HashMap<&str, &str> = self.iter().collect();
+        let new_map: HashMap<&str, &str> = new.iter().collect();
+
+        let mut diff = DictDiff::default();
+        for (key, value) in new.iter() {
+            match old_map.get(key) {
+                None => diff.added.push((key, value)),
+                Some(old_value) if *old_value != value => {
+                    diff.changed.push((key, old_value, value))
+                }
+                Some(_) => {}
+            }
+        }
+        for (key, value) in self.iter() {
+            if !new_map.contains_key(key) {
+                diff.removed.push((key, value));
+            }
+        }
+
+        diff
+    }
+}
+
+/// The differences between two versions of a dictionary, see [`DictRef::diff`].
+///
+/// The pairs are in the order of the dictionary they come from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DictDiff<'a> {
+    /// The key-value pairs only in the new dict.
+    pub added: Vec<(&'a str, &'a str)>,
+    /// The key-value pairs only in the old dict.
+    pub removed: Vec<(&'a str, &'a str)>,
+    /// The keys with a different value, with their old and new value.
+    pub changed: Vec<(&'a str, &'a str, &'a str)>,
+}
+
+impl DictDiff<'_> {
+    /// Returns `true` if both dicts hold the same key-value pairs.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Read access shared by all dictionary types.
+///
+/// This is implemented for every type that can be viewed as a [`DictRef`],
+/// such as [`StaticDict`] or `pipewire::properties::Properties`.
+pub trait ReadableDict {
+    /// Get the dictionary as a [`DictRef`].
+    fn as_dict(&self) -> &DictRef;
+
+    /// See [`DictRef::get`].
+    fn get(&self, key: &str) -> Option<&str> {
+        self.as_dict().get(key)
+    }
+
+    /// See [`DictRef::parse`].
+    fn parse<T: ParsableValue>(&self, key: &str) -> Option<Result<T, ParseValueError>> {
+        self.as_dict().parse(key)
+    }
+
+    /// See [`DictRef::iter`].
+    fn iter(&self) -> Iter {
+        self.as_dict().iter()
+    }
+
+    /// See [`DictRef::keys`].
+    fn keys(&self) -> Keys {
+        self.as_dict().keys()
+    }
+
+    /// See [`DictRef::values`].
+    fn values(&self) -> Values {
+        self.as_dict().values()
+    }
+
+    /// See [`DictRef::len`].
+    fn len(&self) -> usize {
+        self.as_dict().len()
+    }
+
+    /// See [`DictRef::is_empty`].
+    fn is_empty(&self) -> bool {
+        self.as_dict().is_empty()
+    }
+
+    /// See [`DictRef::to_hashmap`].
+    fn to_hashmap(&self) -> HashMap<String, String> {
+        self.as_dict().to_hashmap()
+    }
+
+    /// See [`DictRef::diff`].
+    fn diff<'a, D: ReadableDict + ?Sized>(&'a self, new: &'a D) -> DictDiff<'a> {
+        self.as_dict().diff(new.as_dict())
+    }
+}
+
+impl<T: AsRef<DictRef> + ?Sized> ReadableDict for T {
+    fn as_dict(&self) -> &DictRef {
+        self.as_ref()
+    }
+}
+
+/// Write access shared by all owned, mutable dictionary types.
+pub trait WritableDict {
+    /// Insert the key-value pair, overwriting any old value.
+    fn insert<K: Into<Vec<u8>>, V: Into<Vec<u8>>>(&mut self, key: K, value: V);
+
+    /// Remove the key-value pair if it exists.
+    fn remove<K: Into<Vec<u8>>>(&mut self, key: K);
+
+    /// Clear the object, removing all key-value pairs.
+    fn clear(&mut self);
 }
 
 impl AsRef<Self> for DictRef {
@@ -383,8 +521,8 @@ macro_rules! static_dict {
             )+
         ];
 
-        static mut RAW: spa_sys::spa_dict = unsafe {
-            spa_sys::spa_dict {
+        static mut RAW: $crate::sys::spa_dict = unsafe {
+            $crate::sys::spa_dict {
                 flags: Flags::empty().bits(),
                 n_items: ITEMS.len() as u32,
                 items: ITEMS.as_ptr(),
@@ -398,6 +536,12 @@ macro_rules! static_dict {
     }};
 }
 
+impl AsRef<DictRef> for StaticDict {
+    fn as_ref(&self) -> &DictRef {
+        self
+    }
+}
+
 impl std::ops::Deref for StaticDict {
     type Target = DictRef;
 
@@ -533,6 +677,59 @@ mod tests {
         assert_eq!(DICT.get("K1"), Some("V1"));
     }
 
+    #[test]
+    fn to_hashmap() {
+        let dict = static_dict! {
+            "K0" => "V0",
+            "K1" => "V1"
+        };
+
+        let map = dict.to_hashmap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("K0").map(String::as_str), Some("V0"));
+        assert_eq!(map.get("K1").map(String::as_str), Some("V1"));
+    }
+
+    #[test]
+    fn diff() {
+        static OLD: StaticDict = static_dict! {
+            "K0" => "V0",
+            "K1" => "V1",
+            "K2" => "V2"
+        };
+        static NEW: StaticDict = static_dict! {
+            "K3" => "V3",
+            "K2" => "V2",
+            "K1" => "V4"
+        };
+
+        let diff = OLD.diff(&NEW);
+        assert_eq!(diff.added, [("K3", "V3")]);
+        assert_eq!(diff.removed, [("K0", "V0")]);
+        assert_eq!(diff.changed, [("K1", "V1", "V4")]);
+        assert!(!diff.is_empty());
+
+        assert_eq!(NEW.diff(&OLD).added, [("K0", "V0")]);
+        assert!(OLD.diff(&OLD).is_empty());
+    }
+
+    #[test]
+    fn readable_dict() {
+        use super::ReadableDict;
+
+        fn get_k0<D: ReadableDict + ?Sized>(dict: &D) -> Option<&str> {
+            dict.get("K0")
+        }
+
+        static DICT: StaticDict = static_dict! {
+            "K0" => "V0"
+        };
+
+        assert_eq!(get_k0(&DICT), Some("V0"));
+        assert_eq!(get_k0::<DictRef>(&DICT), Some("V0"));
+        assert_eq!(ReadableDict::len(&DICT), 1);
+    }
+
     #[test]
     fn parse() {
         use super::ParseValueError;
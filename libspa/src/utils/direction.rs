@@ -3,45 +3,60 @@
 
 //! SPA direction.
 
-#[derive(Copy, Clone, PartialEq, Eq)]
-pub struct Direction(spa_sys::spa_direction);
+/// The direction of a port or stream.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum Direction {
+    Input = spa_sys::SPA_DIRECTION_INPUT,
+    Output = spa_sys::SPA_DIRECTION_OUTPUT,
+}
 
-#[allow(non_upper_case_globals)]
 impl Direction {
-    pub const Input: Self = Self(spa_sys::SPA_DIRECTION_INPUT);
-    pub const Output: Self = Self(spa_sys::SPA_DIRECTION_OUTPUT);
+    /// Reverse a raw direction, like the `SPA_DIRECTION_REVERSE` C macro.
+    pub const REVERSE: fn(Self) -> Self = Self::reverse;
 
-    pub fn from_raw(raw: spa_sys::spa_direction) -> Self {
-        Self(raw)
+    /// Convert a raw direction, returning `None` if `raw` is not a valid direction.
+    pub fn from_raw(raw: spa_sys::spa_direction) -> Option<Self> {
+        Self::try_from(raw).ok()
     }
 
     pub fn as_raw(&self) -> spa_sys::spa_direction {
-        self.0
+        *self as spa_sys::spa_direction
     }
 
     /// Return a new [`Direction`] in the opposite direction, turning Input to Output, and Output to Input.
-    ///
-    /// An unknown/invalid direction is unchanged.
-    pub fn reverse(&self) -> Self {
-        match *self {
+    pub const fn reverse(self) -> Self {
+        match self {
             Self::Input => Self::Output,
             Self::Output => Self::Input,
-            _ => *self,
         }
     }
 }
 
-impl std::fmt::Debug for Direction {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let name = format!(
-            "Direction::{}",
-            match *self {
-                Self::Input => "Input",
-                Self::Output => "Output",
-                _ => "Unknown",
-            }
-        );
-        f.write_str(&name)
+impl From<Direction> for u32 {
+    fn from(direction: Direction) -> Self {
+        direction.as_raw()
+    }
+}
+
+impl TryFrom<u32> for Direction {
+    /// The unknown raw direction.
+    type Error = u32;
+
+    fn try_from(raw: u32) -> Result<Self, Self::Error> {
+        match raw {
+            spa_sys::SPA_DIRECTION_INPUT => Ok(Self::Input),
+            spa_sys::SPA_DIRECTION_OUTPUT => Ok(Self::Output),
+            _ => Err(raw),
+        }
+    }
+}
+
+impl std::ops::Not for Direction {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        self.reverse()
     }
 }
 
@@ -58,18 +73,31 @@ mod tests {
     #[test]
     fn from_raw() {
         assert_eq!(
-            Direction::Input,
+            Some(Direction::Input),
             Direction::from_raw(spa_sys::SPA_DIRECTION_INPUT)
         );
         assert_eq!(
-            Direction::Output,
+            Some(Direction::Output),
             Direction::from_raw(spa_sys::SPA_DIRECTION_OUTPUT)
         );
+        assert_eq!(None, Direction::from_raw(42));
+    }
+
+    #[test]
+    fn try_from() {
+        assert_eq!(
+            Direction::try_from(spa_sys::SPA_DIRECTION_OUTPUT),
+            Ok(Direction::Output)
+        );
+        assert_eq!(Direction::try_from(42), Err(42));
+        assert_eq!(u32::from(Direction::Input), spa_sys::SPA_DIRECTION_INPUT);
     }
 
     #[test]
     fn reverse() {
         assert_eq!(Direction::Output.reverse(), Direction::Input);
         assert_eq!(Direction::Input.reverse(), Direction::Output);
+        assert_eq!(!Direction::Input, Direction::Output);
+        assert_eq!(Direction::REVERSE(Direction::Output), Direction::Input);
     }
 }
@@ -0,0 +1,170 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Delay-locked loop and rate matching helpers.
+//!
+//! These are the building blocks used to compensate the clock drift between
+//! a PipeWire graph and an external clock, such as a network source or a second sound card.
+
+/// A delay-locked loop, wrapping `spa_dll`.
+///
+/// The loop is fed with a timing error and returns a correction factor
+/// that converges towards the ratio between the two clocks.
+#[derive(Clone)]
+pub struct Dll(spa_sys::spa_dll);
+
+impl Dll {
+    /// The maximum recommended bandwidth, for fast but noisy convergence.
+    pub const BW_MAX: f64 = spa_sys::SPA_DLL_BW_MAX;
+    /// The minimum recommended bandwidth, for slow but stable convergence.
+    pub const BW_MIN: f64 = spa_sys::SPA_DLL_BW_MIN;
+
+    /// Create a new delay-locked loop.
+    ///
+    /// [`Dll::set_bw`] must be called before the loop is updated.
+    pub fn new() -> Self {
+        unsafe {
+            let mut dll: spa_sys::spa_dll = std::mem::zeroed();
+            spa_sys::spa_dll_init(&mut dll);
+            Self(dll)
+        }
+    }
+
+    pub fn as_raw(&self) -> &spa_sys::spa_dll {
+        &self.0
+    }
+
+    /// Get the bandwidth of the loop.
+    pub fn bw(&self) -> f64 {
+        self.0.bw
+    }
+
+    /// Set the bandwidth of the loop, for updates happening every `period` samples at `rate` Hz.
+    pub fn set_bw(&mut self, bw: f64, period: u32, rate: u32) {
+        unsafe { spa_sys::spa_dll_set_bw(&mut self.0, bw, period, rate) }
+    }
+
+    /// Feed the timing error `err` into the loop, returning the new correction factor.
+    pub fn update(&mut self, err: f64) -> f64 {
+        unsafe { spa_sys::spa_dll_update(&mut self.0, err) }
+    }
+}
+
+impl Default for Dll {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for Dll {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Dll").field("bw", &self.bw()).finish()
+    }
+}
+
+/// Computes resampling ratios that keep the amount of buffered audio at a target level.
+///
+/// Call [`RateMatchHelper::update`] (or [`RateMatchHelper::update_io`] when driving a
+/// `spa_io_rate_match` area) once per cycle with the number of frames currently buffered.
+/// The returned ratio should be applied to the resampler, for example with the `rate` control
+/// of a stream.
+#[derive(Debug, Clone)]
+pub struct RateMatchHelper {
+    dll: Dll,
+    target_delay: f64,
+    max_error: f64,
+}
+
+impl RateMatchHelper {
+    /// The default maximum error, in frames, fed into the loop in one cycle.
+    pub const DEFAULT_MAX_ERROR: f64 = 256.0;
+
+    /// Create a new helper trying to keep `target_delay` frames buffered,
+    /// updated every `period` frames at `rate` Hz.
+    pub fn new(target_delay: u32, period: u32, rate: u32) -> Self {
+        let mut dll = Dll::new();
+        dll.set_bw(Dll::BW_MIN, period, rate);
+
+        Self {
+            dll,
+            target_delay: target_delay.into(),
+            max_error: Self::DEFAULT_MAX_ERROR,
+        }
+    }
+
+    /// Get the underlying delay-locked loop.
+    pub fn dll(&self) -> &Dll {
+        &self.dll
+    }
+
+    /// Get the number of frames the helper tries to keep buffered.
+    pub fn target_delay(&self) -> f64 {
+        self.target_delay
+    }
+
+    /// Set the number of frames the helper tries to keep buffered.
+    pub fn set_target_delay(&mut self, target_delay: f64) {
+        self.target_delay = target_delay;
+    }
+
+    /// Set the maximum error, in frames, fed into the loop in one cycle.
+    ///
+    /// Larger errors are clamped, so that a sudden jump in the buffer level doesn't
+    /// cause an audible change of the rate.
+    pub fn set_max_error(&mut self, max_error: f64) {
+        self.max_error = max_error;
+    }
+
+    /// Reset the loop, for example after an xrun or a change of the period or rate.
+    pub fn reset(&mut self, period: u32, rate: u32) {
+        let bw = self.dll.bw();
+        self.dll = Dll::new();
+        self.dll.set_bw(bw, period, rate);
+    }
+
+    /// Update the loop with the number of frames currently buffered, returning the new resampling ratio.
+    pub fn update(&mut self, delay: f64) -> f64 {
+        let error = (self.target_delay - delay).clamp(-self.max_error, self.max_error);
+        self.dll.update(error)
+    }
+
+    /// Update the loop from a rate match IO area and the number of frames currently buffered.
+    ///
+    /// The delay of the resampler reported in `io` is added to `buffered`, and the new
+    /// ratio is written back to `io`, as well as returned.
+    pub fn update_io(&mut self, io: &mut spa_sys::spa_io_rate_match, buffered: f64) -> f64 {
+        let rate = self.update(buffered + f64::from(io.delay));
+        io.rate = rate;
+        rate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn dll() {
+        let mut dll = Dll::new();
+        dll.set_bw(Dll::BW_MIN, 1024, 48000);
+        assert_eq!(dll.bw(), Dll::BW_MIN);
+
+        assert_eq!(dll.update(0.0), 1.0);
+        assert!(dll.update(100.0) < 1.0);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn rate_match() {
+        let mut helper = RateMatchHelper::new(1024, 1024, 48000);
+        assert_eq!(helper.update(1024.0), 1.0);
+
+        let mut io: spa_sys::spa_io_rate_match = unsafe { std::mem::zeroed() };
+        io.delay = 64;
+        // More frames buffered than the target, so the consumer needs to speed up.
+        let rate = helper.update_io(&mut io, 2048.0);
+        assert!(rate > 1.0);
+        assert_eq!(io.rate, rate);
+    }
+}
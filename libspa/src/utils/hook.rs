@@ -2,6 +2,16 @@
 // SPDX-License-Identifier: MIT
 
 //! SPA hook
+//!
+//! Objects implementing a SPA interface notify their listeners through a list of hooks. Each
+//! hook holds a struct of event callbacks and the data passed back to them, and is added to the
+//! list by the `add_listener` method of the interface. [`HookList`] maintains such a list for
+//! interfaces implemented in Rust, and [`spa_hook_list_emit!`](crate::spa_hook_list_emit) emits
+//! an event to its hooks.
+
+use std::{cell::UnsafeCell, pin::Pin, ptr};
+
+use libc::c_void;
 
 use crate::utils::list;
 
@@ -16,6 +26,257 @@ pub fn remove(mut hook: spa_sys::spa_hook) {
     }
 }
 
+/// The cursor of an emission, unlinked from the list when dropped, even if a callback panics.
+struct Cursor(Pin<Box<spa_sys::spa_hook>>);
+
+impl Drop for Cursor {
+    fn drop(&mut self) {
+        list::remove(&self.0.link);
+    }
+}
+
+/// Set the callback called with `hook` when it is removed from its list, and the private data
+/// it can retrieve from the `priv_` field of the hook.
+///
+/// This is how the implementation of an interface is notified that a listener went away.
+///
+/// # Safety
+/// `hook` must be valid for writes.
+pub unsafe fn set_removed(
+    hook: *mut spa_sys::spa_hook,
+    removed: unsafe extern "C" fn(*mut spa_sys::spa_hook),
+    priv_: *mut c_void,
+) {
+    (*hook).removed = Some(removed);
+    (*hook).priv_ = priv_;
+}
+
+/// A list of hooks, the listeners of an object implementing a SPA interface.
+///
+/// The hooks are owned by the listeners, which remove them from the list, for example with
+/// [`remove`]. The hooks left in the list are removed when it is dropped, calling their
+/// `removed` callback.
+pub struct HookList {
+    // Boxed so the hooks can link to it, and mutated through the pointers of the hooks.
+    list: Box<UnsafeCell<spa_sys::spa_hook_list>>,
+}
+
+impl HookList {
+    /// Create an empty list.
+    pub fn new() -> Self {
+        let list: Box<UnsafeCell<spa_sys::spa_hook_list>> =
+            Box::new(UnsafeCell::new(unsafe { std::mem::zeroed() }));
+        // Safety: the list is boxed, so it can link to itself.
+        unsafe { list::init(ptr::addr_of_mut!((*list.get()).list)) };
+
+        Self { list }
+    }
+
+    pub fn as_raw(&self) -> &spa_sys::spa_hook_list {
+        unsafe { &*self.list.get() }
+    }
+
+    /// The pointer to the list, to pass to C functions.
+    ///
+    /// The list must only be modified through the functions of SPA, such as
+    /// `spa_hook_list_append`.
+    pub fn as_raw_ptr(&self) -> *mut spa_sys::spa_hook_list {
+        self.list.get()
+    }
+
+    fn head(&self) -> *mut spa_sys::spa_list {
+        // Safety: the list is a valid allocation.
+        unsafe { ptr::addr_of_mut!((*self.as_raw_ptr()).list) }
+    }
+
+    /// Add `hook` at the end of the list, with the event callbacks `funcs` and their `data`.
+    ///
+    /// This is what the `add_listener` method of an interface does.
+    ///
+    /// # Safety
+    /// `hook` must be valid, not part of a list, and stay at the same address until it is removed
+    /// from the list. `funcs` must point to the events struct expected by the emitters of the list.
+    pub unsafe fn append(
+        &self,
+        hook: *mut spa_sys::spa_hook,
+        funcs: *const c_void,
+        data: *mut c_void,
+    ) {
+        Self::init_hook(hook, funcs, data);
+        list::insert((*self.head()).prev, ptr::addr_of_mut!((*hook).link));
+    }
+
+    /// Add `hook` at the start of the list, see [`HookList::append`].
+    ///
+    /// # Safety
+    /// Same as [`HookList::append`].
+    pub unsafe fn prepend(
+        &self,
+        hook: *mut spa_sys::spa_hook,
+        funcs: *const c_void,
+        data: *mut c_void,
+    ) {
+        Self::init_hook(hook, funcs, data);
+        list::insert(self.head(), ptr::addr_of_mut!((*hook).link));
+    }
+
+    unsafe fn init_hook(hook: *mut spa_sys::spa_hook, funcs: *const c_void, data: *mut c_void) {
+        (*hook).cb.funcs = funcs;
+        (*hook).cb.data = data;
+    }
+
+    /// The number of hooks in the list.
+    pub fn len(&self) -> usize {
+        let head = self.head();
+        let mut len = 0;
+        // Safety: the hooks stay valid while they are in the list.
+        unsafe {
+            let mut link = (*head).next;
+            while link != head {
+                if !(*link.cast::<spa_sys::spa_hook>()).cb.funcs.is_null() {
+                    len += 1;
+                }
+                link = (*link).next;
+            }
+        }
+        len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Call `f` with the event callbacks and the data of each hook, returning the number of
+    /// hooks `f` returned `true` for.
+    ///
+    /// Hooks can be added and removed by the callbacks, including the hook being called, and
+    /// events can be emitted again from a callback, like with the `spa_hook_list_call` macro of C.
+    /// Hooks added while emitting are called too.
+    ///
+    /// See [`spa_hook_list_emit!`](crate::spa_hook_list_emit) to call a method of the events.
+    ///
+    /// # Safety
+    /// The callbacks of all the hooks must be an `E`.
+    pub unsafe fn emit<E>(&self, mut f: impl FnMut(&E, *mut c_void) -> bool) -> usize {
+        let head = self.head();
+        // The cursor is a hook without callbacks inserted after the hook being called,
+        // so the iteration continues from it even if that hook is removed.
+        let mut guard = Cursor(Box::pin(std::mem::zeroed()));
+        let cursor = ptr::addr_of_mut!(guard.0.as_mut().get_unchecked_mut().link);
+        list::insert(head, cursor);
+
+        let mut count = 0;
+        loop {
+            let link = (*cursor).next;
+            if link == head {
+                break;
+            }
+            list::remove(&*cursor);
+            list::insert(link, cursor);
+
+            // The cursors of other emissions have no callbacks.
+            let hook = link.cast::<spa_sys::spa_hook>();
+            let (funcs, data) = ((*hook).cb.funcs, (*hook).cb.data);
+            if let Some(funcs) = funcs.cast::<E>().as_ref() {
+                if f(funcs, data) {
+                    count += 1;
+                }
+            }
+        }
+
+        drop(guard);
+        count
+    }
+
+    /// Remove all the hooks, calling their `removed` callback.
+    ///
+    /// This can be called while emitting, the emissions in progress then stop after the hook
+    /// being called.
+    pub fn clean(&self) {
+        let head = self.head();
+        // Safety: the hooks stay valid while they are in the list, and are not used by the list
+        // once removed.
+        unsafe {
+            loop {
+                // The cursors of the emissions in progress have no callbacks, and are left to the
+                // emissions, which still iterate from them.
+                let mut link = (*head).next;
+                while link != head && (*link.cast::<spa_sys::spa_hook>()).cb.funcs.is_null() {
+                    link = (*link).next;
+                }
+                if link == head {
+                    break;
+                }
+
+                let hook = link.cast::<spa_sys::spa_hook>();
+                list::remove(&(*hook).link);
+                if let Some(removed) = (*hook).removed {
+                    removed(hook);
+                }
+            }
+        }
+    }
+}
+
+impl Default for HookList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for HookList {
+    fn drop(&mut self) {
+        self.clean();
+    }
+}
+
+impl std::fmt::Debug for HookList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HookList")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Emit an event to the hooks of a [`HookList`], calling the method `$method` of the events
+/// struct `$events_struct` of each hook which has it.
+///
+/// This needs to be called from within an `unsafe` block, as the events of the hooks of the list
+/// must be a `$events_struct`.
+///
+/// The macro takes at least four arguments:
+/// 1. The hook list.
+/// 2. The type of the events struct, which must start with its `u32` version.
+/// 3. The name of the method to call.
+/// 4. The version of the events struct in which the method was added, the method is not called
+///    for hooks whose events struct is older.
+///
+/// All additional arguments are passed to the method, after the data of the hook.
+/// The macro returns the number of hooks whose method was called.
+///
+/// # Examples
+/// ```
+/// use libspa::{spa_hook_list_emit, sys as spa_sys, utils::hook::HookList};
+///
+/// fn emit_info(listeners: &HookList, info: *const spa_sys::spa_device_info) -> usize {
+///     unsafe {
+///         spa_hook_list_emit!(listeners, spa_sys::spa_device_events, info, 0, info)
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! spa_hook_list_emit {
+    ($list:expr, $events_struct:ty, $method:ident, $version:expr $(, $arg:expr)* $(,)?) => {{
+        $list.emit::<$events_struct>(|events, data| match events.$method {
+            Some(f) if events.version >= $version => {
+                f(data, $($arg),*);
+                true
+            }
+            _ => false,
+        })
+    }};
+}
+
 /// Call a method on a spa_interface.
 ///
 /// This needs to be called from within an `unsafe` block.
@@ -29,6 +290,9 @@ pub fn remove(mut hook: spa_sys::spa_hook) {
 ///
 /// The macro returns whatever the called method returns, for example an `i32`, or `()` if the method returns nothing.
 ///
+/// The macro does not check that the object implements the method, see
+/// [`VersionedInterface`](crate::utils::interface::VersionedInterface) for a checked alternative.
+///
 /// # Examples
 /// Here we call the sync method on a `pipewire_sys::pw_core` object.
 /// ```
@@ -51,11 +315,158 @@ pub fn remove(mut hook: spa_sys::spa_hook) {
 /// ```
 #[macro_export]
 macro_rules! spa_interface_call_method {
-    ($interface_ptr:expr, $methods_struct:ty, $method:ident, $( $arg:expr ),*) => {{
-        let iface: *mut spa_sys::spa_interface = $interface_ptr.cast();
+    ($interface_ptr:expr, $methods_struct:ty, $method:ident $(, $arg:expr)* $(,)?) => {{
+        let iface: *mut $crate::sys::spa_interface = $interface_ptr.cast();
         let funcs: *const $methods_struct = (*iface).cb.funcs.cast();
         let f = (*funcs).$method.unwrap();
 
         f((*iface).cb.data, $($arg),*)
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[repr(C)]
+    struct Events {
+        version: u32,
+        event: Option<unsafe extern "C" fn(data: *mut c_void, value: u32)>,
+    }
+
+    struct Listener {
+        hook: spa_sys::spa_hook,
+        calls: RefCell<Vec<u32>>,
+        /// Whether to remove the hook from its list when called.
+        remove_self: bool,
+    }
+
+    unsafe extern "C" fn event(data: *mut c_void, value: u32) {
+        let listener = data.cast::<Listener>();
+        (*listener).calls.borrow_mut().push(value);
+        if (*listener).remove_self {
+            list::remove(&(*listener).hook.link);
+        }
+    }
+
+    unsafe extern "C" fn removed(hook: *mut spa_sys::spa_hook) {
+        *(*hook).priv_.cast::<bool>() = true;
+    }
+
+    static EVENTS: Events = Events {
+        version: 1,
+        event: Some(event),
+    };
+    static OLD_EVENTS: Events = Events {
+        version: 0,
+        event: Some(event),
+    };
+
+    unsafe extern "C" fn clean(data: *mut c_void, _value: u32) {
+        (*data.cast::<HookList>()).clean();
+    }
+
+    static CLEAN_EVENTS: Events = Events {
+        version: 1,
+        event: Some(clean),
+    };
+
+    fn listener(remove_self: bool) -> *mut Listener {
+        Box::into_raw(Box::new(Listener {
+            hook: unsafe { std::mem::zeroed() },
+            calls: RefCell::new(Vec::new()),
+            remove_self,
+        }))
+    }
+
+    unsafe fn append(list: &HookList, listener: *mut Listener, events: &'static Events) {
+        let hook = ptr::addr_of_mut!((*listener).hook);
+        list.append(hook, ptr::addr_of!(*events).cast(), listener.cast());
+    }
+
+    #[test]
+    fn emit() {
+        let list = HookList::new();
+        let (first, second, old) = (listener(true), listener(false), listener(false));
+        let mut second_removed = false;
+
+        unsafe {
+            append(&list, first, &EVENTS);
+            append(&list, second, &EVENTS);
+            set_removed(
+                ptr::addr_of_mut!((*second).hook),
+                removed,
+                ptr::addr_of_mut!(second_removed).cast(),
+            );
+            let hook = ptr::addr_of_mut!((*old).hook);
+            list.prepend(hook, ptr::addr_of!(OLD_EVENTS).cast(), old.cast());
+        }
+        assert_eq!(list.len(), 3);
+
+        // The first listener removes itself while being called.
+        assert_eq!(unsafe { spa_hook_list_emit!(list, Events, event, 1, 1) }, 2);
+        assert_eq!(unsafe { spa_hook_list_emit!(list, Events, event, 1, 2) }, 1);
+        assert_eq!(unsafe { spa_hook_list_emit!(list, Events, event, 0, 3) }, 2);
+        assert_eq!(list.len(), 2);
+
+        drop(list);
+        assert!(second_removed);
+
+        let (first, second, old) = unsafe {
+            (
+                Box::from_raw(first),
+                Box::from_raw(second),
+                Box::from_raw(old),
+            )
+        };
+        assert_eq!(*first.calls.borrow(), [1]);
+        assert_eq!(*second.calls.borrow(), [1, 2, 3]);
+        assert_eq!(*old.calls.borrow(), [3]);
+    }
+
+    #[test]
+    fn emit_panic() {
+        let list = HookList::new();
+        let other = listener(false);
+        unsafe { append(&list, other, &EVENTS) };
+
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| unsafe {
+            list.emit::<Events>(|_, _| panic!("callback panicked"))
+        }));
+        assert!(res.is_err());
+
+        // The cursor was unlinked, leaving only the hook in the list.
+        unsafe {
+            let head = list.head();
+            assert_eq!((*(*head).next).next, head);
+        }
+
+        drop(list);
+        drop(unsafe { Box::from_raw(other) });
+    }
+
+    #[test]
+    fn clean_while_emitting() {
+        let list = HookList::new();
+        let mut cleaner: Box<spa_sys::spa_hook> = Box::new(unsafe { std::mem::zeroed() });
+        let other = listener(false);
+
+        unsafe {
+            list.append(
+                &mut *cleaner,
+                ptr::addr_of!(CLEAN_EVENTS).cast(),
+                ptr::addr_of!(list).cast_mut().cast(),
+            );
+            append(&list, other, &EVENTS);
+        }
+
+        // The first hook cleans the list, which ends the emission.
+        assert_eq!(unsafe { spa_hook_list_emit!(list, Events, event, 1, 1) }, 1);
+        assert!(list.is_empty());
+
+        let other = unsafe { Box::from_raw(other) };
+        assert!(other.calls.borrow().is_empty());
+    }
+}
@@ -0,0 +1,349 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Typed access to the methods of SPA interfaces.
+//!
+//! SPA and PipeWire objects expose their methods through a `spa_interface`, holding a struct of
+//! function pointers such as `pw_node_methods`. The struct starts with its version, and methods
+//! added in later versions of the struct are missing when talking to older implementations.
+//! [`VersionedInterface`] checks the version and the presence of a method before calling it,
+//! like the `spa_interface_call` macros of C do, instead of crashing on a missing method as
+//! [`spa_interface_call_method!`](crate::spa_interface_call_method) would.
+//!
+//! ```no_run
+//! use libspa::{sys as spa_sys, utils::interface::VersionedInterface};
+//!
+//! # fn get_flags(cpu: *mut spa_sys::spa_cpu) {
+//! // Safety: `cpu` points to a valid `spa_cpu` interface.
+//! let interface: VersionedInterface<spa_sys::spa_cpu_methods> =
+//!     unsafe { VersionedInterface::new(cpu.cast()) };
+//!
+//! let flags = interface.call(0, |methods| methods.get_flags, |get_flags, object| unsafe {
+//!     get_flags(object)
+//! });
+//! # }
+//! ```
+//!
+//! Methods called from several places are better wrapped once with
+//! [`interface_methods!`](crate::interface_methods), which generates a safe wrapper for each
+//! of them.
+
+use std::{ffi::CStr, fmt, marker::PhantomData, ptr::NonNull};
+
+use libc::c_void;
+
+use super::result::SpaResult;
+
+/// A SPA interface whose methods are the function pointers of the struct `M`.
+///
+/// This does not own the interface.
+pub struct VersionedInterface<M> {
+    iface: NonNull<spa_sys::spa_interface>,
+    _methods: PhantomData<*const M>,
+}
+
+impl<M> VersionedInterface<M> {
+    /// Wrap a pointer to an interface, such as a `pw_node` or a `spa_cpu`.
+    ///
+    /// # Safety
+    /// `iface` must point to a valid `spa_interface` whose methods are a `M`, which must be
+    /// a `repr(C)` struct starting with its `u32` version, like all the method structs of SPA and
+    /// PipeWire. The interface must stay valid while the returned value is used.
+    ///
+    /// # Panics
+    /// If `iface` is null.
+    pub unsafe fn new(iface: *mut c_void) -> Self {
+        Self {
+            iface: NonNull::new(iface.cast()).expect("interface pointer is NULL"),
+            _methods: PhantomData,
+        }
+    }
+
+    pub fn as_raw_ptr(&self) -> *mut spa_sys::spa_interface {
+        self.iface.as_ptr()
+    }
+
+    fn as_raw(&self) -> &spa_sys::spa_interface {
+        unsafe { self.iface.as_ref() }
+    }
+
+    /// The type of the interface, such as `PipeWire:Interface:Node`.
+    pub fn type_(&self) -> &CStr {
+        unsafe { CStr::from_ptr(self.as_raw().type_) }
+    }
+
+    /// The version of the interface.
+    pub fn version(&self) -> u32 {
+        self.as_raw().version
+    }
+
+    /// The version of the method struct implemented by the object.
+    pub fn methods_version(&self) -> u32 {
+        // Safety: the method struct starts with its version.
+        unsafe { *self.as_raw().cb.funcs.cast::<u32>() }
+    }
+
+    fn methods(&self) -> &M {
+        unsafe { &*self.as_raw().cb.funcs.cast::<M>() }
+    }
+
+    /// Get the method selected by `method`, if the methods are at least at version `since`
+    /// and implement it.
+    ///
+    /// `since` is the version of the method struct which introduced the method, `0` for the
+    /// methods present from the start.
+    /// The method is returned with the object to pass as its first argument.
+    pub fn method<F>(
+        &self,
+        since: u32,
+        method: impl FnOnce(&M) -> Option<F>,
+    ) -> Option<(F, *mut c_void)> {
+        if self.methods_version() < since {
+            return None;
+        }
+
+        let f = method(self.methods())?;
+        Some((f, self.as_raw().cb.data))
+    }
+
+    /// Call the method selected by `method` with `call`, which receives the method and the object
+    /// to pass as its first argument.
+    ///
+    /// Returns `None` without calling `call` if the method is not available,
+    /// see [`method`](Self::method).
+    pub fn call<F, R>(
+        &self,
+        since: u32,
+        method: impl FnOnce(&M) -> Option<F>,
+        call: impl FnOnce(F, *mut c_void) -> R,
+    ) -> Option<R> {
+        self.method(since, method)
+            .map(|(f, object)| call(f, object))
+    }
+
+    /// Call a method returning a SPA result, see [`call`](Self::call).
+    ///
+    /// Results in `-ENOTSUP` if the method is not available.
+    pub fn call_res<F>(
+        &self,
+        since: u32,
+        method: impl FnOnce(&M) -> Option<F>,
+        call: impl FnOnce(F, *mut c_void) -> i32,
+    ) -> SpaResult {
+        SpaResult::from_c(self.call(since, method, call).unwrap_or(-libc::ENOTSUP))
+    }
+}
+
+/// The result of a method wrapper generated by [`interface_methods!`](crate::interface_methods),
+/// made from the result of [`VersionedInterface::call`].
+pub trait MethodResult<R> {
+    fn from_call(res: Option<R>) -> Self;
+}
+
+/// `None` if the method is not available.
+impl<R> MethodResult<R> for Option<R> {
+    fn from_call(res: Option<R>) -> Self {
+        res
+    }
+}
+
+/// `-ENOTSUP` if the method is not available, like [`VersionedInterface::call_res`].
+impl MethodResult<i32> for SpaResult {
+    fn from_call(res: Option<i32>) -> Self {
+        SpaResult::from_c(res.unwrap_or(-libc::ENOTSUP))
+    }
+}
+
+/// Generate a trait of safe, version-checked wrappers for methods of an interface, implemented by
+/// [`VersionedInterface`] of the method struct.
+///
+/// Each wrapper gives the version of the method struct which introduced the method, and converts
+/// its arguments to the ones of the C method after the object. The conversion is written in an
+/// `unsafe` block, as it is where the arguments are made valid for the C method.
+///
+/// A wrapper returns the result of the method as an [`Option`], `None` if the method is not
+/// available, or as a [`SpaResult`], `-ENOTSUP` if the method is not available; see
+/// [`MethodResult`].
+///
+/// # Examples
+/// ```
+/// use libspa::{
+///     interface_methods,
+///     sys as spa_sys,
+///     utils::{interface::VersionedInterface, result::SpaResult},
+/// };
+///
+/// interface_methods! {
+///     /// The methods of a `spa_cpu`.
+///     trait CpuMethods for spa_sys::spa_cpu_methods {
+///         fn get_flags(&self) -> Option<u32> {
+///             since 0;
+///             unsafe { call() }
+///         }
+///
+///         fn zero_denormals(&self, enable: bool) -> SpaResult {
+///             since 0;
+///             unsafe { call(enable) }
+///         }
+///     }
+/// }
+///
+/// fn get_flags(cpu: &VersionedInterface<spa_sys::spa_cpu_methods>) -> Option<u32> {
+///     cpu.get_flags()
+/// }
+/// ```
+#[macro_export]
+macro_rules! interface_methods {
+    (
+        $(#[$attr:meta])*
+        $vis:vis trait $trait:ident for $methods:ty {
+            $(
+                $(#[$method_attr:meta])*
+                fn $name:ident(&self $(, $arg:ident: $arg_ty:ty)*) -> $ret:ty {
+                    since $since:expr;
+                    unsafe { call($($call_arg:expr),* $(,)?) }
+                }
+            )*
+        }
+    ) => {
+        $(#[$attr])*
+        $vis trait $trait {
+            $(
+                $(#[$method_attr])*
+                fn $name(&self $(, $arg: $arg_ty)*) -> $ret;
+            )*
+        }
+
+        impl $trait for $crate::utils::interface::VersionedInterface<$methods> {
+            $(
+                fn $name(&self $(, $arg: $arg_ty)*) -> $ret {
+                    $crate::utils::interface::MethodResult::from_call(self.call(
+                        $since,
+                        |methods| methods.$name,
+                        |f, object| unsafe { f(object $(, $call_arg)*) },
+                    ))
+                }
+            )*
+        }
+    };
+}
+
+impl<M> fmt::Debug for VersionedInterface<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VersionedInterface")
+            .field("type", &self.type_())
+            .field("version", &self.version())
+            .field("methods_version", &self.methods_version())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use super::*;
+
+    unsafe extern "C" fn get_flags(object: *mut c_void) -> u32 {
+        *object.cast::<u32>()
+    }
+
+    unsafe extern "C" fn get_max_align(_object: *mut c_void) -> u32 {
+        16
+    }
+
+    #[test]
+    fn call() {
+        let mut flags = 7u32;
+        let methods = spa_sys::spa_cpu_methods {
+            version: 0,
+            get_flags: Some(get_flags),
+            get_max_align: Some(get_max_align),
+            ..unsafe { std::mem::zeroed() }
+        };
+        let mut iface = spa_sys::spa_interface {
+            type_: spa_sys::SPA_TYPE_INTERFACE_CPU.as_ptr().cast(),
+            version: 0,
+            cb: spa_sys::spa_callbacks {
+                funcs: ptr::addr_of!(methods).cast(),
+                data: ptr::addr_of_mut!(flags).cast(),
+            },
+        };
+
+        let interface: VersionedInterface<spa_sys::spa_cpu_methods> =
+            unsafe { VersionedInterface::new(ptr::addr_of_mut!(iface).cast()) };
+        assert_eq!(interface.type_().to_bytes(), b"Spa:Pointer:Interface:CPU");
+        assert_eq!(interface.methods_version(), 0);
+
+        let res = interface.call(
+            0,
+            |methods| methods.get_flags,
+            |f, object| unsafe { f(object) },
+        );
+        assert_eq!(res, Some(7));
+
+        // Too recent
+        let res = interface.call(
+            1,
+            |methods| methods.get_vm_type,
+            |f, object| unsafe { f(object) },
+        );
+        assert_eq!(res, None);
+
+        // Missing
+        let res = interface.call_res(
+            0,
+            |methods| methods.zero_denormals,
+            |f, object| unsafe { f(object, true) },
+        );
+        assert_eq!(res, SpaResult::from_c(-libc::ENOTSUP));
+    }
+
+    crate::interface_methods! {
+        trait CpuMethods for spa_sys::spa_cpu_methods {
+            fn get_flags(&self) -> Option<u32> {
+                since 0;
+                unsafe { call() }
+            }
+
+            fn get_vm_type(&self) -> Option<u32> {
+                since 1;
+                unsafe { call() }
+            }
+
+            fn zero_denormals(&self, enable: bool) -> SpaResult {
+                since 0;
+                unsafe { call(enable) }
+            }
+        }
+    }
+
+    #[test]
+    fn methods() {
+        let mut flags = 7u32;
+        let methods = spa_sys::spa_cpu_methods {
+            version: 0,
+            get_flags: Some(get_flags),
+            ..unsafe { std::mem::zeroed() }
+        };
+        let mut iface = spa_sys::spa_interface {
+            type_: spa_sys::SPA_TYPE_INTERFACE_CPU.as_ptr().cast(),
+            version: 0,
+            cb: spa_sys::spa_callbacks {
+                funcs: ptr::addr_of!(methods).cast(),
+                data: ptr::addr_of_mut!(flags).cast(),
+            },
+        };
+
+        let interface: VersionedInterface<spa_sys::spa_cpu_methods> =
+            unsafe { VersionedInterface::new(ptr::addr_of_mut!(iface).cast()) };
+        assert_eq!(interface.get_flags(), Some(7));
+        // Too recent
+        assert_eq!(interface.get_vm_type(), None);
+        // Missing
+        assert_eq!(
+            interface.zero_denormals(true),
+            SpaResult::from_c(-libc::ENOTSUP)
+        );
+    }
+}
@@ -3,6 +3,31 @@
 
 //! SPA list
 
+/// Initialize `list` as an empty list, linked to itself.
+///
+/// # Safety
+/// `list` must be valid for writes.
+pub unsafe fn init(list: *mut spa_sys::spa_list) {
+    (*list).next = list;
+    (*list).prev = list;
+}
+
+/// Insert `elem` in a list, right after `list`, which can be the head of the list or an element.
+///
+/// # Safety
+/// `list` must be part of a valid list, and `elem` must be valid for writes and not part of a list.
+pub unsafe fn insert(list: *mut spa_sys::spa_list, elem: *mut spa_sys::spa_list) {
+    (*elem).prev = list;
+    (*elem).next = (*list).next;
+    (*list).next = elem;
+    (*(*elem).next).prev = elem;
+}
+
+/// Whether the list whose head is `list` is empty.
+pub fn is_empty(list: &spa_sys::spa_list) -> bool {
+    std::ptr::eq(list.next, list)
+}
+
 /// Remove an element from its list
 pub fn remove(elem: &spa_sys::spa_list) {
     unsafe {
@@ -3,16 +3,22 @@
 pub mod dict;
 mod direction;
 pub use direction::*;
+pub mod dll;
 pub mod hook;
+pub mod interface;
 pub mod list;
 pub mod result;
+mod ringbuffer;
+pub use ringbuffer::*;
 
 use bitflags::bitflags;
 use convert_case::{Case, Casing};
-use std::{ffi::CStr, fmt::Debug, os::raw::c_uint};
+use std::{ffi::CStr, fmt::Debug, ops::RangeInclusive, os::raw::c_uint};
 
 pub use spa_sys::spa_fraction as Fraction;
+pub use spa_sys::spa_point as Point;
 pub use spa_sys::spa_rectangle as Rectangle;
+pub use spa_sys::spa_region as Region;
 
 use crate::pod::CanonicalFixedSizedPod;
 
@@ -20,6 +26,60 @@ use crate::pod::CanonicalFixedSizedPod;
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct Id(pub u32);
 
+/// A type whose values are serialized into `Id` pods.
+///
+/// This is implemented by the enumerations of SPA, such as [`MediaType`](crate::param::format::MediaType)
+/// or [`AudioFormat`](crate::param::audio::AudioFormat), so that they can be used directly
+/// in [`property!`](crate::pod::property), [`builder_add!`](crate::pod::builder::builder_add)
+/// and [`Choice::id_enum`].
+pub trait IdEnum: Sized {
+    /// Get the id representing `self`.
+    fn to_id(&self) -> Id;
+    /// Get the value represented by `id`, or `None` if `id` is not a valid value.
+    fn from_id(id: Id) -> Option<Self>;
+}
+
+impl IdEnum for Id {
+    fn to_id(&self) -> Id {
+        *self
+    }
+
+    fn from_id(id: Id) -> Option<Self> {
+        Some(id)
+    }
+}
+
+/// Implement [`IdEnum`] for wrappers of the raw enumerations of SPA.
+///
+/// As the raw enumerations can be extended, any id is a valid value.
+macro_rules! impl_id_enum {
+    ($($type_:ty),* $(,)?) => {
+        $(
+            impl IdEnum for $type_ {
+                fn to_id(&self) -> Id {
+                    Id(self.as_raw())
+                }
+
+                fn from_id(id: Id) -> Option<Self> {
+                    Some(Self::from_raw(id.0))
+                }
+            }
+        )*
+    };
+}
+
+impl_id_enum!(
+    SpaTypes,
+    crate::buffer::DataType,
+    crate::param::ParamType,
+    crate::param::format::MediaType,
+    crate::param::format::MediaSubtype,
+    crate::param::format::FormatProperties,
+    crate::param::audio::AudioFormat,
+    crate::param::video::VideoFormat,
+    crate::param::video::VideoInterlaceMode,
+);
+
 /// A file descriptor in a pod
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 #[repr(transparent)]
@@ -29,13 +89,64 @@ pub struct Fd(pub i64);
 /// the flags and choice of a choice pod.
 pub struct Choice<T: CanonicalFixedSizedPod>(pub ChoiceFlags, pub ChoiceEnum<T>);
 
+impl Choice<Id> {
+    /// Create an enumeration choice of ids, from any [`IdEnum`].
+    pub fn id_enum<T: IdEnum>(default: T, alternatives: impl IntoIterator<Item = T>) -> Self {
+        Self(
+            ChoiceFlags::empty(),
+            ChoiceEnum::Enum {
+                default: default.to_id(),
+                alternatives: alternatives.into_iter().map(|id| id.to_id()).collect(),
+            },
+        )
+    }
+}
+
+impl Choice<i32> {
+    /// Create a flags choice from a [`bitflags`] type: the default is `value`, and the flags
+    /// that can be set are all the known flags of `F`.
+    ///
+    /// ```
+    /// use libspa::{pod::ChoiceValue, utils::Choice};
+    ///
+    /// bitflags::bitflags! {
+    ///     #[derive(Clone, Copy)]
+    ///     struct Features: u32 {
+    ///         const A = 1 << 0;
+    ///         const B = 1 << 1;
+    ///     }
+    /// }
+    ///
+    /// let choice = Choice::flags(Features::B);
+    /// let value = ChoiceValue::Int(choice);
+    /// ```
+    pub fn flags<F: bitflags::Flags<Bits = u32>>(value: F) -> Self {
+        Self(
+            ChoiceFlags::empty(),
+            ChoiceEnum::Flags {
+                default: value.bits() as i32,
+                flags: F::FLAGS
+                    .iter()
+                    .map(|flag| flag.value().bits() as i32)
+                    .collect(),
+            },
+        )
+    }
+}
+
+impl<T: CanonicalFixedSizedPod> From<ChoiceEnum<T>> for Choice<T> {
+    /// Create a choice without flags.
+    fn from(choice: ChoiceEnum<T>) -> Self {
+        Self(ChoiceFlags::empty(), choice)
+    }
+}
+
 bitflags! {
     /// [`Choice`] flags
     #[derive(Debug, PartialEq, Eq, Clone, Copy)]
     pub struct ChoiceFlags: u32 {
-        // no flags defined yet but we need at least one to keep bitflags! happy
-        #[doc(hidden)]
-        const _FAKE = 1;
+        // SPA does not define any flag yet, so keep all the bits to round-trip them untouched.
+        const _ = !0;
     }
 }
 
@@ -80,6 +191,347 @@ pub enum ChoiceEnum<T: CanonicalFixedSizedPod> {
     },
 }
 
+impl<T: CanonicalFixedSizedPod> ChoiceEnum<T> {
+    /// The default value of the choice.
+    pub fn default(&self) -> &T {
+        match self {
+            Self::None(value) => value,
+            Self::Range { default, .. }
+            | Self::Step { default, .. }
+            | Self::Enum { default, .. }
+            | Self::Flags { default, .. } => default,
+        }
+    }
+
+    /// An iterator over the values explicitly listed by the choice.
+    ///
+    /// This is the value of a [`ChoiceEnum::None`], the alternatives of a [`ChoiceEnum::Enum`]
+    /// and the flags of a [`ChoiceEnum::Flags`]. Ranges don't list any values.
+    pub fn iter_alternatives(&self) -> std::slice::Iter<'_, T> {
+        match self {
+            Self::None(value) => std::slice::from_ref(value).iter(),
+            Self::Range { .. } | Self::Step { .. } => <&[T]>::default().iter(),
+            Self::Enum { alternatives, .. } => alternatives.iter(),
+            Self::Flags { flags, .. } => flags.iter(),
+        }
+    }
+}
+
+impl<T: CanonicalFixedSizedPod> ChoiceEnum<T> {
+    /// Create a [`ChoiceEnum::Enum`] choice between `default` and `alternatives`.
+    pub fn alternatives(default: T, alternatives: impl IntoIterator<Item = T>) -> Self {
+        Self::Enum {
+            default,
+            alternatives: alternatives.into_iter().collect(),
+        }
+    }
+
+    /// Create a [`ChoiceEnum::Flags`] choice of the flags in `flags`, with `default` set.
+    pub fn flags(default: T, flags: impl IntoIterator<Item = T>) -> Self {
+        Self::Flags {
+            default,
+            flags: flags.into_iter().collect(),
+        }
+    }
+}
+
+impl<T: RangeValue> ChoiceEnum<T> {
+    /// Check whether `value` is allowed by the choice.
+    ///
+    /// For [`ChoiceEnum::Step`], the value also has to be a whole number of steps away from the minimum.
+    pub fn contains(&self, value: &T) -> bool {
+        match self {
+            Self::None(v) => v == value,
+            Self::Range { min, max, .. } => value.in_range(min, max, None),
+            Self::Step { min, max, step, .. } => value.in_range(min, max, Some(step)),
+            Self::Enum { alternatives, .. } => alternatives.contains(value),
+            Self::Flags { flags, .. } => flags.contains(value),
+        }
+    }
+
+    /// Return the value allowed by the choice that is closest to `value`.
+    ///
+    /// Ranges clamp the value to their bounds, snapping it to the nearest step for [`ChoiceEnum::Step`].
+    /// Other choices return `value` if it is allowed, or the default value otherwise.
+    pub fn clamp(&self, value: T) -> T {
+        match self {
+            Self::Range { min, max, .. } => value.clamp_to_range(*min, *max, None),
+            Self::Step { min, max, step, .. } => value.clamp_to_range(*min, *max, Some(*step)),
+            _ if self.contains(&value) => value,
+            _ => *self.default(),
+        }
+    }
+
+    /// Get the bounds of a [`ChoiceEnum::Range`] or [`ChoiceEnum::Step`] choice.
+    pub fn range(&self) -> Option<RangeInclusive<T>> {
+        match self {
+            Self::Range { min, max, .. } | Self::Step { min, max, .. } => Some(*min..=*max),
+            _ => None,
+        }
+    }
+}
+
+impl<T: CanonicalFixedSizedPod + Copy> From<RangeInclusive<T>> for ChoiceEnum<T> {
+    /// Create a [`ChoiceEnum::Range`] choice, using the start of the range as default value.
+    fn from(range: RangeInclusive<T>) -> Self {
+        let (min, max) = range.into_inner();
+        Self::Range {
+            default: min,
+            min,
+            max,
+        }
+    }
+}
+
+/// The bounds and step of a [`ChoiceEnum::Step`] choice.
+///
+/// ```
+/// use libspa::utils::{ChoiceEnum, StepRange};
+///
+/// let choice = ChoiceEnum::from(StepRange::new(0..=100, 10));
+/// assert!(choice.contains(&30));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct StepRange<T> {
+    /// minimum value.
+    pub min: T,
+    /// maximum value.
+    pub max: T,
+    /// step.
+    pub step: T,
+}
+
+impl<T> StepRange<T> {
+    /// Create a step range going from the start to the end of `range` by `step`.
+    pub fn new(range: RangeInclusive<T>, step: T) -> Self {
+        let (min, max) = range.into_inner();
+        Self { min, max, step }
+    }
+}
+
+impl<T: CanonicalFixedSizedPod + Copy> From<StepRange<T>> for ChoiceEnum<T> {
+    /// Create a [`ChoiceEnum::Step`] choice, using the minimum as default value.
+    fn from(range: StepRange<T>) -> Self {
+        Self::Step {
+            default: range.min,
+            min: range.min,
+            max: range.max,
+            step: range.step,
+        }
+    }
+}
+
+impl<T: RangeValue> TryFrom<ChoiceEnum<T>> for RangeInclusive<T> {
+    type Error = ChoiceEnum<T>;
+
+    /// Convert a [`ChoiceEnum::Range`] or [`ChoiceEnum::Step`] choice into its bounds,
+    /// returning the choice back if it isn't a range.
+    fn try_from(choice: ChoiceEnum<T>) -> Result<Self, Self::Error> {
+        choice.range().ok_or(choice)
+    }
+}
+
+/// Values which can be checked against a [`ChoiceEnum`] with [`ChoiceEnum::contains`] and [`ChoiceEnum::clamp`].
+///
+/// This trait is sealed and implemented for all pod values which can be part of a range.
+pub trait RangeValue: CanonicalFixedSizedPod + PartialEq + Copy + private::RangeValueSeal {
+    /// Check that the value is between `min` and `max`, and a whole number of `step`s away from `min`.
+    #[doc(hidden)]
+    fn in_range(&self, min: &Self, max: &Self, step: Option<&Self>) -> bool;
+    /// Clamp the value between `min` and `max`, snapping it to the nearest `step`.
+    #[doc(hidden)]
+    fn clamp_to_range(self, min: Self, max: Self, step: Option<Self>) -> Self;
+}
+
+mod private {
+    pub trait RangeValueSeal {}
+    impl RangeValueSeal for i32 {}
+    impl RangeValueSeal for i64 {}
+    impl RangeValueSeal for f32 {}
+    impl RangeValueSeal for f64 {}
+    impl RangeValueSeal for super::Id {}
+    impl RangeValueSeal for super::Fd {}
+    impl RangeValueSeal for super::Rectangle {}
+    impl RangeValueSeal for super::Fraction {}
+}
+
+fn int_in_range(value: i128, min: i128, max: i128, step: Option<i128>) -> bool {
+    value >= min && value <= max && step.map_or(true, |step| step <= 0 || (value - min) % step == 0)
+}
+
+fn int_clamp_to_range(value: i128, min: i128, max: i128, step: Option<i128>) -> i128 {
+    let value = value.max(min).min(max);
+
+    match step {
+        Some(step) if step > 0 => {
+            // Round to the nearest step, without going past the last step below max.
+            let steps = (value - min + step / 2) / step;
+            let max_steps = (max - min) / step;
+            min + steps.min(max_steps) * step
+        }
+        _ => value,
+    }
+}
+
+macro_rules! impl_choice_value_int {
+    ($type_:ty) => {
+        impl RangeValue for $type_ {
+            fn in_range(&self, min: &Self, max: &Self, step: Option<&Self>) -> bool {
+                int_in_range(
+                    (*self).into(),
+                    (*min).into(),
+                    (*max).into(),
+                    step.map(|s| (*s).into()),
+                )
+            }
+
+            fn clamp_to_range(self, min: Self, max: Self, step: Option<Self>) -> Self {
+                int_clamp_to_range(self.into(), min.into(), max.into(), step.map(Into::into))
+                    .try_into()
+                    .unwrap()
+            }
+        }
+    };
+}
+
+impl_choice_value_int!(i32);
+impl_choice_value_int!(i64);
+
+macro_rules! impl_choice_value_float {
+    ($type_:ty) => {
+        impl RangeValue for $type_ {
+            fn in_range(&self, min: &Self, max: &Self, step: Option<&Self>) -> bool {
+                *self >= *min
+                    && *self <= *max
+                    && step.map_or(true, |step| {
+                        if *step <= 0.0 {
+                            return true;
+                        }
+                        let steps = ((*self - *min) / *step).round();
+                        (*min + steps * *step - *self).abs()
+                            <= <$type_>::EPSILON * self.abs().max(1.0)
+                    })
+            }
+
+            fn clamp_to_range(self, min: Self, max: Self, step: Option<Self>) -> Self {
+                let value = self.max(min).min(max);
+
+                match step {
+                    Some(step) if step > 0.0 => {
+                        let steps = ((value - min) / step).round();
+                        let max_steps = ((max - min) / step).floor();
+                        min + steps.min(max_steps) * step
+                    }
+                    _ => value,
+                }
+            }
+        }
+    };
+}
+
+impl_choice_value_float!(f32);
+impl_choice_value_float!(f64);
+
+impl RangeValue for Id {
+    fn in_range(&self, min: &Self, max: &Self, step: Option<&Self>) -> bool {
+        int_in_range(
+            self.0.into(),
+            min.0.into(),
+            max.0.into(),
+            step.map(|s| s.0.into()),
+        )
+    }
+
+    fn clamp_to_range(self, min: Self, max: Self, step: Option<Self>) -> Self {
+        let value = int_clamp_to_range(
+            self.0.into(),
+            min.0.into(),
+            max.0.into(),
+            step.map(|s| s.0.into()),
+        );
+        Id(value.try_into().unwrap())
+    }
+}
+
+impl RangeValue for Fd {
+    fn in_range(&self, min: &Self, max: &Self, step: Option<&Self>) -> bool {
+        int_in_range(
+            self.0.into(),
+            min.0.into(),
+            max.0.into(),
+            step.map(|s| s.0.into()),
+        )
+    }
+
+    fn clamp_to_range(self, min: Self, max: Self, step: Option<Self>) -> Self {
+        let value = int_clamp_to_range(
+            self.0.into(),
+            min.0.into(),
+            max.0.into(),
+            step.map(|s| s.0.into()),
+        );
+        Fd(value.try_into().unwrap())
+    }
+}
+
+/// Rectangles are in range if both their width and height are.
+impl RangeValue for Rectangle {
+    fn in_range(&self, min: &Self, max: &Self, step: Option<&Self>) -> bool {
+        int_in_range(
+            self.width.into(),
+            min.width.into(),
+            max.width.into(),
+            step.map(|s| s.width.into()),
+        ) && int_in_range(
+            self.height.into(),
+            min.height.into(),
+            max.height.into(),
+            step.map(|s| s.height.into()),
+        )
+    }
+
+    fn clamp_to_range(self, min: Self, max: Self, step: Option<Self>) -> Self {
+        let width = int_clamp_to_range(
+            self.width.into(),
+            min.width.into(),
+            max.width.into(),
+            step.map(|s| s.width.into()),
+        );
+        let height = int_clamp_to_range(
+            self.height.into(),
+            min.height.into(),
+            max.height.into(),
+            step.map(|s| s.height.into()),
+        );
+
+        Rectangle {
+            width: width.try_into().unwrap(),
+            height: height.try_into().unwrap(),
+        }
+    }
+}
+
+/// Fractions are compared by their value. Steps are ignored.
+impl RangeValue for Fraction {
+    fn in_range(&self, min: &Self, max: &Self, _step: Option<&Self>) -> bool {
+        fraction_cmp(self, min).is_ge() && fraction_cmp(self, max).is_le()
+    }
+
+    fn clamp_to_range(self, min: Self, max: Self, _step: Option<Self>) -> Self {
+        if fraction_cmp(&self, &min).is_lt() {
+            min
+        } else if fraction_cmp(&self, &max).is_gt() {
+            max
+        } else {
+            self
+        }
+    }
+}
+
+fn fraction_cmp(a: &Fraction, b: &Fraction) -> std::cmp::Ordering {
+    (u64::from(a.num) * u64::from(b.denom)).cmp(&(u64::from(b.num) * u64::from(a.denom)))
+}
+
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub struct SpaTypes(pub c_uint);
 
@@ -187,6 +639,116 @@ impl Debug for SpaTypes {
 mod tests {
     use super::*;
 
+    #[test]
+    fn choice_enum_default_and_alternatives() {
+        let choice = ChoiceEnum::Enum {
+            default: Id(1),
+            alternatives: vec![Id(1), Id(2)],
+        };
+        assert_eq!(choice.default(), &Id(1));
+        assert_eq!(
+            choice.iter_alternatives().collect::<Vec<_>>(),
+            vec![&Id(1), &Id(2)]
+        );
+        assert!(choice.contains(&Id(2)));
+        assert!(!choice.contains(&Id(3)));
+        assert_eq!(choice.clamp(Id(3)), Id(1));
+
+        let range = ChoiceEnum::from(1..=10);
+        assert_eq!(range.default(), &1);
+        assert_eq!(range.iter_alternatives().count(), 0);
+    }
+
+    #[test]
+    fn choice_enum_range() {
+        let choice = ChoiceEnum::Range {
+            default: 48000,
+            min: 8000,
+            max: 192000,
+        };
+        assert!(choice.contains(&44100));
+        assert!(!choice.contains(&384000));
+        assert_eq!(choice.clamp(384000), 192000);
+        assert_eq!(choice.clamp(1), 8000);
+        assert_eq!(choice.range(), Some(8000..=192000));
+        assert_eq!(RangeInclusive::try_from(choice), Ok(8000..=192000));
+
+        let choice = ChoiceEnum::Step {
+            default: 0.0f32,
+            min: 0.0,
+            max: 1.0,
+            step: 0.25,
+        };
+        assert!(choice.contains(&0.5));
+        assert!(!choice.contains(&0.3));
+        assert_eq!(choice.clamp(0.3), 0.25);
+        assert_eq!(choice.clamp(2.0), 1.0);
+
+        let choice = ChoiceEnum::Step {
+            default: 0,
+            min: 0,
+            max: 10,
+            step: 4,
+        };
+        assert!(choice.contains(&8));
+        assert!(!choice.contains(&10));
+        assert_eq!(choice.clamp(7), 8);
+        assert_eq!(choice.clamp(10), 8);
+
+        let choice = ChoiceEnum::Range {
+            default: Fraction { num: 30, denom: 1 },
+            min: Fraction { num: 0, denom: 1 },
+            max: Fraction { num: 60, denom: 1 },
+        };
+        assert!(choice.contains(&Fraction {
+            num: 60000,
+            denom: 1001
+        }));
+        assert!(!choice.contains(&Fraction { num: 120, denom: 1 }));
+
+        let choice = ChoiceEnum::None(Id(1));
+        assert_eq!(RangeInclusive::try_from(choice.clone()), Err(choice));
+    }
+
+    #[test]
+    fn choice_enum_constructors() {
+        assert_eq!(
+            ChoiceEnum::from(2..=8),
+            ChoiceEnum::Range {
+                default: 2,
+                min: 2,
+                max: 8
+            }
+        );
+        assert_eq!(
+            ChoiceEnum::from(StepRange::new(0.0f32..=1.0, 0.5)),
+            ChoiceEnum::Step {
+                default: 0.0,
+                min: 0.0,
+                max: 1.0,
+                step: 0.5
+            }
+        );
+        assert_eq!(
+            ChoiceEnum::alternatives(Id(1), [Id(1), Id(2)]),
+            ChoiceEnum::Enum {
+                default: Id(1),
+                alternatives: vec![Id(1), Id(2)]
+            }
+        );
+        assert_eq!(
+            ChoiceEnum::flags(1, (0..3).map(|bit| 1 << bit)),
+            ChoiceEnum::Flags {
+                default: 1,
+                flags: vec![1, 2, 4]
+            }
+        );
+        assert_eq!(
+            Choice::from(ChoiceEnum::None(4)),
+            Choice(ChoiceFlags::empty(), ChoiceEnum::None(4))
+        );
+    }
+
     #[test]
     #[cfg_attr(miri, ignore)]
     fn debug_format() {
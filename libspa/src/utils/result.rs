@@ -69,6 +69,27 @@ impl fmt::Debug for AsyncSeq {
     }
 }
 
+impl fmt::Display for AsyncSeq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "async seq {}", self.seq())
+    }
+}
+
+/// Describe the result `res` returned by a SPA method, like the `spa_strerror` C function.
+///
+/// Errors are described by their errno, and asynchronous results as an operation in progress,
+/// as done by SPA. Use the [`Display`](fmt::Display) implementation of [`SpaResult`] to also get
+/// the sequence number of asynchronous results.
+pub fn strerror(res: i32) -> String {
+    if res < 0 {
+        Errno::from_i32(-res).desc().to_owned()
+    } else if is_async(res) {
+        Errno::EINPROGRESS.desc().to_owned()
+    } else {
+        "Success".to_owned()
+    }
+}
+
 impl SpaResult {
     /// Create a new [`SpaResult`] from an `i32` returned by C SPA method.
     pub fn from_c(res: i32) -> Self {
@@ -125,16 +146,39 @@ impl SpaResult {
     }
 }
 
+impl fmt::Display for SpaResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 < 0 {
+            write!(f, "{}", Error::new(-self.0))
+        } else if self.is_async() {
+            write!(f, "{}", AsyncSeq::from_raw(self.0))
+        } else {
+            write!(f, "success ({})", self.0)
+        }
+    }
+}
+
 /// Error returned from a SPA method.
 #[derive(Debug, Eq, PartialEq)]
 pub struct Error(Errno);
 
 impl Error {
-    fn new(e: i32) -> Self {
+    pub(crate) fn new(e: i32) -> Self {
         assert!(e > 0);
 
         Self(Errno::from_i32(e))
     }
+
+    /// The errno describing the error.
+    pub fn errno(&self) -> Errno {
+        self.0
+    }
+}
+
+impl From<Errno> for Error {
+    fn from(errno: Errno) -> Self {
+        Self(errno)
+    }
 }
 
 impl std::error::Error for Error {}
@@ -176,6 +220,24 @@ mod tests {
         assert!(res.is_err());
     }
 
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn display() {
+        assert_eq!(strerror(-libc::ENOENT), "No such file or directory");
+        assert_eq!(
+            strerror(SpaResult::new_return_async(3).0),
+            "Operation now in progress"
+        );
+        assert_eq!(strerror(0), "Success");
+
+        assert_eq!(
+            SpaResult::from_c(-libc::EBUSY).to_string(),
+            "EBUSY: Device or resource busy"
+        );
+        assert_eq!(SpaResult::new_return_async(3).to_string(), "async seq 3");
+        assert_eq!(SpaResult::from_c(2).to_string(), "success (2)");
+    }
+
     #[test]
     fn async_seq() {
         assert_eq!(AsyncSeq::from_seq(0).seq(), 0);
@@ -0,0 +1,317 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! A single-producer single-consumer ring buffer, wrapping `spa_ringbuffer`.
+
+use std::{cell::UnsafeCell, mem, os::raw::c_void, sync::Arc};
+
+/// A lock-free ring buffer of bytes with one reading and one writing half.
+///
+/// The halves can be moved to different threads, making this suitable for passing audio
+/// out of or into a realtime process callback without blocking.
+///
+/// ```
+/// use libspa::utils::RingBuffer;
+///
+/// let (mut writer, mut reader) = RingBuffer::new(1024);
+/// assert_eq!(writer.write_frames(&[[0.5f32, -0.5f32]; 4]), 4);
+///
+/// let mut frames = [[0.0f32; 2]; 8];
+/// assert_eq!(reader.read_frames(&mut frames), 4);
+/// assert_eq!(frames[3], [0.5, -0.5]);
+/// ```
+pub struct RingBuffer {
+    rb: UnsafeCell<spa_sys::spa_ringbuffer>,
+    data: Box<[UnsafeCell<u8>]>,
+}
+
+// The ring buffer is only ever accessed through one reader and one writer,
+// which `spa_ringbuffer` synchronizes with atomic operations on the indices.
+unsafe impl Send for RingBuffer {}
+unsafe impl Sync for RingBuffer {}
+
+impl RingBuffer {
+    /// Create a new ring buffer holding at least `size` bytes, returning its writing and reading halves.
+    ///
+    /// The size is rounded up to the next power of two.
+    ///
+    /// # Panics
+    /// If the size is 0 or does not fit in a `u32`.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(size: usize) -> (RingBufferWriter, RingBufferReader) {
+        assert!(size > 0, "ring buffer size must not be 0");
+        let size = size.next_power_of_two();
+        let _: u32 = size.try_into().expect("ring buffer size too large");
+
+        let rb = unsafe {
+            let mut rb: spa_sys::spa_ringbuffer = mem::zeroed();
+            spa_sys::spa_ringbuffer_init(&mut rb);
+            rb
+        };
+
+        let buffer = Arc::new(RingBuffer {
+            rb: UnsafeCell::new(rb),
+            data: (0..size).map(|_| UnsafeCell::new(0)).collect(),
+        });
+
+        (
+            RingBufferWriter {
+                buffer: buffer.clone(),
+            },
+            RingBufferReader { buffer },
+        )
+    }
+
+    fn size(&self) -> u32 {
+        self.data.len() as u32
+    }
+
+    fn data_ptr(&self) -> *mut c_void {
+        UnsafeCell::raw_get(self.data.as_ptr()).cast()
+    }
+}
+
+/// Types that can be copied in and out of a [`RingBuffer`] as whole frames.
+///
+/// # Safety
+/// The type must be plain old data: it must not contain padding, and any bit pattern must be a
+/// valid value of the type.
+pub unsafe trait Frame: Copy {}
+
+unsafe impl Frame for u8 {}
+unsafe impl Frame for i8 {}
+unsafe impl Frame for u16 {}
+unsafe impl Frame for i16 {}
+unsafe impl Frame for u32 {}
+unsafe impl Frame for i32 {}
+unsafe impl Frame for u64 {}
+unsafe impl Frame for i64 {}
+unsafe impl Frame for f32 {}
+unsafe impl Frame for f64 {}
+unsafe impl<T: Frame, const N: usize> Frame for [T; N] {}
+
+/// The writing half of a [`RingBuffer`].
+pub struct RingBufferWriter {
+    buffer: Arc<RingBuffer>,
+}
+
+impl RingBufferWriter {
+    /// The number of bytes the ring buffer can hold.
+    pub fn capacity(&self) -> usize {
+        self.buffer.data.len()
+    }
+
+    fn write_index(&self) -> (u32, usize) {
+        let mut index = 0;
+        let filled =
+            unsafe { spa_sys::spa_ringbuffer_get_write_index(self.buffer.rb.get(), &mut index) };
+        let free = i64::from(self.buffer.size()) - i64::from(filled);
+
+        (index, free.max(0) as usize)
+    }
+
+    /// The number of bytes that can currently be written.
+    pub fn free(&self) -> usize {
+        self.write_index().1
+    }
+
+    /// Write as many bytes of `data` as fit into the ring buffer, returning the number of bytes written.
+    pub fn write(&mut self, data: &[u8]) -> usize {
+        let (index, free) = self.write_index();
+        let len = data.len().min(free);
+        self.write_at(index, data.as_ptr().cast(), len);
+
+        len
+    }
+
+    /// Write as many whole frames as fit into the ring buffer, returning the number of frames written.
+    pub fn write_frames<T: Frame>(&mut self, frames: &[T]) -> usize {
+        let (index, free) = self.write_index();
+        let n_frames = frames.len().min(free / mem::size_of::<T>().max(1));
+        self.write_at(
+            index,
+            frames.as_ptr().cast(),
+            n_frames * mem::size_of::<T>(),
+        );
+
+        n_frames
+    }
+
+    fn write_at(&mut self, index: u32, data: *const c_void, len: usize) {
+        if len == 0 {
+            return;
+        }
+
+        let size = self.buffer.size();
+        let len = len as u32;
+
+        unsafe {
+            spa_sys::spa_ringbuffer_write_data(
+                self.buffer.rb.get(),
+                self.buffer.data_ptr(),
+                size,
+                index & (size - 1),
+                data,
+                len,
+            );
+            spa_sys::spa_ringbuffer_write_update(
+                self.buffer.rb.get(),
+                index.wrapping_add(len) as i32,
+            );
+        }
+    }
+}
+
+/// The reading half of a [`RingBuffer`].
+pub struct RingBufferReader {
+    buffer: Arc<RingBuffer>,
+}
+
+impl RingBufferReader {
+    /// The number of bytes the ring buffer can hold.
+    pub fn capacity(&self) -> usize {
+        self.buffer.data.len()
+    }
+
+    fn read_index(&self) -> (u32, usize) {
+        let mut index = 0;
+        let avail =
+            unsafe { spa_sys::spa_ringbuffer_get_read_index(self.buffer.rb.get(), &mut index) };
+
+        (index, avail.max(0) as usize)
+    }
+
+    /// The number of bytes that can currently be read.
+    pub fn available(&self) -> usize {
+        self.read_index().1
+    }
+
+    /// Read as many bytes as available into `data`, returning the number of bytes read.
+    pub fn read(&mut self, data: &mut [u8]) -> usize {
+        let (index, avail) = self.read_index();
+        let len = data.len().min(avail);
+        self.read_at(index, data.as_mut_ptr().cast(), len);
+
+        len
+    }
+
+    /// Read as many whole frames as available into `frames`, returning the number of frames read.
+    pub fn read_frames<T: Frame>(&mut self, frames: &mut [T]) -> usize {
+        let (index, avail) = self.read_index();
+        let n_frames = frames.len().min(avail / mem::size_of::<T>().max(1));
+        self.read_at(
+            index,
+            frames.as_mut_ptr().cast(),
+            n_frames * mem::size_of::<T>(),
+        );
+
+        n_frames
+    }
+
+    /// Discard up to `len` bytes, returning the number of bytes skipped.
+    pub fn skip(&mut self, len: usize) -> usize {
+        let (index, avail) = self.read_index();
+        let len = len.min(avail);
+        unsafe {
+            spa_sys::spa_ringbuffer_read_update(
+                self.buffer.rb.get(),
+                index.wrapping_add(len as u32) as i32,
+            );
+        }
+
+        len
+    }
+
+    fn read_at(&mut self, index: u32, data: *mut c_void, len: usize) {
+        if len == 0 {
+            return;
+        }
+
+        let size = self.buffer.size();
+        let len = len as u32;
+
+        unsafe {
+            spa_sys::spa_ringbuffer_read_data(
+                self.buffer.rb.get(),
+                self.buffer.data_ptr(),
+                size,
+                index & (size - 1),
+                data,
+                len,
+            );
+            spa_sys::spa_ringbuffer_read_update(
+                self.buffer.rb.get(),
+                index.wrapping_add(len) as i32,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn write_read() {
+        let (mut writer, mut reader) = RingBuffer::new(6);
+        assert_eq!(writer.capacity(), 8);
+        assert_eq!(reader.available(), 0);
+
+        assert_eq!(writer.write(&[1, 2, 3, 4, 5]), 5);
+        assert_eq!(writer.free(), 3);
+        assert_eq!(reader.available(), 5);
+
+        let mut data = [0; 3];
+        assert_eq!(reader.read(&mut data), 3);
+        assert_eq!(data, [1, 2, 3]);
+
+        // Wraps around the end of the buffer
+        assert_eq!(writer.write(&[6, 7, 8, 9, 10, 11, 12]), 6);
+
+        let mut data = [0; 16];
+        assert_eq!(reader.read(&mut data), 8);
+        assert_eq!(&data[..8], &[4, 5, 6, 7, 8, 9, 10, 11]);
+        assert_eq!(reader.available(), 0);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn frames() {
+        let (mut writer, mut reader) = RingBuffer::new(16);
+
+        // Only whole frames are written
+        assert_eq!(writer.write_frames(&[[1u16, 2, 3]; 3]), 2);
+        assert_eq!(reader.available(), 12);
+
+        let mut frames = [[0u16; 3]; 4];
+        assert_eq!(reader.read_frames(&mut frames), 2);
+        assert_eq!(&frames[..2], &[[1, 2, 3]; 2]);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn threads() {
+        let (mut writer, mut reader) = RingBuffer::new(64);
+
+        let handle = std::thread::spawn(move || {
+            let mut next = 0u32;
+            while next < 1000 {
+                if writer.write_frames(&[next]) == 1 {
+                    next += 1;
+                }
+            }
+        });
+
+        let mut expected = 0u32;
+        while expected < 1000 {
+            let mut frame = [0u32];
+            if reader.read_frames(&mut frame) == 1 {
+                assert_eq!(frame[0], expected);
+                expected += 1;
+            }
+        }
+
+        handle.join().unwrap();
+    }
+}
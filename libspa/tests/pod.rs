@@ -3,7 +3,7 @@ use libspa::{
     pod::{
         deserialize::{
             DeserializeError, DeserializeSuccess, ObjectPodDeserializer, PodDeserialize,
-            StructPodDeserializer, Visitor,
+            StructPodDeserializer, UnknownProperties, Visitor,
         },
         serialize::{PodSerialize, PodSerializer, SerializeSuccess},
         CanonicalFixedSizedPod, ChoiceValue, Object, Property, PropertyFlags, Value, ValueArray,
@@ -1457,6 +1457,160 @@ fn object() {
     assert_eq!(vec_rs, vec_c);
 }
 
+#[test]
+#[cfg_attr(miri, ignore)]
+fn object_unknown_properties() {
+    #[derive(Debug, PartialEq)]
+    struct Volume(f32);
+
+    impl<'de> PodDeserialize<'de> for Volume {
+        fn deserialize(
+            deserializer: PodDeserializer<'de>,
+        ) -> Result<(Self, DeserializeSuccess<'de>), DeserializeError<&'de [u8]>> {
+            struct VolumeVisitor;
+
+            impl<'de> Visitor<'de> for VolumeVisitor {
+                type Value = Volume;
+                type ArrayElem = std::convert::Infallible;
+
+                fn visit_object(
+                    &self,
+                    object_deserializer: &mut ObjectPodDeserializer<'de>,
+                ) -> Result<Self::Value, DeserializeError<&'de [u8]>> {
+                    let (volume, _flags) = object_deserializer
+                        .deserialize_property_key::<f32>(spa_sys::SPA_PROP_volume)?;
+                    Ok(Volume(volume))
+                }
+            }
+
+            deserializer.deserialize_object(VolumeVisitor)
+        }
+    }
+
+    // The volume is surrounded by properties the deserializer does not know about.
+    let bytes = PodSerializer::serialize(
+        Cursor::new(Vec::new()),
+        &Value::Object(Object {
+            type_: spa_sys::SPA_TYPE_OBJECT_Props,
+            id: spa_sys::SPA_PARAM_Props,
+            properties: vec![
+                Property::new(spa_sys::SPA_PROP_mute, Value::Bool(true)),
+                Property::new(spa_sys::SPA_PROP_volume, Value::Float(0.5)),
+                Property::new(spa_sys::SPA_PROP_device, Value::String("hw:0".into())),
+            ],
+        }),
+    )
+    .unwrap()
+    .0
+    .into_inner();
+
+    assert_eq!(
+        PodDeserializer::deserialize_from::<Volume>(&bytes).err(),
+        Some(DeserializeError::PropertyWrongKey(spa_sys::SPA_PROP_mute))
+    );
+
+    let (rest, volume, skipped) =
+        PodDeserializer::deserialize_lenient_from::<Volume>(&bytes, UnknownProperties::Skip)
+            .unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(volume, Volume(0.5));
+    assert!(skipped.is_empty());
+
+    let (_, volume, skipped) =
+        PodDeserializer::deserialize_lenient_from::<Volume>(&bytes, UnknownProperties::Collect)
+            .unwrap();
+    assert_eq!(volume, Volume(0.5));
+    let keys: Vec<u32> = skipped.iter().map(|prop| prop.key).collect();
+    assert_eq!(keys, [spa_sys::SPA_PROP_mute, spa_sys::SPA_PROP_device]);
+    assert_eq!(
+        PodDeserializer::deserialize_any_from(skipped[1].value),
+        Ok((&[] as &[u8], Value::String("hw:0".into())))
+    );
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn object_visitor_metadata() {
+    #[derive(Debug, PartialEq)]
+    struct PropKey(u32);
+
+    impl From<u32> for PropKey {
+        fn from(key: u32) -> Self {
+            Self(key)
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Metadata {
+        type_: u32,
+        id: u32,
+        key: PropKey,
+        flags: Option<PropertyFlags>,
+    }
+
+    struct MetadataVisitor;
+
+    impl<'de> Visitor<'de> for MetadataVisitor {
+        type Value = Metadata;
+        type ArrayElem = std::convert::Infallible;
+
+        fn visit_object(
+            &self,
+            object_deserializer: &mut ObjectPodDeserializer<'de>,
+        ) -> Result<Self::Value, DeserializeError<&'de [u8]>> {
+            assert_eq!(object_deserializer.property_flags(), None);
+
+            let (_volume, key, _flags) = object_deserializer
+                .deserialize_typed_property::<f32, PropKey>()?
+                .ok_or(DeserializeError::PropertyMissing)?;
+
+            Ok(Metadata {
+                type_: object_deserializer.object_type(),
+                id: object_deserializer.object_id(),
+                key,
+                flags: object_deserializer.property_flags(),
+            })
+        }
+    }
+
+    impl<'de> PodDeserialize<'de> for Metadata {
+        fn deserialize(
+            deserializer: PodDeserializer<'de>,
+        ) -> Result<(Self, DeserializeSuccess<'de>), DeserializeError<&'de [u8]>>
+        where
+            Self: Sized,
+        {
+            deserializer.deserialize_object(MetadataVisitor)
+        }
+    }
+
+    let object = Value::Object(Object {
+        type_: spa_sys::SPA_TYPE_OBJECT_Props,
+        id: spa_sys::SPA_PARAM_Props,
+        properties: vec![Property {
+            key: spa_sys::SPA_PROP_volume,
+            flags: PropertyFlags::HARDWARE,
+            value: Value::Float(0.5),
+        }],
+    });
+
+    let vec_rs: Vec<u8> = PodSerializer::serialize(Cursor::new(Vec::new()), &object)
+        .unwrap()
+        .0
+        .into_inner();
+
+    let (_, metadata) = PodDeserializer::deserialize_from::<Metadata>(&vec_rs).unwrap();
+    assert_eq!(
+        metadata,
+        Metadata {
+            type_: spa_sys::SPA_TYPE_OBJECT_Props,
+            id: spa_sys::SPA_PARAM_Props,
+            key: PropKey(spa_sys::SPA_PROP_volume),
+            flags: Some(PropertyFlags::HARDWARE),
+        }
+    );
+}
+
 #[test]
 #[cfg_attr(miri, ignore)]
 fn choice_range_f32() {
@@ -1606,6 +1760,44 @@ fn choice_none_i32() {
     );
 }
 
+#[test]
+#[cfg_attr(miri, ignore)]
+fn choice_unknown_flags() {
+    // Flags which are not defined yet have to round-trip untouched.
+    let flags = ChoiceFlags::from_bits_retain(0x5);
+
+    for (choice_type, values) in [
+        (spa_sys::SPA_CHOICE_None, &[5][..]),
+        (spa_sys::SPA_CHOICE_Enum, &[5, 2, 10][..]),
+    ] {
+        let mut vec_c: Vec<u8> = vec![0; 40];
+        unsafe {
+            assert_ne!(
+                c::build_choice_i32(
+                    vec_c.as_mut_ptr(),
+                    vec_c.len(),
+                    choice_type,
+                    flags.bits(),
+                    values.len() as u32,
+                    values.as_ptr(),
+                ),
+                std::ptr::null()
+            );
+        }
+        let (_, value) = PodDeserializer::deserialize_any_from(&vec_c).unwrap();
+        let Value::Choice(ChoiceValue::Int(choice)) = &value else {
+            panic!("unexpected value {:?}", value);
+        };
+        assert_eq!(choice.0, flags);
+
+        let vec_rs: Vec<u8> = PodSerializer::serialize(Cursor::new(Vec::new()), &value)
+            .unwrap()
+            .0
+            .into_inner();
+        assert_eq!(vec_rs, vec_c[..vec_rs.len()]);
+    }
+}
+
 #[test]
 #[cfg_attr(miri, ignore)]
 fn choice_step_i32() {
@@ -2187,6 +2379,10 @@ fn composite_values() {
             ChoiceEnum::None(0),
         ))),
         Value::Pointer(0, ptr::null_mut()),
+        Value::Other {
+            type_: 0x10000,
+            bytes: vec![1, 2, 3],
+        },
     ];
 
     for value in &all_type_values {
@@ -2225,6 +2421,32 @@ fn composite_values() {
     );
 }
 
+#[test]
+fn value_array_conversions() {
+    let array = ValueArray::from(vec![0.25f32, 1.0]);
+    assert_eq!(array, ValueArray::Float(vec![0.25, 1.0]));
+    assert_eq!(array.len(), 2);
+    assert!(!array.is_empty());
+    assert_eq!(array.get(1), Some(Value::Float(1.0)));
+    assert_eq!(array.get(2), None);
+    assert_eq!(
+        array.iter().collect::<Vec<_>>(),
+        [Value::Float(0.25), Value::Float(1.0)]
+    );
+
+    // Wrong type
+    let array = Vec::<i32>::try_from(array).unwrap_err();
+    assert_eq!(Vec::<f32>::try_from(array), Ok(vec![0.25, 1.0]));
+
+    assert_eq!(
+        Value::array([Id(3), Id(4)]),
+        Value::ValueArray(ValueArray::Id(vec![Id(3), Id(4)]))
+    );
+    let none = ValueArray::from(Vec::<()>::new());
+    assert!(none.is_empty());
+    assert_eq!(none.iter().count(), 0);
+}
+
 #[test]
 #[cfg_attr(miri, ignore)]
 fn audio_info_raw() {
@@ -2255,3 +2477,171 @@ fn audio_info_raw() {
     assert_eq!(vec_rs, vec_c);
     assert!(unsafe { c::parse_audio_info_raw(vec_c.as_mut_ptr()) } > 0);
 }
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn object_macros() {
+    use libspa::{
+        param::{
+            format::{FormatProperties, MediaSubtype, MediaType},
+            ParamType,
+        },
+        pod::{object, property},
+        utils::SpaTypes,
+    };
+
+    let extra: Vec<Property> = vec![property!(
+        FormatProperties::MediaSubtype,
+        Id,
+        MediaSubtype::Raw
+    )];
+
+    let obj = object! {
+        SpaTypes::ObjectParamFormat,
+        ParamType::EnumFormat,
+        property!(
+            FormatProperties::MediaType,
+            flags = PropertyFlags::MANDATORY,
+            Id,
+            MediaType::Audio
+        ),
+        ..extra
+    };
+
+    assert_eq!(
+        obj,
+        Object {
+            type_: SpaTypes::ObjectParamFormat.as_raw(),
+            id: ParamType::EnumFormat.as_raw(),
+            properties: vec![
+                Property::new(
+                    FormatProperties::MediaType.as_raw(),
+                    Value::Id(Id(MediaType::Audio.as_raw()))
+                )
+                .with_flags(PropertyFlags::MANDATORY),
+                Property::new(
+                    FormatProperties::MediaSubtype.as_raw(),
+                    Value::Id(Id(MediaSubtype::Raw.as_raw()))
+                ),
+            ],
+        }
+    );
+}
+
+#[test]
+fn id_enum() {
+    use libspa::{
+        param::{audio::AudioFormat, format::FormatProperties},
+        pod::property,
+        utils::IdEnum,
+    };
+
+    assert_eq!(AudioFormat::S16LE.to_id(), Id(AudioFormat::S16LE.as_raw()));
+    assert_eq!(
+        AudioFormat::from_id(Id(AudioFormat::F32P.as_raw())),
+        Some(AudioFormat::F32P)
+    );
+
+    assert_eq!(
+        property!(FormatProperties::AudioFormat, Id, Id(3)),
+        Property::new(FormatProperties::AudioFormat.as_raw(), Value::Id(Id(3)))
+    );
+    assert_eq!(
+        property!(
+            FormatProperties::AudioFormat,
+            Choice,
+            Enum,
+            Id,
+            AudioFormat::S16LE,
+            AudioFormat::S16LE,
+            AudioFormat::F32LE
+        )
+        .value,
+        Value::Choice(ChoiceValue::Id(Choice::id_enum(
+            AudioFormat::S16LE,
+            [AudioFormat::S16LE, AudioFormat::F32LE]
+        )))
+    );
+}
+
+#[test]
+fn other() {
+    // A struct holding a pod of an unknown vendor type and an int.
+    let mut vec_c: Vec<u8> = Vec::new();
+    for word in [32u32, spa_sys::SPA_TYPE_Struct, 5, 0x10000] {
+        vec_c.extend(word.to_ne_bytes());
+    }
+    vec_c.extend([1, 2, 3, 4, 5, 0, 0, 0]);
+    for word in [4u32, spa_sys::SPA_TYPE_Int, 7, 0] {
+        vec_c.extend(word.to_ne_bytes());
+    }
+
+    let value = Value::Struct(vec![
+        Value::Other {
+            type_: 0x10000,
+            bytes: vec![1, 2, 3, 4, 5],
+        },
+        Value::Int(7),
+    ]);
+    assert_eq!(
+        PodDeserializer::deserialize_any_from(&vec_c),
+        Ok((&[] as &[u8], value.clone()))
+    );
+
+    let vec_rs: Vec<u8> = PodSerializer::serialize(Cursor::new(Vec::new()), &value)
+        .unwrap()
+        .0
+        .into_inner();
+    assert_eq!(vec_rs, vec_c);
+}
+
+#[test]
+fn containers() {
+    fn to_value<P: PodSerialize + ?Sized>(pod: &P) -> Value {
+        let vec_rs: Vec<u8> = PodSerializer::serialize(Cursor::new(Vec::new()), pod)
+            .unwrap()
+            .0
+            .into_inner();
+        PodDeserializer::deserialize_any_from(&vec_rs).unwrap().1
+    }
+
+    let array = Value::ValueArray(ValueArray::Int(vec![1, 2, 3]));
+    assert_eq!(to_value(&[1, 2, 3]), array);
+    assert_eq!(to_value(&vec![1, 2, 3]), array);
+    assert_eq!(to_value(&&[1, 2, 3][..]), array);
+    assert_eq!(
+        to_value(&std::collections::HashSet::from([Id(4)])),
+        Value::ValueArray(ValueArray::Id(vec![Id(4)]))
+    );
+
+    assert_eq!(
+        to_value(&vec!["a".to_owned(), "b".to_owned()]),
+        Value::Struct(vec![
+            Value::String("a".to_owned()),
+            Value::String("b".to_owned())
+        ])
+    );
+}
+
+#[test]
+fn choice_from_bitflags() {
+    bitflags::bitflags! {
+        #[derive(Debug, Clone, Copy)]
+        struct Features: u32 {
+            const A = 1 << 0;
+            const B = 1 << 1;
+            const C = 1 << 4;
+        }
+    }
+
+    assert_eq!(
+        Choice::flags(Features::A | Features::C),
+        Choice(
+            ChoiceFlags::empty(),
+            ChoiceEnum::Flags {
+                default: 0x11,
+                flags: vec![0x1, 0x2, 0x10],
+            }
+        )
+    );
+}
@@ -4,10 +4,28 @@
 use std::env;
 use std::path::PathBuf;
 
+/// Probe the libraries, falling back to the PipeWire built by `libspa-sys` with its `vendored`
+/// feature, which exports the `PKG_CONFIG_PATH` finding it.
+#[cfg(feature = "vendored")]
+fn probe() -> Result<system_deps::Dependencies, system_deps::Error> {
+    println!("cargo:rerun-if-env-changed=DEP_LIBSPA_0.2_PKGCONFIG");
+    let probed = system_deps::Config::new().probe();
+    match env::var_os("DEP_LIBSPA_0.2_PKGCONFIG") {
+        Some(path) if probed.is_err() => {
+            env::set_var("PKG_CONFIG_PATH", path);
+            system_deps::Config::new().probe()
+        }
+        _ => probed,
+    }
+}
+
+#[cfg(not(feature = "vendored"))]
+fn probe() -> Result<system_deps::Dependencies, system_deps::Error> {
+    system_deps::Config::new().probe()
+}
+
 fn main() {
-    let libs = system_deps::Config::new()
-        .probe()
-        .expect("Cannot find libpipewire");
+    let libs = probe().expect("Cannot find libpipewire");
     let libpipewire = libs.get_by_name("libpipewire").unwrap();
 
     // Tell cargo to invalidate the built crate whenever the wrapper changes
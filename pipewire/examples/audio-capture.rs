@@ -181,9 +181,7 @@ pub fn main() -> Result<(), pw::Error> {
     stream.connect(
         spa::utils::Direction::Input,
         None,
-        pw::stream::StreamFlags::AUTOCONNECT
-            | pw::stream::StreamFlags::MAP_BUFFERS
-            | pw::stream::StreamFlags::RT_PROCESS,
+        pw::stream::StreamFlags::capture_defaults(),
         &mut params,
     )?;
 
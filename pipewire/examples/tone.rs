@@ -41,7 +41,7 @@ pub fn main() -> Result<(), pw::Error> {
                 let datas = buffer.datas_mut();
                 let stride = CHAN_SIZE * DEFAULT_CHANNELS as usize;
                 let data = &mut datas[0];
-                let n_frames = if let Some(slice) = data.data() {
+                let n_frames = if let Some(slice) = data.data_mut() {
                     let n_frames = slice.len() / stride;
                     for i in 0..n_frames {
                         *acc += PI_2 * 440.0 / DEFAULT_RATE as f64;
@@ -90,9 +90,7 @@ pub fn main() -> Result<(), pw::Error> {
     stream.connect(
         spa::utils::Direction::Output,
         None,
-        pw::stream::StreamFlags::AUTOCONNECT
-            | pw::stream::StreamFlags::MAP_BUFFERS
-            | pw::stream::StreamFlags::RT_PROCESS,
+        pw::stream::StreamFlags::playback_defaults(),
         &mut params,
     )?;
 
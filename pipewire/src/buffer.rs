@@ -1,9 +1,29 @@
 use super::stream::StreamRef;
 
-use spa::buffer::Data;
+use nix::errno::Errno;
+use spa::buffer::{
+    meta::{CursorMeta, HeaderFlags, HeaderMeta, Meta, MetaType, VideoCropMeta},
+    ChunkFlags, Data, DataFlags, DataType, DataTypes,
+};
+use spa::param::video::VideoInfoRaw;
+use spa::utils::{Point, Region};
+use std::cell::{Cell, RefCell};
 use std::convert::TryFrom;
-use std::ptr::NonNull;
+#[cfg(feature = "v1_2_0")]
+use std::os::fd::BorrowedFd;
+use std::ptr::{self, NonNull};
 
+#[cfg(feature = "v1_2_0")]
+use spa::buffer::meta::SyncTimelineMeta;
+
+use crate::{error::ResultExt, Error};
+
+/// A buffer dequeued from a stream with [`StreamRef::dequeue_buffer`].
+///
+/// The buffer is queued back to the stream when it is dropped: an output stream sends its content,
+/// and an input stream reuses it for new data. Use [`discard`](Self::discard) to send an output
+/// buffer without content, and [`into_parts`](Self::into_parts) to keep the buffer out of the
+/// stream for longer.
 pub struct Buffer<'s> {
     buf: NonNull<pw_sys::pw_buffer>,
 
@@ -12,14 +32,49 @@ pub struct Buffer<'s> {
     stream: &'s StreamRef,
 }
 
-impl Buffer<'_> {
-    pub(crate) unsafe fn from_raw(
-        buf: *mut pw_sys::pw_buffer,
-        stream: &StreamRef,
-    ) -> Option<Buffer<'_>> {
+impl<'s> Buffer<'s> {
+    /// Wrap a buffer dequeued with [`StreamRef::dequeue_raw_buffer`], returning `None` if `buf`
+    /// is null.
+    ///
+    /// # Safety
+    /// `buf` must have been dequeued from `stream` and not queued back since, and must not be
+    /// wrapped in another [`Buffer`].
+    pub unsafe fn from_raw(buf: *mut pw_sys::pw_buffer, stream: &'s StreamRef) -> Option<Self> {
         NonNull::new(buf).map(|buf| Buffer { buf, stream })
     }
 
+    /// Take the buffer without queueing it back to the stream.
+    ///
+    /// The buffer stays out of the pool of the stream until it is queued with
+    /// [`StreamRef::queue_raw_buffer`] or wrapped again with [`Buffer::from_raw`]. It must be
+    /// queued back before the stream is disconnected.
+    pub fn into_parts(self) -> (NonNull<pw_sys::pw_buffer>, &'s StreamRef) {
+        let this = std::mem::ManuallyDrop::new(self);
+        (this.buf, this.stream)
+    }
+
+    /// Queue the buffer back to the stream, like dropping it does, but return the error if the
+    /// stream refuses it.
+    pub fn queue(self) -> Result<(), Error> {
+        let (buf, stream) = self.into_parts();
+        unsafe { stream.try_queue_raw_buffer(buf.as_ptr()) }
+    }
+
+    /// Queue the buffer back to the stream without content, by clearing the chunks of its datas.
+    ///
+    /// This is the same as dropping an input buffer, whose chunks are not read by the stream.
+    pub fn discard(mut self) {
+        for data in self.datas_mut() {
+            let chunk = data.chunk_mut();
+            *chunk.offset_mut() = 0;
+            *chunk.size_mut() = 0;
+        }
+    }
+
+    pub fn as_raw(&self) -> &pw_sys::pw_buffer {
+        unsafe { self.buf.as_ref() }
+    }
+
     pub fn datas_mut(&mut self) -> &mut [Data] {
         let buffer: *mut spa_sys::spa_buffer = unsafe { self.buf.as_ref().buffer };
 
@@ -37,12 +92,282 @@ impl Buffer<'_> {
         slice_of_data
     }
 
+    fn find_raw_meta(&self, type_: MetaType) -> *mut spa_sys::spa_meta {
+        let buffer = unsafe { self.buf.as_ref().buffer };
+        if buffer.is_null() {
+            return ptr::null_mut();
+        }
+
+        unsafe { spa_sys::spa_buffer_find_meta(buffer, type_.as_raw()) }
+    }
+
+    fn find_meta<M: Meta>(&self) -> *mut M {
+        let buffer = unsafe { self.buf.as_ref().buffer };
+        if buffer.is_null() {
+            return ptr::null_mut();
+        }
+
+        unsafe {
+            spa_sys::spa_buffer_find_meta_data(buffer, M::TYPE.as_raw(), std::mem::size_of::<M>())
+                .cast()
+        }
+    }
+
+    /// The metadata of type `M`, if the buffer has it.
+    ///
+    /// The metadata has to be requested with a `Meta` param to be attached to the buffers.
+    pub fn meta<M: Meta>(&self) -> Option<&M> {
+        unsafe { self.find_meta::<M>().as_ref() }
+    }
+
+    /// The metadata of type `M`, if the buffer has it.
+    pub fn meta_mut<M: Meta>(&mut self) -> Option<&mut M> {
+        unsafe { self.find_meta::<M>().as_mut() }
+    }
+
+    /// Set the [`HeaderMeta`] of the buffer.
+    ///
+    /// Fails with `ENOENT` if the buffer does not have the meta.
+    pub fn set_header(&mut self, pts: i64, flags: HeaderFlags, seq: u64) -> Result<(), Error> {
+        let header = self
+            .meta_mut::<HeaderMeta>()
+            .ok_or_else(|| missing_meta("set the header"))?;
+        header.set_flags(flags);
+        header.set_offset(0);
+        header.set_pts(pts);
+        header.set_dts_offset(0);
+        header.set_seq(seq);
+        Ok(())
+    }
+
+    /// Set the region of the frame holding the content, with the [`VideoCropMeta`].
+    ///
+    /// Fails with `ENOENT` if the buffer does not have the meta.
+    pub fn set_crop(&mut self, region: Region) -> Result<(), Error> {
+        self.meta_mut::<VideoCropMeta>()
+            .ok_or_else(|| missing_meta("set the crop region"))?
+            .set_region(region);
+        Ok(())
+    }
+
+    /// Set the [`CursorMeta`] of the buffer.
+    ///
+    /// `id` identifies the cursor, `0` meaning that there is no cursor. `bitmap` is the format and
+    /// the pixels of the cursor image, which only has to be sent when the image changed.
+    /// The meta has to be requested with a size fitting the largest bitmap.
+    ///
+    /// Fails with `ENOENT` if the buffer does not have the meta, and with `ENOSPC` if the bitmap
+    /// does not fit in the meta.
+    pub fn set_cursor(
+        &mut self,
+        id: u32,
+        position: Point,
+        hotspot: Point,
+        bitmap: Option<(&VideoInfoRaw, &[u8])>,
+    ) -> Result<(), Error> {
+        const OPERATION: &str = "set the cursor";
+        const CURSOR_SIZE: usize = std::mem::size_of::<spa_sys::spa_meta_cursor>();
+        const BITMAP_SIZE: usize = std::mem::size_of::<spa_sys::spa_meta_bitmap>();
+
+        let meta = unsafe { self.find_raw_meta(MetaType::Cursor).as_ref() }
+            .filter(|meta| usize::try_from(meta.size).unwrap() >= CURSOR_SIZE)
+            .ok_or_else(|| missing_meta(OPERATION))?;
+        let meta_size = usize::try_from(meta.size).unwrap();
+
+        // Check that the bitmap fits before touching the meta.
+        if let Some((_, pixels)) = bitmap {
+            if CURSOR_SIZE + BITMAP_SIZE + pixels.len() > meta_size {
+                return Err(spa::utils::result::Error::from(Errno::ENOSPC)).context(OPERATION);
+            }
+        }
+
+        let cursor = unsafe { &mut *meta.data.cast::<CursorMeta>() };
+        cursor.set_id(id);
+        cursor.set_position(position);
+        cursor.set_hotspot(hotspot);
+
+        let Some((format, pixels)) = bitmap else {
+            cursor.set_bitmap_offset(0);
+            return Ok(());
+        };
+        cursor.set_bitmap_offset(u32::try_from(CURSOR_SIZE).unwrap());
+
+        let size = format.size();
+        let stride = match size.height {
+            0 => 0,
+            height => pixels.len() / usize::try_from(height).unwrap(),
+        };
+        unsafe {
+            let bitmap = meta
+                .data
+                .cast::<u8>()
+                .add(CURSOR_SIZE)
+                .cast::<spa_sys::spa_meta_bitmap>();
+            (*bitmap).format = format.format().as_raw();
+            (*bitmap).size = size;
+            (*bitmap).stride = i32::try_from(stride).unwrap();
+            (*bitmap).offset = u32::try_from(BITMAP_SIZE).unwrap();
+
+            ptr::copy_nonoverlapping(
+                pixels.as_ptr(),
+                bitmap.cast::<u8>().add(BITMAP_SIZE),
+                pixels.len(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// The explicit sync timeline of the buffer.
+    ///
+    /// Returns `None` unless the buffer has the [`SyncTimelineMeta`] and ends with the two `SyncObj`
+    /// datas holding the acquire and release timelines.
+    #[cfg(feature = "v1_2_0")]
+    pub fn sync_timeline(&mut self) -> Option<SyncTimeline<'_>> {
+        // Safety: the meta does not overlap with the datas.
+        let meta = unsafe { self.find_meta::<SyncTimelineMeta>().as_mut()? };
+
+        let [.., acquire, release] = self.datas_mut() else {
+            return None;
+        };
+        if acquire.type_() != DataType::SyncObj || release.type_() != DataType::SyncObj {
+            return None;
+        }
+
+        Some(SyncTimeline {
+            meta,
+            acquire_fd: acquire.fd()?,
+            release_fd: release.fd()?,
+        })
+    }
+
+    /// Describe the content written to the data at `index`, before the buffer is queued by
+    /// dropping it.
+    ///
+    /// Producers have to set the chunk of each data they filled, or consumers will see empty or
+    /// stale content. Fails with `EINVAL` if there is no data at `index`, and with `ENOSPC` if
+    /// the content does not fit in the data.
+    ///
+    /// ```no_run
+    /// # use pipewire::{buffer::BufferDone, stream::StreamRef};
+    /// # fn process(stream: &StreamRef) -> Result<(), pipewire::Error> {
+    /// const CHANNELS: u32 = 2;
+    /// const STRIDE: u32 = CHANNELS * std::mem::size_of::<f32>() as u32;
+    ///
+    /// let Some(mut buffer) = stream.dequeue_buffer() else {
+    ///     return Ok(());
+    /// };
+    /// let Some(samples) = buffer.datas_mut()[0].data_mut() else {
+    ///     return Ok(());
+    /// };
+    /// let frames = samples.len() as u32 / STRIDE;
+    /// samples[..(frames * STRIDE) as usize].fill(0);
+    ///
+    /// buffer.finish(0, BufferDone::new(frames * STRIDE, STRIDE as i32))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn finish(&mut self, index: usize, done: BufferDone) -> Result<(), Error> {
+        let data = self
+            .datas_mut()
+            .get_mut(index)
+            .ok_or(spa::utils::result::Error::from(Errno::EINVAL))
+            .context(FINISH)?;
+        done.apply(data)
+    }
+
     #[cfg(feature = "v0_3_49")]
     pub fn requested(&self) -> u64 {
         unsafe { self.buf.as_ref().requested }
     }
 }
 
+const FINISH: &str = "finish the buffer";
+
+/// The content written to a data of a [`Buffer`], see [`Buffer::finish`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferDone {
+    /// The offset of the content in the data.
+    pub offset: u32,
+    /// The size of the content in bytes.
+    pub size: u32,
+    /// The size of a frame of audio or of a row of video, in bytes.
+    pub stride: i32,
+    pub flags: ChunkFlags,
+}
+
+impl BufferDone {
+    /// Content of `size` bytes at the start of the data.
+    pub fn new(size: u32, stride: i32) -> Self {
+        Self {
+            offset: 0,
+            size,
+            stride,
+            flags: ChunkFlags::empty(),
+        }
+    }
+
+    fn apply(&self, data: &mut Data) -> Result<(), Error> {
+        let fits = self
+            .offset
+            .checked_add(self.size)
+            .map_or(false, |end| end <= data.max_size());
+        if !fits {
+            return Err(spa::utils::result::Error::from(Errno::ENOSPC)).context(FINISH);
+        }
+
+        let chunk = data.chunk_mut();
+        *chunk.offset_mut() = self.offset;
+        *chunk.size_mut() = self.size;
+        *chunk.stride_mut() = self.stride;
+        chunk.set_flags(self.flags);
+        Ok(())
+    }
+}
+
+/// The explicit sync timeline of a [`Buffer`], see [`SyncTimelineMeta`].
+///
+/// The file descriptors are DRM syncobjs, to be imported into the graphics API which waits for
+/// and signals the timeline points, such as Vulkan timeline semaphores.
+#[cfg(feature = "v1_2_0")]
+#[derive(Debug)]
+pub struct SyncTimeline<'a> {
+    meta: &'a mut SyncTimelineMeta,
+    acquire_fd: BorrowedFd<'a>,
+    release_fd: BorrowedFd<'a>,
+}
+
+#[cfg(feature = "v1_2_0")]
+impl<'a> SyncTimeline<'a> {
+    /// The timeline and point to wait for before reading the buffer, signaled by the producer.
+    pub fn acquire(&self) -> (BorrowedFd<'a>, u64) {
+        (self.acquire_fd, self.meta.acquire_point())
+    }
+
+    /// The timeline and point to wait for before reusing the buffer, signaled by the consumer.
+    pub fn release(&self) -> (BorrowedFd<'a>, u64) {
+        (self.release_fd, self.meta.release_point())
+    }
+
+    /// Set the point the producer will signal once the content of the buffer is ready.
+    pub fn set_acquire_point(&mut self, point: u64) {
+        self.meta.set_acquire_point(point);
+    }
+
+    /// Set the point the consumer has to signal once it is done with the buffer.
+    pub fn set_release_point(&mut self, point: u64) {
+        self.meta.set_release_point(point);
+    }
+
+    pub fn meta(&self) -> &SyncTimelineMeta {
+        self.meta
+    }
+
+    pub fn meta_mut(&mut self) -> &mut SyncTimelineMeta {
+        self.meta
+    }
+}
+
 impl Drop for Buffer<'_> {
     fn drop(&mut self) {
         unsafe {
@@ -50,3 +375,252 @@ impl Drop for Buffer<'_> {
         }
     }
 }
+
+/// Allocates the memory of the buffers of a stream connected with
+/// [`StreamFlags::ALLOC_BUFFERS`](crate::stream::StreamFlags::ALLOC_BUFFERS).
+///
+/// Each data of the buffers is backed by its own memfd of [`size`](Self::size) bytes, which is
+/// mapped readable and writable so that it can be filled through [`Data::data_mut`].
+///
+/// The pool is usually set on the listener of the stream with
+/// [`ListenerLocalBuilder::mem_pool`](crate::stream::ListenerLocalBuilder::mem_pool),
+/// which allocates the memory in the `add_buffer` event and frees it in the `remove_buffer` event.
+/// The size can be updated in the `param_changed` event, once the format of the stream is known.
+#[derive(Debug)]
+pub struct MemPool {
+    size: Cell<u32>,
+    error: RefCell<Option<Error>>,
+}
+
+impl MemPool {
+    /// Create a pool allocating `size` bytes for each data of the buffers.
+    pub fn new(size: u32) -> Self {
+        Self {
+            size: Cell::new(size),
+            error: RefCell::new(None),
+        }
+    }
+
+    /// The number of bytes allocated for each data of the buffers.
+    pub fn size(&self) -> u32 {
+        self.size.get()
+    }
+
+    /// Set the number of bytes allocated for each data of the buffers added from now on.
+    pub fn set_size(&self, size: u32) {
+        self.size.set(size);
+    }
+
+    /// Take the error of the last allocation which failed in the `add_buffer` event,
+    /// if any.
+    pub fn take_error(&self) -> Option<Error> {
+        self.error.take()
+    }
+
+    pub(crate) fn set_error(&self, error: Error) {
+        *self.error.borrow_mut() = Some(error);
+    }
+
+    /// Allocate and map the memory of all the datas of `buffer`.
+    ///
+    /// The datas must accept [`DataType::MemFd`] memory. On failure, the memory allocated
+    /// for the previous datas is freed.
+    ///
+    /// # Safety
+    /// `buffer` must be a valid buffer received in the `add_buffer` event of a stream connected
+    /// with `ALLOC_BUFFERS`.
+    pub unsafe fn alloc(&self, buffer: *mut pw_sys::pw_buffer) -> Result<(), Error> {
+        let datas = raw_datas(buffer);
+        let size = self.size();
+
+        let mut allocated = 0;
+        let res = datas.iter_mut().try_for_each(|data| {
+            alloc_data(data, size)?;
+            allocated += 1;
+            Ok(())
+        });
+
+        if res.is_err() {
+            for data in &mut datas[..allocated] {
+                free_data(data);
+            }
+        }
+
+        res
+    }
+
+    /// Unmap and close the memory allocated by [`alloc`](Self::alloc) for the datas of `buffer`.
+    ///
+    /// # Safety
+    /// `buffer` must be a valid buffer received in the `remove_buffer` event of a stream connected
+    /// with `ALLOC_BUFFERS`.
+    pub unsafe fn free(&self, buffer: *mut pw_sys::pw_buffer) {
+        for data in raw_datas(buffer) {
+            free_data(data);
+        }
+    }
+}
+
+unsafe fn raw_datas<'a>(buffer: *mut pw_sys::pw_buffer) -> &'a mut [spa_sys::spa_data] {
+    let buffer = (*buffer).buffer;
+
+    if buffer.is_null() || (*buffer).n_datas == 0 || (*buffer).datas.is_null() {
+        &mut []
+    } else {
+        std::slice::from_raw_parts_mut((*buffer).datas, usize::try_from((*buffer).n_datas).unwrap())
+    }
+}
+
+unsafe fn alloc_data(data: &mut spa_sys::spa_data, size: u32) -> Result<(), Error> {
+    // Before the memory is allocated, the type holds the mask of the accepted types.
+    if !DataTypes::from_bits(data.type_).contains(DataType::MemFd) {
+        return Err(spa::utils::result::Error::from(Errno::ENOTSUP)).context("allocate a memfd");
+    }
+
+    let fd = libc::memfd_create(
+        b"pipewire-rs-memfd\0".as_ptr().cast(),
+        libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING,
+    );
+    if fd < 0 {
+        return Err(last_error()).context("allocate a memfd");
+    }
+
+    if libc::ftruncate(fd, libc::off_t::try_from(size).unwrap()) < 0 {
+        let err = last_error();
+        libc::close(fd);
+        return Err(err).context("allocate a memfd");
+    }
+
+    // Sealing is not required, so ignore failures.
+    libc::fcntl(
+        fd,
+        libc::F_ADD_SEALS,
+        libc::F_SEAL_GROW | libc::F_SEAL_SHRINK | libc::F_SEAL_SEAL,
+    );
+
+    let mem = libc::mmap(
+        ptr::null_mut(),
+        usize::try_from(size).unwrap(),
+        libc::PROT_READ | libc::PROT_WRITE,
+        libc::MAP_SHARED,
+        fd,
+        0,
+    );
+    if mem == libc::MAP_FAILED {
+        let err = last_error();
+        libc::close(fd);
+        return Err(err).context("map a memfd");
+    }
+
+    data.type_ = DataType::MemFd.as_raw();
+    data.flags = DataFlags::READWRITE.bits();
+    data.fd = i64::from(fd);
+    data.mapoffset = 0;
+    data.maxsize = size;
+    data.data = mem;
+
+    Ok(())
+}
+
+unsafe fn free_data(data: &mut spa_sys::spa_data) {
+    if data.type_ != DataType::MemFd.as_raw() {
+        return;
+    }
+
+    if !data.data.is_null() {
+        libc::munmap(data.data, usize::try_from(data.maxsize).unwrap());
+        data.data = ptr::null_mut();
+    }
+    if data.fd >= 0 {
+        libc::close(i32::try_from(data.fd).unwrap());
+        data.fd = -1;
+    }
+}
+
+fn missing_meta(operation: &'static str) -> Error {
+    Error::from(spa::utils::result::Error::from(Errno::ENOENT)).context(operation)
+}
+
+fn last_error() -> spa::utils::result::Error {
+    Errno::last().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn mem_pool() {
+        let mut datas: [spa_sys::spa_data; 2] = unsafe { std::mem::zeroed() };
+        for data in &mut datas {
+            data.type_ = 1 << DataType::MemFd.as_raw();
+            data.fd = -1;
+        }
+        let mut spa_buffer: spa_sys::spa_buffer = unsafe { std::mem::zeroed() };
+        spa_buffer.n_datas = 2;
+        spa_buffer.datas = datas.as_mut_ptr();
+        let mut buffer: pw_sys::pw_buffer = unsafe { std::mem::zeroed() };
+        buffer.buffer = &mut spa_buffer;
+
+        let pool = MemPool::new(4096);
+        unsafe { pool.alloc(&mut buffer) }.unwrap();
+        for data in &mut datas {
+            assert_eq!(data.type_, DataType::MemFd.as_raw());
+            assert!(data.fd >= 0);
+            assert_eq!(data.maxsize, 4096);
+
+            let mem = unsafe { std::slice::from_raw_parts_mut(data.data.cast::<u8>(), 4096) };
+            mem.fill(0xAA);
+        }
+
+        unsafe { pool.free(&mut buffer) };
+        for data in &datas {
+            assert!(data.data.is_null());
+            assert_eq!(data.fd, -1);
+        }
+
+        // Only MemPtr memory is accepted.
+        datas[1].type_ = 1 << DataType::MemPtr.as_raw();
+        let err = unsafe { pool.alloc(&mut buffer) }.unwrap_err();
+        assert_eq!(err.errno(), Some(Errno::ENOTSUP));
+        // The first data has been freed again.
+        assert!(datas[0].data.is_null());
+    }
+
+    #[test]
+    fn buffer_done() {
+        let mut chunk: spa_sys::spa_chunk = unsafe { std::mem::zeroed() };
+        let mut raw: spa_sys::spa_data = unsafe { std::mem::zeroed() };
+        raw.maxsize = 1024;
+        raw.chunk = &mut chunk;
+        let data = unsafe { &mut *ptr::addr_of_mut!(raw).cast::<Data>() };
+
+        BufferDone::new(1024, 8).apply(data).unwrap();
+        assert_eq!(data.chunk().size(), 1024);
+        assert_eq!(data.chunk().stride(), 8);
+
+        let done = BufferDone {
+            offset: 512,
+            size: 256,
+            stride: 4,
+            flags: ChunkFlags::CORRUPTED,
+        };
+        done.apply(data).unwrap();
+        assert_eq!(data.chunk().offset(), 512);
+        assert_eq!(data.chunk().size(), 256);
+        assert_eq!(data.chunk().flags(), ChunkFlags::CORRUPTED);
+
+        // The content does not fit, the chunk is left untouched.
+        let err = BufferDone::new(1025, 8).apply(data).unwrap_err();
+        assert_eq!(err.errno(), Some(Errno::ENOSPC));
+        let err = BufferDone {
+            offset: u32::MAX,
+            ..done
+        }
+        .apply(data)
+        .unwrap_err();
+        assert_eq!(err.errno(), Some(Errno::ENOSPC));
+        assert_eq!(data.chunk().size(), 256);
+    }
+}
@@ -4,16 +4,44 @@
 use bitflags::bitflags;
 use libc::c_void;
 use std::ops::Deref;
-use std::pin::Pin;
-use std::{ffi::CString, ptr};
-use std::{fmt, mem};
+use std::{
+    ffi::{CStr, CString},
+    ptr,
+};
+use std::{fmt, marker::PhantomData, mem};
 
 use crate::{
+    core::CoreRef,
+    listeners,
     permissions::Permission,
     proxy::{Listener, Proxy, ProxyT},
     types::ObjectType,
 };
-use spa::spa_interface_call_method;
+use spa::utils::interface::VersionedInterface;
+
+spa::interface_methods! {
+    trait ClientMethods for pw_sys::pw_client_methods {
+        fn error(&self, id: u32, res: i32, message: &CStr) -> Option<i32> {
+            since 0;
+            unsafe { call(id, res, message.as_ptr()) }
+        }
+
+        fn update_properties(&self, properties: &spa::utils::dict::DictRef) -> Option<i32> {
+            since 0;
+            unsafe { call(properties.as_raw_ptr()) }
+        }
+
+        fn get_permissions(&self, index: u32, num: u32) -> Option<i32> {
+            since 0;
+            unsafe { call(index, num) }
+        }
+
+        fn update_permissions(&self, permissions: &[Permission]) -> Option<i32> {
+            since 0;
+            unsafe { call(permissions.len() as u32, permissions.as_ptr().cast()) }
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Client {
@@ -42,6 +70,11 @@ impl ProxyT for Client {
 }
 
 impl Client {
+    fn interface(&self) -> VersionedInterface<pw_sys::pw_client_methods> {
+        // Safety: the proxy is a client, which stays valid as long as `self`.
+        unsafe { VersionedInterface::new(self.proxy.as_ptr().cast()) }
+    }
+
     // TODO: add non-local version when we'll bind pw_thread_loop_start()
     #[must_use]
     pub fn add_listener_local(&self) -> ClientListenerLocalBuilder {
@@ -54,52 +87,58 @@ impl Client {
     pub fn error(&self, id: u32, res: i32, message: &str) {
         let message = CString::new(message).expect("Null byte in message parameter");
 
-        unsafe {
-            spa_interface_call_method!(
-                self.proxy.as_ptr(),
-                pw_sys::pw_client_methods,
-                error,
-                id,
-                res,
-                message.as_ptr() as *const _
-            );
-        };
+        self.interface().error(id, res, &message);
     }
 
     pub fn update_properties(&self, properties: &spa::utils::dict::DictRef) {
-        unsafe {
-            spa_interface_call_method!(
-                self.proxy.as_ptr(),
-                pw_sys::pw_client_methods,
-                update_properties,
-                properties.as_raw_ptr()
-            );
-        }
+        self.interface().update_properties(properties);
     }
 
     pub fn get_permissions(&self, index: u32, num: u32) {
-        unsafe {
-            spa_interface_call_method!(
-                self.proxy.as_ptr(),
-                pw_sys::pw_client_methods,
-                get_permissions,
-                index,
-                num
-            );
-        }
+        self.interface().get_permissions(index, num);
     }
 
     pub fn update_permissions(&self, permissions: &[Permission]) {
-        unsafe {
-            spa_interface_call_method!(
-                self.proxy.as_ptr(),
-                pw_sys::pw_client_methods,
-                update_permissions,
-                permissions.len() as u32,
-                permissions.as_ptr().cast()
-            );
+        self.interface().update_permissions(permissions);
+    }
+}
+
+/// The client of the connection of a core, see [`CoreRef::client`].
+///
+/// The proxy is owned by the core, so it is not destroyed when this is dropped.
+pub struct CoreClient<'a> {
+    client: mem::ManuallyDrop<Client>,
+    _core: PhantomData<&'a CoreRef>,
+}
+
+impl<'a> CoreClient<'a> {
+    pub(crate) fn new(_core: &'a CoreRef, client: ptr::NonNull<pw_sys::pw_client>) -> Self {
+        Self {
+            client: mem::ManuallyDrop::new(Client {
+                proxy: Proxy::new(client.cast()),
+            }),
+            _core: PhantomData,
         }
     }
+
+    /// The global id of the client, known once the server processed the connection.
+    pub fn id(&self) -> Option<u32> {
+        self.client.proxy.bound_id()
+    }
+}
+
+impl Deref for CoreClient<'_> {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.client
+    }
+}
+
+impl fmt::Debug for CoreClient<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CoreClient").field(&*self.client).finish()
+    }
 }
 
 #[derive(Default)]
@@ -209,22 +248,11 @@ impl fmt::Debug for ClientInfo {
 }
 
 pub struct ClientListener {
-    // Need to stay allocated while the listener is registered
-    #[allow(dead_code)]
-    events: Pin<Box<pw_sys::pw_client_events>>,
-    listener: Pin<Box<spa_sys::spa_hook>>,
-    #[allow(dead_code)]
-    data: Box<ListenerLocalCallbacks>,
+    _listener: listeners::Listener<pw_sys::pw_client_events, ListenerLocalCallbacks>,
 }
 
 impl Listener for ClientListener {}
 
-impl Drop for ClientListener {
-    fn drop(&mut self) {
-        spa::utils::hook::remove(*self.listener);
-    }
-}
-
 impl<'a> ClientListenerLocalBuilder<'a> {
     #[must_use]
     pub fn info<F>(mut self, info: F) -> Self
@@ -249,11 +277,13 @@ impl<'a> ClientListenerLocalBuilder<'a> {
             data: *mut c_void,
             info: *const pw_sys::pw_client_info,
         ) {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-            let info =
-                ptr::NonNull::new(info as *mut pw_sys::pw_client_info).expect("info is NULL");
-            let info = info.cast::<ClientInfoRef>().as_ref();
-            callbacks.info.as_ref().unwrap()(info);
+            crate::unwind::guard("client_events_info", || {
+                let callbacks = listeners::callback_data::<ListenerLocalCallbacks>(data);
+                let info =
+                    ptr::NonNull::new(info as *mut pw_sys::pw_client_info).expect("info is NULL");
+                let info = info.cast::<ClientInfoRef>().as_ref();
+                callbacks.info.as_ref().unwrap()(info);
+            })
         }
 
         unsafe extern "C" fn client_events_permissions(
@@ -262,15 +292,17 @@ impl<'a> ClientListenerLocalBuilder<'a> {
             n_permissions: u32,
             permissions: *const pw_sys::pw_permission,
         ) {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-            let permissions =
-                std::slice::from_raw_parts(permissions.cast(), n_permissions as usize);
+            crate::unwind::guard("client_events_permissions", || {
+                let callbacks = listeners::callback_data::<ListenerLocalCallbacks>(data);
+                let permissions =
+                    std::slice::from_raw_parts(permissions.cast(), n_permissions as usize);
 
-            callbacks.permissions.as_ref().unwrap()(index, permissions);
+                callbacks.permissions.as_ref().unwrap()(index, permissions);
+            })
         }
 
         let e = unsafe {
-            let mut e: Pin<Box<pw_sys::pw_client_events>> = Box::pin(mem::zeroed());
+            let mut e: pw_sys::pw_client_events = mem::zeroed();
             e.version = pw_sys::PW_VERSION_CLIENT_EVENTS;
 
             if self.cbs.info.is_some() {
@@ -283,29 +315,19 @@ impl<'a> ClientListenerLocalBuilder<'a> {
             e
         };
 
-        let (listener, data) = unsafe {
-            let client = &self.client.proxy.as_ptr();
-
-            let data = Box::into_raw(Box::new(self.cbs));
-            let mut listener: Pin<Box<spa_sys::spa_hook>> = Box::pin(mem::zeroed());
-            let listener_ptr: *mut spa_sys::spa_hook = listener.as_mut().get_unchecked_mut();
-
-            spa_interface_call_method!(
-                client,
-                pw_sys::pw_client_methods,
-                add_listener,
-                listener_ptr.cast(),
-                e.as_ref().get_ref(),
-                data as *mut _
-            );
-
-            (listener, Box::from_raw(data))
+        let interface = self.client.interface();
+        let listener = unsafe {
+            listeners::Listener::new(e, self.cbs, |hook, events, data| {
+                interface.call(
+                    0,
+                    |methods| methods.add_listener,
+                    |f, object| f(object, hook.cast(), events, data),
+                );
+            })
         };
 
         ClientListener {
-            events: e,
-            listener,
-            data,
+            _listener: listener,
         }
     }
 }
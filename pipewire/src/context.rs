@@ -2,17 +2,26 @@
 // SPDX-License-Identifier: MIT
 
 use std::{
-    fmt,
+    cell::Cell,
+    ffi::{CStr, CString},
+    fmt, mem,
     ops::Deref,
     os::unix::prelude::{IntoRawFd, OwnedFd},
+    pin::Pin,
     ptr,
     rc::Rc,
 };
 
+use libc::c_void;
+
 use crate::core::Core;
-use crate::error::Error;
+use crate::error::{Error, ResultExt};
 use crate::loop_::{IsLoopRc, LoopRef};
 use crate::properties::{Properties, PropertiesRef};
+use nix::errno::Errno;
+#[cfg(feature = "v0_3_64")]
+use spa::utils::result::SpaResult;
+use spa::{support::plugin::HandleRef, utils::dict::DictRef};
 
 #[repr(transparent)]
 pub struct ContextRef(pw_sys::pw_context);
@@ -33,11 +42,214 @@ impl ContextRef {
             props.cast().as_ref()
         }
     }
+
     pub fn update_properties(&self, properties: &spa::utils::dict::DictRef) {
         unsafe {
             pw_sys::pw_context_update_properties(self.as_raw_ptr(), properties.as_raw_ptr());
         }
     }
+
+    /// Get a section of the configuration loaded by the context, such as `context.modules` or
+    /// `context.objects`, in its SPA-JSON representation.
+    ///
+    /// Returns [`None`] if the section does not exist.
+    ///
+    /// # Panics
+    /// If `section` contains a null byte.
+    pub fn conf_section(&self, section: &str) -> Option<&str> {
+        let section = CString::new(section).expect("Null byte in section parameter");
+
+        unsafe {
+            let conf = pw_sys::pw_context_get_conf_section(self.as_raw_ptr(), section.as_ptr());
+            if conf.is_null() {
+                None
+            } else {
+                Some(CStr::from_ptr(conf).to_str().unwrap())
+            }
+        }
+    }
+
+    /// Apply the section `section` of the configuration `config`, as if it was part of the
+    /// configuration the context was created with.
+    ///
+    /// `config` maps the names of the sections, such as `context.modules` or `context.objects`,
+    /// to their SPA-JSON value. Returns the number of items of the section which were applied,
+    /// such as the number of loaded modules.
+    ///
+    /// # Panics
+    /// If `section` contains a null byte.
+    #[cfg(feature = "v0_3_64")]
+    pub fn set_config(&self, config: &PropertiesRef, section: &str) -> Result<u32, Error> {
+        let section = CString::new(section).expect("Null byte in section parameter");
+
+        let res = unsafe {
+            pw_sys::pw_context_parse_conf_section(
+                self.as_raw_ptr(),
+                config.as_raw_ptr(),
+                section.as_ptr(),
+            )
+        };
+        let count = SpaResult::from_c(res)
+            .into_sync_result()
+            .context("apply a configuration section")?;
+        Ok(count.unsigned_abs())
+    }
+
+    /// Update `properties` with the section `section` of the configuration loaded by the context,
+    /// such as `stream.properties`.
+    ///
+    /// Returns the number of properties which were updated.
+    ///
+    /// # Panics
+    /// If `section` contains a null byte.
+    #[cfg(feature = "v0_3_64")]
+    pub fn update_properties_from_config(
+        &self,
+        section: &str,
+        properties: &mut PropertiesRef,
+    ) -> Result<u32, Error> {
+        let section = CString::new(section).expect("Null byte in section parameter");
+
+        let res = unsafe {
+            pw_sys::pw_context_conf_update_props(
+                self.as_raw_ptr(),
+                section.as_ptr(),
+                properties.as_raw_ptr(),
+            )
+        };
+        let count = SpaResult::from_c(res)
+            .into_sync_result()
+            .context("update properties from the configuration")?;
+        Ok(count.unsigned_abs())
+    }
+
+    /// Get the SPA support interfaces (log, system, loop, cpu, ...) the context was created with.
+    pub fn support(&self) -> &[spa_sys::spa_support] {
+        unsafe {
+            let mut n_support = 0;
+            let support = pw_sys::pw_context_get_support(self.as_raw_ptr(), &mut n_support);
+
+            if support.is_null() {
+                &[]
+            } else {
+                std::slice::from_raw_parts(support, n_support.try_into().unwrap())
+            }
+        }
+    }
+
+    /// Instantiate an object from the SPA plugin factory `factory_name`, such as `api.alsa.pcm.sink`.
+    ///
+    /// The plugin is looked up using the `context.spa-libs` section of the configuration.
+    /// The returned handle can be exported into the graph with [`CoreRef::export_handle`](crate::core::CoreRef::export_handle).
+    ///
+    /// # Panics
+    /// If `factory_name` contains a null byte.
+    pub fn load_spa_handle(
+        &self,
+        factory_name: &str,
+        info: Option<&DictRef>,
+    ) -> Result<SpaHandle, Error> {
+        let factory_name = CString::new(factory_name).expect("Null byte in factory_name parameter");
+
+        let handle = unsafe {
+            pw_sys::pw_context_load_spa_handle(
+                self.as_raw_ptr(),
+                factory_name.as_ptr(),
+                info.map_or(ptr::null(), |info| info.as_raw_ptr().cast_const()),
+            )
+        };
+        let ptr = ptr::NonNull::new(handle).ok_or(Error::CreationFailed)?;
+
+        Ok(SpaHandle { ptr })
+    }
+}
+
+/// A module loaded with [`Context::load_module`].
+///
+/// The module is unloaded when this is dropped, unless it already destroyed itself, as some
+/// modules do on errors.
+pub struct LoadedModule {
+    ptr: ptr::NonNull<pw_sys::pw_impl_module>,
+    listener: Pin<Box<ModuleListener>>,
+    // The module is destroyed with the context, which has to outlive it.
+    _context: Context,
+}
+
+impl LoadedModule {
+    /// The pointer to the module, dangling once it is [destroyed](Self::is_destroyed).
+    pub fn as_raw_ptr(&self) -> *mut pw_sys::pw_impl_module {
+        self.ptr.as_ptr()
+    }
+
+    /// Whether the module destroyed itself.
+    pub fn is_destroyed(&self) -> bool {
+        self.listener.destroyed.get()
+    }
+}
+
+impl fmt::Debug for LoadedModule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoadedModule")
+            .field("ptr", &self.ptr)
+            .field("destroyed", &self.is_destroyed())
+            .finish()
+    }
+}
+
+impl Drop for LoadedModule {
+    fn drop(&mut self) {
+        if !self.is_destroyed() {
+            // This removes the listener through its `destroy` callback.
+            unsafe {
+                pw_sys::pw_impl_module_destroy(self.ptr.as_ptr());
+            }
+        }
+    }
+}
+
+/// Follows the destruction of a [`LoadedModule`].
+struct ModuleListener {
+    hook: spa_sys::spa_hook,
+    events: pw_sys::pw_impl_module_events,
+    destroyed: Cell<bool>,
+}
+
+unsafe extern "C" fn module_destroyed(data: *mut c_void) {
+    crate::unwind::guard("module_destroyed", || {
+        let listener = data.cast::<ModuleListener>();
+        // The hooks of the module are not used once it is freed.
+        spa::utils::hook::remove((*listener).hook);
+        (*listener).destroyed.set(true);
+    })
+}
+
+/// An object instantiated from a SPA plugin with [`ContextRef::load_spa_handle`].
+///
+/// The object is unloaded when this is dropped.
+pub struct SpaHandle {
+    ptr: ptr::NonNull<spa_sys::spa_handle>,
+}
+
+impl Deref for SpaHandle {
+    type Target = HandleRef;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.ptr.cast::<HandleRef>().as_ref() }
+    }
+}
+
+impl fmt::Debug for SpaHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpaHandle").field("ptr", &self.ptr).finish()
+    }
+}
+
+impl Drop for SpaHandle {
+    fn drop(&mut self) {
+        unsafe {
+            pw_sys::pw_unload_spa_handle(self.ptr.as_ptr());
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -77,6 +289,61 @@ impl Context {
         })
     }
 
+    /// Load the module `name`, such as `libpipewire-module-loopback`, into the context.
+    ///
+    /// `args` are the arguments of the module, usually a SPA-JSON object. The module is loaded in
+    /// this process, and the objects it creates are exported to the server through the context,
+    /// which the returned handle keeps alive.
+    ///
+    /// # Panics
+    /// If `name` or `args` contain a null byte.
+    pub fn load_module(
+        &self,
+        name: &str,
+        args: Option<&str>,
+        properties: Option<Properties>,
+    ) -> Result<LoadedModule, Error> {
+        let name = CString::new(name).expect("Null byte in name parameter");
+        let args = args.map(|args| CString::new(args).expect("Null byte in args parameter"));
+
+        let module = unsafe {
+            pw_sys::pw_context_load_module(
+                self.as_raw_ptr(),
+                name.as_ptr(),
+                args.as_ref().map_or(ptr::null(), |args| args.as_ptr()),
+                properties.map_or(ptr::null_mut(), |props| props.into_raw()),
+            )
+        };
+        let ptr = ptr::NonNull::new(module)
+            .ok_or_else(|| spa::utils::result::Error::from(Errno::last()))
+            .context("load a module")?;
+
+        let mut listener = Box::pin(ModuleListener {
+            hook: unsafe { mem::zeroed() },
+            events: pw_sys::pw_impl_module_events {
+                version: pw_sys::PW_VERSION_IMPL_MODULE_EVENTS,
+                destroy: Some(module_destroyed),
+                ..unsafe { mem::zeroed() }
+            },
+            destroyed: Cell::new(false),
+        });
+        unsafe {
+            let listener = listener.as_mut().get_unchecked_mut();
+            pw_sys::pw_impl_module_add_listener(
+                ptr.as_ptr(),
+                &mut listener.hook,
+                &listener.events,
+                (listener as *mut ModuleListener).cast(),
+            );
+        }
+
+        Ok(LoadedModule {
+            ptr,
+            listener,
+            _context: self.clone(),
+        })
+    }
+
     pub fn new<T: IsLoopRc>(loop_: &T) -> Result<Self, Error> {
         Self::new_internal(loop_, None)
     }
@@ -85,6 +352,8 @@ impl Context {
         Self::new_internal(loop_, Some(properties))
     }
 
+    /// Connect to the default remote, or to the one given by the `PIPEWIRE_REMOTE` environment variable
+    /// or the [`REMOTE_NAME`](crate::keys::REMOTE_NAME) of `properties`.
     pub fn connect(&self, properties: Option<Properties>) -> Result<Core, Error> {
         let properties = properties.map_or(ptr::null_mut(), |p| p.into_raw());
 
@@ -96,6 +365,19 @@ impl Context {
         }
     }
 
+    /// Connect to the remote `remote`, either the name of a socket in the runtime directory,
+    /// such as `pipewire-0`, or the path of a socket.
+    ///
+    /// This overrides the `PIPEWIRE_REMOTE` environment variable and the [`REMOTE_NAME`](crate::keys::REMOTE_NAME)
+    /// of `properties`.
+    pub fn connect_to(&self, remote: &str, properties: Option<Properties>) -> Result<Core, Error> {
+        let mut properties = properties.unwrap_or_default();
+        properties.insert(*crate::keys::REMOTE_NAME, remote);
+
+        self.connect(Some(properties))
+    }
+
+    /// Connect to the remote through `fd`, such as the file descriptor handed over by a portal.
     pub fn connect_fd(&self, fd: OwnedFd, properties: Option<Properties>) -> Result<Core, Error> {
         let properties = properties.map_or(ptr::null_mut(), |p| p.into_raw());
 
@@ -107,6 +389,21 @@ impl Context {
             Ok(Core::from_ptr(ptr, self.clone()))
         }
     }
+
+    /// Create a core talking to this context, without a connection to a daemon.
+    ///
+    /// This is used to run the objects of the process, such as its loaded modules, as an
+    /// in-process server.
+    pub fn connect_self(&self, properties: Option<Properties>) -> Result<Core, Error> {
+        let properties = properties.map_or(ptr::null_mut(), |p| p.into_raw());
+
+        unsafe {
+            let core = pw_sys::pw_context_connect_self(self.as_raw_ptr(), properties, 0);
+            let ptr = ptr::NonNull::new(core).ok_or(Error::CreationFailed)?;
+
+            Ok(Core::from_ptr(ptr, self.clone()))
+        }
+    }
 }
 
 impl std::convert::AsRef<ContextRef> for Context {
@@ -3,16 +3,26 @@
 
 use bitflags::bitflags;
 use libc::{c_char, c_void};
+use nix::errno::Errno;
 use std::{
+    cell::{Cell, RefCell},
     ffi::{CStr, CString},
-    rc::Rc,
+    rc::{Rc, Weak},
 };
 use std::{fmt, mem, ptr};
 use std::{ops::Deref, pin::Pin};
 
 use crate::{
+    client::CoreClient,
+    context::SpaHandle,
+    error::ResultExt,
+    keys,
+    node::Node,
+    properties::properties,
     proxy::{Proxy, ProxyT},
     registry::Registry,
+    scope::ListenerScope,
+    version::{ServerFeature, Version},
     Error,
 };
 use spa::{
@@ -43,6 +53,27 @@ impl CoreRef {
         }
     }
 
+    /// Run `f` with a [`ListenerScope`], in which listeners can borrow data of the stack.
+    ///
+    /// The listeners held by the scope are removed before this returns, and the data
+    /// borrowed by the scope can't be accessed anymore. See the [`scope`](crate::scope) module.
+    pub fn with_listeners<'env, R>(&self, f: impl FnOnce(&ListenerScope<'env>) -> R) -> R {
+        ListenerScope::run(f)
+    }
+
+    /// Get the proxy of the client of this connection.
+    ///
+    /// This allows updating the properties of the client after connecting, such as its
+    /// application name, and listening to the permissions granted to it, without having to find
+    /// the client in the registry.
+    pub fn client(&self) -> CoreClient<'_> {
+        let client = unsafe { pw_sys::pw_core_get_client(self.as_raw_ptr()) };
+        CoreClient::new(
+            self,
+            ptr::NonNull::new(client).expect("core client is NULL"),
+        )
+    }
+
     pub fn get_registry(&self) -> Result<Registry, Error> {
         let registry = unsafe {
             spa_interface_call_method!(
@@ -69,7 +100,23 @@ impl CoreRef {
             )
         };
 
-        let res = SpaResult::from_c(res).into_async_result()?;
+        let res = SpaResult::from_c(res)
+            .into_async_result()
+            .context("sync with the server")?;
+        Ok(res)
+    }
+
+    /// Reply to a ping event from the server.
+    ///
+    /// `id` and `seq` should be the values received in the [`ping`](ListenerLocalBuilder::ping) event.
+    pub fn pong(&self, id: u32, seq: i32) -> Result<AsyncSeq, Error> {
+        let res = unsafe {
+            spa_interface_call_method!(self.as_raw_ptr(), pw_sys::pw_core_methods, pong, id, seq)
+        };
+
+        let res = SpaResult::from_c(res)
+            .into_async_result()
+            .context("reply to a ping")?;
         Ok(res)
     }
 
@@ -138,7 +185,7 @@ impl CoreRef {
                 create_object,
                 factory_name.as_ptr(),
                 type_str.as_ptr(),
-                type_.client_version(),
+                P::client_version(),
                 properties.as_ref().as_raw_ptr(),
                 0
             )
@@ -162,9 +209,159 @@ impl CoreRef {
             )
         };
 
-        let res = SpaResult::from_c(res).into_async_result()?;
+        let res = SpaResult::from_c(res)
+            .into_async_result()
+            .context("destroy an object")?;
         Ok(res)
     }
+
+    /// Create a null audio sink, a node discarding the audio it receives.
+    ///
+    /// The node does not use any hardware, which makes it useful to link streams to in tests
+    /// or when processing audio offline. It is driven by the graph like other sinks.
+    ///
+    /// The node is destroyed when the returned [`NullSink`] is dropped.
+    ///
+    /// ```no_run
+    /// use pipewire::{context::Context, main_loop::MainLoop};
+    ///
+    /// let mainloop = MainLoop::new(None)?;
+    /// let context = Context::new(&mainloop)?;
+    /// let core = context.connect(None)?;
+    ///
+    /// let sink = core.create_null_sink("test-sink", 2, 48000)?;
+    /// // Once the server has created the node:
+    /// # let _ = mainloop.loop_().iterate(std::time::Duration::from_millis(100));
+    /// if let Some(id) = sink.node_id() {
+    ///     println!("Created node {id}");
+    /// }
+    /// # Ok::<(), pipewire::Error>(())
+    /// ```
+    pub fn create_null_sink(
+        &self,
+        name: &str,
+        channels: u32,
+        rate: u32,
+    ) -> Result<NullSink, Error> {
+        let node = self
+            .create_object::<Node>(
+                "adapter",
+                &properties! {
+                    *keys::FACTORY_NAME => "support.null-audio-sink",
+                    *keys::NODE_NAME => name,
+                    *keys::MEDIA_CLASS => "Audio/Sink",
+                    *keys::AUDIO_CHANNELS => channels.to_string(),
+                    *keys::AUDIO_RATE => rate.to_string(),
+                    *keys::OBJECT_LINGER => "false",
+                },
+            )
+            .context("create a null sink")?;
+
+        Ok(NullSink { node })
+    }
+
+    /// Export a local object implementing the interface `type_` into the graph.
+    ///
+    /// The returned proxy represents the object on the server. Exporting requires the module
+    /// handling `type_` to be loaded in the context, such as `libpipewire-module-client-node` for nodes,
+    /// which the default client configuration does.
+    ///
+    /// # Safety
+    /// `object` must point to a valid implementation of the interface `type_`,
+    /// which must stay valid for as long as the returned proxy exists.
+    ///
+    /// # Panics
+    /// If `type_` contains a null byte.
+    pub unsafe fn export(
+        &self,
+        type_: &str,
+        properties: Option<&spa::utils::dict::DictRef>,
+        object: *mut c_void,
+    ) -> Result<Proxy, Error> {
+        let type_ = CString::new(type_).expect("Null byte in type_ parameter");
+
+        let proxy = pw_sys::pw_core_export(
+            self.as_raw_ptr(),
+            type_.as_ptr(),
+            properties.map_or(ptr::null(), |props| props.as_raw_ptr().cast_const()),
+            object,
+            0,
+        );
+        let ptr = ptr::NonNull::new(proxy).ok_or(Error::CreationFailed)?;
+
+        Ok(Proxy::new(ptr))
+    }
+
+    /// Export the interface `type_` of an object loaded from a SPA plugin into the graph.
+    ///
+    /// The handle is kept alive alongside the proxy representing the object on the server.
+    ///
+    /// ```no_run
+    /// use pipewire::{context::Context, main_loop::MainLoop, spa::support::plugin::TYPE_INTERFACE_NODE};
+    ///
+    /// let mainloop = MainLoop::new(None)?;
+    /// let context = Context::new(&mainloop)?;
+    /// let core = context.connect(None)?;
+    ///
+    /// let handle = context.load_spa_handle("audiotestsrc", None)?;
+    /// let _exported = core.export_handle(handle, TYPE_INTERFACE_NODE, None)?;
+    ///
+    /// mainloop.run();
+    /// # Ok::<(), pipewire::Error>(())
+    /// ```
+    pub fn export_handle(
+        &self,
+        handle: SpaHandle,
+        type_: &str,
+        properties: Option<&spa::utils::dict::DictRef>,
+    ) -> Result<ExportedHandle, Error> {
+        let interface = handle.get_interface(type_)?;
+        let proxy = unsafe { self.export(type_, properties, interface.as_raw_ptr().cast())? };
+
+        Ok(ExportedHandle {
+            proxy,
+            _handle: handle,
+        })
+    }
+}
+
+/// An object loaded from a SPA plugin and exported with [`CoreRef::export_handle`].
+///
+/// The object is removed from the graph and unloaded when this is dropped.
+#[derive(Debug)]
+pub struct ExportedHandle {
+    // The proxy has to be destroyed before the object it exports.
+    proxy: Proxy,
+    _handle: SpaHandle,
+}
+
+impl ExportedHandle {
+    /// The proxy representing the exported object on the server.
+    pub fn proxy(&self) -> &Proxy {
+        &self.proxy
+    }
+}
+
+/// A null audio sink created with [`CoreRef::create_null_sink`].
+///
+/// The node is destroyed when this is dropped.
+#[derive(Debug)]
+pub struct NullSink {
+    node: Node,
+}
+
+impl NullSink {
+    /// The proxy of the node.
+    pub fn node(&self) -> &Node {
+        &self.node
+    }
+
+    /// The global id of the node, to link other nodes to it.
+    ///
+    /// Returns `None` until the server has created the node.
+    pub fn node_id(&self) -> Option<u32> {
+        self.node.upcast_ref().bound_id()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -182,6 +379,93 @@ impl Core {
             inner: Rc::new(inner),
         }
     }
+
+    /// The version of the PipeWire daemon the core is connected to.
+    ///
+    /// Returns `None` until the info of the core has been received from the daemon, which happens
+    /// shortly after connecting while the loop is running, or if the version cannot be parsed.
+    pub fn server_version(&self) -> Option<Version> {
+        self.inner.server_version.get()
+    }
+
+    /// The state of the connection to the daemon.
+    pub fn connection_state(&self) -> ConnectionState {
+        self.inner.connection_state.borrow().clone()
+    }
+
+    /// Whether the daemon has answered and the connection has not been lost since.
+    pub fn is_connected(&self) -> bool {
+        *self.inner.connection_state.borrow() == ConnectionState::Connected
+    }
+
+    /// Disconnect from the daemon.
+    ///
+    /// The proxies created with the core are destroyed along with it. Once the connection has been
+    /// lost, which is reported by the [`connection_state`](ListenerLocalBuilder::connection_state)
+    /// event, the core has to be disconnected and a new one created with the context to reconnect.
+    ///
+    /// Fails with `EBUSY` if the core is still shared with clones, which are dropped on failure.
+    ///
+    /// # Safety
+    /// All the proxies created with the core, such as its registry and the objects bound with it,
+    /// must have been dropped.
+    pub unsafe fn disconnect(self) -> Result<(), Error> {
+        let inner = Rc::try_unwrap(self.inner)
+            .map_err(|_| Error::from(spa::utils::result::Error::from(Errno::EBUSY)))
+            .context("disconnect the core")?;
+        let CoreInner {
+            ptr,
+            _state_listener,
+            _context,
+            ..
+        } = inner;
+
+        // The listener is removed before the core is destroyed.
+        drop(_state_listener);
+        pw_sys::pw_core_disconnect(ptr.as_ptr());
+        Ok(())
+    }
+
+    /// Whether `feature` can be used with the daemon the core is connected to.
+    ///
+    /// This requires the bindings of the feature to be compiled, and both the library the program
+    /// is running with and the daemon to be recent enough to support it. Returns `false` while the [`server_version`](Self::server_version)
+    /// is unknown.
+    pub fn has_server_feature(&self, feature: ServerFeature) -> bool {
+        self.server_version()
+            .map_or(false, |version| feature.is_supported_by(version))
+    }
+
+    /// Get a weak handle to the core, which does not keep it alive.
+    ///
+    /// Callbacks of the listeners of the core or of its proxies should hold a weak handle
+    /// rather than a clone of the core, which would never be dropped.
+    pub fn downgrade(&self) -> WeakCore {
+        WeakCore {
+            weak: Rc::downgrade(&self.inner),
+        }
+    }
+}
+
+/// A weak handle to a [`Core`], see [`Core::downgrade`].
+#[derive(Clone)]
+pub struct WeakCore {
+    weak: Weak<CoreInner>,
+}
+
+impl WeakCore {
+    /// Get the core, or `None` if all its clones have been dropped or it was disconnected.
+    pub fn upgrade(&self) -> Option<Core> {
+        self.weak.upgrade().map(|inner| Core { inner })
+    }
+}
+
+impl fmt::Debug for WeakCore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WeakCore")
+            .field("alive", &(self.weak.strong_count() > 0))
+            .finish()
+    }
 }
 
 impl Deref for Core {
@@ -198,26 +482,115 @@ impl AsRef<CoreRef> for Core {
     }
 }
 
-#[derive(Debug)]
 struct CoreInner {
     ptr: ptr::NonNull<pw_sys::pw_core>,
+    server_version: Rc<Cell<Option<Version>>>,
+    connection_state: Rc<RefCell<ConnectionState>>,
+    // Has to be removed before the context is dropped.
+    _state_listener: Listener,
     _context: crate::context::Context,
 }
 
 impl CoreInner {
     fn from_ptr(ptr: ptr::NonNull<pw_sys::pw_core>, _context: crate::context::Context) -> Self {
-        Self { ptr, _context }
+        let server_version = Rc::new(Cell::new(None));
+        let connection_state = Rc::new(RefCell::new(ConnectionState::Connecting));
+        let core = unsafe { ptr.cast::<CoreRef>().as_ref() };
+        let _state_listener = core
+            .add_listener_local()
+            .info({
+                let server_version = server_version.clone();
+                move |info| server_version.set(info.version().parse().ok())
+            })
+            .connection_state({
+                let connection_state = connection_state.clone();
+                move |state| *connection_state.borrow_mut() = state.clone()
+            })
+            .register();
+
+        Self {
+            ptr,
+            server_version,
+            connection_state,
+            _state_listener,
+            _context,
+        }
+    }
+}
+
+impl fmt::Debug for CoreInner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CoreInner")
+            .field("ptr", &self.ptr)
+            .field("server_version", &self.server_version.get())
+            .field("connection_state", &self.connection_state.borrow())
+            .field("_context", &self._context)
+            .finish()
     }
 }
 
 #[derive(Default)]
 struct ListenerLocalCallbacks {
     #[allow(clippy::type_complexity)]
-    info: Option<Box<dyn Fn(&Info)>>,
+    info: Option<Box<dyn Fn(&CoreInfoRef)>>,
     done: Option<Box<dyn Fn(u32, AsyncSeq)>>,
+    ping: Option<Box<dyn Fn(u32, i32)>>,
     #[allow(clippy::type_complexity)]
-    error: Option<Box<dyn Fn(u32, i32, i32, &str)>>, // TODO: return a proper Error enum?
-                                                     // TODO: ping, remove_id, bound_id, add_mem, remove_mem
+    error: Option<Box<dyn Fn(u32, i32, i32, &str)>>,
+    remove_id: Option<Box<dyn Fn(u32)>>,
+    bound_id: Option<Box<dyn Fn(u32, u32)>>,
+    connection_state: Option<Box<dyn Fn(&ConnectionState)>>,
+    // The last state passed to `connection_state`, to only report its changes.
+    state: RefCell<ConnectionState>,
+    // TODO: add_mem, remove_mem
+}
+
+impl ListenerLocalCallbacks {
+    fn set_state(&self, state: ConnectionState) {
+        let Some(connection_state) = &self.connection_state else {
+            return;
+        };
+
+        let mut current = self.state.borrow_mut();
+        // A lost connection is never recovered.
+        if *current == state || *current == ConnectionState::Disconnected {
+            return;
+        }
+        *current = state.clone();
+        drop(current);
+
+        connection_state(&state);
+    }
+}
+
+/// The state of the connection of a core to the daemon.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum ConnectionState {
+    /// The daemon has not answered yet.
+    #[default]
+    Connecting,
+    /// The daemon has sent the info of the core.
+    Connected,
+    /// The connection to the daemon was lost, such as when it exited.
+    Disconnected,
+    /// The daemon reported an error on the core.
+    Error { errno: Errno, message: String },
+}
+
+impl ConnectionState {
+    /// The state reported by an [`error`](ListenerLocalBuilder::error) event on the core.
+    fn from_core_error(res: i32, message: &str) -> Self {
+        let errno = Errno::from_i32(-res);
+        if errno == Errno::EPIPE {
+            Self::Disconnected
+        } else {
+            Self::Error {
+                errno,
+                message: message.to_owned(),
+            }
+        }
+    }
 }
 
 pub struct ListenerLocalBuilder<'a> {
@@ -238,6 +611,14 @@ impl Listener {
     pub fn unregister(self) {
         // Consuming the listener will call drop()
     }
+
+    /// Unregister the listener in the next iteration of `loop_`.
+    ///
+    /// Unlike [`unregister`](Self::unregister), this can be called from a callback of the listener
+    /// itself, see [`LoopRef::drop_later`](crate::loop_::LoopRef::drop_later).
+    pub fn unregister_later(self, loop_: &crate::loop_::LoopRef) {
+        loop_.drop_later(self);
+    }
 }
 
 impl Drop for Listener {
@@ -250,7 +631,7 @@ impl<'a> ListenerLocalBuilder<'a> {
     #[must_use]
     pub fn info<F>(mut self, info: F) -> Self
     where
-        F: Fn(&Info) + 'static,
+        F: Fn(&CoreInfoRef) + 'static,
     {
         self.cbs.info = Some(Box::new(info));
         self
@@ -265,6 +646,24 @@ impl<'a> ListenerLocalBuilder<'a> {
         self
     }
 
+    /// Emitted when the server sends a ping.
+    ///
+    /// The callback receives the object id and sequence number of the ping,
+    /// which should be answered with [`CoreRef::pong`].
+    #[must_use]
+    pub fn ping<F>(mut self, ping: F) -> Self
+    where
+        F: Fn(u32, i32) + 'static,
+    {
+        self.cbs.ping = Some(Box::new(ping));
+        self
+    }
+
+    /// Emitted when the server reports an error on an object.
+    ///
+    /// The callback receives the id of the object the error is about, the sequence number of the
+    /// method that caused it (if any), a negative errno-style result code and an error message.
+    /// Use [`Error::from_core_error`] to turn them into an [`Error`].
     #[must_use]
     pub fn error<F>(mut self, error: F) -> Self
     where
@@ -274,20 +673,72 @@ impl<'a> ListenerLocalBuilder<'a> {
         self
     }
 
+    /// Emitted when the server removed an id, after which the id can be reused.
+    #[must_use]
+    pub fn remove_id<F>(mut self, remove_id: F) -> Self
+    where
+        F: Fn(u32) + 'static,
+    {
+        self.cbs.remove_id = Some(Box::new(remove_id));
+        self
+    }
+
+    /// Emitted when a local proxy id was bound to a global id.
+    ///
+    /// The callback receives the proxy id and the global id.
+    #[must_use]
+    pub fn bound_id<F>(mut self, bound_id: F) -> Self
+    where
+        F: Fn(u32, u32) + 'static,
+    {
+        self.cbs.bound_id = Some(Box::new(bound_id));
+        self
+    }
+
+    /// Emitted when the state of the connection to the daemon changed.
+    ///
+    /// The state starts as [`ConnectionState::Connecting`], and becomes [`ConnectionState::Disconnected`]
+    /// when the daemon is gone, after which the core has to be [disconnected](Core::disconnect) to
+    /// reconnect with a new one.
+    #[must_use]
+    pub fn connection_state<F>(mut self, connection_state: F) -> Self
+    where
+        F: Fn(&ConnectionState) + 'static,
+    {
+        self.cbs.connection_state = Some(Box::new(connection_state));
+        self
+    }
+
     #[must_use]
     pub fn register(self) -> Listener {
         unsafe extern "C" fn core_events_info(
             data: *mut c_void,
             info: *const pw_sys::pw_core_info,
         ) {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-            let info = Info::new(ptr::NonNull::new(info as *mut _).expect("info is NULL"));
-            callbacks.info.as_ref().unwrap()(&info);
+            crate::unwind::guard("core_events_info", || {
+                let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
+                let info =
+                    ptr::NonNull::new(info as *mut pw_sys::pw_core_info).expect("info is NULL");
+                let info = info.cast::<CoreInfoRef>().as_ref();
+                if let Some(cb) = &callbacks.info {
+                    cb(info);
+                }
+                callbacks.set_state(ConnectionState::Connected);
+            })
         }
 
         unsafe extern "C" fn core_events_done(data: *mut c_void, id: u32, seq: i32) {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-            callbacks.done.as_ref().unwrap()(id, AsyncSeq::from_raw(seq));
+            crate::unwind::guard("core_events_done", || {
+                let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
+                callbacks.done.as_ref().unwrap()(id, AsyncSeq::from_raw(seq));
+            })
+        }
+
+        unsafe extern "C" fn core_events_ping(data: *mut c_void, id: u32, seq: i32) {
+            crate::unwind::guard("core_events_ping", || {
+                let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
+                callbacks.ping.as_ref().unwrap()(id, seq);
+            })
         }
 
         unsafe extern "C" fn core_events_error(
@@ -297,24 +748,54 @@ impl<'a> ListenerLocalBuilder<'a> {
             res: i32,
             message: *const c_char,
         ) {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-            let message = CStr::from_ptr(message).to_str().unwrap();
-            callbacks.error.as_ref().unwrap()(id, seq, res, message);
+            crate::unwind::guard("core_events_error", || {
+                let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
+                let message = CStr::from_ptr(message).to_str().unwrap();
+                if let Some(cb) = &callbacks.error {
+                    cb(id, seq, res, message);
+                }
+                if id == PW_ID_CORE {
+                    callbacks.set_state(ConnectionState::from_core_error(res, message));
+                }
+            })
+        }
+
+        unsafe extern "C" fn core_events_remove_id(data: *mut c_void, id: u32) {
+            crate::unwind::guard("core_events_remove_id", || {
+                let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
+                callbacks.remove_id.as_ref().unwrap()(id);
+            })
+        }
+
+        unsafe extern "C" fn core_events_bound_id(data: *mut c_void, id: u32, global_id: u32) {
+            crate::unwind::guard("core_events_bound_id", || {
+                let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
+                callbacks.bound_id.as_ref().unwrap()(id, global_id);
+            })
         }
 
         let e = unsafe {
             let mut e: Pin<Box<pw_sys::pw_core_events>> = Box::pin(mem::zeroed());
             e.version = pw_sys::PW_VERSION_CORE_EVENTS;
 
-            if self.cbs.info.is_some() {
+            if self.cbs.info.is_some() || self.cbs.connection_state.is_some() {
                 e.info = Some(core_events_info);
             }
             if self.cbs.done.is_some() {
                 e.done = Some(core_events_done);
             }
-            if self.cbs.error.is_some() {
+            if self.cbs.ping.is_some() {
+                e.ping = Some(core_events_ping);
+            }
+            if self.cbs.error.is_some() || self.cbs.connection_state.is_some() {
                 e.error = Some(core_events_error);
             }
+            if self.cbs.remove_id.is_some() {
+                e.remove_id = Some(core_events_remove_id);
+            }
+            if self.cbs.bound_id.is_some() {
+                e.bound_id = Some(core_events_bound_id);
+            }
 
             e
         };
@@ -348,62 +829,59 @@ impl<'a> ListenerLocalBuilder<'a> {
     }
 }
 
-pub struct Info {
-    ptr: ptr::NonNull<pw_sys::pw_core_info>,
-}
+/// The former name of [`CoreInfoRef`], which listeners now receive by reference.
+#[deprecated(note = "Use `CoreInfoRef` instead")]
+pub type Info = CoreInfoRef;
+
+#[repr(transparent)]
+pub struct CoreInfoRef(pw_sys::pw_core_info);
+
+impl CoreInfoRef {
+    pub fn as_raw(&self) -> &pw_sys::pw_core_info {
+        &self.0
+    }
 
-impl Info {
-    fn new(info: ptr::NonNull<pw_sys::pw_core_info>) -> Self {
-        Self { ptr: info }
+    pub fn as_raw_ptr(&self) -> *mut pw_sys::pw_core_info {
+        std::ptr::addr_of!(self.0).cast_mut()
     }
 
     pub fn id(&self) -> u32 {
-        unsafe { self.ptr.as_ref().id }
+        self.0.id
     }
 
     pub fn cookie(&self) -> u32 {
-        unsafe { self.ptr.as_ref().cookie }
+        self.0.cookie
     }
 
     pub fn user_name(&self) -> &str {
-        unsafe {
-            CStr::from_ptr(self.ptr.as_ref().user_name)
-                .to_str()
-                .unwrap()
-        }
+        unsafe { CStr::from_ptr(self.0.user_name).to_str().unwrap() }
     }
 
     pub fn host_name(&self) -> &str {
-        unsafe {
-            CStr::from_ptr(self.ptr.as_ref().host_name)
-                .to_str()
-                .unwrap()
-        }
+        unsafe { CStr::from_ptr(self.0.host_name).to_str().unwrap() }
     }
 
     pub fn version(&self) -> &str {
-        unsafe { CStr::from_ptr(self.ptr.as_ref().version).to_str().unwrap() }
+        unsafe { CStr::from_ptr(self.0.version).to_str().unwrap() }
     }
 
     pub fn name(&self) -> &str {
-        unsafe { CStr::from_ptr(self.ptr.as_ref().name).to_str().unwrap() }
+        unsafe { CStr::from_ptr(self.0.name).to_str().unwrap() }
     }
 
     pub fn change_mask(&self) -> ChangeMask {
-        let mask = unsafe { self.ptr.as_ref().change_mask };
-        ChangeMask::from_bits_retain(mask)
+        ChangeMask::from_bits_retain(self.0.change_mask)
     }
 
     pub fn props(&self) -> Option<&spa::utils::dict::DictRef> {
-        let props_ptr: *mut spa::utils::dict::DictRef = unsafe { self.ptr.as_ref().props.cast() };
-
+        let props_ptr: *mut spa::utils::dict::DictRef = self.0.props.cast();
         ptr::NonNull::new(props_ptr).map(|ptr| unsafe { ptr.as_ref() })
     }
 }
 
-impl fmt::Debug for Info {
+impl fmt::Debug for CoreInfoRef {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("CoreInfo")
+        f.debug_struct("CoreInfoRef")
             .field("id", &self.id())
             .field("cookie", &self.cookie())
             .field("user-name", &self.user_name())
@@ -422,3 +900,40 @@ bitflags! {
         const PROPS = pw_sys::PW_CORE_CHANGE_MASK_PROPS as u64;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connection_state() {
+        let states = Rc::new(RefCell::new(Vec::new()));
+        let callbacks = ListenerLocalCallbacks {
+            connection_state: Some(Box::new({
+                let states = states.clone();
+                move |state| states.borrow_mut().push(state.clone())
+            })),
+            ..Default::default()
+        };
+
+        callbacks.set_state(ConnectionState::Connected);
+        callbacks.set_state(ConnectionState::Connected);
+        callbacks.set_state(ConnectionState::from_core_error(
+            -libc::EPIPE,
+            "connection error",
+        ));
+        callbacks.set_state(ConnectionState::Connected);
+
+        assert_eq!(
+            *states.borrow(),
+            [ConnectionState::Connected, ConnectionState::Disconnected]
+        );
+        assert_eq!(
+            ConnectionState::from_core_error(-libc::EPROTO, "protocol error"),
+            ConnectionState::Error {
+                errno: Errno::EPROTO,
+                message: "protocol error".to_owned()
+            }
+        );
+    }
+}
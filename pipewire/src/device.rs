@@ -4,14 +4,43 @@
 use bitflags::bitflags;
 use libc::c_void;
 use std::ops::Deref;
+use std::ptr;
 use std::{fmt, mem};
-use std::{pin::Pin, ptr};
 
 use crate::{
+    error::ResultExt,
+    listeners,
     proxy::{Listener, Proxy, ProxyT},
     types::ObjectType,
+    volume::VolumeProps,
+    Error,
 };
-use spa::{pod::Pod, spa_interface_call_method};
+use spa::{
+    pod::{io::PodBuf, Pod, Value},
+    utils::{
+        interface::VersionedInterface,
+        result::{AsyncSeq, SpaResult},
+    },
+};
+
+spa::interface_methods! {
+    trait DeviceMethods for pw_sys::pw_device_methods {
+        fn subscribe_params(&self, ids: &[spa::param::ParamType]) -> SpaResult {
+            since 0;
+            unsafe { call(ids.as_ptr() as *mut _, ids.len().try_into().unwrap()) }
+        }
+
+        fn enum_params(&self, seq: i32, id: u32, start: u32, num: u32) -> SpaResult {
+            since 0;
+            unsafe { call(seq, id, start, num, ptr::null()) }
+        }
+
+        fn set_param(&self, id: u32, flags: u32, param: &Pod) -> SpaResult {
+            since 0;
+            unsafe { call(id, flags, param.as_ptr()) }
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Device {
@@ -19,6 +48,11 @@ pub struct Device {
 }
 
 impl Device {
+    fn interface(&self) -> VersionedInterface<pw_sys::pw_device_methods> {
+        // Safety: the proxy is a device, which stays valid as long as `self`.
+        unsafe { VersionedInterface::new(self.proxy.as_ptr().cast()) }
+    }
+
     // TODO: add non-local version when we'll bind pw_thread_loop_start()
     #[must_use]
     pub fn add_listener_local(&self) -> DeviceListenerLocalBuilder {
@@ -31,17 +65,13 @@ impl Device {
     /// Subscribe to parameter changes
     ///
     /// Automatically emit `param` events for the given ids when they are changed
-    // FIXME: Return result?
-    pub fn subscribe_params(&self, ids: &[spa::param::ParamType]) {
-        unsafe {
-            spa_interface_call_method!(
-                self.proxy.as_ptr(),
-                pw_sys::pw_device_methods,
-                subscribe_params,
-                ids.as_ptr() as *mut _,
-                ids.len().try_into().unwrap()
-            );
-        }
+    pub fn subscribe_params(&self, ids: &[spa::param::ParamType]) -> Result<AsyncSeq, Error> {
+        let res = self
+            .interface()
+            .subscribe_params(ids)
+            .into_async_result()
+            .context("subscribe to the device params")?;
+        Ok(res)
     }
 
     /// Enumerate device parameters
@@ -55,35 +85,52 @@ impl Device {
     /// `start`: the start index or 0 for the first param \
     /// `num`: the maximum number of params to retrieve ([`u32::MAX`] may be used to retrieve all params)
     // FIXME: Add filter parameter
-    // FIXME: Return result?
-    pub fn enum_params(&self, seq: i32, id: Option<spa::param::ParamType>, start: u32, num: u32) {
+    pub fn enum_params(
+        &self,
+        seq: i32,
+        id: Option<spa::param::ParamType>,
+        start: u32,
+        num: u32,
+    ) -> Result<AsyncSeq, Error> {
         let id = id.map(|id| id.as_raw()).unwrap_or(crate::constants::ID_ANY);
 
-        unsafe {
-            spa_interface_call_method!(
-                self.proxy.as_ptr(),
-                pw_sys::pw_device_methods,
-                enum_params,
-                seq,
-                id,
-                start,
-                num,
-                std::ptr::null()
-            );
-        }
+        let res = self
+            .interface()
+            .enum_params(seq, id, start, num)
+            .into_async_result()
+            .context("enumerate the device params")?;
+        Ok(res)
     }
 
-    pub fn set_param(&self, id: spa::param::ParamType, flags: u32, param: &Pod) {
-        unsafe {
-            spa_interface_call_method!(
-                self.proxy.as_ptr(),
-                pw_sys::pw_device_methods,
-                set_param,
-                id.as_raw(),
-                flags,
-                param.as_raw_ptr()
-            );
-        }
+    pub fn set_param(
+        &self,
+        id: spa::param::ParamType,
+        flags: u32,
+        param: &Pod,
+    ) -> Result<AsyncSeq, Error> {
+        let res = self
+            .interface()
+            .set_param(id.as_raw(), flags, param)
+            .into_async_result()
+            .context("set a device param")?;
+        Ok(res)
+    }
+
+    /// Apply volume properties to a route of the device.
+    ///
+    /// This is the preferred way to change the volume of the nodes of a sound card, as the session
+    /// manager then remembers the volume of the route. `route_device` is the value of the
+    /// `card.profile.device` property of the node, and `route_index` is the index of the route
+    /// currently active for it, as reported by the `Route` params of the device.
+    pub fn set_route_volume(
+        &self,
+        route_index: i32,
+        route_device: i32,
+        props: &VolumeProps,
+    ) -> Result<AsyncSeq, Error> {
+        let route = props.to_route_object(route_index, route_device, true);
+        let pod = PodBuf::serialize(&Value::Object(route)).expect("Failed to serialize pod");
+        self.set_param(spa::param::ParamType::Route, 0, &pod)
     }
 }
 
@@ -229,22 +276,11 @@ impl fmt::Debug for DeviceInfo {
 }
 
 pub struct DeviceListener {
-    // Need to stay allocated while the listener is registered
-    #[allow(dead_code)]
-    events: Pin<Box<pw_sys::pw_device_events>>,
-    listener: Pin<Box<spa_sys::spa_hook>>,
-    #[allow(dead_code)]
-    data: Box<ListenerLocalCallbacks>,
+    _listener: listeners::Listener<pw_sys::pw_device_events, ListenerLocalCallbacks>,
 }
 
 impl Listener for DeviceListener {}
 
-impl Drop for DeviceListener {
-    fn drop(&mut self) {
-        spa::utils::hook::remove(*self.listener);
-    }
-}
-
 impl<'a> DeviceListenerLocalBuilder<'a> {
     #[must_use]
     pub fn info<F>(mut self, info: F) -> Self
@@ -270,11 +306,13 @@ impl<'a> DeviceListenerLocalBuilder<'a> {
             data: *mut c_void,
             info: *const pw_sys::pw_device_info,
         ) {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-            let info =
-                ptr::NonNull::new(info as *mut pw_sys::pw_device_info).expect("info is NULL");
-            let info = info.cast::<DeviceInfoRef>().as_ref();
-            callbacks.info.as_ref().unwrap()(info);
+            crate::unwind::guard("device_events_info", || {
+                let callbacks = listeners::callback_data::<ListenerLocalCallbacks>(data);
+                let info =
+                    ptr::NonNull::new(info as *mut pw_sys::pw_device_info).expect("info is NULL");
+                let info = info.cast::<DeviceInfoRef>().as_ref();
+                callbacks.info.as_ref().unwrap()(info);
+            })
         }
 
         unsafe extern "C" fn device_events_param(
@@ -285,20 +323,22 @@ impl<'a> DeviceListenerLocalBuilder<'a> {
             next: u32,
             param: *const spa_sys::spa_pod,
         ) {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-
-            let id = spa::param::ParamType::from_raw(id);
-            let param = if !param.is_null() {
-                unsafe { Some(Pod::from_raw(param)) }
-            } else {
-                None
-            };
-
-            callbacks.param.as_ref().unwrap()(seq, id, index, next, param);
+            crate::unwind::guard("device_events_param", || {
+                let callbacks = listeners::callback_data::<ListenerLocalCallbacks>(data);
+
+                let id = spa::param::ParamType::from_raw(id);
+                let param = if !param.is_null() {
+                    unsafe { Some(Pod::from_raw(param)) }
+                } else {
+                    None
+                };
+
+                callbacks.param.as_ref().unwrap()(seq, id, index, next, param);
+            })
         }
 
         let e = unsafe {
-            let mut e: Pin<Box<pw_sys::pw_device_events>> = Box::pin(mem::zeroed());
+            let mut e: pw_sys::pw_device_events = mem::zeroed();
             e.version = pw_sys::PW_VERSION_DEVICE_EVENTS;
 
             if self.cbs.info.is_some() {
@@ -311,29 +351,19 @@ impl<'a> DeviceListenerLocalBuilder<'a> {
             e
         };
 
-        let (listener, data) = unsafe {
-            let device = &self.device.proxy.as_ptr();
-
-            let data = Box::into_raw(Box::new(self.cbs));
-            let mut listener: Pin<Box<spa_sys::spa_hook>> = Box::pin(mem::zeroed());
-            let listener_ptr: *mut spa_sys::spa_hook = listener.as_mut().get_unchecked_mut();
-
-            spa_interface_call_method!(
-                device,
-                pw_sys::pw_device_methods,
-                add_listener,
-                listener_ptr.cast(),
-                e.as_ref().get_ref(),
-                data as *mut _
-            );
-
-            (listener, Box::from_raw(data))
+        let interface = self.device.interface();
+        let listener = unsafe {
+            listeners::Listener::new(e, self.cbs, |hook, events, data| {
+                interface.call(
+                    0,
+                    |methods| methods.add_listener,
+                    |f, object| f(object, hook.cast(), events, data),
+                );
+            })
         };
 
         DeviceListener {
-            events: e,
-            listener,
-            data,
+            _listener: listener,
         }
     }
 }
@@ -0,0 +1,837 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Discovery of the audio and video devices of the graph.
+//!
+//! The functions of this module bind the relevant objects of the graph, enumerate their params
+//! and return owned descriptions of them. They iterate the loop of the core until the server
+//! has answered, so they have to be called from the thread running the loop, outside of its
+//! callbacks.
+//!
+//! ```no_run
+//! use pipewire::{context::Context, discovery, main_loop::MainLoop};
+//!
+//! pipewire::init();
+//! let mainloop = MainLoop::new(None)?;
+//! let context = Context::new(&mainloop)?;
+//! let core = context.connect(None)?;
+//!
+//! for device in discovery::audio_devices(&core)? {
+//!     println!("{}: {} ({:?})", device.node_id, device.description, device.volume.volume);
+//! }
+//! for camera in discovery::video_sources(&core)? {
+//!     println!("{}: {} ({} modes)", camera.node_id, camera.description, camera.modes.len());
+//! }
+//! # Ok::<(), pipewire::Error>(())
+//! ```
+
+use std::{cell::RefCell, rc::Rc};
+
+use spa::{
+    param::{
+        format::{FormatProperties, MediaSubtype, MediaType},
+        video::VideoFormat,
+        ParamType,
+    },
+    pod::{deserialize::PodDeserializer, ChoiceValue, Object, Pod, Value},
+    utils::{dict::DictRef, Choice, ChoiceEnum, Direction, Fraction, Id, Rectangle},
+};
+
+use crate::{
+    core::CoreRef,
+    device::{Device, DeviceListener},
+    error::{Error, ResultExt},
+    keys,
+    loop_::LoopRef,
+    node::{Node, NodeListener},
+    pending::{core_loop, roundtrip},
+    properties::Properties,
+    registry::{GlobalObject, Registry},
+    types::ObjectType,
+    volume::VolumeProps,
+};
+
+/// A profile of a device, as listed by its `EnumProfile` param.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceProfile {
+    /// The index of the profile, used to select it.
+    pub index: i32,
+    /// The name of the profile, such as `output:analog-stereo`.
+    pub name: String,
+    /// The human readable description of the profile.
+    pub description: String,
+}
+
+impl DeviceProfile {
+    /// Read a `Profile` object, returning `None` if it has no index.
+    pub fn from_object(object: &Object) -> Option<Self> {
+        Some(Self {
+            index: int_property(object, spa_sys::SPA_PARAM_PROFILE_index)?,
+            name: string_property(object, spa_sys::SPA_PARAM_PROFILE_name).unwrap_or_default(),
+            description: string_property(object, spa_sys::SPA_PARAM_PROFILE_description)
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// An active route of a device, as listed by its `Route` param.
+///
+/// A route is the physical destination or origin of the audio, such as speakers or headphones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceRoute {
+    /// The index of the route.
+    pub index: i32,
+    /// The route device, matching the `card.profile.device` property of the nodes using the route.
+    pub device: i32,
+    /// The direction of the route, [`Direction::Output`] for playback.
+    pub direction: Option<Direction>,
+    /// The name of the route, such as `analog-output-headphones`.
+    pub name: String,
+    /// The human readable description of the route.
+    pub description: String,
+    /// The volume of the route.
+    pub volume: VolumeProps,
+}
+
+impl DeviceRoute {
+    /// Read a `Route` object, returning `None` if it has no index or device.
+    pub fn from_object(object: &Object) -> Option<Self> {
+        Some(Self {
+            index: int_property(object, spa_sys::SPA_PARAM_ROUTE_index)?,
+            device: int_property(object, spa_sys::SPA_PARAM_ROUTE_device)?,
+            direction: match property(object, spa_sys::SPA_PARAM_ROUTE_direction) {
+                Some(Value::Id(Id(direction))) => Direction::try_from(*direction).ok(),
+                _ => None,
+            },
+            name: string_property(object, spa_sys::SPA_PARAM_ROUTE_name).unwrap_or_default(),
+            description: string_property(object, spa_sys::SPA_PARAM_ROUTE_description)
+                .unwrap_or_default(),
+            volume: match property(object, spa_sys::SPA_PARAM_ROUTE_props) {
+                Some(Value::Object(props)) => VolumeProps::from_object(props),
+                _ => VolumeProps::default(),
+            },
+        })
+    }
+}
+
+/// A video mode supported by a video source, decoded from one of its `EnumFormat` params.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoMode {
+    /// The media subtype of the mode, such as [`MediaSubtype::Raw`] or [`MediaSubtype::Mjpg`].
+    pub subtype: MediaSubtype,
+    /// The pixel format of the mode, only set for raw video.
+    pub format: Option<VideoFormat>,
+    /// The frame sizes of the mode.
+    ///
+    /// Cameras usually list their sizes, see [`ChoiceEnum::iter_alternatives`], but can also
+    /// accept a range of sizes, see [`ChoiceEnum::range`].
+    pub sizes: ChoiceEnum<Rectangle>,
+    /// The framerates of the mode, listed or as a range like the sizes.
+    pub framerates: ChoiceEnum<Fraction>,
+}
+
+impl VideoMode {
+    /// Decode an `EnumFormat` object into video modes.
+    ///
+    /// A raw video format offering a choice of pixel formats is split into one mode per pixel
+    /// format. Objects which are not video formats, or lack a size or framerate, give no modes.
+    pub fn from_object(object: &Object) -> Vec<Self> {
+        let media_type = property(object, FormatProperties::MediaType.as_raw());
+        if !matches!(media_type, Some(Value::Id(Id(id))) if *id == MediaType::Video.as_raw()) {
+            return Vec::new();
+        }
+        let Some(Value::Id(Id(subtype))) =
+            property(object, FormatProperties::MediaSubtype.as_raw())
+        else {
+            return Vec::new();
+        };
+        let subtype = MediaSubtype::from_raw(*subtype);
+        let (Some(sizes), Some(framerates)) = (
+            property(object, FormatProperties::VideoSize.as_raw()).and_then(rectangle_choice),
+            property(object, FormatProperties::VideoFramerate.as_raw()).and_then(fraction_choice),
+        ) else {
+            return Vec::new();
+        };
+
+        let formats: Vec<Option<VideoFormat>> =
+            match property(object, FormatProperties::VideoFormat.as_raw()).and_then(id_choice) {
+                Some(ChoiceEnum::None(format)) => vec![Some(VideoFormat::from_raw(format.0))],
+                Some(ChoiceEnum::Enum {
+                    default,
+                    alternatives,
+                }) => {
+                    let mut formats = vec![default];
+                    for format in alternatives {
+                        if !formats.contains(&format) {
+                            formats.push(format);
+                        }
+                    }
+                    formats
+                        .into_iter()
+                        .map(|format| Some(VideoFormat::from_raw(format.0)))
+                        .collect()
+                }
+                _ => vec![None],
+            };
+
+        formats
+            .into_iter()
+            .map(|format| Self {
+                subtype,
+                format,
+                sizes: sizes.clone(),
+                framerates: framerates.clone(),
+            })
+            .collect()
+    }
+}
+
+/// A video source of the graph, such as a camera, as returned by [`video_sources`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoSourceDesc {
+    /// The id of the node.
+    pub node_id: u32,
+    /// The name of the node, to target it when connecting a stream.
+    pub name: String,
+    /// The human readable description of the node.
+    pub description: String,
+    /// The id of the device of the node.
+    pub device_id: Option<u32>,
+    /// The video modes supported by the node.
+    pub modes: Vec<VideoMode>,
+}
+
+/// An audio sink or source of the graph, as returned by [`audio_devices`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioDeviceDesc {
+    /// The id of the node.
+    pub node_id: u32,
+    /// The name of the node, as used to pick the default nodes.
+    pub name: String,
+    /// The human readable description of the node.
+    pub description: String,
+    /// The media class of the node, such as `Audio/Sink`.
+    pub media_class: String,
+    /// The direction of the ports of the node: [`Direction::Input`] for sinks,
+    /// and [`Direction::Output`] for sources.
+    pub direction: Direction,
+    /// The id of the device of the node, `None` for virtual nodes such as null sinks.
+    pub device_id: Option<u32>,
+    /// The human readable description of the device of the node.
+    pub device_description: Option<String>,
+    /// The route device of the node, from its `card.profile.device` property.
+    pub profile_device: Option<i32>,
+    /// The volume of the node, from its `Props` param.
+    pub volume: VolumeProps,
+    /// The profiles of the device of the node.
+    pub profiles: Vec<DeviceProfile>,
+    /// The active profile of the device of the node.
+    pub active_profile: Option<DeviceProfile>,
+    /// The active route of the device for the node.
+    ///
+    /// The volume of a node of a sound card is better changed through its route, see
+    /// [`Device::set_route_volume`].
+    pub active_route: Option<DeviceRoute>,
+}
+
+impl AudioDeviceDesc {
+    /// Whether the node is a sink, playing audio.
+    pub fn is_sink(&self) -> bool {
+        self.direction == Direction::Input
+    }
+
+    /// Whether the node is a source, capturing audio.
+    pub fn is_source(&self) -> bool {
+        self.direction == Direction::Output
+    }
+}
+
+/// List the audio sinks and sources of the graph, with the profiles and routes of their device.
+///
+/// See the [module documentation](self) for the thread this can be called from.
+pub fn audio_devices(core: &CoreRef) -> Result<Vec<AudioDeviceDesc>, Error> {
+    const OP: &str = "discover the audio devices";
+
+    let loop_ = core_loop(core);
+    let registry = core.get_registry().context(OP)?;
+    let globals = collect_globals(core, loop_, &registry, |global| {
+        audio_node_direction(global).is_some() || is_audio_device(global)
+    })
+    .context(OP)?;
+
+    let mut nodes = Vec::new();
+    let mut devices = Vec::new();
+    for global in &globals {
+        if global.type_ == ObjectType::Node {
+            nodes.push((global, bind_node(&registry, global, &[ParamType::Props])?));
+        } else {
+            let ids = [ParamType::EnumProfile, ParamType::Profile, ParamType::Route];
+            devices.push((global, bind_device(&registry, global, &ids)?));
+        }
+    }
+    roundtrip(core, loop_).context(OP)?;
+
+    let mut descs: Vec<_> = nodes
+        .into_iter()
+        .filter_map(|(global, node)| {
+            let props = global.props()?;
+            let direction = audio_node_direction(global)?;
+            let device_id = node_device_id(props);
+            let profile_device = node_profile_device(props);
+            let device = devices
+                .iter()
+                .find(|(device, _)| Some(device.id) == device_id);
+
+            let (name, description) = node_names(props);
+            let mut desc = AudioDeviceDesc {
+                node_id: global.id,
+                name,
+                description,
+                media_class: props.get(*keys::MEDIA_CLASS).unwrap_or_default().to_owned(),
+                direction,
+                device_id,
+                device_description: None,
+                profile_device,
+                volume: node
+                    .params
+                    .objects(ParamType::Props)
+                    .first()
+                    .map(VolumeProps::from_object)
+                    .unwrap_or_default(),
+                profiles: Vec::new(),
+                active_profile: None,
+                active_route: None,
+            };
+
+            if let Some((device, bound)) = device {
+                desc.device_description = device
+                    .props()
+                    .and_then(|props| props.get(*keys::DEVICE_DESCRIPTION))
+                    .map(str::to_owned);
+                desc.profiles = bound
+                    .params
+                    .objects(ParamType::EnumProfile)
+                    .iter()
+                    .filter_map(DeviceProfile::from_object)
+                    .collect();
+                desc.active_profile = bound
+                    .params
+                    .objects(ParamType::Profile)
+                    .first()
+                    .and_then(DeviceProfile::from_object);
+                desc.active_route = bound
+                    .params
+                    .objects(ParamType::Route)
+                    .iter()
+                    .filter_map(DeviceRoute::from_object)
+                    .find(|route| Some(route.device) == profile_device);
+            }
+
+            Some(desc)
+        })
+        .collect();
+    descs.sort_by_key(|desc| desc.node_id);
+
+    Ok(descs)
+}
+
+/// Apply `props` to the active route of the device of the node `node_id`.
+///
+/// Returns `false` without changing anything if the node has no device route, such as a virtual
+/// node or a node whose device has no active route for it.
+/// See the [module documentation](self) for the thread this can be called from.
+pub(crate) fn set_node_route_volume(
+    core: &CoreRef,
+    node_id: u32,
+    props: &VolumeProps,
+) -> Result<bool, Error> {
+    const OP: &str = "set the volume of the route of a node";
+
+    let loop_ = core_loop(core);
+    let registry = core.get_registry().context(OP)?;
+    let globals = collect_globals(core, loop_, &registry, move |global| {
+        (global.type_ == ObjectType::Node && global.id == node_id)
+            || global.type_ == ObjectType::Device
+    })
+    .context(OP)?;
+    let Some(node) = globals.iter().find(|global| global.id == node_id) else {
+        return Ok(false);
+    };
+
+    // The route device is only part of the full properties of the node, sent with its info.
+    let node: Node = registry.bind(node).context(OP)?;
+    let route = Rc::new(RefCell::new(None));
+    let listener = node
+        .add_listener_local()
+        .info({
+            let route = route.clone();
+            move |info| {
+                if let Some(props) = info.props() {
+                    *route.borrow_mut() = node_device_id(props).zip(node_profile_device(props));
+                }
+            }
+        })
+        .register();
+    roundtrip(core, loop_).context(OP)?;
+    drop(listener);
+    let Some((device_id, route_device)) = route.take() else {
+        return Ok(false);
+    };
+
+    let Some(device) = globals
+        .iter()
+        .find(|global| global.type_ == ObjectType::Device && global.id == device_id)
+    else {
+        return Ok(false);
+    };
+    let device = bind_device(&registry, device, &[ParamType::Route])?;
+    roundtrip(core, loop_).context(OP)?;
+    let Some(route) = device
+        .params
+        .objects(ParamType::Route)
+        .iter()
+        .filter_map(DeviceRoute::from_object)
+        .find(|route| route.device == route_device)
+    else {
+        return Ok(false);
+    };
+
+    device
+        .proxy
+        .set_route_volume(route.index, route.device, props)
+        .context(OP)?;
+    // Keep the device bound until the server has processed the route.
+    roundtrip(core, loop_).context(OP)?;
+    Ok(true)
+}
+
+/// List the video sources of the graph, such as cameras, with the video modes they support.
+///
+/// See the [module documentation](self) for the thread this can be called from.
+pub fn video_sources(core: &CoreRef) -> Result<Vec<VideoSourceDesc>, Error> {
+    const OP: &str = "discover the video sources";
+
+    let loop_ = core_loop(core);
+    let registry = core.get_registry().context(OP)?;
+    let globals = collect_globals(core, loop_, &registry, is_video_source).context(OP)?;
+
+    let nodes = globals
+        .iter()
+        .map(|global| {
+            Ok((
+                global,
+                bind_node(&registry, global, &[ParamType::EnumFormat])?,
+            ))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    roundtrip(core, loop_).context(OP)?;
+
+    let mut descs: Vec<_> = nodes
+        .into_iter()
+        .filter_map(|(global, node)| {
+            let props = global.props()?;
+            let (name, description) = node_names(props);
+            Some(VideoSourceDesc {
+                node_id: global.id,
+                name,
+                description,
+                device_id: node_device_id(props),
+                modes: node
+                    .params
+                    .objects(ParamType::EnumFormat)
+                    .iter()
+                    .flat_map(VideoMode::from_object)
+                    .collect(),
+            })
+        })
+        .collect();
+    descs.sort_by_key(|desc| desc.node_id);
+
+    Ok(descs)
+}
+
+/// The name of a node and its human readable description, falling back to its nick or name.
+fn node_names(props: &DictRef) -> (String, String) {
+    let name = props.get(*keys::NODE_NAME).unwrap_or_default().to_owned();
+    let description = props
+        .get(*keys::NODE_DESCRIPTION)
+        .or_else(|| props.get(*keys::NODE_NICK))
+        .map_or_else(|| name.clone(), str::to_owned);
+    (name, description)
+}
+
+/// The id of the device of a node.
+fn node_device_id(props: &DictRef) -> Option<u32> {
+    props
+        .get(*keys::DEVICE_ID)
+        .and_then(|id| id.parse::<u32>().ok())
+}
+
+/// The route device of a node, from its `card.profile.device` property.
+fn node_profile_device(props: &DictRef) -> Option<i32> {
+    props
+        .get("card.profile.device")
+        .and_then(|device| device.parse::<i32>().ok())
+}
+
+/// The direction of the ports of `global` if it is an audio sink or source node.
+fn audio_node_direction<P: AsRef<DictRef>>(global: &GlobalObject<P>) -> Option<Direction> {
+    if global.type_ != ObjectType::Node {
+        return None;
+    }
+    let class = global.props()?.get(*keys::MEDIA_CLASS)?;
+    if class.starts_with("Audio/Sink") {
+        Some(Direction::Input)
+    } else if class.starts_with("Audio/Source") {
+        Some(Direction::Output)
+    } else {
+        None
+    }
+}
+
+fn is_audio_device(global: &GlobalObject<&DictRef>) -> bool {
+    global.type_ == ObjectType::Device
+        && global
+            .props()
+            .and_then(|props| props.get(*keys::MEDIA_CLASS))
+            .map_or(false, |class| class == "Audio/Device")
+}
+
+fn is_video_source(global: &GlobalObject<&DictRef>) -> bool {
+    global.type_ == ObjectType::Node
+        && global
+            .props()
+            .and_then(|props| props.get(*keys::MEDIA_CLASS))
+            .map_or(false, |class| class == "Video/Source")
+}
+
+/// Collect the globals of `registry` accepted by `filter`.
+fn collect_globals(
+    core: &CoreRef,
+    loop_: &LoopRef,
+    registry: &Registry,
+    filter: impl Fn(&GlobalObject<&DictRef>) -> bool + 'static,
+) -> Result<Vec<GlobalObject<Properties>>, Error> {
+    let globals = Rc::new(RefCell::new(Vec::new()));
+    let listener = registry
+        .add_listener_local()
+        .global({
+            let globals = globals.clone();
+            move |global| {
+                if filter(global) {
+                    globals.borrow_mut().push(global.to_owned());
+                }
+            }
+        })
+        .register();
+    roundtrip(core, loop_)?;
+    drop(listener);
+
+    Ok(globals.take())
+}
+
+/// The params received for a bound object.
+#[derive(Clone, Default)]
+struct Params(Rc<RefCell<Vec<(ParamType, Object)>>>);
+
+impl Params {
+    fn add(&self, id: ParamType, param: Option<&Pod>) {
+        let Some(param) = param else {
+            return;
+        };
+        if let Ok((_, Value::Object(object))) =
+            PodDeserializer::deserialize_any_from(param.as_bytes())
+        {
+            self.0.borrow_mut().push((id, object));
+        }
+    }
+
+    /// The objects received for the param `id`, in the order of their index.
+    fn objects(&self, id: ParamType) -> Vec<Object> {
+        self.0
+            .borrow()
+            .iter()
+            .filter(|(param_id, _)| *param_id == id)
+            .map(|(_, object)| object.clone())
+            .collect()
+    }
+}
+
+/// A bound object whose params are being enumerated.
+struct Bound<P, L> {
+    // The listener has to be dropped before the proxy it is registered on.
+    _listener: L,
+    proxy: P,
+    params: Params,
+}
+
+/// Bind the node `global` and enumerate its params `ids`.
+fn bind_node(
+    registry: &Registry,
+    global: &GlobalObject<Properties>,
+    ids: &[ParamType],
+) -> Result<Bound<Node, NodeListener>, Error> {
+    const OP: &str = "enumerate the params of a node";
+
+    let node: Node = registry.bind(global).context(OP)?;
+    let params = Params::default();
+    let listener = node
+        .add_listener_local()
+        .param({
+            let params = params.clone();
+            move |_, id, _, _, param| params.add(id, param)
+        })
+        .register();
+    for id in ids {
+        node.enum_params(0, Some(*id), 0, u32::MAX).context(OP)?;
+    }
+
+    Ok(Bound {
+        _listener: listener,
+        proxy: node,
+        params,
+    })
+}
+
+/// Bind the device `global` and enumerate its params `ids`.
+fn bind_device(
+    registry: &Registry,
+    global: &GlobalObject<Properties>,
+    ids: &[ParamType],
+) -> Result<Bound<Device, DeviceListener>, Error> {
+    const OP: &str = "enumerate the params of a device";
+
+    let device: Device = registry.bind(global).context(OP)?;
+    let params = Params::default();
+    let listener = device
+        .add_listener_local()
+        .param({
+            let params = params.clone();
+            move |_, id, _, _, param| params.add(id, param)
+        })
+        .register();
+    for id in ids {
+        device.enum_params(0, Some(*id), 0, u32::MAX).context(OP)?;
+    }
+
+    Ok(Bound {
+        _listener: listener,
+        proxy: device,
+        params,
+    })
+}
+
+fn id_choice(value: &Value) -> Option<ChoiceEnum<Id>> {
+    match value {
+        Value::Id(id) => Some(ChoiceEnum::None(*id)),
+        Value::Choice(ChoiceValue::Id(Choice(_, choice))) => Some(choice.clone()),
+        _ => None,
+    }
+}
+
+fn rectangle_choice(value: &Value) -> Option<ChoiceEnum<Rectangle>> {
+    match value {
+        Value::Rectangle(rectangle) => Some(ChoiceEnum::None(*rectangle)),
+        Value::Choice(ChoiceValue::Rectangle(Choice(_, choice))) => Some(choice.clone()),
+        _ => None,
+    }
+}
+
+fn fraction_choice(value: &Value) -> Option<ChoiceEnum<Fraction>> {
+    match value {
+        Value::Fraction(fraction) => Some(ChoiceEnum::None(*fraction)),
+        Value::Choice(ChoiceValue::Fraction(Choice(_, choice))) => Some(choice.clone()),
+        _ => None,
+    }
+}
+
+/// The value of the property `key` of `object`.
+fn property(object: &Object, key: u32) -> Option<&Value> {
+    object
+        .properties
+        .iter()
+        .find(|property| property.key == key)
+        .map(|property| &property.value)
+}
+
+fn int_property(object: &Object, key: u32) -> Option<i32> {
+    match property(object, key)? {
+        Value::Int(value) => Some(*value),
+        _ => None,
+    }
+}
+
+fn string_property(object: &Object, key: u32) -> Option<String> {
+    match property(object, key)? {
+        Value::String(value) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use spa::{
+        pod::Property,
+        utils::{ChoiceFlags, SpaTypes},
+    };
+
+    use super::*;
+
+    #[test]
+    fn route() {
+        let props = VolumeProps {
+            volume: Some(0.5),
+            mute: Some(false),
+            channel_volumes: None,
+        };
+        let object = Object {
+            type_: SpaTypes::ObjectParamRoute.as_raw(),
+            id: ParamType::Route.as_raw(),
+            properties: vec![
+                Property::new(spa_sys::SPA_PARAM_ROUTE_index, Value::Int(3)),
+                Property::new(
+                    spa_sys::SPA_PARAM_ROUTE_direction,
+                    Value::Id(Id(Direction::Output.as_raw())),
+                ),
+                Property::new(spa_sys::SPA_PARAM_ROUTE_device, Value::Int(1)),
+                Property::new(
+                    spa_sys::SPA_PARAM_ROUTE_name,
+                    Value::String("analog-output-headphones".to_owned()),
+                ),
+                Property::new(
+                    spa_sys::SPA_PARAM_ROUTE_props,
+                    Value::Object(props.to_object()),
+                ),
+            ],
+        };
+
+        let route = DeviceRoute::from_object(&object).unwrap();
+        assert_eq!(route.index, 3);
+        assert_eq!(route.device, 1);
+        assert_eq!(route.direction, Some(Direction::Output));
+        assert_eq!(route.name, "analog-output-headphones");
+        assert_eq!(route.description, "");
+        assert_eq!(route.volume, props);
+
+        let object = Object {
+            properties: object.properties[..1].to_vec(),
+            ..object
+        };
+        assert_eq!(DeviceRoute::from_object(&object), None);
+    }
+
+    #[test]
+    fn video_modes() {
+        let object = Object {
+            type_: SpaTypes::ObjectParamFormat.as_raw(),
+            id: ParamType::EnumFormat.as_raw(),
+            properties: vec![
+                Property::new(
+                    FormatProperties::MediaType.as_raw(),
+                    Value::Id(Id(MediaType::Video.as_raw())),
+                ),
+                Property::new(
+                    FormatProperties::MediaSubtype.as_raw(),
+                    Value::Id(Id(MediaSubtype::Raw.as_raw())),
+                ),
+                Property::new(
+                    FormatProperties::VideoFormat.as_raw(),
+                    Value::Choice(ChoiceValue::Id(Choice(
+                        ChoiceFlags::empty(),
+                        ChoiceEnum::Enum {
+                            default: Id(VideoFormat::YUY2.as_raw()),
+                            alternatives: vec![
+                                Id(VideoFormat::YUY2.as_raw()),
+                                Id(VideoFormat::NV12.as_raw()),
+                            ],
+                        },
+                    ))),
+                ),
+                Property::new(
+                    FormatProperties::VideoSize.as_raw(),
+                    Value::Rectangle(Rectangle {
+                        width: 640,
+                        height: 480,
+                    }),
+                ),
+                Property::new(
+                    FormatProperties::VideoFramerate.as_raw(),
+                    Value::Choice(ChoiceValue::Fraction(Choice(
+                        ChoiceFlags::empty(),
+                        ChoiceEnum::Range {
+                            default: Fraction { num: 30, denom: 1 },
+                            min: Fraction { num: 1, denom: 1 },
+                            max: Fraction { num: 30, denom: 1 },
+                        },
+                    ))),
+                ),
+            ],
+        };
+
+        let modes = VideoMode::from_object(&object);
+        assert_eq!(modes.len(), 2);
+        assert_eq!(modes[0].subtype, MediaSubtype::Raw);
+        assert_eq!(modes[0].format, Some(VideoFormat::YUY2));
+        assert_eq!(modes[1].format, Some(VideoFormat::NV12));
+        assert_eq!(
+            modes[1].sizes.iter_alternatives().collect::<Vec<_>>(),
+            [&Rectangle {
+                width: 640,
+                height: 480
+            }]
+        );
+        assert_eq!(
+            modes[1].framerates.range().map(|range| range.end().num),
+            Some(30)
+        );
+
+        // Compressed formats have no pixel format.
+        let mut properties = object.properties.clone();
+        properties.retain(|p| p.key != FormatProperties::VideoFormat.as_raw());
+        properties[1].value = Value::Id(Id(MediaSubtype::Mjpg.as_raw()));
+        let modes = VideoMode::from_object(&Object {
+            properties,
+            ..object.clone()
+        });
+        assert_eq!(modes.len(), 1);
+        assert_eq!(modes[0].subtype, MediaSubtype::Mjpg);
+        assert_eq!(modes[0].format, None);
+
+        // Not a video format.
+        let mut properties = object.properties.clone();
+        properties[0].value = Value::Id(Id(MediaType::Audio.as_raw()));
+        assert!(VideoMode::from_object(&Object {
+            properties,
+            ..object
+        })
+        .is_empty());
+    }
+
+    #[test]
+    fn profile() {
+        let object = Object {
+            type_: SpaTypes::ObjectParamProfile.as_raw(),
+            id: ParamType::EnumProfile.as_raw(),
+            properties: vec![
+                Property::new(spa_sys::SPA_PARAM_PROFILE_index, Value::Int(1)),
+                Property::new(
+                    spa_sys::SPA_PARAM_PROFILE_name,
+                    Value::String("output:analog-stereo".to_owned()),
+                ),
+                Property::new(
+                    spa_sys::SPA_PARAM_PROFILE_description,
+                    Value::String("Analog Stereo Output".to_owned()),
+                ),
+            ],
+        };
+
+        assert_eq!(
+            DeviceProfile::from_object(&object),
+            Some(DeviceProfile {
+                index: 1,
+                name: "output:analog-stereo".to_owned(),
+                description: "Analog Stereo Output".to_owned(),
+            })
+        );
+    }
+}
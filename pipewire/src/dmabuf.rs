@@ -0,0 +1,250 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Zero-copy video with DMA-BUF.
+//!
+//! This module requires the `dmabuf` feature.
+//!
+//! A producer negotiates DMA-BUF buffers by offering the modifiers it can allocate with
+//! [`modifiers_property`] in its `EnumFormat` params, and by requesting DMA-BUF memory with
+//! [`buffers_param`] once the format is negotiated. Its stream is connected with
+//! [`StreamFlags::ALLOC_BUFFERS`](crate::stream::StreamFlags::ALLOC_BUFFERS), so that the
+//! buffers exported by GBM, VA-API or Vulkan can be attached to the stream buffers with
+//! [`DmaBuf::attach`] in the `add_buffer` event.
+//!
+//! A consumer gets the planes of the received buffers with [`planes`], to import them into
+//! another API.
+
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd};
+
+use spa::{
+    buffer::{Data, DataFlags, DataType, DataTypes},
+    param::{format::FormatProperties, ParamType},
+    pod::{ChoiceValue, Object, Property, PropertyFlags, Value},
+    utils::{Choice, ChoiceEnum, ChoiceFlags, SpaTypes},
+};
+
+use crate::{buffer::Buffer, error::ResultExt, Error};
+
+/// A plane of a [`DmaBuf`].
+#[derive(Debug)]
+pub struct DmaBufPlane {
+    /// The DMA-BUF file descriptor holding the plane.
+    pub fd: OwnedFd,
+    /// The offset of the plane in the DMA-BUF.
+    pub offset: u32,
+    /// The distance in bytes between two lines of the plane, signed like in a `spa_chunk`.
+    pub stride: i32,
+    /// The size in bytes of the plane.
+    pub size: u32,
+}
+
+impl AsFd for DmaBufPlane {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+/// A frame exported as DMA-BUF by another API, such as a GBM buffer object.
+#[derive(Debug)]
+pub struct DmaBuf {
+    /// The modifier describing the layout of the planes in memory.
+    pub modifier: u64,
+    /// The planes of the frame, in order.
+    pub planes: Vec<DmaBufPlane>,
+}
+
+impl DmaBuf {
+    /// Attach the planes to the datas of `buffer`, one plane per data.
+    ///
+    /// The file descriptors are not duplicated, so `self` has to outlive the buffer, until it
+    /// is removed from the stream in the `remove_buffer` event.
+    ///
+    /// # Errors
+    /// Fails with `EINVAL` if the buffer does not have one data per plane, and with `ENOTSUP` if
+    /// the datas do not accept DMA-BUF memory.
+    ///
+    /// # Safety
+    /// `buffer` must be a valid buffer received in the `add_buffer` event of a stream connected
+    /// with `ALLOC_BUFFERS`.
+    pub unsafe fn attach(&self, buffer: *mut pw_sys::pw_buffer) -> Result<(), Error> {
+        let spa_buffer = (*buffer).buffer;
+        if spa_buffer.is_null()
+            || (*spa_buffer).datas.is_null()
+            || usize::try_from((*spa_buffer).n_datas).unwrap() != self.planes.len()
+        {
+            return Err(errno_error(libc::EINVAL)).context("attach a DMA-BUF");
+        }
+
+        let datas = std::slice::from_raw_parts_mut((*spa_buffer).datas, self.planes.len());
+
+        // Before the memory is attached, the type holds the mask of the accepted types.
+        if datas
+            .iter()
+            .any(|data| !DataTypes::from_bits(data.type_).contains(DataType::DmaBuf))
+        {
+            return Err(errno_error(libc::ENOTSUP)).context("attach a DMA-BUF");
+        }
+
+        for (data, plane) in datas.iter_mut().zip(&self.planes) {
+            data.type_ = DataType::DmaBuf.as_raw();
+            data.flags = DataFlags::READABLE.bits();
+            data.fd = i64::from(plane.fd.as_raw_fd());
+            data.mapoffset = 0;
+            data.maxsize = plane.offset.saturating_add(plane.size);
+            data.data = std::ptr::null_mut();
+
+            if let Some(chunk) = data.chunk.as_mut() {
+                chunk.offset = plane.offset;
+                chunk.stride = plane.stride;
+                chunk.size = plane.size;
+                chunk.flags = 0;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn errno_error(errno: i32) -> spa::utils::result::Error {
+    nix::errno::Errno::from_i32(errno).into()
+}
+
+/// A plane of a DMA-BUF buffer received from a stream.
+#[derive(Debug, Clone, Copy)]
+pub struct DmaBufPlaneRef<'a> {
+    /// The DMA-BUF file descriptor holding the plane.
+    pub fd: BorrowedFd<'a>,
+    /// The offset of the plane in the DMA-BUF.
+    pub offset: u32,
+    /// The distance in bytes between two lines of the plane.
+    pub stride: i32,
+    /// The size in bytes of the plane.
+    pub size: u32,
+}
+
+impl<'a> DmaBufPlaneRef<'a> {
+    fn from_data(data: &'a Data) -> Option<Self> {
+        if data.type_() != DataType::DmaBuf {
+            return None;
+        }
+
+        let chunk = data.chunk();
+        Some(Self {
+            fd: data.fd()?,
+            offset: chunk.offset(),
+            stride: chunk.stride(),
+            size: chunk.size(),
+        })
+    }
+}
+
+/// The DMA-BUF planes of `buffer`, to hand to the API importing them.
+///
+/// Returns `None` if the buffer is not made of DMA-BUF memory.
+/// The modifier of the planes is the one of the negotiated format, see
+/// [`VideoInfoRaw::modifier`](spa::param::video::VideoInfoRaw::modifier).
+pub fn planes<'a>(buffer: &'a mut Buffer) -> Option<Vec<DmaBufPlaneRef<'a>>> {
+    let datas: &'a [Data] = buffer.datas_mut();
+    if datas.is_empty() {
+        return None;
+    }
+
+    datas.iter().map(DmaBufPlaneRef::from_data).collect()
+}
+
+/// The `VideoModifier` property of an `EnumFormat` param offering `modifiers`,
+/// in order of preference.
+///
+/// With the `v0_3_33` feature, the property lets the consumer pick the modifier,
+/// which the producer then has to fixate.
+///
+/// # Panics
+/// If `modifiers` is empty.
+pub fn modifiers_property(modifiers: &[u64]) -> Property {
+    // Modifiers are 64-bit values carried in a Long pod.
+    let modifiers: Vec<i64> = modifiers.iter().map(|&m| m as i64).collect();
+    let flags = PropertyFlags::MANDATORY;
+    #[cfg(feature = "v0_3_33")]
+    let flags = flags | PropertyFlags::DONT_FIXATE;
+
+    Property::new(
+        FormatProperties::VideoModifier.as_raw(),
+        Value::Choice(ChoiceValue::Long(Choice(
+            ChoiceFlags::empty(),
+            ChoiceEnum::Enum {
+                default: modifiers[0],
+                alternatives: modifiers,
+            },
+        ))),
+    )
+    .with_flags(flags)
+}
+
+/// The `Buffers` param requesting `buffers` DMA-BUF buffers of `planes` planes.
+///
+/// The counts are clamped to `i32::MAX`, the largest value of the param.
+pub fn buffers_param(buffers: u32, planes: u32) -> Object {
+    let int = |value: u32| Value::Int(i32::try_from(value).unwrap_or(i32::MAX));
+
+    Object {
+        type_: SpaTypes::ObjectParamBuffers.as_raw(),
+        id: ParamType::Buffers.as_raw(),
+        properties: vec![
+            Property::new(spa_sys::SPA_PARAM_BUFFERS_buffers, int(buffers)),
+            Property::new(spa_sys::SPA_PARAM_BUFFERS_blocks, int(planes)),
+            DataTypes::from(DataType::DmaBuf).to_property(),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn attach() {
+        // Any fd will do to check the plumbing.
+        let fd = OwnedFd::from(File::open("/dev/null").unwrap());
+        let dmabuf = DmaBuf {
+            modifier: 0,
+            planes: vec![DmaBufPlane {
+                fd,
+                offset: 64,
+                stride: 256,
+                size: 4096,
+            }],
+        };
+
+        let mut chunk: spa_sys::spa_chunk = unsafe { std::mem::zeroed() };
+        let mut data: spa_sys::spa_data = unsafe { std::mem::zeroed() };
+        data.type_ = 1 << DataType::MemFd.as_raw();
+        data.chunk = &mut chunk;
+        let mut spa_buffer: spa_sys::spa_buffer = unsafe { std::mem::zeroed() };
+        spa_buffer.n_datas = 1;
+        spa_buffer.datas = &mut data;
+        let mut buffer: pw_sys::pw_buffer = unsafe { std::mem::zeroed() };
+        buffer.buffer = &mut spa_buffer;
+
+        let err = unsafe { dmabuf.attach(&mut buffer) }.unwrap_err();
+        assert_eq!(err.errno(), Some(nix::errno::Errno::ENOTSUP));
+
+        data.type_ |= 1 << DataType::DmaBuf.as_raw();
+        unsafe { dmabuf.attach(&mut buffer) }.unwrap();
+
+        let data = unsafe { &*std::ptr::addr_of!(data).cast::<Data>() };
+        let plane = DmaBufPlaneRef::from_data(data).unwrap();
+        assert_eq!(plane.fd.as_raw_fd(), dmabuf.planes[0].fd.as_raw_fd());
+        assert_eq!((plane.offset, plane.stride, plane.size), (64, 256, 4096));
+    }
+
+    #[test]
+    fn buffers_param_clamped() {
+        let param = buffers_param(u32::MAX, 2);
+        assert_eq!(param.properties[0].value, Value::Int(i32::MAX));
+        assert_eq!(param.properties[1].value, Value::Int(2));
+    }
+}
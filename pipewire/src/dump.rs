@@ -0,0 +1,1381 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Snapshots of the whole graph, in the JSON format of `pw-dump`, and events describing its changes.
+//!
+//! This module is only available with the `serde` feature.
+//!
+//! ```no_run
+//! use pipewire::{context::Context, dump::GraphSnapshot, main_loop::MainLoop};
+//!
+//! pipewire::init();
+//! let mainloop = MainLoop::new(None)?;
+//! let context = Context::new(&mainloop)?;
+//! let core = context.connect(None)?;
+//!
+//! let snapshot = GraphSnapshot::capture(&core)?;
+//! println!("{}", serde_json::to_string_pretty(&snapshot).unwrap());
+//! # Ok::<(), pipewire::Error>(())
+//! ```
+//!
+//! [`GraphMonitor`] reports the changes of the graph as they happen, while
+//! [`GraphSnapshot::diff`] computes them between two snapshots.
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    ffi::CStr,
+    ptr,
+    rc::{Rc, Weak},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value as JsonValue};
+use spa::{
+    param::{ParamInfo, ParamInfoFlags, ParamType},
+    pod::{
+        deserialize::PodDeserializer, CanonicalFixedSizedPod, ChoiceValue, Pod, Value, ValueArray,
+    },
+    utils::{dict::DictRef, Choice, ChoiceEnum, Direction, Fraction, Id, Rectangle},
+};
+
+use crate::{
+    client::{Client, ClientListener},
+    core::Core,
+    device::{Device, DeviceListener},
+    factory::{Factory, FactoryListener},
+    link::{Link, LinkListener, LinkState},
+    metadata::{Metadata, MetadataListener},
+    module::{Module, ModuleListener},
+    node::{Node, NodeInfoRef, NodeListener, NodeState},
+    pending::{core_loop, roundtrip},
+    permissions::PermissionFlags,
+    port::{Port, PortListener},
+    registry::{self, GlobalObject, Registry},
+    types::ObjectType,
+    Error,
+};
+
+/// A snapshot of all the objects of the graph.
+///
+/// This serializes to the same JSON array as the output of `pw-dump`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GraphSnapshot {
+    pub objects: Vec<DumpObject>,
+}
+
+/// An object of a [`GraphSnapshot`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpObject {
+    /// The id of the global.
+    pub id: u32,
+    /// The interface type, such as `PipeWire:Interface:Node`.
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub version: u32,
+    /// The permissions of the client on the object, as `r`, `w`, `x`, `m` and `l`.
+    pub permissions: Vec<String>,
+    /// The info of the object, including its `params` for nodes, ports and devices.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub info: Option<Map<String, JsonValue>>,
+    /// The properties of the global, for objects without an info.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub props: Option<Map<String, JsonValue>>,
+    /// The properties of a metadata object.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Vec<MetadataEntry>>,
+}
+
+/// A property of a metadata object.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetadataEntry {
+    pub subject: u32,
+    pub key: String,
+    #[serde(rename = "type")]
+    pub type_: Option<String>,
+    /// The value, parsed if its type is `Spa:String:JSON`.
+    pub value: JsonValue,
+}
+
+impl GraphSnapshot {
+    /// Capture all the objects of the graph, with their info, params and metadata.
+    ///
+    /// This iterates the loop of the context of `core` until the server answered,
+    /// so it must be called from the thread of that loop, and not from inside one of its callbacks.
+    ///
+    /// Objects of interfaces which can't be bound, such as the core, only contain the
+    /// properties of their global.
+    pub fn capture(core: &Core) -> Result<Self, Error> {
+        let loop_ = core_loop(core);
+        let registry = core.get_registry()?;
+
+        let globals = Rc::new(RefCell::new(Vec::new()));
+        let listener = registry
+            .add_listener_local()
+            .global({
+                let globals = globals.clone();
+                move |global| {
+                    globals.borrow_mut().push((
+                        GlobalObject {
+                            id: global.id,
+                            permissions: global.permissions,
+                            type_: global.type_.clone(),
+                            version: global.version,
+                            props: None::<&DictRef>,
+                        },
+                        DumpObject {
+                            id: global.id,
+                            type_: global.type_.to_str().to_owned(),
+                            version: global.version,
+                            permissions: permissions_to_json(global.permissions),
+                            info: None,
+                            props: Some(dict_to_json(global.props)),
+                            metadata: None,
+                        },
+                    ))
+                }
+            })
+            .register();
+        roundtrip(core, loop_)?;
+        drop(listener);
+
+        let globals = globals.take();
+        let mut bound = Vec::with_capacity(globals.len());
+        for (global, _) in &globals {
+            let collected = Rc::new(RefCell::new(Collected::default()));
+            bound.push((Bound::new(&registry, global, &collected)?, collected));
+        }
+        // Wait for the info of the objects, which lists the params to enumerate.
+        roundtrip(core, loop_)?;
+
+        for (bound, collected) in &bound {
+            if let Some(bound) = bound {
+                bound.enum_params(&collected.borrow().param_ids)?;
+            }
+        }
+        roundtrip(core, loop_)?;
+
+        let objects = globals
+            .into_iter()
+            .zip(bound)
+            .map(|((_, mut object), (bound, collected))| {
+                // Drop the listener before taking the collected state.
+                drop(bound);
+                let collected = Rc::try_unwrap(collected)
+                    .map(RefCell::into_inner)
+                    .unwrap_or_default();
+
+                if let Some(mut info) = collected.info {
+                    if let Some(params) = collected.params {
+                        info.insert("params".to_owned(), JsonValue::Object(params));
+                    }
+                    object.info = Some(info);
+                    object.props = None;
+                }
+                if let Some(metadata) = collected.metadata {
+                    object.metadata = Some(metadata);
+                }
+
+                object
+            })
+            .collect();
+
+        Ok(Self { objects })
+    }
+
+    /// Get the object with the id `id`.
+    pub fn object(&self, id: u32) -> Option<&DumpObject> {
+        self.objects.iter().find(|object| object.id == id)
+    }
+}
+
+/// The info, params and metadata received for an object.
+#[derive(Default)]
+struct Collected {
+    info: Option<Map<String, JsonValue>>,
+    /// The params to enumerate.
+    param_ids: Vec<ParamType>,
+    params: Option<Map<String, JsonValue>>,
+    metadata: Option<Vec<MetadataEntry>>,
+}
+
+impl Collected {
+    fn set_params(&mut self, params: &[ParamInfo]) {
+        let map = self.params.get_or_insert_with(Map::new);
+        self.param_ids.clear();
+
+        for param in params {
+            map.entry(param_name(param.id()))
+                .or_insert_with(|| JsonValue::Array(Vec::new()));
+            if param.flags().contains(ParamInfoFlags::READ) {
+                self.param_ids.push(param.id());
+            }
+        }
+    }
+
+    fn add_param(&mut self, id: ParamType, param: Option<&Pod>) {
+        let Some(param) = param else {
+            return;
+        };
+
+        if let JsonValue::Array(params) = self
+            .params
+            .get_or_insert_with(Map::new)
+            .entry(param_name(id))
+            .or_insert_with(|| JsonValue::Array(Vec::new()))
+        {
+            params.push(pod_to_json(param));
+        }
+    }
+
+    fn set_metadata(
+        &mut self,
+        subject: u32,
+        key: Option<&str>,
+        type_: Option<&str>,
+        value: Option<&str>,
+    ) {
+        let metadata = self.metadata.get_or_insert_with(Vec::new);
+        let Some(key) = key else {
+            metadata.clear();
+            return;
+        };
+
+        metadata.retain(|entry| entry.subject != subject || entry.key != key);
+        if let Some(value) = value {
+            let value = match type_ {
+                Some("Spa:String:JSON") => serde_json::from_str(value)
+                    .unwrap_or_else(|_| JsonValue::String(value.to_owned())),
+                _ => JsonValue::String(value.to_owned()),
+            };
+
+            metadata.push(MetadataEntry {
+                subject,
+                key: key.to_owned(),
+                type_: type_.map(str::to_owned),
+                value,
+            });
+        }
+    }
+}
+
+/// A proxy bound to collect the info of an object, with its listener.
+///
+/// The listener is declared first so it is dropped before the proxy.
+enum Bound {
+    Client(ClientListener, Client),
+    Device(DeviceListener, Device),
+    Factory(FactoryListener, Factory),
+    Link(LinkListener, Link),
+    Metadata(MetadataListener, Metadata),
+    Module(ModuleListener, Module),
+    Node(NodeListener, Node),
+    Port(PortListener, Port),
+}
+
+impl Bound {
+    fn new(
+        registry: &Registry,
+        global: &GlobalObject<&DictRef>,
+        collected: &Rc<RefCell<Collected>>,
+    ) -> Result<Option<Self>, Error> {
+        let bound = match global.type_ {
+            ObjectType::Client => {
+                let client: Client = registry.bind(global)?;
+                let listener = client
+                    .add_listener_local()
+                    .info({
+                        let collected = collected.clone();
+                        move |info| {
+                            let mut map = Map::new();
+                            map.insert(
+                                "change-mask".to_owned(),
+                                change_mask_to_json(info.change_mask().iter_names()),
+                            );
+                            map.insert("props".to_owned(), dict_to_json(info.props()).into());
+                            collected.borrow_mut().info = Some(map);
+                        }
+                    })
+                    .register();
+                Bound::Client(listener, client)
+            }
+            ObjectType::Device => {
+                let device: Device = registry.bind(global)?;
+                let listener = device
+                    .add_listener_local()
+                    .info({
+                        let collected = collected.clone();
+                        move |info| {
+                            let mut map = Map::new();
+                            map.insert(
+                                "change-mask".to_owned(),
+                                change_mask_to_json(info.change_mask().iter_names()),
+                            );
+                            map.insert("props".to_owned(), dict_to_json(info.props()).into());
+
+                            let mut collected = collected.borrow_mut();
+                            collected.set_params(info.params());
+                            collected.info = Some(map);
+                        }
+                    })
+                    .param({
+                        let collected = collected.clone();
+                        move |_, id, _, _, param| collected.borrow_mut().add_param(id, param)
+                    })
+                    .register();
+                Bound::Device(listener, device)
+            }
+            ObjectType::Factory => {
+                let factory: Factory = registry.bind(global)?;
+                let listener = factory
+                    .add_listener_local()
+                    .info({
+                        let collected = collected.clone();
+                        move |info| {
+                            let mut map = Map::new();
+                            map.insert("name".to_owned(), info.name().into());
+                            map.insert("type".to_owned(), info.type_().to_str().into());
+                            map.insert("version".to_owned(), info.version().into());
+                            map.insert(
+                                "change-mask".to_owned(),
+                                change_mask_to_json(info.change_mask().iter_names()),
+                            );
+                            map.insert("props".to_owned(), dict_to_json(info.props()).into());
+                            collected.borrow_mut().info = Some(map);
+                        }
+                    })
+                    .register();
+                Bound::Factory(listener, factory)
+            }
+            ObjectType::Link => {
+                let link: Link = registry.bind(global)?;
+                let listener = link
+                    .add_listener_local()
+                    .info({
+                        let collected = collected.clone();
+                        move |info| {
+                            let (state, error) = link_state_to_json(info.state());
+                            let mut map = Map::new();
+                            map.insert("output-node-id".to_owned(), info.output_node_id().into());
+                            map.insert("output-port-id".to_owned(), info.output_port_id().into());
+                            map.insert("input-node-id".to_owned(), info.input_node_id().into());
+                            map.insert("input-port-id".to_owned(), info.input_port_id().into());
+                            map.insert(
+                                "change-mask".to_owned(),
+                                change_mask_to_json(info.change_mask().iter_names()),
+                            );
+                            map.insert("state".to_owned(), state.into());
+                            map.insert("error".to_owned(), error.into());
+                            map.insert(
+                                "format".to_owned(),
+                                info.format().map_or(JsonValue::Null, pod_to_json),
+                            );
+                            map.insert("props".to_owned(), dict_to_json(info.props()).into());
+                            collected.borrow_mut().info = Some(map);
+                        }
+                    })
+                    .register();
+                Bound::Link(listener, link)
+            }
+            ObjectType::Metadata => {
+                let metadata: Metadata = registry.bind(global)?;
+                let listener = metadata
+                    .add_listener_local()
+                    .property({
+                        let collected = collected.clone();
+                        move |subject, key, type_, value| {
+                            collected
+                                .borrow_mut()
+                                .set_metadata(subject, key, type_, value);
+                            0
+                        }
+                    })
+                    .register();
+                collected.borrow_mut().metadata = Some(Vec::new());
+                Bound::Metadata(listener, metadata)
+            }
+            ObjectType::Module => {
+                let module: Module = registry.bind(global)?;
+                let listener = module
+                    .add_listener_local()
+                    .info({
+                        let collected = collected.clone();
+                        move |info| {
+                            let mut map = Map::new();
+                            map.insert("name".to_owned(), info.name().into());
+                            map.insert("filename".to_owned(), info.filename().into());
+                            map.insert("args".to_owned(), info.args().into());
+                            map.insert(
+                                "change-mask".to_owned(),
+                                change_mask_to_json(info.change_mask().iter_names()),
+                            );
+                            map.insert("props".to_owned(), dict_to_json(info.props()).into());
+                            collected.borrow_mut().info = Some(map);
+                        }
+                    })
+                    .register();
+                Bound::Module(listener, module)
+            }
+            ObjectType::Node => {
+                let node: Node = registry.bind(global)?;
+                let listener = node
+                    .add_listener_local()
+                    .info({
+                        let collected = collected.clone();
+                        move |info| {
+                            let (state, error) = node_state_to_json(info.state());
+                            let mut map = Map::new();
+                            map.insert("max-input-ports".to_owned(), info.max_input_ports().into());
+                            map.insert(
+                                "max-output-ports".to_owned(),
+                                info.max_output_ports().into(),
+                            );
+                            map.insert(
+                                "change-mask".to_owned(),
+                                change_mask_to_json(info.change_mask().iter_names()),
+                            );
+                            map.insert("n-input-ports".to_owned(), info.n_input_ports().into());
+                            map.insert("n-output-ports".to_owned(), info.n_output_ports().into());
+                            map.insert("state".to_owned(), state.into());
+                            map.insert("error".to_owned(), error.into());
+                            map.insert("props".to_owned(), dict_to_json(info.props()).into());
+
+                            let mut collected = collected.borrow_mut();
+                            collected.set_params(info.params());
+                            collected.info = Some(map);
+                        }
+                    })
+                    .param({
+                        let collected = collected.clone();
+                        move |_, id, _, _, param| collected.borrow_mut().add_param(id, param)
+                    })
+                    .register();
+                Bound::Node(listener, node)
+            }
+            ObjectType::Port => {
+                let port: Port = registry.bind(global)?;
+                let listener = port
+                    .add_listener_local()
+                    .info({
+                        let collected = collected.clone();
+                        move |info| {
+                            let direction = match info.direction() {
+                                Some(Direction::Input) => "input",
+                                Some(Direction::Output) => "output",
+                                None => "unknown",
+                            };
+                            let mut map = Map::new();
+                            map.insert("direction".to_owned(), direction.into());
+                            map.insert(
+                                "change-mask".to_owned(),
+                                change_mask_to_json(info.change_mask().iter_names()),
+                            );
+                            map.insert("props".to_owned(), dict_to_json(info.props()).into());
+
+                            let mut collected = collected.borrow_mut();
+                            collected.set_params(info.params());
+                            collected.info = Some(map);
+                        }
+                    })
+                    .param({
+                        let collected = collected.clone();
+                        move |_, id, _, _, param| collected.borrow_mut().add_param(id, param)
+                    })
+                    .register();
+                Bound::Port(listener, port)
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(bound))
+    }
+
+    fn enum_params(&self, ids: &[ParamType]) -> Result<(), Error> {
+        for id in ids {
+            match self {
+                Bound::Device(_, device) => device.enum_params(0, Some(*id), 0, u32::MAX)?,
+                Bound::Node(_, node) => node.enum_params(0, Some(*id), 0, u32::MAX)?,
+                Bound::Port(_, port) => port.enum_params(0, Some(*id), 0, u32::MAX)?,
+                _ => continue,
+            };
+        }
+
+        Ok(())
+    }
+}
+
+fn permissions_to_json(permissions: PermissionFlags) -> Vec<String> {
+    permissions
+        .iter_names()
+        .map(|(name, _)| name.to_lowercase())
+        .collect()
+}
+
+/// Convert the names of the flags of a change mask, such as `INPUT_PORTS`, to `input-ports`.
+fn change_mask_to_json<T>(names: impl Iterator<Item = (&'static str, T)>) -> JsonValue {
+    names
+        .map(|(name, _)| JsonValue::String(name.to_lowercase().replace('_', "-")))
+        .collect()
+}
+
+fn node_state_to_json(state: NodeState) -> (&'static str, Option<&str>) {
+    match state {
+        NodeState::Error(error) => ("error", Some(error)),
+        NodeState::Creating => ("creating", None),
+        NodeState::Suspended => ("suspended", None),
+        NodeState::Idle => ("idle", None),
+        NodeState::Running => ("running", None),
+    }
+}
+
+fn link_state_to_json(state: LinkState) -> (&'static str, Option<&str>) {
+    match state {
+        LinkState::Error(error) => ("error", Some(error)),
+        LinkState::Unlinked => ("unlinked", None),
+        LinkState::Init => ("init", None),
+        LinkState::Negotiating => ("negotiating", None),
+        LinkState::Allocating => ("allocating", None),
+        LinkState::Paused => ("paused", None),
+        LinkState::Active => ("active", None),
+    }
+}
+
+fn dict_to_json(dict: Option<&DictRef>) -> Map<String, JsonValue> {
+    dict.into_iter()
+        .flat_map(|dict| dict.iter())
+        .map(|(key, value)| (key.to_owned(), prop_value_to_json(value)))
+        .collect()
+}
+
+/// Convert a property value to a JSON number or boolean if it looks like one, like `pw-dump` does.
+fn prop_value_to_json(value: &str) -> JsonValue {
+    if let Ok(value) = value.parse::<i64>() {
+        value.into()
+    } else if let Some(value) = value.parse::<f64>().ok().filter(|value| value.is_finite()) {
+        value.into()
+    } else if let Ok(value) = value.parse::<bool>() {
+        value.into()
+    } else {
+        value.into()
+    }
+}
+
+fn param_name(id: ParamType) -> String {
+    let name = unsafe {
+        let name = spa_sys::spa_debug_type_find_short_name(spa_sys::spa_type_param, id.as_raw());
+        (!name.is_null()).then(|| CStr::from_ptr(name).to_string_lossy().into_owned())
+    };
+
+    name.unwrap_or_else(|| format!("id-{:08x}", id.as_raw()))
+}
+
+fn pod_to_json(pod: &Pod) -> JsonValue {
+    match PodDeserializer::deserialize_any_from(pod.as_bytes()) {
+        Ok((_, value)) => value_to_json(&value, ptr::null()),
+        Err(_) => JsonValue::Null,
+    }
+}
+
+/// Convert a pod value to JSON.
+///
+/// `info` is the type info table naming the ids of the value, or `NULL` to use the root table.
+fn value_to_json(value: &Value, info: *const spa_sys::spa_type_info) -> JsonValue {
+    match value {
+        Value::None | Value::Pointer(..) => JsonValue::Null,
+        Value::Bool(value) => (*value).into(),
+        Value::Id(id) => id_to_json(*id, info),
+        Value::Int(value) => (*value).into(),
+        Value::Long(value) => (*value).into(),
+        Value::Float(value) => (*value).into(),
+        Value::Double(value) => (*value).into(),
+        Value::String(value) => value.as_str().into(),
+        Value::Bytes(value) => value.as_slice().into(),
+        Value::Other { bytes, .. } => bytes.as_slice().into(),
+        Value::Rectangle(value) => rectangle_to_json(value),
+        Value::Fraction(value) => fraction_to_json(value),
+        Value::Fd(fd) => fd.0.into(),
+        Value::ValueArray(array) => {
+            // Array properties, such as the channel positions, name their items with the values of their type.
+            let info = unsafe {
+                match info.as_ref() {
+                    Some(info) if !info.values.is_null() => info.values,
+                    _ => info,
+                }
+            };
+
+            match array {
+                ValueArray::None(values) => values.iter().map(|_| JsonValue::Null).collect(),
+                ValueArray::Bool(values) => values.as_slice().into(),
+                ValueArray::Id(values) => values.iter().map(|id| id_to_json(*id, info)).collect(),
+                ValueArray::Int(values) => values.as_slice().into(),
+                ValueArray::Long(values) => values.as_slice().into(),
+                ValueArray::Float(values) => values.as_slice().into(),
+                ValueArray::Double(values) => values.as_slice().into(),
+                ValueArray::Rectangle(values) => values.iter().map(rectangle_to_json).collect(),
+                ValueArray::Fraction(values) => values.iter().map(fraction_to_json).collect(),
+                ValueArray::Fd(values) => values.iter().map(|fd| fd.0.into()).collect(),
+            }
+        }
+        Value::Struct(values) => values
+            .iter()
+            .map(|value| value_to_json(value, ptr::null()))
+            .collect(),
+        Value::Object(object) => {
+            let props_info = unsafe {
+                spa_sys::spa_debug_type_find(spa_sys::spa_types, object.type_)
+                    .as_ref()
+                    .map_or(ptr::null(), |info| info.values)
+            };
+
+            let map = object
+                .properties
+                .iter()
+                .map(|prop| {
+                    let key_info = unsafe {
+                        if props_info.is_null() {
+                            None
+                        } else {
+                            spa_sys::spa_debug_type_find(props_info, prop.key).as_ref()
+                        }
+                    };
+
+                    match key_info {
+                        Some(key_info) => (
+                            type_short_name(key_info.name),
+                            value_to_json(&prop.value, key_info.values),
+                        ),
+                        None => (
+                            format!("id-{:08x}", prop.key),
+                            value_to_json(&prop.value, ptr::null()),
+                        ),
+                    }
+                })
+                .collect();
+
+            JsonValue::Object(map)
+        }
+        Value::Choice(choice) => match choice {
+            ChoiceValue::Bool(choice) => choice_to_json(choice, |value| (*value).into()),
+            ChoiceValue::Int(choice) => choice_to_json(choice, |value| (*value).into()),
+            ChoiceValue::Long(choice) => choice_to_json(choice, |value| (*value).into()),
+            ChoiceValue::Float(choice) => choice_to_json(choice, |value| (*value).into()),
+            ChoiceValue::Double(choice) => choice_to_json(choice, |value| (*value).into()),
+            ChoiceValue::Id(choice) => choice_to_json(choice, |id| id_to_json(*id, info)),
+            ChoiceValue::Rectangle(choice) => choice_to_json(choice, rectangle_to_json),
+            ChoiceValue::Fraction(choice) => choice_to_json(choice, fraction_to_json),
+            ChoiceValue::Fd(choice) => choice_to_json(choice, |fd| fd.0.into()),
+        },
+    }
+}
+
+fn id_to_json(id: Id, info: *const spa_sys::spa_type_info) -> JsonValue {
+    let name = unsafe {
+        let info = if info.is_null() {
+            spa_sys::spa_types
+        } else {
+            info
+        };
+        let name = spa_sys::spa_debug_type_find_short_name(info, id.0);
+        (!name.is_null()).then(|| CStr::from_ptr(name).to_string_lossy().into_owned())
+    };
+
+    name.unwrap_or_else(|| format!("id-{:08x}", id.0)).into()
+}
+
+fn type_short_name(name: *const std::os::raw::c_char) -> String {
+    let name = unsafe { CStr::from_ptr(name).to_string_lossy() };
+    name.rsplit(':').next().unwrap_or_default().to_owned()
+}
+
+fn rectangle_to_json(rectangle: &Rectangle) -> JsonValue {
+    json!({ "width": rectangle.width, "height": rectangle.height })
+}
+
+fn fraction_to_json(fraction: &Fraction) -> JsonValue {
+    json!({ "num": fraction.num, "denom": fraction.denom })
+}
+
+/// Convert a choice to an object labelling its values, or to its value if it is not a choice.
+fn choice_to_json<T: CanonicalFixedSizedPod>(
+    choice: &Choice<T>,
+    to_json: impl Fn(&T) -> JsonValue,
+) -> JsonValue {
+    let mut map = Map::new();
+    match &choice.1 {
+        ChoiceEnum::None(value) => return to_json(value),
+        ChoiceEnum::Range { default, min, max } => {
+            map.insert("default".to_owned(), to_json(default));
+            map.insert("min".to_owned(), to_json(min));
+            map.insert("max".to_owned(), to_json(max));
+        }
+        ChoiceEnum::Step {
+            default,
+            min,
+            max,
+            step,
+        } => {
+            map.insert("default".to_owned(), to_json(default));
+            map.insert("min".to_owned(), to_json(min));
+            map.insert("max".to_owned(), to_json(max));
+            map.insert("step".to_owned(), to_json(step));
+        }
+        ChoiceEnum::Enum {
+            default,
+            alternatives,
+        } => {
+            map.insert("default".to_owned(), to_json(default));
+            for (i, value) in alternatives.iter().enumerate() {
+                map.insert(format!("alt{}", i + 1), to_json(value));
+            }
+        }
+        ChoiceEnum::Flags { default, flags } => {
+            map.insert("default".to_owned(), to_json(default));
+            for (i, value) in flags.iter().enumerate() {
+                map.insert(format!("flag{}", i + 1), to_json(value));
+            }
+        }
+    }
+
+    JsonValue::Object(map)
+}
+
+/// The changes of the properties of an object.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PropsDiff {
+    /// The properties which were added, with their value.
+    pub added: Map<String, JsonValue>,
+    /// The properties whose value changed, with their new value.
+    pub changed: Map<String, JsonValue>,
+    /// The properties which were removed.
+    pub removed: Vec<String>,
+}
+
+impl PropsDiff {
+    /// Compute the changes from the properties `old` to `new`.
+    pub fn new(old: &Map<String, JsonValue>, new: &Map<String, JsonValue>) -> Self {
+        let mut diff = Self::default();
+        for (key, value) in new {
+            match old.get(key) {
+                None => {
+                    diff.added.insert(key.clone(), value.clone());
+                }
+                Some(old_value) if old_value != value => {
+                    diff.changed.insert(key.clone(), value.clone());
+                }
+                Some(_) => {}
+            }
+        }
+        diff.removed = old
+            .keys()
+            .filter(|key| !new.contains_key(*key))
+            .cloned()
+            .collect();
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// A change of the graph, as reported by [`GraphMonitor`] or [`GraphSnapshot::diff`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum GraphEvent {
+    /// A node was added, with the properties of its global.
+    NodeAdded {
+        id: u32,
+        props: Map<String, JsonValue>,
+    },
+    NodeRemoved {
+        id: u32,
+    },
+    /// A link was added from an output port to an input port.
+    LinkAdded {
+        id: u32,
+        output_node: u32,
+        output_port: u32,
+        input_node: u32,
+        input_port: u32,
+    },
+    LinkRemoved {
+        id: u32,
+    },
+    /// The properties of an object changed.
+    PropsChanged {
+        id: u32,
+        diff: PropsDiff,
+    },
+    /// A value of a param of an object changed.
+    ///
+    /// `index` is the index of the value among the values of the param,
+    /// and `value` is `None` if there is no value at this index anymore.
+    ParamChanged {
+        id: u32,
+        param: String,
+        index: u32,
+        value: Option<JsonValue>,
+    },
+}
+
+impl GraphSnapshot {
+    /// Compute the events turning this snapshot into the `newer` one.
+    ///
+    /// Objects are matched by id and type, so an id reused by a new object
+    /// is reported as a removal followed by an addition.
+    pub fn diff(&self, newer: &GraphSnapshot) -> Vec<GraphEvent> {
+        let index = |snapshot: &GraphSnapshot| -> HashMap<(u32, String), usize> {
+            snapshot
+                .objects
+                .iter()
+                .enumerate()
+                .map(|(i, object)| ((object.id, object.type_.clone()), i))
+                .collect()
+        };
+        let (old_index, new_index) = (index(self), index(newer));
+
+        let mut events = Vec::new();
+        for old in &self.objects {
+            if !new_index.contains_key(&(old.id, old.type_.clone())) {
+                events.extend(old.removed_event());
+            }
+        }
+        for new in &newer.objects {
+            match old_index.get(&(new.id, new.type_.clone())) {
+                Some(&i) => self.objects[i].diff_into(new, &mut events),
+                None => events.extend(new.added_event()),
+            }
+        }
+
+        events
+    }
+}
+
+impl DumpObject {
+    /// The properties of the object, from its info or its global.
+    fn object_props(&self) -> Option<&Map<String, JsonValue>> {
+        match self.info.as_ref().and_then(|info| info.get("props")) {
+            Some(JsonValue::Object(props)) => Some(props),
+            _ => self.props.as_ref(),
+        }
+    }
+
+    fn object_params(&self) -> Option<&Map<String, JsonValue>> {
+        match self.info.as_ref().and_then(|info| info.get("params")) {
+            Some(JsonValue::Object(params)) => Some(params),
+            _ => None,
+        }
+    }
+
+    fn info_id(&self, key: &str) -> u32 {
+        self.info
+            .as_ref()
+            .and_then(|info| info.get(key))
+            .and_then(JsonValue::as_u64)
+            .and_then(|id| id.try_into().ok())
+            .unwrap_or(crate::constants::ID_ANY)
+    }
+
+    fn added_event(&self) -> Option<GraphEvent> {
+        match ObjectType::from_interface_name(&self.type_) {
+            ObjectType::Node => Some(GraphEvent::NodeAdded {
+                id: self.id,
+                props: self.object_props().cloned().unwrap_or_default(),
+            }),
+            ObjectType::Link => Some(GraphEvent::LinkAdded {
+                id: self.id,
+                output_node: self.info_id("output-node-id"),
+                output_port: self.info_id("output-port-id"),
+                input_node: self.info_id("input-node-id"),
+                input_port: self.info_id("input-port-id"),
+            }),
+            _ => None,
+        }
+    }
+
+    fn removed_event(&self) -> Option<GraphEvent> {
+        match ObjectType::from_interface_name(&self.type_) {
+            ObjectType::Node => Some(GraphEvent::NodeRemoved { id: self.id }),
+            ObjectType::Link => Some(GraphEvent::LinkRemoved { id: self.id }),
+            _ => None,
+        }
+    }
+
+    /// Push the events describing the changes from this object to `new`, the same object in a newer snapshot.
+    fn diff_into(&self, new: &DumpObject, events: &mut Vec<GraphEvent>) {
+        let empty = Map::new();
+        let diff = PropsDiff::new(
+            self.object_props().unwrap_or(&empty),
+            new.object_props().unwrap_or(&empty),
+        );
+        if !diff.is_empty() {
+            events.push(GraphEvent::PropsChanged { id: new.id, diff });
+        }
+
+        let (old_params, new_params) = (
+            self.object_params().unwrap_or(&empty),
+            new.object_params().unwrap_or(&empty),
+        );
+        let values = |params: &Map<String, JsonValue>, name: &str| match params.get(name) {
+            Some(JsonValue::Array(values)) => values.clone(),
+            _ => Vec::new(),
+        };
+        let names = new_params.keys().chain(
+            old_params
+                .keys()
+                .filter(|name| !new_params.contains_key(*name)),
+        );
+        for name in names {
+            let (old_values, new_values) = (values(old_params, name), values(new_params, name));
+            for index in 0..old_values.len().max(new_values.len()) {
+                let value = new_values.get(index);
+                if old_values.get(index) != value {
+                    events.push(GraphEvent::ParamChanged {
+                        id: new.id,
+                        param: name.clone(),
+                        index: index as u32,
+                        value: value.cloned(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+type GraphEventCallback = dyn Fn(&GraphEvent);
+
+/// A node followed by a [`GraphMonitor`].
+struct MonitoredNode {
+    // Declared before the proxy so it is dropped first.
+    _listener: NodeListener,
+    node: Node,
+    /// The properties from the last info, to diff the next one against.
+    props: Option<Map<String, JsonValue>>,
+    subscribed: Vec<ParamType>,
+    params: HashMap<(String, u32), JsonValue>,
+}
+
+#[derive(Default)]
+struct MonitorState {
+    nodes: HashMap<u32, MonitoredNode>,
+    links: HashSet<u32>,
+}
+
+struct MonitorInner {
+    state: RefCell<MonitorState>,
+    callback: Box<GraphEventCallback>,
+}
+
+impl MonitorInner {
+    fn global(
+        self: &Rc<Self>,
+        registry: &Weak<Registry>,
+        obj: &GlobalObject<&spa::utils::dict::DictRef>,
+    ) {
+        let event = match obj.type_ {
+            ObjectType::Node => {
+                let Some(registry) = registry.upgrade() else {
+                    return;
+                };
+                let Ok(node) = registry.bind::<Node, _>(obj) else {
+                    return;
+                };
+
+                let id = obj.id;
+                let (inner_info, inner_param) = (Rc::downgrade(self), Rc::downgrade(self));
+                let listener = node
+                    .add_listener_local()
+                    .info(move |info| {
+                        if let Some(inner) = inner_info.upgrade() {
+                            inner.node_info(id, info);
+                        }
+                    })
+                    .param(move |_, param_id, index, _, param| {
+                        if let Some(inner) = inner_param.upgrade() {
+                            inner.node_param(id, param_id, index, param);
+                        }
+                    })
+                    .register();
+
+                self.state.borrow_mut().nodes.insert(
+                    id,
+                    MonitoredNode {
+                        _listener: listener,
+                        node,
+                        props: None,
+                        subscribed: Vec::new(),
+                        params: HashMap::new(),
+                    },
+                );
+
+                GraphEvent::NodeAdded {
+                    id,
+                    props: dict_to_json(obj.props),
+                }
+            }
+            ObjectType::Link => {
+                let link_id = |key: &str| {
+                    obj.props
+                        .and_then(|props| props.get(key))
+                        .and_then(|id| id.parse().ok())
+                        .unwrap_or(crate::constants::ID_ANY)
+                };
+
+                self.state.borrow_mut().links.insert(obj.id);
+                GraphEvent::LinkAdded {
+                    id: obj.id,
+                    output_node: link_id(*crate::keys::LINK_OUTPUT_NODE),
+                    output_port: link_id(*crate::keys::LINK_OUTPUT_PORT),
+                    input_node: link_id(*crate::keys::LINK_INPUT_NODE),
+                    input_port: link_id(*crate::keys::LINK_INPUT_PORT),
+                }
+            }
+            _ => return,
+        };
+
+        (self.callback)(&event);
+    }
+
+    fn global_remove(&self, id: u32) {
+        let event = {
+            let mut state = self.state.borrow_mut();
+            if state.nodes.remove(&id).is_some() {
+                GraphEvent::NodeRemoved { id }
+            } else if state.links.remove(&id) {
+                GraphEvent::LinkRemoved { id }
+            } else {
+                return;
+            }
+        };
+
+        (self.callback)(&event);
+    }
+
+    fn node_info(&self, id: u32, info: &NodeInfoRef) {
+        let event = {
+            let mut state = self.state.borrow_mut();
+            let Some(node) = state.nodes.get_mut(&id) else {
+                return;
+            };
+
+            // Param events are emitted for the subscribed params whenever they change.
+            let readable: Vec<ParamType> = info
+                .params()
+                .iter()
+                .filter(|param| param.flags().contains(ParamInfoFlags::READ))
+                .map(ParamInfo::id)
+                .collect();
+            if readable != node.subscribed && node.node.subscribe_params(&readable).is_ok() {
+                node.subscribed = readable;
+            }
+
+            let props = dict_to_json(info.props());
+            let diff = node.props.as_ref().map(|old| PropsDiff::new(old, &props));
+            node.props = Some(props);
+
+            match diff {
+                Some(diff) if !diff.is_empty() => GraphEvent::PropsChanged { id, diff },
+                _ => return,
+            }
+        };
+
+        (self.callback)(&event);
+    }
+
+    fn node_param(&self, id: u32, param_id: ParamType, index: u32, param: Option<&Pod>) {
+        let event = {
+            let mut state = self.state.borrow_mut();
+            let Some(node) = state.nodes.get_mut(&id) else {
+                return;
+            };
+
+            let name = param_name(param_id);
+            let value = param.map(pod_to_json);
+            let key = (name.clone(), index);
+            let old = match &value {
+                Some(value) => node.params.insert(key, value.clone()),
+                None => node.params.remove(&key),
+            };
+            if old == value {
+                return;
+            }
+
+            GraphEvent::ParamChanged {
+                id,
+                param: name,
+                index,
+                value,
+            }
+        };
+
+        (self.callback)(&event);
+    }
+}
+
+/// Follows the nodes and links of the graph, reporting their changes as [`GraphEvent`]s.
+///
+/// Nodes are bound to follow their properties and params. The current values of the params
+/// of a node are reported with [`GraphEvent::ParamChanged`] after it was added.
+///
+/// The monitor stops following changes when it is dropped.
+pub struct GraphMonitor {
+    inner: Rc<MonitorInner>,
+    _listener: registry::Listener,
+}
+
+impl GraphMonitor {
+    /// Start monitoring the graph using `registry`, calling `event` for each change.
+    pub fn new<F>(registry: &Rc<Registry>, event: F) -> Self
+    where
+        F: Fn(&GraphEvent) + 'static,
+    {
+        let inner = Rc::new(MonitorInner {
+            state: RefCell::new(MonitorState::default()),
+            callback: Box::new(event),
+        });
+
+        let registry_weak = Rc::downgrade(registry);
+        let inner_global = Rc::downgrade(&inner);
+        let inner_global_remove = Rc::downgrade(&inner);
+
+        let listener = registry
+            .add_listener_local()
+            .global(move |obj| {
+                if let Some(inner) = inner_global.upgrade() {
+                    inner.global(&registry_weak, obj);
+                }
+            })
+            .global_remove(move |id| {
+                if let Some(inner) = inner_global_remove.upgrade() {
+                    inner.global_remove(id);
+                }
+            })
+            .register();
+
+        Self {
+            inner,
+            _listener: listener,
+        }
+    }
+
+    /// The ids of the nodes currently known to the monitor.
+    pub fn nodes(&self) -> Vec<u32> {
+        self.inner.state.borrow().nodes.keys().copied().collect()
+    }
+
+    /// The ids of the links currently known to the monitor.
+    pub fn links(&self) -> Vec<u32> {
+        self.inner.state.borrow().links.iter().copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spa::{
+        param::format::{FormatProperties, MediaSubtype, MediaType},
+        pod::{Object, Property},
+        utils::{ChoiceFlags, SpaTypes},
+    };
+
+    #[test]
+    fn prop_values() {
+        assert_eq!(prop_value_to_json("42"), json!(42));
+        assert_eq!(prop_value_to_json("0.5"), json!(0.5));
+        assert_eq!(prop_value_to_json("true"), json!(true));
+        assert_eq!(prop_value_to_json("inf"), json!("inf"));
+        assert_eq!(prop_value_to_json("alsa_output"), json!("alsa_output"));
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(
+            permissions_to_json(PermissionFlags::R | PermissionFlags::X),
+            ["r", "x"]
+        );
+        assert_eq!(
+            change_mask_to_json(
+                (crate::node::NodeChangeMask::INPUT_PORTS | crate::node::NodeChangeMask::PROPS)
+                    .iter_names()
+            ),
+            json!(["input-ports", "props"])
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn format_object() {
+        let value = Value::Object(Object {
+            type_: SpaTypes::ObjectParamFormat.as_raw(),
+            id: ParamType::EnumFormat.as_raw(),
+            properties: vec![
+                Property::new(
+                    FormatProperties::MediaType.as_raw(),
+                    Value::Id(Id(MediaType::Audio.as_raw())),
+                ),
+                Property::new(
+                    FormatProperties::MediaSubtype.as_raw(),
+                    Value::Id(Id(MediaSubtype::Raw.as_raw())),
+                ),
+                Property::new(
+                    FormatProperties::AudioRate.as_raw(),
+                    Value::Choice(ChoiceValue::Int(Choice(
+                        ChoiceFlags::empty(),
+                        ChoiceEnum::Range {
+                            default: 48000,
+                            min: 1,
+                            max: 384000,
+                        },
+                    ))),
+                ),
+            ],
+        });
+
+        assert_eq!(
+            value_to_json(&value, ptr::null()),
+            json!({
+                "mediaType": "audio",
+                "mediaSubtype": "raw",
+                "rate": { "default": 48000, "min": 1, "max": 384000 },
+            })
+        );
+        assert_eq!(param_name(ParamType::EnumFormat), "EnumFormat");
+    }
+
+    #[test]
+    fn serialize() {
+        let snapshot = GraphSnapshot {
+            objects: vec![DumpObject {
+                id: 0,
+                type_: "PipeWire:Interface:Metadata".to_owned(),
+                version: 3,
+                permissions: vec!["r".to_owned()],
+                info: None,
+                props: Some(Map::new()),
+                metadata: Some(vec![MetadataEntry {
+                    subject: 0,
+                    key: "default.audio.sink".to_owned(),
+                    type_: Some("Spa:String:JSON".to_owned()),
+                    value: json!({ "name": "sink" }),
+                }]),
+            }],
+        };
+
+        let json = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(
+            json,
+            json!([{
+                "id": 0,
+                "type": "PipeWire:Interface:Metadata",
+                "version": 3,
+                "permissions": ["r"],
+                "props": {},
+                "metadata": [{
+                    "subject": 0,
+                    "key": "default.audio.sink",
+                    "type": "Spa:String:JSON",
+                    "value": { "name": "sink" },
+                }],
+            }])
+        );
+        assert_eq!(
+            serde_json::from_value::<GraphSnapshot>(json).unwrap(),
+            snapshot
+        );
+    }
+
+    fn node(id: u32, volume: f64, params: JsonValue) -> DumpObject {
+        DumpObject {
+            id,
+            type_: "PipeWire:Interface:Node".to_owned(),
+            version: 3,
+            permissions: vec!["r".to_owned()],
+            info: Some(
+                json!({ "props": { "node.name": "sink", "volume": volume }, "params": params })
+                    .as_object()
+                    .unwrap()
+                    .clone(),
+            ),
+            props: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn props_diff() {
+        let old = json!({ "a": 1, "b": "x", "c": true });
+        let new = json!({ "a": 2, "b": "x", "d": null });
+        let diff = PropsDiff::new(old.as_object().unwrap(), new.as_object().unwrap());
+
+        assert_eq!(diff.added, *json!({ "d": null }).as_object().unwrap());
+        assert_eq!(diff.changed, *json!({ "a": 2 }).as_object().unwrap());
+        assert_eq!(diff.removed, ["c"]);
+        assert!(PropsDiff::new(&diff.added, &diff.added).is_empty());
+    }
+
+    #[test]
+    fn snapshot_diff() {
+        let old = GraphSnapshot {
+            objects: vec![
+                node(30, 1.0, json!({ "Props": [{ "mute": false }] })),
+                node(31, 1.0, json!({})),
+            ],
+        };
+        let mut link = node(40, 1.0, json!({}));
+        link.type_ = "PipeWire:Interface:Link".to_owned();
+        link.info = Some(
+            json!({ "output-node-id": 30, "output-port-id": 32, "input-node-id": 33, "input-port-id": 34 })
+                .as_object()
+                .unwrap()
+                .clone(),
+        );
+        let new = GraphSnapshot {
+            objects: vec![
+                node(
+                    30,
+                    0.5,
+                    json!({ "Props": [{ "mute": true }, { "channel": 1 }] }),
+                ),
+                link,
+            ],
+        };
+
+        let mut changed = Map::new();
+        changed.insert("volume".to_owned(), json!(0.5));
+        assert_eq!(
+            old.diff(&new),
+            [
+                GraphEvent::NodeRemoved { id: 31 },
+                GraphEvent::PropsChanged {
+                    id: 30,
+                    diff: PropsDiff {
+                        changed,
+                        ..Default::default()
+                    }
+                },
+                GraphEvent::ParamChanged {
+                    id: 30,
+                    param: "Props".to_owned(),
+                    index: 0,
+                    value: Some(json!({ "mute": true })),
+                },
+                GraphEvent::ParamChanged {
+                    id: 30,
+                    param: "Props".to_owned(),
+                    index: 1,
+                    value: Some(json!({ "channel": 1 })),
+                },
+                GraphEvent::LinkAdded {
+                    id: 40,
+                    output_node: 30,
+                    output_port: 32,
+                    input_node: 33,
+                    input_port: 34,
+                },
+            ]
+        );
+        assert!(new.diff(&new).is_empty());
+    }
+}
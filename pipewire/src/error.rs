@@ -1,8 +1,16 @@
 // Copyright The pipewire-rs Contributors.
 // SPDX-License-Identifier: MIT
 
+use nix::errno::Errno;
+use spa::utils::result::AsyncSeq;
 use thiserror::Error;
+
+/// The error type returned by the fallible methods of this crate.
+///
+/// Errors can be wrapped with the operation that was being performed when they happened,
+/// use [`Error::without_context`] to get to the underlying error.
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum Error {
     #[error("Creation failed")]
     CreationFailed,
@@ -12,4 +20,139 @@ pub enum Error {
     WrongProxyType,
     #[error(transparent)]
     SpaError(#[from] spa::utils::result::Error),
+    /// An error reported by the server through the [`error`](crate::core::ListenerLocalBuilder::error)
+    /// event of the core.
+    #[error("Server error on object {id} (seq {seq}): {message} ({errno})")]
+    Server {
+        /// The id of the object the error is about.
+        id: u32,
+        /// The sequence number of the method that caused the error.
+        seq: i32,
+        errno: Errno,
+        message: String,
+    },
+    /// An operation failed because of `source`.
+    #[error("Failed to {operation}")]
+    Context {
+        operation: &'static str,
+        #[source]
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Create an error from the arguments of the [`error`](crate::core::ListenerLocalBuilder::error)
+    /// event of the core.
+    pub fn from_core_error(id: u32, seq: i32, res: i32, message: &str) -> Self {
+        Self::Server {
+            id,
+            seq,
+            errno: Errno::from_i32(-res),
+            message: message.to_owned(),
+        }
+    }
+
+    /// Wrap the error with the operation that failed because of it.
+    pub fn context(self, operation: &'static str) -> Self {
+        Self::Context {
+            operation,
+            source: Box::new(self),
+        }
+    }
+
+    /// The error without the operations it is wrapped with.
+    pub fn without_context(&self) -> &Self {
+        match self {
+            Self::Context { source, .. } => source.without_context(),
+            _ => self,
+        }
+    }
+
+    /// The operation that failed, if the error has been given a context.
+    pub fn operation(&self) -> Option<&'static str> {
+        match self {
+            Self::Context { operation, .. } => Some(operation),
+            _ => None,
+        }
+    }
+
+    /// The errno describing the error, if any.
+    pub fn errno(&self) -> Option<Errno> {
+        match self.without_context() {
+            Self::NoMemory => Some(Errno::ENOMEM),
+            Self::SpaError(e) => Some(e.errno()),
+            Self::Server { errno, .. } => Some(*errno),
+            _ => None,
+        }
+    }
+
+    /// The sequence number of the asynchronous method the error is about, if any.
+    ///
+    /// This can be compared to the [`AsyncSeq`] returned by the method.
+    pub fn seq(&self) -> Option<AsyncSeq> {
+        match self.without_context() {
+            Self::Server { seq, .. } => Some(AsyncSeq::from_seq(*seq)),
+            _ => None,
+        }
+    }
+
+    /// The message sent by the server, if the error was reported by the server.
+    pub fn server_message(&self) -> Option<&str> {
+        match self.without_context() {
+            Self::Server { message, .. } => Some(message),
+            _ => None,
+        }
+    }
+}
+
+/// Extension trait to give a context to the errors of a [`Result`].
+pub(crate) trait ResultExt<T> {
+    fn context(self, operation: &'static str) -> Result<T, Error>;
+}
+
+impl<T, E: Into<Error>> ResultExt<T> for Result<T, E> {
+    fn context(self, operation: &'static str) -> Result<T, Error> {
+        self.map_err(|e| e.into().context(operation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use spa::utils::result::SpaResult;
+
+    use super::*;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn context() {
+        let res: Result<i32, _> = SpaResult::from_c(-libc::EBUSY).into_sync_result();
+        let err = res.context("connect the stream").unwrap_err();
+
+        assert_eq!(err.to_string(), "Failed to connect the stream");
+        assert_eq!(err.operation(), Some("connect the stream"));
+        assert_eq!(err.errno(), Some(Errno::EBUSY));
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            "EBUSY: Device or resource busy"
+        );
+        assert!(matches!(err.without_context(), Error::SpaError(_)));
+        assert_eq!(err.seq(), None);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn server() {
+        let err = Error::from_core_error(42, 7, -libc::ENOENT, "unknown object")
+            .context("create the link");
+
+        assert_eq!(err.errno(), Some(Errno::ENOENT));
+        assert_eq!(err.seq(), Some(AsyncSeq::from_seq(7)));
+        assert_eq!(err.server_message(), Some("unknown object"));
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            "Server error on object 42 (seq 7): unknown object (ENOENT: No such file or directory)"
+        );
+    }
 }
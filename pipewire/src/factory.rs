@@ -4,11 +4,11 @@
 use bitflags::bitflags;
 use libc::c_void;
 use std::ops::Deref;
-use std::pin::Pin;
 use std::{ffi::CStr, ptr};
 use std::{fmt, mem};
 
 use crate::{
+    listeners,
     proxy::{Listener, Proxy, ProxyT},
     types::ObjectType,
 };
@@ -78,8 +78,12 @@ impl FactoryInfoRef {
         self.0.id
     }
 
+    pub fn name(&self) -> &str {
+        unsafe { CStr::from_ptr(self.0.name).to_str().unwrap() }
+    }
+
     pub fn type_(&self) -> ObjectType {
-        ObjectType::from_str(unsafe { CStr::from_ptr(self.0.type_).to_str().unwrap() })
+        ObjectType::from_interface_name(unsafe { CStr::from_ptr(self.0.type_).to_str().unwrap() })
     }
 
     pub fn version(&self) -> u32 {
@@ -100,6 +104,7 @@ impl fmt::Debug for FactoryInfoRef {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("FactoryInfoRef")
             .field("id", &self.id())
+            .field("name", &self.name())
             .field("type", &self.type_())
             .field("version", &self.version())
             .field("change_mask", &self.change_mask())
@@ -168,22 +173,11 @@ impl fmt::Debug for FactoryInfo {
 }
 
 pub struct FactoryListener {
-    // Need to stay allocated while the listener is registered
-    #[allow(dead_code)]
-    events: Pin<Box<pw_sys::pw_factory_events>>,
-    listener: Pin<Box<spa_sys::spa_hook>>,
-    #[allow(dead_code)]
-    data: Box<ListenerLocalCallbacks>,
+    _listener: listeners::Listener<pw_sys::pw_factory_events, ListenerLocalCallbacks>,
 }
 
 impl Listener for FactoryListener {}
 
-impl Drop for FactoryListener {
-    fn drop(&mut self) {
-        spa::utils::hook::remove(*self.listener);
-    }
-}
-
 impl<'a> FactoryListenerLocalBuilder<'a> {
     #[must_use]
     pub fn info<F>(mut self, info: F) -> Self
@@ -200,15 +194,17 @@ impl<'a> FactoryListenerLocalBuilder<'a> {
             data: *mut c_void,
             info: *const pw_sys::pw_factory_info,
         ) {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-            let info =
-                ptr::NonNull::new(info as *mut pw_sys::pw_factory_info).expect("info is NULL");
-            let info = info.cast::<FactoryInfoRef>().as_ref();
-            callbacks.info.as_ref().unwrap()(info);
+            crate::unwind::guard("factory_events_info", || {
+                let callbacks = listeners::callback_data::<ListenerLocalCallbacks>(data);
+                let info =
+                    ptr::NonNull::new(info as *mut pw_sys::pw_factory_info).expect("info is NULL");
+                let info = info.cast::<FactoryInfoRef>().as_ref();
+                callbacks.info.as_ref().unwrap()(info);
+            })
         }
 
         let e = unsafe {
-            let mut e: Pin<Box<pw_sys::pw_factory_events>> = Box::pin(mem::zeroed());
+            let mut e: pw_sys::pw_factory_events = mem::zeroed();
             e.version = pw_sys::PW_VERSION_FACTORY_EVENTS;
 
             if self.cbs.info.is_some() {
@@ -218,29 +214,22 @@ impl<'a> FactoryListenerLocalBuilder<'a> {
             e
         };
 
-        let (listener, data) = unsafe {
-            let factory = &self.factory.proxy.as_ptr();
-
-            let data = Box::into_raw(Box::new(self.cbs));
-            let mut listener: Pin<Box<spa_sys::spa_hook>> = Box::pin(mem::zeroed());
-            let listener_ptr: *mut spa_sys::spa_hook = listener.as_mut().get_unchecked_mut();
-
-            spa_interface_call_method!(
-                factory,
-                pw_sys::pw_factory_methods,
-                add_listener,
-                listener_ptr.cast(),
-                e.as_ref().get_ref(),
-                data as *mut _
-            );
-
-            (listener, Box::from_raw(data))
+        let factory = self.factory.proxy.as_ptr();
+        let listener = unsafe {
+            listeners::Listener::new(e, self.cbs, |hook, events, data| {
+                spa_interface_call_method!(
+                    factory,
+                    pw_sys::pw_factory_methods,
+                    add_listener,
+                    hook.cast(),
+                    events,
+                    data
+                );
+            })
         };
 
         FactoryListener {
-            events: e,
-            listener,
-            data,
+            _listener: listener,
         }
     }
 }
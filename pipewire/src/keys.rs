@@ -279,6 +279,14 @@ key_constant!(NODE_NETWORK, PW_KEY_NODE_NETWORK,
 key_constant!(NODE_TRIGGER, PW_KEY_NODE_TRIGGER,
     /// the node is not scheduled automatically based on the dependencies in the graph but it will be triggered explicitly.
 );
+#[cfg(feature = "v1_2_0")]
+key_constant!(NODE_SUPPORTS_LAZY, PW_KEY_NODE_SUPPORTS_LAZY,
+    /// the node can be a lazy driver. It will listen to RequestProcess commands and take them into account when deciding to start the graph. A value of 0 disables support, a value of > 0 enables with increasing preference.
+);
+#[cfg(feature = "v1_2_0")]
+key_constant!(NODE_SUPPORTS_REQUEST, PW_KEY_NODE_SUPPORTS_REQUEST,
+    /// The node supports emiting RequestProcess events when it wants the graph to be scheduled. A value of 0 disables support, a value of > 0 enables with increasing preference.
+);
 #[cfg(feature = "v0_3_64")]
 key_constant!(NODE_CHANNELNAMES, PW_KEY_NODE_CHANNELNAMES,
     /// names of node's channels (unrelated to positions)
@@ -533,6 +541,9 @@ key_constant!(VIDEO_SIZE, PW_KEY_VIDEO_SIZE,
 key_constant!(TARGET_OBJECT, PW_KEY_TARGET_OBJECT,
     /// a target object to link to. This can be and object name or object.serial PIPEWIRE_KEYS_H
 );
+key_constant!(METADATA_NAME, PW_KEY_METADATA_NAME,
+    /// the name of a metadata object. Ex: "default"
+);
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,518 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Measure the latency of the path between two nodes of the graph.
+//!
+//! [`LatencyMeter`] follows the links of the graph to find the path from a source node to a sink
+//! node, and adds up the latency of the nodes on it:
+//! - the latency reported by the source in the [`Direction::Output`] direction, which is the
+//!   capture latency of a device,
+//! - the processing latency of the nodes between the source and the sink,
+//! - the latency reported by the sink in the [`Direction::Input`] direction, which is the
+//!   playback latency of a device.
+//!
+//! The latency is converted to samples and nanoseconds with the clock configured in
+//! the `settings` metadata.
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, VecDeque},
+    rc::{Rc, Weak},
+};
+
+use spa::{
+    param::{
+        latency::{LatencyInfo, ProcessLatencyInfo},
+        ParamType,
+    },
+    pod::Pod,
+    utils::Direction,
+};
+
+use crate::{
+    core::PW_ID_CORE,
+    metadata::{Metadata, MetadataListener},
+    node::{Node, NodeListener},
+    registry::{self, GlobalObject, Registry},
+    types::ObjectType,
+};
+
+/// The rate and quantum of the graph, used to convert latencies to samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GraphClock {
+    /// The sample rate in Hz.
+    pub rate: u32,
+    /// The number of samples processed in one cycle.
+    pub quantum: u32,
+}
+
+impl Default for GraphClock {
+    /// The default clock of PipeWire, 1024 samples at 48kHz.
+    fn default() -> Self {
+        Self {
+            rate: 48000,
+            quantum: 1024,
+        }
+    }
+}
+
+impl GraphClock {
+    /// Convert a latency made of a multiple of the quantum, a number of samples and a
+    /// number of nanoseconds to samples.
+    pub fn latency_to_samples(&self, quantum: f32, samples: u64, ns: u64) -> u64 {
+        let quantum = (f64::from(quantum) * f64::from(self.quantum)).round() as u64;
+        quantum + samples + ns * u64::from(self.rate) / 1_000_000_000
+    }
+
+    /// Convert a number of samples to nanoseconds.
+    pub fn samples_to_ns(&self, samples: u64) -> u64 {
+        samples * 1_000_000_000 / u64::from(self.rate.max(1))
+    }
+}
+
+/// The latency of the path between two nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathLatency {
+    /// The ids of the nodes on the path, from the source to the sink.
+    pub nodes: Vec<u32>,
+    pub min_samples: u64,
+    pub max_samples: u64,
+    pub min_ns: u64,
+    pub max_ns: u64,
+    /// The clock used to compute the latency.
+    pub clock: GraphClock,
+}
+
+/// The latency params reported by a node.
+#[derive(Debug, Default, Clone, Copy)]
+struct NodeLatency {
+    output: Option<LatencyInfo>,
+    input: Option<LatencyInfo>,
+    process: Option<ProcessLatencyInfo>,
+}
+
+/// Find the shortest path following the links from `source` to `sink`.
+///
+/// `links` maps link ids to their output and input nodes.
+fn find_path(links: &BTreeMap<u32, (u32, u32)>, source: u32, sink: u32) -> Option<Vec<u32>> {
+    let mut previous = HashMap::new();
+    let mut queue = VecDeque::from([source]);
+
+    while let Some(node) = queue.pop_front() {
+        if node == sink {
+            let mut path = vec![sink];
+            while let Some(&node) = previous.get(path.last().unwrap()) {
+                path.push(node);
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        for &(output, input) in links.values() {
+            if output == node && input != source && !previous.contains_key(&input) {
+                previous.insert(input, node);
+                queue.push_back(input);
+            }
+        }
+    }
+
+    None
+}
+
+/// The sum of the minimum and maximum latencies of nodes.
+#[derive(Default)]
+struct LatencySum {
+    quantum: (f32, f32),
+    samples: (u64, u64),
+    ns: (u64, u64),
+}
+
+impl LatencySum {
+    fn add_latency(&mut self, latency: &LatencyInfo) {
+        self.quantum.0 += latency.min_quantum();
+        self.quantum.1 += latency.max_quantum();
+        self.samples.0 += u64::from(latency.min_rate());
+        self.samples.1 += u64::from(latency.max_rate());
+        self.ns.0 += latency.min_ns();
+        self.ns.1 += latency.max_ns();
+    }
+
+    fn add_process_latency(&mut self, latency: &ProcessLatencyInfo) {
+        self.quantum.0 += latency.quantum();
+        self.quantum.1 += latency.quantum();
+        self.samples.0 += u64::from(latency.rate());
+        self.samples.1 += u64::from(latency.rate());
+        self.ns.0 += latency.ns();
+        self.ns.1 += latency.ns();
+    }
+}
+
+/// Add up the latency of the nodes on `path`.
+fn path_latency(
+    path: Vec<u32>,
+    latencies: &HashMap<u32, NodeLatency>,
+    clock: GraphClock,
+) -> PathLatency {
+    let mut sum = LatencySum::default();
+
+    let last = path.len() - 1;
+    for (i, id) in path.iter().enumerate() {
+        let latency = latencies.get(id).copied().unwrap_or_default();
+        if i == 0 {
+            if let Some(output) = &latency.output {
+                sum.add_latency(output);
+            }
+        }
+        if i == last {
+            if let Some(input) = &latency.input {
+                sum.add_latency(input);
+            }
+        }
+        if i != 0 && i != last {
+            if let Some(process) = &latency.process {
+                sum.add_process_latency(process);
+            }
+        }
+    }
+
+    let min_samples = clock.latency_to_samples(sum.quantum.0, sum.samples.0, sum.ns.0);
+    let max_samples = clock.latency_to_samples(sum.quantum.1, sum.samples.1, sum.ns.1);
+
+    PathLatency {
+        nodes: path,
+        min_samples,
+        max_samples,
+        min_ns: clock.samples_to_ns(min_samples),
+        max_ns: clock.samples_to_ns(max_samples),
+        clock,
+    }
+}
+
+#[derive(Default)]
+struct State {
+    // Declared before the proxies so they are dropped first.
+    node_listeners: HashMap<u32, NodeListener>,
+    nodes: HashMap<u32, Node>,
+    latencies: HashMap<u32, NodeLatency>,
+    links: BTreeMap<u32, (u32, u32)>,
+    settings: Option<(u32, MetadataListener, Metadata)>,
+    clock_settings: HashMap<String, u32>,
+    latency: Option<PathLatency>,
+}
+
+impl State {
+    fn clock(&self) -> GraphClock {
+        let setting = |key: &str| self.clock_settings.get(key).copied().filter(|v| *v > 0);
+        let default = GraphClock::default();
+
+        GraphClock {
+            rate: setting("clock.force-rate")
+                .or_else(|| setting("clock.rate"))
+                .unwrap_or(default.rate),
+            quantum: setting("clock.force-quantum")
+                .or_else(|| setting("clock.quantum"))
+                .unwrap_or(default.quantum),
+        }
+    }
+}
+
+type LatencyChangedCallback = dyn Fn(Option<&PathLatency>);
+
+struct Inner {
+    source: u32,
+    sink: u32,
+    state: RefCell<State>,
+    callback: Box<LatencyChangedCallback>,
+}
+
+impl Inner {
+    /// Apply `update` to the state, then notify if the latency of the path changed.
+    fn update(&self, update: impl FnOnce(&mut State)) {
+        let latency = {
+            let mut state = self.state.borrow_mut();
+            update(&mut state);
+
+            let latency = find_path(&state.links, self.source, self.sink)
+                .map(|path| path_latency(path, &state.latencies, state.clock()));
+            if latency == state.latency {
+                return;
+            }
+            state.latency = latency.clone();
+            latency
+        };
+
+        (self.callback)(latency.as_ref());
+    }
+
+    fn global(
+        self: &Rc<Self>,
+        registry: &Weak<Registry>,
+        obj: &GlobalObject<&spa::utils::dict::DictRef>,
+    ) {
+        match obj.type_ {
+            ObjectType::Node => {
+                let Some(registry) = registry.upgrade() else {
+                    return;
+                };
+                let Ok(node) = registry.bind::<Node, _>(obj) else {
+                    return;
+                };
+
+                let id = obj.id;
+                let inner = Rc::downgrade(self);
+                let listener = node
+                    .add_listener_local()
+                    .param(move |_, param_type, _, _, param| {
+                        if let Some(inner) = inner.upgrade() {
+                            inner.node_param(id, param_type, param);
+                        }
+                    })
+                    .register();
+                if node
+                    .subscribe_params(&[ParamType::Latency, ParamType::ProcessLatency])
+                    .is_err()
+                {
+                    return;
+                }
+
+                let mut state = self.state.borrow_mut();
+                state.node_listeners.insert(id, listener);
+                state.nodes.insert(id, node);
+            }
+            ObjectType::Link => {
+                let Some(props) = obj.props else {
+                    return;
+                };
+                let node = |key: &str| props.get(key).and_then(|id| id.parse::<u32>().ok());
+                let (Some(output), Some(input)) = (
+                    node(*crate::keys::LINK_OUTPUT_NODE),
+                    node(*crate::keys::LINK_INPUT_NODE),
+                ) else {
+                    return;
+                };
+
+                self.update(|state| {
+                    state.links.insert(obj.id, (output, input));
+                });
+            }
+            ObjectType::Metadata => {
+                if obj
+                    .props
+                    .and_then(|props| props.get(*crate::keys::METADATA_NAME))
+                    != Some("settings")
+                    || self.state.borrow().settings.is_some()
+                {
+                    return;
+                }
+                let Some(registry) = registry.upgrade() else {
+                    return;
+                };
+                let Ok(metadata) = registry.bind::<Metadata, _>(obj) else {
+                    return;
+                };
+
+                let inner = Rc::downgrade(self);
+                let listener = metadata
+                    .add_listener_local()
+                    .property(move |subject, key, _type, value| {
+                        if let Some(inner) = inner.upgrade() {
+                            inner.setting(subject, key, value);
+                        }
+                        0
+                    })
+                    .register();
+
+                self.state.borrow_mut().settings = Some((obj.id, listener, metadata));
+            }
+            _ => {}
+        }
+    }
+
+    fn global_remove(&self, id: u32) {
+        self.update(|state| {
+            state.node_listeners.remove(&id);
+            state.nodes.remove(&id);
+            state.latencies.remove(&id);
+            state.links.remove(&id);
+            if matches!(state.settings, Some((settings_id, ..)) if settings_id == id) {
+                state.settings = None;
+                state.clock_settings.clear();
+            }
+        });
+    }
+
+    fn node_param(&self, id: u32, param_type: ParamType, param: Option<&Pod>) {
+        let Some(param) = param else {
+            return;
+        };
+
+        self.update(|state| {
+            let latency = state.latencies.entry(id).or_default();
+            if param_type == ParamType::Latency {
+                if let Ok(info) = LatencyInfo::parse(param) {
+                    match info.direction() {
+                        Some(Direction::Output) => latency.output = Some(info),
+                        Some(Direction::Input) => latency.input = Some(info),
+                        None => {}
+                    }
+                }
+            } else if param_type == ParamType::ProcessLatency {
+                latency.process = ProcessLatencyInfo::parse(param).ok();
+            }
+        });
+    }
+
+    fn setting(&self, subject: u32, key: Option<&str>, value: Option<&str>) {
+        if subject != PW_ID_CORE {
+            return;
+        }
+
+        self.update(|state| match key {
+            // All properties were removed
+            None => state.clock_settings.clear(),
+            Some(key) if key.starts_with("clock.") => {
+                match value.and_then(|value| value.parse().ok()) {
+                    Some(value) => {
+                        state.clock_settings.insert(key.to_owned(), value);
+                    }
+                    None => {
+                        state.clock_settings.remove(key);
+                    }
+                }
+            }
+            Some(_) => {}
+        });
+    }
+}
+
+/// Measures the latency of the path between a source and a sink node.
+///
+/// The meter binds the nodes announced by the registry to follow their latency params,
+/// and uses the links announced by the registry to find the path between the nodes.
+///
+/// The meter stops following changes when it is dropped.
+pub struct LatencyMeter {
+    inner: Rc<Inner>,
+    _listener: registry::Listener,
+}
+
+impl LatencyMeter {
+    /// Start measuring the latency from the node `source` to the node `sink` using `registry`.
+    ///
+    /// `changed` is called whenever the latency changes, with `None` if there is no path
+    /// between the nodes.
+    pub fn new<F>(registry: &Rc<Registry>, source: u32, sink: u32, changed: F) -> Self
+    where
+        F: Fn(Option<&PathLatency>) + 'static,
+    {
+        let inner = Rc::new(Inner {
+            source,
+            sink,
+            state: RefCell::new(State::default()),
+            callback: Box::new(changed),
+        });
+
+        let registry_weak = Rc::downgrade(registry);
+        let inner_global = Rc::downgrade(&inner);
+        let inner_global_remove = Rc::downgrade(&inner);
+
+        let listener = registry
+            .add_listener_local()
+            .global(move |obj| {
+                if let Some(inner) = inner_global.upgrade() {
+                    inner.global(&registry_weak, obj);
+                }
+            })
+            .global_remove(move |id| {
+                if let Some(inner) = inner_global_remove.upgrade() {
+                    inner.global_remove(id);
+                }
+            })
+            .register();
+
+        Self {
+            inner,
+            _listener: listener,
+        }
+    }
+
+    /// Get the current latency of the path, if there is one.
+    pub fn latency(&self) -> Option<PathLatency> {
+        self.inner.state.borrow().latency.clone()
+    }
+
+    /// Get the clock used to convert latencies to samples.
+    pub fn clock(&self) -> GraphClock {
+        self.inner.state.borrow().clock()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock() {
+        let clock = GraphClock {
+            rate: 48000,
+            quantum: 256,
+        };
+        assert_eq!(clock.latency_to_samples(1.5, 10, 1_000_000), 384 + 10 + 48);
+        assert_eq!(clock.samples_to_ns(48), 1_000_000);
+    }
+
+    #[test]
+    fn path() {
+        let links = BTreeMap::from([(10, (1, 2)), (11, (2, 3)), (12, (1, 4)), (13, (4, 5))]);
+
+        assert_eq!(find_path(&links, 1, 3), Some(vec![1, 2, 3]));
+        assert_eq!(find_path(&links, 1, 5), Some(vec![1, 4, 5]));
+        assert_eq!(find_path(&links, 1, 1), Some(vec![1]));
+        assert_eq!(find_path(&links, 3, 1), None);
+    }
+
+    #[test]
+    fn latency() {
+        let mut source = LatencyInfo::new(Direction::Output);
+        source.set_min_rate(64);
+        source.set_max_rate(128);
+        let mut process = ProcessLatencyInfo::new();
+        process.set_quantum(1.0);
+        let mut sink = LatencyInfo::new(Direction::Input);
+        sink.set_min_ns(1_000_000);
+        sink.set_max_ns(2_000_000);
+
+        let latencies = HashMap::from([
+            (
+                1,
+                NodeLatency {
+                    output: Some(source),
+                    ..Default::default()
+                },
+            ),
+            (
+                2,
+                NodeLatency {
+                    process: Some(process),
+                    ..Default::default()
+                },
+            ),
+            (
+                3,
+                NodeLatency {
+                    input: Some(sink),
+                    ..Default::default()
+                },
+            ),
+        ]);
+
+        let clock = GraphClock {
+            rate: 48000,
+            quantum: 1024,
+        };
+        let latency = path_latency(vec![1, 2, 3], &latencies, clock);
+        assert_eq!(latency.min_samples, 64 + 1024 + 48);
+        assert_eq!(latency.max_samples, 128 + 1024 + 96);
+        assert_eq!(latency.min_ns, clock.samples_to_ns(64 + 1024 + 48));
+    }
+}
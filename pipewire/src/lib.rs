@@ -96,6 +96,9 @@
 //!
 //! Using similar methods, you can also react to IO or Signals, or call a callback whenever the loop is idle.
 //!
+//! A panic in a callback aborts the process by default, as it cannot unwind through the loop;
+//! see the [`unwind`](`crate::unwind`) module to handle such panics instead.
+//!
 //! ## Multithreading
 //! The pipewire library is not really thread-safe, so pipewire objects do not implement [`Send`](`std::marker::Send`)
 //! or [`Sync`](`std::marker::Sync`).
@@ -115,22 +118,40 @@ pub mod constants;
 pub mod context;
 pub mod core;
 pub mod device;
+pub mod discovery;
+#[cfg(feature = "dmabuf")]
+pub mod dmabuf;
+#[cfg(feature = "serde")]
+pub mod dump;
 pub mod factory;
 pub mod keys;
+pub mod latency;
 pub mod link;
+pub mod listeners;
 pub mod loop_;
 pub mod main_loop;
 pub mod metadata;
 pub mod module;
+pub mod modules;
 pub mod node;
+pub mod pending;
 pub mod permissions;
 pub mod port;
 pub mod properties;
 pub mod proxy;
 pub mod registry;
+pub mod scope;
+pub mod session;
+pub mod simple;
 pub mod stream;
+#[cfg(feature = "test-utils")]
+pub mod test;
 pub mod thread_loop;
+pub mod tools;
 pub mod types;
+pub mod unwind;
+pub mod version;
+pub mod volume;
 
 mod error;
 pub use error::*;
@@ -146,18 +167,109 @@ pub mod prelude {
     pub use spa::prelude::*;
 }
 
-use std::ptr;
+use std::{
+    ffi::{CStr, CString},
+    os::raw::{c_char, c_int},
+    ptr,
+};
+
+use once_cell::sync::OnceCell;
+use spa::support::log::LogLevel;
+
+static INITIALIZED: OnceCell<()> = OnceCell::new();
 
 /// Initialize PipeWire
 ///
 /// Initialize the PipeWire system and set up debugging
 /// through the environment variable `PIPEWIRE_DEBUG`.
 pub fn init() {
-    use once_cell::sync::OnceCell;
-    static INITIALIZED: OnceCell<()> = OnceCell::new();
     INITIALIZED.get_or_init(|| unsafe { pw_sys::pw_init(ptr::null_mut(), ptr::null_mut()) });
 }
 
+/// Initialize PipeWire with command line arguments
+///
+/// Like [`init`], but passes `args` to `pw_init()`, which may consume PipeWire specific arguments.
+/// `args` is updated to contain the remaining arguments afterwards.
+///
+/// If PipeWire was already initialized, this does nothing and `args` is left untouched.
+///
+/// # Panics
+/// If any of the arguments contains a null byte.
+pub fn init_with_args(args: &mut Vec<String>) {
+    INITIALIZED.get_or_init(|| {
+        let c_args: Vec<CString> = args
+            .iter()
+            .map(|arg| CString::new(arg.as_str()).expect("Null byte in argument"))
+            .collect();
+        let mut argv: Vec<*mut c_char> = c_args
+            .iter()
+            .map(|arg| arg.as_ptr().cast_mut())
+            .chain(std::iter::once(ptr::null_mut()))
+            .collect();
+        let mut argc: c_int = c_args.len().try_into().expect("Too many arguments");
+        let mut argv_ptr = argv.as_mut_ptr();
+
+        unsafe {
+            pw_sys::pw_init(&mut argc, &mut argv_ptr);
+
+            *args = (0..argc.try_into().unwrap())
+                .map(|i| {
+                    CStr::from_ptr(*argv_ptr.add(i))
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect();
+        }
+    });
+}
+
+/// Set the level of PipeWire's global log
+///
+/// This overrides the level set through the `PIPEWIRE_DEBUG` environment variable.
+pub fn set_log_level(level: LogLevel) {
+    unsafe { pw_sys::pw_log_set_level(level.as_raw()) }
+}
+
+/// Get the level of PipeWire's global log
+pub fn log_level() -> LogLevel {
+    unsafe { LogLevel::from_raw(pw_sys::pw_log_level) }
+}
+
+/// Replace PipeWire's global log, used by libpipewire and all SPA plugins it loads.
+///
+/// The log has to stay alive for the rest of the program, as PipeWire may log at any time.
+pub fn set_log(log: &'static spa::support::log::Log) {
+    unsafe { pw_sys::pw_log_set(log.as_raw_ptr()) }
+}
+
+/// Route PipeWire's internal logging into the [`log`](https://docs.rs/log) crate
+///
+/// See [`Log::with_log_crate`](spa::support::log::Log::with_log_crate) for details.
+/// This should be called after the logger has been installed and before creating a
+/// [`Context`](context::Context), as the context picks up the global log when it is created.
+#[cfg(feature = "log")]
+pub fn init_log_backend() {
+    static LOG: OnceCell<spa::support::log::Log> = OnceCell::new();
+
+    let log = LOG.get_or_init(spa::support::log::Log::with_log_crate);
+    set_log(log);
+    set_log_level(log.level());
+}
+
+/// Route PipeWire's internal logging into [`tracing`](https://docs.rs/tracing)
+///
+/// See [`Log::with_tracing`](spa::support::log::Log::with_tracing) for details.
+/// This should be called after the subscriber has been installed and before creating a
+/// [`Context`](context::Context), as the context picks up the global log when it is created.
+#[cfg(feature = "tracing")]
+pub fn init_tracing_backend() {
+    static LOG: OnceCell<spa::support::log::Log> = OnceCell::new();
+
+    let log = LOG.get_or_init(spa::support::log::Log::with_tracing);
+    set_log(log);
+    set_log_level(log.level());
+}
+
 /// Deinitialize PipeWire
 ///
 /// # Safety
@@ -1,15 +1,16 @@
 use std::{
+    cell::RefCell,
     ffi::{c_void, CStr},
     fmt, mem,
     ops::Deref,
-    pin::Pin,
     ptr,
 };
 
 use bitflags::bitflags;
-use spa::spa_interface_call_method;
+use spa::utils::{interface::VersionedInterface, Direction};
 
 use crate::{
+    listeners,
     proxy::{Listener, Proxy, ProxyT},
     types::ObjectType,
 };
@@ -41,6 +42,11 @@ impl ProxyT for Link {
 }
 
 impl Link {
+    fn interface(&self) -> VersionedInterface<pw_sys::pw_link_methods> {
+        // Safety: the proxy is a link, which stays valid as long as `self`.
+        unsafe { VersionedInterface::new(self.proxy.as_ptr().cast()) }
+    }
+
     #[must_use]
     pub fn add_listener_local(&self) -> LinkListenerLocalBuilder {
         LinkListenerLocalBuilder {
@@ -51,26 +57,19 @@ impl Link {
 }
 
 pub struct LinkListener {
-    // Need to stay allocated while the listener is registered
-    #[allow(dead_code)]
-    events: Pin<Box<pw_sys::pw_link_events>>,
-    listener: Pin<Box<spa_sys::spa_hook>>,
-    #[allow(dead_code)]
-    data: Box<ListenerLocalCallbacks>,
+    _listener: listeners::Listener<pw_sys::pw_link_events, ListenerLocalCallbacks>,
 }
 
 impl Listener for LinkListener {}
 
-impl Drop for LinkListener {
-    fn drop(&mut self) {
-        spa::utils::hook::remove(*self.listener);
-    }
-}
-
 #[derive(Default)]
 struct ListenerLocalCallbacks {
     #[allow(clippy::type_complexity)]
     info: Option<Box<dyn Fn(&LinkInfoRef)>>,
+    #[allow(clippy::type_complexity)]
+    state_changed: Option<Box<dyn Fn(Option<OwnedLinkState>, OwnedLinkState)>>,
+    /// The last state received, to be passed as the old state of `state_changed`.
+    state: RefCell<Option<OwnedLinkState>>,
 }
 
 pub struct LinkListenerLocalBuilder<'link> {
@@ -88,52 +87,67 @@ impl<'a> LinkListenerLocalBuilder<'a> {
         self
     }
 
+    /// Call `state_changed` with the old and new states of the link when its state changes.
+    ///
+    /// The old state is `None` for the first info event, which reports the state of the link when
+    /// the listener was registered. The states are owned, so they can be kept after the callback.
+    #[must_use]
+    pub fn state_changed<F>(mut self, state_changed: F) -> Self
+    where
+        F: Fn(Option<OwnedLinkState>, OwnedLinkState) + 'static,
+    {
+        self.cbs.state_changed = Some(Box::new(state_changed));
+        self
+    }
+
     #[must_use]
     pub fn register(self) -> LinkListener {
         unsafe extern "C" fn link_events_info(
             data: *mut c_void,
             info: *const pw_sys::pw_link_info,
         ) {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-            let info = ptr::NonNull::new(info as *mut pw_sys::pw_link_info).expect("info is NULL");
-            let info = info.cast::<LinkInfoRef>().as_ref();
-            callbacks.info.as_ref().unwrap()(info);
+            crate::unwind::guard("link_events_info", || {
+                let callbacks = listeners::callback_data::<ListenerLocalCallbacks>(data);
+                let info =
+                    ptr::NonNull::new(info as *mut pw_sys::pw_link_info).expect("info is NULL");
+                let info = info.cast::<LinkInfoRef>().as_ref();
+                if let Some(info_cb) = &callbacks.info {
+                    info_cb(info);
+                }
+                if let Some(state_changed) = &callbacks.state_changed {
+                    if info.change_mask().contains(LinkChangeMask::STATE) {
+                        let new = info.state().into_owned();
+                        let old = callbacks.state.replace(Some(new.clone()));
+                        state_changed(old, new);
+                    }
+                }
+            })
         }
 
         let e = unsafe {
-            let mut e: Pin<Box<pw_sys::pw_link_events>> = Box::pin(mem::zeroed());
+            let mut e: pw_sys::pw_link_events = mem::zeroed();
             e.version = pw_sys::PW_VERSION_LINK_EVENTS;
 
-            if self.cbs.info.is_some() {
+            if self.cbs.info.is_some() || self.cbs.state_changed.is_some() {
                 e.info = Some(link_events_info);
             }
 
             e
         };
 
-        let (listener, data) = unsafe {
-            let link = &self.link.proxy.as_ptr();
-
-            let data = Box::into_raw(Box::new(self.cbs));
-            let mut listener: Pin<Box<spa_sys::spa_hook>> = Box::pin(mem::zeroed());
-            let listener_ptr: *mut spa_sys::spa_hook = listener.as_mut().get_unchecked_mut();
-
-            spa_interface_call_method!(
-                link,
-                pw_sys::pw_link_methods,
-                add_listener,
-                listener_ptr.cast(),
-                e.as_ref().get_ref(),
-                data as *mut _
-            );
-
-            (listener, Box::from_raw(data))
+        let interface = self.link.interface();
+        let listener = unsafe {
+            listeners::Listener::new(e, self.cbs, |hook, events, data| {
+                interface.call(
+                    0,
+                    |methods| methods.add_listener,
+                    |f, object| f(object, hook.cast(), events, data),
+                );
+            })
         };
 
         LinkListener {
-            events: e,
-            listener,
-            data,
+            _listener: listener,
         }
     }
 }
@@ -170,6 +184,22 @@ impl LinkInfoRef {
         self.0.input_port_id
     }
 
+    /// Get the id of the node on the `direction` side of the link.
+    pub fn node_id(&self, direction: Direction) -> u32 {
+        match direction {
+            Direction::Input => self.input_node_id(),
+            Direction::Output => self.output_node_id(),
+        }
+    }
+
+    /// Get the id of the port on the `direction` side of the link.
+    pub fn port_id(&self, direction: Direction) -> u32 {
+        match direction {
+            Direction::Input => self.input_port_id(),
+            Direction::Output => self.output_port_id(),
+        }
+    }
+
     pub fn state(&self) -> LinkState {
         let raw_state = self.0.state;
         match raw_state {
@@ -287,7 +317,7 @@ impl fmt::Debug for LinkInfo {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LinkState<'a> {
     Error(&'a str),
     Unlinked,
@@ -297,3 +327,71 @@ pub enum LinkState<'a> {
     Paused,
     Active,
 }
+
+impl LinkState<'_> {
+    /// Copy the state, including its error message, so it can outlive the link info.
+    pub fn into_owned(self) -> OwnedLinkState {
+        match self {
+            Self::Error(error) => OwnedLinkState::Error(error.to_owned()),
+            Self::Unlinked => OwnedLinkState::Unlinked,
+            Self::Init => OwnedLinkState::Init,
+            Self::Negotiating => OwnedLinkState::Negotiating,
+            Self::Allocating => OwnedLinkState::Allocating,
+            Self::Paused => OwnedLinkState::Paused,
+            Self::Active => OwnedLinkState::Active,
+        }
+    }
+}
+
+/// An owned [`LinkState`], as passed to [`LinkListenerLocalBuilder::state_changed`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum OwnedLinkState {
+    Error(String),
+    Unlinked,
+    Init,
+    Negotiating,
+    Allocating,
+    Paused,
+    Active,
+}
+
+impl OwnedLinkState {
+    /// Borrow the state as a [`LinkState`].
+    pub fn as_link_state(&self) -> LinkState<'_> {
+        match self {
+            Self::Error(error) => LinkState::Error(error),
+            Self::Unlinked => LinkState::Unlinked,
+            Self::Init => LinkState::Init,
+            Self::Negotiating => LinkState::Negotiating,
+            Self::Allocating => LinkState::Allocating,
+            Self::Paused => LinkState::Paused,
+            Self::Active => LinkState::Active,
+        }
+    }
+}
+
+impl From<LinkState<'_>> for OwnedLinkState {
+    fn from(state: LinkState<'_>) -> Self {
+        state.into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn owned_state() {
+        let error = String::from("no format");
+        let state = LinkState::Error(&error).into_owned();
+        drop(error);
+        assert_eq!(state, OwnedLinkState::Error("no format".to_owned()));
+        assert_eq!(state.as_link_state(), LinkState::Error("no format"));
+
+        assert_eq!(
+            OwnedLinkState::from(LinkState::Active),
+            OwnedLinkState::Active
+        );
+        assert_eq!(OwnedLinkState::Paused.as_link_state(), LinkState::Paused);
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Registration of the listeners of objects.
+//!
+//! PipeWire objects notify their listeners through a `spa_hook`, which is registered with a C struct
+//! of event callbacks and a pointer passed back to the callbacks. The hook, the events and the data
+//! must stay at the same address until the hook is removed.
+//! [`Listener`] keeps them allocated and removes the hook when it is dropped, so that wrappers only
+//! need to provide the events and the method registering them.
+
+use std::{mem, pin::Pin, ptr};
+
+use libc::c_void;
+
+use crate::loop_::LoopRef;
+
+/// A listener registered on an object.
+///
+/// `E` is the C struct of the event callbacks, such as [`pw_sys::pw_node_events`], and `D` the data
+/// passed to the callbacks, usually the Rust closures they call.
+///
+/// The listener is removed when this is dropped, which must not happen from one of its own
+/// callbacks: use [`remove_later`](Self::remove_later) there instead.
+pub struct Listener<E, D> {
+    hook: Pin<Box<spa_sys::spa_hook>>,
+    // Need to stay allocated while the listener is registered
+    _events: Pin<Box<E>>,
+    data: ptr::NonNull<D>,
+}
+
+impl<E, D> Listener<E, D> {
+    /// Register a listener.
+    ///
+    /// `add_listener` is called with the pointers to the hook, the events and the data to register,
+    /// usually to call the `add_listener` method of the object.
+    ///
+    /// # Safety
+    /// `add_listener` must register the hook on an object which outlives the listener, and the
+    /// callbacks in `events` must only access their data through [`callback_data`] or
+    /// [`callback_data_mut`] with the type `D`.
+    pub unsafe fn new<F>(events: E, data: D, add_listener: F) -> Self
+    where
+        F: FnOnce(*mut spa_sys::spa_hook, *const E, *mut c_void),
+    {
+        let events = Box::pin(events);
+        let mut hook: Pin<Box<spa_sys::spa_hook>> = Box::pin(mem::zeroed());
+        let data = ptr::NonNull::new_unchecked(Box::into_raw(Box::new(data)));
+
+        add_listener(
+            hook.as_mut().get_unchecked_mut(),
+            events.as_ref().get_ref(),
+            data.as_ptr().cast(),
+        );
+
+        Self {
+            hook,
+            _events: events,
+            data,
+        }
+    }
+
+    /// The data passed to the callbacks.
+    pub fn data(&self) -> &D {
+        unsafe { self.data.as_ref() }
+    }
+
+    /// The data passed to the callbacks, mutably.
+    ///
+    /// # Safety
+    /// The callbacks must not be called while the returned reference is alive.
+    pub unsafe fn data_mut(&mut self) -> &mut D {
+        self.data.as_mut()
+    }
+
+    /// Remove the listener and take back the data passed to the callbacks.
+    pub fn into_data(self) -> D {
+        let this = mem::ManuallyDrop::new(self);
+        spa::utils::hook::remove(*this.hook);
+        // Safety: the fields are not used again, as `this` is not dropped.
+        unsafe {
+            drop(ptr::read(&this.hook));
+            drop(ptr::read(&this._events));
+            *Box::from_raw(this.data.as_ptr())
+        }
+    }
+
+    /// Remove the listener in the next iteration of `loop_`.
+    ///
+    /// This can be called from a callback of the listener itself, see [`LoopRef::drop_later`].
+    pub fn remove_later(self, loop_: &LoopRef)
+    where
+        E: 'static,
+        D: 'static,
+    {
+        loop_.drop_later(self);
+    }
+}
+
+impl<E, D> Drop for Listener<E, D> {
+    fn drop(&mut self) {
+        spa::utils::hook::remove(*self.hook);
+        // The callbacks can no longer be called, so the data can be freed.
+        unsafe { drop(Box::from_raw(self.data.as_ptr())) }
+    }
+}
+
+/// Get the data of a [`Listener`] from the pointer received by its callbacks.
+///
+/// # Safety
+/// `data` must be the pointer received by a callback of a `Listener<_, D>`.
+pub unsafe fn callback_data<'a, D>(data: *mut c_void) -> &'a D {
+    (data as *const D).as_ref().expect("listener data is NULL")
+}
+
+/// Get the data of a [`Listener`] mutably from the pointer received by its callbacks.
+///
+/// # Safety
+/// `data` must be the pointer received by a callback of a `Listener<_, D>`, and the callbacks
+/// of the listener must not be reentered while the returned reference is alive.
+pub unsafe fn callback_data_mut<'a, D>(data: *mut c_void) -> &'a mut D {
+    (data as *mut D).as_mut().expect("listener data is NULL")
+}
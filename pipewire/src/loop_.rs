@@ -3,6 +3,8 @@
 
 use std::{
     convert::TryInto,
+    marker::PhantomData,
+    mem,
     ops::Deref,
     os::unix::prelude::*,
     ptr::{self, NonNull},
@@ -14,7 +16,8 @@ use libc::{c_int, c_void};
 pub use nix::sys::signal::Signal;
 use spa::{spa_interface_call_method, support::system::IoFlags, utils::result::SpaResult};
 
-use crate::{utils::assert_main_thread, Error};
+use crate::{error::ResultExt, utils::assert_main_thread, Error};
+use nix::errno::Errno;
 
 /// A transparent wrapper around a raw [`pw_loop`](`pw_sys::pw_loop`).
 /// It is usually only seen in a reference (`&LoopRef`).
@@ -35,6 +38,10 @@ impl LoopRef {
     }
 
     /// Get the file descriptor backing this loop.
+    ///
+    /// The file descriptor becomes readable when the loop has events to dispatch, which allows
+    /// registering it in another event loop, such as glib or calloop, instead of running the loop.
+    /// The events are then dispatched with [`enter_guard()`](Self::enter_guard).
     pub fn fd(&self) -> BorrowedFd<'_> {
         unsafe {
             let mut iface = self.as_raw().control.as_ref().unwrap().iface;
@@ -108,6 +115,49 @@ impl LoopRef {
         }
     }
 
+    /// Enter the loop until the returned guard is dropped.
+    ///
+    /// This is the safe version of [`enter()`](Self::enter) and [`leave()`](Self::leave), and is used to
+    /// dispatch the events of the loop from another event loop:
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use pipewire::main_loop::MainLoop;
+    ///
+    /// let mainloop = MainLoop::new(None)?;
+    /// let loop_ = mainloop.loop_();
+    /// // Register `loop_.fd()` in the other event loop, then, when it is readable:
+    /// let guard = loop_.enter_guard();
+    /// guard.dispatch();
+    /// # Ok::<(), pipewire::Error>(())
+    /// ```
+    #[must_use]
+    pub fn enter_guard(&self) -> LoopEnterGuard<'_> {
+        unsafe { self.enter() };
+        LoopEnterGuard { loop_: self }
+    }
+
+    /// Get a handle to run closures in the thread of the loop, which can be sent to other threads.
+    ///
+    /// ```no_run
+    /// use pipewire::thread_loop::ThreadLoop;
+    ///
+    /// let thread_loop = unsafe { ThreadLoop::new(None, None)? };
+    /// thread_loop.start();
+    ///
+    /// let invoker = thread_loop.loop_().invoker();
+    /// std::thread::scope(|s| {
+    ///     s.spawn(|| invoker.invoke(|| println!("Running in the loop thread")));
+    /// });
+    /// # Ok::<(), pipewire::Error>(())
+    /// ```
+    pub fn invoker(&self) -> LoopInvoker<'_> {
+        LoopInvoker {
+            ptr: NonNull::from(&self.0),
+            _loop: PhantomData,
+        }
+    }
+
     /// A variant of [`iterate()`](`Self::iterate()`) that does not call [`Self::enter()`]  and [`Self::leave()`] on the loop.
     ///
     /// # Safety
@@ -145,8 +195,10 @@ impl LoopRef {
         where
             I: AsRawFd,
         {
-            let (io, callback) = (data as *mut IoSourceData<I>).as_mut().unwrap();
-            callback(io);
+            crate::unwind::guard("io_source", || {
+                let (io, callback) = (data as *mut IoSourceData<I>).as_mut().unwrap();
+                callback(io);
+            })
         }
 
         let fd = io.as_raw_fd();
@@ -192,8 +244,10 @@ impl LoopRef {
         where
             F: Fn(),
         {
-            let callback = (data as *mut F).as_ref().unwrap();
-            callback();
+            crate::unwind::guard("idle_source", || {
+                let callback = (data as *mut F).as_ref().unwrap();
+                callback();
+            })
         }
 
         let data = Box::into_raw(Box::new(callback));
@@ -237,8 +291,10 @@ impl LoopRef {
         where
             F: Fn(),
         {
-            let callback = (data as *mut F).as_ref().unwrap();
-            callback();
+            crate::unwind::guard("signal_source", || {
+                let callback = (data as *mut F).as_ref().unwrap();
+                callback();
+            })
         }
 
         let data = Box::into_raw(Box::new(callback));
@@ -280,8 +336,10 @@ impl LoopRef {
         where
             F: Fn(),
         {
-            let callback = (data as *mut F).as_ref().unwrap();
-            callback();
+            crate::unwind::guard("event_source", || {
+                let callback = (data as *mut F).as_ref().unwrap();
+                callback();
+            })
         }
 
         let data = Box::into_raw(Box::new(callback));
@@ -323,8 +381,10 @@ impl LoopRef {
         where
             F: Fn(u64),
         {
-            let callback = (data as *mut F).as_ref().unwrap();
-            callback(expirations);
+            crate::unwind::guard("timer_source", || {
+                let callback = (data as *mut F).as_ref().unwrap();
+                callback(expirations);
+            })
         }
 
         let data = Box::into_raw(Box::new(callback));
@@ -351,6 +411,64 @@ impl LoopRef {
         }
     }
 
+    /// Drop `value` in the next iteration of the loop, once the current callback has returned.
+    ///
+    /// Dropping a listener frees the closures of its callbacks, and destroying a proxy emits
+    /// events to its listeners, so neither can be done from a callback of the listener itself:
+    /// the closure being run would be freed. Pass the listener or the proxy to this method instead,
+    /// such as with [`Listener::unregister_later`](crate::proxy::Listener::unregister_later) or
+    /// [`ProxyT::destroy_later`](crate::proxy::ProxyT::destroy_later).
+    ///
+    /// If the loop is destroyed before its next iteration, `value` is leaked.
+    pub fn drop_later<T: 'static>(&self, value: T) {
+        struct DeferredDrop<T> {
+            value: T,
+            loop_: NonNull<pw_sys::pw_loop>,
+            source: *mut spa_sys::spa_source,
+        }
+
+        impl<T> IsSource for DeferredDrop<T> {
+            fn as_ptr(&self) -> *mut spa_sys::spa_source {
+                self.source
+            }
+        }
+
+        unsafe extern "C" fn drop_value<T>(data: *mut c_void) {
+            crate::unwind::guard("deferred_drop", || {
+                let deferred = Box::from_raw(data as *mut DeferredDrop<T>);
+                // The loop frees the source once it is done dispatching it.
+                deferred
+                    .loop_
+                    .cast::<LoopRef>()
+                    .as_ref()
+                    .destroy_source(&*deferred);
+                drop(deferred.value);
+            })
+        }
+
+        let data = Box::into_raw(Box::new(DeferredDrop {
+            value,
+            loop_: NonNull::from(&self.0),
+            source: ptr::null_mut(),
+        }));
+
+        unsafe {
+            let mut iface = self.as_raw().utils.as_ref().unwrap().iface;
+
+            let source = spa_interface_call_method!(
+                &mut iface as *mut spa_sys::spa_interface,
+                spa_sys::spa_loop_utils_methods,
+                add_idle,
+                true,
+                Some(drop_value::<T>),
+                data as *mut _
+            );
+
+            assert!(!source.is_null(), "source is NULL");
+            (*data).source = source;
+        }
+    }
+
     /// Destroy a source that belongs to this loop.
     ///
     /// # Safety
@@ -371,6 +489,137 @@ impl LoopRef {
     }
 }
 
+impl AsFd for LoopRef {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd()
+    }
+}
+
+/// A guard keeping a loop entered, created with [`LoopRef::enter_guard`].
+///
+/// The loop is left when this is dropped.
+pub struct LoopEnterGuard<'a> {
+    loop_: &'a LoopRef,
+}
+
+impl<'a> LoopEnterGuard<'a> {
+    /// Perform one iteration of the loop, see [`LoopRef::iterate`].
+    ///
+    /// # Panics
+    /// This function will panic if the provided timeout as milliseconds does not fit inside a
+    /// `c_int` integer.
+    pub fn iterate(&self, timeout: Duration) -> i32 {
+        unsafe { self.loop_.iterate_unguarded(timeout) }
+    }
+
+    /// Dispatch the pending events of the loop without blocking.
+    ///
+    /// The number of dispatched fds is returned.
+    pub fn dispatch(&self) -> i32 {
+        self.iterate(Duration::ZERO)
+    }
+}
+
+impl<'a> Drop for LoopEnterGuard<'a> {
+    fn drop(&mut self) {
+        unsafe { self.loop_.leave() }
+    }
+}
+
+/// A handle to run closures in the thread of a loop, created with [`LoopRef::invoker`].
+///
+/// Unlike the loop, it can be sent to and shared with other threads.
+#[derive(Clone, Copy, Debug)]
+pub struct LoopInvoker<'l> {
+    ptr: NonNull<pw_sys::pw_loop>,
+    _loop: PhantomData<&'l LoopRef>,
+}
+
+// Safety: Invoking is the way to interact with a loop from other threads, and is thread-safe.
+unsafe impl<'l> Send for LoopInvoker<'l> {}
+unsafe impl<'l> Sync for LoopInvoker<'l> {}
+
+impl<'l> LoopInvoker<'l> {
+    /// Queue `f` to be called in the thread of the loop, and return without waiting for it.
+    ///
+    /// When called from the thread of the loop, `f` is called right away.
+    pub fn invoke<F>(&self, f: F) -> Result<(), Error>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.invoke_raw(f, false)
+            .context("invoke a closure in the loop")
+    }
+
+    /// Call `f` in the thread of the loop and return its result, waiting until the loop called it.
+    ///
+    /// When called from the thread of the loop, `f` is called right away. Otherwise, the loop must be
+    /// running, and the lock of a [`ThreadLoop`](crate::thread_loop::ThreadLoop) must not be held, or
+    /// this never returns.
+    pub fn invoke_blocking<F, R>(&self, f: F) -> Result<R, Error>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.invoke_raw(
+            move || {
+                let _ = sender.send(f());
+            },
+            true,
+        )
+        .context("invoke a closure in the loop")?;
+
+        receiver
+            .try_recv()
+            .map_err(|_| Error::from(spa::utils::result::Error::from(Errno::EIO)))
+            .context("receive the result of a closure invoked in the loop")
+    }
+
+    fn invoke_raw<F>(&self, f: F, block: bool) -> Result<(), Error>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        unsafe extern "C" fn call<F: FnOnce()>(
+            _loop: *mut spa_sys::spa_loop,
+            _async: bool,
+            _seq: u32,
+            data: *const c_void,
+            _size: usize,
+            _user_data: *mut c_void,
+        ) -> c_int {
+            crate::unwind::guard("invoke", || {
+                // The pointer was copied in the queue of the loop, which does not keep its alignment.
+                let f = Box::from_raw(ptr::read_unaligned(data.cast::<*mut F>()));
+                f();
+                0
+            })
+        }
+
+        let f = Box::into_raw(Box::new(f));
+        let res = unsafe {
+            let mut iface = self.ptr.as_ref().loop_.as_ref().unwrap().iface;
+
+            spa_interface_call_method!(
+                &mut iface as *mut spa_sys::spa_interface,
+                spa_sys::spa_loop_methods,
+                invoke,
+                Some(call::<F>),
+                spa_sys::SPA_ID_INVALID,
+                ptr::addr_of!(f).cast(),
+                mem::size_of::<*mut F>(),
+                block,
+                ptr::null_mut()
+            )
+        };
+
+        // On failure, the closure is leaked rather than freed, as it is unknown whether it was queued.
+        // A closure queued from another thread while the loop runs is an asynchronous success.
+        SpaResult::from_c(res).into_result()?;
+        Ok(())
+    }
+}
+
 /// Trait implemented by objects that implement a `pw_loop` and are reference counted in some way.
 ///
 /// # Safety
@@ -671,3 +920,40 @@ impl<'l> Drop for TimerSource<'l> {
         unsafe { self.loop_.destroy_source(self) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn invoke_in_thread() {
+        let loop_ = Loop::new(None).unwrap();
+        let invoker = loop_.invoker();
+
+        // The loop was never entered, so the closures are called right away.
+        assert_eq!(invoker.invoke_blocking(|| 42).unwrap(), 42);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        invoker.invoke(move || sender.send(()).unwrap()).unwrap();
+        assert!(receiver.try_recv().is_ok());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn invoke_in_running_thread_loop() {
+        let thread_loop = unsafe { crate::thread_loop::ThreadLoop::new(None, None).unwrap() };
+        thread_loop.start();
+        let invoker = thread_loop.loop_().invoker();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        invoker.invoke(move || sender.send(()).unwrap()).unwrap();
+        receiver
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .unwrap();
+
+        assert_eq!(invoker.invoke_blocking(|| 42).unwrap(), 42);
+
+        thread_loop.stop();
+    }
+}
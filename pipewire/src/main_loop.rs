@@ -46,6 +46,9 @@ impl MainLoop {
         self.inner.ptr.as_ptr()
     }
 
+    /// Get a weak handle to the loop, which does not keep it alive.
+    ///
+    /// This is how callbacks run by the loop should refer to it, for example to quit it.
     pub fn downgrade(&self) -> WeakMainLoop {
         let weak = Rc::downgrade(&self.inner);
         WeakMainLoop { weak }
@@ -82,11 +85,14 @@ impl std::convert::AsRef<LoopRef> for MainLoop {
     }
 }
 
+/// A weak handle to a [`MainLoop`], see [`MainLoop::downgrade`].
+#[derive(Debug, Clone)]
 pub struct WeakMainLoop {
     weak: Weak<MainLoopInner>,
 }
 
 impl WeakMainLoop {
+    /// Get the loop, or `None` if all its clones have been dropped.
     pub fn upgrade(&self) -> Option<MainLoop> {
         self.weak.upgrade().map(|inner| MainLoop { inner })
     }
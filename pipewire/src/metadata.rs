@@ -5,12 +5,12 @@ use std::ffi::CString;
 use std::os::raw::c_char;
 use std::{
     ffi::{c_void, CStr},
-    mem,
-    pin::Pin,
-    ptr,
+    mem, ptr,
 };
 
 use crate::{
+    core::PW_ID_CORE,
+    listeners,
     proxy::{Listener, Proxy, ProxyT},
     types::ObjectType,
 };
@@ -68,6 +68,28 @@ impl Metadata {
         }
     }
 
+    /// Force the quantum of the whole graph to `quantum` samples, or stop forcing it with `None`.
+    ///
+    /// This sets the `clock.force-quantum` setting, so `self` has to be the `settings`
+    /// metadata. Clients can only change the properties of the nodes they own: use
+    /// `StreamRef::set_forced_quantum` to force the quantum only while a stream is active.
+    pub fn set_forced_quantum(&self, quantum: Option<u32>) {
+        self.set_clock_setting("clock.force-quantum", quantum);
+    }
+
+    /// Force the rate of the whole graph to `rate` Hz, or stop forcing it with `None`.
+    ///
+    /// This sets the `clock.force-rate` setting, see [`Metadata::set_forced_quantum`].
+    pub fn set_forced_rate(&self, rate: Option<u32>) {
+        self.set_clock_setting("clock.force-rate", rate);
+    }
+
+    fn set_clock_setting(&self, key: &str, value: Option<u32>) {
+        // A value of 0 disables the forcing.
+        let value = value.unwrap_or(0).to_string();
+        self.set_property(PW_ID_CORE, key, None, Some(&value));
+    }
+
     pub fn clear(&self) {
         unsafe {
             spa::spa_interface_call_method!(
@@ -80,22 +102,11 @@ impl Metadata {
 }
 
 pub struct MetadataListener {
-    // Need to stay allocated while the listener is registered
-    #[allow(dead_code)]
-    events: Pin<Box<pw_sys::pw_metadata_events>>,
-    listener: Pin<Box<spa_sys::spa_hook>>,
-    #[allow(dead_code)]
-    data: Box<ListenerLocalCallbacks>,
+    _listener: listeners::Listener<pw_sys::pw_metadata_events, ListenerLocalCallbacks>,
 }
 
 impl Listener for MetadataListener {}
 
-impl Drop for MetadataListener {
-    fn drop(&mut self) {
-        spa::utils::hook::remove(*self.listener);
-    }
-}
-
 #[derive(Default)]
 struct ListenerLocalCallbacks {
     #[allow(clippy::type_complexity)]
@@ -132,32 +143,34 @@ impl<'meta> MetadataListenerLocalBuilder<'meta> {
             type_: *const c_char,
             value: *const c_char,
         ) -> i32 {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-            let key = if !key.is_null() {
-                Some(CStr::from_ptr(key).to_string_lossy())
-            } else {
-                None
-            };
-            let type_ = if !type_.is_null() {
-                Some(CStr::from_ptr(type_).to_string_lossy())
-            } else {
-                None
-            };
-            let value = if !value.is_null() {
-                Some(CStr::from_ptr(value).to_string_lossy())
-            } else {
-                None
-            };
-            callbacks.property.as_ref().unwrap()(
-                subject,
-                key.as_deref(),
-                type_.as_deref(),
-                value.as_deref(),
-            )
+            crate::unwind::guard("metadata_events_property", || {
+                let callbacks = listeners::callback_data::<ListenerLocalCallbacks>(data);
+                let key = if !key.is_null() {
+                    Some(CStr::from_ptr(key).to_string_lossy())
+                } else {
+                    None
+                };
+                let type_ = if !type_.is_null() {
+                    Some(CStr::from_ptr(type_).to_string_lossy())
+                } else {
+                    None
+                };
+                let value = if !value.is_null() {
+                    Some(CStr::from_ptr(value).to_string_lossy())
+                } else {
+                    None
+                };
+                callbacks.property.as_ref().unwrap()(
+                    subject,
+                    key.as_deref(),
+                    type_.as_deref(),
+                    value.as_deref(),
+                )
+            })
         }
 
         let e = unsafe {
-            let mut e: Pin<Box<pw_sys::pw_metadata_events>> = Box::pin(mem::zeroed());
+            let mut e: pw_sys::pw_metadata_events = mem::zeroed();
             e.version = pw_sys::PW_VERSION_METADATA_EVENTS;
 
             if self.cbs.property.is_some() {
@@ -167,29 +180,22 @@ impl<'meta> MetadataListenerLocalBuilder<'meta> {
             e
         };
 
-        let (listener, data) = unsafe {
-            let metadata = &self.metadata.proxy.as_ptr();
-
-            let data = Box::into_raw(Box::new(self.cbs));
-            let mut listener: Pin<Box<spa_sys::spa_hook>> = Box::pin(mem::zeroed());
-            let listener_ptr: *mut spa_sys::spa_hook = listener.as_mut().get_unchecked_mut();
-
-            spa_interface_call_method!(
-                metadata,
-                pw_sys::pw_metadata_methods,
-                add_listener,
-                listener_ptr.cast(),
-                e.as_ref().get_ref(),
-                data as *mut _
-            );
-
-            (listener, Box::from_raw(data))
+        let metadata = self.metadata.proxy.as_ptr();
+        let listener = unsafe {
+            listeners::Listener::new(e, self.cbs, |hook, events, data| {
+                spa_interface_call_method!(
+                    metadata,
+                    pw_sys::pw_metadata_methods,
+                    add_listener,
+                    hook.cast(),
+                    events,
+                    data
+                );
+            })
         };
 
         MetadataListener {
-            events: e,
-            listener,
-            data,
+            _listener: listener,
         }
     }
 }
@@ -4,11 +4,11 @@
 use bitflags::bitflags;
 use libc::c_void;
 use std::ops::Deref;
-use std::pin::Pin;
 use std::{ffi::CStr, ptr};
 use std::{fmt, mem};
 
 use crate::{
+    listeners,
     proxy::{Listener, Proxy, ProxyT},
     types::ObjectType,
 };
@@ -83,7 +83,7 @@ impl ModuleInfoRef {
     }
 
     pub fn filename(&self) -> &str {
-        unsafe { CStr::from_ptr(self.0.name).to_str().unwrap() }
+        unsafe { CStr::from_ptr(self.0.filename).to_str().unwrap() }
     }
 
     pub fn args(&self) -> Option<&str> {
@@ -177,22 +177,11 @@ impl fmt::Debug for ModuleInfo {
 }
 
 pub struct ModuleListener {
-    // Need to stay allocated while the listener is registered
-    #[allow(dead_code)]
-    events: Pin<Box<pw_sys::pw_module_events>>,
-    listener: Pin<Box<spa_sys::spa_hook>>,
-    #[allow(dead_code)]
-    data: Box<ListenerLocalCallbacks>,
+    _listener: listeners::Listener<pw_sys::pw_module_events, ListenerLocalCallbacks>,
 }
 
 impl Listener for ModuleListener {}
 
-impl Drop for ModuleListener {
-    fn drop(&mut self) {
-        spa::utils::hook::remove(*self.listener);
-    }
-}
-
 impl<'a> ModuleListenerLocalBuilder<'a> {
     #[must_use]
     pub fn info<F>(mut self, info: F) -> Self
@@ -209,15 +198,17 @@ impl<'a> ModuleListenerLocalBuilder<'a> {
             data: *mut c_void,
             info: *const pw_sys::pw_module_info,
         ) {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-            let info =
-                ptr::NonNull::new(info as *mut pw_sys::pw_module_info).expect("info is NULL");
-            let info = info.cast::<ModuleInfoRef>().as_ref();
-            callbacks.info.as_ref().unwrap()(info);
+            crate::unwind::guard("module_events_info", || {
+                let callbacks = listeners::callback_data::<ListenerLocalCallbacks>(data);
+                let info =
+                    ptr::NonNull::new(info as *mut pw_sys::pw_module_info).expect("info is NULL");
+                let info = info.cast::<ModuleInfoRef>().as_ref();
+                callbacks.info.as_ref().unwrap()(info);
+            })
         }
 
         let e = unsafe {
-            let mut e: Pin<Box<pw_sys::pw_module_events>> = Box::pin(mem::zeroed());
+            let mut e: pw_sys::pw_module_events = mem::zeroed();
             e.version = pw_sys::PW_VERSION_MODULE_EVENTS;
 
             if self.cbs.info.is_some() {
@@ -227,29 +218,22 @@ impl<'a> ModuleListenerLocalBuilder<'a> {
             e
         };
 
-        let (listener, data) = unsafe {
-            let module = &self.module.proxy.as_ptr();
-
-            let data = Box::into_raw(Box::new(self.cbs));
-            let mut listener: Pin<Box<spa_sys::spa_hook>> = Box::pin(mem::zeroed());
-            let listener_ptr: *mut spa_sys::spa_hook = listener.as_mut().get_unchecked_mut();
-
-            spa_interface_call_method!(
-                module,
-                pw_sys::pw_module_methods,
-                add_listener,
-                listener_ptr.cast(),
-                e.as_ref().get_ref(),
-                data as *mut _
-            );
-
-            (listener, Box::from_raw(data))
+        let module = self.module.proxy.as_ptr();
+        let listener = unsafe {
+            listeners::Listener::new(e, self.cbs, |hook, events, data| {
+                spa_interface_call_method!(
+                    module,
+                    pw_sys::pw_module_methods,
+                    add_listener,
+                    hook.cast(),
+                    events,
+                    data
+                );
+            })
         };
 
         ModuleListener {
-            events: e,
-            listener,
-            data,
+            _listener: listener,
         }
     }
 }
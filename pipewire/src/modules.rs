@@ -0,0 +1,666 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Typed wrappers for the modules commonly loaded by applications, such as loopbacks, filter chains
+//! and the network audio modules.
+//!
+//! The modules are loaded in the process with [`Context::load_module`], with arguments built
+//! from the configuration types of this module, and unloaded when their handle is dropped.
+//! Unset options of the configurations keep the defaults of the module, and so do non-finite
+//! floating point values, which SPA-JSON can't represent.
+//!
+//! ```no_run
+//! use pipewire::{
+//!     context::Context,
+//!     main_loop::MainLoop,
+//!     modules::{Loopback, LoopbackConfig},
+//! };
+//!
+//! let mainloop = MainLoop::new(None)?;
+//! let context = Context::new(&mainloop)?;
+//! let _core = context.connect(None)?;
+//!
+//! let _loopback = Loopback::load(
+//!     &context,
+//!     &LoopbackConfig {
+//!         description: Some("Monitor".to_owned()),
+//!         channels: Some(2),
+//!         ..Default::default()
+//!     },
+//! )?;
+//!
+//! mainloop.run();
+//! # Ok::<(), pipewire::Error>(())
+//! ```
+
+use std::fmt::Write;
+
+use crate::{
+    context::{Context, LoadedModule},
+    Error,
+};
+
+/// Writer of SPA-JSON objects, the format of the module arguments.
+#[derive(Default)]
+struct JsonObject(String);
+
+impl JsonObject {
+    fn key(&mut self, key: &str) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+        write_string(&mut self.0, key);
+        self.0.push_str(" = ");
+    }
+
+    fn string(&mut self, key: &str, value: &str) {
+        self.key(key);
+        write_string(&mut self.0, value);
+    }
+
+    fn opt_string(&mut self, key: &str, value: Option<&str>) {
+        if let Some(value) = value {
+            self.string(key, value);
+        }
+    }
+
+    fn number(&mut self, key: &str, value: impl std::fmt::Display) {
+        self.key(key);
+        write!(self.0, "{value}").unwrap();
+    }
+
+    /// Write a floating point value, leaving out the key if the value is not finite.
+    fn float(&mut self, key: &str, value: f32) {
+        if value.is_finite() {
+            self.number(key, value);
+        }
+    }
+
+    fn opt_number(&mut self, key: &str, value: Option<impl std::fmt::Display>) {
+        if let Some(value) = value {
+            self.number(key, value);
+        }
+    }
+
+    fn opt_bool(&mut self, key: &str, value: Option<bool>) {
+        if let Some(value) = value {
+            self.raw(key, if value { "true" } else { "false" });
+        }
+    }
+
+    fn raw(&mut self, key: &str, value: &str) {
+        self.key(key);
+        self.0.push_str(value);
+    }
+
+    fn strings<'a>(&mut self, key: &str, values: impl IntoIterator<Item = &'a str>) {
+        let values: Vec<String> = values
+            .into_iter()
+            .map(|value| {
+                let mut s = String::new();
+                write_string(&mut s, value);
+                s
+            })
+            .collect();
+        self.raw(key, &array(&values));
+    }
+
+    fn properties(&mut self, key: &str, properties: &[(String, String)]) {
+        let mut object = JsonObject::default();
+        for (k, v) in properties {
+            object.string(k, v);
+        }
+        self.raw(key, &object.finish());
+    }
+
+    fn finish(self) -> String {
+        if self.0.is_empty() {
+            String::from("{ }")
+        } else {
+            format!("{{ {} }}", self.0)
+        }
+    }
+}
+
+fn array(items: &[String]) -> String {
+    if items.is_empty() {
+        String::from("[ ]")
+    } else {
+        format!("[ {} ]", items.join(" "))
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => write!(out, "\\u{:04x}", u32::from(c)).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Define the handle of a module loaded with the arguments of a configuration type.
+macro_rules! module_handle {
+    ($(#[$attr:meta])* $name:ident, $config:ty, $module:literal) => {
+        $(#[$attr])*
+        ///
+        /// The module is unloaded when this is dropped.
+        #[derive(Debug)]
+        pub struct $name {
+            module: LoadedModule,
+        }
+
+        impl $name {
+            #[doc = concat!("Load `", $module, "` into `context`.")]
+            pub fn load(context: &Context, config: &$config) -> Result<Self, Error> {
+                Ok(Self {
+                    module: load(context, $module, &config.to_args())?,
+                })
+            }
+
+            pub fn module(&self) -> &LoadedModule {
+                &self.module
+            }
+        }
+    };
+}
+
+/// The configuration of a [`Loopback`].
+#[derive(Debug, Clone, Default)]
+pub struct LoopbackConfig {
+    /// The description of the nodes, shown by user interfaces.
+    pub description: Option<String>,
+    /// The number of channels.
+    pub channels: Option<u32>,
+    /// The positions of the channels, such as `FL` and `FR`.
+    pub positions: Vec<String>,
+    /// The delay added between the capture and the playback, in seconds.
+    pub delay: Option<f32>,
+    /// The properties of the capture stream, such as `target.object` or `media.class`.
+    pub capture_props: Vec<(String, String)>,
+    /// The properties of the playback stream.
+    pub playback_props: Vec<(String, String)>,
+}
+
+impl LoopbackConfig {
+    /// The arguments of `libpipewire-module-loopback`.
+    pub fn to_args(&self) -> String {
+        let mut args = JsonObject::default();
+        args.opt_string("node.description", self.description.as_deref());
+        if let Some(channels) = self.channels {
+            args.number("audio.channels", channels);
+        }
+        if !self.positions.is_empty() {
+            args.strings("audio.position", self.positions.iter().map(String::as_str));
+        }
+        if let Some(delay) = self.delay {
+            args.float("target.delay.sec", delay);
+        }
+        args.properties("capture.props", &self.capture_props);
+        args.properties("playback.props", &self.playback_props);
+        args.finish()
+    }
+}
+
+module_handle!(
+    /// A loaded `libpipewire-module-loopback`, which plays back what it captures.
+    ///
+    /// This is used to create virtual sinks and sources, or to route the audio of a node to another.
+    Loopback,
+    LoopbackConfig,
+    "libpipewire-module-loopback"
+);
+
+/// A node of a [`FilterGraph`].
+#[derive(Debug, Clone, Default)]
+pub struct FilterNode {
+    /// The type of the plugin, such as `builtin`, `ladspa` or `lv2`.
+    pub type_: String,
+    /// The name of the node, used in the links.
+    pub name: String,
+    /// The plugin providing the filter, not needed for `builtin` filters.
+    pub plugin: Option<String>,
+    /// The label of the filter in the plugin, such as `bq_lowshelf`.
+    pub label: String,
+    /// The values of the controls of the filter.
+    pub control: Vec<(String, f32)>,
+}
+
+/// A link between two ports of a [`FilterGraph`], named `<node>:<port>`.
+#[derive(Debug, Clone, Default)]
+pub struct FilterLink {
+    pub output: String,
+    pub input: String,
+}
+
+/// The graph of filters processing the audio of a [`FilterChain`].
+#[derive(Debug, Clone, Default)]
+pub struct FilterGraph {
+    pub nodes: Vec<FilterNode>,
+    pub links: Vec<FilterLink>,
+    /// The ports receiving the input of the graph, defaulting to the unconnected input ports.
+    pub inputs: Vec<String>,
+    /// The ports producing the output of the graph, defaulting to the unconnected output ports.
+    pub outputs: Vec<String>,
+}
+
+impl FilterGraph {
+    fn to_json(&self) -> String {
+        let nodes: Vec<String> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                let mut object = JsonObject::default();
+                object.string("type", &node.type_);
+                object.string("name", &node.name);
+                object.opt_string("plugin", node.plugin.as_deref());
+                object.string("label", &node.label);
+
+                let mut control = JsonObject::default();
+                for (name, value) in &node.control {
+                    control.float(name, *value);
+                }
+                object.raw("control", &control.finish());
+                object.finish()
+            })
+            .collect();
+        let links: Vec<String> = self
+            .links
+            .iter()
+            .map(|link| {
+                let mut object = JsonObject::default();
+                object.string("output", &link.output);
+                object.string("input", &link.input);
+                object.finish()
+            })
+            .collect();
+
+        let mut graph = JsonObject::default();
+        graph.raw("nodes", &array(&nodes));
+        graph.raw("links", &array(&links));
+        if !self.inputs.is_empty() {
+            graph.strings("inputs", self.inputs.iter().map(String::as_str));
+        }
+        if !self.outputs.is_empty() {
+            graph.strings("outputs", self.outputs.iter().map(String::as_str));
+        }
+        graph.finish()
+    }
+}
+
+/// The configuration of a [`FilterChain`].
+#[derive(Debug, Clone, Default)]
+pub struct FilterChainConfig {
+    /// The description of the nodes, shown by user interfaces.
+    pub description: Option<String>,
+    /// The name of the media processed by the filters.
+    pub media_name: Option<String>,
+    /// The number of channels.
+    pub channels: Option<u32>,
+    /// The positions of the channels, such as `FL` and `FR`.
+    pub positions: Vec<String>,
+    pub graph: FilterGraph,
+    /// The properties of the capture stream, such as `media.class = Audio/Sink` to create
+    /// a virtual sink.
+    pub capture_props: Vec<(String, String)>,
+    /// The properties of the playback stream.
+    pub playback_props: Vec<(String, String)>,
+}
+
+impl FilterChainConfig {
+    /// The arguments of `libpipewire-module-filter-chain`.
+    pub fn to_args(&self) -> String {
+        let mut args = JsonObject::default();
+        args.opt_string("node.description", self.description.as_deref());
+        args.opt_string("media.name", self.media_name.as_deref());
+        if let Some(channels) = self.channels {
+            args.number("audio.channels", channels);
+        }
+        if !self.positions.is_empty() {
+            args.strings("audio.position", self.positions.iter().map(String::as_str));
+        }
+        args.raw("filter.graph", &self.graph.to_json());
+        args.properties("capture.props", &self.capture_props);
+        args.properties("playback.props", &self.playback_props);
+        args.finish()
+    }
+}
+
+module_handle!(
+    /// A loaded `libpipewire-module-filter-chain`, which processes audio with a graph of filters.
+    FilterChain,
+    FilterChainConfig,
+    "libpipewire-module-filter-chain"
+);
+
+/// The configuration of a [`RtpSource`].
+#[derive(Debug, Clone, Default)]
+pub struct RtpSourceConfig {
+    /// The address to receive from, such as the multicast group `224.0.0.56`.
+    pub source_ip: Option<String>,
+    pub source_port: Option<u16>,
+    /// The network interface to receive from.
+    pub interface: Option<String>,
+    /// The latency of the session, in milliseconds.
+    pub latency_msec: Option<u32>,
+    /// The properties of the created source stream.
+    pub stream_props: Vec<(String, String)>,
+}
+
+impl RtpSourceConfig {
+    /// The arguments of `libpipewire-module-rtp-source`.
+    pub fn to_args(&self) -> String {
+        let mut args = JsonObject::default();
+        args.opt_string("source.ip", self.source_ip.as_deref());
+        args.opt_number("source.port", self.source_port);
+        args.opt_string("local.ifname", self.interface.as_deref());
+        args.opt_number("sess.latency.msec", self.latency_msec);
+        args.properties("stream.props", &self.stream_props);
+        args.finish()
+    }
+}
+
+module_handle!(
+    /// A loaded `libpipewire-module-rtp-source`, which plays the RTP streams received from the network.
+    RtpSource,
+    RtpSourceConfig,
+    "libpipewire-module-rtp-source"
+);
+
+/// The configuration of a [`RtpSink`].
+#[derive(Debug, Clone, Default)]
+pub struct RtpSinkConfig {
+    /// The local address to send from.
+    pub source_ip: Option<String>,
+    /// The address to send to, such as the multicast group `224.0.0.56`.
+    pub destination_ip: Option<String>,
+    pub destination_port: Option<u16>,
+    pub mtu: Option<u32>,
+    pub ttl: Option<u32>,
+    /// Whether to loop back the multicast packets to the local host.
+    pub multicast_loop: Option<bool>,
+    /// The name of the session, announced to the receivers.
+    pub session_name: Option<String>,
+    /// The media of the session, such as `audio`, `midi` or `opus`.
+    pub session_media: Option<String>,
+    /// The sample format, such as `S16BE`.
+    pub format: Option<String>,
+    pub rate: Option<u32>,
+    pub channels: Option<u32>,
+    /// The positions of the channels, such as `FL` and `FR`.
+    pub positions: Vec<String>,
+    /// The properties of the created sink stream.
+    pub stream_props: Vec<(String, String)>,
+}
+
+impl RtpSinkConfig {
+    /// The arguments of `libpipewire-module-rtp-sink`.
+    pub fn to_args(&self) -> String {
+        let mut args = JsonObject::default();
+        args.opt_string("source.ip", self.source_ip.as_deref());
+        args.opt_string("destination.ip", self.destination_ip.as_deref());
+        args.opt_number("destination.port", self.destination_port);
+        args.opt_number("net.mtu", self.mtu);
+        args.opt_number("net.ttl", self.ttl);
+        args.opt_bool("net.loop", self.multicast_loop);
+        args.opt_string("sess.name", self.session_name.as_deref());
+        args.opt_string("sess.media", self.session_media.as_deref());
+        args.opt_string("audio.format", self.format.as_deref());
+        args.opt_number("audio.rate", self.rate);
+        args.opt_number("audio.channels", self.channels);
+        if !self.positions.is_empty() {
+            args.strings("audio.position", self.positions.iter().map(String::as_str));
+        }
+        args.properties("stream.props", &self.stream_props);
+        args.finish()
+    }
+}
+
+module_handle!(
+    /// A loaded `libpipewire-module-rtp-sink`, which sends what it plays to the network with RTP.
+    RtpSink,
+    RtpSinkConfig,
+    "libpipewire-module-rtp-sink"
+);
+
+/// The configuration of a [`RocSource`].
+#[derive(Debug, Clone, Default)]
+pub struct RocSourceConfig {
+    /// The local address to receive from.
+    pub local_ip: Option<String>,
+    pub local_source_port: Option<u16>,
+    /// The port receiving the repair packets of the FEC.
+    pub local_repair_port: Option<u16>,
+    /// The latency of the session, in milliseconds.
+    pub latency_msec: Option<u32>,
+    /// The resampler profile, `disable`, `high`, `medium` or `low`.
+    pub resampler_profile: Option<String>,
+    /// The FEC code, `disable`, `rs8m` or `ldpc`.
+    pub fec_code: Option<String>,
+    /// The properties of the created source stream.
+    pub source_props: Vec<(String, String)>,
+}
+
+impl RocSourceConfig {
+    /// The arguments of `libpipewire-module-roc-source`.
+    pub fn to_args(&self) -> String {
+        let mut args = JsonObject::default();
+        args.opt_string("local.ip", self.local_ip.as_deref());
+        args.opt_number("local.source.port", self.local_source_port);
+        args.opt_number("local.repair.port", self.local_repair_port);
+        args.opt_number("sess.latency.msec", self.latency_msec);
+        args.opt_string("resampler.profile", self.resampler_profile.as_deref());
+        args.opt_string("fec.code", self.fec_code.as_deref());
+        args.properties("source.props", &self.source_props);
+        args.finish()
+    }
+}
+
+module_handle!(
+    /// A loaded `libpipewire-module-roc-source`, which plays the ROC streams received from the network.
+    RocSource,
+    RocSourceConfig,
+    "libpipewire-module-roc-source"
+);
+
+/// The configuration of a [`RocSink`].
+#[derive(Debug, Clone, Default)]
+pub struct RocSinkConfig {
+    /// The address of the receiver.
+    pub remote_ip: Option<String>,
+    pub remote_source_port: Option<u16>,
+    /// The port of the receiver for the repair packets of the FEC.
+    pub remote_repair_port: Option<u16>,
+    /// The FEC code, `disable`, `rs8m` or `ldpc`.
+    pub fec_code: Option<String>,
+    /// The properties of the created sink stream.
+    pub sink_props: Vec<(String, String)>,
+}
+
+impl RocSinkConfig {
+    /// The arguments of `libpipewire-module-roc-sink`.
+    pub fn to_args(&self) -> String {
+        let mut args = JsonObject::default();
+        args.opt_string("remote.ip", self.remote_ip.as_deref());
+        args.opt_number("remote.source.port", self.remote_source_port);
+        args.opt_number("remote.repair.port", self.remote_repair_port);
+        args.opt_string("fec.code", self.fec_code.as_deref());
+        args.properties("sink.props", &self.sink_props);
+        args.finish()
+    }
+}
+
+module_handle!(
+    /// A loaded `libpipewire-module-roc-sink`, which sends what it plays to a ROC receiver.
+    RocSink,
+    RocSinkConfig,
+    "libpipewire-module-roc-sink"
+);
+
+/// The configuration of a [`ZeroconfDiscover`].
+#[derive(Debug, Clone, Default)]
+pub struct ZeroconfDiscoverConfig {
+    /// The latency of the created tunnels, in milliseconds.
+    pub latency_msec: Option<u32>,
+}
+
+impl ZeroconfDiscoverConfig {
+    /// The arguments of `libpipewire-module-zeroconf-discover`.
+    pub fn to_args(&self) -> String {
+        let mut args = JsonObject::default();
+        args.opt_number("pulse.latency", self.latency_msec);
+        args.finish()
+    }
+}
+
+module_handle!(
+    /// A loaded `libpipewire-module-zeroconf-discover`, which creates tunnels to the PulseAudio
+    /// servers announced on the network.
+    ZeroconfDiscover,
+    ZeroconfDiscoverConfig,
+    "libpipewire-module-zeroconf-discover"
+);
+
+/// The configuration of a [`ProtocolPulse`].
+#[derive(Debug, Clone, Default)]
+pub struct ProtocolPulseConfig {
+    /// The addresses to listen on, such as `unix:native` or `tcp:4713`.
+    pub server_addresses: Vec<String>,
+    /// The minimum request size, as a fraction of seconds such as `256/48000`.
+    pub min_req: Option<String>,
+    pub default_req: Option<String>,
+    pub min_frag: Option<String>,
+    pub default_frag: Option<String>,
+    pub default_tlength: Option<String>,
+    pub min_quantum: Option<String>,
+}
+
+impl ProtocolPulseConfig {
+    /// The arguments of `libpipewire-module-protocol-pulse`.
+    pub fn to_args(&self) -> String {
+        let mut properties = JsonObject::default();
+        if !self.server_addresses.is_empty() {
+            properties.strings(
+                "server.address",
+                self.server_addresses.iter().map(String::as_str),
+            );
+        }
+        properties.opt_string("pulse.min.req", self.min_req.as_deref());
+        properties.opt_string("pulse.default.req", self.default_req.as_deref());
+        properties.opt_string("pulse.min.frag", self.min_frag.as_deref());
+        properties.opt_string("pulse.default.frag", self.default_frag.as_deref());
+        properties.opt_string("pulse.default.tlength", self.default_tlength.as_deref());
+        properties.opt_string("pulse.min.quantum", self.min_quantum.as_deref());
+
+        let mut args = JsonObject::default();
+        args.raw("pulse.properties", &properties.finish());
+        args.finish()
+    }
+}
+
+module_handle!(
+    /// A loaded `libpipewire-module-protocol-pulse`, a PulseAudio server.
+    ProtocolPulse,
+    ProtocolPulseConfig,
+    "libpipewire-module-protocol-pulse"
+);
+
+fn load(context: &Context, name: &str, args: &str) -> Result<LoadedModule, Error> {
+    context.load_module(name, Some(args), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loopback_args() {
+        let config = LoopbackConfig {
+            description: Some("Say \"hi\"".to_owned()),
+            channels: Some(2),
+            positions: vec!["FL".to_owned(), "FR".to_owned()],
+            capture_props: vec![("media.class".to_owned(), "Audio/Sink".to_owned())],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config.to_args(),
+            r#"{ "node.description" = "Say \"hi\"" "audio.channels" = 2 "audio.position" = [ "FL" "FR" ] "capture.props" = { "media.class" = "Audio/Sink" } "playback.props" = { } }"#
+        );
+    }
+
+    #[test]
+    fn filter_chain_args() {
+        let config = FilterChainConfig {
+            graph: FilterGraph {
+                nodes: vec![FilterNode {
+                    type_: "builtin".to_owned(),
+                    name: "eq".to_owned(),
+                    label: "bq_lowshelf".to_owned(),
+                    control: vec![("Freq".to_owned(), 100.0)],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config.to_args(),
+            r#"{ "filter.graph" = { "nodes" = [ { "type" = "builtin" "name" = "eq" "label" = "bq_lowshelf" "control" = { "Freq" = 100 } } ] "links" = [ ] } "capture.props" = { } "playback.props" = { } }"#
+        );
+    }
+
+    #[test]
+    fn non_finite_floats() {
+        let config = LoopbackConfig {
+            delay: Some(f32::NAN),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.to_args(),
+            r#"{ "capture.props" = { } "playback.props" = { } }"#
+        );
+
+        let mut control = JsonObject::default();
+        control.float("Freq", f32::INFINITY);
+        control.float("Q", 0.5);
+        assert_eq!(control.finish(), r#"{ "Q" = 0.5 }"#);
+    }
+
+    #[test]
+    fn rtp_sink_args() {
+        let config = RtpSinkConfig {
+            destination_ip: Some("224.0.0.56".to_owned()),
+            destination_port: Some(46000),
+            multicast_loop: Some(false),
+            session_media: Some("audio".to_owned()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config.to_args(),
+            r#"{ "destination.ip" = "224.0.0.56" "destination.port" = 46000 "net.loop" = false "sess.media" = "audio" "stream.props" = { } }"#
+        );
+    }
+
+    #[test]
+    fn protocol_pulse_args() {
+        let config = ProtocolPulseConfig {
+            server_addresses: vec!["unix:native".to_owned(), "tcp:4713".to_owned()],
+            min_req: Some("256/48000".to_owned()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config.to_args(),
+            r#"{ "pulse.properties" = { "server.address" = [ "unix:native" "tcp:4713" ] "pulse.min.req" = "256/48000" } }"#
+        );
+    }
+}
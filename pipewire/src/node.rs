@@ -4,15 +4,45 @@
 use bitflags::bitflags;
 use libc::c_void;
 use std::ops::Deref;
-use std::pin::Pin;
 use std::{ffi::CStr, ptr};
 use std::{fmt, mem};
 
 use crate::{
+    core::CoreRef,
+    discovery,
+    error::ResultExt,
+    listeners,
     proxy::{Listener, Proxy, ProxyT},
     types::ObjectType,
+    volume::VolumeProps,
+    Error,
 };
-use spa::{pod::Pod, spa_interface_call_method};
+use spa::{
+    pod::{io::PodBuf, Pod, Value},
+    utils::{
+        interface::VersionedInterface,
+        result::{AsyncSeq, SpaResult},
+    },
+};
+
+spa::interface_methods! {
+    trait NodeMethods for pw_sys::pw_node_methods {
+        fn subscribe_params(&self, ids: &[spa::param::ParamType]) -> SpaResult {
+            since 0;
+            unsafe { call(ids.as_ptr() as *mut _, ids.len().try_into().unwrap()) }
+        }
+
+        fn enum_params(&self, seq: i32, id: u32, start: u32, num: u32) -> SpaResult {
+            since 0;
+            unsafe { call(seq, id, start, num, ptr::null()) }
+        }
+
+        fn set_param(&self, id: u32, flags: u32, param: &Pod) -> SpaResult {
+            since 0;
+            unsafe { call(id, flags, param.as_ptr()) }
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Node {
@@ -20,6 +50,11 @@ pub struct Node {
 }
 
 impl Node {
+    fn interface(&self) -> VersionedInterface<pw_sys::pw_node_methods> {
+        // Safety: the proxy is a node, which stays valid as long as `self`.
+        unsafe { VersionedInterface::new(self.proxy.as_ptr().cast()) }
+    }
+
     // TODO: add non-local version when we'll bind pw_thread_loop_start()
     #[must_use]
     pub fn add_listener_local(&self) -> NodeListenerLocalBuilder {
@@ -32,17 +67,13 @@ impl Node {
     /// Subscribe to parameter changes
     ///
     /// Automatically emit `param` events for the given ids when they are changed
-    // FIXME: Return result?
-    pub fn subscribe_params(&self, ids: &[spa::param::ParamType]) {
-        unsafe {
-            spa_interface_call_method!(
-                self.proxy.as_ptr(),
-                pw_sys::pw_node_methods,
-                subscribe_params,
-                ids.as_ptr() as *mut _,
-                ids.len().try_into().unwrap()
-            );
-        }
+    pub fn subscribe_params(&self, ids: &[spa::param::ParamType]) -> Result<AsyncSeq, Error> {
+        let res = self
+            .interface()
+            .subscribe_params(ids)
+            .into_async_result()
+            .context("subscribe to the node params")?;
+        Ok(res)
     }
 
     /// Enumerate node parameters
@@ -56,35 +87,107 @@ impl Node {
     /// `start`: the start index or 0 for the first param \
     /// `num`: the maximum number of params to retrieve ([`u32::MAX`] may be used to retrieve all params)
     // FIXME: Add filter parameter
-    // FIXME: Return result?
-    pub fn enum_params(&self, seq: i32, id: Option<spa::param::ParamType>, start: u32, num: u32) {
+    pub fn enum_params(
+        &self,
+        seq: i32,
+        id: Option<spa::param::ParamType>,
+        start: u32,
+        num: u32,
+    ) -> Result<AsyncSeq, Error> {
         let id = id.map(|id| id.as_raw()).unwrap_or(crate::constants::ID_ANY);
 
+        let res = self
+            .interface()
+            .enum_params(seq, id, start, num)
+            .into_async_result()
+            .context("enumerate the node params")?;
+        Ok(res)
+    }
+
+    pub fn set_param(
+        &self,
+        id: spa::param::ParamType,
+        flags: u32,
+        param: &Pod,
+    ) -> Result<AsyncSeq, Error> {
+        let res = self
+            .interface()
+            .set_param(id.as_raw(), flags, param)
+            .into_async_result()
+            .context("set a node param")?;
+        Ok(res)
+    }
+
+    /// The core the node proxy was created on.
+    fn core(&self) -> &CoreRef {
         unsafe {
-            spa_interface_call_method!(
-                self.proxy.as_ptr(),
-                pw_sys::pw_node_methods,
-                enum_params,
-                seq,
-                id,
-                start,
-                num,
-                std::ptr::null()
-            );
+            let core = pw_sys::pw_proxy_get_core(self.proxy.as_ptr());
+            ptr::NonNull::new(core)
+                .expect("proxy core is NULL")
+                .cast()
+                .as_ref()
         }
     }
 
-    pub fn set_param(&self, id: spa::param::ParamType, flags: u32, param: &Pod) {
-        unsafe {
-            spa_interface_call_method!(
-                self.proxy.as_ptr(),
-                pw_sys::pw_node_methods,
-                set_param,
-                id.as_raw(),
-                flags,
-                param.as_raw_ptr()
-            );
+    /// Set the linear volume of all the channels of the node.
+    ///
+    /// See [`set_volume_props`](Self::set_volume_props) for how the volume is applied, and the
+    /// [`volume`](crate::volume) module for converting from a cubic volume.
+    pub fn set_volume(&self, volume: f32) -> Result<(), Error> {
+        self.set_volume_props(&VolumeProps {
+            volume: Some(volume),
+            ..Default::default()
+        })
+    }
+
+    /// Mute or unmute the node.
+    ///
+    /// See [`set_volume_props`](Self::set_volume_props) for how the change is applied.
+    pub fn set_mute(&self, mute: bool) -> Result<(), Error> {
+        self.set_volume_props(&VolumeProps {
+            mute: Some(mute),
+            ..Default::default()
+        })
+    }
+
+    /// Set the linear volume of each channel of the node.
+    ///
+    /// See [`set_volume_props`](Self::set_volume_props) for how the volumes are applied.
+    pub fn set_channel_volumes(&self, volumes: &[f32]) -> Result<(), Error> {
+        self.set_volume_props(&VolumeProps {
+            channel_volumes: Some(volumes.to_vec()),
+            ..Default::default()
+        })
+    }
+
+    /// Set several volume properties of the node at once.
+    ///
+    /// Nodes of a sound card, which have `device.id` and `card.profile.device` properties, are
+    /// changed through the active route of their device, so that the session manager remembers
+    /// the volume of the route. Other nodes are changed through their `Props` param, like with
+    /// [`set_props`](Self::set_props).
+    ///
+    /// Looking up the route iterates the loop of the core until the server has answered, so this
+    /// has to be called from the thread running the loop, outside of its callbacks, like the
+    /// functions of the [`discovery`](crate::discovery) module.
+    pub fn set_volume_props(&self, props: &VolumeProps) -> Result<(), Error> {
+        if let Some(id) = self.proxy.bound_id() {
+            if discovery::set_node_route_volume(self.core(), id, props)? {
+                return Ok(());
+            }
         }
+
+        self.set_props(props)?;
+        Ok(())
+    }
+
+    /// Set volume properties through the `Props` param of the node only.
+    ///
+    /// This does not reach the device of the node, see [`set_volume_props`](Self::set_volume_props).
+    pub fn set_props(&self, props: &VolumeProps) -> Result<AsyncSeq, Error> {
+        let pod =
+            PodBuf::serialize(&Value::Object(props.to_object())).expect("Failed to serialize pod");
+        self.set_param(spa::param::ParamType::Props, 0, &pod)
     }
 }
 
@@ -295,22 +398,11 @@ impl fmt::Debug for NodeInfo {
 }
 
 pub struct NodeListener {
-    // Need to stay allocated while the listener is registered
-    #[allow(dead_code)]
-    events: Pin<Box<pw_sys::pw_node_events>>,
-    listener: Pin<Box<spa_sys::spa_hook>>,
-    #[allow(dead_code)]
-    data: Box<ListenerLocalCallbacks>,
+    _listener: listeners::Listener<pw_sys::pw_node_events, ListenerLocalCallbacks>,
 }
 
 impl Listener for NodeListener {}
 
-impl Drop for NodeListener {
-    fn drop(&mut self) {
-        spa::utils::hook::remove(*self.listener);
-    }
-}
-
 impl<'a> NodeListenerLocalBuilder<'a> {
     #[must_use]
     pub fn info<F>(mut self, info: F) -> Self
@@ -336,10 +428,13 @@ impl<'a> NodeListenerLocalBuilder<'a> {
             data: *mut c_void,
             info: *const pw_sys::pw_node_info,
         ) {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-            let info = ptr::NonNull::new(info as *mut pw_sys::pw_node_info).expect("info is NULL");
-            let info = info.cast::<NodeInfoRef>().as_ref();
-            callbacks.info.as_ref().unwrap()(info);
+            crate::unwind::guard("node_events_info", || {
+                let callbacks = listeners::callback_data::<ListenerLocalCallbacks>(data);
+                let info =
+                    ptr::NonNull::new(info as *mut pw_sys::pw_node_info).expect("info is NULL");
+                let info = info.cast::<NodeInfoRef>().as_ref();
+                callbacks.info.as_ref().unwrap()(info);
+            })
         }
 
         unsafe extern "C" fn node_events_param(
@@ -350,20 +445,22 @@ impl<'a> NodeListenerLocalBuilder<'a> {
             next: u32,
             param: *const spa_sys::spa_pod,
         ) {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-
-            let id = spa::param::ParamType::from_raw(id);
-            let param = if !param.is_null() {
-                unsafe { Some(Pod::from_raw(param)) }
-            } else {
-                None
-            };
+            crate::unwind::guard("node_events_param", || {
+                let callbacks = listeners::callback_data::<ListenerLocalCallbacks>(data);
+
+                let id = spa::param::ParamType::from_raw(id);
+                let param = if !param.is_null() {
+                    unsafe { Some(Pod::from_raw(param)) }
+                } else {
+                    None
+                };
 
-            callbacks.param.as_ref().unwrap()(seq, id, index, next, param);
+                callbacks.param.as_ref().unwrap()(seq, id, index, next, param);
+            })
         }
 
         let e = unsafe {
-            let mut e: Pin<Box<pw_sys::pw_node_events>> = Box::pin(mem::zeroed());
+            let mut e: pw_sys::pw_node_events = mem::zeroed();
             e.version = pw_sys::PW_VERSION_NODE_EVENTS;
 
             if self.cbs.info.is_some() {
@@ -376,29 +473,19 @@ impl<'a> NodeListenerLocalBuilder<'a> {
             e
         };
 
-        let (listener, data) = unsafe {
-            let node = &self.node.proxy.as_ptr();
-
-            let data = Box::into_raw(Box::new(self.cbs));
-            let mut listener: Pin<Box<spa_sys::spa_hook>> = Box::pin(mem::zeroed());
-            let listener_ptr: *mut spa_sys::spa_hook = listener.as_mut().get_unchecked_mut();
-
-            spa_interface_call_method!(
-                node,
-                pw_sys::pw_node_methods,
-                add_listener,
-                listener_ptr.cast(),
-                e.as_ref().get_ref(),
-                data as *mut _
-            );
-
-            (listener, Box::from_raw(data))
+        let interface = self.node.interface();
+        let listener = unsafe {
+            listeners::Listener::new(e, self.cbs, |hook, events, data| {
+                interface.call(
+                    0,
+                    |methods| methods.add_listener,
+                    |f, object| f(object, hook.cast(), events, data),
+                );
+            })
         };
 
         NodeListener {
-            events: e,
-            listener,
-            data,
+            _listener: listener,
         }
     }
 }
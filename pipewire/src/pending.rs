@@ -0,0 +1,265 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Tracking of asynchronous methods.
+//!
+//! Most methods of the proxies return an [`AsyncSeq`] as they are only processed once the server
+//! receives them. The server later reports their completion through the `done` event of the
+//! core (for [`CoreRef::sync`] and [`Proxy::sync`]) or their failure through the `error` event.
+//! [`PendingSeqTracker`] pairs the issued sequence numbers with these events.
+//!
+//! ```no_run
+//! use pipewire::{context::Context, core::PW_ID_CORE, main_loop::MainLoop, pending::PendingSeqTracker};
+//!
+//! let mainloop = MainLoop::new(None)?;
+//! let context = Context::new(&mainloop)?;
+//! let core = context.connect(None)?;
+//!
+//! let tracker = PendingSeqTracker::new();
+//! let _listener = tracker.listen_core(&core);
+//!
+//! // Wait for the server to process all the methods sent so far.
+//! let pending = tracker.track(PW_ID_CORE, core.sync(0)?);
+//! tracker.wait(mainloop.loop_(), PW_ID_CORE, pending)?;
+//! # Ok::<(), pipewire::Error>(())
+//! ```
+
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc, time::Duration};
+
+use spa::utils::result::{AsyncSeq, SpaResult};
+
+use crate::{
+    core::{self, CoreRef, PW_ID_CORE},
+    error::ResultExt,
+    loop_::LoopRef,
+    proxy::{Proxy, ProxyListener},
+    Error,
+};
+
+#[derive(Default)]
+struct State {
+    /// The tracked methods, by object id and sequence number.
+    pending: HashMap<(u32, i32), Option<Result<(), Error>>>,
+    /// An error of the core that does not belong to a tracked method.
+    fatal: Option<Error>,
+}
+
+/// Pairs the sequence numbers of asynchronous methods with their completion.
+///
+/// Methods are identified by the id of the object they were called on and their sequence number.
+/// The tracker is cheap to clone, clones share the same state.
+#[derive(Clone, Default)]
+pub struct PendingSeqTracker {
+    state: Rc<RefCell<State>>,
+}
+
+impl PendingSeqTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start tracking the method called on the object `id` and returning `seq`.
+    pub fn track(&self, id: u32, seq: AsyncSeq) -> AsyncSeq {
+        self.state
+            .borrow_mut()
+            .pending
+            .insert((id, seq.seq()), None);
+
+        seq
+    }
+
+    /// Mark the method `seq` called on `id` as completed.
+    ///
+    /// Returns `false` if the method is not tracked or already finished.
+    pub fn complete(&self, id: u32, seq: AsyncSeq) -> bool {
+        self.finish(id, seq.seq(), Ok(()))
+    }
+
+    /// Mark a method as failed, using the arguments of the `error` event of the core.
+    ///
+    /// Errors on the core itself which do not belong to a tracked method,
+    /// such as the connection being lost, are returned by the next call to [`wait`](Self::wait).
+    ///
+    /// Returns `false` if the error does not belong to a tracked method.
+    pub fn fail(&self, id: u32, seq: i32, res: i32, message: &str) -> bool {
+        let seq = AsyncSeq::from_seq(seq).seq();
+        let error = Error::from_core_error(id, seq, res, message);
+
+        if self.is_pending(id, AsyncSeq::from_seq(seq)) {
+            self.finish(id, seq, Err(error))
+        } else {
+            if id == PW_ID_CORE {
+                self.state.borrow_mut().fatal = Some(error);
+            }
+            false
+        }
+    }
+
+    fn finish(&self, id: u32, seq: i32, result: Result<(), Error>) -> bool {
+        match self.state.borrow_mut().pending.get_mut(&(id, seq)) {
+            Some(slot @ None) => {
+                *slot = Some(result);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the method `seq` called on `id` is tracked and not finished yet.
+    pub fn is_pending(&self, id: u32, seq: AsyncSeq) -> bool {
+        matches!(
+            self.state.borrow().pending.get(&(id, seq.seq())),
+            Some(None)
+        )
+    }
+
+    /// The number of tracked methods which are not finished yet.
+    pub fn pending_count(&self) -> usize {
+        self.state
+            .borrow()
+            .pending
+            .values()
+            .filter(|result| result.is_none())
+            .count()
+    }
+
+    /// Stop tracking the method `seq` called on `id` and return its result if it is finished.
+    ///
+    /// Methods that are not finished yet are still tracked.
+    pub fn take_result(&self, id: u32, seq: AsyncSeq) -> Option<Result<(), Error>> {
+        let mut state = self.state.borrow_mut();
+        let key = (id, seq.seq());
+        if !matches!(state.pending.get(&key), Some(Some(_))) {
+            return None;
+        }
+
+        state.pending.remove(&key).flatten()
+    }
+
+    /// Iterate `loop_` until the method `seq` called on `id` is finished, and return its result.
+    ///
+    /// Returns immediately if the method is not tracked.
+    pub fn wait(&self, loop_: &LoopRef, id: u32, seq: AsyncSeq) -> Result<(), Error> {
+        loop {
+            if let Some(result) = self.take_result(id, seq) {
+                return result;
+            }
+            if let Some(error) = self.state.borrow_mut().fatal.take() {
+                return Err(error);
+            }
+            if !self.is_pending(id, seq) {
+                return Ok(());
+            }
+
+            let res = loop_.iterate(Duration::from_millis(100));
+            if res < 0 && res != -libc::EINTR {
+                SpaResult::from_c(res)
+                    .into_sync_result()
+                    .context("iterate the loop")?;
+            }
+        }
+    }
+
+    /// Follow the `done` and `error` events of `core`.
+    ///
+    /// The tracker stops following the events when the returned listener is dropped.
+    #[must_use]
+    pub fn listen_core(&self, core: &CoreRef) -> core::Listener {
+        let done = self.clone();
+        let error = self.clone();
+
+        core.add_listener_local()
+            .done(move |id, seq| {
+                done.complete(id, seq);
+            })
+            .error(move |id, seq, res, message| {
+                error.fail(id, seq, res, message);
+            })
+            .register()
+    }
+
+    /// Follow the `done` and `error` events of `proxy`.
+    ///
+    /// The tracker stops following the events when the returned listener is dropped.
+    #[must_use]
+    pub fn listen_proxy(&self, proxy: &Proxy) -> ProxyListener {
+        let id = proxy.id();
+        let done = self.clone();
+        let error = self.clone();
+
+        proxy
+            .add_listener_local()
+            .done(move |seq| {
+                done.complete(id, AsyncSeq::from_seq(seq));
+            })
+            .error(move |seq, res, message| {
+                error.fail(id, seq, res, message);
+            })
+            .register()
+    }
+}
+
+impl fmt::Debug for PendingSeqTracker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PendingSeqTracker")
+            .field("pending_count", &self.pending_count())
+            .finish()
+    }
+}
+
+/// Get the loop of the context of `core`.
+pub(crate) fn core_loop(core: &CoreRef) -> &LoopRef {
+    unsafe {
+        let context = pw_sys::pw_core_get_context(core.as_raw_ptr());
+        let loop_ = pw_sys::pw_context_get_main_loop(context);
+        &*(loop_ as *const LoopRef)
+    }
+}
+
+/// Iterate `loop_` until the server processed all the requests sent so far.
+pub(crate) fn roundtrip(core: &CoreRef, loop_: &LoopRef) -> Result<(), Error> {
+    let tracker = PendingSeqTracker::new();
+    let _listener = tracker.listen_core(core);
+
+    let pending = tracker.track(PW_ID_CORE, core.sync(0)?);
+    tracker.wait(loop_, PW_ID_CORE, pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use nix::errno::Errno;
+
+    use super::*;
+
+    #[test]
+    fn complete() {
+        let tracker = PendingSeqTracker::new();
+        let seq = tracker.track(PW_ID_CORE, AsyncSeq::from_seq(3));
+        assert!(tracker.is_pending(PW_ID_CORE, seq));
+        assert_eq!(tracker.pending_count(), 1);
+        assert!(tracker.take_result(PW_ID_CORE, seq).is_none());
+
+        // Wrong object
+        assert!(!tracker.complete(5, seq));
+        assert!(tracker.complete(PW_ID_CORE, seq));
+        assert!(!tracker.complete(PW_ID_CORE, seq));
+        assert!(!tracker.is_pending(PW_ID_CORE, seq));
+        assert_eq!(tracker.pending_count(), 0);
+
+        assert!(matches!(tracker.take_result(PW_ID_CORE, seq), Some(Ok(()))));
+        assert!(tracker.take_result(PW_ID_CORE, seq).is_none());
+    }
+
+    #[test]
+    fn fail() {
+        let tracker = PendingSeqTracker::new();
+        let seq = tracker.track(5, AsyncSeq::from_seq(8));
+
+        assert!(!tracker.fail(5, 9, -libc::EINVAL, "invalid"));
+        assert!(tracker.fail(5, seq.raw(), -libc::EINVAL, "invalid"));
+
+        let error = tracker.take_result(5, seq).unwrap().unwrap_err();
+        assert_eq!(error.errno(), Some(Errno::EINVAL));
+        assert_eq!(error.seq(), Some(seq));
+    }
+}
@@ -4,15 +4,22 @@
 use bitflags::bitflags;
 use libc::c_void;
 use std::ops::Deref;
+use std::ptr;
 use std::{fmt, mem};
-use std::{pin::Pin, ptr};
 
 use crate::{
+    error::ResultExt,
+    keys, listeners,
     proxy::{Listener, Proxy, ProxyT},
     spa::utils::Direction,
     types::ObjectType,
+    Error,
+};
+use spa::{
+    pod::Pod,
+    spa_interface_call_method,
+    utils::result::{AsyncSeq, SpaResult},
 };
-use spa::{pod::Pod, spa_interface_call_method};
 
 #[derive(Debug)]
 pub struct Port {
@@ -32,17 +39,21 @@ impl Port {
     /// Subscribe to parameter changes
     ///
     /// Automatically emit `param` events for the given ids when they are changed
-    // FIXME: Return result?
-    pub fn subscribe_params(&self, ids: &[spa::param::ParamType]) {
-        unsafe {
+    pub fn subscribe_params(&self, ids: &[spa::param::ParamType]) -> Result<AsyncSeq, Error> {
+        let res = unsafe {
             spa_interface_call_method!(
                 self.proxy.as_ptr(),
                 pw_sys::pw_port_methods,
                 subscribe_params,
                 ids.as_ptr() as *mut _,
                 ids.len().try_into().unwrap()
-            );
-        }
+            )
+        };
+
+        let res = SpaResult::from_c(res)
+            .into_async_result()
+            .context("subscribe to the port params")?;
+        Ok(res)
     }
 
     /// Enumerate node parameters
@@ -56,22 +67,32 @@ impl Port {
     /// `start`: the start index or 0 for the first param \
     /// `num`: the maximum number of params to retrieve ([`u32::MAX`] may be used to retrieve all params)
     // FIXME: Add filter parameter
-    // FIXME: Return result?
-    pub fn enum_params(&self, seq: i32, id: Option<spa::param::ParamType>, start: u32, num: u32) {
+    pub fn enum_params(
+        &self,
+        seq: i32,
+        id: Option<spa::param::ParamType>,
+        start: u32,
+        num: u32,
+    ) -> Result<AsyncSeq, Error> {
         let id = id.map(|id| id.as_raw()).unwrap_or(crate::constants::ID_ANY);
 
-        unsafe {
+        let res = unsafe {
             spa_interface_call_method!(
                 self.proxy.as_ptr(),
-                pw_sys::pw_node_methods,
+                pw_sys::pw_port_methods,
                 enum_params,
                 seq,
                 id,
                 start,
                 num,
                 std::ptr::null()
-            );
-        }
+            )
+        };
+
+        let res = SpaResult::from_c(res)
+            .into_async_result()
+            .context("enumerate the port params")?;
+        Ok(res)
     }
 }
 
@@ -125,7 +146,8 @@ impl PortInfoRef {
         self.0.id
     }
 
-    pub fn direction(&self) -> Direction {
+    /// The direction of the port, `None` if the server reported an unknown direction.
+    pub fn direction(&self) -> Option<Direction> {
         Direction::from_raw(self.0.direction)
     }
 
@@ -138,6 +160,41 @@ impl PortInfoRef {
         ptr::NonNull::new(props_ptr).map(|ptr| unsafe { ptr.as_ref() })
     }
 
+    /// The flags of the port, read from its properties.
+    pub fn flags(&self) -> PortFlags {
+        let Some(props) = self.props() else {
+            return PortFlags::empty();
+        };
+        let is_set = |key: &str| matches!(props.parse(key), Some(Ok(true)));
+
+        let mut flags = PortFlags::empty();
+        flags.set(PortFlags::PHYSICAL, is_set(&keys::PORT_PHYSICAL));
+        flags.set(PortFlags::TERMINAL, is_set(&keys::PORT_TERMINAL));
+        flags.set(PortFlags::MONITOR, is_set(&keys::PORT_MONITOR));
+        flags.set(PortFlags::CONTROL, is_set(&keys::PORT_CONTROL));
+        flags
+    }
+
+    /// The name of the port, unique among the ports of its node.
+    pub fn name(&self) -> Option<&str> {
+        self.props()?.get(&keys::PORT_NAME)
+    }
+
+    /// The alias of the port, such as the name of the hardware port it represents.
+    pub fn alias(&self) -> Option<&str> {
+        self.props()?.get(&keys::PORT_ALIAS)
+    }
+
+    /// The id of the node the port belongs to.
+    pub fn node_id(&self) -> Option<u32> {
+        self.props()?.parse(&keys::NODE_ID)?.ok()
+    }
+
+    /// The audio channel of the port, such as `FL`.
+    pub fn audio_channel(&self) -> Option<&str> {
+        self.props()?.get(&keys::AUDIO_CHANNEL)
+    }
+
     /// Get the param infos for the port.
     pub fn params(&self) -> &[spa::param::ParamInfo] {
         let params = self.0.params;
@@ -157,6 +214,7 @@ impl fmt::Debug for PortInfoRef {
             .field("id", &self.id())
             .field("direction", &self.direction())
             .field("change-mask", &self.change_mask())
+            .field("flags", &self.flags())
             .field("props", &self.props())
             .field("params", &self.params())
             .finish()
@@ -211,12 +269,28 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// The properties of a port which patchbays use to group ports, see [`PortInfoRef::flags`].
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub struct PortFlags: u32 {
+        /// The port is a physical port of a device.
+        const PHYSICAL = 1 << 0;
+        /// Data does not flow further than the port, like at a sound card or a recorder.
+        const TERMINAL = 1 << 1;
+        /// The port monitors the data of another port.
+        const MONITOR = 1 << 2;
+        /// The port carries control data, such as MIDI.
+        const CONTROL = 1 << 3;
+    }
+}
+
 impl fmt::Debug for PortInfo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("PortInfo")
             .field("id", &self.id())
             .field("direction", &self.direction())
             .field("change-mask", &self.change_mask())
+            .field("flags", &self.flags())
             .field("props", &self.props())
             .field("params", &self.params())
             .finish()
@@ -224,22 +298,11 @@ impl fmt::Debug for PortInfo {
 }
 
 pub struct PortListener {
-    // Need to stay allocated while the listener is registered
-    #[allow(dead_code)]
-    events: Pin<Box<pw_sys::pw_port_events>>,
-    listener: Pin<Box<spa_sys::spa_hook>>,
-    #[allow(dead_code)]
-    data: Box<ListenerLocalCallbacks>,
+    _listener: listeners::Listener<pw_sys::pw_port_events, ListenerLocalCallbacks>,
 }
 
 impl Listener for PortListener {}
 
-impl Drop for PortListener {
-    fn drop(&mut self) {
-        spa::utils::hook::remove(*self.listener);
-    }
-}
-
 impl<'a> PortListenerLocalBuilder<'a> {
     #[must_use]
     pub fn info<F>(mut self, info: F) -> Self
@@ -265,10 +328,13 @@ impl<'a> PortListenerLocalBuilder<'a> {
             data: *mut c_void,
             info: *const pw_sys::pw_port_info,
         ) {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-            let info = ptr::NonNull::new(info as *mut pw_sys::pw_port_info).expect("info is NULL");
-            let info = info.cast::<PortInfoRef>().as_ref();
-            callbacks.info.as_ref().unwrap()(info);
+            crate::unwind::guard("port_events_info", || {
+                let callbacks = listeners::callback_data::<ListenerLocalCallbacks>(data);
+                let info =
+                    ptr::NonNull::new(info as *mut pw_sys::pw_port_info).expect("info is NULL");
+                let info = info.cast::<PortInfoRef>().as_ref();
+                callbacks.info.as_ref().unwrap()(info);
+            })
         }
 
         unsafe extern "C" fn port_events_param(
@@ -279,20 +345,22 @@ impl<'a> PortListenerLocalBuilder<'a> {
             next: u32,
             param: *const spa_sys::spa_pod,
         ) {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-
-            let id = spa::param::ParamType::from_raw(id);
-            let param = if !param.is_null() {
-                unsafe { Some(Pod::from_raw(param)) }
-            } else {
-                None
-            };
-
-            callbacks.param.as_ref().unwrap()(seq, id, index, next, param);
+            crate::unwind::guard("port_events_param", || {
+                let callbacks = listeners::callback_data::<ListenerLocalCallbacks>(data);
+
+                let id = spa::param::ParamType::from_raw(id);
+                let param = if !param.is_null() {
+                    unsafe { Some(Pod::from_raw(param)) }
+                } else {
+                    None
+                };
+
+                callbacks.param.as_ref().unwrap()(seq, id, index, next, param);
+            })
         }
 
         let e = unsafe {
-            let mut e: Pin<Box<pw_sys::pw_port_events>> = Box::pin(mem::zeroed());
+            let mut e: pw_sys::pw_port_events = mem::zeroed();
             e.version = pw_sys::PW_VERSION_PORT_EVENTS;
 
             if self.cbs.info.is_some() {
@@ -305,29 +373,60 @@ impl<'a> PortListenerLocalBuilder<'a> {
             e
         };
 
-        let (listener, data) = unsafe {
-            let port = &self.port.proxy.as_ptr();
-
-            let data = Box::into_raw(Box::new(self.cbs));
-            let mut listener: Pin<Box<spa_sys::spa_hook>> = Box::pin(mem::zeroed());
-            let listener_ptr: *mut spa_sys::spa_hook = listener.as_mut().get_unchecked_mut();
-
-            spa_interface_call_method!(
-                port,
-                pw_sys::pw_port_methods,
-                add_listener,
-                listener_ptr.cast(),
-                e.as_ref().get_ref(),
-                data as *mut _
-            );
-
-            (listener, Box::from_raw(data))
+        let port = self.port.proxy.as_ptr();
+        let listener = unsafe {
+            listeners::Listener::new(e, self.cbs, |hook, events, data| {
+                spa_interface_call_method!(
+                    port,
+                    pw_sys::pw_port_methods,
+                    add_listener,
+                    hook.cast(),
+                    events,
+                    data
+                );
+            })
         };
 
         PortListener {
-            events: e,
-            listener,
-            data,
+            _listener: listener,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::properties::properties;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn info_props() {
+        let props = properties! {
+            *keys::PORT_NAME => "capture_FL",
+            *keys::PORT_ALIAS => "Built-in Audio:capture_FL",
+            *keys::PORT_PHYSICAL => "true",
+            *keys::PORT_TERMINAL => "1",
+            *keys::PORT_MONITOR => "false",
+            *keys::NODE_ID => "42",
+            *keys::AUDIO_CHANNEL => "FL",
+        };
+
+        let mut raw: pw_sys::pw_port_info = unsafe { mem::zeroed() };
+        raw.direction = Direction::Output.as_raw();
+        raw.props = props.dict().as_raw_ptr();
+        let info = unsafe { &*ptr::addr_of!(raw).cast::<PortInfoRef>() };
+
+        assert_eq!(info.direction(), Some(Direction::Output));
+        assert_eq!(info.flags(), PortFlags::PHYSICAL | PortFlags::TERMINAL);
+        assert_eq!(info.name(), Some("capture_FL"));
+        assert_eq!(info.alias(), Some("Built-in Audio:capture_FL"));
+        assert_eq!(info.node_id(), Some(42));
+        assert_eq!(info.audio_channel(), Some("FL"));
+        assert!(info.params().is_empty());
+
+        raw.props = ptr::null_mut();
+        let info = unsafe { &*ptr::addr_of!(raw).cast::<PortInfoRef>() };
+        assert_eq!(info.flags(), PortFlags::empty());
+        assert_eq!(info.name(), None);
+    }
+}
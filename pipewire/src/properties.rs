@@ -1,4 +1,4 @@
-use std::{ffi::CString, fmt, mem::ManuallyDrop, ops::Deref, ptr};
+use std::{collections::HashMap, ffi::CString, fmt, mem::ManuallyDrop, ops::Deref, ptr};
 
 /// A collection of key/value pairs.
 ///
@@ -106,6 +106,16 @@ impl Properties {
     }
 }
 
+impl From<&HashMap<String, String>> for Properties {
+    fn from(map: &HashMap<String, String>) -> Self {
+        let mut properties = Self::new();
+        for (k, v) in map {
+            properties.insert(k.as_str(), v.as_str());
+        }
+        properties
+    }
+}
+
 impl AsRef<PropertiesRef> for Properties {
     fn as_ref(&self) -> &PropertiesRef {
         self.deref()
@@ -230,6 +240,39 @@ impl PropertiesRef {
     pub fn clear(&mut self) {
         unsafe { pw_sys::pw_properties_clear(self.as_raw_ptr()) }
     }
+
+    /// Compare the properties with a `new` version of them, see [`DictRef::diff`](spa::utils::dict::DictRef::diff).
+    pub fn diff<'a>(&'a self, new: &'a PropertiesRef) -> spa::utils::dict::DictDiff<'a> {
+        self.dict().diff(new.dict())
+    }
+}
+
+impl spa::utils::dict::WritableDict for PropertiesRef {
+    fn insert<K: Into<Vec<u8>>, V: Into<Vec<u8>>>(&mut self, key: K, value: V) {
+        PropertiesRef::insert(self, key, value)
+    }
+
+    fn remove<K: Into<Vec<u8>>>(&mut self, key: K) {
+        PropertiesRef::remove(self, key)
+    }
+
+    fn clear(&mut self) {
+        PropertiesRef::clear(self)
+    }
+}
+
+impl spa::utils::dict::WritableDict for Properties {
+    fn insert<K: Into<Vec<u8>>, V: Into<Vec<u8>>>(&mut self, key: K, value: V) {
+        PropertiesRef::insert(self, key, value)
+    }
+
+    fn remove<K: Into<Vec<u8>>>(&mut self, key: K) {
+        PropertiesRef::remove(self, key)
+    }
+
+    fn clear(&mut self) {
+        PropertiesRef::clear(self)
+    }
 }
 
 impl AsRef<spa::utils::dict::DictRef> for PropertiesRef {
@@ -315,6 +358,36 @@ mod tests {
         assert_eq!(props.dict().get("K1"), Some("V1"));
     }
 
+    #[test]
+    fn from_hashmap() {
+        use spa::prelude::*;
+
+        let mut map = HashMap::new();
+        map.insert("K0".to_string(), "V0".to_string());
+        map.insert("K1".to_string(), "V1".to_string());
+
+        let props = Properties::from(&map);
+        assert_eq!(props.dict().len(), 2);
+        assert_eq!(props.dict().get("K0"), Some("V0"));
+        assert_eq!(props.to_hashmap(), map);
+    }
+
+    #[test]
+    fn diff() {
+        let old = properties! {
+            "K0" => "V0",
+            "K1" => "V1"
+        };
+        let mut new = old.clone();
+        new.insert("K1", "V2");
+        new.insert("K2", "V3");
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.added, [("K2", "V3")]);
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.changed, [("K1", "V1", "V2")]);
+    }
+
     #[test]
     fn properties_ref() {
         let props = properties! {
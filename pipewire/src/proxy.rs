@@ -7,7 +7,9 @@ use std::mem;
 use std::pin::Pin;
 use std::{ffi::CStr, ptr};
 
-use crate::{types::ObjectType, Error};
+use spa::utils::result::{AsyncSeq, SpaResult};
+
+use crate::{error::ResultExt, loop_::LoopRef, types::ObjectType, Error};
 
 pub struct Proxy {
     ptr: ptr::NonNull<pw_sys::pw_proxy>,
@@ -19,7 +21,8 @@ impl Proxy {
         Proxy { ptr }
     }
 
-    pub(crate) fn as_ptr(&self) -> *mut pw_sys::pw_proxy {
+    /// The raw `pw_proxy`, which is also the interface of the object it represents.
+    pub fn as_ptr(&self) -> *mut pw_sys::pw_proxy {
         self.ptr.as_ptr()
     }
 
@@ -34,6 +37,26 @@ impl Proxy {
         unsafe { pw_sys::pw_proxy_get_id(self.as_ptr()) }
     }
 
+    /// Get the global id this proxy is bound to, or [`None`] if it is not bound yet.
+    pub fn bound_id(&self) -> Option<u32> {
+        let id = unsafe { pw_sys::pw_proxy_get_bound_id(self.as_ptr()) };
+        (id != spa_sys::SPA_ID_INVALID).then_some(id)
+    }
+
+    /// Ask the server to emit the `done` event on this proxy once it processed all the methods
+    /// sent before.
+    ///
+    /// The returned sequence number is the one received by the [`done`](ProxyListenerLocalBuilder::done)
+    /// callback, see [`PendingSeqTracker`](crate::pending::PendingSeqTracker).
+    pub fn sync(&self, seq: i32) -> Result<AsyncSeq, Error> {
+        let res = unsafe { pw_sys::pw_proxy_sync(self.as_ptr(), seq) };
+
+        let res = SpaResult::from_c(res)
+            .into_async_result()
+            .context("sync the proxy")?;
+        Ok(res)
+    }
+
     /// Get the type of the proxy as well as it's version.
     pub fn get_type(&self) -> (ObjectType, u32) {
         unsafe {
@@ -42,7 +65,7 @@ impl Proxy {
             let proxy_type = CStr::from_ptr(proxy_type);
 
             (
-                ObjectType::from_str(proxy_type.to_str().expect("invalid proxy type")),
+                ObjectType::from_interface_name(proxy_type.to_str().expect("invalid proxy type")),
                 version,
             )
         }
@@ -62,6 +85,16 @@ impl Proxy {
     }
 }
 
+impl Proxy {
+    /// Destroy the proxy in the next iteration of `loop_`.
+    ///
+    /// This can be called from a callback of the proxy or of one of its listeners, see
+    /// [`LoopRef::drop_later`](crate::loop_::LoopRef::drop_later).
+    pub fn destroy_later(self, loop_: &LoopRef) {
+        loop_.drop_later(self);
+    }
+}
+
 impl Drop for Proxy {
     fn drop(&mut self) {
         unsafe {
@@ -90,6 +123,14 @@ pub trait ProxyT {
     where
         Self: Sized;
 
+    /// The version of the interface implemented by the wrapper, used when binding objects.
+    fn client_version() -> u32
+    where
+        Self: Sized,
+    {
+        Self::type_().client_version()
+    }
+
     fn upcast(self) -> Proxy;
     fn upcast_ref(&self) -> &Proxy;
 
@@ -104,10 +145,29 @@ pub trait ProxyT {
     unsafe fn from_proxy_unchecked(proxy: Proxy) -> Self
     where
         Self: Sized;
+
+    /// Destroy the proxy in the next iteration of `loop_`, see [`Proxy::destroy_later`].
+    fn destroy_later(self, loop_: &LoopRef)
+    where
+        Self: Sized + 'static,
+    {
+        loop_.drop_later(self);
+    }
 }
 
 // Trait implemented by listener on high level proxy wrappers.
-pub trait Listener {}
+pub trait Listener {
+    /// Unregister the listener in the next iteration of `loop_`.
+    ///
+    /// This can be called from a callback of the listener itself, see
+    /// [`LoopRef::drop_later`](crate::loop_::LoopRef::drop_later).
+    fn unregister_later(self, loop_: &LoopRef)
+    where
+        Self: Sized + 'static,
+    {
+        loop_.drop_later(self);
+    }
+}
 
 pub struct ProxyListener {
     // Need to stay allocated while the listener is registered
@@ -129,6 +189,9 @@ impl Drop for ProxyListener {
 struct ListenerLocalCallbacks {
     destroy: Option<Box<dyn Fn()>>,
     bound: Option<Box<dyn Fn(u32)>>,
+    #[cfg(feature = "v0_3_77")]
+    #[allow(clippy::type_complexity)]
+    bound_props: Option<Box<dyn Fn(u32, &spa::utils::dict::DictRef)>>,
     removed: Option<Box<dyn Fn()>>,
     done: Option<Box<dyn Fn(i32)>>,
     #[allow(clippy::type_complexity)]
@@ -159,6 +222,17 @@ impl<'a> ProxyListenerLocalBuilder<'a> {
         self
     }
 
+    /// Emitted when the proxy is bound to a global, with the global id and its properties.
+    #[cfg(feature = "v0_3_77")]
+    #[must_use]
+    pub fn bound_props<F>(mut self, bound_props: F) -> Self
+    where
+        F: Fn(u32, &spa::utils::dict::DictRef) + 'static,
+    {
+        self.cbs.bound_props = Some(Box::new(bound_props));
+        self
+    }
+
     #[must_use]
     pub fn removed<F>(mut self, removed: F) -> Self
     where
@@ -189,23 +263,46 @@ impl<'a> ProxyListenerLocalBuilder<'a> {
     #[must_use]
     pub fn register(self) -> ProxyListener {
         unsafe extern "C" fn proxy_destroy(data: *mut c_void) {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-            callbacks.destroy.as_ref().unwrap()();
+            crate::unwind::guard("proxy_destroy", || {
+                let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
+                callbacks.destroy.as_ref().unwrap()();
+            })
         }
 
         unsafe extern "C" fn proxy_bound(data: *mut c_void, global_id: u32) {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-            callbacks.bound.as_ref().unwrap()(global_id);
+            crate::unwind::guard("proxy_bound", || {
+                let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
+                callbacks.bound.as_ref().unwrap()(global_id);
+            })
+        }
+
+        #[cfg(feature = "v0_3_77")]
+        unsafe extern "C" fn proxy_bound_props(
+            data: *mut c_void,
+            global_id: u32,
+            props: *const spa_sys::spa_dict,
+        ) {
+            crate::unwind::guard("proxy_bound_props", || {
+                let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
+                let props =
+                    ptr::NonNull::new(props as *mut spa_sys::spa_dict).expect("props is NULL");
+                let props = props.cast::<spa::utils::dict::DictRef>().as_ref();
+                callbacks.bound_props.as_ref().unwrap()(global_id, props);
+            })
         }
 
         unsafe extern "C" fn proxy_removed(data: *mut c_void) {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-            callbacks.removed.as_ref().unwrap()();
+            crate::unwind::guard("proxy_removed", || {
+                let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
+                callbacks.removed.as_ref().unwrap()();
+            })
         }
 
         unsafe extern "C" fn proxy_done(data: *mut c_void, seq: i32) {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-            callbacks.done.as_ref().unwrap()(seq);
+            crate::unwind::guard("proxy_done", || {
+                let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
+                callbacks.done.as_ref().unwrap()(seq);
+            })
         }
 
         unsafe extern "C" fn proxy_error(
@@ -214,9 +311,11 @@ impl<'a> ProxyListenerLocalBuilder<'a> {
             res: i32,
             message: *const c_char,
         ) {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-            let message = CStr::from_ptr(message).to_str().unwrap();
-            callbacks.error.as_ref().unwrap()(seq, res, message);
+            crate::unwind::guard("proxy_error", || {
+                let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
+                let message = CStr::from_ptr(message).to_str().unwrap();
+                callbacks.error.as_ref().unwrap()(seq, res, message);
+            })
         }
 
         let e = unsafe {
@@ -231,6 +330,11 @@ impl<'a> ProxyListenerLocalBuilder<'a> {
                 e.bound = Some(proxy_bound);
             }
 
+            #[cfg(feature = "v0_3_77")]
+            if self.cbs.bound_props.is_some() {
+                e.bound_props = Some(proxy_bound_props);
+            }
+
             if self.cbs.removed.is_some() {
                 e.removed = Some(proxy_removed);
             }
@@ -271,3 +375,133 @@ impl<'a> ProxyListenerLocalBuilder<'a> {
         }
     }
 }
+
+/// Generate a proxy wrapper for an interface which is not bound by this crate, such as the
+/// interfaces of extension modules.
+///
+/// Given the interface name, its version and the C structs of its methods and events, this
+/// generates a struct implementing [`ProxyT`], which can be bound with
+/// [`Registry::bind`](crate::registry::Registry::bind) or created with
+/// [`Core::create_object`](crate::core::Core::create_object), along with:
+/// - `interface()`, returning the [`VersionedInterface`](spa::utils::interface::VersionedInterface)
+///   the methods are called through,
+/// - `add_listener_raw(events, data)`, registering the C event callbacks, which receive `data`
+///   through [`listeners::callback_data`](crate::listeners::callback_data).
+///
+/// The typed methods are then written on top of them, like the wrappers of this crate, with
+/// [`interface_methods!`](spa::interface_methods) wrapping the C methods:
+///
+/// ```no_run
+/// use pipewire::{
+///     proxy::proxy_interface,
+///     spa::{
+///         self,
+///         utils::result::{AsyncSeq, SpaResult},
+///     },
+///     sys, Error,
+/// };
+///
+/// proxy_interface! {
+///     /// A node, as an extension interface would be bound.
+///     pub struct RawNode {
+///         interface: "PipeWire:Interface:Node",
+///         version: sys::PW_VERSION_NODE,
+///         methods: sys::pw_node_methods,
+///         events: sys::pw_node_events,
+///     }
+/// }
+///
+/// spa::interface_methods! {
+///     trait RawNodeMethods for sys::pw_node_methods {
+///         fn enum_params(&self, seq: i32, id: u32, start: u32, num: u32) -> SpaResult {
+///             since 0;
+///             unsafe { call(seq, id, start, num, std::ptr::null()) }
+///         }
+///     }
+/// }
+///
+/// impl RawNode {
+///     pub fn enum_all_params(&self) -> Result<AsyncSeq, Error> {
+///         let res = self
+///             .interface()
+///             .enum_params(0, u32::MAX, 0, u32::MAX)
+///             .into_async_result()?;
+///         Ok(res)
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! __proxy_interface__ {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident {
+            interface: $interface:expr,
+            version: $version:expr,
+            methods: $methods:ty,
+            events: $events:ty $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(Debug)]
+        $vis struct $name {
+            proxy: $crate::proxy::Proxy,
+        }
+
+        impl $name {
+            /// The methods of the interface.
+            $vis fn interface(&self) -> $crate::spa::utils::interface::VersionedInterface<$methods> {
+                // Safety: the proxy implements the interface, and stays valid as long as `self`.
+                unsafe {
+                    $crate::spa::utils::interface::VersionedInterface::new(
+                        self.proxy.as_ptr().cast(),
+                    )
+                }
+            }
+
+            /// Register the event callbacks of `events`, which receive `data`.
+            ///
+            /// # Safety
+            /// The callbacks must only access their data through
+            /// [`callback_data`]($crate::listeners::callback_data) or
+            /// [`callback_data_mut`]($crate::listeners::callback_data_mut) with the type `D`.
+            $vis unsafe fn add_listener_raw<D>(
+                &self,
+                events: $events,
+                data: D,
+            ) -> $crate::listeners::Listener<$events, D> {
+                let interface = self.interface();
+                $crate::listeners::Listener::new(events, data, |hook, events, data| {
+                    interface.call(
+                        0,
+                        |methods| methods.add_listener,
+                        |f, object| f(object, hook.cast(), events, data),
+                    );
+                })
+            }
+        }
+
+        impl $crate::proxy::ProxyT for $name {
+            fn type_() -> $crate::types::ObjectType {
+                $crate::types::ObjectType::from_interface_name($interface)
+            }
+
+            fn client_version() -> u32 {
+                $version
+            }
+
+            fn upcast(self) -> $crate::proxy::Proxy {
+                self.proxy
+            }
+
+            fn upcast_ref(&self) -> &$crate::proxy::Proxy {
+                &self.proxy
+            }
+
+            unsafe fn from_proxy_unchecked(proxy: $crate::proxy::Proxy) -> Self {
+                Self { proxy }
+            }
+        }
+    };
+}
+
+pub use __proxy_interface__ as proxy_interface;
@@ -4,6 +4,8 @@
 use libc::{c_char, c_void};
 
 use std::{
+    cell::RefCell,
+    collections::HashSet,
     ffi::{CStr, CString},
     mem,
     pin::Pin,
@@ -41,13 +43,42 @@ impl Registry {
         }
     }
 
+    /// Add a listener only notified of the globals matching `filter`.
+    ///
+    /// The filtering is done on the client side, as the registry announces all the globals.
+    /// The `global_remove` callback is only called for globals that matched the filter.
+    ///
+    /// ```no_run
+    /// use pipewire::{registry::GlobalFilter, types::ObjectType};
+    /// # fn example(registry: &pipewire::registry::Registry) {
+    /// let filter = GlobalFilter::new()
+    ///     .type_(ObjectType::Node)
+    ///     .prop("media.class", "Audio/Sink");
+    /// let _listener = registry
+    ///     .add_filtered_listener_local(filter)
+    ///     .global(|global| println!("new sink: {}", global.id))
+    ///     .global_remove(|id| println!("sink {id} removed"))
+    ///     .register();
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn add_filtered_listener_local(&self, filter: GlobalFilter) -> ListenerLocalBuilder {
+        ListenerLocalBuilder {
+            registry: self,
+            cbs: ListenerLocalCallbacks {
+                filter: Some(filter),
+                ..Default::default()
+            },
+        }
+    }
+
     pub fn bind<T: ProxyT, P: AsRef<spa::utils::dict::DictRef>>(
         &self,
         object: &GlobalObject<P>,
     ) -> Result<T, Error> {
         let proxy = unsafe {
             let type_ = CString::new(object.type_.to_str()).unwrap();
-            let version = object.type_.client_version();
+            let version = T::client_version();
 
             let proxy = spa::spa_interface_call_method!(
                 self.as_ptr(),
@@ -97,6 +128,9 @@ type GlobalRemoveCallback = dyn Fn(u32);
 struct ListenerLocalCallbacks {
     global: Option<Box<GlobalCallback>>,
     global_remove: Option<Box<GlobalRemoveCallback>>,
+    filter: Option<GlobalFilter>,
+    /// The ids of the globals which matched `filter`.
+    matched: RefCell<HashSet<u32>>,
 }
 
 pub struct ListenerLocalBuilder<'a> {
@@ -113,6 +147,16 @@ pub struct Listener {
     data: Box<ListenerLocalCallbacks>,
 }
 
+impl Listener {
+    /// Unregister the listener in the next iteration of `loop_`.
+    ///
+    /// This can be called from a callback of the listener itself, see
+    /// [`LoopRef::drop_later`](crate::loop_::LoopRef::drop_later).
+    pub fn unregister_later(self, loop_: &crate::loop_::LoopRef) {
+        loop_.drop_later(self);
+    }
+}
+
 impl Drop for Listener {
     fn drop(&mut self) {
         spa::utils::hook::remove(*self.listener);
@@ -148,22 +192,38 @@ impl<'a> ListenerLocalBuilder<'a> {
             version: u32,
             props: *const spa_sys::spa_dict,
         ) {
-            let type_ = CStr::from_ptr(type_).to_str().unwrap();
-            let obj = GlobalObject::new(id, permissions, type_, version, props);
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-            callbacks.global.as_ref().unwrap()(&obj);
+            crate::unwind::guard("registry_events_global", || {
+                let type_ = CStr::from_ptr(type_).to_str().unwrap();
+                let obj = GlobalObject::new(id, permissions, type_, version, props);
+                let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
+                if let Some(filter) = &callbacks.filter {
+                    if !filter.matches(&obj) {
+                        return;
+                    }
+                    callbacks.matched.borrow_mut().insert(id);
+                }
+                if let Some(global) = &callbacks.global {
+                    global(&obj);
+                }
+            })
         }
 
         unsafe extern "C" fn registry_events_global_remove(data: *mut c_void, id: u32) {
-            let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
-            callbacks.global_remove.as_ref().unwrap()(id);
+            crate::unwind::guard("registry_events_global_remove", || {
+                let callbacks = (data as *mut ListenerLocalCallbacks).as_ref().unwrap();
+                if callbacks.filter.is_some() && !callbacks.matched.borrow_mut().remove(&id) {
+                    return;
+                }
+                callbacks.global_remove.as_ref().unwrap()(id);
+            })
         }
 
         let e = unsafe {
             let mut e: Pin<Box<pw_sys::pw_registry_events>> = Box::pin(mem::zeroed());
             e.version = pw_sys::PW_VERSION_REGISTRY_EVENTS;
 
-            if self.cbs.global.is_some() {
+            // The filter has to see the globals to know which removals to report.
+            if self.cbs.global.is_some() || self.cbs.filter.is_some() {
                 e.global = Some(registry_events_global);
             }
             if self.cbs.global_remove.is_some() {
@@ -199,7 +259,61 @@ impl<'a> ListenerLocalBuilder<'a> {
     }
 }
 
-#[derive(Debug)]
+/// A filter of the globals announced by the registry, see
+/// [`Registry::add_filtered_listener_local`].
+///
+/// A global matches if it has one of the types of the filter, or any type if none was added, and
+/// all the properties of the filter.
+#[derive(Debug, Clone, Default)]
+pub struct GlobalFilter {
+    types: Vec<ObjectType>,
+    props: Vec<(String, String)>,
+}
+
+impl GlobalFilter {
+    /// A filter matching all the globals.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match the globals of type `type_`, in addition to the types already added.
+    #[must_use]
+    pub fn type_(mut self, type_: ObjectType) -> Self {
+        self.types.push(type_);
+        self
+    }
+
+    /// Only match the globals whose property `key` is `value`, such as `media.class` being
+    /// `Audio/Sink`.
+    #[must_use]
+    pub fn prop(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.props.push((key.into(), value.into()));
+        self
+    }
+
+    /// Whether `object` matches the filter.
+    pub fn matches<P: AsRef<spa::utils::dict::DictRef>>(&self, object: &GlobalObject<P>) -> bool {
+        if !self.types.is_empty() && !self.types.contains(&object.type_) {
+            return false;
+        }
+        if self.props.is_empty() {
+            return true;
+        }
+
+        let Some(props) = object.props() else {
+            return false;
+        };
+        self.props
+            .iter()
+            .all(|(key, value)| props.get(key) == Some(value.as_str()))
+    }
+}
+
+/// A global object announced by the registry.
+///
+/// The registry passes a `GlobalObject<&DictRef>` borrowing the properties of the event to its
+/// global callback. Use [`GlobalObject::to_owned`] to keep the object after the callback.
+#[derive(Debug, Clone)]
 pub struct GlobalObject<P: AsRef<spa::utils::dict::DictRef>> {
     pub id: u32,
     pub permissions: PermissionFlags,
@@ -216,7 +330,7 @@ impl GlobalObject<&spa::utils::dict::DictRef> {
         version: u32,
         props: *const spa_sys::spa_dict,
     ) -> Self {
-        let type_ = ObjectType::from_str(type_);
+        let type_ = ObjectType::from_interface_name(type_);
         let permissions = PermissionFlags::from_bits_retain(permissions);
         let props = ptr::NonNull::new(props.cast_mut())
             .map(|ptr| ptr.cast::<spa::utils::dict::DictRef>().as_ref());
@@ -232,6 +346,8 @@ impl GlobalObject<&spa::utils::dict::DictRef> {
 }
 
 impl<P: AsRef<spa::utils::dict::DictRef>> GlobalObject<P> {
+    /// Copy the object along with its properties, so it can be stored, such as in a cache of
+    /// the globals of the registry.
     pub fn to_owned(&self) -> GlobalObject<Properties> {
         GlobalObject {
             id: self.id,
@@ -244,6 +360,66 @@ impl<P: AsRef<spa::utils::dict::DictRef>> GlobalObject<P> {
                 .map(|props| Properties::from_dict(props.as_ref())),
         }
     }
+
+    /// The properties of the object, whether they are borrowed or owned.
+    pub fn props(&self) -> Option<&spa::utils::dict::DictRef> {
+        self.props.as_ref().map(AsRef::as_ref)
+    }
+
+    /// The identity of the object, made of its id and its `object.serial` property.
+    pub fn global_id(&self) -> GlobalId {
+        // The key is only defined by the bindings of PipeWire 0.3.41 and later.
+        let serial = self
+            .props()
+            .and_then(|props| props.get("object.serial"))
+            .and_then(|serial| serial.parse().ok());
+
+        GlobalId::new(self.id, serial)
+    }
+}
+
+/// The identity of a global object, to use as the key of caches of globals.
+///
+/// The server reuses the id of a global once it is removed, so an id alone can refer to a new
+/// object which replaced the one that was cached. The serial of an object is never reused, and is
+/// known for the globals announced by PipeWire 0.3.41 and later.
+///
+/// Two ids are equal if both their id and serial are. Use [`GlobalId::same_object`] to compare
+/// ids whose serial may be unknown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GlobalId {
+    pub id: u32,
+    pub serial: Option<u64>,
+}
+
+impl GlobalId {
+    pub fn new(id: u32, serial: Option<u64>) -> Self {
+        Self { id, serial }
+    }
+
+    /// Whether `self` and `other` can be the same object: they have the same id, and the same
+    /// serial if both serials are known.
+    pub fn same_object(&self, other: &GlobalId) -> bool {
+        self.id == other.id
+            && match (self.serial, other.serial) {
+                (Some(serial), Some(other)) => serial == other,
+                _ => true,
+            }
+    }
+
+    /// Whether `other` is a different object reusing the id of `self`.
+    pub fn is_reused_by(&self, other: &GlobalId) -> bool {
+        self.id == other.id && !self.same_object(other)
+    }
+}
+
+impl std::fmt::Display for GlobalId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.serial {
+            Some(serial) => write!(f, "{} (serial {serial})", self.id),
+            None => write!(f, "{}", self.id),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -252,17 +428,103 @@ mod tests {
     #[test]
     fn set_object_type() {
         assert_eq!(
-            ObjectType::from_str("PipeWire:Interface:Client"),
+            ObjectType::from_interface_name("PipeWire:Interface:Client"),
             ObjectType::Client
         );
         assert_eq!(ObjectType::Client.to_str(), "PipeWire:Interface:Client");
         assert_eq!(ObjectType::Client.client_version(), 3);
 
         let o = ObjectType::Other("PipeWire:Interface:Badger".to_string());
-        assert_eq!(ObjectType::from_str("PipeWire:Interface:Badger"), o);
+        assert_eq!(
+            ObjectType::from_interface_name("PipeWire:Interface:Badger"),
+            o
+        );
         assert_eq!(o.to_str(), "PipeWire:Interface:Badger");
     }
 
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn global_to_owned() {
+        let props = crate::properties::properties! {
+            "node.name" => "sink",
+        };
+        let owned = {
+            let global = GlobalObject {
+                id: 42,
+                permissions: PermissionFlags::R | PermissionFlags::X,
+                type_: ObjectType::Node,
+                version: 3,
+                props: Some(props.dict()),
+            };
+            global.to_owned()
+        };
+        drop(props);
+
+        let cached = owned.clone();
+        assert_eq!(cached.id, 42);
+        assert_eq!(cached.permissions, PermissionFlags::R | PermissionFlags::X);
+        assert_eq!(cached.type_, ObjectType::Node);
+        assert_eq!(cached.version, 3);
+        assert_eq!(cached.props().unwrap().get("node.name"), Some("sink"));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn global_id() {
+        let props = crate::properties::properties! {
+            "object.serial" => "1234",
+        };
+        let global = GlobalObject {
+            id: 42,
+            permissions: PermissionFlags::R,
+            type_: ObjectType::Node,
+            version: 3,
+            props: Some(props.dict()),
+        };
+        let id = global.global_id();
+        assert_eq!(id, GlobalId::new(42, Some(1234)));
+        assert_eq!(id.to_string(), "42 (serial 1234)");
+
+        let reused = GlobalId::new(42, Some(1300));
+        assert!(!id.same_object(&reused));
+        assert!(id.is_reused_by(&reused));
+        assert!(id.same_object(&GlobalId::new(42, None)));
+        assert!(!id.same_object(&GlobalId::new(43, Some(1234))));
+        assert!(!id.is_reused_by(&GlobalId::new(43, Some(1300))));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn global_filter() {
+        let props = crate::properties::properties! {
+            "media.class" => "Audio/Sink",
+        };
+        let global = |type_, props| GlobalObject {
+            id: 42,
+            permissions: PermissionFlags::R,
+            type_,
+            version: 3,
+            props,
+        };
+        let sink = global(ObjectType::Node, Some(props.dict()));
+        let client = global(ObjectType::Client, None);
+
+        assert!(GlobalFilter::new().matches(&sink));
+        assert!(GlobalFilter::new().matches(&client));
+
+        let nodes = GlobalFilter::new().type_(ObjectType::Node);
+        assert!(nodes.matches(&sink));
+        assert!(!nodes.matches(&client));
+        assert!(nodes.clone().type_(ObjectType::Client).matches(&client));
+
+        let sinks = nodes.prop("media.class", "Audio/Sink");
+        assert!(sinks.matches(&sink));
+        assert!(!sinks.matches(&global(ObjectType::Node, None)));
+        assert!(!GlobalFilter::new()
+            .prop("media.class", "Audio/Source")
+            .matches(&sink));
+    }
+
     #[test]
     #[should_panic(expected = "Invalid object type")]
     fn client_version_panic() {
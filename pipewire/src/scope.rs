@@ -0,0 +1,298 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Listeners borrowing data of the stack for the duration of a scope.
+//!
+//! The callbacks of the listeners must be `'static`, because a listener can be kept for as long
+//! as wanted, which usually means sharing their state through `Rc<RefCell<_>>`.
+//! [`CoreRef::with_listeners`](crate::core::CoreRef::with_listeners) runs a closure with a
+//! [`ListenerScope`] instead, similarly to [`std::thread::scope`]:
+//! - [`ListenerScope::borrow`] and [`ListenerScope::borrow_mut`] turn references to data living
+//!   outside of the scope into `'static` handles, which can be moved into the callbacks,
+//! - [`ListenerScope::hold`] keeps a listener until the end of the scope, where it is removed.
+//!
+//! The user data of stream listeners can also borrow such data directly, by registering them
+//! with [`ListenerLocalBuilder::register_scoped`](crate::stream::ListenerLocalBuilder::register_scoped).
+//!
+//! Once the scope has ended, the handles don't give access to the data anymore, so the
+//! callbacks of listeners which were not held by the scope can't access data which has been
+//! dropped or borrowed again.
+//!
+//! ```no_run
+//! use pipewire::{context::Context, main_loop::MainLoop};
+//!
+//! pipewire::init();
+//! let mainloop = MainLoop::new(None)?;
+//! let context = Context::new(&mainloop)?;
+//! let core = context.connect(None)?;
+//! let registry = core.get_registry()?;
+//!
+//! let mut globals = Vec::new();
+//! core.with_listeners(|scope| {
+//!     let globals = scope.borrow_mut(&mut globals);
+//!     scope.hold(
+//!         registry
+//!             .add_listener_local()
+//!             .global(move |global| {
+//!                 globals.with_mut(|globals| globals.push(global.id));
+//!             })
+//!             .register(),
+//!     );
+//!
+//!     // Iterate the loop until the registry announced the globals...
+//! });
+//! println!("{globals:?}");
+//! # Ok::<(), pipewire::Error>(())
+//! ```
+
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    marker::PhantomData,
+    ptr::NonNull,
+    rc::Rc,
+};
+
+/// The state of a scope shared with its handles.
+#[derive(Debug)]
+struct ScopeState {
+    /// Whether the scope is still running, and the borrowed data can be accessed.
+    alive: Cell<bool>,
+}
+
+/// Anything kept alive until the end of the scope.
+trait Held {}
+
+impl<T> Held for T {}
+
+/// A scope in which listeners can borrow data, see the [module documentation](self).
+pub struct ListenerScope<'env> {
+    state: Rc<ScopeState>,
+    held: RefCell<Vec<Box<dyn Held + 'env>>>,
+    // Invariant over 'env, like `std::thread::Scope`.
+    _env: PhantomData<&'env mut &'env ()>,
+}
+
+impl<'env> ListenerScope<'env> {
+    /// Run `f` with a new scope, removing the listeners it holds before returning.
+    pub(crate) fn run<R>(f: impl FnOnce(&ListenerScope<'env>) -> R) -> R {
+        let scope = ListenerScope {
+            state: Rc::new(ScopeState {
+                alive: Cell::new(true),
+            }),
+            held: RefCell::new(Vec::new()),
+            _env: PhantomData,
+        };
+
+        // The scope is ended when dropped, even if `f` panics.
+        f(&scope)
+    }
+
+    /// Give access to `data` to the callbacks of listeners, until the end of the scope.
+    pub fn borrow<T: ?Sized>(&self, data: &'env T) -> Scoped<T> {
+        Scoped {
+            state: self.state.clone(),
+            data: NonNull::from(data),
+        }
+    }
+
+    /// Give mutable access to `data` to the callbacks of listeners, until the end of the scope.
+    pub fn borrow_mut<T: ?Sized>(&self, data: &'env mut T) -> ScopedMut<T> {
+        ScopedMut {
+            state: self.state.clone(),
+            in_use: Rc::new(Cell::new(false)),
+            data: NonNull::from(data),
+            _invariant: PhantomData,
+        }
+    }
+
+    /// Keep `listener` until the end of the scope, where it is dropped.
+    ///
+    /// Listeners are dropped in the reverse order in which they were added to the scope.
+    pub fn hold<L: 'env>(&self, listener: L) {
+        self.held.borrow_mut().push(Box::new(listener));
+    }
+}
+
+impl Drop for ListenerScope<'_> {
+    fn drop(&mut self) {
+        let held = self.held.get_mut();
+        while let Some(listener) = held.pop() {
+            drop(listener);
+        }
+        self.state.alive.set(false);
+    }
+}
+
+impl fmt::Debug for ListenerScope<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ListenerScope")
+            .field("alive", &self.state.alive.get())
+            .field("held", &self.held.borrow().len())
+            .finish()
+    }
+}
+
+/// A shared reference to data borrowed by a [`ListenerScope`], usable until the end of the scope.
+pub struct Scoped<T: ?Sized> {
+    state: Rc<ScopeState>,
+    data: NonNull<T>,
+}
+
+impl<T: ?Sized> Scoped<T> {
+    /// Call `f` with the data, or return `None` if the scope has ended.
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> Option<R> {
+        // Safety: the data is borrowed for the whole scope, which hasn't ended.
+        self.state
+            .alive
+            .get()
+            .then(|| f(unsafe { self.data.as_ref() }))
+    }
+
+    /// Whether the scope has ended, so the data can't be accessed anymore.
+    pub fn is_expired(&self) -> bool {
+        !self.state.alive.get()
+    }
+}
+
+impl<T: ?Sized> Clone for Scoped<T> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            data: self.data,
+        }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for Scoped<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scoped")
+            .field("expired", &self.is_expired())
+            .finish()
+    }
+}
+
+/// A mutable reference to data borrowed by a [`ListenerScope`], usable until the end of the scope.
+///
+/// The handle can be cloned to be used by several callbacks, but the data can't be accessed
+/// again while it is being accessed, such as by a callback emitted from another one.
+///
+/// Like `&mut T`, the handle is invariant over `T`, so the data can't be given references which
+/// live shorter than it:
+///
+/// ```compile_fail
+/// use pipewire::{core::CoreRef, scope::ScopedMut};
+///
+/// fn push_local(core: &CoreRef, names: &mut Vec<&'static str>) {
+///     core.with_listeners(|scope| {
+///         let names: ScopedMut<Vec<&str>> = scope.borrow_mut(names);
+///         let local = String::from("local");
+///         names.with_mut(|names| names.push(&local));
+///     });
+///     // `names` would hold a reference to `local` here.
+/// }
+/// ```
+pub struct ScopedMut<T: ?Sized> {
+    state: Rc<ScopeState>,
+    in_use: Rc<Cell<bool>>,
+    data: NonNull<T>,
+    // Invariant over T, like `&mut T`.
+    _invariant: PhantomData<*mut T>,
+}
+
+impl<T: ?Sized> ScopedMut<T> {
+    /// Call `f` with the data, or return `None` if the scope has ended.
+    ///
+    /// # Panics
+    /// If the data is already being accessed through this handle or one of its clones.
+    pub fn with_mut<R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        if !self.state.alive.get() {
+            return None;
+        }
+        assert!(
+            !self.in_use.replace(true),
+            "scoped data is already being accessed"
+        );
+
+        struct Release<'a>(&'a Cell<bool>);
+        impl Drop for Release<'_> {
+            fn drop(&mut self) {
+                self.0.set(false);
+            }
+        }
+        let _release = Release(&self.in_use);
+
+        // Safety: the data is borrowed mutably for the whole scope, which hasn't ended,
+        // and this is the only access to it.
+        let mut data = self.data;
+        Some(f(unsafe { data.as_mut() }))
+    }
+
+    /// Whether the scope has ended, so the data can't be accessed anymore.
+    pub fn is_expired(&self) -> bool {
+        !self.state.alive.get()
+    }
+}
+
+impl<T: ?Sized> Clone for ScopedMut<T> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            in_use: self.in_use.clone(),
+            data: self.data,
+            _invariant: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for ScopedMut<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScopedMut")
+            .field("expired", &self.is_expired())
+            .field("in_use", &self.in_use.get())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scoped() {
+        let mut count = 0;
+        let name = String::from("scope");
+
+        let (shared, mutable) = ListenerScope::run(|scope| {
+            let shared = scope.borrow(name.as_str());
+            let mutable = scope.borrow_mut(&mut count);
+            let callback = {
+                let mutable = mutable.clone();
+                move || mutable.with_mut(|count| *count += 1)
+            };
+            scope.hold(callback.clone());
+
+            assert_eq!(shared.with(str::len), Some(5));
+            assert_eq!(callback(), Some(()));
+            assert_eq!(callback(), Some(()));
+
+            (shared, mutable)
+        });
+
+        assert!(shared.is_expired());
+        assert_eq!(shared.with(str::len), None);
+        assert_eq!(mutable.with_mut(|count| *count += 1), None);
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "already being accessed")]
+    fn reentrant() {
+        let mut count = 0;
+        ListenerScope::run(|scope| {
+            let mutable = scope.borrow_mut(&mut count);
+            let inner = mutable.clone();
+            mutable.with_mut(|_| inner.with_mut(|count| *count += 1));
+        });
+    }
+}
@@ -0,0 +1,396 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Helpers following the policy of the session manager.
+//!
+//! The session manager (usually WirePlumber) stores the default nodes in the `default` [`Metadata`] object.
+//! [`DefaultNodesTracker`] follows this metadata and the nodes announced by the registry,
+//! so that applications such as volume OSDs or tray applets know which node to control.
+//!
+//! ```no_run
+//! use std::rc::Rc;
+//! use pipewire::{context::Context, main_loop::MainLoop, session::{DefaultNode, DefaultNodesTracker}};
+//!
+//! let mainloop = MainLoop::new(None)?;
+//! let context = Context::new(&mainloop)?;
+//! let core = context.connect(None)?;
+//! let registry = Rc::new(core.get_registry()?);
+//!
+//! let tracker = DefaultNodesTracker::new(&registry, |default, name, id| {
+//!     println!("{default:?} is now {name:?} (node {id:?})");
+//! });
+//!
+//! mainloop.run();
+//! # Ok::<(), pipewire::Error>(())
+//! ```
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::CStr,
+    mem,
+    os::raw::c_char,
+    rc::{Rc, Weak},
+};
+
+use crate::{
+    core::PW_ID_CORE,
+    metadata::{Metadata, MetadataListener},
+    registry::{self, GlobalObject, Registry},
+    types::ObjectType,
+};
+
+/// A default node chosen by the session manager.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DefaultNode {
+    /// The sink currently used by default, `default.audio.sink`.
+    AudioSink,
+    /// The source currently used by default, `default.audio.source`.
+    AudioSource,
+    /// The video source currently used by default, `default.video.source`.
+    VideoSource,
+    /// The sink chosen by the user, `default.configured.audio.sink`.
+    ///
+    /// This may differ from [`DefaultNode::AudioSink`] when the configured sink is not available.
+    ConfiguredAudioSink,
+    /// The source chosen by the user, `default.configured.audio.source`.
+    ConfiguredAudioSource,
+    /// The video source chosen by the user, `default.configured.video.source`.
+    ConfiguredVideoSource,
+}
+
+impl DefaultNode {
+    /// All the default nodes.
+    pub const ALL: [Self; 6] = [
+        Self::AudioSink,
+        Self::AudioSource,
+        Self::VideoSource,
+        Self::ConfiguredAudioSink,
+        Self::ConfiguredAudioSource,
+        Self::ConfiguredVideoSource,
+    ];
+
+    /// The key of the default node in the `default` metadata.
+    pub fn metadata_key(&self) -> &'static str {
+        match self {
+            Self::AudioSink => "default.audio.sink",
+            Self::AudioSource => "default.audio.source",
+            Self::VideoSource => "default.video.source",
+            Self::ConfiguredAudioSink => "default.configured.audio.sink",
+            Self::ConfiguredAudioSource => "default.configured.audio.source",
+            Self::ConfiguredVideoSource => "default.configured.video.source",
+        }
+    }
+
+    /// Get the default node stored under `key` in the `default` metadata.
+    pub fn from_metadata_key(key: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|default| default.metadata_key() == key)
+    }
+}
+
+/// Parse the node name out of a default node metadata value, such as `{ "name": "alsa_output.pci" }`.
+fn parse_node_name(value: &str) -> Option<String> {
+    unsafe {
+        let mut it: [spa_sys::spa_json; 2] = mem::zeroed();
+        spa_sys::spa_json_init(&mut it[0], value.as_ptr().cast(), value.len());
+        if spa_sys::spa_json_enter_object(&mut it[0], &mut it[1]) <= 0 {
+            return None;
+        }
+
+        // Unescaped strings are never longer than the JSON they are parsed from.
+        let mut buf = vec![0 as c_char; value.len() + 1];
+        let len = buf.len().try_into().ok()?;
+        while spa_sys::spa_json_get_string(&mut it[1], buf.as_mut_ptr(), len) > 0 {
+            if CStr::from_ptr(buf.as_ptr()).to_bytes() == b"name" {
+                if spa_sys::spa_json_get_string(&mut it[1], buf.as_mut_ptr(), len) <= 0 {
+                    return None;
+                }
+                return Some(CStr::from_ptr(buf.as_ptr()).to_string_lossy().into_owned());
+            }
+
+            // Skip the value of other keys
+            let mut skipped = std::ptr::null();
+            if spa_sys::spa_json_next(&mut it[1], &mut skipped) <= 0 {
+                return None;
+            }
+        }
+
+        None
+    }
+}
+
+type DefaultChangedCallback = dyn Fn(DefaultNode, Option<&str>, Option<u32>);
+
+#[derive(Default)]
+struct State {
+    /// Node ids by node name, as announced by the registry.
+    nodes: HashMap<String, u32>,
+    /// Names of the default nodes, as set in the metadata.
+    defaults: HashMap<DefaultNode, String>,
+    /// The `default` metadata object, with its global id.
+    /// The listener comes first so that it is removed before the proxy is destroyed.
+    metadata: Option<(u32, MetadataListener, Metadata)>,
+}
+
+impl State {
+    fn node_id(&self, default: DefaultNode) -> Option<u32> {
+        self.defaults
+            .get(&default)
+            .and_then(|name| self.nodes.get(name))
+            .copied()
+    }
+
+    /// Snapshot the resolved defaults, to find out what an update changed.
+    fn snapshot(&self) -> Vec<(DefaultNode, Option<String>, Option<u32>)> {
+        DefaultNode::ALL
+            .into_iter()
+            .map(|default| {
+                (
+                    default,
+                    self.defaults.get(&default).cloned(),
+                    self.node_id(default),
+                )
+            })
+            .collect()
+    }
+}
+
+struct Inner {
+    state: RefCell<State>,
+    callback: Box<DefaultChangedCallback>,
+}
+
+impl Inner {
+    /// Apply `update` to the state, then notify about the defaults it changed.
+    ///
+    /// The callback is called without the state being borrowed,
+    /// so it is free to query the tracker.
+    fn update(&self, update: impl FnOnce(&mut State)) {
+        let (before, after) = {
+            let mut state = self.state.borrow_mut();
+            let before = state.snapshot();
+            update(&mut state);
+            (before, state.snapshot())
+        };
+
+        for (before, (default, name, id)) in before.into_iter().zip(after) {
+            if before.1 != name || before.2 != id {
+                (self.callback)(default, name.as_deref(), id);
+            }
+        }
+    }
+
+    fn global(
+        self: &Rc<Self>,
+        registry: &Weak<Registry>,
+        obj: &GlobalObject<&spa::utils::dict::DictRef>,
+    ) {
+        let Some(props) = obj.props else {
+            return;
+        };
+
+        match obj.type_ {
+            ObjectType::Node => {
+                if let Some(name) = props.get(*crate::keys::NODE_NAME) {
+                    let name = name.to_owned();
+                    self.update(|state| {
+                        state.nodes.insert(name, obj.id);
+                    });
+                }
+            }
+            ObjectType::Metadata => {
+                if props.get(*crate::keys::METADATA_NAME) != Some("default")
+                    || self.state.borrow().metadata.is_some()
+                {
+                    return;
+                }
+                let Some(registry) = registry.upgrade() else {
+                    return;
+                };
+                let Ok(metadata) = registry.bind::<Metadata, _>(obj) else {
+                    return;
+                };
+
+                let inner = Rc::downgrade(self);
+                let listener = metadata
+                    .add_listener_local()
+                    .property(move |subject, key, _type, value| {
+                        if let Some(inner) = inner.upgrade() {
+                            inner.property(subject, key, value);
+                        }
+                        0
+                    })
+                    .register();
+
+                self.state.borrow_mut().metadata = Some((obj.id, listener, metadata));
+            }
+            _ => {}
+        }
+    }
+
+    fn global_remove(&self, id: u32) {
+        self.update(|state| {
+            state.nodes.retain(|_, node_id| *node_id != id);
+            if matches!(state.metadata, Some((metadata_id, ..)) if metadata_id == id) {
+                state.metadata = None;
+                state.defaults.clear();
+            }
+        });
+    }
+
+    fn property(&self, subject: u32, key: Option<&str>, value: Option<&str>) {
+        if subject != PW_ID_CORE {
+            return;
+        }
+
+        self.update(|state| match key {
+            // All properties were removed
+            None => state.defaults.clear(),
+            Some(key) => {
+                let Some(default) = DefaultNode::from_metadata_key(key) else {
+                    return;
+                };
+                match value.and_then(parse_node_name) {
+                    Some(name) => {
+                        state.defaults.insert(default, name);
+                    }
+                    None => {
+                        state.defaults.remove(&default);
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Tracks the default nodes chosen by the session manager.
+///
+/// The tracker binds the `default` metadata object once the registry announces it, and resolves
+/// the node names stored there to node ids using the nodes announced by the registry.
+///
+/// The tracker stops following changes when it is dropped.
+pub struct DefaultNodesTracker {
+    inner: Rc<Inner>,
+    _listener: registry::Listener,
+}
+
+impl DefaultNodesTracker {
+    /// Start tracking the default nodes using `registry`.
+    ///
+    /// `changed` is called with the default node, its name and its node id whenever one of them changes.
+    /// The id is `None` when the node is not known to the registry (yet).
+    pub fn new<F>(registry: &Rc<Registry>, changed: F) -> Self
+    where
+        F: Fn(DefaultNode, Option<&str>, Option<u32>) + 'static,
+    {
+        let inner = Rc::new(Inner {
+            state: RefCell::new(State::default()),
+            callback: Box::new(changed),
+        });
+
+        let registry_weak = Rc::downgrade(registry);
+        let inner_global = Rc::downgrade(&inner);
+        let inner_global_remove = Rc::downgrade(&inner);
+
+        let listener = registry
+            .add_listener_local()
+            .global(move |obj| {
+                if let Some(inner) = inner_global.upgrade() {
+                    inner.global(&registry_weak, obj);
+                }
+            })
+            .global_remove(move |id| {
+                if let Some(inner) = inner_global_remove.upgrade() {
+                    inner.global_remove(id);
+                }
+            })
+            .register();
+
+        Self {
+            inner,
+            _listener: listener,
+        }
+    }
+
+    /// Get the name of a default node, if it is set.
+    pub fn name(&self, default: DefaultNode) -> Option<String> {
+        self.inner.state.borrow().defaults.get(&default).cloned()
+    }
+
+    /// Get the id of a default node, if it is set and known to the registry.
+    pub fn node_id(&self, default: DefaultNode) -> Option<u32> {
+        self.inner.state.borrow().node_id(default)
+    }
+
+    /// Whether the `default` metadata object was found.
+    pub fn has_metadata(&self) -> bool {
+        self.inner.state.borrow().metadata.is_some()
+    }
+
+    /// Ask the session manager to use the node named `name` as configured default.
+    ///
+    /// Only the configured defaults can be set; the session manager then updates the
+    /// current default accordingly. Passing `None` removes the configured default.
+    ///
+    /// Returns `false` if the `default` metadata object was not found yet.
+    pub fn set_configured(&self, default: DefaultNode, name: Option<&str>) -> bool {
+        let state = self.inner.state.borrow();
+        let Some((_, _, metadata)) = &state.metadata else {
+            return false;
+        };
+
+        let value = name.map(|name| {
+            let mut value = String::from("{ \"name\": \"");
+            for c in name.chars() {
+                if matches!(c, '"' | '\\') {
+                    value.push('\\');
+                }
+                value.push(c);
+            }
+            value.push_str("\" }");
+            value
+        });
+        metadata.set_property(
+            PW_ID_CORE,
+            default.metadata_key(),
+            value.as_ref().map(|_| "Spa:String:JSON"),
+            value.as_deref(),
+        );
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_keys() {
+        for default in DefaultNode::ALL {
+            assert_eq!(
+                DefaultNode::from_metadata_key(default.metadata_key()),
+                Some(default)
+            );
+        }
+        assert_eq!(DefaultNode::from_metadata_key("default.badger"), None);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn node_name() {
+        assert_eq!(
+            parse_node_name(r#"{ "name": "alsa_output.pci-0000_00_1f.3.analog-stereo" }"#)
+                .as_deref(),
+            Some("alsa_output.pci-0000_00_1f.3.analog-stereo")
+        );
+        assert_eq!(
+            parse_node_name(r#"{ "other": [ 1, 2 ], "name": "sink" }"#).as_deref(),
+            Some("sink")
+        );
+        assert_eq!(parse_node_name(r#"{ "other": 1 }"#), None);
+        assert_eq!(parse_node_name("sink"), None);
+    }
+}
@@ -0,0 +1,411 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! A simple blocking API to play and record audio, similar to the PulseAudio simple API.
+//!
+//! [`AudioPlayback`] and [`AudioCapture`] run a PipeWire main loop on a thread they manage,
+//! and exchange interleaved `f32` samples with it through a ring buffer.
+//!
+//! ```no_run
+//! use pipewire::simple::{AudioPlayback, AudioSpec};
+//!
+//! let spec = AudioSpec::default();
+//! let mut playback = AudioPlayback::new("beep", spec)?;
+//!
+//! let samples: Vec<f32> = (0..spec.rate)
+//!     .flat_map(|i| {
+//!         let value = (i as f32 * 440.0 * std::f32::consts::TAU / spec.rate as f32).sin() * 0.2;
+//!         std::iter::repeat(value).take(spec.channels as usize)
+//!     })
+//!     .collect();
+//! playback.write(&samples)?;
+//! playback.drain()?;
+//! # Ok::<(), pipewire::Error>(())
+//! ```
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Condvar, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use spa::{
+    param::{
+        audio::{AudioFormat, AudioInfoRaw},
+        ParamType,
+    },
+    pod::{io::PodBuf, Object, Value},
+    utils::{
+        result::SpaResult, Direction, RingBuffer, RingBufferReader, RingBufferWriter, SpaTypes,
+    },
+};
+
+use crate::{
+    channel,
+    context::Context,
+    main_loop::MainLoop,
+    properties::properties,
+    stream::{Stream, StreamFlags, StreamState},
+    Error,
+};
+
+#[cfg(target_endian = "little")]
+const NATIVE_F32: AudioFormat = AudioFormat::F32LE;
+#[cfg(target_endian = "big")]
+const NATIVE_F32: AudioFormat = AudioFormat::F32BE;
+
+/// The format of the samples exchanged with [`AudioPlayback`] and [`AudioCapture`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioSpec {
+    /// The sample rate in Hz.
+    pub rate: u32,
+    /// The number of interleaved channels.
+    pub channels: u32,
+    /// The amount of audio buffered between the caller and PipeWire.
+    ///
+    /// Larger buffers make underruns less likely, at the cost of latency.
+    pub buffer: Duration,
+}
+
+impl Default for AudioSpec {
+    fn default() -> Self {
+        Self {
+            rate: 48000,
+            channels: 2,
+            buffer: Duration::from_millis(100),
+        }
+    }
+}
+
+impl AudioSpec {
+    fn stride(&self) -> usize {
+        std::mem::size_of::<f32>() * self.channels as usize
+    }
+
+    fn buffer_size(&self) -> usize {
+        let frames = (self.buffer.as_secs_f64() * f64::from(self.rate)).ceil() as usize;
+        frames.max(1) * self.stride()
+    }
+}
+
+/// Message asking the PipeWire thread to stop.
+struct Terminate;
+
+/// State shared between the caller and the PipeWire thread.
+#[derive(Default)]
+struct Shared {
+    stopped: AtomicBool,
+    lock: Mutex<()>,
+    processed: Condvar,
+}
+
+impl Shared {
+    /// Wake up the caller after a buffer was processed or the stream stopped.
+    fn notify(&self) {
+        let _guard = self.lock.lock().unwrap();
+        self.processed.notify_all();
+    }
+
+    /// Wait for the next buffer to be processed.
+    fn wait(&self) -> Result<(), Error> {
+        if self.stopped.load(Ordering::Acquire) {
+            return Err(SpaResult::from_c(-libc::EPIPE)
+                .into_sync_result()
+                .unwrap_err()
+                .into());
+        }
+
+        let guard = self.lock.lock().unwrap();
+        // Time out in case the notification raced with this wait.
+        let _ = self
+            .processed
+            .wait_timeout(guard, Duration::from_millis(10))
+            .unwrap();
+        Ok(())
+    }
+}
+
+/// The side of the ring buffer used by the PipeWire thread.
+enum Io {
+    Playback(RingBufferReader),
+    Capture(RingBufferWriter),
+}
+
+/// The handle on the PipeWire thread, stopping it when dropped.
+struct Worker {
+    sender: channel::Sender<Terminate>,
+    thread: Option<thread::JoinHandle<()>>,
+    shared: Arc<Shared>,
+}
+
+impl Worker {
+    fn spawn(name: &str, spec: AudioSpec, target: Option<u32>, io: Io) -> Result<Self, Error> {
+        crate::init();
+
+        let shared = Arc::new(Shared::default());
+        let (sender, receiver) = channel::channel();
+        let (setup_sender, setup_receiver) = mpsc::channel();
+
+        let thread = thread::Builder::new()
+            .name(format!("pw-simple-{name}"))
+            .spawn({
+                let name = name.to_owned();
+                let shared = shared.clone();
+                move || {
+                    let result = run(&name, spec, target, io, &shared, receiver, || {
+                        let _ = setup_sender.send(Ok(()));
+                    });
+                    if let Err(e) = result {
+                        let _ = setup_sender.send(Err(e));
+                    }
+                    shared.stopped.store(true, Ordering::Release);
+                    shared.notify();
+                }
+            })
+            .map_err(|_| Error::CreationFailed)?;
+
+        let worker = Self {
+            sender,
+            thread: Some(thread),
+            shared,
+        };
+        setup_receiver
+            .recv()
+            .unwrap_or(Err(Error::CreationFailed))?;
+
+        Ok(worker)
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        let _ = self.sender.send(Terminate);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Run the stream on the current thread until [`Terminate`] is received.
+fn run(
+    name: &str,
+    spec: AudioSpec,
+    target: Option<u32>,
+    io: Io,
+    shared: &Arc<Shared>,
+    receiver: channel::Receiver<Terminate>,
+    started: impl FnOnce(),
+) -> Result<(), Error> {
+    let mainloop = MainLoop::new(None)?;
+    let context = Context::new(&mainloop)?;
+    let core = context.connect(None)?;
+
+    let (category, direction) = match io {
+        Io::Playback(_) => ("Playback", Direction::Output),
+        Io::Capture(_) => ("Capture", Direction::Input),
+    };
+    let stream = Stream::new(
+        &core,
+        name,
+        properties! {
+            *crate::keys::MEDIA_TYPE => "Audio",
+            *crate::keys::MEDIA_CATEGORY => category,
+            *crate::keys::MEDIA_ROLE => "Music",
+        },
+    )?;
+
+    let stride = spec.stride();
+    let _listener = stream
+        .add_local_listener_with_user_data(io)
+        .state_changed({
+            let shared = shared.clone();
+            let mainloop = mainloop.clone();
+            move |_, _, _, new| {
+                if matches!(new, StreamState::Error(_) | StreamState::Unconnected) {
+                    shared.stopped.store(true, Ordering::Release);
+                    shared.notify();
+                    mainloop.quit();
+                }
+            }
+        })
+        .process({
+            let shared = shared.clone();
+            move |stream, io| {
+                let Some(mut buffer) = stream.dequeue_buffer() else {
+                    return;
+                };
+                let datas = buffer.datas_mut();
+                let Some(data) = datas.first_mut() else {
+                    return;
+                };
+
+                match io {
+                    Io::Playback(reader) => {
+                        let Some(slice) = data.data_mut() else {
+                            return;
+                        };
+                        let len = slice.len() / stride * stride;
+                        let available = reader.available() / stride * stride;
+                        let read = reader.read(&mut slice[..len.min(available)]);
+                        // Play silence on underruns to keep the stream running.
+                        slice[read..len].fill(0);
+
+                        let chunk = data.chunk_mut();
+                        *chunk.offset_mut() = 0;
+                        *chunk.stride_mut() = stride as i32;
+                        *chunk.size_mut() = len as u32;
+                    }
+                    Io::Capture(writer) => {
+                        let (offset, size) = (data.chunk().offset(), data.chunk().size());
+                        if let Some(slice) = data.data() {
+                            let start = (offset as usize).min(slice.len());
+                            let end = (start + size as usize).min(slice.len());
+                            // Only write whole frames, dropping the ones which don't fit on overruns.
+                            let len = (end - start).min(writer.free()) / stride * stride;
+                            writer.write(&slice[start..start + len]);
+                        }
+                    }
+                }
+
+                shared.notify();
+            }
+        })
+        .register()?;
+
+    let mut audio_info = AudioInfoRaw::new();
+    audio_info.set_format(NATIVE_F32);
+    audio_info.set_rate(spec.rate);
+    audio_info.set_channels(spec.channels);
+    let values = PodBuf::serialize(&Value::Object(Object {
+        type_: SpaTypes::ObjectParamFormat.as_raw(),
+        id: ParamType::EnumFormat.as_raw(),
+        properties: audio_info.into(),
+    }))
+    .expect("Failed to serialize pod");
+    let mut params = [values.as_pod()];
+
+    stream.connect(
+        direction,
+        target,
+        StreamFlags::AUTOCONNECT | StreamFlags::MAP_BUFFERS,
+        &mut params,
+    )?;
+
+    let _receiver = receiver.attach(mainloop.loop_(), {
+        let mainloop = mainloop.clone();
+        move |_| mainloop.quit()
+    });
+
+    started();
+    mainloop.run();
+
+    Ok(())
+}
+
+/// A blocking audio playback stream.
+///
+/// Samples are interleaved `f32` values, in the rate and channels of the [`AudioSpec`].
+/// The stream is stopped when this is dropped; call [`AudioPlayback::drain`] before to play
+/// the buffered samples.
+pub struct AudioPlayback {
+    writer: RingBufferWriter,
+    worker: Worker,
+}
+
+impl AudioPlayback {
+    /// Open a playback stream, connected to the default sink.
+    pub fn new(name: &str, spec: AudioSpec) -> Result<Self, Error> {
+        Self::with_target(name, spec, None)
+    }
+
+    /// Open a playback stream, connected to the node `target`, or to the default sink if `None`.
+    pub fn with_target(name: &str, spec: AudioSpec, target: Option<u32>) -> Result<Self, Error> {
+        let (writer, reader) = RingBuffer::new(spec.buffer_size());
+        let worker = Worker::spawn(name, spec, target, Io::Playback(reader))?;
+
+        Ok(Self { writer, worker })
+    }
+
+    /// Write samples, blocking until they all fit in the buffer.
+    pub fn write(&mut self, mut samples: &[f32]) -> Result<(), Error> {
+        loop {
+            let written = self.writer.write_frames(samples);
+            samples = &samples[written..];
+            if samples.is_empty() {
+                return Ok(());
+            }
+
+            self.worker.shared.wait()?;
+        }
+    }
+
+    /// Block until all the buffered samples were played.
+    pub fn drain(&mut self) -> Result<(), Error> {
+        while self.writer.free() < self.writer.capacity() {
+            self.worker.shared.wait()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A blocking audio capture stream.
+///
+/// Samples are interleaved `f32` values, in the rate and channels of the [`AudioSpec`].
+/// When samples are not read fast enough, the most recent ones are dropped.
+pub struct AudioCapture {
+    reader: RingBufferReader,
+    worker: Worker,
+}
+
+impl AudioCapture {
+    /// Open a capture stream, connected to the default source.
+    pub fn new(name: &str, spec: AudioSpec) -> Result<Self, Error> {
+        Self::with_target(name, spec, None)
+    }
+
+    /// Open a capture stream, connected to the node `target`, or to the default source if `None`.
+    pub fn with_target(name: &str, spec: AudioSpec, target: Option<u32>) -> Result<Self, Error> {
+        let (writer, reader) = RingBuffer::new(spec.buffer_size());
+        let worker = Worker::spawn(name, spec, target, Io::Capture(writer))?;
+
+        Ok(Self { reader, worker })
+    }
+
+    /// Read samples, blocking until `samples` is filled.
+    pub fn read(&mut self, mut samples: &mut [f32]) -> Result<(), Error> {
+        loop {
+            let read = self.reader.read_frames(samples);
+            samples = &mut samples[read..];
+            if samples.is_empty() {
+                return Ok(());
+            }
+
+            self.worker.shared.wait()?;
+        }
+    }
+
+    /// The number of samples which can be read without blocking.
+    pub fn available(&self) -> usize {
+        self.reader.available() / std::mem::size_of::<f32>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffer_size() {
+        let spec = AudioSpec {
+            rate: 48000,
+            channels: 2,
+            buffer: Duration::from_millis(10),
+        };
+        assert_eq!(spec.stride(), 8);
+        assert_eq!(spec.buffer_size(), 480 * 8);
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Controls of a stream, such as its volume.
+
+use std::ffi::CStr;
+
+macro_rules! control_ids {
+    ($($variant:ident => $raw:ident,)*) => {
+        /// The id of a stream control.
+        ///
+        /// The standard controls are the properties of the `Props` param, `Other` holds the ids of
+        /// the other controls, such as custom ones.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum ControlId {
+            $($variant,)*
+            Other(u32),
+        }
+
+        impl ControlId {
+            /// The standard controls.
+            pub const STANDARD: &'static [Self] = &[$(Self::$variant,)*];
+
+            pub fn from_raw(raw: u32) -> Self {
+                match raw {
+                    $(spa_sys::$raw => Self::$variant,)*
+                    _ => Self::Other(raw),
+                }
+            }
+
+            pub fn as_raw(self) -> u32 {
+                match self {
+                    $(Self::$variant => spa_sys::$raw,)*
+                    Self::Other(raw) => raw,
+                }
+            }
+        }
+    };
+}
+
+control_ids! {
+    Volume => SPA_PROP_volume,
+    Mute => SPA_PROP_mute,
+    ChannelVolumes => SPA_PROP_channelVolumes,
+    SoftVolumes => SPA_PROP_softVolumes,
+    SoftMute => SPA_PROP_softMute,
+    MonitorVolumes => SPA_PROP_monitorVolumes,
+    MonitorMute => SPA_PROP_monitorMute,
+    Brightness => SPA_PROP_brightness,
+    Contrast => SPA_PROP_contrast,
+    Saturation => SPA_PROP_saturation,
+    Hue => SPA_PROP_hue,
+    Gamma => SPA_PROP_gamma,
+    Exposure => SPA_PROP_exposure,
+    Gain => SPA_PROP_gain,
+    Sharpness => SPA_PROP_sharpness,
+}
+
+/// A control of a stream, as announced by the `control_info` event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamControl {
+    pub id: ControlId,
+    /// The name of the control, such as `Spa:Pod:Object:Param:Props:volume`.
+    pub name: String,
+    pub default: f32,
+    pub min: f32,
+    pub max: f32,
+    /// The current values of the control.
+    pub values: Vec<f32>,
+    /// The maximum number of values of the control.
+    pub max_values: u32,
+}
+
+impl StreamControl {
+    /// Copy the control `id` described by `control`.
+    ///
+    /// # Safety
+    /// `control` must point to a valid `pw_stream_control`.
+    pub unsafe fn from_raw(id: u32, control: *const pw_sys::pw_stream_control) -> Self {
+        let control = &*control;
+
+        let name = if control.name.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(control.name).to_string_lossy().into_owned()
+        };
+        let values = if control.values.is_null() {
+            Vec::new()
+        } else {
+            std::slice::from_raw_parts(control.values, usize::try_from(control.n_values).unwrap())
+                .to_vec()
+        };
+
+        Self {
+            id: ControlId::from_raw(id),
+            name,
+            default: control.def,
+            min: control.min,
+            max: control.max,
+            values,
+            max_values: control.max_values,
+        }
+    }
+
+    /// The last component of the name, such as `volume`.
+    pub fn short_name(&self) -> &str {
+        self.name.rsplit(':').next().unwrap_or_default()
+    }
+
+    /// Whether the control is named `name`, either with its full name or its
+    /// [`short_name`](Self::short_name), ignoring case.
+    pub fn has_name(&self, name: &str) -> bool {
+        self.name == name || self.short_name().eq_ignore_ascii_case(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn control_id() {
+        for &id in ControlId::STANDARD {
+            assert_eq!(ControlId::from_raw(id.as_raw()), id);
+        }
+        assert_eq!(
+            ControlId::from_raw(spa_sys::SPA_PROP_START_CUSTOM),
+            ControlId::Other(spa_sys::SPA_PROP_START_CUSTOM)
+        );
+    }
+
+    #[test]
+    fn from_raw() {
+        let name = b"Spa:Pod:Object:Param:Props:volume\0";
+        let mut values = [0.5f32];
+        let raw = pw_sys::pw_stream_control {
+            name: name.as_ptr().cast(),
+            def: 1.0,
+            min: 0.0,
+            max: 10.0,
+            values: values.as_mut_ptr(),
+            n_values: 1,
+            max_values: 1,
+            ..unsafe { std::mem::zeroed() }
+        };
+
+        let control = unsafe { StreamControl::from_raw(spa_sys::SPA_PROP_volume, &raw) };
+        assert_eq!(control.id, ControlId::Volume);
+        assert_eq!(control.short_name(), "volume");
+        assert!(control.has_name("Volume"));
+        assert!(!control.has_name("mute"));
+        assert_eq!(control.values, [0.5]);
+    }
+}
@@ -0,0 +1,239 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Coalescing of the params updates of a stream.
+//!
+//! Updating the params of a stream for each event of a burst, such as the resizes of a window being
+//! captured, renegotiates the format over and over, which causes glitches. [`ParamsDebouncer`]
+//! batches these updates and applies them at most once per interval.
+
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+    time::Duration,
+};
+
+use spa::pod::{io::PodBuf, Pod};
+
+use super::Stream;
+use crate::{
+    error::ResultExt,
+    loop_::{LoopRef, TimerSource},
+    Error,
+};
+
+/// Params waiting to be applied, in the order they were first queued.
+#[derive(Debug, Default)]
+struct PendingParams {
+    /// The copied params, with the id of the param objects.
+    params: Vec<(Option<u32>, PodBuf)>,
+    error: Option<Error>,
+}
+
+impl PendingParams {
+    /// Queue the params of one update.
+    ///
+    /// The params of each object id replace the whole set of params with that id queued by earlier
+    /// updates, at the place of the first of them.
+    fn queue(&mut self, params: &[&Pod]) {
+        let mut params: Vec<(Option<u32>, PodBuf)> = params
+            .iter()
+            .map(|&pod| (object_id(pod), PodBuf::from(pod)))
+            .collect();
+
+        while !params.is_empty() {
+            let Some(id) = params[0].0 else {
+                self.params.push(params.remove(0));
+                continue;
+            };
+            let (group, rest): (Vec<_>, Vec<_>) = params
+                .into_iter()
+                .partition(|(queued, _)| *queued == Some(id));
+            params = rest;
+
+            let position = self
+                .params
+                .iter()
+                .position(|(queued, _)| *queued == Some(id))
+                .unwrap_or(self.params.len());
+            self.params.retain(|(queued, _)| *queued != Some(id));
+            self.params.splice(position..position, group);
+        }
+    }
+}
+
+/// Apply `params` to the stream, unless it was dropped.
+fn apply(stream: &Weak<Stream>, params: Vec<(Option<u32>, PodBuf)>) -> Result<(), Error> {
+    let Some(stream) = stream.upgrade() else {
+        return Ok(());
+    };
+    if params.is_empty() {
+        return Ok(());
+    }
+
+    let mut pods: Vec<&Pod> = params.iter().map(|(_, pod)| pod.as_pod()).collect();
+    stream.update_params(&mut pods)
+}
+
+/// The id of a param object, such as `SPA_PARAM_Format`.
+fn object_id(pod: &Pod) -> Option<u32> {
+    if !pod.is_object() {
+        return None;
+    }
+
+    let object = pod.as_ptr().cast::<spa_sys::spa_pod_object>();
+    Some(unsafe { (*object).body.id })
+}
+
+/// Batches the params updates of a stream, applying them at most once per interval.
+///
+/// The first update arms a timer on the loop, and the updates queued until it expires are applied
+/// with a single call to [`StreamRef::update_params`](super::StreamRef::update_params). Only the
+/// params of each id from the last update queuing that id are kept, so that a burst of format
+/// changes results in a single renegotiation with the last set of formats.
+///
+/// ```no_run
+/// use std::{rc::Rc, time::Duration};
+/// use pipewire::{
+///     context::Context, main_loop::MainLoop, properties::properties,
+///     stream::{debounce::ParamsDebouncer, Stream},
+/// };
+///
+/// let mainloop = MainLoop::new(None)?;
+/// let context = Context::new(&mainloop)?;
+/// let core = context.connect(None)?;
+/// let stream = Rc::new(Stream::new(&core, "capture", properties! {})?);
+///
+/// let debouncer = ParamsDebouncer::new(mainloop.loop_(), &stream, Duration::from_millis(50));
+/// // On each resize of the captured window:
+/// # let format = pipewire::spa::pod::Pod::from_bytes(&[]).unwrap();
+/// debouncer.update_params(&[format])?;
+/// # Ok::<(), pipewire::Error>(())
+/// ```
+pub struct ParamsDebouncer<'l> {
+    pending: Rc<RefCell<PendingParams>>,
+    stream: Weak<Stream>,
+    interval: Duration,
+    timer: TimerSource<'l>,
+}
+
+impl<'l> ParamsDebouncer<'l> {
+    /// Create a debouncer applying the params of `stream` at most once per `interval`.
+    ///
+    /// A zero `interval` only coalesces the updates queued during one iteration of the loop.
+    pub fn new(loop_: &'l LoopRef, stream: &Rc<Stream>, interval: Duration) -> Self {
+        let pending = Rc::new(RefCell::new(PendingParams::default()));
+        let stream = Rc::downgrade(stream);
+
+        let timer = loop_.add_timer({
+            let pending = pending.clone();
+            let stream = stream.clone();
+            move |_| {
+                // The borrow is released before updating the params, whose callbacks may queue
+                // new ones.
+                let params = std::mem::take(&mut pending.borrow_mut().params);
+                if let Err(err) = apply(&stream, params) {
+                    pending.borrow_mut().error = Some(err);
+                }
+            }
+        });
+
+        Self {
+            pending,
+            stream,
+            // A zero timeout would disarm the timer.
+            interval: interval.max(Duration::from_nanos(1)),
+            timer,
+        }
+    }
+
+    /// Queue `params` to be applied when the interval expires.
+    ///
+    /// The params of each object id replace all the params with that id queued by earlier calls.
+    pub fn update_params(&self, params: &[&Pod]) -> Result<(), Error> {
+        let mut pending = self.pending.borrow_mut();
+        let armed = !pending.params.is_empty();
+        pending.queue(params);
+
+        if !armed && !pending.params.is_empty() {
+            self.timer
+                .update_timer(Some(self.interval), None)
+                .into_sync_result()
+                .context("arm the debounce timer")?;
+        }
+        Ok(())
+    }
+
+    /// Apply the queued params right away.
+    pub fn flush(&self) -> Result<(), Error> {
+        self.timer
+            .update_timer(None, None)
+            .into_sync_result()
+            .context("disarm the debounce timer")?;
+        let params = std::mem::take(&mut self.pending.borrow_mut().params);
+        apply(&self.stream, params)
+    }
+
+    /// Whether params are waiting to be applied.
+    pub fn is_pending(&self) -> bool {
+        !self.pending.borrow().params.is_empty()
+    }
+
+    /// Take the error of the last params update done when the interval expired, if it failed.
+    pub fn take_error(&self) -> Option<Error> {
+        self.pending.borrow_mut().error.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use spa::pod::{Object, Value};
+
+    use super::*;
+
+    fn object(id: u32, type_: u32) -> PodBuf {
+        PodBuf::serialize(&Value::Object(Object {
+            type_,
+            id,
+            properties: Vec::new(),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn coalesce() {
+        let format = object(spa_sys::SPA_PARAM_Format, spa_sys::SPA_TYPE_OBJECT_Format);
+        let buffers = object(
+            spa_sys::SPA_PARAM_Buffers,
+            spa_sys::SPA_TYPE_OBJECT_ParamBuffers,
+        );
+
+        let mut pending = PendingParams::default();
+        pending.queue(&[format.as_pod(), format.as_pod()]);
+        pending.queue(&[buffers.as_pod()]);
+        pending.queue(&[format.as_pod()]);
+
+        // The single format of the last update replaces both formats of the first one.
+        let ids: Vec<_> = pending.params.iter().map(|(id, _)| *id).collect();
+        assert_eq!(
+            ids,
+            [
+                Some(spa_sys::SPA_PARAM_Format),
+                Some(spa_sys::SPA_PARAM_Buffers)
+            ]
+        );
+
+        // Both formats of the last update are kept.
+        pending.queue(&[format.as_pod(), format.as_pod()]);
+        let ids: Vec<_> = pending.params.iter().map(|(id, _)| *id).collect();
+        assert_eq!(
+            ids,
+            [
+                Some(spa_sys::SPA_PARAM_Format),
+                Some(spa_sys::SPA_PARAM_Format),
+                Some(spa_sys::SPA_PARAM_Buffers)
+            ]
+        );
+    }
+}
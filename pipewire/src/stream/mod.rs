@@ -0,0 +1,1551 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Pipewire Stream
+
+pub mod control;
+pub mod debounce;
+pub mod node;
+pub mod presets;
+pub mod reconnect;
+#[cfg(feature = "test-shims")]
+pub mod shim;
+
+mod ops;
+
+use crate::buffer::{Buffer, MemPool};
+use crate::{
+    core::{Core, CoreRef},
+    error::{Error, ResultExt},
+    keys, listeners,
+    properties::{Properties, PropertiesRef},
+    scope::ListenerScope,
+};
+use bitflags::bitflags;
+use control::{ControlId, StreamControl};
+use ops::{Ops, StreamOps};
+use spa::utils::dict::DictRef;
+use spa::utils::result::SpaResult;
+use std::{
+    cell::{Cell, RefCell},
+    ffi::{self, CStr, CString},
+    fmt::Debug,
+    mem, os, ptr,
+    rc::{Rc, Weak},
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+#[derive(Debug, PartialEq)]
+pub enum StreamState {
+    Error(String),
+    Unconnected,
+    Connecting,
+    Paused,
+    Streaming,
+}
+
+impl StreamState {
+    pub(crate) fn from_raw(state: pw_sys::pw_stream_state, error: *const os::raw::c_char) -> Self {
+        match state {
+            pw_sys::pw_stream_state_PW_STREAM_STATE_UNCONNECTED => StreamState::Unconnected,
+            pw_sys::pw_stream_state_PW_STREAM_STATE_CONNECTING => StreamState::Connecting,
+            pw_sys::pw_stream_state_PW_STREAM_STATE_PAUSED => StreamState::Paused,
+            pw_sys::pw_stream_state_PW_STREAM_STATE_STREAMING => StreamState::Streaming,
+            _ => {
+                let error = if error.is_null() {
+                    "".to_string()
+                } else {
+                    unsafe { ffi::CStr::from_ptr(error).to_string_lossy().to_string() }
+                };
+
+                StreamState::Error(error)
+            }
+        }
+    }
+}
+
+/// What happens to the underlying `pw_stream` when a [`Stream`] is dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DropBehavior {
+    /// Destroy the stream, which also disconnects it.
+    #[default]
+    Destroy,
+    /// Only disconnect the stream, leaving it allocated.
+    Disconnect,
+    /// Do nothing, leaving the stream connected and allocated.
+    ///
+    /// This is useful when ownership of the stream has been passed on to C code.
+    Leak,
+}
+
+/// A wrapper around the pipewire stream interface. Streams are a higher
+/// level abstraction around nodes in the graph. A stream can be used to send or
+/// receive frames of audio or video data by connecting it to another node.
+/// `D` is the user data, to allow passing extra context to the callbacks.
+///
+/// Callbacks needing the stream should hold a [`WeakStream`] obtained with
+/// [`Stream::downgrade`], so the stream is not kept alive by its own listeners.
+pub struct Stream {
+    inner: Rc<StreamInner>,
+}
+
+impl Stream {
+    /// Create a [`Stream`]
+    ///
+    /// Initialises a new stream with the given `name` and `properties`.
+    pub fn new(core: &Core, name: &str, properties: Properties) -> Result<Self, Error> {
+        let name = CString::new(name).expect("Invalid byte in stream name");
+        let stream = unsafe {
+            pw_sys::pw_stream_new(core.as_raw_ptr(), name.as_ptr(), properties.into_raw())
+        };
+        let stream = ptr::NonNull::new(stream).ok_or(Error::CreationFailed)?;
+
+        let mut inner = StreamInner {
+            ptr: stream,
+            drop_behavior: Cell::new(DropBehavior::default()),
+            connection: RefCell::new(None),
+            tracked: Rc::default(),
+            tracked_listener: None,
+            _core: core.clone(),
+        };
+        let tracked_listener = unsafe { stream.cast::<StreamRef>().as_ref() }
+            .add_local_listener_with_user_data(Rc::clone(&inner.tracked))
+            .control_info(|_, tracked, id, _| {
+                let mut control_ids = tracked.control_ids.borrow_mut();
+                if !control_ids.contains(&id) {
+                    control_ids.push(id);
+                }
+            })
+            .io_changed(|_, tracked, id, area, _| {
+                if id == spa_sys::SPA_IO_Position {
+                    tracked.position.store(area.cast(), Ordering::Release);
+                }
+            })
+            .register()?;
+        inner.tracked_listener = Some(tracked_listener);
+
+        Ok(Stream {
+            inner: Rc::new(inner),
+        })
+    }
+
+    /// Get the clock of the driver of the graph, as of the current cycle.
+    ///
+    /// The clock is read from the `Position` io area the server gives to the stream once it is
+    /// scheduled, and is `None` before that. The server updates the area every cycle, so the
+    /// clock is only consistent when read from the `process` callback.
+    pub fn clock(&self) -> Option<StreamClock> {
+        let position = self.inner.tracked.position.load(Ordering::Acquire);
+        if position.is_null() {
+            return None;
+        }
+
+        let clock = unsafe { ptr::read_volatile(ptr::addr_of!((*position).clock)) };
+        Some(StreamClock::from_raw(&clock))
+    }
+
+    /// Get what happens to the stream when this `Stream` is dropped.
+    pub fn drop_behavior(&self) -> DropBehavior {
+        self.inner.drop_behavior.get()
+    }
+
+    /// Set what happens to the stream when this `Stream` is dropped.
+    ///
+    /// By default, the stream is destroyed.
+    pub fn set_drop_behavior(&mut self, behavior: DropBehavior) {
+        self.inner.drop_behavior.set(behavior);
+    }
+
+    /// Destroy the stream, regardless of the configured [`DropBehavior`].
+    ///
+    /// Fails, returning the stream back, if handles upgraded from a [`WeakStream`] still own it.
+    pub fn destroy(self) -> Result<(), Self> {
+        if Rc::strong_count(&self.inner) != 1 {
+            return Err(self);
+        }
+        self.inner.drop_behavior.set(DropBehavior::Destroy);
+        Ok(())
+    }
+
+    /// Give up ownership of the stream, returning the raw pointer.
+    ///
+    /// The stream is neither disconnected nor destroyed, that is left to the caller.
+    /// The weak handles of the stream can't be upgraded anymore afterwards.
+    ///
+    /// # Panics
+    /// If handles upgraded from a [`WeakStream`] still own the stream, as they would dangle once
+    /// the caller destroys it.
+    pub fn into_raw(self) -> *mut pw_sys::pw_stream {
+        assert_eq!(
+            Rc::strong_count(&self.inner),
+            1,
+            "Stream::into_raw called while the stream has other owners"
+        );
+        self.inner.drop_behavior.set(DropBehavior::Leak);
+        self.inner.ptr.as_ptr()
+    }
+
+    /// Get the controls of the stream, in the order the `control_info` event announced them.
+    ///
+    /// Unlike [`StreamRef::control`], this is only available on [`Stream`], which tracks the ids
+    /// announced by the `control_info` event; a [`StreamRef`] cannot list them. Callbacks, which
+    /// are given a [`StreamRef`], can reach it through a [`WeakStream`] instead:
+    ///
+    /// ```no_run
+    /// # use pipewire::stream::Stream;
+    /// # fn example(stream: &Stream) -> Result<(), pipewire::Error> {
+    /// let weak = stream.downgrade();
+    /// let _listener = stream
+    ///     .add_local_listener::<()>()
+    ///     .state_changed(move |_, _, _, _| {
+    ///         if let Some(stream) = weak.upgrade() {
+    ///             let _ = stream.set_control_by_name("Volume", &[0.5]);
+    ///         }
+    ///     })
+    ///     .register()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn controls(&self) -> Vec<StreamControl> {
+        self.inner
+            .tracked
+            .control_ids
+            .borrow()
+            .iter()
+            .filter_map(|&id| self.control(ControlId::from_raw(id)))
+            .collect()
+    }
+
+    /// Set the control named `name`, see [`StreamControl::has_name`].
+    ///
+    /// Like [`controls`](Self::controls), this is only available on [`Stream`].
+    /// Fails with `ENOENT` if the stream has no such control.
+    pub fn set_control_by_name(&self, name: &str, values: &[f32]) -> Result<(), Error> {
+        let control = self
+            .controls()
+            .into_iter()
+            .find(|control| control.has_name(name))
+            .ok_or_else(|| {
+                Error::from(spa::utils::result::Error::from(nix::errno::Errno::ENOENT))
+                    .context("set a stream control")
+            })?;
+
+        self.set_control(control.id.as_raw(), values)
+    }
+
+    /// Get a weak handle to the stream, which does not keep it alive.
+    pub fn downgrade(&self) -> WeakStream {
+        WeakStream {
+            weak: Rc::downgrade(&self.inner),
+        }
+    }
+}
+
+impl std::ops::Deref for Stream {
+    type Target = StreamRef;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.inner.ptr.cast().as_ref() }
+    }
+}
+
+impl std::fmt::Debug for Stream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Stream")
+            .field("name", &self.name())
+            .field("state", &self.state())
+            .field("node-id", &self.node_id())
+            .field("properties", &self.properties())
+            .finish()
+    }
+}
+
+/// A weak handle to a [`Stream`], see [`Stream::downgrade`].
+#[derive(Clone)]
+pub struct WeakStream {
+    weak: Weak<StreamInner>,
+}
+
+impl WeakStream {
+    /// Get the stream, or `None` if it has been dropped.
+    pub fn upgrade(&self) -> Option<Stream> {
+        self.weak.upgrade().map(|inner| Stream { inner })
+    }
+}
+
+impl std::fmt::Debug for WeakStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WeakStream")
+            .field("alive", &(self.weak.strong_count() > 0))
+            .finish()
+    }
+}
+
+/// The state of a [`Stream`] shared with its weak handles.
+struct StreamInner {
+    ptr: ptr::NonNull<pw_sys::pw_stream>,
+    drop_behavior: Cell<DropBehavior>,
+    /// How the stream was last connected, to reconnect it.
+    connection: RefCell<Option<reconnect::Connection>>,
+    tracked: Rc<Tracked>,
+    /// Keeps `tracked` up to date, removed before the stream is destroyed.
+    tracked_listener: Option<StreamListener<Rc<Tracked>>>,
+    // objects that need to stay alive while the Stream is
+    _core: Core,
+}
+
+/// The state of a stream followed from its own events.
+#[derive(Default)]
+struct Tracked {
+    /// The `Position` io area of the stream, null while it has none.
+    position: AtomicPtr<spa_sys::spa_io_position>,
+    /// The ids of the controls announced by the stream, in order.
+    control_ids: RefCell<Vec<u32>>,
+}
+
+impl std::ops::Drop for StreamInner {
+    fn drop(&mut self) {
+        // The listener must not outlive its callbacks, even if the stream is leaked.
+        self.tracked_listener.take();
+        match self.drop_behavior.get() {
+            DropBehavior::Destroy => unsafe { pw_sys::pw_stream_destroy(self.ptr.as_ptr()) },
+            DropBehavior::Disconnect => unsafe {
+                pw_sys::pw_stream_disconnect(self.ptr.as_ptr());
+            },
+            DropBehavior::Leak => {}
+        }
+    }
+}
+
+#[repr(transparent)]
+pub struct StreamRef(pw_sys::pw_stream);
+
+impl StreamRef {
+    pub fn as_raw(&self) -> &pw_sys::pw_stream {
+        &self.0
+    }
+
+    pub fn as_raw_ptr(&self) -> *mut pw_sys::pw_stream {
+        ptr::addr_of!(self.0).cast_mut()
+    }
+
+    /// Add a local listener builder
+    ///
+    /// Several listeners can be registered on the same stream, and each of them receives all the
+    /// events of the stream.
+    ///
+    /// User data borrowing state of the stack, such as counters read after
+    /// [`MainLoop::run`](crate::main_loop::MainLoop::run) returns, can only be registered for
+    /// the duration of a scope, with [`ListenerLocalBuilder::register_scoped`].
+    #[must_use = "Fluent builder API"]
+    pub fn add_local_listener_with_user_data<D>(
+        &self,
+        user_data: D,
+    ) -> ListenerLocalBuilder<'_, D> {
+        self.add_local_listener_with_callbacks(ListenerLocalCallbacks::with_user_data(user_data))
+    }
+
+    /// Add a local listener builder starting from the callbacks and user data of a listener
+    /// which was unregistered with [`StreamListener::into_callbacks`].
+    #[must_use = "Fluent builder API"]
+    pub fn add_local_listener_with_callbacks<D>(
+        &self,
+        mut callbacks: ListenerLocalCallbacks<D>,
+    ) -> ListenerLocalBuilder<'_, D> {
+        callbacks.stream =
+            Some(ptr::NonNull::new(self.as_raw_ptr()).expect("Pointer should be nonnull"));
+        ListenerLocalBuilder {
+            stream: self,
+            callbacks,
+        }
+    }
+
+    /// Add a local listener builder. User data is initialized with its default value
+    #[must_use = "Fluent builder API"]
+    pub fn add_local_listener<D: Default>(&self) -> ListenerLocalBuilder<'_, D> {
+        self.add_local_listener_with_user_data(Default::default())
+    }
+
+    /// Connect the stream
+    ///
+    /// Tries to connect to the node `id` in the given `direction`. If no node
+    /// is provided then any suitable node will be used.
+    ///
+    /// Fails without connecting if the flags contradict each other, see [`StreamFlags::validate`].
+    /// Use [`StreamRef::connect_to`] to target a node by name or serial instead.
+    ///
+    /// Connect through [`Stream::connect`] instead to be able to [reconnect](Stream::reconnect)
+    /// the stream later.
+    // FIXME: high-level API for params
+    pub fn connect(
+        &self,
+        direction: spa::utils::Direction,
+        id: Option<u32>,
+        flags: StreamFlags,
+        params: &mut [&spa::pod::Pod],
+    ) -> Result<(), Error> {
+        self.connect_to(direction, StreamTarget::from(id), flags, params)
+    }
+
+    /// Connect the stream to `target` in the given `direction`.
+    ///
+    /// Targets given by name or serial are set as the `target.object` property of the stream
+    /// before connecting, for the session manager to link the stream to the matching node.
+    /// Connecting to any node or to a node given by id leaves the property as it is, as it may
+    /// have been set by the application; [`Stream::connect_to`] removes it if it was set by an
+    /// earlier connection to a name or serial.
+    pub fn connect_to(
+        &self,
+        direction: spa::utils::Direction,
+        target: StreamTarget<'_>,
+        flags: StreamFlags,
+        params: &mut [&spa::pod::Pod],
+    ) -> Result<(), Error> {
+        flags.validate().context("connect the stream")?;
+
+        match target {
+            StreamTarget::Any | StreamTarget::Id(_) => {}
+            StreamTarget::Serial(serial) => {
+                self.set_target_object(Some(&CString::new(serial.to_string()).unwrap()))
+            }
+            StreamTarget::Name(name) => self.set_target_object(Some(
+                &CString::new(name).expect("Invalid byte in target name"),
+            )),
+        }
+        let id = match target {
+            StreamTarget::Id(id) => id,
+            _ => crate::constants::ID_ANY,
+        };
+
+        let r = unsafe {
+            Ops::connect(
+                self.as_raw_ptr(),
+                direction.as_raw(),
+                id,
+                flags.bits(),
+                // We cast from *mut [&spa::pod::Pod] to *mut [*const spa_sys::spa_pod] here,
+                // which is valid because spa::pod::Pod is a transparent wrapper around spa_sys::spa_pod
+                params.as_mut_ptr().cast(),
+                params.len() as u32,
+            )
+        };
+
+        SpaResult::from_c(r)
+            .into_sync_result()
+            .context("connect the stream")?;
+        Ok(())
+    }
+
+    /// Set the `target.object` property of the stream to `value`, or remove it if `None`.
+    pub(super) fn set_target_object(&self, value: Option<&CStr>) {
+        // The key is only defined by the bindings of PipeWire 0.3.44 and later.
+        const TARGET_OBJECT: &CStr =
+            unsafe { CStr::from_bytes_with_nul_unchecked(b"target.object\0") };
+        // A NULL value removes the property.
+        let item = spa_sys::spa_dict_item {
+            key: TARGET_OBJECT.as_ptr(),
+            value: value.map_or(ptr::null(), CStr::as_ptr),
+        };
+        let dict = spa_sys::spa_dict {
+            flags: 0,
+            n_items: 1,
+            items: &item,
+        };
+        unsafe { pw_sys::pw_stream_update_properties(self.as_raw_ptr(), &dict) };
+    }
+
+    /// Update Parameters
+    ///
+    /// Call from the `param_changed` callback to negotiate a new set of
+    /// parameters for the stream.
+    // FIXME: high-level API for params
+    pub fn update_params(&self, params: &mut [&spa::pod::Pod]) -> Result<(), Error> {
+        let r = unsafe {
+            Ops::update_params(
+                self.as_raw_ptr(),
+                params.as_mut_ptr().cast(),
+                params.len() as u32,
+            )
+        };
+
+        SpaResult::from_c(r)
+            .into_sync_result()
+            .context("update the stream params")?;
+        Ok(())
+    }
+
+    /// Activate or deactivate the stream
+    pub fn set_active(&self, active: bool) -> Result<(), Error> {
+        let r = unsafe { pw_sys::pw_stream_set_active(self.as_raw_ptr(), active) };
+
+        SpaResult::from_c(r)
+            .into_sync_result()
+            .context("set the stream active state")?;
+        Ok(())
+    }
+
+    /// Take a Buffer from the Stream
+    ///
+    /// Removes a buffer from the stream. If this is an input stream the buffer
+    /// will contain data ready to process. If this is an output stream it can
+    /// be filled.
+    ///
+    /// # Safety
+    ///
+    /// The pointer returned could be NULL if no buffer is available. The buffer
+    /// should be returned to the stream once processing is complete.
+    pub unsafe fn dequeue_raw_buffer(&self) -> *mut pw_sys::pw_buffer {
+        Ops::dequeue_buffer(self.as_raw_ptr())
+    }
+
+    pub fn dequeue_buffer(&self) -> Option<Buffer> {
+        unsafe { Buffer::from_raw(self.dequeue_raw_buffer(), self) }
+    }
+
+    /// Dequeue all the available buffers, from the oldest to the most recent.
+    ///
+    /// This lets an input stream which fell behind skip to the most recent data, by processing
+    /// the last buffer and dropping the others, which queues them back to the stream.
+    ///
+    /// ```no_run
+    /// # fn process(stream: &pipewire::stream::StreamRef) {
+    /// let mut buffers = stream.dequeue_all();
+    /// if let Some(latest) = buffers.pop() {
+    ///     // The older buffers are queued back right away.
+    ///     drop(buffers);
+    ///     // Process `latest`...
+    /// }
+    /// # }
+    /// ```
+    pub fn dequeue_all(&self) -> Vec<Buffer> {
+        std::iter::from_fn(|| self.dequeue_buffer()).collect()
+    }
+
+    /// Return a Buffer to the Stream
+    ///
+    /// Give back a buffer once processing is complete. Use this to queue up a
+    /// frame for an output stream, or return the buffer to the pool ready to
+    /// receive new data for an input stream.
+    ///
+    /// # Safety
+    ///
+    /// The buffer pointer should be one obtained from this stream instance by
+    /// a call to [StreamRef::dequeue_raw_buffer()].
+    pub unsafe fn queue_raw_buffer(&self, buffer: *mut pw_sys::pw_buffer) {
+        Ops::queue_buffer(self.as_raw_ptr(), buffer);
+    }
+
+    /// Like [`queue_raw_buffer`](Self::queue_raw_buffer), but returns the error of the queueing.
+    pub(crate) unsafe fn try_queue_raw_buffer(
+        &self,
+        buffer: *mut pw_sys::pw_buffer,
+    ) -> Result<(), Error> {
+        let r = Ops::queue_buffer(self.as_raw_ptr(), buffer);
+
+        SpaResult::from_c(r)
+            .into_sync_result()
+            .context("queue the buffer")?;
+        Ok(())
+    }
+
+    /// Disconnect the stream
+    pub fn disconnect(&self) -> Result<(), Error> {
+        let r = unsafe { pw_sys::pw_stream_disconnect(self.as_raw_ptr()) };
+
+        SpaResult::from_c(r)
+            .into_sync_result()
+            .context("disconnect the stream")?;
+        Ok(())
+    }
+
+    /// Set the stream in error state
+    ///
+    /// # Panics
+    /// Will panic if `error` contains a 0 byte.
+    ///
+    pub fn set_error(&mut self, res: i32, error: &str) {
+        let error = CString::new(error).expect("failed to convert error to CString");
+        unsafe {
+            pw_sys::pw_stream_set_error(self.as_raw_ptr(), res, error.as_c_str().as_ptr());
+        }
+    }
+
+    /// Flush the stream. When  `drain` is `true`, the `drained` callback will
+    /// be called when all data is played or recorded.
+    pub fn flush(&self, drain: bool) -> Result<(), Error> {
+        let r = unsafe { pw_sys::pw_stream_flush(self.as_raw_ptr(), drain) };
+
+        SpaResult::from_c(r)
+            .into_sync_result()
+            .context("flush the stream")?;
+        Ok(())
+    }
+
+    pub fn set_control(&self, id: u32, values: &[f32]) -> Result<(), Error> {
+        let r = unsafe {
+            pw_sys::pw_stream_set_control(
+                self.as_raw_ptr(),
+                id,
+                values.len() as u32,
+                values.as_ptr() as *mut f32,
+            )
+        };
+        SpaResult::from_c(r)
+            .into_sync_result()
+            .context("set a stream control")?;
+        Ok(())
+    }
+
+    /// Get the control `id`, if the stream has it.
+    ///
+    /// The controls are known once the stream is connected, and reported by the `control_info` event.
+    pub fn control(&self, id: ControlId) -> Option<StreamControl> {
+        let id = id.as_raw();
+        let control = unsafe { pw_sys::pw_stream_get_control(self.as_raw_ptr(), id) };
+        if control.is_null() {
+            None
+        } else {
+            Some(unsafe { StreamControl::from_raw(id, control) })
+        }
+    }
+
+    // getters
+
+    /// Get the name of the stream.
+    pub fn name(&self) -> String {
+        let name = unsafe {
+            let name = pw_sys::pw_stream_get_name(self.as_raw_ptr());
+            CStr::from_ptr(name)
+        };
+
+        name.to_string_lossy().to_string()
+    }
+
+    /// Get the current state of the stream.
+    pub fn state(&self) -> StreamState {
+        let mut error: *const std::os::raw::c_char = ptr::null();
+        let state = unsafe {
+            pw_sys::pw_stream_get_state(self.as_raw_ptr(), (&mut error) as *mut *const _)
+        };
+        StreamState::from_raw(state, error)
+    }
+
+    /// Get the properties of the stream.
+    pub fn properties(&self) -> &PropertiesRef {
+        unsafe {
+            let props = pw_sys::pw_stream_get_properties(self.as_raw_ptr());
+            let props = ptr::NonNull::new(props.cast_mut()).expect("stream properties is NULL");
+            props.cast().as_ref()
+        }
+    }
+
+    #[inline]
+    pub fn update_properties(&self, dict: &DictRef) {
+        unsafe {
+            pw_sys::pw_stream_update_properties(self.as_raw_ptr(), dict.as_raw_ptr());
+        }
+    }
+
+    /// Force the quantum of the graph to `quantum` samples while the stream is active,
+    /// or stop forcing it with `None`.
+    ///
+    /// This sets the `node.force-quantum` property of the node of the stream.
+    #[cfg(feature = "v0_3_45")]
+    pub fn set_forced_quantum(&self, quantum: Option<u32>) {
+        self.set_forced_property(*keys::NODE_FORCE_QUANTUM, quantum);
+    }
+
+    /// Force the rate of the graph to `rate` Hz while the stream is active,
+    /// or stop forcing it with `None`.
+    ///
+    /// This sets the `node.force-rate` property of the node of the stream.
+    #[cfg(feature = "v0_3_45")]
+    pub fn set_forced_rate(&self, rate: Option<u32>) {
+        self.set_forced_property(*keys::NODE_FORCE_RATE, rate);
+    }
+
+    #[cfg(feature = "v0_3_45")]
+    fn set_forced_property(&self, key: &str, value: Option<u32>) {
+        // A value of 0 disables the forcing.
+        let mut props = Properties::new();
+        props.insert(key, value.unwrap_or(0).to_string());
+        self.update_properties(props.dict());
+    }
+
+    /// Get the node ID of the stream.
+    pub fn node_id(&self) -> u32 {
+        unsafe { pw_sys::pw_stream_get_node_id(self.as_raw_ptr()) }
+    }
+
+    #[cfg(feature = "v0_3_34")]
+    pub fn is_driving(&self) -> bool {
+        unsafe { pw_sys::pw_stream_is_driving(self.as_raw_ptr()) }
+    }
+
+    #[cfg(feature = "v0_3_34")]
+    pub fn trigger_process(&self) -> Result<(), Error> {
+        let r = unsafe { pw_sys::pw_stream_trigger_process(self.as_raw_ptr()) };
+
+        SpaResult::from_c(r)
+            .into_result()
+            .context("trigger the stream process")?;
+        Ok(())
+    }
+
+    /// Ask the driver of the graph to schedule it, by emitting a `RequestProcess` event.
+    ///
+    /// This is how a follower pulls data from a lazy driver; the stream should set the
+    /// [`NODE_SUPPORTS_REQUEST`](crate::keys::NODE_SUPPORTS_REQUEST) property.
+    #[cfg(feature = "v1_2_0")]
+    pub fn request_process(&self) -> Result<(), Error> {
+        let event = spa_sys::spa_event {
+            pod: spa_sys::spa_pod {
+                size: mem::size_of::<spa_sys::spa_event_body>() as u32,
+                type_: spa_sys::SPA_TYPE_Object,
+            },
+            body: spa_sys::spa_event_body {
+                body: spa_sys::spa_pod_object_body {
+                    type_: spa_sys::SPA_TYPE_EVENT_Node,
+                    id: spa_sys::SPA_NODE_EVENT_RequestProcess,
+                },
+            },
+        };
+
+        unsafe { self.emit_event(&event) }
+    }
+
+    /// Emit `event` from the node of the stream.
+    ///
+    /// # Safety
+    /// `event` must point to a valid event pod.
+    #[cfg(feature = "v1_2_0")]
+    pub unsafe fn emit_event(&self, event: *const spa_sys::spa_event) -> Result<(), Error> {
+        let r = pw_sys::pw_stream_emit_event(self.as_raw_ptr(), event);
+
+        SpaResult::from_c(r)
+            .into_sync_result()
+            .context("emit the stream event")?;
+        Ok(())
+    }
+
+    /// Get the core the stream was created on.
+    pub fn core(&self) -> &CoreRef {
+        unsafe {
+            let core = pw_sys::pw_stream_get_core(self.as_raw_ptr());
+            ptr::NonNull::new(core)
+                .expect("stream core is NULL")
+                .cast()
+                .as_ref()
+        }
+    }
+
+    /// Get the timing information of the stream, as of the last cycle of the graph.
+    ///
+    /// The information comes from the position of the driver of the graph, which is updated
+    /// by the server every cycle, and is read safely from any thread, including the `process`
+    /// callback of a stream connected with [`StreamFlags::RT_PROCESS`].
+    ///
+    /// The quantum of the graph is part of the clock of the driver, see [`Stream::clock`].
+    pub fn position(&self) -> Result<StreamPosition, Error> {
+        let mut time: pw_sys::pw_time = unsafe { mem::zeroed() };
+        #[cfg(feature = "v0_3_50")]
+        let res = unsafe {
+            pw_sys::pw_stream_get_time_n(
+                self.as_raw_ptr(),
+                &mut time,
+                mem::size_of::<pw_sys::pw_time>(),
+            )
+        };
+        #[cfg(not(feature = "v0_3_50"))]
+        let res = unsafe { pw_sys::pw_stream_get_time(self.as_raw_ptr(), &mut time) };
+        SpaResult::from_c(res)
+            .into_sync_result()
+            .context("get the stream position")?;
+
+        Ok(StreamPosition::from_raw(&time))
+    }
+}
+
+/// The timing information of a stream, see [`StreamRef::position`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamPosition {
+    /// The time of the last cycle, in nanoseconds of the monotonic clock.
+    pub now: i64,
+    /// The rate of the graph clock, as the duration of a tick in seconds, such as `1/48000`.
+    ///
+    /// This is `0/0` until the stream is scheduled by a driver.
+    pub rate: spa::utils::Fraction,
+    /// The position of the graph clock, in ticks.
+    pub ticks: u64,
+    /// The delay to the device, in ticks.
+    pub delay: i64,
+    /// The amount of data queued in the stream, in bytes or frames depending on the stream.
+    pub queued: u64,
+}
+
+impl StreamPosition {
+    fn from_raw(time: &pw_sys::pw_time) -> Self {
+        Self {
+            now: time.now,
+            rate: time.rate,
+            ticks: time.ticks,
+            delay: time.delay,
+            queued: time.queued,
+        }
+    }
+
+    /// The sample rate of the graph, if the clock runs at a rate of one tick per sample.
+    pub fn sample_rate(&self) -> Option<u32> {
+        (self.rate.num == 1).then_some(self.rate.denom)
+    }
+
+    /// The position of the graph clock, in nanoseconds.
+    pub fn ticks_nsec(&self) -> Option<u64> {
+        (self.rate.denom != 0).then(|| {
+            (u128::from(self.ticks) * u128::from(self.rate.num) * 1_000_000_000
+                / u128::from(self.rate.denom)) as u64
+        })
+    }
+}
+
+/// The clock of the driver of the graph, see [`Stream::clock`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamClock {
+    /// The time of the current cycle, in nanoseconds of the monotonic clock.
+    pub nsec: u64,
+    /// The rate of the clock, as the duration of a tick in seconds, such as `1/48000`.
+    pub rate: spa::utils::Fraction,
+    /// The position of the clock, in ticks.
+    pub position: u64,
+    /// The quantum of the graph, the number of ticks processed in the current cycle.
+    pub quantum: u64,
+    /// The delay to the device, in ticks.
+    pub delay: i64,
+    /// How much faster than the monotonic clock the clock runs.
+    pub rate_diff: f64,
+    /// The expected time of the next cycle, in nanoseconds of the monotonic clock.
+    pub next_nsec: u64,
+}
+
+impl StreamClock {
+    fn from_raw(clock: &spa_sys::spa_io_clock) -> Self {
+        Self {
+            nsec: clock.nsec,
+            rate: clock.rate,
+            position: clock.position,
+            quantum: clock.duration,
+            delay: clock.delay,
+            rate_diff: clock.rate_diff,
+            next_nsec: clock.next_nsec,
+        }
+    }
+
+    /// The sample rate of the graph, if the clock runs at a rate of one tick per sample.
+    pub fn sample_rate(&self) -> Option<u32> {
+        (self.rate.num == 1).then_some(self.rate.denom)
+    }
+}
+
+type ParamChangedCB<D> = dyn FnMut(&StreamRef, &mut D, u32, Option<&spa::pod::Pod>);
+type ProcessCB<D> = dyn FnMut(&StreamRef, &mut D);
+
+#[allow(clippy::type_complexity)]
+pub struct ListenerLocalCallbacks<D> {
+    pub destroy: Option<Box<dyn FnMut(&StreamRef, &mut D)>>,
+    pub state_changed: Option<Box<dyn FnMut(&StreamRef, &mut D, StreamState, StreamState)>>,
+    pub control_info:
+        Option<Box<dyn FnMut(&StreamRef, &mut D, u32, *const pw_sys::pw_stream_control)>>,
+    pub io_changed: Option<Box<dyn FnMut(&StreamRef, &mut D, u32, *mut os::raw::c_void, u32)>>,
+    pub param_changed: Option<Box<ParamChangedCB<D>>>,
+    pub add_buffer: Option<Box<dyn FnMut(&StreamRef, &mut D, *mut pw_sys::pw_buffer)>>,
+    pub remove_buffer: Option<Box<dyn FnMut(&StreamRef, &mut D, *mut pw_sys::pw_buffer)>>,
+    pub process: Option<Box<ProcessCB<D>>>,
+    pub drained: Option<Box<dyn FnMut(&StreamRef, &mut D)>>,
+    #[cfg(feature = "v0_3_39")]
+    pub command: Option<Box<dyn FnMut(&StreamRef, &mut D, *const spa_sys::spa_command)>>,
+    #[cfg(feature = "v0_3_40")]
+    pub trigger_done: Option<Box<dyn FnMut(&StreamRef, &mut D)>>,
+    #[cfg(feature = "v1_2_0")]
+    pub request_process: Option<Box<dyn FnMut(&StreamRef, &mut D)>>,
+    pub user_data: D,
+    stream: Option<ptr::NonNull<pw_sys::pw_stream>>,
+}
+
+unsafe fn unwrap_stream_ptr<'a>(stream: Option<ptr::NonNull<pw_sys::pw_stream>>) -> &'a StreamRef {
+    stream
+        .map(|ptr| ptr.cast::<StreamRef>().as_ref())
+        .expect("stream cannot be null")
+}
+
+impl<D> ListenerLocalCallbacks<D> {
+    fn with_user_data(user_data: D) -> Self {
+        ListenerLocalCallbacks {
+            destroy: Default::default(),
+            process: Default::default(),
+            stream: Default::default(),
+            drained: Default::default(),
+            add_buffer: Default::default(),
+            control_info: Default::default(),
+            io_changed: Default::default(),
+            param_changed: Default::default(),
+            remove_buffer: Default::default(),
+            state_changed: Default::default(),
+            #[cfg(feature = "v0_3_39")]
+            command: Default::default(),
+            #[cfg(feature = "v0_3_40")]
+            trigger_done: Default::default(),
+            #[cfg(feature = "v1_2_0")]
+            request_process: Default::default(),
+            user_data,
+        }
+    }
+
+    pub(crate) fn into_raw(self) -> (pw_sys::pw_stream_events, Self) {
+        let callbacks = self;
+
+        unsafe extern "C" fn on_destroy<D>(data: *mut os::raw::c_void) {
+            crate::unwind::guard("on_destroy", || {
+                let state = listeners::callback_data_mut::<ListenerLocalCallbacks<D>>(data);
+                if let Some(cb) = &mut state.destroy {
+                    let stream = unwrap_stream_ptr(state.stream);
+                    cb(stream, &mut state.user_data);
+                }
+            })
+        }
+
+        unsafe extern "C" fn on_state_changed<D>(
+            data: *mut os::raw::c_void,
+            old: pw_sys::pw_stream_state,
+            new: pw_sys::pw_stream_state,
+            error: *const os::raw::c_char,
+        ) {
+            crate::unwind::guard("on_state_changed", || {
+                let state = listeners::callback_data_mut::<ListenerLocalCallbacks<D>>(data);
+                if let Some(cb) = &mut state.state_changed {
+                    let stream = unwrap_stream_ptr(state.stream);
+                    let old = StreamState::from_raw(old, error);
+                    let new = StreamState::from_raw(new, error);
+                    cb(stream, &mut state.user_data, old, new)
+                };
+            })
+        }
+
+        unsafe extern "C" fn on_control_info<D>(
+            data: *mut os::raw::c_void,
+            id: u32,
+            control: *const pw_sys::pw_stream_control,
+        ) {
+            crate::unwind::guard("on_control_info", || {
+                let state = listeners::callback_data_mut::<ListenerLocalCallbacks<D>>(data);
+                if let Some(cb) = &mut state.control_info {
+                    let stream = unwrap_stream_ptr(state.stream);
+                    cb(stream, &mut state.user_data, id, control);
+                }
+            })
+        }
+
+        unsafe extern "C" fn on_io_changed<D>(
+            data: *mut os::raw::c_void,
+            id: u32,
+            area: *mut os::raw::c_void,
+            size: u32,
+        ) {
+            crate::unwind::guard("on_io_changed", || {
+                let state = listeners::callback_data_mut::<ListenerLocalCallbacks<D>>(data);
+                if let Some(cb) = &mut state.io_changed {
+                    let stream = unwrap_stream_ptr(state.stream);
+                    cb(stream, &mut state.user_data, id, area, size);
+                }
+            })
+        }
+
+        unsafe extern "C" fn on_param_changed<D>(
+            data: *mut os::raw::c_void,
+            id: u32,
+            param: *const spa_sys::spa_pod,
+        ) {
+            crate::unwind::guard("on_param_changed", || {
+                let state = listeners::callback_data_mut::<ListenerLocalCallbacks<D>>(data);
+                if let Some(cb) = &mut state.param_changed {
+                    let stream = unwrap_stream_ptr(state.stream);
+                    let param = if !param.is_null() {
+                        Some(spa::pod::Pod::from_raw(param))
+                    } else {
+                        None
+                    };
+
+                    cb(stream, &mut state.user_data, id, param);
+                }
+            })
+        }
+
+        unsafe extern "C" fn on_add_buffer<D>(
+            data: *mut ::std::os::raw::c_void,
+            buffer: *mut pw_sys::pw_buffer,
+        ) {
+            crate::unwind::guard("on_add_buffer", || {
+                let state = listeners::callback_data_mut::<ListenerLocalCallbacks<D>>(data);
+                if let Some(cb) = &mut state.add_buffer {
+                    let stream = unwrap_stream_ptr(state.stream);
+                    cb(stream, &mut state.user_data, buffer);
+                }
+            })
+        }
+
+        unsafe extern "C" fn on_remove_buffer<D>(
+            data: *mut ::std::os::raw::c_void,
+            buffer: *mut pw_sys::pw_buffer,
+        ) {
+            crate::unwind::guard("on_remove_buffer", || {
+                let state = listeners::callback_data_mut::<ListenerLocalCallbacks<D>>(data);
+                if let Some(cb) = &mut state.remove_buffer {
+                    let stream = unwrap_stream_ptr(state.stream);
+                    cb(stream, &mut state.user_data, buffer);
+                }
+            })
+        }
+
+        unsafe extern "C" fn on_process<D>(data: *mut ::std::os::raw::c_void) {
+            crate::unwind::guard("on_process", || {
+                let state = listeners::callback_data_mut::<ListenerLocalCallbacks<D>>(data);
+                if let Some(cb) = &mut state.process {
+                    let stream = unwrap_stream_ptr(state.stream);
+                    cb(stream, &mut state.user_data);
+                }
+            })
+        }
+
+        unsafe extern "C" fn on_drained<D>(data: *mut ::std::os::raw::c_void) {
+            crate::unwind::guard("on_drained", || {
+                let state = listeners::callback_data_mut::<ListenerLocalCallbacks<D>>(data);
+                if let Some(cb) = &mut state.drained {
+                    let stream = unwrap_stream_ptr(state.stream);
+                    cb(stream, &mut state.user_data);
+                }
+            })
+        }
+
+        #[cfg(feature = "v0_3_39")]
+        unsafe extern "C" fn on_command<D>(
+            data: *mut ::std::os::raw::c_void,
+            command: *const spa_sys::spa_command,
+        ) {
+            crate::unwind::guard("on_command", || {
+                let state = listeners::callback_data_mut::<ListenerLocalCallbacks<D>>(data);
+                #[cfg(feature = "v1_2_0")]
+                if is_request_process(command) {
+                    if let Some(cb) = &mut state.request_process {
+                        let stream = unwrap_stream_ptr(state.stream);
+                        cb(stream, &mut state.user_data);
+                        return;
+                    }
+                }
+                if let Some(cb) = &mut state.command {
+                    let stream = unwrap_stream_ptr(state.stream);
+                    cb(stream, &mut state.user_data, command);
+                }
+            })
+        }
+
+        #[cfg(feature = "v0_3_40")]
+        unsafe extern "C" fn on_trigger_done<D>(data: *mut ::std::os::raw::c_void) {
+            crate::unwind::guard("on_trigger_done", || {
+                let state = listeners::callback_data_mut::<ListenerLocalCallbacks<D>>(data);
+                if let Some(cb) = &mut state.trigger_done {
+                    let stream = unwrap_stream_ptr(state.stream);
+                    cb(stream, &mut state.user_data);
+                }
+            })
+        }
+
+        let events = unsafe {
+            let mut events: pw_sys::pw_stream_events = mem::zeroed();
+            events.version = callbacks.events_version();
+
+            if callbacks.destroy.is_some() {
+                events.destroy = Some(on_destroy::<D>);
+            }
+            if callbacks.state_changed.is_some() {
+                events.state_changed = Some(on_state_changed::<D>);
+            }
+            if callbacks.control_info.is_some() {
+                events.control_info = Some(on_control_info::<D>);
+            }
+            if callbacks.io_changed.is_some() {
+                events.io_changed = Some(on_io_changed::<D>);
+            }
+            if callbacks.param_changed.is_some() {
+                events.param_changed = Some(on_param_changed::<D>);
+            }
+            if callbacks.add_buffer.is_some() {
+                events.add_buffer = Some(on_add_buffer::<D>);
+            }
+            if callbacks.remove_buffer.is_some() {
+                events.remove_buffer = Some(on_remove_buffer::<D>);
+            }
+            if callbacks.process.is_some() {
+                events.process = Some(on_process::<D>);
+            }
+            if callbacks.drained.is_some() {
+                events.drained = Some(on_drained::<D>);
+            }
+            #[cfg(all(feature = "v0_3_39", not(feature = "v1_2_0")))]
+            if callbacks.command.is_some() {
+                events.command = Some(on_command::<D>);
+            }
+            #[cfg(feature = "v1_2_0")]
+            if callbacks.command.is_some() || callbacks.request_process.is_some() {
+                events.command = Some(on_command::<D>);
+            }
+            #[cfg(feature = "v0_3_40")]
+            if callbacks.trigger_done.is_some() {
+                events.trigger_done = Some(on_trigger_done::<D>);
+            }
+
+            events
+        };
+
+        (events, callbacks)
+    }
+
+    /// The lowest version of `pw_stream_events` with all the events of the callbacks.
+    ///
+    /// Events are only emitted by a libpipewire as recent as the version of the struct, so
+    /// listeners not using the newer events keep working with older libraries.
+    fn events_version(&self) -> u32 {
+        #[allow(unused_mut)]
+        let mut version = 0;
+        #[cfg(all(feature = "v0_3_39", not(feature = "v1_2_0")))]
+        if self.command.is_some() {
+            version = 1;
+        }
+        #[cfg(feature = "v1_2_0")]
+        if self.command.is_some() || self.request_process.is_some() {
+            version = 1;
+        }
+        #[cfg(feature = "v0_3_40")]
+        if self.trigger_done.is_some() {
+            version = 2;
+        }
+
+        version.min(pw_sys::PW_VERSION_STREAM_EVENTS)
+    }
+}
+
+/// Whether `command` is the `RequestProcess` command of a node.
+#[cfg(feature = "v1_2_0")]
+unsafe fn is_request_process(command: *const spa_sys::spa_command) -> bool {
+    command.as_ref().map_or(false, |command| {
+        command.body.body.type_ == spa_sys::SPA_TYPE_COMMAND_Node
+            && command.body.body.id == spa_sys::SPA_NODE_COMMAND_RequestProcess
+    })
+}
+
+#[must_use]
+pub struct ListenerLocalBuilder<'a, D> {
+    stream: &'a StreamRef,
+    callbacks: ListenerLocalCallbacks<D>,
+}
+
+impl<'a, D> ListenerLocalBuilder<'a, D> {
+    /// Set the callback for the `destroy` event, emitted when the stream is being destroyed.
+    pub fn destroy<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&StreamRef, &mut D) + 'static,
+    {
+        self.callbacks.destroy = Some(Box::new(callback));
+        self
+    }
+
+    /// Set the callback for the `state_changed` event.
+    pub fn state_changed<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&StreamRef, &mut D, StreamState, StreamState) + 'static,
+    {
+        self.callbacks.state_changed = Some(Box::new(callback));
+        self
+    }
+
+    /// Set the callback for the `control_info` event.
+    pub fn control_info<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&StreamRef, &mut D, u32, *const pw_sys::pw_stream_control) + 'static,
+    {
+        self.callbacks.control_info = Some(Box::new(callback));
+        self
+    }
+
+    /// Set the callback for the `io_changed` event.
+    pub fn io_changed<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&StreamRef, &mut D, u32, *mut os::raw::c_void, u32) + 'static,
+    {
+        self.callbacks.io_changed = Some(Box::new(callback));
+        self
+    }
+
+    /// Set the callback for the `param_changed` event.
+    ///
+    /// The param is `None` when the param with the given id was removed, such as the format when
+    /// the stream is disconnected from its peer.
+    pub fn param_changed<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&StreamRef, &mut D, u32, Option<&spa::pod::Pod>) + 'static,
+    {
+        self.callbacks.param_changed = Some(Box::new(callback));
+        self
+    }
+
+    /// Set the callback for the `add_buffer` event.
+    pub fn add_buffer<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&StreamRef, &mut D, *mut pw_sys::pw_buffer) + 'static,
+    {
+        self.callbacks.add_buffer = Some(Box::new(callback));
+        self
+    }
+
+    /// Set the callback for the `remove_buffer` event.
+    pub fn remove_buffer<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&StreamRef, &mut D, *mut pw_sys::pw_buffer) + 'static,
+    {
+        self.callbacks.remove_buffer = Some(Box::new(callback));
+        self
+    }
+
+    /// Allocate the memory of the buffers of a stream connected with
+    /// [`StreamFlags::ALLOC_BUFFERS`] from `pool`.
+    ///
+    /// This replaces the callbacks of the `add_buffer` and `remove_buffer` events.
+    /// Errors happening while allocating a buffer can be retrieved with [`MemPool::take_error`].
+    pub fn mem_pool(mut self, pool: Rc<MemPool>) -> Self {
+        let add_pool = pool.clone();
+        self.callbacks.add_buffer = Some(Box::new(move |_, _, buffer| {
+            // Safety: the buffer comes from the add_buffer event.
+            if let Err(err) = unsafe { add_pool.alloc(buffer) } {
+                add_pool.set_error(err);
+            }
+        }));
+        self.callbacks.remove_buffer = Some(Box::new(move |_, _, buffer| {
+            // Safety: the buffer comes from the remove_buffer event.
+            unsafe { pool.free(buffer) }
+        }));
+        self
+    }
+
+    /// Set the callback for the `process` event.
+    pub fn process<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&StreamRef, &mut D) + 'static,
+    {
+        self.callbacks.process = Some(Box::new(callback));
+        self
+    }
+
+    /// Set the callback for the `drained` event, emitted once the queued data has been played
+    /// after [`StreamRef::flush`] was called with `drain` set.
+    pub fn drained<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&StreamRef, &mut D) + 'static,
+    {
+        self.callbacks.drained = Some(Box::new(callback));
+        self
+    }
+
+    /// Set the callback for the `command` event, emitted with the commands sent to the node of
+    /// the stream.
+    ///
+    /// The `RequestProcess` command is passed to the callback set with
+    /// [`ListenerLocalBuilder::request_process`] instead, when there is one.
+    #[cfg(feature = "v0_3_39")]
+    pub fn command<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&StreamRef, &mut D, *const spa_sys::spa_command) + 'static,
+    {
+        self.callbacks.command = Some(Box::new(callback));
+        self
+    }
+
+    /// Set the callback for the `trigger_done` event, emitted when the graph triggered with
+    /// [`StreamRef::trigger_process`] completed.
+    ///
+    /// It is emitted from the main thread, or from the realtime data thread with the
+    /// `RT_TRIGGER_DONE` flag.
+    #[cfg(feature = "v0_3_40")]
+    pub fn trigger_done<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&StreamRef, &mut D) + 'static,
+    {
+        self.callbacks.trigger_done = Some(Box::new(callback));
+        self
+    }
+
+    /// Set the callback called when a follower of the graph asks for it to be scheduled, with
+    /// the `RequestProcess` command.
+    ///
+    /// A lazy driver, such as a screencast only rendering frames when they are consumed, sets the
+    /// [`NODE_SUPPORTS_LAZY`](crate::keys::NODE_SUPPORTS_LAZY) property and produces a
+    /// frame from this callback, before calling [`StreamRef::trigger_process`].
+    #[cfg(feature = "v1_2_0")]
+    pub fn request_process<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&StreamRef, &mut D) + 'static,
+    {
+        self.callbacks.request_process = Some(Box::new(callback));
+        self
+    }
+
+    //// Register the Callbacks
+    ///
+    /// Stop building the listener and register it on the stream. Returns a
+    /// `StreamListener` handlle that will un-register the listener on drop.
+    pub fn register(self) -> Result<StreamListener<D>, Error>
+    where
+        D: 'static,
+    {
+        self.register_unbounded()
+    }
+
+    /// Register the listener until the end of `scope`, where it is removed.
+    ///
+    /// Unlike with [`register`](Self::register), the user data can borrow data living outside
+    /// of the scope, as the scope guarantees that the listener does not outlive it.
+    pub fn register_scoped<'env>(self, scope: &ListenerScope<'env>) -> Result<(), Error>
+    where
+        D: 'env,
+    {
+        scope.hold(self.register_unbounded()?);
+        Ok(())
+    }
+
+    /// Register the listener, whose lifetime has to be bounded by the caller.
+    fn register_unbounded(self) -> Result<StreamListener<D>, Error> {
+        let (events, data) = self.callbacks.into_raw();
+        let stream = self.stream.as_raw_ptr();
+        let listener = unsafe {
+            listeners::Listener::new(events, data, |hook, events, data| {
+                pw_sys::pw_stream_add_listener(stream, hook, events, data);
+            })
+        };
+        Ok(StreamListener {
+            _listener: listener,
+        })
+    }
+}
+
+pub struct StreamListener<D> {
+    _listener: listeners::Listener<pw_sys::pw_stream_events, ListenerLocalCallbacks<D>>,
+}
+
+impl<D> StreamListener<D> {
+    /// Stop the listener from receiving any events
+    ///
+    /// Removes the listener registration and cleans up allocated resources.
+    pub fn unregister(self) {
+        // do nothing, drop will clean up.
+    }
+
+    /// Stop the listener from receiving any events, and take back its user data.
+    ///
+    /// This is the safe way to read the state accumulated by the callbacks, for example once
+    /// [`MainLoop::run`](crate::main_loop::MainLoop::run) returned.
+    pub fn into_user_data(self) -> D {
+        self._listener.into_data().user_data
+    }
+
+    /// The user data passed to the callbacks.
+    ///
+    /// # Safety
+    /// No callback may run while the returned reference is alive, which is the case as long as
+    /// the loops of the stream are not iterated. Streams connected with [`StreamFlags::RT_PROCESS`]
+    /// call the `process` callback from the data loop thread, which has to be stopped too.
+    pub unsafe fn user_data(&self) -> &D {
+        &self._listener.data().user_data
+    }
+
+    /// The user data passed to the callbacks, mutably.
+    ///
+    /// # Safety
+    /// Same as [`user_data`](Self::user_data).
+    pub unsafe fn user_data_mut(&mut self) -> &mut D {
+        &mut self._listener.data_mut().user_data
+    }
+
+    /// Stop the listener from receiving any events, and take back its callbacks and user data.
+    ///
+    /// The callbacks can be registered again, on this stream or another one, with
+    /// [`StreamRef::add_local_listener_with_callbacks`].
+    pub fn into_callbacks(self) -> ListenerLocalCallbacks<D> {
+        let mut callbacks = self._listener.into_data();
+        callbacks.stream = None;
+        callbacks
+    }
+}
+
+/// The node a stream connects to, see [`StreamRef::connect_to`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StreamTarget<'a> {
+    /// Any suitable node, picked by the session manager.
+    #[default]
+    Any,
+    /// The node with this global id.
+    Id(u32),
+    /// The node with this `object.serial`, which unlike its id is never reused.
+    ///
+    /// Requires PipeWire 0.3.44 or later.
+    Serial(u64),
+    /// The node with this `node.name`, such as `alsa_output.pci-0000_00_1f.3.analog-stereo`.
+    ///
+    /// Requires PipeWire 0.3.44 or later.
+    Name(&'a str),
+}
+
+impl From<Option<u32>> for StreamTarget<'_> {
+    fn from(id: Option<u32>) -> Self {
+        id.map_or(Self::Any, Self::Id)
+    }
+}
+
+impl<'a> From<&'a str> for StreamTarget<'a> {
+    fn from(name: &'a str) -> Self {
+        Self::Name(name)
+    }
+}
+
+bitflags! {
+    /// Extra flags that can be used in [`Stream::connect()`]
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub struct StreamFlags: pw_sys::pw_stream_flags {
+        const AUTOCONNECT = pw_sys::pw_stream_flags_PW_STREAM_FLAG_AUTOCONNECT;
+        const INACTIVE = pw_sys::pw_stream_flags_PW_STREAM_FLAG_INACTIVE;
+        const MAP_BUFFERS = pw_sys::pw_stream_flags_PW_STREAM_FLAG_MAP_BUFFERS;
+        const DRIVER = pw_sys::pw_stream_flags_PW_STREAM_FLAG_DRIVER;
+        const RT_PROCESS = pw_sys::pw_stream_flags_PW_STREAM_FLAG_RT_PROCESS;
+        const NO_CONVERT = pw_sys::pw_stream_flags_PW_STREAM_FLAG_NO_CONVERT;
+        const EXCLUSIVE = pw_sys::pw_stream_flags_PW_STREAM_FLAG_EXCLUSIVE;
+        const DONT_RECONNECT = pw_sys::pw_stream_flags_PW_STREAM_FLAG_DONT_RECONNECT;
+        const ALLOC_BUFFERS = pw_sys::pw_stream_flags_PW_STREAM_FLAG_ALLOC_BUFFERS;
+        #[cfg(feature = "v0_3_41")]
+        const TRIGGER = pw_sys::pw_stream_flags_PW_STREAM_FLAG_TRIGGER;
+        /// Buffers are not dequeued and queued from the `process` callback,
+        /// which is only called to notify that buffers are available.
+        #[cfg(feature = "v0_3_77")]
+        const ASYNC = pw_sys::pw_stream_flags_PW_STREAM_FLAG_ASYNC;
+        /// Call `process` as soon as the stream is scheduled, to let a driver stream produce its
+        /// data before the rest of the graph.
+        #[cfg(feature = "v1_2_0")]
+        const EARLY_PROCESS = pw_sys::pw_stream_flags_PW_STREAM_FLAG_EARLY_PROCESS;
+        /// Call `trigger_done` from the realtime data thread.
+        #[cfg(feature = "v1_2_0")]
+        const RT_TRIGGER_DONE = pw_sys::pw_stream_flags_PW_STREAM_FLAG_RT_TRIGGER_DONE;
+    }
+}
+
+impl StreamFlags {
+    /// The flags of a stream playing to a device: connect it automatically, map the buffers and
+    /// call `process` from the realtime data thread.
+    pub fn playback_defaults() -> Self {
+        Self::AUTOCONNECT | Self::MAP_BUFFERS | Self::RT_PROCESS
+    }
+
+    /// The flags of a stream capturing from a device, the same as
+    /// [`playback_defaults`](Self::playback_defaults).
+    pub fn capture_defaults() -> Self {
+        Self::AUTOCONNECT | Self::MAP_BUFFERS | Self::RT_PROCESS
+    }
+
+    /// The flags of a stream driving the graph with its own clock, such as a video source
+    /// producing frames from a timer.
+    pub fn driver() -> Self {
+        Self::DRIVER | Self::MAP_BUFFERS
+    }
+
+    /// Check that the flags don't contradict each other.
+    ///
+    /// [`ALLOC_BUFFERS`](Self::ALLOC_BUFFERS) can't be combined with
+    /// [`MAP_BUFFERS`](Self::MAP_BUFFERS), as the memory allocated by the application is
+    /// already accessible to it.
+    pub fn validate(self) -> Result<(), Error> {
+        if self.contains(Self::ALLOC_BUFFERS | Self::MAP_BUFFERS) {
+            return Err(spa::utils::result::Error::from(nix::errno::Errno::EINVAL))
+                .context("map buffers allocated by the application");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_flags() {
+        StreamFlags::playback_defaults().validate().unwrap();
+        StreamFlags::capture_defaults().validate().unwrap();
+        StreamFlags::driver().validate().unwrap();
+        (StreamFlags::ALLOC_BUFFERS | StreamFlags::AUTOCONNECT)
+            .validate()
+            .unwrap();
+
+        let err = (StreamFlags::ALLOC_BUFFERS | StreamFlags::MAP_BUFFERS)
+            .validate()
+            .unwrap_err();
+        assert_eq!(err.errno(), Some(nix::errno::Errno::EINVAL));
+        assert_eq!(
+            err.operation(),
+            Some("map buffers allocated by the application")
+        );
+    }
+
+    #[test]
+    fn events_version() {
+        let mut callbacks = ListenerLocalCallbacks::with_user_data(());
+        callbacks.process = Some(Box::new(|_, _| {}));
+        assert_eq!(callbacks.events_version(), 0);
+
+        #[cfg(feature = "v0_3_40")]
+        {
+            callbacks.trigger_done = Some(Box::new(|_, _| {}));
+            assert_eq!(callbacks.events_version(), 2);
+        }
+    }
+
+    #[test]
+    fn target() {
+        assert_eq!(StreamTarget::from(None), StreamTarget::Any);
+        assert_eq!(StreamTarget::from(Some(42)), StreamTarget::Id(42));
+        assert_eq!(
+            StreamTarget::from("alsa_output.analog-stereo"),
+            StreamTarget::Name("alsa_output.analog-stereo")
+        );
+        assert_eq!(StreamTarget::default(), StreamTarget::Any);
+    }
+
+    #[test]
+    #[cfg(feature = "v1_2_0")]
+    fn request_process_command() {
+        let command = |id| spa_sys::spa_command {
+            pod: spa_sys::spa_pod {
+                size: mem::size_of::<spa_sys::spa_command_body>() as u32,
+                type_: spa_sys::SPA_TYPE_Object,
+            },
+            body: spa_sys::spa_command_body {
+                body: spa_sys::spa_pod_object_body {
+                    type_: spa_sys::SPA_TYPE_COMMAND_Node,
+                    id,
+                },
+            },
+        };
+
+        unsafe {
+            assert!(is_request_process(&command(
+                spa_sys::SPA_NODE_COMMAND_RequestProcess
+            )));
+            assert!(!is_request_process(&command(
+                spa_sys::SPA_NODE_COMMAND_Start
+            )));
+            assert!(!is_request_process(ptr::null()));
+        }
+    }
+}
@@ -0,0 +1,120 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Access to the node and ports backing a stream.
+//!
+//! A connected stream is exported to the server as a node, with one port per channel or
+//! per buffer queue. [`StreamRef::node`] and [`StreamRef::ports`] find these objects through the
+//! registry of the core the stream was created on, so node level params can be set on them.
+
+use std::{cell::RefCell, rc::Rc};
+
+use nix::errno::Errno;
+
+use super::StreamRef;
+use crate::{
+    error::{Error, ResultExt},
+    keys,
+    node::Node,
+    permissions::PermissionFlags,
+    properties::Properties,
+    registry::{self, GlobalFilter, GlobalObject, Registry},
+    types::ObjectType,
+};
+
+/// The ports of a stream, as returned by [`StreamRef::ports`].
+///
+/// The ports are announced asynchronously by the registry: the list is filled once the
+/// server has processed the registry creation, such as after a [`sync`](crate::core::CoreRef::sync)
+/// of the core, and is kept up to date while this object is alive.
+pub struct StreamPorts {
+    ports: Rc<RefCell<Vec<GlobalObject<Properties>>>>,
+    // The listener has to be dropped before the registry it is registered on.
+    _listener: registry::Listener,
+    _registry: Registry,
+}
+
+impl StreamPorts {
+    /// The ports of the stream known so far.
+    pub fn ports(&self) -> std::cell::Ref<'_, [GlobalObject<Properties>]> {
+        std::cell::Ref::map(self.ports.borrow(), Vec::as_slice)
+    }
+
+    /// The ids of the ports of the stream known so far.
+    pub fn ids(&self) -> Vec<u32> {
+        self.ports.borrow().iter().map(|port| port.id).collect()
+    }
+}
+
+impl std::fmt::Debug for StreamPorts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamPorts")
+            .field("ports", &self.ports.borrow())
+            .finish()
+    }
+}
+
+impl StreamRef {
+    /// The id of the node of the stream, or an error if the stream is not connected.
+    fn connected_node_id(&self, operation: &'static str) -> Result<u32, Error> {
+        match self.node_id() {
+            spa_sys::SPA_ID_INVALID => {
+                Err(spa::utils::result::Error::from(Errno::ENOTCONN)).context(operation)
+            }
+            id => Ok(id),
+        }
+    }
+
+    /// Bind the node backing the stream.
+    ///
+    /// The stream has to be connected, and its node exported to the server, which is the case
+    /// once it left the [`Connecting`](super::StreamState::Connecting) state.
+    pub fn node(&self) -> Result<Node, Error> {
+        const OP: &str = "bind the node of the stream";
+
+        let id = self.connected_node_id(OP)?;
+        let registry = self.core().get_registry().context(OP)?;
+        let object = GlobalObject {
+            id,
+            permissions: PermissionFlags::empty(),
+            type_: ObjectType::Node,
+            version: 0,
+            props: None::<Properties>,
+        };
+
+        registry.bind(&object).context(OP)
+    }
+
+    /// List the ports of the node backing the stream.
+    ///
+    /// The stream has to be connected, see [`StreamRef::node`]. The ports are collected from the
+    /// registry, see [`StreamPorts`], and can be bound with [`Registry::bind`].
+    pub fn ports(&self) -> Result<StreamPorts, Error> {
+        const OP: &str = "list the ports of the stream";
+
+        let id = self.connected_node_id(OP)?;
+        let registry = self.core().get_registry().context(OP)?;
+        let ports = Rc::new(RefCell::new(Vec::new()));
+        let filter = GlobalFilter::new()
+            .type_(ObjectType::Port)
+            .prop(*keys::NODE_ID, id.to_string());
+
+        let listener = registry
+            .add_filtered_listener_local(filter)
+            .global({
+                let ports = ports.clone();
+                move |global| ports.borrow_mut().push(global.to_owned())
+            })
+            .global_remove({
+                let ports = ports.clone();
+                move |id| ports.borrow_mut().retain(|port| port.id != id)
+            })
+            .register();
+
+        Ok(StreamPorts {
+            ports,
+            _listener: listener,
+            _registry: registry,
+        })
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! The fallible calls of [`StreamRef`](super::StreamRef) into libpipewire.
+//!
+//! They go through [`StreamOps`] so that the `test-shims` feature can put
+//! [failures](super::shim) in front of them.
+
+use std::os::raw::c_int;
+
+/// The stream functions of libpipewire, with the same arguments and results.
+pub(super) trait StreamOps {
+    unsafe fn connect(
+        stream: *mut pw_sys::pw_stream,
+        direction: spa_sys::spa_direction,
+        target_id: u32,
+        flags: pw_sys::pw_stream_flags,
+        params: *mut *const spa_sys::spa_pod,
+        n_params: u32,
+    ) -> c_int;
+
+    unsafe fn update_params(
+        stream: *mut pw_sys::pw_stream,
+        params: *mut *const spa_sys::spa_pod,
+        n_params: u32,
+    ) -> c_int;
+
+    unsafe fn dequeue_buffer(stream: *mut pw_sys::pw_stream) -> *mut pw_sys::pw_buffer;
+
+    unsafe fn queue_buffer(stream: *mut pw_sys::pw_stream, buffer: *mut pw_sys::pw_buffer)
+        -> c_int;
+}
+
+/// Direct calls to libpipewire.
+pub(super) struct Sys;
+
+impl StreamOps for Sys {
+    unsafe fn connect(
+        stream: *mut pw_sys::pw_stream,
+        direction: spa_sys::spa_direction,
+        target_id: u32,
+        flags: pw_sys::pw_stream_flags,
+        params: *mut *const spa_sys::spa_pod,
+        n_params: u32,
+    ) -> c_int {
+        pw_sys::pw_stream_connect(stream, direction, target_id, flags, params, n_params)
+    }
+
+    unsafe fn update_params(
+        stream: *mut pw_sys::pw_stream,
+        params: *mut *const spa_sys::spa_pod,
+        n_params: u32,
+    ) -> c_int {
+        pw_sys::pw_stream_update_params(stream, params, n_params)
+    }
+
+    unsafe fn dequeue_buffer(stream: *mut pw_sys::pw_stream) -> *mut pw_sys::pw_buffer {
+        pw_sys::pw_stream_dequeue_buffer(stream)
+    }
+
+    unsafe fn queue_buffer(
+        stream: *mut pw_sys::pw_stream,
+        buffer: *mut pw_sys::pw_buffer,
+    ) -> c_int {
+        pw_sys::pw_stream_queue_buffer(stream, buffer)
+    }
+}
+
+/// The implementation used by the streams.
+#[cfg(not(feature = "test-shims"))]
+pub(super) type Ops = Sys;
+#[cfg(feature = "test-shims")]
+pub(super) type Ops = super::shim::Shimmed;
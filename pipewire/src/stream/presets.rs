@@ -0,0 +1,478 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Ready-made streams for common use cases.
+//!
+//! These presets take care of the format negotiation and buffer handling boilerplate,
+//! and hand decoded data to a callback.
+
+use std::{cell::Cell, os::fd::RawFd, rc::Rc};
+
+use spa::{
+    buffer::{ChunkFlags, DataType, DataTypes},
+    param::{
+        format::{FormatProperties, MediaSubtype, MediaType},
+        format_utils,
+        video::{VideoFormat, VideoInfoRaw},
+        ParamType,
+    },
+    pod::{
+        deserialize::PodDeserializer, io::PodBuf, ChoiceValue, Object, Pod, Property,
+        PropertyFlags, Value,
+    },
+    utils::{
+        Choice, ChoiceEnum, ChoiceFlags, Direction, Fraction, Id, IdEnum, Rectangle, SpaTypes,
+    },
+};
+
+use super::{Stream, StreamFlags, StreamListener, StreamRef};
+use crate::{buffer::Buffer, core::Core, properties::properties, Error};
+
+/// Options of a [`video_capture`] stream.
+#[derive(Debug, Clone)]
+pub struct VideoCaptureOptions {
+    /// The name of the stream.
+    pub name: String,
+    /// The accepted pixel formats, in order of preference.
+    pub formats: Vec<VideoFormat>,
+    /// The accepted DMA-BUF modifiers, in order of preference.
+    ///
+    /// When empty, only shared memory buffers are negotiated.
+    pub modifiers: Vec<u64>,
+    /// The preferred frame size.
+    pub size: Rectangle,
+    /// The maximum frame size.
+    pub max_size: Rectangle,
+    /// The preferred frame rate.
+    pub framerate: Fraction,
+    /// The maximum frame rate.
+    pub max_framerate: Fraction,
+}
+
+impl Default for VideoCaptureOptions {
+    fn default() -> Self {
+        Self {
+            name: String::from("video-capture"),
+            formats: vec![
+                VideoFormat::BGRx,
+                VideoFormat::RGBx,
+                VideoFormat::BGRA,
+                VideoFormat::RGBA,
+            ],
+            modifiers: Vec::new(),
+            size: Rectangle {
+                width: 1920,
+                height: 1080,
+            },
+            max_size: Rectangle {
+                width: 8192,
+                height: 8192,
+            },
+            framerate: Fraction { num: 30, denom: 1 },
+            max_framerate: Fraction {
+                num: 1000,
+                denom: 1,
+            },
+        }
+    }
+}
+
+impl VideoCaptureOptions {
+    /// Build the `EnumFormat` objects offered by the stream.
+    ///
+    /// When modifiers are set, a DMA-BUF format is offered first,
+    /// followed by a shared memory format as fallback.
+    pub fn enum_formats(&self) -> Vec<Object> {
+        let mut objects = Vec::new();
+        if !self.modifiers.is_empty() {
+            objects.push(self.enum_format(Some(&self.modifiers)));
+        }
+        objects.push(self.enum_format(None));
+        objects
+    }
+
+    fn enum_format(&self, modifiers: Option<&[u64]>) -> Object {
+        let default = self.formats.first().map(IdEnum::to_id).unwrap_or(Id(0));
+        let formats = Choice::id_enum(default, self.formats.iter().map(IdEnum::to_id));
+
+        let mut properties = vec![
+            Property::new(
+                FormatProperties::MediaType.as_raw(),
+                Value::Id(MediaType::Video.to_id()),
+            ),
+            Property::new(
+                FormatProperties::MediaSubtype.as_raw(),
+                Value::Id(MediaSubtype::Raw.to_id()),
+            ),
+            Property::new(
+                FormatProperties::VideoFormat.as_raw(),
+                Value::Choice(ChoiceValue::Id(formats)),
+            ),
+        ];
+
+        if let Some(modifiers) = modifiers {
+            // Modifiers are 64-bit values carried in a Long pod.
+            let modifiers: Vec<i64> = modifiers.iter().map(|&m| m as i64).collect();
+            let flags = PropertyFlags::MANDATORY;
+            #[cfg(feature = "v0_3_33")]
+            let flags = flags | PropertyFlags::DONT_FIXATE;
+
+            properties.push(
+                Property::new(
+                    FormatProperties::VideoModifier.as_raw(),
+                    Value::Choice(ChoiceValue::Long(Choice(
+                        ChoiceFlags::empty(),
+                        ChoiceEnum::Enum {
+                            default: modifiers[0],
+                            alternatives: modifiers,
+                        },
+                    ))),
+                )
+                .with_flags(flags),
+            );
+        }
+
+        properties.push(Property::new(
+            FormatProperties::VideoSize.as_raw(),
+            Value::Choice(ChoiceValue::Rectangle(Choice(
+                ChoiceFlags::empty(),
+                ChoiceEnum::Range {
+                    default: self.size,
+                    min: Rectangle {
+                        width: 1,
+                        height: 1,
+                    },
+                    max: self.max_size,
+                },
+            ))),
+        ));
+        properties.push(Property::new(
+            FormatProperties::VideoFramerate.as_raw(),
+            Value::Choice(ChoiceValue::Fraction(Choice(
+                ChoiceFlags::empty(),
+                ChoiceEnum::Range {
+                    default: self.framerate,
+                    min: Fraction { num: 0, denom: 1 },
+                    max: self.max_framerate,
+                },
+            ))),
+        ));
+
+        Object {
+            type_: SpaTypes::ObjectParamFormat.as_raw(),
+            id: ParamType::EnumFormat.as_raw(),
+            properties,
+        }
+    }
+}
+
+/// A plane of a [`VideoFrame`].
+#[derive(Debug)]
+pub struct VideoPlane<'a> {
+    /// The type of memory holding the plane.
+    pub data_type: DataType,
+    /// The mapped content of the plane, starting at its offset.
+    ///
+    /// This is `None` for DMA-BUF planes, which have to be imported through [`VideoPlane::fd`].
+    pub data: Option<&'a [u8]>,
+    /// The file descriptor of the plane memory, for DMA-BUF and memfd planes.
+    pub fd: Option<RawFd>,
+    /// The offset of the plane in its memory.
+    pub offset: u32,
+    /// The distance in bytes between two lines of the plane.
+    pub stride: i32,
+    /// The size in bytes of the plane.
+    pub size: u32,
+}
+
+/// A frame received by a [`video_capture`] stream.
+#[derive(Debug)]
+pub struct VideoFrame<'a> {
+    /// The negotiated format of the frame.
+    pub format: &'a VideoInfoRaw,
+    /// The planes of the frame.
+    pub planes: Vec<VideoPlane<'a>>,
+    /// The presentation timestamp of the frame in nanoseconds, if provided by the producer.
+    pub pts: Option<i64>,
+}
+
+/// A stream set up by [`video_capture`].
+///
+/// The stream is disconnected when this is dropped.
+pub struct VideoCapture {
+    // The listener has to be dropped before the stream.
+    _listener: StreamListener<()>,
+    stream: Stream,
+    format: Rc<Cell<Option<VideoInfoRaw>>>,
+}
+
+impl VideoCapture {
+    /// The underlying stream.
+    pub fn stream(&self) -> &Stream {
+        &self.stream
+    }
+
+    /// The negotiated video format, once the format negotiation completed.
+    pub fn format(&self) -> Option<VideoInfoRaw> {
+        self.format.get()
+    }
+}
+
+/// If the modifier of `format` still needs to be fixated, return the format with its preferred modifier picked.
+fn fixate_modifier(format: &Pod) -> Option<Object> {
+    let (_, Value::Object(mut object)) =
+        PodDeserializer::deserialize_any_from(format.as_bytes()).ok()?
+    else {
+        return None;
+    };
+
+    let property = object
+        .properties
+        .iter_mut()
+        .find(|p| p.key == FormatProperties::VideoModifier.as_raw())?;
+    let Value::Choice(ChoiceValue::Long(Choice(_, choice))) = &property.value else {
+        return None;
+    };
+
+    property.value = Value::Long(*choice.default());
+    property.flags = PropertyFlags::MANDATORY;
+    object.id = ParamType::EnumFormat.as_raw();
+    Some(object)
+}
+
+/// Build the `Buffers` and `Meta` params for the negotiated format.
+fn buffer_params(dmabuf: bool) -> [Object; 2] {
+    let data_types = if dmabuf {
+        DataTypes::from(DataType::DmaBuf)
+    } else {
+        DataTypes::from(DataType::MemPtr).with(DataType::MemFd)
+    };
+
+    [
+        Object {
+            type_: SpaTypes::ObjectParamBuffers.as_raw(),
+            id: ParamType::Buffers.as_raw(),
+            properties: vec![data_types.to_property()],
+        },
+        Object {
+            type_: SpaTypes::ObjectParamMeta.as_raw(),
+            id: ParamType::Meta.as_raw(),
+            properties: vec![
+                Property::new(
+                    spa_sys::SPA_PARAM_META_type,
+                    Value::Id(Id(spa_sys::SPA_META_Header)),
+                ),
+                Property::new(
+                    spa_sys::SPA_PARAM_META_size,
+                    Value::Int(std::mem::size_of::<spa_sys::spa_meta_header>() as i32),
+                ),
+            ],
+        },
+    ]
+}
+
+fn param_changed(stream: &StreamRef, format_cell: &Cell<Option<VideoInfoRaw>>, param: &Pod) {
+    if format_utils::parse_format(param).ok() != Some((MediaType::Video, MediaSubtype::Raw)) {
+        return;
+    }
+
+    if let Some(fixated) = fixate_modifier(param) {
+        // Offer the fixated format; the producer will then announce the final format.
+        if let Ok(pod) = PodBuf::serialize(&Value::Object(fixated)) {
+            let _ = stream.update_params(&mut [&pod]);
+        }
+        return;
+    }
+
+    let mut format = VideoInfoRaw::new();
+    if format.parse(param).is_err() {
+        return;
+    }
+    format_cell.set(Some(format));
+
+    let params: Vec<PodBuf> = buffer_params(has_modifier(param))
+        .into_iter()
+        .filter_map(|object| PodBuf::serialize(&Value::Object(object)).ok())
+        .collect();
+    let mut pods: Vec<&Pod> = params.iter().map(PodBuf::as_pod).collect();
+    let _ = stream.update_params(&mut pods);
+}
+
+/// Whether the format carries a DMA-BUF modifier.
+fn has_modifier(format: &Pod) -> bool {
+    let Ok((_, Value::Object(object))) = PodDeserializer::deserialize_any_from(format.as_bytes())
+    else {
+        return false;
+    };
+
+    object
+        .properties
+        .iter()
+        .any(|p| p.key == FormatProperties::VideoModifier.as_raw())
+}
+
+fn decode_frame<'a>(buffer: &'a mut Buffer, format: &'a VideoInfoRaw) -> VideoFrame<'a> {
+    let pts = unsafe {
+        let header: *mut spa_sys::spa_meta_header = spa_sys::spa_buffer_find_meta_data(
+            buffer.as_raw().buffer,
+            spa_sys::SPA_META_Header,
+            std::mem::size_of::<spa_sys::spa_meta_header>(),
+        )
+        .cast();
+        header.as_ref().map(|header| header.pts)
+    };
+
+    let planes = buffer
+        .datas_mut()
+        .iter_mut()
+        .filter(|data| !data.chunk().flags().contains(ChunkFlags::CORRUPTED))
+        .map(|data| {
+            let data_type = data.type_();
+            let chunk = data.chunk();
+            let (offset, stride, size) = (chunk.offset(), chunk.stride(), chunk.size());
+            let fd = match data_type {
+                DataType::DmaBuf | DataType::MemFd if data.as_raw().fd >= 0 => {
+                    Some(data.as_raw().fd as RawFd)
+                }
+                _ => None,
+            };
+            let data = data.data().and_then(|bytes| {
+                let start = offset as usize;
+                let end = start.checked_add(size as usize)?;
+                bytes.get(start..end)
+            });
+
+            VideoPlane {
+                data_type,
+                data,
+                fd,
+                offset,
+                stride,
+                size,
+            }
+        })
+        .collect();
+
+    VideoFrame {
+        format,
+        planes,
+        pts,
+    }
+}
+
+/// Create a stream capturing video from the node `node_id`, such as a node of the ScreenCast portal.
+///
+/// The stream offers the formats of `options`, negotiates the buffers and calls `on_frame` with every
+/// decoded frame. When using the portal, the core has to be connected to the PipeWire file
+/// descriptor it returned, with [`Context::connect_fd`](crate::context::Context::connect_fd).
+///
+/// ```no_run
+/// use pipewire::{context::Context, main_loop::MainLoop, stream::presets};
+///
+/// let mainloop = MainLoop::new(None)?;
+/// let context = Context::new(&mainloop)?;
+/// let core = context.connect(None)?;
+///
+/// let _capture = presets::video_capture(&core, Some(42), &Default::default(), |frame| {
+///     let size = frame.format.size();
+///     println!("{}x{} frame with {} planes", size.width, size.height, frame.planes.len());
+/// })?;
+///
+/// mainloop.run();
+/// # Ok::<(), pipewire::Error>(())
+/// ```
+pub fn video_capture<F>(
+    core: &Core,
+    node_id: Option<u32>,
+    options: &VideoCaptureOptions,
+    mut on_frame: F,
+) -> Result<VideoCapture, Error>
+where
+    F: FnMut(&VideoFrame) + 'static,
+{
+    let stream = Stream::new(
+        core,
+        &options.name,
+        properties! {
+            *crate::keys::MEDIA_TYPE => "Video",
+            *crate::keys::MEDIA_CATEGORY => "Capture",
+            *crate::keys::MEDIA_ROLE => "Screen",
+        },
+    )?;
+
+    let format = Rc::new(Cell::new(None));
+    let format_param = format.clone();
+    let format_process = format.clone();
+
+    let listener = stream
+        .add_local_listener()
+        .param_changed(move |stream, _, id, param| {
+            if let Some(param) = param.filter(|_| id == ParamType::Format.as_raw()) {
+                param_changed(stream, &format_param, param);
+            }
+        })
+        .process(move |stream, _| {
+            // Only the most recent frame is of interest, drop the older ones.
+            let mut latest = None;
+            while let Some(buffer) = stream.dequeue_buffer() {
+                latest = Some(buffer);
+            }
+
+            if let (Some(mut buffer), Some(format)) = (latest, format_process.get()) {
+                on_frame(&decode_frame(&mut buffer, &format));
+            }
+        })
+        .register()?;
+
+    let objects: Vec<PodBuf> = options
+        .enum_formats()
+        .into_iter()
+        .filter_map(|object| PodBuf::serialize(&Value::Object(object)).ok())
+        .collect();
+    let mut params: Vec<&Pod> = objects.iter().map(PodBuf::as_pod).collect();
+
+    stream.connect(
+        Direction::Input,
+        node_id,
+        StreamFlags::AUTOCONNECT | StreamFlags::MAP_BUFFERS,
+        &mut params,
+    )?;
+
+    Ok(VideoCapture {
+        _listener: listener,
+        stream,
+        format,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn enum_formats() {
+        let options = VideoCaptureOptions {
+            modifiers: vec![0],
+            ..Default::default()
+        };
+        let formats = options.enum_formats();
+        assert_eq!(formats.len(), 2);
+
+        let has_modifier = |object: &Object| {
+            object
+                .properties
+                .iter()
+                .any(|p| p.key == FormatProperties::VideoModifier.as_raw())
+        };
+        assert!(has_modifier(&formats[0]));
+        assert!(!has_modifier(&formats[1]));
+
+        let pod = PodBuf::serialize(&Value::Object(formats[0].clone())).unwrap();
+        assert!(has_modifier(&fixate_modifier(&pod).unwrap()));
+        assert_eq!(
+            format_utils::parse_format(&pod).unwrap(),
+            (MediaType::Video, MediaSubtype::Raw)
+        );
+    }
+}
@@ -0,0 +1,429 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Reconnection of streams whose target node went away.
+//!
+//! [`Stream::connect`] records how the stream was connected, so that [`Stream::reconnect`] can
+//! connect it again with the same params, such as after the device it was capturing from has been
+//! plugged back. [`AutoReconnect`] does so when the target node of the stream is removed and the
+//! session manager does not move the stream to another node.
+
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+    time::Duration,
+};
+
+use nix::errno::Errno;
+use spa::{
+    pod::{io::PodBuf, Pod},
+    utils::Direction,
+};
+
+use super::{Stream, StreamFlags, StreamRef, StreamState, StreamTarget};
+use crate::{
+    core::Core,
+    error::{Error, ResultExt},
+    keys,
+    loop_::{LoopRef, TimerSource},
+    pending::core_loop,
+    registry::{self, Registry},
+    types::ObjectType,
+};
+
+/// How a stream was last connected.
+#[derive(Debug)]
+pub(super) struct Connection {
+    direction: Direction,
+    target: Target,
+    flags: StreamFlags,
+    /// Copies of the params.
+    params: Vec<PodBuf>,
+}
+
+impl Connection {
+    fn connect(&self, stream: &StreamRef) -> Result<(), Error> {
+        let mut pods: Vec<&Pod> = self.params.iter().map(PodBuf::as_pod).collect();
+        stream.connect_to(self.direction, self.target.get(), self.flags, &mut pods)
+    }
+}
+
+/// An owned [`StreamTarget`].
+#[derive(Debug)]
+enum Target {
+    Any,
+    Id(u32),
+    Serial(u64),
+    Name(String),
+}
+
+impl Target {
+    fn new(target: StreamTarget<'_>) -> Self {
+        match target {
+            StreamTarget::Any => Self::Any,
+            StreamTarget::Id(id) => Self::Id(id),
+            StreamTarget::Serial(serial) => Self::Serial(serial),
+            StreamTarget::Name(name) => Self::Name(name.to_owned()),
+        }
+    }
+
+    /// Whether connecting to the target sets the `target.object` property of the stream.
+    fn is_object(&self) -> bool {
+        matches!(self, Self::Serial(_) | Self::Name(_))
+    }
+
+    fn get(&self) -> StreamTarget<'_> {
+        match self {
+            Self::Any => StreamTarget::Any,
+            Self::Id(id) => StreamTarget::Id(*id),
+            Self::Serial(serial) => StreamTarget::Serial(*serial),
+            Self::Name(name) => StreamTarget::Name(name),
+        }
+    }
+}
+
+impl Stream {
+    /// Connect the stream, see [`StreamRef::connect`].
+    ///
+    /// The arguments are recorded, so that [`Stream::reconnect`] can connect the stream again.
+    /// They are not recorded when connecting through a [`StreamRef`].
+    pub fn connect(
+        &self,
+        direction: Direction,
+        id: Option<u32>,
+        flags: StreamFlags,
+        params: &mut [&Pod],
+    ) -> Result<(), Error> {
+        self.connect_to(direction, StreamTarget::from(id), flags, params)
+    }
+
+    /// Connect the stream to `target`, see [`StreamRef::connect_to`].
+    ///
+    /// The arguments are recorded like with [`Stream::connect`], so the stream reconnects to the
+    /// node with the same name or serial. Connecting to any node or to a node given by id removes
+    /// the `target.object` property set by an earlier connection to a name or serial.
+    pub fn connect_to(
+        &self,
+        direction: Direction,
+        target: StreamTarget<'_>,
+        flags: StreamFlags,
+        params: &mut [&Pod],
+    ) -> Result<(), Error> {
+        self.forget_target_object(&Target::new(target));
+        (**self).connect_to(direction, target, flags, params)?;
+
+        *self.inner.connection.borrow_mut() = Some(Connection {
+            direction,
+            target: Target::new(target),
+            flags,
+            params: params.iter().map(|&pod| PodBuf::from(pod)).collect(),
+        });
+        Ok(())
+    }
+
+    /// Disconnect the stream and connect it again, to the same target and with the same params as
+    /// the last call to [`Stream::connect`].
+    ///
+    /// Only the params given when connecting are replayed: params applied afterwards with
+    /// [`StreamRef::update_params`] are not recorded, and have to be applied again once the format
+    /// is negotiated, such as from the `param_changed` callback like after the first connection.
+    ///
+    /// Fails with `ENOTCONN` if the stream was never connected.
+    pub fn reconnect(&self) -> Result<(), Error> {
+        self.reconnect_with(None)
+    }
+
+    /// Disconnect the stream and connect it again to the node `target`, or to any suitable node
+    /// if `None`, with the same params as the last call to [`Stream::connect`].
+    ///
+    /// Fails with `ENOTCONN` if the stream was never connected.
+    pub fn reconnect_to(&self, target: Option<u32>) -> Result<(), Error> {
+        self.reconnect_with(Some(Target::new(StreamTarget::from(target))))
+    }
+
+    /// Reconnect the stream, to `target` if given or to its last target otherwise.
+    fn reconnect_with(&self, target: Option<Target>) -> Result<(), Error> {
+        const OP: &str = "reconnect the stream";
+
+        if let Some(target) = &target {
+            self.forget_target_object(target);
+        }
+        match self.inner.connection.borrow_mut().as_mut() {
+            Some(connection) => {
+                if let Some(target) = target {
+                    connection.target = target;
+                }
+            }
+            None => return Err(spa::utils::result::Error::from(Errno::ENOTCONN)).context(OP),
+        }
+
+        self.disconnect().context(OP)?;
+        // Only borrow the connection, so the callbacks emitted while connecting can read it.
+        let connection = self.inner.connection.borrow();
+        connection
+            .as_ref()
+            .expect("connection was recorded")
+            .connect(self)
+            .context(OP)
+    }
+
+    /// The id of the node the stream was last connected to with [`Stream::connect`] or
+    /// [`Stream::reconnect_to`], or `None` if it let the session manager pick one, or was connected
+    /// to a node by name or serial.
+    pub fn target(&self) -> Option<u32> {
+        self.inner
+            .connection
+            .borrow()
+            .as_ref()
+            .and_then(|connection| match connection.target {
+                Target::Id(id) => Some(id),
+                _ => None,
+            })
+    }
+
+    /// Remove the `target.object` property if it was set by the last connection and connecting to
+    /// `target` does not replace it.
+    fn forget_target_object(&self, target: &Target) {
+        let set = self
+            .inner
+            .connection
+            .borrow()
+            .as_ref()
+            .map_or(false, |connection| connection.target.is_object());
+        if set && !target.is_object() {
+            self.set_target_object(None);
+        }
+    }
+
+    /// The flags the stream was last connected with, if it was connected with [`Stream::connect`].
+    fn connection_flags(&self) -> Option<StreamFlags> {
+        self.inner
+            .connection
+            .borrow()
+            .as_ref()
+            .map(|connection| connection.flags)
+    }
+}
+
+/// What [`AutoReconnect`] does when the target node of a stream is removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReconnectPolicy {
+    /// Reconnect the stream right away without a target, letting the session manager pick a
+    /// node.
+    #[default]
+    AnyNode,
+    /// Wait for a node with the same name as the target to be added, such as when the device is
+    /// plugged back, and reconnect the stream to it.
+    SameNode,
+}
+
+struct State {
+    stream: Weak<Stream>,
+    policy: ReconnectPolicy,
+    /// How long the session manager has to move the stream after its target was removed.
+    move_timeout: Duration,
+    /// The `node.name` of the target of the stream.
+    target_name: Option<String>,
+    /// Whether the target was removed and the session manager may still move the stream.
+    moving: bool,
+    /// Whether the stream waits for a node named like its former target.
+    waiting: bool,
+    error: Option<Error>,
+    // Declared before the core, whose context keeps the loop of the timer alive.
+    timer: Option<TimerSource<'static>>,
+    _core: Core,
+}
+
+impl State {
+    fn reconnect(&mut self, stream: &Stream, target: Option<u32>) {
+        self.waiting = false;
+        if let Err(err) = stream.reconnect_to(target) {
+            self.error = Some(err);
+        }
+    }
+
+    fn global(&mut self, id: u32, name: Option<&str>) {
+        let Some(stream) = self.stream.upgrade() else {
+            return;
+        };
+
+        if stream.target() == Some(id) {
+            self.target_name = name.map(str::to_owned);
+        } else if self.waiting && name.is_some() && name == self.target_name.as_deref() {
+            self.reconnect(&stream, Some(id));
+        }
+    }
+
+    fn global_remove(&mut self, id: u32) {
+        let Some(stream) = self.stream.upgrade() else {
+            return;
+        };
+        if stream.target() != Some(id) {
+            return;
+        }
+        // The stream asked not to be moved when its target goes away.
+        if stream
+            .connection_flags()
+            .map_or(true, |flags| flags.contains(StreamFlags::DONT_RECONNECT))
+        {
+            return;
+        }
+
+        // Give the session manager some time to link the stream to another node.
+        self.moving = true;
+        let timer = self
+            .timer
+            .as_ref()
+            .expect("timer is created with the state");
+        if let Err(err) = timer
+            .update_timer(Some(self.move_timeout), None)
+            .into_sync_result()
+            .context("arm the reconnection timer")
+        {
+            self.error = Some(err);
+        }
+    }
+
+    fn expired(&mut self) {
+        if !std::mem::take(&mut self.moving) {
+            return;
+        }
+        let Some(stream) = self.stream.upgrade() else {
+            return;
+        };
+        // The session manager moved the stream to another node.
+        if stream.state() == StreamState::Streaming {
+            return;
+        }
+
+        match self.policy {
+            ReconnectPolicy::AnyNode => self.reconnect(&stream, None),
+            ReconnectPolicy::SameNode => self.waiting = true,
+        }
+    }
+}
+
+/// Reconnects a stream when its target node is removed, as long as the stream lives.
+///
+/// The stream has to be connected to a target given by id with [`Stream::connect`], without the
+/// [`DONT_RECONNECT`](StreamFlags::DONT_RECONNECT) flag. When the target is removed, the session
+/// manager is given the [move timeout](Self::set_move_timeout) to move the stream to another
+/// node. If the stream is not streaming once it expires, it is reconnected according to the
+/// [`ReconnectPolicy`].
+///
+/// ```no_run
+/// use std::rc::Rc;
+/// use pipewire::{
+///     context::Context, main_loop::MainLoop, properties::properties,
+///     spa::utils::Direction,
+///     stream::{reconnect::{AutoReconnect, ReconnectPolicy}, Stream, StreamFlags},
+/// };
+///
+/// let mainloop = MainLoop::new(None)?;
+/// let context = Context::new(&mainloop)?;
+/// let core = context.connect(None)?;
+/// let stream = Rc::new(Stream::new(&core, "capture", properties! {})?);
+///
+/// # let camera_id = 42;
+/// stream.connect(Direction::Input, Some(camera_id), StreamFlags::capture_defaults(), &mut [])?;
+/// let _reconnect = AutoReconnect::new(&stream, ReconnectPolicy::SameNode)?;
+/// mainloop.run();
+/// # Ok::<(), pipewire::Error>(())
+/// ```
+pub struct AutoReconnect {
+    state: Rc<RefCell<State>>,
+    // The listener has to be dropped before the registry it is registered on.
+    _registry_listener: registry::Listener,
+    _registry: Registry,
+}
+
+impl AutoReconnect {
+    /// How long the session manager has to move the stream by default, see
+    /// [`set_move_timeout`](Self::set_move_timeout).
+    pub const DEFAULT_MOVE_TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Watch the target of `stream`, reconnecting it according to `policy` when it is removed.
+    pub fn new(stream: &Rc<Stream>, policy: ReconnectPolicy) -> Result<Self, Error> {
+        const OP: &str = "watch the target of the stream";
+
+        let core = stream.inner._core.clone();
+        let registry = core.get_registry().context(OP)?;
+        // Safety: the loop is owned by the context of the core, which the state holds until
+        // the timer is dropped.
+        let loop_: &'static LoopRef = unsafe { &*(core_loop(&core) as *const LoopRef) };
+        let state = Rc::new(RefCell::new(State {
+            stream: Rc::downgrade(stream),
+            policy,
+            move_timeout: Self::DEFAULT_MOVE_TIMEOUT,
+            target_name: None,
+            moving: false,
+            waiting: false,
+            error: None,
+            timer: None,
+            _core: core,
+        }));
+
+        // The timer only holds a weak reference, as it is owned by the state.
+        state.borrow_mut().timer = Some(loop_.add_timer({
+            let state = Rc::downgrade(&state);
+            move |_| {
+                if let Some(state) = state.upgrade() {
+                    state.borrow_mut().expired();
+                }
+            }
+        }));
+
+        let registry_listener = registry
+            .add_listener_local()
+            .global({
+                let state = state.clone();
+                move |global| {
+                    if global.type_ == ObjectType::Node {
+                        let name = global.props.and_then(|props| props.get(*keys::NODE_NAME));
+                        state.borrow_mut().global(global.id, name);
+                    }
+                }
+            })
+            .global_remove({
+                let state = state.clone();
+                move |id| state.borrow_mut().global_remove(id)
+            })
+            .register();
+
+        Ok(Self {
+            state,
+            _registry_listener: registry_listener,
+            _registry: registry,
+        })
+    }
+
+    /// Set how long the session manager has to move the stream to another node after its target
+    /// was removed, before the stream is reconnected. Defaults to
+    /// [`DEFAULT_MOVE_TIMEOUT`](Self::DEFAULT_MOVE_TIMEOUT).
+    pub fn set_move_timeout(&self, timeout: Duration) {
+        // A zero timeout would disarm the timer.
+        self.state.borrow_mut().move_timeout = timeout.max(Duration::from_nanos(1));
+    }
+
+    /// Whether the target of the stream was removed, and the stream waits for a node with the
+    /// same name to reconnect to, with [`ReconnectPolicy::SameNode`].
+    pub fn is_waiting(&self) -> bool {
+        self.state.borrow().waiting
+    }
+
+    /// Take the error of the last reconnection, if it failed.
+    pub fn take_error(&self) -> Option<Error> {
+        self.state.borrow_mut().error.take()
+    }
+}
+
+impl std::fmt::Debug for AutoReconnect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state.borrow();
+        f.debug_struct("AutoReconnect")
+            .field("policy", &state.policy)
+            .field("target_name", &state.target_name)
+            .field("waiting", &state.waiting)
+            .finish()
+    }
+}
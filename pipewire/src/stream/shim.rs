@@ -0,0 +1,199 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Injection of failures into streams, to test the error paths of an application without a
+//! broken daemon.
+//!
+//! A failure injected into an operation of a stream makes the matching [`StreamRef`] methods
+//! fail with the given errno instead of calling libpipewire:
+//! - [`StreamOp::Connect`]: [`StreamRef::connect`] and [`StreamRef::connect_to`].
+//! - [`StreamOp::UpdateParams`]: [`StreamRef::update_params`].
+//! - [`StreamOp::DequeueBuffer`]: [`StreamRef::dequeue_buffer`] and
+//!   [`StreamRef::dequeue_raw_buffer`] return no buffer, as if none was available.
+//! - [`StreamOp::QueueBuffer`]: [`Buffer::queue`](crate::buffer::Buffer::queue) fails. The
+//!   buffer is not given back to the stream, neither when it is queued nor when it is dropped.
+//!
+//! Failures are kept per stream and apply to all the threads, so the real-time thread of a
+//! stream sees them as well.
+//!
+//! ```no_run
+//! use pipewire::stream::{
+//!     shim::{self, StreamOp},
+//!     StreamFlags, StreamRef,
+//! };
+//! use pipewire::spa::utils::Direction;
+//! use nix::errno::Errno;
+//!
+//! fn connect_fails(stream: &StreamRef) {
+//!     shim::fail_next(stream, StreamOp::Connect, Errno::ECONNREFUSED);
+//!     let err = stream
+//!         .connect(Direction::Output, None, StreamFlags::empty(), &mut [])
+//!         .unwrap_err();
+//!     assert_eq!(err.errno(), Some(Errno::ECONNREFUSED));
+//!
+//!     // The failure was consumed, the next call reaches the daemon.
+//!     stream
+//!         .connect(Direction::Output, None, StreamFlags::empty(), &mut [])
+//!         .unwrap();
+//! }
+//! ```
+
+use std::{os::raw::c_int, ptr, sync::Mutex};
+
+use nix::errno::Errno;
+
+use super::{
+    ops::{StreamOps, Sys},
+    StreamRef,
+};
+
+/// An operation of a stream which failures can be injected into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum StreamOp {
+    Connect,
+    UpdateParams,
+    DequeueBuffer,
+    QueueBuffer,
+}
+
+struct Failure {
+    stream: usize,
+    op: StreamOp,
+    errno: Errno,
+    /// `None` for a failure which stays until it is cleared.
+    remaining: Option<usize>,
+}
+
+static FAILURES: Mutex<Vec<Failure>> = Mutex::new(Vec::new());
+
+fn failures() -> std::sync::MutexGuard<'static, Vec<Failure>> {
+    FAILURES.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+fn inject(stream: *mut pw_sys::pw_stream, op: StreamOp, errno: Errno, remaining: Option<usize>) {
+    failures().push(Failure {
+        stream: stream as usize,
+        op,
+        errno,
+        remaining,
+    });
+}
+
+/// Make the next call of `op` on `stream` fail with `errno`.
+///
+/// Failures injected several times are consumed in order, one per call.
+pub fn fail_next(stream: &StreamRef, op: StreamOp, errno: Errno) {
+    inject(stream.as_raw_ptr(), op, errno, Some(1));
+}
+
+/// Make all the calls of `op` on `stream` fail with `errno`, until the failures of the stream are
+/// [cleared](clear).
+pub fn fail_always(stream: &StreamRef, op: StreamOp, errno: Errno) {
+    inject(stream.as_raw_ptr(), op, errno, None);
+}
+
+/// Remove the failures injected into `stream`.
+///
+/// This should be done before the stream is destroyed, as failures are kept by address and would
+/// apply to a new stream allocated at the same place.
+pub fn clear(stream: &StreamRef) {
+    let stream = stream.as_raw_ptr() as usize;
+    failures().retain(|failure| failure.stream != stream);
+}
+
+/// Remove the failures injected into all the streams.
+pub fn clear_all() {
+    failures().clear();
+}
+
+/// Consume the next failure of `op` on `stream`, if any.
+fn take(stream: *mut pw_sys::pw_stream, op: StreamOp) -> Option<Errno> {
+    let mut failures = failures();
+    let index = failures
+        .iter()
+        .position(|failure| failure.stream == stream as usize && failure.op == op)?;
+    let failure = &mut failures[index];
+    let errno = failure.errno;
+    if let Some(remaining) = &mut failure.remaining {
+        *remaining -= 1;
+        if *remaining == 0 {
+            failures.remove(index);
+        }
+    }
+    Some(errno)
+}
+
+/// The libpipewire calls, behind the injected failures.
+pub(super) struct Shimmed;
+
+impl StreamOps for Shimmed {
+    unsafe fn connect(
+        stream: *mut pw_sys::pw_stream,
+        direction: spa_sys::spa_direction,
+        target_id: u32,
+        flags: pw_sys::pw_stream_flags,
+        params: *mut *const spa_sys::spa_pod,
+        n_params: u32,
+    ) -> c_int {
+        match take(stream, StreamOp::Connect) {
+            Some(errno) => -(errno as c_int),
+            None => Sys::connect(stream, direction, target_id, flags, params, n_params),
+        }
+    }
+
+    unsafe fn update_params(
+        stream: *mut pw_sys::pw_stream,
+        params: *mut *const spa_sys::spa_pod,
+        n_params: u32,
+    ) -> c_int {
+        match take(stream, StreamOp::UpdateParams) {
+            Some(errno) => -(errno as c_int),
+            None => Sys::update_params(stream, params, n_params),
+        }
+    }
+
+    unsafe fn dequeue_buffer(stream: *mut pw_sys::pw_stream) -> *mut pw_sys::pw_buffer {
+        match take(stream, StreamOp::DequeueBuffer) {
+            Some(_) => ptr::null_mut(),
+            None => Sys::dequeue_buffer(stream),
+        }
+    }
+
+    unsafe fn queue_buffer(
+        stream: *mut pw_sys::pw_stream,
+        buffer: *mut pw_sys::pw_buffer,
+    ) -> c_int {
+        match take(stream, StreamOp::QueueBuffer) {
+            Some(errno) => -(errno as c_int),
+            None => Sys::queue_buffer(stream, buffer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn injected_failures() {
+        // Streams are never dereferenced by the table, so dangling pointers stand for them.
+        let a = ptr::NonNull::<pw_sys::pw_stream>::dangling().as_ptr();
+        let b = a.wrapping_add(1);
+
+        inject(a, StreamOp::Connect, Errno::EIO, Some(1));
+        inject(a, StreamOp::Connect, Errno::EPIPE, Some(1));
+        inject(b, StreamOp::QueueBuffer, Errno::EINVAL, None);
+
+        assert_eq!(take(a, StreamOp::UpdateParams), None);
+        assert_eq!(take(b, StreamOp::Connect), None);
+        assert_eq!(take(a, StreamOp::Connect), Some(Errno::EIO));
+        assert_eq!(take(a, StreamOp::Connect), Some(Errno::EPIPE));
+        assert_eq!(take(a, StreamOp::Connect), None);
+        assert_eq!(take(b, StreamOp::QueueBuffer), Some(Errno::EINVAL));
+        assert_eq!(take(b, StreamOp::QueueBuffer), Some(Errno::EINVAL));
+
+        super::failures().retain(|failure| failure.stream != b as usize);
+        assert_eq!(take(b, StreamOp::QueueBuffer), None);
+    }
+}
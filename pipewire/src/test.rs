@@ -0,0 +1,175 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Utilities to test code using PipeWire against a private daemon.
+//!
+//! This module requires the `test-utils` feature, which is meant to be enabled for the
+//! dev-dependency on this crate:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! pipewire = { version = "0.8", features = ["test-utils"] }
+//! ```
+//!
+//! [`TestDaemon`] runs the `pipewire` daemon in a temporary runtime directory, so that tests neither
+//! depend on nor disturb the daemon of the session, and [`Fixture`] connects to it:
+//!
+//! ```no_run
+//! use pipewire::test::Fixture;
+//!
+//! let fixture = Fixture::new()?;
+//! let registry = fixture.core.get_registry()?;
+//! // Run the loop until the daemon answered.
+//! fixture.sync()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    context::Context,
+    core::{Core, PW_ID_CORE},
+    main_loop::MainLoop,
+    pending::PendingSeqTracker,
+    Error,
+};
+
+/// The name of the socket of the daemon, in its runtime directory.
+const SOCKET_NAME: &str = "pipewire-0";
+/// How long to wait for the daemon to start.
+const START_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A private `pipewire` daemon, killed when this is dropped.
+///
+/// The daemon binary is taken from the `PIPEWIRE_TEST_DAEMON` environment variable,
+/// defaulting to `pipewire` in the `PATH`.
+#[derive(Debug)]
+pub struct TestDaemon {
+    child: Child,
+    runtime_dir: PathBuf,
+}
+
+impl TestDaemon {
+    /// Start a daemon and wait until it accepts connections.
+    pub fn spawn() -> io::Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let runtime_dir = std::env::temp_dir().join(format!(
+            "pipewire-rs-test-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&runtime_dir)?;
+
+        let binary = std::env::var_os("PIPEWIRE_TEST_DAEMON").unwrap_or_else(|| "pipewire".into());
+        let child = Command::new(binary)
+            .env("XDG_RUNTIME_DIR", &runtime_dir)
+            .env("PIPEWIRE_RUNTIME_DIR", &runtime_dir)
+            .env_remove("PIPEWIRE_REMOTE")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .spawn();
+        let child = match child {
+            Ok(child) => child,
+            Err(err) => {
+                let _ = std::fs::remove_dir_all(&runtime_dir);
+                return Err(err);
+            }
+        };
+
+        let mut daemon = Self { child, runtime_dir };
+        daemon.wait_for_socket()?;
+        Ok(daemon)
+    }
+
+    fn wait_for_socket(&mut self) -> io::Result<()> {
+        let start = Instant::now();
+        while !self.socket_path().exists() {
+            if let Some(status) = self.child.try_wait()? {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("pipewire daemon exited with {status}"),
+                ));
+            }
+            if start.elapsed() > START_TIMEOUT {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "pipewire daemon did not start",
+                ));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        Ok(())
+    }
+
+    /// The temporary runtime directory of the daemon.
+    pub fn runtime_dir(&self) -> &Path {
+        &self.runtime_dir
+    }
+
+    /// The path of the socket the daemon listens on.
+    pub fn socket_path(&self) -> PathBuf {
+        self.runtime_dir.join(SOCKET_NAME)
+    }
+
+    /// Connect `context` to the daemon.
+    pub fn connect(&self, context: &Context) -> Result<Core, Error> {
+        let socket = self.socket_path();
+        context.connect_to(socket.to_str().expect("non UTF-8 runtime directory"), None)
+    }
+}
+
+impl Drop for TestDaemon {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = std::fs::remove_dir_all(&self.runtime_dir);
+    }
+}
+
+/// A core connected to a private [`TestDaemon`].
+///
+/// Everything is torn down when this is dropped.
+#[derive(Debug)]
+pub struct Fixture {
+    // Fields are dropped in declaration order, the daemon has to be stopped last.
+    pub core: Core,
+    pub context: Context,
+    pub mainloop: MainLoop,
+    pub daemon: TestDaemon,
+}
+
+impl Fixture {
+    /// Start a daemon and connect to it.
+    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        crate::init();
+
+        let daemon = TestDaemon::spawn()?;
+        let mainloop = MainLoop::new(None)?;
+        let context = Context::new(&mainloop)?;
+        let core = daemon.connect(&context)?;
+
+        Ok(Self {
+            core,
+            context,
+            mainloop,
+            daemon,
+        })
+    }
+
+    /// Run the loop until the daemon processed all the previous requests.
+    pub fn sync(&self) -> Result<(), Error> {
+        let tracker = PendingSeqTracker::new();
+        let _listener = tracker.listen_core(&self.core);
+
+        let pending = tracker.track(PW_ID_CORE, self.core.sync(0)?);
+        tracker.wait(self.mainloop.loop_(), PW_ID_CORE, pending)
+    }
+}
@@ -0,0 +1,442 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Reusable implementations of the PipeWire command line tools.
+//!
+//! [`Monitor`] reports every global of the registry and the changes of the info, properties,
+//! params and metadata of the objects, like `pw-mon`:
+//!
+//! ```no_run
+//! use std::rc::Rc;
+//! use pipewire::{
+//!     context::Context, main_loop::MainLoop, registry::GlobalFilter, tools::Monitor,
+//!     types::ObjectType,
+//! };
+//!
+//! pipewire::init();
+//! let mainloop = MainLoop::new(None)?;
+//! let context = Context::new(&mainloop)?;
+//! let core = context.connect(None)?;
+//! let registry = Rc::new(core.get_registry()?);
+//!
+//! let filter = GlobalFilter::new().type_(ObjectType::Node);
+//! let _monitor = Monitor::new(&registry, filter, |event| println!("{event}"));
+//! mainloop.run();
+//! # Ok::<(), pipewire::Error>(())
+//! ```
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt,
+    rc::{Rc, Weak},
+};
+
+use spa::{
+    param::{ParamInfo, ParamInfoFlags, ParamType},
+    pod::Pod,
+    utils::dict::{DictDiff, DictRef},
+};
+
+use crate::{
+    link::{Link, LinkInfoRef},
+    metadata::Metadata,
+    node::{Node, NodeInfoRef},
+    port::{Port, PortInfoRef},
+    properties::Properties,
+    proxy::{Listener, ProxyT},
+    registry::{self, GlobalFilter, GlobalId, GlobalObject, Registry},
+    types::ObjectType,
+};
+
+/// An event reported by a [`Monitor`].
+///
+/// The [`Display`](fmt::Display) implementation prints the event in a format close to the
+/// output of `pw-mon`.
+pub enum MonitorEvent<'a> {
+    /// A global was added to the registry.
+    Added(&'a GlobalObject<&'a DictRef>),
+    /// The global `id` was removed from the registry.
+    Removed { id: GlobalId, type_: ObjectType },
+    /// The info of a node changed.
+    NodeInfo(&'a NodeInfoRef),
+    /// The info of a port changed.
+    PortInfo(&'a PortInfoRef),
+    /// The info of a link changed.
+    LinkInfo(&'a LinkInfoRef),
+    /// The properties in the info of a node, port or link changed.
+    ///
+    /// This follows the info event holding the new properties,
+    /// and is not emitted for the first info of the object.
+    PropsChanged { id: u32, diff: DictDiff<'a> },
+    /// A value of a param of a node or port changed.
+    ///
+    /// `index` is the index of the value among the values of the param,
+    /// and `param` is `None` if there is no value at this index anymore.
+    Param {
+        id: u32,
+        param_type: ParamType,
+        index: u32,
+        param: Option<&'a Pod>,
+    },
+    /// A property of a metadata object changed.
+    ///
+    /// `key` is `None` if all the properties of `subject` were removed,
+    /// and `value` is `None` if the property was removed.
+    Metadata {
+        id: u32,
+        subject: u32,
+        key: Option<&'a str>,
+        type_: Option<&'a str>,
+        value: Option<&'a str>,
+    },
+}
+
+impl fmt::Display for MonitorEvent<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Added(global) => {
+                writeln!(f, "added:")?;
+                writeln!(f, "\tid: {}", global.id)?;
+                writeln!(f, "\tpermissions: {:?}", global.permissions)?;
+                writeln!(f, "\ttype: {}/{}", global.type_.to_str(), global.version)?;
+                if let Some(props) = global.props {
+                    writeln!(f, "\tproperties:")?;
+                    for (key, value) in props.iter() {
+                        writeln!(f, "\t\t{key} = \"{value}\"")?;
+                    }
+                }
+                Ok(())
+            }
+            Self::Removed { id, type_ } => {
+                writeln!(f, "removed:")?;
+                writeln!(f, "\tid: {id}")?;
+                writeln!(f, "\ttype: {}", type_.to_str())
+            }
+            Self::NodeInfo(info) => writeln!(f, "changed:\n\t{info:?}"),
+            Self::PortInfo(info) => writeln!(f, "changed:\n\t{info:?}"),
+            Self::LinkInfo(info) => writeln!(f, "changed:\n\t{info:?}"),
+            Self::PropsChanged { id, diff } => {
+                writeln!(f, "properties changed:")?;
+                writeln!(f, "\tid: {id}")?;
+                for (key, value) in &diff.added {
+                    writeln!(f, "\t+ {key} = \"{value}\"")?;
+                }
+                for (key, value) in &diff.removed {
+                    writeln!(f, "\t- {key} = \"{value}\"")?;
+                }
+                for (key, old, new) in &diff.changed {
+                    writeln!(f, "\t* {key} = \"{old}\" -> \"{new}\"")?;
+                }
+                Ok(())
+            }
+            Self::Param {
+                id,
+                param_type,
+                index,
+                param,
+            } => {
+                writeln!(f, "param:")?;
+                writeln!(f, "\tid: {id}")?;
+                writeln!(f, "\tparam: {param_type} ({index})")?;
+                match param {
+                    Some(param) => writeln!(f, "\tvalue: {}", spa::pod::text::to_text(param)),
+                    None => writeln!(f, "\tvalue: none"),
+                }
+            }
+            Self::Metadata {
+                id,
+                subject,
+                key,
+                type_,
+                value,
+            } => {
+                writeln!(f, "metadata:")?;
+                writeln!(f, "\tid: {id}")?;
+                writeln!(f, "\tsubject: {subject}")?;
+                writeln!(f, "\tkey: {}", key.unwrap_or("(all)"))?;
+                writeln!(f, "\ttype: {}", type_.unwrap_or("(none)"))?;
+                writeln!(f, "\tvalue: {}", value.unwrap_or("(removed)"))
+            }
+        }
+    }
+}
+
+/// The proxy of an object bound by a [`Monitor`].
+enum BoundProxy {
+    Node(Node),
+    Port(Port),
+    // Only kept alive, their changes are followed through their listener.
+    #[allow(dead_code)]
+    Other(Box<dyn ProxyT>),
+}
+
+/// A global followed by a [`Monitor`].
+struct Monitored {
+    global_id: GlobalId,
+    type_: ObjectType,
+    // Declared before the proxy so it is dropped first.
+    _listener: Option<Box<dyn Listener>>,
+    proxy: Option<BoundProxy>,
+    /// The params the object is subscribed to.
+    subscribed: Vec<ParamType>,
+    /// The properties of the last info of the object.
+    props: Option<Properties>,
+}
+
+type MonitorEventCallback = dyn Fn(&MonitorEvent);
+
+struct Inner {
+    objects: RefCell<HashMap<u32, Monitored>>,
+    callback: Box<MonitorEventCallback>,
+}
+
+impl Inner {
+    fn global(self: &Rc<Self>, registry: &Weak<Registry>, obj: &GlobalObject<&DictRef>) {
+        (self.callback)(&MonitorEvent::Added(obj));
+
+        let bound = registry
+            .upgrade()
+            .and_then(|registry| self.bind(&registry, obj));
+        let (listener, proxy) = match bound {
+            Some((listener, proxy)) => (Some(listener), Some(proxy)),
+            None => (None, None),
+        };
+
+        self.objects.borrow_mut().insert(
+            obj.id,
+            Monitored {
+                global_id: obj.global_id(),
+                type_: obj.type_.clone(),
+                _listener: listener,
+                proxy,
+                subscribed: Vec::new(),
+                props: None,
+            },
+        );
+    }
+
+    /// Bind the object to follow its changes, if its interface has any.
+    fn bind(
+        self: &Rc<Self>,
+        registry: &Registry,
+        obj: &GlobalObject<&DictRef>,
+    ) -> Option<(Box<dyn Listener>, BoundProxy)> {
+        let id = obj.id;
+        let (inner_info, inner_param) = (Rc::downgrade(self), Rc::downgrade(self));
+
+        let bound: (Box<dyn Listener>, BoundProxy) = match obj.type_ {
+            ObjectType::Node => {
+                let node: Node = registry.bind(obj).ok()?;
+                let listener = node
+                    .add_listener_local()
+                    .info(move |info| {
+                        if let Some(inner) = inner_info.upgrade() {
+                            inner.subscribe(id, info.params());
+                            (inner.callback)(&MonitorEvent::NodeInfo(info));
+                            inner.props(id, info.props());
+                        }
+                    })
+                    .param(move |_, param_type, index, _, param| {
+                        if let Some(inner) = inner_param.upgrade() {
+                            inner.param(id, param_type, index, param);
+                        }
+                    })
+                    .register();
+                (Box::new(listener), BoundProxy::Node(node))
+            }
+            ObjectType::Port => {
+                let port: Port = registry.bind(obj).ok()?;
+                let listener = port
+                    .add_listener_local()
+                    .info(move |info| {
+                        if let Some(inner) = inner_info.upgrade() {
+                            inner.subscribe(id, info.params());
+                            (inner.callback)(&MonitorEvent::PortInfo(info));
+                            inner.props(id, info.props());
+                        }
+                    })
+                    .param(move |_, param_type, index, _, param| {
+                        if let Some(inner) = inner_param.upgrade() {
+                            inner.param(id, param_type, index, param);
+                        }
+                    })
+                    .register();
+                (Box::new(listener), BoundProxy::Port(port))
+            }
+            ObjectType::Link => {
+                let link: Link = registry.bind(obj).ok()?;
+                let listener = link
+                    .add_listener_local()
+                    .info(move |info| {
+                        if let Some(inner) = inner_info.upgrade() {
+                            (inner.callback)(&MonitorEvent::LinkInfo(info));
+                            inner.props(id, info.props());
+                        }
+                    })
+                    .register();
+                (Box::new(listener), BoundProxy::Other(Box::new(link)))
+            }
+            ObjectType::Metadata => {
+                let metadata: Metadata = registry.bind(obj).ok()?;
+                let listener = metadata
+                    .add_listener_local()
+                    .property(move |subject, key, type_, value| {
+                        if let Some(inner) = inner_info.upgrade() {
+                            (inner.callback)(&MonitorEvent::Metadata {
+                                id,
+                                subject,
+                                key,
+                                type_,
+                                value,
+                            });
+                        }
+                        0
+                    })
+                    .register();
+                (Box::new(listener), BoundProxy::Other(Box::new(metadata)))
+            }
+            _ => return None,
+        };
+
+        Some(bound)
+    }
+
+    fn global_remove(&self, id: u32) {
+        // Drop the proxy before notifying, so it is not used by the callback.
+        let Some(object) = self.objects.borrow_mut().remove(&id) else {
+            return;
+        };
+        let (global_id, type_) = (object.global_id, object.type_.clone());
+        drop(object);
+
+        (self.callback)(&MonitorEvent::Removed {
+            id: global_id,
+            type_,
+        });
+    }
+
+    /// Subscribe to the readable params of the object `id`, so param events are emitted
+    /// whenever they change.
+    fn subscribe(&self, id: u32, params: &[ParamInfo]) {
+        let readable: Vec<ParamType> = params
+            .iter()
+            .filter(|param| param.flags().contains(ParamInfoFlags::READ))
+            .map(ParamInfo::id)
+            .collect();
+
+        let mut objects = self.objects.borrow_mut();
+        let Some(object) = objects.get_mut(&id) else {
+            return;
+        };
+        if readable == object.subscribed {
+            return;
+        }
+
+        let res = match &object.proxy {
+            Some(BoundProxy::Node(node)) => node.subscribe_params(&readable),
+            Some(BoundProxy::Port(port)) => port.subscribe_params(&readable),
+            _ => return,
+        };
+        if res.is_ok() {
+            object.subscribed = readable;
+        }
+    }
+
+    /// Record the properties of the last info of the object `id`, reporting how they changed.
+    fn props(&self, id: u32, props: Option<&DictRef>) {
+        let Some(props) = props else {
+            return;
+        };
+        let old = match self.objects.borrow_mut().get_mut(&id) {
+            Some(object) => object.props.replace(Properties::from_dict(props)),
+            None => return,
+        };
+        let Some(old) = old else {
+            return;
+        };
+
+        let diff = old.dict().diff(props);
+        if !diff.is_empty() {
+            (self.callback)(&MonitorEvent::PropsChanged { id, diff });
+        }
+    }
+
+    fn param(&self, id: u32, param_type: ParamType, index: u32, param: Option<&Pod>) {
+        (self.callback)(&MonitorEvent::Param {
+            id,
+            param_type,
+            index,
+            param,
+        });
+    }
+}
+
+/// Reports the globals of the registry and the changes of their info, params and metadata
+/// as [`MonitorEvent`]s, like `pw-mon`.
+///
+/// Only the globals matching the filter are reported and followed. Nodes, ports, links and
+/// metadata objects are bound to follow their changes, subscribing to all the readable params
+/// of nodes and ports.
+///
+/// The monitor stops following changes when it is dropped.
+pub struct Monitor {
+    inner: Rc<Inner>,
+    _listener: registry::Listener,
+}
+
+impl Monitor {
+    /// Start monitoring the globals matching `filter` using `registry`, calling `event` for
+    /// each event.
+    pub fn new<F>(registry: &Rc<Registry>, filter: GlobalFilter, event: F) -> Self
+    where
+        F: Fn(&MonitorEvent) + 'static,
+    {
+        let inner = Rc::new(Inner {
+            objects: RefCell::new(HashMap::new()),
+            callback: Box::new(event),
+        });
+
+        let registry_weak = Rc::downgrade(registry);
+        let inner_global = Rc::downgrade(&inner);
+        let inner_global_remove = Rc::downgrade(&inner);
+
+        let listener = registry
+            .add_filtered_listener_local(filter)
+            .global(move |obj| {
+                if let Some(inner) = inner_global.upgrade() {
+                    inner.global(&registry_weak, obj);
+                }
+            })
+            .global_remove(move |id| {
+                if let Some(inner) = inner_global_remove.upgrade() {
+                    inner.global_remove(id);
+                }
+            })
+            .register();
+
+        Self {
+            inner,
+            _listener: listener,
+        }
+    }
+
+    /// Start monitoring the globals matching `filter`, printing the events to the standard
+    /// output.
+    pub fn print(registry: &Rc<Registry>, filter: GlobalFilter) -> Self {
+        Self::new(registry, filter, |event| println!("{event}"))
+    }
+
+    /// The ids of the globals currently followed by the monitor.
+    ///
+    /// The ids include the serials of the objects, so they can be cached without mistaking
+    /// a new object reusing the id of a removed one for the removed object.
+    pub fn globals(&self) -> Vec<GlobalId> {
+        self.inner
+            .objects
+            .borrow()
+            .values()
+            .map(|object| object.global_id)
+            .collect()
+    }
+}
@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{convert::Infallible, fmt, str::FromStr};
 
 // Macro generating the ObjectType enum
 macro_rules! object_type {
@@ -10,7 +10,8 @@ macro_rules! object_type {
         }
 
         impl ObjectType {
-            pub(crate) fn from_str(s: &str) -> ObjectType {
+            /// The type with the interface name `s`, such as `PipeWire:Interface:Node`.
+            pub fn from_interface_name(s: &str) -> ObjectType {
                 match s {
                     $(
                     concat!("PipeWire:Interface:", stringify!($x)) => ObjectType::$x,
@@ -38,6 +39,14 @@ macro_rules! object_type {
             }
         }
 
+        impl FromStr for ObjectType {
+            type Err = Infallible;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Ok(Self::from_interface_name(s))
+            }
+        }
+
         impl fmt::Display for ObjectType {
             fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
                 write!(f, "{}", self.to_str())
@@ -0,0 +1,182 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Handling of panics in callbacks.
+//!
+//! The callbacks of listeners and loop sources are called from C code, which a panic must not
+//! unwind into. Panics are therefore caught before leaving the callback, and handled according
+//! to the [`PanicPolicy`] set with [`set_panic_policy`]:
+//! by default, the process is aborted.
+//!
+//! ```no_run
+//! use pipewire::unwind::{self, PanicPolicy};
+//!
+//! unwind::set_panic_policy(PanicPolicy::Store);
+//! // Run the loop, and check whether a callback panicked once it returns.
+//! if let Some(panic) = unwind::take_panic() {
+//!     panic.resume();
+//! }
+//! ```
+
+use std::{
+    any::Any,
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex},
+};
+
+/// A panic caught in a callback.
+pub struct CallbackPanic {
+    callback: &'static str,
+    payload: Box<dyn Any + Send>,
+}
+
+impl CallbackPanic {
+    /// The name of the C callback which was running, such as `node_events_info`.
+    pub fn callback(&self) -> &'static str {
+        self.callback
+    }
+
+    /// The message of the panic, if it was raised with a string.
+    pub fn message(&self) -> Option<&str> {
+        self.payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| self.payload.downcast_ref::<String>().map(String::as_str))
+    }
+
+    /// The payload of the panic, as passed to [`std::panic::resume_unwind`].
+    pub fn into_payload(self) -> Box<dyn Any + Send> {
+        self.payload
+    }
+
+    /// Resume the panic on the current thread, outside of the callback.
+    pub fn resume(self) -> ! {
+        panic::resume_unwind(self.payload)
+    }
+}
+
+impl fmt::Debug for CallbackPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CallbackPanic")
+            .field("callback", &self.callback)
+            .field("message", &self.message())
+            .finish()
+    }
+}
+
+impl fmt::Display for CallbackPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "callback {} panicked", self.callback)?;
+        if let Some(message) = self.message() {
+            write!(f, ": {message}")?;
+        }
+        Ok(())
+    }
+}
+
+/// What happens when a callback panics.
+///
+/// Unless the process is aborted, the callback returns as if it did nothing once the panic is
+/// handled, so the state of the listener may be inconsistent.
+#[derive(Clone, Default)]
+pub enum PanicPolicy {
+    /// Print the panic and abort the process.
+    #[default]
+    Abort,
+    /// Keep the panic, to be taken with [`take_panic`].
+    ///
+    /// Only the first panic is kept until it is taken, later ones are dropped.
+    Store,
+    /// Call the handler with the panic.
+    Handler(Arc<dyn Fn(CallbackPanic) + Send + Sync>),
+}
+
+impl fmt::Debug for PanicPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Abort => f.write_str("Abort"),
+            Self::Store => f.write_str("Store"),
+            Self::Handler(_) => f.write_str("Handler"),
+        }
+    }
+}
+
+static POLICY: Mutex<PanicPolicy> = Mutex::new(PanicPolicy::Abort);
+static STORED: Mutex<Option<CallbackPanic>> = Mutex::new(None);
+
+/// Set what happens when a callback panics, for the callbacks of all the threads.
+pub fn set_panic_policy(policy: PanicPolicy) {
+    *POLICY.lock().unwrap_or_else(|err| err.into_inner()) = policy;
+}
+
+/// The current [`PanicPolicy`].
+pub fn panic_policy() -> PanicPolicy {
+    POLICY.lock().unwrap_or_else(|err| err.into_inner()).clone()
+}
+
+/// Take the panic kept with [`PanicPolicy::Store`], if a callback panicked.
+pub fn take_panic() -> Option<CallbackPanic> {
+    STORED.lock().unwrap_or_else(|err| err.into_inner()).take()
+}
+
+fn handle(panic: CallbackPanic) {
+    match panic_policy() {
+        PanicPolicy::Abort => {
+            eprintln!("pipewire: {panic}, aborting");
+            std::process::abort();
+        }
+        PanicPolicy::Store => {
+            let mut stored = STORED.lock().unwrap_or_else(|err| err.into_inner());
+            if stored.is_none() {
+                *stored = Some(panic);
+            }
+        }
+        PanicPolicy::Handler(handler) => handler(panic),
+    }
+}
+
+/// Run the body of the C callback `callback`, handling its panics according to the
+/// [`PanicPolicy`].
+///
+/// The callback returns the default value of `R` if it panicked.
+pub(crate) fn guard<R: Default>(callback: &'static str, f: impl FnOnce() -> R) -> R {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(res) => res,
+        Err(payload) => {
+            handle(CallbackPanic { callback, payload });
+            R::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policies() {
+        // The policy is global, so all the policies are tested in the same test.
+        set_panic_policy(PanicPolicy::Store);
+        assert_eq!(guard("test_callback", || 1), 1);
+        assert_eq!(guard("test_callback", || -> i32 { panic!("first") }), 0);
+        guard("other_callback", || panic!("second"));
+
+        let panic = take_panic().unwrap();
+        assert_eq!(panic.callback(), "test_callback");
+        assert_eq!(panic.message(), Some("first"));
+        assert_eq!(panic.to_string(), "callback test_callback panicked: first");
+        assert!(take_panic().is_none());
+
+        let callbacks = Arc::new(Mutex::new(Vec::new()));
+        set_panic_policy(PanicPolicy::Handler(Arc::new({
+            let callbacks = callbacks.clone();
+            move |panic: CallbackPanic| callbacks.lock().unwrap().push(panic.callback())
+        })));
+        guard("handled_callback", || panic!("{}", 3));
+        assert_eq!(*callbacks.lock().unwrap(), ["handled_callback"]);
+        assert!(take_panic().is_none());
+
+        set_panic_policy(PanicPolicy::Abort);
+    }
+}
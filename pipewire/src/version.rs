@@ -0,0 +1,248 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Versions of PipeWire and detection of the features they support.
+//!
+//! The bindings of features added in later versions of PipeWire are only compiled with the matching
+//! version feature of this crate, such as `v0_3_34`. An application compiled with such a feature can
+//! still run against an older PipeWire daemon, so it should check that the daemon supports
+//! a feature before relying on it:
+//!
+//! ```no_run
+//! use pipewire::{context::Context, main_loop::MainLoop, version::ServerFeature};
+//!
+//! let mainloop = MainLoop::new(None)?;
+//! let context = Context::new(&mainloop)?;
+//! let core = context.connect(None)?;
+//!
+//! // The version of the server is known once its info has been received.
+//! # let _ = mainloop.loop_().iterate(std::time::Duration::from_millis(100));
+//! if core.has_server_feature(ServerFeature::TriggerProcess) {
+//!     // Drive the graph with `StreamRef::trigger_process`.
+//! }
+//! # Ok::<(), pipewire::Error>(())
+//! ```
+//!
+//! The client library the program runs with can also be older than the headers it was compiled
+//! against, when a single binary is distributed to several distributions. [`capabilities`] tells
+//! which features are usable with both, and features are only reported as supported by a daemon
+//! when the library supports them too.
+
+use std::{ffi::CStr, fmt, str::FromStr};
+
+use once_cell::sync::OnceCell;
+
+/// A version of PipeWire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub micro: u32,
+}
+
+impl Version {
+    pub const fn new(major: u32, minor: u32, micro: u32) -> Self {
+        Self {
+            major,
+            minor,
+            micro,
+        }
+    }
+
+    /// The version of the PipeWire library the program is running with.
+    pub fn library() -> Option<Self> {
+        let version = unsafe { CStr::from_ptr(pw_sys::pw_get_library_version()) };
+        version.to_str().ok()?.parse().ok()
+    }
+}
+
+/// The version of the PipeWire headers the bindings were compiled against.
+pub fn compiled() -> Version {
+    Version::new(pw_sys::PW_MAJOR, pw_sys::PW_MINOR, pw_sys::PW_MICRO)
+}
+
+/// The version of the PipeWire library the program is running with, see [`Version::library`].
+///
+/// The version is read once and then cached, as the library can't change while the program runs.
+pub fn linked() -> Option<Version> {
+    static LINKED: OnceCell<Option<Version>> = OnceCell::new();
+    *LINKED.get_or_init(Version::library)
+}
+
+/// Whether a feature is usable by the program, see [`capabilities`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FeatureSupport {
+    pub feature: ServerFeature,
+    /// Whether the bindings of the feature are compiled.
+    pub compiled: bool,
+    /// Whether the PipeWire library the program is running with supports the feature.
+    pub linked: bool,
+}
+
+impl FeatureSupport {
+    /// Whether the feature can be used, as far as the client side is concerned.
+    ///
+    /// The daemon has to support it too, see [`ServerFeature::is_supported_by`].
+    pub fn is_usable(&self) -> bool {
+        self.compiled && self.linked
+    }
+}
+
+/// The support of all the known features by the build and by the library the program is running
+/// with.
+pub fn capabilities() -> Vec<FeatureSupport> {
+    ServerFeature::ALL
+        .iter()
+        .map(|&feature| FeatureSupport {
+            feature,
+            compiled: feature.is_compiled(),
+            linked: feature.is_linked(),
+        })
+        .collect()
+}
+
+/// Error returned when parsing an invalid [`Version`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseVersionError;
+
+impl fmt::Display for ParseVersionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid PipeWire version")
+    }
+}
+
+impl std::error::Error for ParseVersionError {}
+
+impl FromStr for Version {
+    type Err = ParseVersionError;
+
+    /// Parse a version such as `1.0.3`, ignoring suffixes such as in `1.1.81-dev`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(3, '.').map(|part| {
+            let end = part
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(part.len());
+            part[..end].parse::<u32>().map_err(|_| ParseVersionError)
+        });
+        let mut next = || parts.next().unwrap_or(Err(ParseVersionError));
+
+        Ok(Self::new(next()?, next()?, next()?))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.micro)
+    }
+}
+
+/// A feature which depends on the version of the PipeWire daemon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ServerFeature {
+    /// The `DONT_FIXATE` flag of pod properties, used to negotiate DMA-BUF modifiers.
+    DontFixate,
+    /// Streams driving the graph with `StreamRef::trigger_process`.
+    TriggerProcess,
+    /// The `command` event of streams.
+    StreamCommand,
+    /// The `trigger_done` event of streams.
+    TriggerDone,
+    /// Explicit sync of buffers with the `SyncTimeline` meta.
+    SyncTimeline,
+}
+
+impl ServerFeature {
+    /// All the known features.
+    pub const ALL: &'static [Self] = &[
+        Self::DontFixate,
+        Self::TriggerProcess,
+        Self::StreamCommand,
+        Self::TriggerDone,
+        Self::SyncTimeline,
+    ];
+
+    /// The first version of PipeWire supporting the feature.
+    pub fn min_version(self) -> Version {
+        match self {
+            Self::DontFixate => Version::new(0, 3, 33),
+            Self::TriggerProcess => Version::new(0, 3, 34),
+            Self::StreamCommand => Version::new(0, 3, 39),
+            Self::TriggerDone => Version::new(0, 3, 40),
+            Self::SyncTimeline => Version::new(1, 2, 0),
+        }
+    }
+
+    /// Whether the bindings of the feature are compiled, with the matching version feature
+    /// of this crate.
+    pub fn is_compiled(self) -> bool {
+        match self {
+            Self::DontFixate => cfg!(feature = "v0_3_33"),
+            Self::TriggerProcess => cfg!(feature = "v0_3_34"),
+            Self::StreamCommand => cfg!(feature = "v0_3_39"),
+            Self::TriggerDone => cfg!(feature = "v0_3_40"),
+            Self::SyncTimeline => cfg!(feature = "v1_2_0"),
+        }
+    }
+
+    /// Whether the PipeWire library the program is running with supports the feature, checked
+    /// against [`linked`] at runtime.
+    pub fn is_linked(self) -> bool {
+        linked().map_or(false, |version| version >= self.min_version())
+    }
+
+    /// Whether the feature can be used with a daemon of version `version`.
+    ///
+    /// The feature must also be [compiled](Self::is_compiled) and supported by the
+    /// [library](Self::is_linked) the program is running with.
+    pub fn is_supported_by(self, version: Version) -> bool {
+        self.is_compiled() && self.is_linked() && version >= self.min_version()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!("1.0.3".parse(), Ok(Version::new(1, 0, 3)));
+        assert_eq!("1.1.81-dev".parse(), Ok(Version::new(1, 1, 81)));
+        assert_eq!("0.3".parse::<Version>(), Err(ParseVersionError));
+        assert_eq!("a.b.c".parse::<Version>(), Err(ParseVersionError));
+        assert_eq!(Version::new(0, 3, 77).to_string(), "0.3.77");
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn supported() {
+        let old = Version::new(0, 3, 30);
+        assert!(!ServerFeature::TriggerProcess.is_supported_by(old));
+        assert_eq!(
+            ServerFeature::TriggerProcess.is_supported_by(Version::new(1, 0, 0)),
+            cfg!(feature = "v0_3_34") && ServerFeature::TriggerProcess.is_linked()
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn capabilities() {
+        assert!(compiled() >= Version::new(0, 3, 0));
+        assert!(linked().is_some());
+
+        let capabilities = super::capabilities();
+        assert_eq!(capabilities.len(), ServerFeature::ALL.len());
+        for support in capabilities {
+            assert_eq!(support.compiled, support.feature.is_compiled());
+            assert_eq!(support.linked, support.feature.is_linked());
+        }
+
+        // Features older than the library are linked.
+        let linked = linked().unwrap();
+        for &feature in ServerFeature::ALL {
+            if feature.min_version() <= linked {
+                assert!(feature.is_linked());
+            }
+        }
+    }
+}
@@ -0,0 +1,150 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+//! Volume control helpers.
+//!
+//! PipeWire stores volumes as linear factors, where `1.0` is the nominal volume.
+//! User interfaces usually show cubic volumes instead, which better match the perceived loudness;
+//! use [`cubic_to_linear`] and [`linear_to_cubic`] to convert between the two.
+//!
+//! Volumes are changed with [`Node::set_volume`](crate::node::Node::set_volume) and related
+//! methods. Nodes of a sound card, which have a `card.profile.device` property, are changed through
+//! the active route of their device, so that the session manager saves the volume of the route;
+//! see [`Device::set_route_volume`](crate::device::Device::set_route_volume). Other nodes are
+//! changed through their `Props` param.
+
+use spa::{
+    param::ParamType,
+    pod::{Object, Property, Value, ValueArray},
+    utils::SpaTypes,
+};
+
+/// Convert a cubic volume, as shown to users, to a linear volume.
+pub fn cubic_to_linear(volume: f32) -> f32 {
+    volume * volume * volume
+}
+
+/// Convert a linear volume to a cubic volume, as shown to users.
+pub fn linear_to_cubic(volume: f32) -> f32 {
+    volume.cbrt()
+}
+
+/// A set of volume properties of a node or a device route.
+///
+/// Properties left to `None` are unchanged when applied.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VolumeProps {
+    /// The linear volume of all the channels.
+    pub volume: Option<f32>,
+    /// Whether the output is muted.
+    pub mute: Option<bool>,
+    /// The linear volume of each channel.
+    pub channel_volumes: Option<Vec<f32>>,
+}
+
+impl VolumeProps {
+    /// Read the volume properties of a `Props` object, such as the `Props` param of a node or
+    /// the `props` of a route.
+    ///
+    /// Properties missing from the object, or holding a value of the wrong type, are left to `None`.
+    pub fn from_object(object: &Object) -> Self {
+        let mut props = Self::default();
+        for property in &object.properties {
+            match (property.key, &property.value) {
+                (spa_sys::SPA_PROP_volume, Value::Float(volume)) => props.volume = Some(*volume),
+                (spa_sys::SPA_PROP_mute, Value::Bool(mute)) => props.mute = Some(*mute),
+                (
+                    spa_sys::SPA_PROP_channelVolumes,
+                    Value::ValueArray(ValueArray::Float(volumes)),
+                ) => props.channel_volumes = Some(volumes.clone()),
+                _ => {}
+            }
+        }
+        props
+    }
+
+    /// Build the `Props` object holding the volume properties.
+    pub fn to_object(&self) -> Object {
+        let mut properties = Vec::new();
+        if let Some(volume) = self.volume {
+            properties.push(Property::new(
+                spa_sys::SPA_PROP_volume,
+                Value::Float(volume),
+            ));
+        }
+        if let Some(mute) = self.mute {
+            properties.push(Property::new(spa_sys::SPA_PROP_mute, Value::Bool(mute)));
+        }
+        if let Some(channel_volumes) = &self.channel_volumes {
+            properties.push(Property::new(
+                spa_sys::SPA_PROP_channelVolumes,
+                Value::array(channel_volumes.iter().copied()),
+            ));
+        }
+
+        Object {
+            type_: SpaTypes::ObjectParamProps.as_raw(),
+            id: ParamType::Props.as_raw(),
+            properties,
+        }
+    }
+
+    /// Build the `Route` object applying the volume properties to the route `route_index`
+    /// of the device, for the route device `route_device`.
+    ///
+    /// `save` asks the session manager to remember the volume of the route.
+    pub fn to_route_object(&self, route_index: i32, route_device: i32, save: bool) -> Object {
+        Object {
+            type_: SpaTypes::ObjectParamRoute.as_raw(),
+            id: ParamType::Route.as_raw(),
+            properties: vec![
+                Property::new(spa_sys::SPA_PARAM_ROUTE_index, Value::Int(route_index)),
+                Property::new(spa_sys::SPA_PARAM_ROUTE_device, Value::Int(route_device)),
+                Property::new(
+                    spa_sys::SPA_PARAM_ROUTE_props,
+                    Value::Object(self.to_object()),
+                ),
+                Property::new(spa_sys::SPA_PARAM_ROUTE_save, Value::Bool(save)),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use spa::pod::io::PodBuf;
+
+    use super::*;
+
+    #[test]
+    fn cubic_linear() {
+        assert_eq!(cubic_to_linear(0.5), 0.125);
+        assert_eq!(linear_to_cubic(0.125), 0.5);
+        assert_eq!(linear_to_cubic(cubic_to_linear(1.0)), 1.0);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn props_object() {
+        let props = VolumeProps {
+            volume: Some(0.5),
+            mute: None,
+            channel_volumes: Some(vec![0.25, 1.0]),
+        };
+        let pod = PodBuf::serialize(&Value::Object(props.to_object())).unwrap();
+        let (_, value) =
+            spa::pod::deserialize::PodDeserializer::deserialize_any_from(pod.as_bytes()).unwrap();
+
+        let Value::Object(object) = value else {
+            panic!("expected an object");
+        };
+        assert_eq!(object.id, ParamType::Props.as_raw());
+        assert_eq!(object.properties.len(), 2);
+        assert_eq!(object.properties[0].value, Value::Float(0.5));
+        assert_eq!(
+            object.properties[1].value,
+            Value::ValueArray(ValueArray::Float(vec![0.25, 1.0]))
+        );
+        assert_eq!(VolumeProps::from_object(&object), props);
+    }
+}
@@ -0,0 +1,651 @@
+// Copyright The pipewire-rs Contributors.
+// SPDX-License-Identifier: MIT
+
+#![cfg(feature = "test-utils")]
+
+use std::{
+    cell::{Cell, RefCell},
+    ffi::c_void,
+    rc::Rc,
+};
+
+use pipewire::{
+    context::Context,
+    core::{self, PW_ID_CORE},
+    discovery, listeners,
+    permissions::PermissionFlags,
+    properties::properties,
+    proxy::proxy_interface,
+    registry::{GlobalFilter, GlobalObject},
+    spa::utils::{dict::DictRef, Direction},
+    stream::{
+        reconnect::{AutoReconnect, ReconnectPolicy},
+        Stream, StreamFlags, StreamTarget,
+    },
+    sys,
+    test::Fixture,
+    tools::{Monitor, MonitorEvent},
+    types::ObjectType,
+};
+
+#[test]
+fn registry() {
+    let fixture = Fixture::new().unwrap();
+    let registry = fixture.core.get_registry().unwrap();
+
+    let globals = Rc::new(RefCell::new(Vec::new()));
+    let _listener = registry
+        .add_listener_local()
+        .global({
+            let globals = globals.clone();
+            move |global| globals.borrow_mut().push((global.id, global.type_.clone()))
+        })
+        .register();
+
+    fixture.sync().unwrap();
+
+    assert!(globals.borrow().contains(&(PW_ID_CORE, ObjectType::Core)));
+    assert!(fixture.core.server_version().is_some());
+}
+
+#[test]
+fn filtered_registry() {
+    let fixture = Fixture::new().unwrap();
+    let registry = fixture.core.get_registry().unwrap();
+
+    let sinks = Rc::new(RefCell::new(Vec::new()));
+    let filter = GlobalFilter::new()
+        .type_(ObjectType::Node)
+        .prop("media.class", "Audio/Sink");
+    let _listener = registry
+        .add_filtered_listener_local(filter)
+        .global({
+            let sinks = sinks.clone();
+            move |global| sinks.borrow_mut().push(global.to_owned())
+        })
+        .register();
+
+    let _sink = fixture
+        .core
+        .create_null_sink("test-sink", 2, 48000)
+        .unwrap();
+    fixture.sync().unwrap();
+
+    let sinks = sinks.borrow();
+    assert_eq!(sinks.len(), 1);
+    assert_eq!(sinks[0].type_, ObjectType::Node);
+    assert_eq!(
+        sinks[0].props().and_then(|props| props.get("node.name")),
+        Some("test-sink")
+    );
+}
+
+#[test]
+fn null_sink() {
+    let fixture = Fixture::new().unwrap();
+
+    let sink = fixture
+        .core
+        .create_null_sink("test-sink", 2, 48000)
+        .unwrap();
+    fixture.sync().unwrap();
+
+    assert!(sink.node_id().is_some());
+}
+
+#[test]
+fn connect_self() {
+    let fixture = Fixture::new().unwrap();
+
+    let core = fixture.context.connect_self(None).unwrap();
+    let registry = core.get_registry();
+    assert!(registry.is_ok());
+}
+
+#[test]
+fn connection_state() {
+    let fixture = Fixture::new().unwrap();
+    fixture.sync().unwrap();
+    assert!(fixture.core.is_connected());
+
+    let core = fixture.daemon.connect(&fixture.context).unwrap();
+    let clone = core.clone();
+    assert!(unsafe { clone.disconnect() }.is_err());
+    unsafe { core.disconnect() }.unwrap();
+}
+
+#[test]
+fn stream_listeners() {
+    let fixture = Fixture::new().unwrap();
+    let stream = Stream::new(&fixture.core, "test-stream", properties! {}).unwrap();
+
+    let first_listener = stream
+        .add_local_listener_with_user_data(0)
+        .state_changed(|_, count, _, _| *count += 1)
+        .register()
+        .unwrap();
+    let second_listener = stream
+        .add_local_listener_with_user_data(0)
+        .state_changed(|_, count, _, _| *count += 1)
+        .register()
+        .unwrap();
+
+    stream
+        .connect(Direction::Output, None, StreamFlags::empty(), &mut [])
+        .unwrap();
+    fixture.sync().unwrap();
+
+    // Only the second listener receives the events of the disconnection.
+    let first = first_listener.into_user_data();
+    let callbacks = second_listener.into_callbacks();
+    let mut second_listener = stream
+        .add_local_listener_with_callbacks(callbacks)
+        .register()
+        .unwrap();
+    stream.disconnect().unwrap();
+
+    // Safety: the loop is not iterated while the user data is borrowed.
+    unsafe { *second_listener.user_data_mut() += 100 };
+    let second = second_listener.into_user_data();
+
+    assert!(first > 0);
+    assert!(second > first + 100);
+}
+
+#[test]
+fn stream_scoped_listener() {
+    let fixture = Fixture::new().unwrap();
+    let stream = Stream::new(&fixture.core, "test-stream", properties! {}).unwrap();
+
+    // The listener borrows a counter living on the stack.
+    let mut count = 0;
+    fixture.core.with_listeners(|scope| {
+        stream
+            .add_local_listener_with_user_data(&mut count)
+            .state_changed(|_, count, _, _| **count += 1)
+            .register_scoped(scope)
+            .unwrap();
+
+        stream
+            .connect(Direction::Output, None, StreamFlags::empty(), &mut [])
+            .unwrap();
+        fixture.sync().unwrap();
+    });
+
+    // The listener was removed with the scope.
+    stream.disconnect().unwrap();
+    assert!(count > 0);
+}
+
+proxy_interface! {
+    /// A node bound as an extension interface would be.
+    struct RawNode {
+        interface: "PipeWire:Interface:Node",
+        version: sys::PW_VERSION_NODE,
+        methods: sys::pw_node_methods,
+        events: sys::pw_node_events,
+    }
+}
+
+#[test]
+fn proxy_interface() {
+    let fixture = Fixture::new().unwrap();
+    let sink = fixture
+        .core
+        .create_null_sink("test-sink", 2, 48000)
+        .unwrap();
+    fixture.sync().unwrap();
+
+    let registry = fixture.core.get_registry().unwrap();
+    let node: RawNode = registry
+        .bind(&GlobalObject::<&DictRef> {
+            id: sink.node_id().unwrap(),
+            permissions: PermissionFlags::all(),
+            type_: ObjectType::Node,
+            version: sys::PW_VERSION_NODE,
+            props: None,
+        })
+        .unwrap();
+
+    unsafe extern "C" fn info(data: *mut c_void, _info: *const sys::pw_node_info) {
+        let count = listeners::callback_data::<Cell<u32>>(data);
+        count.set(count.get() + 1);
+    }
+
+    let mut events: sys::pw_node_events = unsafe { std::mem::zeroed() };
+    events.version = sys::PW_VERSION_NODE_EVENTS;
+    events.info = Some(info);
+    let listener = unsafe { node.add_listener_raw(events, Cell::new(0)) };
+    fixture.sync().unwrap();
+
+    assert!(listener.data().get() > 0);
+}
+
+#[test]
+fn stream_node() {
+    let fixture = Fixture::new().unwrap();
+    let stream = Stream::new(&fixture.core, "test-stream", properties! {}).unwrap();
+
+    let err = stream.node().unwrap_err();
+    assert_eq!(err.errno(), Some(nix::errno::Errno::ENOTCONN));
+
+    stream
+        .connect(Direction::Output, None, StreamFlags::empty(), &mut [])
+        .unwrap();
+    fixture.sync().unwrap();
+
+    let _node = stream.node().unwrap();
+    let ports = stream.ports().unwrap();
+    fixture.sync().unwrap();
+
+    let node_id = stream.node_id().to_string();
+    for port in ports.ports().iter() {
+        assert_eq!(port.type_, ObjectType::Port);
+        let props = port.props.as_ref().unwrap();
+        assert_eq!(props.get("node.id"), Some(node_id.as_str()));
+    }
+}
+
+#[test]
+fn stream_position() {
+    let fixture = Fixture::new().unwrap();
+    let stream = Stream::new(&fixture.core, "test-stream", properties! {}).unwrap();
+    stream
+        .connect(Direction::Output, None, StreamFlags::empty(), &mut [])
+        .unwrap();
+    fixture.sync().unwrap();
+
+    let position = stream.position().unwrap();
+    if let Some(rate) = position.sample_rate() {
+        assert!(rate > 0);
+        assert!(position.ticks_nsec().is_some());
+    }
+    if let Some(clock) = stream.clock() {
+        assert!(clock.rate.denom > 0);
+    }
+}
+
+#[test]
+fn stream_controls() {
+    let fixture = Fixture::new().unwrap();
+    let stream = Stream::new(&fixture.core, "test-stream", properties! {}).unwrap();
+
+    // No control was announced yet.
+    assert!(stream.controls().is_empty());
+    let err = stream.set_control_by_name("volume", &[0.5]).unwrap_err();
+    assert_eq!(err.errno(), Some(nix::errno::Errno::ENOENT));
+}
+
+#[test]
+#[cfg(feature = "v0_3_64")]
+fn context_set_config() {
+    let fixture = Fixture::new().unwrap();
+    let config = properties! {
+        "context.objects" => "[]",
+    };
+    assert_eq!(
+        fixture
+            .context
+            .set_config(&config, "context.objects")
+            .unwrap(),
+        0
+    );
+}
+
+#[test]
+fn loaded_module_keeps_context() {
+    let fixture = Fixture::new().unwrap();
+    let context = Context::new(&fixture.mainloop).unwrap();
+    let module = context
+        .load_module("libpipewire-module-metadata", None, None)
+        .unwrap();
+
+    // The module is destroyed by its handle, not with the context.
+    drop(context);
+    assert!(!module.is_destroyed());
+    drop(module);
+}
+
+#[test]
+fn monitor() {
+    let fixture = Fixture::new().unwrap();
+    let registry = Rc::new(fixture.core.get_registry().unwrap());
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let monitor = Monitor::new(&registry, GlobalFilter::new().type_(ObjectType::Node), {
+        let events = events.clone();
+        move |event| {
+            let event = match event {
+                MonitorEvent::Added(global) => format!("added {}", global.id),
+                MonitorEvent::Removed { id, .. } => format!("removed {id}"),
+                MonitorEvent::NodeInfo(info) => format!("info {}", info.id()),
+                _ => return,
+            };
+            events.borrow_mut().push(event);
+        }
+    });
+
+    let sink = fixture
+        .core
+        .create_null_sink("test-sink", 2, 48000)
+        .unwrap();
+    fixture.sync().unwrap();
+    fixture.sync().unwrap();
+
+    let id = sink.node_id().unwrap();
+    assert!(monitor.globals().iter().any(|global| global.id == id));
+    assert!(events.borrow().contains(&format!("added {id}")));
+    assert!(events.borrow().contains(&format!("info {id}")));
+}
+
+#[test]
+fn monitor_props_changed() {
+    let fixture = Fixture::new().unwrap();
+    let registry = Rc::new(fixture.core.get_registry().unwrap());
+
+    let changes = Rc::new(RefCell::new(Vec::new()));
+    let _monitor = Monitor::new(&registry, GlobalFilter::new().type_(ObjectType::Node), {
+        let changes = changes.clone();
+        move |event| {
+            if let MonitorEvent::PropsChanged { id, diff } = event {
+                for (key, value) in &diff.added {
+                    changes
+                        .borrow_mut()
+                        .push((*id, key.to_string(), value.to_string()));
+                }
+            }
+        }
+    });
+
+    let stream = Stream::new(&fixture.core, "test-stream", properties! {}).unwrap();
+    stream
+        .connect(Direction::Output, None, StreamFlags::empty(), &mut [])
+        .unwrap();
+    fixture.sync().unwrap();
+    fixture.sync().unwrap();
+
+    stream.update_properties(properties! { "test.key" => "value" }.dict());
+    fixture.sync().unwrap();
+    fixture.sync().unwrap();
+
+    assert!(changes.borrow().contains(&(
+        stream.node_id(),
+        "test.key".to_owned(),
+        "value".to_owned()
+    )));
+}
+
+#[test]
+fn weak_handles() {
+    let fixture = Fixture::new().unwrap();
+    let stream = Stream::new(&fixture.core, "test-stream", properties! {}).unwrap();
+
+    // The listener of the stream refers to it without keeping it alive.
+    let node_ids = Rc::new(RefCell::new(Vec::new()));
+    let listener = stream
+        .add_local_listener_with_user_data(stream.downgrade())
+        .state_changed({
+            let node_ids = node_ids.clone();
+            move |_, weak, _, _| {
+                if let Some(stream) = weak.upgrade() {
+                    node_ids.borrow_mut().push(stream.node_id());
+                }
+            }
+        })
+        .register()
+        .unwrap();
+    stream
+        .connect(Direction::Output, None, StreamFlags::empty(), &mut [])
+        .unwrap();
+    fixture.sync().unwrap();
+    assert!(node_ids.borrow().contains(&stream.node_id()));
+
+    let weak_stream = stream.downgrade();
+    let weak_core = fixture.core.downgrade();
+    assert!(weak_core.upgrade().is_some());
+    drop(listener);
+
+    // The stream is not destroyed while another handle owns it.
+    let upgraded = weak_stream.upgrade().unwrap();
+    let stream = stream.destroy().unwrap_err();
+    drop(upgraded);
+    stream.destroy().unwrap();
+    assert!(weak_stream.upgrade().is_none());
+}
+
+#[test]
+fn unregister_from_callback() {
+    let fixture = Fixture::new().unwrap();
+
+    // The listener unregisters itself the first time it is called.
+    let calls = Rc::new(Cell::new(0));
+    let slot: Rc<RefCell<Option<core::Listener>>> = Rc::new(RefCell::new(None));
+    let listener = fixture
+        .core
+        .add_listener_local()
+        .done({
+            let calls = calls.clone();
+            let slot = slot.clone();
+            let mainloop = fixture.mainloop.clone();
+            move |_, _| {
+                calls.set(calls.get() + 1);
+                if let Some(listener) = slot.borrow_mut().take() {
+                    listener.unregister_later(mainloop.loop_());
+                }
+            }
+        })
+        .register();
+    *slot.borrow_mut() = Some(listener);
+
+    fixture.sync().unwrap();
+    assert_eq!(calls.get(), 1);
+    fixture.sync().unwrap();
+    assert_eq!(calls.get(), 1);
+    assert!(slot.borrow().is_none());
+}
+
+#[test]
+fn core_client() {
+    let fixture = Fixture::new().unwrap();
+    fixture.sync().unwrap();
+
+    let client = fixture.core.client();
+    let id = client.id().unwrap();
+
+    let infos = Rc::new(RefCell::new(Vec::new()));
+    let _listener = client
+        .add_listener_local()
+        .info({
+            let infos = infos.clone();
+            move |info| {
+                let name = info
+                    .props()
+                    .and_then(|props| props.get("application.name"))
+                    .map(str::to_owned);
+                infos.borrow_mut().push((info.id(), name));
+            }
+        })
+        .register();
+    client.update_properties(properties! { "application.name" => "core-client-test" }.dict());
+    fixture.sync().unwrap();
+
+    assert!(infos
+        .borrow()
+        .contains(&(id, Some("core-client-test".to_owned()))));
+}
+
+#[test]
+fn audio_devices() {
+    let fixture = Fixture::new().unwrap();
+    let sink = fixture
+        .core
+        .create_null_sink("test-sink", 2, 48000)
+        .unwrap();
+    fixture.sync().unwrap();
+
+    let devices = discovery::audio_devices(&fixture.core).unwrap();
+    let device = devices
+        .iter()
+        .find(|device| device.name == "test-sink")
+        .unwrap();
+    assert_eq!(Some(device.node_id), sink.node_id());
+    assert!(device.is_sink());
+    assert_eq!(device.media_class, "Audio/Sink");
+    assert_eq!(device.device_id, None);
+    assert!(device.profiles.is_empty());
+}
+
+#[test]
+fn node_set_volume_without_route() {
+    let fixture = Fixture::new().unwrap();
+    let sink = fixture
+        .core
+        .create_null_sink("test-sink", 2, 48000)
+        .unwrap();
+    fixture.sync().unwrap();
+
+    // A null sink has no device, so the volume is set through the Props param of the node.
+    sink.node().set_volume(0.5).unwrap();
+    fixture.sync().unwrap();
+
+    let devices = discovery::audio_devices(&fixture.core).unwrap();
+    let device = devices
+        .iter()
+        .find(|device| device.name == "test-sink")
+        .unwrap();
+    assert_eq!(device.active_route, None);
+    assert_eq!(device.volume.volume, Some(0.5));
+}
+
+#[test]
+fn stream_reconnect() {
+    let fixture = Fixture::new().unwrap();
+    let stream = Rc::new(Stream::new(&fixture.core, "test-stream", properties! {}).unwrap());
+
+    let err = stream.reconnect().unwrap_err();
+    assert_eq!(err.errno(), Some(nix::errno::Errno::ENOTCONN));
+
+    let sink = fixture
+        .core
+        .create_null_sink("test-sink", 2, 48000)
+        .unwrap();
+    fixture.sync().unwrap();
+    let sink_id = sink.node_id().unwrap();
+
+    stream
+        .connect(
+            Direction::Output,
+            Some(sink_id),
+            StreamFlags::empty(),
+            &mut [],
+        )
+        .unwrap();
+    stream.reconnect().unwrap();
+    assert_eq!(stream.target(), Some(sink_id));
+
+    let reconnect = AutoReconnect::new(&stream, ReconnectPolicy::SameNode).unwrap();
+    reconnect.set_move_timeout(std::time::Duration::from_millis(10));
+    fixture.sync().unwrap();
+
+    // Without a session manager, nothing moves the stream once its target is removed.
+    drop(sink);
+    fixture.sync().unwrap();
+    for _ in 0..100 {
+        if reconnect.is_waiting() {
+            break;
+        }
+        fixture
+            .mainloop
+            .loop_()
+            .iterate(std::time::Duration::from_millis(10));
+    }
+    assert!(reconnect.is_waiting());
+
+    let sink = fixture
+        .core
+        .create_null_sink("test-sink", 2, 48000)
+        .unwrap();
+    fixture.sync().unwrap();
+    fixture.sync().unwrap();
+
+    assert!(!reconnect.is_waiting());
+    assert!(reconnect.take_error().is_none());
+    assert_eq!(stream.target(), sink.node_id());
+}
+
+#[test]
+fn stream_target_name() {
+    let fixture = Fixture::new().unwrap();
+    let _sink = fixture
+        .core
+        .create_null_sink("test-sink", 2, 48000)
+        .unwrap();
+    let stream = Stream::new(&fixture.core, "test-stream", properties! {}).unwrap();
+
+    stream
+        .connect_to(
+            Direction::Output,
+            StreamTarget::Name("test-sink"),
+            StreamFlags::empty(),
+            &mut [],
+        )
+        .unwrap();
+    fixture.sync().unwrap();
+
+    assert_eq!(stream.properties().get("target.object"), Some("test-sink"));
+    assert_eq!(stream.target(), None);
+
+    // Reconnecting keeps the name, while a connection to any node removes it.
+    stream.reconnect().unwrap();
+    assert_eq!(stream.properties().get("target.object"), Some("test-sink"));
+    stream.reconnect_to(None).unwrap();
+    assert_eq!(stream.properties().get("target.object"), None);
+}
+
+#[test]
+fn stream_keeps_target_object_property() {
+    let fixture = Fixture::new().unwrap();
+    let stream = Stream::new(
+        &fixture.core,
+        "test-stream",
+        properties! { "target.object" => "test-sink" },
+    )
+    .unwrap();
+
+    stream
+        .connect(Direction::Output, None, StreamFlags::empty(), &mut [])
+        .unwrap();
+    fixture.sync().unwrap();
+
+    assert_eq!(stream.properties().get("target.object"), Some("test-sink"));
+}
+
+#[cfg(feature = "test-shims")]
+#[test]
+fn stream_injected_failures() {
+    use nix::errno::Errno;
+    use pipewire::stream::shim::{self, StreamOp};
+
+    let fixture = Fixture::new().unwrap();
+    let stream = Stream::new(&fixture.core, "test-stream", properties! {}).unwrap();
+
+    shim::fail_next(&stream, StreamOp::Connect, Errno::ECONNREFUSED);
+    shim::fail_always(&stream, StreamOp::DequeueBuffer, Errno::EPIPE);
+    let err = stream
+        .connect(Direction::Output, None, StreamFlags::empty(), &mut [])
+        .unwrap_err();
+    assert_eq!(err.errno(), Some(Errno::ECONNREFUSED));
+
+    stream
+        .connect(Direction::Output, None, StreamFlags::empty(), &mut [])
+        .unwrap();
+    fixture.sync().unwrap();
+    assert!(stream.dequeue_buffer().is_none());
+
+    shim::fail_next(&stream, StreamOp::UpdateParams, Errno::EINVAL);
+    let err = stream.update_params(&mut []).unwrap_err();
+    assert_eq!(err.errno(), Some(Errno::EINVAL));
+    stream.update_params(&mut []).unwrap();
+
+    shim::clear(&stream);
+}